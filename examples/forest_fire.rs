@@ -24,7 +24,7 @@
 
 use std::collections::HashSet;
 
-use bevy::prelude::IVec2;
+use bevy::prelude::{IVec2, Local};
 use incerto::prelude::*;
 use rand::prelude::*;
 
@@ -32,6 +32,10 @@ use rand::prelude::*;
 const SIMULATION_STEPS: usize = 500;
 const GRID_WIDTH: i32 = 50;
 const GRID_HEIGHT: i32 = 50;
+const GRID_BOUNDS: GridBounds2D = GridBounds2D {
+    min: IVec2::new(0, 0),
+    max: IVec2::new(GRID_WIDTH - 1, GRID_HEIGHT - 1),
+};
 
 // Fire parameters
 const INITIAL_FOREST_DENSITY: f64 = 0.7; // Probability a cell starts as forest
@@ -149,13 +153,9 @@ fn main()
     println!();
 
     // Build the simulation
-    let bounds = GridBounds2D {
-        min: IVec2::new(0, 0),
-        max: IVec2::new(GRID_WIDTH - 1, GRID_HEIGHT - 1),
-    };
     let mut simulation = SimulationBuilder::new()
         // Add spatial grid support
-        .add_spatial_grid::<IVec2, ForestCell>(Some(bounds))
+        .add_dense_spatial_grid::<IVec2, ForestCell>(GRID_BOUNDS)
         // Spawn the forest grid
         .add_entity_spawner(spawn_forest_grid)
         // Add fire spread system
@@ -236,31 +236,22 @@ fn spawn_forest_grid(spawner: &mut Spawner)
     let mut rng = rand::rng();
 
     // Spawn all grid cells
-    for x in 0..GRID_WIDTH
-    {
-        for y in 0..GRID_HEIGHT
+    spawner.spawn_grid(GRID_BOUNDS, |_| {
+        // Determine initial state
+        let state = if rng.random_bool(INITIAL_FOREST_DENSITY)
         {
-            let position = GridPosition2D::new(x, y);
-
-            // Determine initial state
-            let state = if rng.random_bool(INITIAL_FOREST_DENSITY)
-            {
-                CellState::Healthy
-            }
-            else
-            {
-                CellState::Empty
-            };
-
-            let cell = ForestCell { state };
-            spawner.spawn((position, cell));
+            CellState::Healthy
         }
-    }
+        else
+        {
+            CellState::Empty
+        };
+
+        Some(ForestCell { state })
+    });
 
     // Start some initial fires at random locations
-    let healthy_positions: Vec<GridPosition2D> = (0..GRID_WIDTH)
-        .flat_map(|x| (0..GRID_HEIGHT).map(move |y| GridPosition2D::new(x, y)))
-        .collect();
+    let healthy_positions: Vec<GridPosition2D> = GRID_BOUNDS.positions().collect();
 
     // This is a simplified approach - in a real implementation you'd query existing entities
     // For this example, we'll start fires by spawning burning cells at random positions
@@ -283,6 +274,7 @@ fn fire_spread_system(
     spatial_grid: Res<SpatialGrid<IVec2, ForestCell>>,
     query_burning: Query<(Entity, &GridPosition2D), With<ForestCell>>,
     mut query_cells: Query<(&GridPosition2D, &mut ForestCell)>,
+    mut neighbors: Local<Vec<Entity>>,
 )
 {
     let mut rng = rand::rng();
@@ -295,10 +287,11 @@ fn fire_spread_system(
         if let Ok((_, cell)) = query_cells.get(burning_entity)
             && matches!(cell.state, CellState::Burning { .. })
         {
-            // Get orthogonal neighbors using the spatial grid
-            let neighbors = spatial_grid.orthogonal_neighbors_of(burning_pos);
+            // Get orthogonal neighbors using the spatial grid, reusing the buffer across cells
+            neighbors.clear();
+            spatial_grid.orthogonal_neighbors_of_into(burning_pos, &mut neighbors);
 
-            for neighbor_entity in neighbors
+            for &neighbor_entity in &*neighbors
             {
                 if let Ok((neighbor_pos, neighbor_cell)) = query_cells.get(neighbor_entity)
                 {
@@ -48,17 +48,21 @@ struct Position
 /// Note that adding/removing components to entities at runtime is
 /// typically inefficient.
 /// It is done here mainly to showcase how to do it by using [`Commands`].
+/// Storing the marker in a sparse set, instead of the archetype tables, avoids moving
+/// the whole entity to a different table every time it is added or removed.
 ///
 /// In a real scenario it would be more performant to mark entities as
 /// healthy or infected using an enum.
 #[derive(Component)]
+#[component(storage = "SparseSet")]
 struct Infected;
 
 fn main()
 {
     // Build the simulation.
     let mut simulation = SimulationBuilder::new()
-        .add_entity_spawner(spawn_people)
+        .add_seeded_entity_spawner(spawn_people)
+        .track_population::<Person>()
         .add_systems((
             people_move,
             people_infect_others,
@@ -77,14 +81,14 @@ fn main()
         .count::<(With<Person>, Without<Infected>)>()
         .unwrap();
 
-    println!("survivors: {survivors}, infected: {infected}, healthy: {healthy}");
+    let deaths = simulation.ledger::<Person>().unwrap().despawned();
+
+    println!("survivors: {survivors}, infected: {infected}, healthy: {healthy}, deaths: {deaths}");
 }
 
 /// Spawns the initial population placed uniformly around the grid.
-fn spawn_people(spawner: &mut Spawner)
+fn spawn_people(spawner: &mut Spawner, rng: &mut SimRng)
 {
-    let mut rng = rand::rng();
-
     for _ in 0..INITIAL_POPULATION
     {
         // each person starts somewhere randomly in the grid
@@ -22,7 +22,10 @@
 use std::collections::HashSet;
 
 use bevy::prelude::IVec2;
-use incerto::prelude::*;
+use incerto::{
+    kernel::{InteractionKernel, Metric},
+    prelude::*,
+};
 use rand::prelude::*;
 
 // Simulation parameters
@@ -32,9 +35,7 @@ const GRID_SIZE: i32 = 40;
 
 // Disease parameters
 const CHANCE_START_INFECTED: f64 = 0.02;
-const INFECTION_RADIUS: i32 = 2; // Can infect within 2 cells distance
-const CHANCE_INFECT_AT_DISTANCE_1: f64 = 0.15; // High chance at close distance
-const CHANCE_INFECT_AT_DISTANCE_2: f64 = 0.05; // Lower chance at farther distance
+const CHANCE_INFECT_BY_DISTANCE: [f64; 3] = [0.15, 0.15, 0.05]; // At Manhattan distance 0, 1 and 2
 const CHANCE_RECOVER: f64 = 0.03;
 const CHANCE_DIE: f64 = 0.001;
 const INCUBATION_PERIOD: usize = 5; // Steps before becoming infectious
@@ -79,10 +80,22 @@ pub struct Person
 }
 
 /// Component for people under quarantine (contact tracing)
+///
+/// Stored in a sparse set, since it is frequently added and removed.
 #[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
 pub struct Quarantined
 {
-    pub remaining_duration: usize,
+    /// The step at which the quarantine ends, unless it is renewed before then.
+    pub until: usize,
+}
+
+/// Event emitted when the quarantine of a person ends, at the given step
+#[derive(Event, Debug)]
+pub struct QuarantineEnded
+{
+    pub person: Entity,
+    pub until: usize,
 }
 
 /// Component to track contact history for contact tracing
@@ -142,7 +155,10 @@ fn main()
     println!("🦠 Starting Enhanced Pandemic Simulation");
     println!("Population: {INITIAL_POPULATION}");
     println!("Grid size: {GRID_SIZE}x{GRID_SIZE}");
-    println!("Infection radius: {INFECTION_RADIUS} cells");
+    println!(
+        "Infection radius: {} cells",
+        CHANCE_INFECT_BY_DISTANCE.len() - 1
+    );
     println!(
         "Social distancing: {}",
         if SOCIAL_DISTANCING_ENABLED
@@ -182,6 +198,7 @@ fn main()
             disease_recovery_and_death,
         ))
         // Contact tracing and quarantine
+        .add_future_events::<QuarantineEnded>()
         .add_systems((
             update_contact_history,
             process_contact_tracing,
@@ -440,6 +457,8 @@ fn spatial_disease_transmission(
 {
     let mut rng = rand::rng();
     let mut new_exposures = Vec::new();
+    let kernel = InteractionKernel::table(CHANCE_INFECT_BY_DISTANCE).with_metric(Metric::Manhattan);
+    let mut contacts = Vec::new();
 
     // Collect infectious people first to avoid borrowing conflicts
     let infectious_people: Vec<(Entity, GridPosition2D)> = query
@@ -458,51 +477,22 @@ fn spatial_disease_transmission(
 
     for (infectious_entity, infectious_pos) in infectious_people
     {
-        // Get all people within infection radius using iterative approach
-        let mut nearby_entities = Vec::new();
-        let infectious_coord = infectious_pos.0;
-
-        // Check all positions within Manhattan distance of INFECTION_RADIUS
-        for dx in -INFECTION_RADIUS..=INFECTION_RADIUS
-        {
-            for dy in -INFECTION_RADIUS..=INFECTION_RADIUS
-            {
-                let manhattan_distance = dx.abs() + dy.abs();
-                if manhattan_distance <= INFECTION_RADIUS
-                {
-                    let check_pos =
-                        GridPosition2D::new(infectious_coord.x + dx, infectious_coord.y + dy);
-                    nearby_entities.extend(spatial_grid.entities_at(&check_pos));
-                }
-            }
-        }
+        // Get all people within infection radius, along with the chance of infecting them
+        kernel.contacts(&spatial_grid, infectious_pos, &mut contacts);
 
-        for nearby_entity in nearby_entities
+        for &(nearby_entity, infection_chance) in &contacts
         {
             if nearby_entity == infectious_entity
             {
                 continue; // Don't infect self
             }
 
-            if let Ok((entity, susceptible_pos, person)) = query.get(nearby_entity)
+            // Only infect healthy people
+            if let Ok((entity, _, person)) = query.get(nearby_entity)
+                && matches!(person.disease_state, DiseaseState::Healthy)
+                && rng.random_bool(infection_chance)
             {
-                // Only infect healthy people
-                if matches!(person.disease_state, DiseaseState::Healthy)
-                {
-                    // Calculate infection probability based on distance
-                    let distance = (infectious_pos.0 - susceptible_pos.0).abs().element_sum();
-                    let infection_chance = match distance
-                    {
-                        0 | 1 => CHANCE_INFECT_AT_DISTANCE_1, // Same cell or adjacent
-                        2 => CHANCE_INFECT_AT_DISTANCE_2,     // 2 cells away
-                        _ => 0.0,                             // Too far
-                    };
-
-                    if rng.random_bool(infection_chance)
-                    {
-                        new_exposures.push(entity);
-                    }
-                }
+                new_exposures.push(entity);
             }
         }
     }
@@ -564,6 +554,8 @@ fn update_contact_history(mut query: Query<(&GridPosition2D, &mut ContactHistory
 /// Process contact tracing when someone becomes infectious
 fn process_contact_tracing(
     mut commands: Commands,
+    mut future_events: ResMut<FutureEvents<QuarantineEnded>>,
+    step: Res<StepNumber>,
     spatial_grid: Res<SpatialGrid<IVec2, Person>>,
     query_newly_infectious: Query<
         (Entity, &GridPosition2D, &ContactHistory),
@@ -577,6 +569,7 @@ fn process_contact_tracing(
         return;
     }
 
+    let until = step.get() + CONTACT_QUARANTINE_DURATION;
     for (infectious_entity, _infectious_pos, contact_history) in &query_newly_infectious
     {
         // Check if this person just became infectious (simplified check)
@@ -598,28 +591,38 @@ fn process_contact_tracing(
                 if query_potential_contacts.get(potential_contact).is_ok()
                 {
                     // Use try_insert to handle entities that may have been despawned
-                    commands.entity(potential_contact).try_insert(Quarantined {
-                        remaining_duration: CONTACT_QUARANTINE_DURATION,
-                    });
+                    // a renewed quarantine replaces the end step of the previous one
+                    commands
+                        .entity(potential_contact)
+                        .try_insert(Quarantined { until });
+                    future_events.schedule_at(
+                        until,
+                        QuarantineEnded {
+                            person: potential_contact,
+                            until,
+                        },
+                    );
                 }
             }
         }
     }
 }
 
-/// Update quarantine status
-fn update_quarantine_status(mut commands: Commands, mut query: Query<(Entity, &mut Quarantined)>)
+/// End quarantines that have run their course
+fn update_quarantine_status(
+    mut commands: Commands,
+    mut events: EventReader<QuarantineEnded>,
+    query: Query<&Quarantined>,
+)
 {
-    for (entity, mut quarantined) in &mut query
+    for QuarantineEnded { person, until } in events.read()
     {
-        if quarantined.remaining_duration > 1
-        {
-            quarantined.remaining_duration -= 1;
-        }
-        else
+        // ignore the events of quarantines that were renewed since, or of despawned people
+        if query
+            .get(*person)
+            .is_ok_and(|quarantined| quarantined.until == *until)
         {
-            // End quarantine
-            commands.entity(entity).remove::<Quarantined>();
+            commands.entity(*person).try_remove::<Quarantined>();
         }
     }
 }
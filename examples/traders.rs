@@ -8,8 +8,9 @@
 //! * A number of traders are spawned, starting with the same amount of cash and empty portfolios.
 //! * A number of stocks are spawned, starting at the same price.
 //! * Each simulation step represents one day, during which:
-//!     * Every stock's price changes according to a normal distribution.
+//!     * Every stock's price follows a Brownian motion, with a normally distributed daily change.
 //!         * Stocks that reach a price of `0.0` become delisted and don't update anymore.
+//!         * This is declared as a [`StochasticProcess`], which the simulation integrates on its own.
 //!     * Every trader looks at every available stock, and decides whether to buy it with some probability.
 //!         * The amount of shares to buy is sampled uniformly from a range.
 //!     * Every trader looks at every stock in his portfolio, and decides whether to sell all his shares with some probability.
@@ -22,15 +23,18 @@
 #![allow(clippy::unwrap_used)]
 #![allow(clippy::expect_used)]
 #![allow(clippy::cast_precision_loss)]
+#![allow(clippy::suboptimal_flops)]
 use std::{collections::HashMap, ops::RangeInclusive};
 
-use incerto::prelude::*;
+use incerto::{
+    prelude::*,
+    sde::{Process, StochasticProcess},
+};
 use plotters::{
     prelude::*,
     style::full_palette::{AMBER, INDIGO, ORANGE, PURPLE},
 };
 use rand::prelude::*;
-use rand_distr::Normal;
 
 const SIMULATION_STEPS: usize = 2 * 365;
 const NUM_TRADERS: usize = 10;
@@ -50,12 +54,6 @@ const PLOT_STORE_PATH: &str = "images/trader_net_worths.png";
 #[derive(Debug, Component, Hash, PartialEq, Eq, Clone, Copy)]
 struct StockId(usize);
 
-#[derive(Debug, Component)]
-struct StockPrice(f64);
-
-#[derive(Debug, Component)]
-struct StockDelisted;
-
 #[derive(Debug, Component, Hash, PartialEq, Eq, Clone, Copy)]
 struct TraderId(usize);
 
@@ -93,7 +91,7 @@ fn main() -> Result<(), SimulationError>
         ))
         // stocks
         .add_entity_spawner(spawn_stocks)
-        .add_systems(stocks_price_change)
+        .add_stochastic_processes()
         .record_time_series::<TraderNetWorth, TraderId, _>(1)?
         .build();
 
@@ -132,39 +130,23 @@ fn spawn_stocks(spawner: &mut Spawner)
 {
     for id in 0..NUM_STOCKS
     {
-        spawner.spawn((StockId(id), StockPrice(STOCK_INITIAL_PRICE)));
-    }
-}
-
-/// During each step, the prices of all stocks move by a random amount.
-fn stocks_price_change(
-    mut commands: Commands,
-    mut query: Query<(Entity, &mut StockPrice), Without<StockDelisted>>,
-)
-{
-    let mut rng = rand::rng();
-
-    // sample the deltas from a normal distribution
-    let price_change_distribution =
-        Normal::new(STOCK_PRICE_CHANGE_MEAN, STOCK_PRICE_CHANGE_STD_DEV).unwrap();
-
-    for (stock, mut stock_price) in &mut query
-    {
-        let price_change = rng.sample(price_change_distribution);
-
-        stock_price.0 = (stock_price.0 + price_change).max(0.0);
+        // a stock is delisted permanently if its price hits zero
+        let price = StochasticProcess::new(
+            STOCK_INITIAL_PRICE,
+            Process::Brownian {
+                mu: STOCK_PRICE_CHANGE_MEAN,
+                sigma: STOCK_PRICE_CHANGE_STD_DEV,
+            },
+        )
+        .with_lower_bound(0.0);
 
-        if stock_price.0 < f64::EPSILON
-        {
-            // a stock is delisted permanently if its price hits zero
-            commands.entity(stock).insert(StockDelisted);
-        }
+        spawner.spawn((StockId(id), price));
     }
 }
 
 fn traders_may_buy_shares(
     mut query: Query<&mut Trader>,
-    query_stocks: Query<(&StockId, &StockPrice), Without<StockDelisted>>,
+    query_stocks: Query<(&StockId, &StochasticProcess)>,
 )
 {
     let mut rng = rand::rng();
@@ -173,7 +155,13 @@ fn traders_may_buy_shares(
     {
         for (stock_id, stock_price) in &query_stocks
         {
-            let price = stock_price.0;
+            if stock_price.is_absorbed()
+            {
+                // delisted stocks cannot be bought
+                continue;
+            }
+
+            let price = stock_price.value();
             let already_owned = trader.portfolio.contains_key(stock_id);
             let decides_to_buy = rng.random_bool(TRADER_CHANCE_BUY_STOCK);
 
@@ -198,7 +186,7 @@ fn traders_may_buy_shares(
 
 fn traders_may_sell_shares(
     mut query: Query<&mut Trader>,
-    query_stocks: Query<(&StockId, &StockPrice)>,
+    query_stocks: Query<(&StockId, &StochasticProcess)>,
 )
 {
     let mut rng = rand::rng();
@@ -206,7 +194,7 @@ fn traders_may_sell_shares(
     // build a stock-price lookup table
     let stock_prices: HashMap<StockId, f64> = query_stocks
         .iter()
-        .map(|(id, price)| (*id, price.0))
+        .map(|(id, price)| (*id, price.value()))
         .collect();
 
     for mut trader in &mut query
@@ -238,13 +226,13 @@ fn traders_may_sell_shares(
 /// Computes each trader's net worth and stores in the auxiliary [`TraderNetWorth`] component.
 fn traders_calculate_net_worth(
     mut query: Query<(&Trader, &mut TraderNetWorth)>,
-    query_stocks: Query<(&StockId, &StockPrice)>,
+    query_stocks: Query<(&StockId, &StochasticProcess)>,
 )
 {
     // build a stock-price lookup table
     let stock_prices: HashMap<StockId, f64> = query_stocks
         .iter()
-        .map(|(id, price)| (*id, price.0))
+        .map(|(id, price)| (*id, price.value()))
         .collect();
 
     for (trader, mut net_worth) in &mut query
@@ -0,0 +1,247 @@
+use std::{collections::HashMap, fs::File, path::Path, sync::Arc, time::SystemTime};
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Float32Array, Float64Array, Int8Array, Int16Array, Int32Array,
+    Int64Array, RecordBatch, StringArray, UInt8Array, UInt16Array, UInt32Array, UInt64Array,
+};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema};
+use bevy::prelude::*;
+use parquet::arrow::ArrowWriter;
+
+use crate::{
+    Identifier, Sample, Simulation, SimulationError, TimeSeries, error::ExportError,
+    traits::ArrowValue,
+};
+
+macro_rules! impl_arrow_value {
+    ($t:ty, $array:ty, $data_type:expr) => {
+        impl ArrowValue for $t
+        {
+            fn data_type() -> DataType
+            {
+                $data_type
+            }
+
+            fn into_array(values: Vec<Self>) -> ArrayRef
+            {
+                Arc::new(<$array>::from(values))
+            }
+        }
+    };
+}
+
+impl_arrow_value!(bool, BooleanArray, DataType::Boolean);
+impl_arrow_value!(i8, Int8Array, DataType::Int8);
+impl_arrow_value!(i16, Int16Array, DataType::Int16);
+impl_arrow_value!(i32, Int32Array, DataType::Int32);
+impl_arrow_value!(i64, Int64Array, DataType::Int64);
+impl_arrow_value!(u8, UInt8Array, DataType::UInt8);
+impl_arrow_value!(u16, UInt16Array, DataType::UInt16);
+impl_arrow_value!(u32, UInt32Array, DataType::UInt32);
+impl_arrow_value!(u64, UInt64Array, DataType::UInt64);
+impl_arrow_value!(f32, Float32Array, DataType::Float32);
+impl_arrow_value!(f64, Float64Array, DataType::Float64);
+impl_arrow_value!(String, StringArray, DataType::Utf8);
+
+impl ArrowValue for usize
+{
+    fn data_type() -> DataType
+    {
+        DataType::UInt64
+    }
+
+    fn into_array(values: Vec<Self>) -> ArrayRef
+    {
+        u64::into_array(values.into_iter().map(|v| v as u64).collect())
+    }
+}
+
+/// Builds a record batch from named columns of values.
+pub fn record_batch(columns: Vec<(&str, DataType, ArrayRef)>) -> Result<RecordBatch, ExportError>
+{
+    let (fields, arrays): (Vec<_>, Vec<_>) = columns
+        .into_iter()
+        .map(|(name, data_type, array)| (Field::new(name, data_type, false), array))
+        .unzip();
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).map_err(|_| ExportError::Arrow)
+}
+
+/// Writes a record batch to a Parquet file at the given `path`, replacing it if it exists.
+///
+/// # Errors
+///
+/// - [`ExportError::Io`]
+/// - [`ExportError::Arrow`]
+pub fn write_parquet(batch: &RecordBatch, path: impl AsRef<Path>) -> Result<(), ExportError>
+{
+    let file = File::create(path).map_err(|_| ExportError::Io)?;
+    let mut writer =
+        ArrowWriter::try_new(file, batch.schema(), None).map_err(|_| ExportError::Arrow)?;
+
+    writer.write(batch).map_err(|_| ExportError::Arrow)?;
+    writer.close().map_err(|_| ExportError::Arrow)?;
+
+    Ok(())
+}
+
+/// Writes a record batch to a Feather file at the given `path`, replacing it if it exists.
+///
+/// Feather is the Arrow IPC file format, which is read without any conversion by the `arrow`
+/// package in R and by `pyarrow`, including the metadata of the batch's schema.
+///
+/// # Errors
+///
+/// - [`ExportError::Io`]
+/// - [`ExportError::Arrow`]
+pub fn write_feather(batch: &RecordBatch, path: impl AsRef<Path>) -> Result<(), ExportError>
+{
+    let file = File::create(path).map_err(|_| ExportError::Io)?;
+    let mut writer = FileWriter::try_new(file, &batch.schema()).map_err(|_| ExportError::Arrow)?;
+
+    writer.write(batch).map_err(|_| ExportError::Arrow)?;
+    writer.finish().map_err(|_| ExportError::Arrow)?;
+
+    Ok(())
+}
+
+/// Returns the given record batch, with the given `metadata` added to that of its schema.
+pub fn with_schema_metadata(
+    batch: &RecordBatch,
+    metadata: impl IntoIterator<Item = (String, String)>,
+) -> Result<RecordBatch, ExportError>
+{
+    let schema = batch.schema();
+    let mut merged = schema.metadata().clone();
+    merged.extend(metadata);
+
+    let schema = Schema::new_with_metadata(schema.fields().clone(), merged);
+    batch
+        .clone()
+        .with_schema(Arc::new(schema))
+        .map_err(|_| ExportError::Arrow)
+}
+
+impl<T: ArrowValue + Clone> TimeSeries<'_, T>
+{
+    /// Converts the time series into an Arrow record batch.
+    ///
+    /// The batch has two columns, `step` with the simulation step of each sample,
+    /// and `value` with the sampled values.
+    ///
+    /// # Errors
+    ///
+    /// - [`ExportError::Arrow`]
+    pub fn to_record_batch(&self) -> Result<RecordBatch, ExportError>
+    {
+        record_batch(vec![
+            (
+                "step",
+                usize::data_type(),
+                usize::into_array(self.time.to_vec()),
+            ),
+            ("value", T::data_type(), T::into_array(self.values.to_vec())),
+        ])
+    }
+}
+
+impl Simulation
+{
+    /// Exports the values of type `O` sampled from all components `C` into an Arrow record batch,
+    /// with one row per entity.
+    ///
+    /// The batch has two columns, `id` with the [`Identifier`] of each entity, and `value` with the
+    /// values sampled from its component. Entities without an identifier `Id` are skipped.
+    ///
+    /// See [`Self::export_columns_with_ids`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`crate::SamplingError::ComponentDoesNotExist`]
+    /// - [`ExportError::Arrow`]
+    pub fn export_record_batch<C, Id, O>(&self) -> Result<RecordBatch, SimulationError>
+    where
+        C: Sample<O> + Component,
+        Id: Identifier + Clone + ArrowValue,
+        O: ArrowValue,
+    {
+        let (ids, values) = self.export_columns_with_ids::<C, Id, O>()?;
+
+        Ok(record_batch(vec![
+            ("id", Id::data_type(), Id::into_array(ids)),
+            ("value", O::data_type(), O::into_array(values)),
+        ])?)
+    }
+
+    /// Annotates a record batch of the results of this simulation with the metadata of the run,
+    /// stored in the metadata of its schema, so that it travels along with the results once
+    /// written with [`write_feather`] or [`crate::write_parquet`].
+    ///
+    /// The keys `name`, `description`, `seed`, `parameter_hash`, `created_at` and `steps` hold
+    /// the [`Simulation::meta`] of the simulation and the number of steps it has run for, while
+    /// the given named `parameters` it was configured with are stored as `parameter.<name>`.
+    /// Missing values are stored as empty strings, and timestamps as seconds since the Unix epoch.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use incerto::prelude::*;
+    /// # #[derive(Component)]
+    /// # struct Infected(bool);
+    /// # impl SampleAggregate<u32> for Infected {
+    /// #     fn sample_aggregate(components: &[&Self]) -> u32 { 0 }
+    /// # }
+    /// # let mut simulation = SimulationBuilder::new()
+    /// #     .record_aggregate_time_series::<Infected, u32>(1)
+    /// #     .expect("already recorded")
+    /// #     .build();
+    /// let batch = simulation
+    ///     .get_aggregate_time_series::<Infected, u32>()
+    ///     .expect("not recorded")
+    ///     .to_record_batch()
+    ///     .expect("failed to convert");
+    /// let batch = simulation
+    ///     .with_run_metadata(&batch, &[("beta", 0.3), ("gamma", 0.1)])
+    ///     .expect("failed to annotate");
+    ///
+    /// incerto::write_feather(&batch, "infected.feather").expect("failed to write");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - [`ExportError::Arrow`]
+    pub fn with_run_metadata(
+        &self,
+        batch: &RecordBatch,
+        parameters: &[(&str, f64)],
+    ) -> Result<RecordBatch, ExportError>
+    {
+        let meta = self.meta();
+        let optional =
+            |value: Option<u64>| value.map(|value| value.to_string()).unwrap_or_default();
+        let created_at = meta
+            .created_at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        let mut metadata = HashMap::from([
+            (String::from("name"), meta.name.clone()),
+            (String::from("description"), meta.description.clone()),
+            (String::from("seed"), optional(meta.seed)),
+            (
+                String::from("parameter_hash"),
+                optional(meta.parameter_hash),
+            ),
+            (String::from("created_at"), created_at.to_string()),
+            (String::from("steps"), self.current_step().to_string()),
+        ]);
+        metadata.extend(
+            parameters
+                .iter()
+                .map(|(name, value)| (format!("parameter.{name}"), value.to_string())),
+        );
+
+        with_schema_metadata(batch, metadata)
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    panic,
+    sync::{
+        Arc, Condvar, Mutex, PoisonError,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::{self, JoinHandle},
+};
+
+use bevy::app::SubApp;
+
+use crate::{StepNumber, plugins::run_step_end_hooks, simulation::Simulation, trace::trace_span};
+
+/// A snapshot of the status of a simulation running in the background.
+///
+/// These are sent periodically by a [`BackgroundSimulation`], and can be received
+/// through [`BackgroundSimulation::status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationStatus
+{
+    /// The number of simulation steps that have been run so far.
+    pub step: usize,
+
+    /// The number of simulation steps remaining until the background run completes.
+    pub remaining_steps: usize,
+}
+
+/// The requested state of a background simulation, set from the controlling thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlState
+{
+    Running,
+    Paused,
+    Stopped,
+}
+
+/// Shared control block between a [`BackgroundSimulation`] and its worker thread.
+struct Control
+{
+    state: Mutex<ControlState>,
+    changed: Condvar,
+}
+
+impl Control
+{
+    fn set(&self, state: ControlState)
+    {
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = state;
+        self.changed.notify_all();
+    }
+
+    /// Blocks while the simulation is paused.
+    ///
+    /// Returns `false` if the simulation has been stopped.
+    fn wait_while_paused(&self) -> bool
+    {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = self
+            .changed
+            .wait_while(state, |state| *state == ControlState::Paused)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        *state != ControlState::Stopped
+    }
+}
+
+/// Handle to a [`Simulation`] running on its own thread.
+///
+/// Constructed using [`Simulation::spawn_background`].
+///
+/// The handle can be used to pause, resume or stop the simulation, and to receive
+/// periodic [`SimulationStatus`] snapshots while it runs.
+/// Once the run is over, the [`Simulation`] is handed back through [`Self::stop`] or
+/// [`Self::join`], so that results can be sampled from it as usual.
+pub struct BackgroundSimulation
+{
+    control: Arc<Control>,
+    status: Receiver<SimulationStatus>,
+    thread: JoinHandle<SubApp>,
+}
+
+impl BackgroundSimulation
+{
+    pub(crate) fn spawn(simulation: Simulation, num_steps: usize, status_interval: usize) -> Self
+    {
+        assert!(status_interval > 0);
+
+        let control = Arc::new(Control {
+            state: Mutex::new(ControlState::Running),
+            changed: Condvar::new(),
+        });
+        let (sender, status) = mpsc::channel();
+
+        let main = simulation.into_main();
+        let thread = {
+            let control = Arc::clone(&control);
+            thread::spawn(move || Self::run(main, num_steps, status_interval, &control, &sender))
+        };
+
+        Self {
+            control,
+            status,
+            thread,
+        }
+    }
+
+    fn run(
+        mut main: SubApp,
+        num_steps: usize,
+        status_interval: usize,
+        control: &Control,
+        sender: &Sender<SimulationStatus>,
+    ) -> SubApp
+    {
+        for remaining_steps in (0..num_steps).rev()
+        {
+            if !control.wait_while_paused()
+            {
+                break;
+            }
+
+            trace_span!(
+                main.world(),
+                INFO,
+                "step",
+                step = main.world().resource::<StepNumber>().get()
+            );
+
+            main.update();
+
+            let flow = run_step_end_hooks(main.world_mut());
+
+            let step = main.world().resource::<StepNumber>().get() - 1;
+            if step.is_multiple_of(status_interval) || remaining_steps == 0
+            {
+                // the receiving end may have been dropped, which is fine
+                let _ = sender.send(SimulationStatus {
+                    step,
+                    remaining_steps,
+                });
+            }
+
+            if flow.is_break()
+            {
+                break;
+            }
+        }
+
+        main
+    }
+
+    /// Pauses the simulation after the step that is currently being executed.
+    pub fn pause(&self)
+    {
+        self.control.set(ControlState::Paused);
+    }
+
+    /// Resumes a simulation that was previously paused with [`Self::pause`].
+    pub fn resume(&self)
+    {
+        self.control.set(ControlState::Running);
+    }
+
+    /// Returns `true` if the background run has completed, or has been stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool
+    {
+        self.thread.is_finished()
+    }
+
+    /// The receiving end of the periodic status snapshots sent by the simulation.
+    ///
+    /// A snapshot is sent once every `status_interval` steps, as well as after the final step.
+    #[must_use]
+    pub const fn status(&self) -> &Receiver<SimulationStatus>
+    {
+        &self.status
+    }
+
+    /// Stops the simulation after the step that is currently being executed, and hands
+    /// it back to the calling thread.
+    ///
+    /// # Panics
+    ///
+    /// If any system in the simulation panicked on the background thread,
+    /// the panic is propagated to the caller.
+    #[must_use]
+    pub fn stop(self) -> Simulation
+    {
+        self.control.set(ControlState::Stopped);
+        self.join()
+    }
+
+    /// Waits for the simulation to complete all of its steps, and hands it back to
+    /// the calling thread.
+    ///
+    /// Note that this will block forever if the simulation is paused.
+    ///
+    /// # Panics
+    ///
+    /// If any system in the simulation panicked on the background thread,
+    /// the panic is propagated to the caller.
+    #[must_use]
+    pub fn join(self) -> Simulation
+    {
+        let main = self
+            .thread
+            .join()
+            .unwrap_or_else(|err| panic::resume_unwind(err));
+
+        Simulation::from_main(main)
+    }
+}
@@ -0,0 +1,8 @@
+//! Runs experiments described in TOML files, see [`incerto::cli`].
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode
+{
+    incerto::cli::main()
+}
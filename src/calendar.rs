@@ -0,0 +1,389 @@
+//! Calendar time for models with daily, weekly or seasonal cycles, such as commuting, weekend
+//! behavior or seasonal epidemics.
+//!
+//! The [`SimClock`] resource, added with [`crate::SimulationBuilder::add_clock`], maps every step
+//! to a time of day, a day of the week and a day of the year, given the number of steps in a day.
+//! Each day is split into daytime, from sunrise until sunset, and nighttime, and each year into
+//! four seasons of equal length, starting from [`Season::Spring`] on the first day of the year.
+//!
+//! User-defined systems can read the clock using [`Res<SimClock>`] arguments, or only run at
+//! certain times using the run conditions of this module, such as [`is_day`], [`on`] and
+//! [`in_season`].
+//!
+//! Example of agents that forage during the day and rest on weekends:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::calendar::{self, SimClock};
+//!
+//! #[derive(Component)]
+//! struct Forager(usize);
+//!
+//! fn forage(mut foragers: Query<&mut Forager>)
+//! {
+//!     for mut forager in &mut foragers
+//!     {
+//!         forager.0 += 1;
+//!     }
+//! }
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     // hourly steps, starting at midnight on a Monday
+//!     .add_clock(SimClock::new(24))
+//!     .add_entity_spawner(|spawner| {
+//!         spawner.spawn(Forager(0));
+//!     })
+//!     .add_systems(
+//!         forage
+//!             .run_if(calendar::is_day)
+//!             .run_if(calendar::is_weekday),
+//!     )
+//!     .build();
+//!
+//! // a week, with twelve hours of daylight on each day
+//! simulation.run(7 * 24);
+//! let forager = simulation.iter::<Forager>().next().unwrap();
+//! assert_eq!(forager.0, 5 * 12);
+//! ```
+
+use bevy::prelude::*;
+
+use crate::plugins::{StepNumber, step_counter_increment};
+
+/// A day of the week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Weekday
+{
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday
+{
+    /// All days of the week, in order.
+    pub const ALL: [Self; 7] = [
+        Self::Monday,
+        Self::Tuesday,
+        Self::Wednesday,
+        Self::Thursday,
+        Self::Friday,
+        Self::Saturday,
+        Self::Sunday,
+    ];
+
+    /// The day `days` days after this one.
+    #[must_use]
+    pub const fn after(self, days: usize) -> Self
+    {
+        Self::ALL[(self as usize + days) % 7]
+    }
+
+    /// Checks whether the day is a Saturday or a Sunday.
+    #[must_use]
+    pub const fn is_weekend(self) -> bool
+    {
+        matches!(self, Self::Saturday | Self::Sunday)
+    }
+}
+
+/// A season of the year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Season
+{
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season
+{
+    /// All seasons, in the order they occur in a year.
+    pub const ALL: [Self; 4] = [Self::Spring, Self::Summer, Self::Autumn, Self::Winter];
+}
+
+/// Resource mapping the steps of the simulation to calendar time.
+///
+/// The clock always refers to the same step as the [`StepNumber`] resource, so that during the
+/// `n`-th step user-defined systems read the time of the `n`-th step, and step `1` falls at the
+/// very start of the first day.
+/// See the [module documentation](self) for an example.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SimClock
+{
+    steps_per_day: usize,
+    sunrise: f64,
+    sunset: f64,
+    days_per_year: usize,
+    start_weekday: Weekday,
+    start_day_of_year: usize,
+    step: usize,
+}
+
+impl SimClock
+{
+    /// Creates a clock with `steps_per_day` steps in every day, starting at midnight on a Monday,
+    /// on the first day of a year of `365` days.
+    ///
+    /// Daytime lasts from `6:00` until `18:00` by default.
+    ///
+    /// # Panics
+    ///
+    /// If `steps_per_day` is `0`.
+    #[must_use]
+    pub fn new(steps_per_day: usize) -> Self
+    {
+        assert!(steps_per_day > 0, "a day must have at least one step");
+
+        Self {
+            steps_per_day,
+            sunrise: 0.25,
+            sunset: 0.75,
+            days_per_year: 365,
+            start_weekday: Weekday::Monday,
+            start_day_of_year: 0,
+            step: 1,
+        }
+    }
+
+    /// Sets the times of sunrise and sunset, as fractions of the day in `[0, 1]`, such that
+    /// `0.5` is noon.
+    ///
+    /// # Panics
+    ///
+    /// If the times are not within `[0, 1]`, or sunset is before sunrise.
+    #[must_use]
+    pub fn with_daylight(mut self, sunrise: f64, sunset: f64) -> Self
+    {
+        assert!(
+            (0.0..=1.0).contains(&sunrise) && (sunrise..=1.0).contains(&sunset),
+            "invalid daylight from {sunrise} to {sunset}"
+        );
+        self.sunrise = sunrise;
+        self.sunset = sunset;
+        self
+    }
+
+    /// Sets the number of days in every year.
+    ///
+    /// # Panics
+    ///
+    /// If `days_per_year` is less than `4`, or not after the day of the year of the first step.
+    #[must_use]
+    pub fn with_days_per_year(mut self, days_per_year: usize) -> Self
+    {
+        assert!(
+            days_per_year >= 4,
+            "a year must have at least one day per season"
+        );
+        assert!(
+            self.start_day_of_year < days_per_year,
+            "a year of {days_per_year} days has no day {}",
+            self.start_day_of_year
+        );
+        self.days_per_year = days_per_year;
+        self
+    }
+
+    /// Sets the day of the week and the day of the year of the first step, where the first day
+    /// of the year is `0`.
+    ///
+    /// # Panics
+    ///
+    /// If `day_of_year` is not within the year.
+    #[must_use]
+    pub fn starting_on(mut self, weekday: Weekday, day_of_year: usize) -> Self
+    {
+        assert!(
+            day_of_year < self.days_per_year,
+            "a year of {} days has no day {day_of_year}",
+            self.days_per_year
+        );
+        self.start_weekday = weekday;
+        self.start_day_of_year = day_of_year;
+        self
+    }
+
+    /// Sets the clock to the step of the given [`StepNumber`].
+    pub(crate) const fn synchronize(&mut self, step_number: &StepNumber)
+    {
+        self.step = step_number.get();
+    }
+
+    /// The number of steps in every day.
+    #[must_use]
+    pub const fn steps_per_day(&self) -> usize
+    {
+        self.steps_per_day
+    }
+
+    /// The number of days in every year.
+    #[must_use]
+    pub const fn days_per_year(&self) -> usize
+    {
+        self.days_per_year
+    }
+
+    /// The number of the step the clock refers to, as read from [`StepNumber`].
+    #[must_use]
+    pub const fn step(&self) -> usize
+    {
+        self.step
+    }
+
+    /// The number of whole days since the start of the simulation, which is `0` on the first day.
+    #[must_use]
+    pub const fn day(&self) -> usize
+    {
+        (self.step - 1) / self.steps_per_day
+    }
+
+    /// The index of the step within the current day, which is `0` on the first step of the day.
+    #[must_use]
+    pub const fn step_of_day(&self) -> usize
+    {
+        (self.step - 1) % self.steps_per_day
+    }
+
+    /// The time of day, as the fraction of the day that has passed until the start of the
+    /// current step, such that `0.5` is noon.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn time_of_day(&self) -> f64
+    {
+        self.step_of_day() as f64 / self.steps_per_day as f64
+    }
+
+    /// The hour of the day, from `0` to `23`, at the start of the current step.
+    #[must_use]
+    pub const fn hour(&self) -> usize
+    {
+        self.step_of_day() * 24 / self.steps_per_day
+    }
+
+    /// Checks whether the current step starts during daytime, i.e. between sunrise and sunset.
+    #[must_use]
+    pub fn is_day(&self) -> bool
+    {
+        (self.sunrise..self.sunset).contains(&self.time_of_day())
+    }
+
+    /// Checks whether the current step starts during nighttime, i.e. before sunrise or after
+    /// sunset.
+    #[must_use]
+    pub fn is_night(&self) -> bool
+    {
+        !self.is_day()
+    }
+
+    /// Checks whether the current step is the first of its day.
+    #[must_use]
+    pub const fn is_start_of_day(&self) -> bool
+    {
+        self.step_of_day() == 0
+    }
+
+    /// The current day of the week.
+    #[must_use]
+    pub const fn weekday(&self) -> Weekday
+    {
+        self.start_weekday.after(self.day())
+    }
+
+    /// Checks whether the current day is a Saturday or a Sunday.
+    #[must_use]
+    pub const fn is_weekend(&self) -> bool
+    {
+        self.weekday().is_weekend()
+    }
+
+    /// The current day of the year, where the first day of the year is `0`.
+    #[must_use]
+    pub const fn day_of_year(&self) -> usize
+    {
+        (self.start_day_of_year + self.day()) % self.days_per_year
+    }
+
+    /// The number of whole years since the first day of the year the simulation started in.
+    #[must_use]
+    pub const fn year(&self) -> usize
+    {
+        (self.start_day_of_year + self.day()) / self.days_per_year
+    }
+
+    /// The current season, where each season lasts a quarter of the year, rounded down.
+    #[must_use]
+    pub const fn season(&self) -> Season
+    {
+        let season = self.day_of_year() * 4 / self.days_per_year;
+        Season::ALL[season]
+    }
+}
+
+/// Run condition that is `true` when the current step starts during daytime.
+#[must_use]
+pub fn is_day(clock: Res<SimClock>) -> bool
+{
+    clock.is_day()
+}
+
+/// Run condition that is `true` when the current step starts during nighttime.
+#[must_use]
+pub fn is_night(clock: Res<SimClock>) -> bool
+{
+    clock.is_night()
+}
+
+/// Run condition that is `true` on the first step of every day.
+#[must_use]
+pub fn is_start_of_day(clock: Res<SimClock>) -> bool
+{
+    clock.is_start_of_day()
+}
+
+/// Run condition that is `true` from Mondays to Fridays.
+#[must_use]
+pub fn is_weekday(clock: Res<SimClock>) -> bool
+{
+    !clock.is_weekend()
+}
+
+/// Run condition that is `true` on Saturdays and Sundays.
+#[must_use]
+pub fn is_weekend(clock: Res<SimClock>) -> bool
+{
+    clock.is_weekend()
+}
+
+/// Run condition that is `true` on every step of the given day of the week.
+pub fn on(weekday: Weekday) -> impl FnMut(Res<SimClock>) -> bool + Clone
+{
+    move |clock: Res<SimClock>| clock.weekday() == weekday
+}
+
+/// Run condition that is `true` on every step of the given season.
+pub fn in_season(season: Season) -> impl FnMut(Res<SimClock>) -> bool + Clone
+{
+    move |clock: Res<SimClock>| clock.season() == season
+}
+
+/// Plugin that keeps the [`SimClock`] on the same step as the [`StepNumber`], added with
+/// [`crate::SimulationBuilder::add_clock`].
+pub(crate) struct ClockPlugin;
+
+impl Plugin for ClockPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(Last, clock_system.after(step_counter_increment));
+    }
+}
+
+fn clock_system(mut clock: ResMut<SimClock>, step_number: Res<StepNumber>)
+{
+    clock.synchronize(&step_number);
+}
@@ -0,0 +1,362 @@
+//! Cellular automata, in which every cell of a bounded grid updates its state on every step
+//! according to a rule over its own state and those of its neighbors.
+//!
+//! Cells are entities with a [`GridPosition2D`] and a state component `C`, and the automaton is
+//! added to a simulation with [`crate::SimulationBuilder::add_cellular_automaton`], configured
+//! through a [`CellularAutomatonPlugin`].
+//! The rule is either a function of the state of the cell and its [`Neighbors`], or a rule string
+//! of a life-like automaton, such as `"B3/S23"` for Conway's Game of Life.
+//!
+//! Updates are synchronous: the states of all cells at the start of a step are copied to a buffer,
+//! and every cell computes its next state from that buffer, so that no cell sees the updated
+//! state of another within the same step, regardless of the order in which they are visited.
+//! Hand-written systems that update cells through a mutable query do not have this property.
+//!
+//! Example of the Game of Life on a torus:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::cellular::CellularAutomatonPlugin;
+//! use rand::Rng;
+//!
+//! #[derive(Component, Clone, PartialEq)]
+//! struct Alive(bool);
+//!
+//! impl From<bool> for Alive
+//! {
+//!     fn from(alive: bool) -> Self
+//!     {
+//!         Self(alive)
+//!     }
+//! }
+//!
+//! impl From<&Alive> for bool
+//! {
+//!     fn from(cell: &Alive) -> Self
+//!     {
+//!         cell.0
+//!     }
+//! }
+//!
+//! let bounds = GridBounds2D {
+//!     min: IVec2::new(0, 0),
+//!     max: IVec2::new(63, 63),
+//! };
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_cellular_automaton(CellularAutomatonPlugin::<Alive>::life_like(bounds, "B3/S23").with_wrapping())
+//!     .add_seeded_entity_spawner(move |spawner, rng| {
+//!         spawner.spawn_grid(bounds, |_| Some(Alive(rng.random_bool(0.3))));
+//!     })
+//!     .build();
+//!
+//! simulation.run(100);
+//! let alive = simulation.iter::<Alive>().filter(|cell| cell.0).count();
+//! ```
+
+use std::sync::Arc;
+
+use bevy::{ecs::component::Mutable, prelude::*};
+
+use crate::plugins::{GridBounds2D, GridCoordinates, GridPosition2D};
+
+/// The state of a cell in a cellular automaton.
+///
+/// Automatically implemented for any mutable component that is [`Clone`] + [`PartialEq`].
+pub trait CellState: Component<Mutability = Mutable> + Clone + PartialEq {}
+
+impl<C> CellState for C where C: Component<Mutability = Mutable> + Clone + PartialEq {}
+
+/// Which of the surrounding cells are the neighbors of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Neighborhood
+{
+    /// The eight cells surrounding a cell, including the diagonal ones.
+    #[default]
+    Moore,
+
+    /// The four cells orthogonally adjacent to a cell.
+    VonNeumann,
+}
+
+impl Neighborhood
+{
+    const fn offsets(self) -> &'static [IVec2]
+    {
+        const MOORE: [IVec2; 8] = [
+            IVec2::new(-1, -1),
+            IVec2::new(-1, 0),
+            IVec2::new(-1, 1),
+            IVec2::new(0, -1),
+            IVec2::new(0, 1),
+            IVec2::new(1, -1),
+            IVec2::new(1, 0),
+            IVec2::new(1, 1),
+        ];
+        const VON_NEUMANN: [IVec2; 4] = [
+            IVec2::new(-1, 0),
+            IVec2::new(0, -1),
+            IVec2::new(0, 1),
+            IVec2::new(1, 0),
+        ];
+
+        match self
+        {
+            Self::Moore => &MOORE,
+            Self::VonNeumann => &VON_NEUMANN,
+        }
+    }
+}
+
+/// The states of the cells around a cell, as of the start of the current step.
+pub struct Neighbors<'a, C>
+{
+    automaton: &'a CellularAutomaton<C>,
+    position: IVec2,
+}
+
+impl<C> Neighbors<'_, C>
+{
+    /// The state of the cell at the given `offset` from this one, which need not be within the
+    /// neighborhood of the automaton.
+    ///
+    /// Returns `None` if there is no cell at that position, or it is outside the bounds of a grid
+    /// without wrapping.
+    #[must_use]
+    pub fn get(&self, offset: IVec2) -> Option<&C>
+    {
+        self.automaton.get(self.position + offset)
+    }
+
+    /// Iterates over the states of the cells in the neighborhood of this one.
+    pub fn iter(&self) -> impl Iterator<Item = &C>
+    {
+        self.automaton
+            .neighborhood
+            .offsets()
+            .iter()
+            .filter_map(|&offset| self.get(offset))
+    }
+
+    /// The number of cells in the neighborhood of this one whose state satisfies the `predicate`.
+    pub fn count(&self, predicate: impl Fn(&C) -> bool) -> usize
+    {
+        self.iter().filter(|&state| predicate(state)).count()
+    }
+}
+
+/// The rule of a cellular automaton, computing the next state of a cell from its current state
+/// and those of its neighbors.
+type Rule<C> = Arc<dyn Fn(&C, &Neighbors<C>) -> C + Send + Sync>;
+
+/// Plugin that updates the cells with state `C` of a bounded grid on every step,
+/// added to a simulation with [`crate::SimulationBuilder::add_cellular_automaton`].
+///
+/// Cells are entities with a [`GridPosition2D`] and a `C` component, with at most one cell on
+/// every position; cells outside the bounds are not updated, and positions without a cell
+/// are skipped when iterating over the [`Neighbors`] of a cell.
+/// The cells are updated once per step, before any user-defined systems run.
+pub struct CellularAutomatonPlugin<C: CellState>
+{
+    bounds: GridBounds2D,
+    neighborhood: Neighborhood,
+    wrapping: bool,
+    rule: Rule<C>,
+}
+
+impl<C: CellState> CellularAutomatonPlugin<C>
+{
+    /// Creates an automaton on the given `bounds`, in which every cell moves to the state returned
+    /// by the `rule`, given its current state and its neighbors.
+    ///
+    /// By default, the neighbors of a cell are those of the [`Neighborhood::Moore`], and the
+    /// edges of the grid do not wrap around.
+    #[must_use]
+    pub fn new(
+        bounds: GridBounds2D,
+        rule: impl Fn(&C, &Neighbors<C>) -> C + Send + Sync + 'static,
+    ) -> Self
+    {
+        Self {
+            bounds,
+            neighborhood: Neighborhood::Moore,
+            wrapping: false,
+            rule: Arc::new(rule),
+        }
+    }
+
+    /// Sets which of the surrounding cells are the neighbors of a cell.
+    #[must_use]
+    pub const fn with_neighborhood(mut self, neighborhood: Neighborhood) -> Self
+    {
+        self.neighborhood = neighborhood;
+        self
+    }
+
+    /// Makes the edges of the grid wrap around, so that the cells on opposite edges are
+    /// neighbors, as on a torus.
+    #[must_use]
+    pub const fn with_wrapping(mut self) -> Self
+    {
+        self.wrapping = true;
+        self
+    }
+}
+
+impl<C> CellularAutomatonPlugin<C>
+where
+    C: CellState + From<bool>,
+    for<'a> &'a C: Into<bool>,
+{
+    /// Creates a life-like automaton on the given `bounds`, in which every cell is either alive or
+    /// dead, from a rule string in the `B/S` notation.
+    ///
+    /// The digits after `B` are the numbers of live neighbors for which a dead cell is born,
+    /// and those after `S` the numbers for which a live cell survives, so that `"B3/S23"` is
+    /// Conway's Game of Life and `"B36/S23"` is `HighLife`.
+    /// The letters are case-insensitive, and the two parts may be given in either order.
+    ///
+    /// # Panics
+    ///
+    /// If the rule string is not in the `B/S` notation.
+    #[must_use]
+    pub fn life_like(bounds: GridBounds2D, rule: &str) -> Self
+    {
+        let Some((born, survive)) = parse_rule_string(rule)
+        else
+        {
+            panic!("invalid rule string: {rule}");
+        };
+
+        Self::new(bounds, move |cell, neighbors| {
+            let alive = neighbors.count(|neighbor| neighbor.into());
+            let counts = if cell.into() { survive } else { born };
+            C::from(counts[alive])
+        })
+    }
+}
+
+impl<C: CellState> Plugin for CellularAutomatonPlugin<C>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.insert_resource(CellularAutomaton::<C> {
+            bounds: self.bounds,
+            neighborhood: self.neighborhood,
+            wrapping: self.wrapping,
+            rule: self.rule.clone(),
+            cells: Vec::new(),
+        })
+        .add_systems(PreUpdate, cellular_automaton_system::<C>);
+    }
+}
+
+/// Parses a rule string in the `B/S` notation into the numbers of live neighbors for which
+/// cells are born and survive.
+fn parse_rule_string(rule: &str) -> Option<([bool; 9], [bool; 9])>
+{
+    let mut born = None;
+    let mut survive = None;
+    for part in rule.split('/')
+    {
+        let mut chars = part.trim().chars();
+        let target = match chars.next()?.to_ascii_uppercase()
+        {
+            'B' => &mut born,
+            'S' => &mut survive,
+            _ => return None,
+        };
+
+        let mut counts = [false; 9];
+        for digit in chars
+        {
+            *counts.get_mut(digit.to_digit(10)? as usize)? = true;
+        }
+
+        if target.replace(counts).is_some()
+        {
+            return None;
+        }
+    }
+
+    Some((born?, survive?))
+}
+
+/// Resource holding the rule of an automaton, and the buffer with the states of its cells
+/// at the start of the current step.
+#[derive(Resource)]
+struct CellularAutomaton<C>
+{
+    bounds: GridBounds2D,
+    neighborhood: Neighborhood,
+    wrapping: bool,
+    rule: Rule<C>,
+    cells: Vec<Option<C>>,
+}
+
+impl<C> CellularAutomaton<C>
+{
+    /// The state of the cell at the given position in the buffer, wrapping it around the edges
+    /// of the grid if enabled.
+    fn get(&self, position: IVec2) -> Option<&C>
+    {
+        let position = if self.wrapping
+        {
+            let size = self.bounds.max - self.bounds.min + IVec2::ONE;
+            self.bounds.min + (position - self.bounds.min).rem_euclid(size)
+        }
+        else
+        {
+            position
+        };
+
+        if !self.bounds.contains(&position)
+        {
+            return None;
+        }
+
+        self.cells[position.cell_index(&self.bounds)].as_ref()
+    }
+}
+
+/// System that moves every cell to its next state, computed from the states of all cells at the
+/// start of the step.
+fn cellular_automaton_system<C: CellState>(
+    mut automaton: ResMut<CellularAutomaton<C>>,
+    mut query: Query<(&GridPosition2D, &mut C)>,
+)
+{
+    let automaton = &mut *automaton;
+    let bounds = automaton.bounds;
+
+    // the buffer is kept between steps to reuse its allocation
+    automaton.cells.clear();
+    automaton
+        .cells
+        .resize_with(IVec2::cell_count(&bounds), || None);
+    for (position, state) in &query
+    {
+        if bounds.contains(&position.0)
+        {
+            automaton.cells[position.0.cell_index(&bounds)] = Some(state.clone());
+        }
+    }
+
+    let automaton = &*automaton;
+    for (position, mut state) in &mut query
+    {
+        if !bounds.contains(&position.0)
+        {
+            continue;
+        }
+        let Some(current) = &automaton.cells[position.0.cell_index(&bounds)]
+        else
+        {
+            continue;
+        };
+
+        let neighbors = Neighbors {
+            automaton,
+            position: position.0,
+        };
+        state.set_if_neq((automaton.rule)(current, &neighbors));
+    }
+}
@@ -0,0 +1,628 @@
+//! Batch execution of experiments described in TOML files, enabled with the `cli` feature.
+//!
+//! Models are registered in a [`ModelRegistry`] within a plugin, which is a library crate
+//! built with `crate-type = ["cdylib"]` that exports its registry with [`crate::export_models`].
+//! The `incerto-run` binary then loads the plugin referenced by each experiment description,
+//! runs the requested ensemble of replicas for every combination of parameters, and writes their
+//! results to a CSV file, or to a Parquet or Feather file if the `arrow` feature is also enabled.
+//! This makes batch execution on clusters scriptable, without a new `main.rs` per experiment.
+//!
+//! The plugin and the binary exchange requests and results as JSON, so the simulations are both
+//! built and run within the plugin. The plugin should be built against the same version of this
+//! crate as the binary.
+//!
+//! Example plugin:
+//! ```
+//! # use incerto::{prelude::*, cli::ModelRegistry};
+//! #[derive(Component)]
+//! struct Cash(f64);
+//!
+//! fn models() -> ModelRegistry
+//! {
+//!     ModelRegistry::new().register(
+//!         "compound_interest",
+//!         &["cash"],
+//!         |parameters| {
+//!             let rate = parameters.get("rate").copied().unwrap_or(0.01);
+//!             SimulationBuilder::new()
+//!                 .add_entity_spawner(|spawner| {
+//!                     spawner.spawn(Cash(100.0));
+//!                 })
+//!                 .add_systems(move |mut query: Query<&mut Cash>| {
+//!                     for mut cash in &mut query
+//!                     {
+//!                         cash.0 *= 1.0 + rate;
+//!                     }
+//!                 })
+//!         },
+//!         |simulation| simulation.iter::<Cash>().map(|cash| cash.0).collect(),
+//!     )
+//! }
+//!
+//! incerto::export_models!(models());
+//! ```
+//!
+//! And the experiment description, run with `incerto-run experiment.toml`:
+//! ```toml
+//! plugin = "target/release/libinterest.so"
+//! model = "compound_interest"
+//! replicas = 100
+//! steps = 365
+//! seed = 7
+//! output = "results.csv"
+//!
+//! [parameters]
+//! rate = 0.02
+//!
+//! # optionally, run the ensemble for every combination of these values
+//! [sweep]
+//! rate = [0.01, 0.02, 0.05]
+//! ```
+//!
+//! The output holds one row per replica, with a column for each parameter, a `replica` column
+//! with the index of the replica in its ensemble, and the columns of the model's results.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::{CStr, CString, c_char},
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Simulation, SimulationBuilder, error::RunnerError};
+
+/// The named parameters of a single ensemble of an experiment.
+pub type Parameters = BTreeMap<String, f64>;
+
+type BuildFn = Box<dyn Fn(&Parameters) -> SimulationBuilder + Send + Sync>;
+type CollectFn = Box<dyn Fn(&Simulation) -> Vec<f64> + Send + Sync>;
+
+/// The name of the function through which plugins run their models, see [`crate::export_models`].
+const RUN_SYMBOL: &[u8] = b"incerto_run_model";
+
+/// The name of the function through which plugins free the results they returned.
+const FREE_SYMBOL: &[u8] = b"incerto_free_results";
+
+/// A model registered in a [`ModelRegistry`].
+struct Model
+{
+    columns: Vec<String>,
+    build: BuildFn,
+    collect: CollectFn,
+}
+
+/// A collection of named models, which can be run by the `incerto-run` binary.
+///
+/// See the [module-level documentation](self) for an example.
+#[derive(Default)]
+pub struct ModelRegistry
+{
+    models: HashMap<String, Model>,
+}
+
+impl ModelRegistry
+{
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Registers a model under the given `name`, replacing any registered before it.
+    ///
+    /// The function `build` is called once for every replica, with the parameters of the run,
+    /// and shall return the builder of the simulation.
+    /// Once the replica has finished running, the function `collect` is called to extract its
+    /// results, one for each of the given `columns`.
+    #[must_use]
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        columns: &[&str],
+        build: impl Fn(&Parameters) -> SimulationBuilder + Send + Sync + 'static,
+        collect: impl Fn(&Simulation) -> Vec<f64> + Send + Sync + 'static,
+    ) -> Self
+    {
+        self.models.insert(
+            name.into(),
+            Model {
+                columns: columns.iter().map(ToString::to_string).collect(),
+                build: Box::new(build),
+                collect: Box::new(collect),
+            },
+        );
+        self
+    }
+
+    /// Runs an ensemble of replicas of a model in parallel, and collects their results
+    /// in order of their index.
+    ///
+    /// If a `seed` is given, the replicas are seeded from it as with
+    /// [`Simulation::replicate_seeded`].
+    ///
+    /// # Errors
+    ///
+    /// - [`RunnerError::ModelNotFound`]
+    /// - [`RunnerError::InvalidResults`]
+    pub fn run(&self, request: &RunRequest) -> Result<ModelResults, RunnerError>
+    {
+        let model = self
+            .models
+            .get(&request.model)
+            .ok_or_else(|| RunnerError::ModelNotFound {
+                model: request.model.clone(),
+            })?;
+        let build_replica = |_| (model.build)(&request.parameters);
+
+        let simulations = request.seed.map_or_else(
+            || Simulation::replicate(request.replicas, request.steps, build_replica),
+            |seed| {
+                Simulation::replicate_seeded(seed, request.replicas, request.steps, build_replica)
+            },
+        );
+
+        let rows = simulations
+            .iter()
+            .map(|simulation| (model.collect)(simulation))
+            .collect::<Vec<_>>();
+        if rows.iter().any(|row| row.len() != model.columns.len())
+        {
+            return Err(RunnerError::InvalidResults {
+                model: request.model.clone(),
+            });
+        }
+
+        Ok(ModelResults {
+            columns: model.columns.clone(),
+            rows,
+        })
+    }
+}
+
+/// A request to run an ensemble of replicas of a model, see [`ModelRegistry::run`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRequest
+{
+    pub model: String,
+    pub replicas: usize,
+    pub steps: usize,
+    pub seed: Option<u64>,
+    pub parameters: Parameters,
+}
+
+/// The results of an ensemble of replicas of a model, with one row per replica.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelResults
+{
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<f64>>,
+}
+
+/// The description of an experiment, as read from a TOML file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentConfig
+{
+    /// The path to the plugin holding the model, relative to the experiment description.
+    pub plugin: PathBuf,
+
+    /// The name under which the model is registered in the plugin.
+    pub model: String,
+
+    /// The number of replicas in each ensemble.
+    pub replicas: usize,
+
+    /// The number of steps to run each replica for.
+    pub steps: usize,
+
+    /// The seed from which the replicas of each ensemble are seeded, if any.
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// The path to the output file, relative to the experiment description.
+    pub output: PathBuf,
+
+    /// The parameters common to all ensembles.
+    #[serde(default)]
+    pub parameters: Parameters,
+
+    /// The values of the swept parameters, with one ensemble run for every combination of them.
+    #[serde(default)]
+    pub sweep: BTreeMap<String, Vec<f64>>,
+}
+
+impl ExperimentConfig
+{
+    /// Parses the description of an experiment from TOML.
+    ///
+    /// # Errors
+    ///
+    /// - [`RunnerError::InvalidConfig`]
+    pub fn parse(contents: &str) -> Result<Self, RunnerError>
+    {
+        toml::from_str(contents).map_err(|err| RunnerError::InvalidConfig {
+            message: err.message().to_string(),
+        })
+    }
+
+    /// Reads the description of an experiment from a TOML file, resolving the paths in it
+    /// relative to the directory of the file.
+    ///
+    /// # Errors
+    ///
+    /// - [`RunnerError::InvalidConfig`]
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, RunnerError>
+    {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|err| RunnerError::InvalidConfig {
+            message: err.to_string(),
+        })?;
+
+        let mut config = Self::parse(&contents)?;
+        if let Some(dir) = path.parent()
+        {
+            config.plugin = dir.join(&config.plugin);
+            config.output = dir.join(&config.output);
+        }
+        Ok(config)
+    }
+
+    /// Every set of parameters to run an ensemble with, combining the common parameters with
+    /// each combination of the swept values.
+    #[must_use]
+    pub fn parameter_sets(&self) -> Vec<Parameters>
+    {
+        self.sweep
+            .iter()
+            .fold(vec![self.parameters.clone()], |sets, (name, values)| {
+                sets.iter()
+                    .flat_map(|set| {
+                        values.iter().map(move |&value| {
+                            let mut set = set.clone();
+                            set.insert(name.clone(), value);
+                            set
+                        })
+                    })
+                    .collect()
+            })
+    }
+
+    /// Runs the experiment, with each ensemble run by the given `run` function,
+    /// and writes the results to the output file.
+    ///
+    /// The output is written as Parquet if its extension is `parquet`, as Feather if it is
+    /// `feather` or `arrow`, and as CSV otherwise. Parquet and Feather outputs additionally hold
+    /// the `model`, `replicas`, `steps` and `seed` of the experiment in the metadata of their
+    /// schema, where a missing seed is stored as an empty string.
+    ///
+    /// # Errors
+    ///
+    /// - [`RunnerError::Output`]
+    /// - Any error returned by `run`.
+    pub fn execute(
+        &self,
+        run: impl Fn(&RunRequest) -> Result<ModelResults, RunnerError>,
+    ) -> Result<(), RunnerError>
+    {
+        let mut table = Table::default();
+        for parameters in self.parameter_sets()
+        {
+            let request = RunRequest {
+                model: self.model.clone(),
+                replicas: self.replicas,
+                steps: self.steps,
+                seed: self.seed,
+                parameters,
+            };
+            table.append(&request.parameters, run(&request)?);
+        }
+
+        let format = match self.output.extension().and_then(|ext| ext.to_str())
+        {
+            Some("parquet") => OutputFormat::Parquet,
+            Some("feather" | "arrow") => OutputFormat::Feather,
+            _ => return table.write_csv(&self.output),
+        };
+        let metadata = [
+            ("model", self.model.clone()),
+            ("replicas", self.replicas.to_string()),
+            ("steps", self.steps.to_string()),
+            (
+                "seed",
+                self.seed.map(|seed| seed.to_string()).unwrap_or_default(),
+            ),
+        ];
+        table.write_arrow(&self.output, format, &metadata)
+    }
+}
+
+/// The formats of Arrow-based output files.
+enum OutputFormat
+{
+    Parquet,
+    Feather,
+}
+
+/// The rows of results of all ensembles of an experiment.
+#[derive(Default)]
+struct Table
+{
+    columns: Vec<String>,
+    rows: Vec<Vec<f64>>,
+}
+
+impl Table
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn append(&mut self, parameters: &Parameters, results: ModelResults)
+    {
+        if self.columns.is_empty()
+        {
+            self.columns = parameters
+                .keys()
+                .cloned()
+                .chain([String::from("replica")])
+                .chain(results.columns)
+                .collect();
+        }
+
+        for (replica, row) in results.rows.into_iter().enumerate()
+        {
+            self.rows.push(
+                parameters
+                    .values()
+                    .copied()
+                    .chain([replica as f64])
+                    .chain(row)
+                    .collect(),
+            );
+        }
+    }
+
+    fn write_csv(&self, path: &Path) -> Result<(), RunnerError>
+    {
+        let mut writer = csv::Writer::from_path(path).map_err(|_| RunnerError::Output)?;
+
+        writer
+            .write_record(&self.columns)
+            .map_err(|_| RunnerError::Output)?;
+        for row in &self.rows
+        {
+            writer
+                .write_record(row.iter().map(ToString::to_string))
+                .map_err(|_| RunnerError::Output)?;
+        }
+
+        writer.flush().map_err(|_| RunnerError::Output)
+    }
+
+    #[cfg(feature = "arrow")]
+    fn write_arrow(
+        &self,
+        path: &Path,
+        format: OutputFormat,
+        metadata: &[(&str, String)],
+    ) -> Result<(), RunnerError>
+    {
+        use crate::ArrowValue;
+
+        let columns = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let values = self.rows.iter().map(|row| row[idx]).collect();
+                (name.as_str(), f64::data_type(), f64::into_array(values))
+            })
+            .collect();
+        let batch = crate::arrow::record_batch(columns)
+            .and_then(|batch| {
+                crate::arrow::with_schema_metadata(
+                    &batch,
+                    metadata
+                        .iter()
+                        .map(|(key, value)| ((*key).to_string(), value.clone())),
+                )
+            })
+            .map_err(|_| RunnerError::Output)?;
+
+        match format
+        {
+            OutputFormat::Parquet => crate::write_parquet(&batch, path),
+            OutputFormat::Feather => crate::write_feather(&batch, path),
+        }
+        .map_err(|_| RunnerError::Output)
+    }
+
+    #[cfg(not(feature = "arrow"))]
+    #[allow(clippy::unused_self)]
+    fn write_arrow(
+        &self,
+        _path: &Path,
+        _format: OutputFormat,
+        _metadata: &[(&str, String)],
+    ) -> Result<(), RunnerError>
+    {
+        Err(RunnerError::InvalidConfig {
+            message: String::from("writing parquet and feather files requires the `arrow` feature"),
+        })
+    }
+}
+
+/// A plugin library holding a [`ModelRegistry`], exported with [`crate::export_models`].
+pub struct Plugin
+{
+    library: libloading::Library,
+}
+
+impl Plugin
+{
+    /// Loads the plugin library at the given `path`.
+    ///
+    /// # Errors
+    ///
+    /// - [`RunnerError::PluginUnavailable`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RunnerError>
+    {
+        // SAFETY: loading a library runs its initialization routines, which is the reason for
+        // loading a plugin in the first place
+        let library = unsafe { libloading::Library::new(path.as_ref()) }.map_err(|err| {
+            RunnerError::PluginUnavailable {
+                message: err.to_string(),
+            }
+        })?;
+
+        Ok(Self { library })
+    }
+
+    /// Runs an ensemble of replicas of a model registered in the plugin.
+    ///
+    /// # Errors
+    ///
+    /// - [`RunnerError::PluginUnavailable`]
+    /// - Any error returned by [`ModelRegistry::run`] within the plugin.
+    pub fn run(&self, request: &RunRequest) -> Result<ModelResults, RunnerError>
+    {
+        let unavailable = |message: String| RunnerError::PluginUnavailable { message };
+
+        let request = serde_json::to_string(request)
+            .ok()
+            .and_then(|request| CString::new(request).ok())
+            .ok_or_else(|| unavailable(String::from("the request could not be encoded")))?;
+
+        // SAFETY: the symbols are defined by `export_models!` with these exact signatures
+        let response = unsafe {
+            let run = self
+                .library
+                .get::<unsafe extern "C" fn(*const c_char) -> *mut c_char>(RUN_SYMBOL)
+                .map_err(|err| unavailable(err.to_string()))?;
+            let free = self
+                .library
+                .get::<unsafe extern "C" fn(*mut c_char)>(FREE_SYMBOL)
+                .map_err(|err| unavailable(err.to_string()))?;
+
+            let results = run(request.as_ptr());
+            let response = CStr::from_ptr(results).to_string_lossy().into_owned();
+            free(results);
+            response
+        };
+
+        serde_json::from_str::<Result<ModelResults, RunnerError>>(&response)
+            .map_err(|_| unavailable(String::from("the plugin returned malformed results")))?
+    }
+}
+
+/// Runs a model on behalf of the `incerto-run` binary, for the implementation of
+/// [`crate::export_models`].
+///
+/// The request and the results are exchanged as JSON, so that only C strings cross the
+/// boundary between the plugin and the binary.
+///
+/// # Safety
+///
+/// The `request` must be a valid, nul-terminated C string.
+#[doc(hidden)]
+#[must_use]
+pub unsafe fn run_exported(registry: &ModelRegistry, request: *const c_char) -> *mut c_char
+{
+    // SAFETY: guaranteed by the caller
+    let request = unsafe { CStr::from_ptr(request) }.to_string_lossy();
+    let results = serde_json::from_str::<RunRequest>(&request)
+        .map_err(|err| RunnerError::InvalidConfig {
+            message: err.to_string(),
+        })
+        .and_then(|request| registry.run(&request));
+
+    let response = serde_json::to_string(&results).unwrap_or_default();
+    CString::new(response).unwrap_or_default().into_raw()
+}
+
+/// Frees the results returned by [`run_exported`], for the implementation of
+/// [`crate::export_models`].
+///
+/// # Safety
+///
+/// The `results` must have been returned by [`run_exported`], and not been freed before.
+#[doc(hidden)]
+pub unsafe fn free_exported(results: *mut c_char)
+{
+    // SAFETY: guaranteed by the caller
+    drop(unsafe { CString::from_raw(results) });
+}
+
+/// Exports a [`ModelRegistry`] from a plugin library, to be run by the `incerto-run` binary.
+///
+/// The given expression is evaluated once, the first time a model is run, and shall return
+/// the registry. See the [`cli`](crate::cli) module for details.
+#[macro_export]
+macro_rules! export_models {
+    ($registry:expr) => {
+        fn __incerto_models() -> &'static $crate::cli::ModelRegistry
+        {
+            static REGISTRY: ::std::sync::OnceLock<$crate::cli::ModelRegistry> =
+                ::std::sync::OnceLock::new();
+            REGISTRY.get_or_init(|| $registry)
+        }
+
+        /// # Safety
+        ///
+        /// The `request` must be a valid, nul-terminated C string.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn incerto_run_model(
+            request: *const ::std::ffi::c_char,
+        ) -> *mut ::std::ffi::c_char
+        {
+            // SAFETY: guaranteed by the caller
+            unsafe { $crate::cli::run_exported(__incerto_models(), request) }
+        }
+
+        /// # Safety
+        ///
+        /// The `results` must have been returned by `incerto_run_model`, and not been freed before.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn incerto_free_results(results: *mut ::std::ffi::c_char)
+        {
+            // SAFETY: guaranteed by the caller
+            unsafe { $crate::cli::free_exported(results) }
+        }
+    };
+}
+
+/// The entry point of the `incerto-run` binary.
+///
+/// Runs every experiment description given as a command line argument in order,
+/// and stops at the first one that fails.
+#[must_use]
+pub fn main() -> ExitCode
+{
+    let paths = std::env::args().skip(1).collect::<Vec<_>>();
+    if paths.is_empty()
+    {
+        eprintln!("usage: incerto-run <experiment.toml>...");
+        return ExitCode::FAILURE;
+    }
+
+    for path in paths
+    {
+        let result = ExperimentConfig::read(&path).and_then(|config| {
+            let plugin = Plugin::load(&config.plugin)?;
+            config.execute(|request| plugin.run(request))?;
+            Ok(config.output)
+        });
+
+        match result
+        {
+            Ok(output) => eprintln!("{path}: results written to {}", output.display()),
+            Err(err) =>
+            {
+                eprintln!("{path}: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,496 @@
+//! Comparisons of scenarios, such as a baseline and the interventions on it, with hypothesis
+//! tests on their outputs.
+//!
+//! A set of named [`Scenarios`], each given by a different configuration of the simulation, is run
+//! for the same number of replicas with paired seeds, such that the `i`-th replica of every
+//! scenario shares the same seed.
+//! As long as the scenarios draw their randomness in a similar order, the paired replicas then
+//! face the same circumstances, and the differences between them are due to the scenarios rather
+//! than chance, which makes the comparisons much more sensitive.
+//!
+//! The chosen outputs of every replica are collected into a [`ScenarioComparison`], which tests
+//! the differences between any two scenarios with a [`paired_t_test`] or a [`mann_whitney_u`]
+//! test, and summarizes every scenario against the first one in a table of [`ComparisonRow`]s.
+//!
+//! Example of an intervention that lowers the chance of infection:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::comparison::Scenarios;
+//! use rand::Rng;
+//!
+//! #[derive(Component)]
+//! struct Infected(bool);
+//!
+//! fn population(chance: f64) -> impl Fn(usize) -> SimulationBuilder + Sync
+//! {
+//!     move |_| {
+//!         SimulationBuilder::new().add_seeded_entity_spawner(move |spawner, rng| {
+//!             for _ in 0..100
+//!             {
+//!                 spawner.spawn(Infected(rng.random_bool(chance)));
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! let comparison = Scenarios::new(42, 30, 1)
+//!     .add_scenario("baseline", population(0.3))
+//!     .add_scenario("masks", population(0.25))
+//!     .add_output("infected", |simulation| {
+//!         simulation.iter::<Infected>().filter(|infected| infected.0).count() as f64
+//!     })
+//!     .run();
+//!
+//! let test = comparison.paired_t_test("baseline", "masks", "infected").unwrap();
+//! assert!(test.is_significant(0.01));
+//!
+//! let row = &comparison.table()[0];
+//! assert_eq!(row.scenario, "masks");
+//! assert!(row.difference.unwrap().upper < 0.0);
+//! println!("{comparison}");
+//! ```
+
+use std::fmt;
+
+use crate::{
+    ConfidenceInterval, EnsembleResult, Simulation, SimulationBuilder,
+    statistics::{normal_cdf, student_t_cdf},
+};
+
+type BuildScenario = Box<dyn Fn(usize) -> SimulationBuilder + Sync>;
+type SampleOutput = Box<dyn Fn(&Simulation) -> f64 + Sync>;
+
+/// A set of named scenarios to compare on a set of named outputs.
+///
+/// See the [module documentation](self) for an example.
+pub struct Scenarios
+{
+    seed: u64,
+    num_replicas: usize,
+    num_steps: usize,
+    builders: Vec<(String, BuildScenario)>,
+    outputs: Vec<(String, SampleOutput)>,
+}
+
+impl Scenarios
+{
+    /// Creates a comparison in which every scenario is run for `num_replicas` replicas of
+    /// `num_steps` steps each, with seeds derived from the common `seed` as in
+    /// [`Simulation::replicate_seeded`].
+    ///
+    /// # Panics
+    ///
+    /// If `num_replicas` is less than `2`, since no differences can be tested with fewer.
+    #[must_use]
+    pub fn new(seed: u64, num_replicas: usize, num_steps: usize) -> Self
+    {
+        assert!(
+            num_replicas >= 2,
+            "a comparison needs at least two replicas per scenario"
+        );
+
+        Self {
+            seed,
+            num_replicas,
+            num_steps,
+            builders: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Adds a scenario, whose replicas are built by `build_replica` given their index.
+    ///
+    /// The first scenario added is the baseline that the others are compared against in
+    /// [`ScenarioComparison::table`].
+    /// Any seed set by `build_replica` is overridden by the paired seed of the replica.
+    ///
+    /// # Panics
+    ///
+    /// If a scenario with the same name has already been added.
+    #[must_use]
+    pub fn add_scenario(
+        mut self,
+        name: impl Into<String>,
+        build_replica: impl Fn(usize) -> SimulationBuilder + Sync + 'static,
+    ) -> Self
+    {
+        let name = name.into();
+        assert!(
+            self.builders.iter().all(|(other, _)| *other != name),
+            "duplicate scenario: {name}"
+        );
+        self.builders.push((name, Box::new(build_replica)));
+        self
+    }
+
+    /// Adds an output, sampled by `output` from every replica once it has run.
+    ///
+    /// # Panics
+    ///
+    /// If an output with the same name has already been added.
+    #[must_use]
+    pub fn add_output(
+        mut self,
+        name: impl Into<String>,
+        output: impl Fn(&Simulation) -> f64 + Sync + 'static,
+    ) -> Self
+    {
+        let name = name.into();
+        assert!(
+            self.outputs.iter().all(|(other, _)| *other != name),
+            "duplicate output: {name}"
+        );
+        self.outputs.push((name, Box::new(output)));
+        self
+    }
+
+    /// Runs the replicas of every scenario, one scenario after the other, and collects their
+    /// outputs.
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer than two scenarios or no outputs, or any of the outputs is not finite.
+    /// If any system in any of the replicas panicked, the panic is propagated to the caller.
+    #[must_use]
+    pub fn run(&self) -> ScenarioComparison
+    {
+        assert!(
+            self.builders.len() >= 2,
+            "a comparison needs at least two scenarios"
+        );
+        assert!(!self.outputs.is_empty(), "a comparison needs an output");
+
+        let results = self
+            .builders
+            .iter()
+            .map(|(_, build_replica)| {
+                let replicas = Simulation::replicate_seeded(
+                    self.seed,
+                    self.num_replicas,
+                    self.num_steps,
+                    build_replica,
+                );
+                self.outputs
+                    .iter()
+                    .map(|(_, output)| EnsembleResult::from_replicas(&replicas, output))
+                    .collect()
+            })
+            .collect();
+
+        ScenarioComparison {
+            scenarios: self.builders.iter().map(|(name, _)| name.clone()).collect(),
+            outputs: self.outputs.iter().map(|(name, _)| name.clone()).collect(),
+            results,
+        }
+    }
+}
+
+/// The outputs of every replica of a set of [`Scenarios`], as returned by [`Scenarios::run`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScenarioComparison
+{
+    scenarios: Vec<String>,
+    outputs: Vec<String>,
+    /// The results of every output, of every scenario.
+    results: Vec<Vec<EnsembleResult>>,
+}
+
+impl ScenarioComparison
+{
+    /// The names of the scenarios, in the order they were added.
+    #[must_use]
+    pub fn scenarios(&self) -> &[String]
+    {
+        &self.scenarios
+    }
+
+    /// The names of the outputs, in the order they were added.
+    #[must_use]
+    pub fn outputs(&self) -> &[String]
+    {
+        &self.outputs
+    }
+
+    /// The values of an output in every replica of a scenario, in order of the replicas, or
+    /// `None` if there is no such scenario or output.
+    #[must_use]
+    pub fn result(&self, scenario: &str, output: &str) -> Option<&EnsembleResult>
+    {
+        let scenario = self.scenarios.iter().position(|name| name == scenario)?;
+        let output = self.outputs.iter().position(|name| name == output)?;
+        Some(&self.results[scenario][output])
+    }
+
+    /// The differences of an output between every pair of replicas of scenarios `a` and `b`,
+    /// as `b - a`, or `None` if there is no such scenario or output.
+    #[must_use]
+    pub fn differences(&self, a: &str, b: &str, output: &str) -> Option<EnsembleResult>
+    {
+        let (a, b) = (self.result(a, output)?, self.result(b, output)?);
+        Some(
+            a.values()
+                .iter()
+                .zip(b.values())
+                .map(|(a, b)| b - a)
+                .collect(),
+        )
+    }
+
+    /// Tests whether an output differs between scenarios `a` and `b` with a [`paired_t_test`],
+    /// or `None` if there is no such scenario or output.
+    #[must_use]
+    pub fn paired_t_test(&self, a: &str, b: &str, output: &str) -> Option<HypothesisTest>
+    {
+        let (a, b) = (self.result(a, output)?, self.result(b, output)?);
+        paired_t_test(a.values(), b.values())
+    }
+
+    /// Tests whether an output differs between scenarios `a` and `b` with a [`mann_whitney_u`]
+    /// test, or `None` if there is no such scenario or output.
+    #[must_use]
+    pub fn mann_whitney_u(&self, a: &str, b: &str, output: &str) -> Option<HypothesisTest>
+    {
+        let (a, b) = (self.result(a, output)?, self.result(b, output)?);
+        mann_whitney_u(a.values(), b.values())
+    }
+
+    /// Compares every scenario to the first one, on every output, in order of the outputs and
+    /// then the scenarios.
+    #[must_use]
+    pub fn table(&self) -> Vec<ComparisonRow>
+    {
+        let baseline = &self.scenarios[0];
+        let mut rows = Vec::new();
+        for output in &self.outputs
+        {
+            for scenario in &self.scenarios[1..]
+            {
+                let result = |name| self.result(name, output).and_then(EnsembleResult::mean);
+                let differences = self.differences(baseline, scenario, output);
+
+                rows.push(ComparisonRow {
+                    output: output.clone(),
+                    scenario: scenario.clone(),
+                    baseline: result(baseline).unwrap_or(f64::NAN),
+                    mean: result(scenario).unwrap_or(f64::NAN),
+                    difference: differences.and_then(|differences| {
+                        differences.confidence_interval(ComparisonRow::LEVEL)
+                    }),
+                    paired_t: self.paired_t_test(baseline, scenario, output),
+                    mann_whitney: self.mann_whitney_u(baseline, scenario, output),
+                });
+            }
+        }
+        rows
+    }
+}
+
+impl fmt::Display for ScenarioComparison
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let rows = self.table();
+        let output_width = rows.iter().map(|row| row.output.len()).fold(6, usize::max);
+        let scenario_width = rows
+            .iter()
+            .map(|row| row.scenario.len())
+            .fold(8, usize::max);
+        let p_value = |test: Option<HypothesisTest>| {
+            test.map_or_else(|| "-".to_string(), |test| format!("{:.4}", test.p_value))
+        };
+
+        write!(
+            f,
+            "{:<output_width$}  {:<scenario_width$}  {:>12}  {:>12}  {:>28}  {:>10}  {:>10}",
+            "output", "scenario", "baseline", "mean", "difference (95% CI)", "paired t", "U test"
+        )?;
+        for row in rows
+        {
+            let difference = row.difference.map_or_else(
+                || "-".to_string(),
+                |interval| {
+                    format!(
+                        "{:.4} [{:.4}, {:.4}]",
+                        interval.estimate, interval.lower, interval.upper
+                    )
+                },
+            );
+            write!(
+                f,
+                "\n{:<output_width$}  {:<scenario_width$}  {:>12.4}  {:>12.4}  {:>28}  {:>10}  {:>10}",
+                row.output,
+                row.scenario,
+                row.baseline,
+                row.mean,
+                difference,
+                p_value(row.paired_t),
+                p_value(row.mann_whitney)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The comparison of a scenario to the baseline on an output, as listed by
+/// [`ScenarioComparison::table`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComparisonRow
+{
+    pub output: String,
+    pub scenario: String,
+
+    /// The mean of the output in the baseline, i.e. the first scenario.
+    pub baseline: f64,
+
+    /// The mean of the output in this scenario.
+    pub mean: f64,
+
+    /// The 95% confidence interval of the mean difference from the baseline.
+    pub difference: Option<ConfidenceInterval>,
+
+    pub paired_t: Option<HypothesisTest>,
+    pub mann_whitney: Option<HypothesisTest>,
+}
+
+impl ComparisonRow
+{
+    /// The confidence level of the interval of the difference.
+    pub const LEVEL: f64 = 0.95;
+}
+
+/// The outcome of a two-sided hypothesis test of the difference between two samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HypothesisTest
+{
+    /// The test statistic, e.g. `t` for a t-test.
+    pub statistic: f64,
+
+    /// The probability of a difference at least as large as the observed one, if there was
+    /// none at all.
+    pub p_value: f64,
+}
+
+impl HypothesisTest
+{
+    /// Checks whether the difference is significant at the given significance level `alpha`,
+    /// e.g. `0.05`.
+    #[must_use]
+    pub fn is_significant(&self, alpha: f64) -> bool
+    {
+        self.p_value < alpha
+    }
+}
+
+/// Tests whether the mean of the differences between paired values of `a` and `b` is zero,
+/// with a two-sided paired t-test whose statistic is `t` for the differences `b - a`.
+///
+/// Returns `None` if there are fewer than two pairs.
+///
+/// # Panics
+///
+/// If `a` and `b` have different lengths, or any of the values is not finite.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn paired_t_test(a: &[f64], b: &[f64]) -> Option<HypothesisTest>
+{
+    assert_eq!(a.len(), b.len(), "a paired test needs paired values");
+    let differences = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| b - a)
+        .collect::<EnsembleResult>();
+    let mean = differences.mean()?;
+    let standard_error = differences.standard_error()?;
+
+    if standard_error == 0.0
+    {
+        // all pairs differ by the same amount
+        let (statistic, p_value) = if mean == 0.0
+        {
+            (0.0, 1.0)
+        }
+        else
+        {
+            (f64::INFINITY.copysign(mean), 0.0)
+        };
+        return Some(HypothesisTest { statistic, p_value });
+    }
+
+    let statistic = mean / standard_error;
+    let degrees_of_freedom = (differences.len() - 1) as f64;
+    Some(HypothesisTest {
+        statistic,
+        p_value: 2.0 * student_t_cdf(-statistic.abs(), degrees_of_freedom),
+    })
+}
+
+/// Tests whether the values of `a` tend to be larger or smaller than those of `b`, with a
+/// two-sided Mann-Whitney U test whose statistic is `U` for `a`.
+///
+/// Unlike the [`paired_t_test`], this makes no assumption about the distribution of the values,
+/// nor pairs them, so it is suited to skewed outputs and independent samples.
+/// The p-value is approximated with the normal distribution, with a correction for ties and
+/// for continuity, which is accurate for about ten or more values in each sample.
+///
+/// Returns `None` if either sample is empty.
+///
+/// # Panics
+///
+/// If any of the values is not finite.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<HypothesisTest>
+{
+    if a.is_empty() || b.is_empty()
+    {
+        return None;
+    }
+
+    let mut values = a
+        .iter()
+        .map(|value| (*value, true))
+        .chain(b.iter().map(|value| (*value, false)))
+        .collect::<Vec<_>>();
+    for (value, _) in &values
+    {
+        assert!(value.is_finite(), "invalid sample value: {value}");
+    }
+    values.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // the sum of the ranks of `a`, where tied values share their average rank
+    let mut rank_sum = 0.0;
+    let mut ties = 0.0;
+    let mut start = 0;
+    while start < values.len()
+    {
+        let end = start
+            + values[start..]
+                .iter()
+                .take_while(|(value, _)| value.total_cmp(&values[start].0).is_eq())
+                .count();
+        let rank = (start + end + 1) as f64 / 2.0;
+        let count = (end - start) as f64;
+        rank_sum += rank * values[start..end].iter().filter(|(_, in_a)| *in_a).count() as f64;
+        ties += count.powi(3) - count;
+        start = end;
+    }
+
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let n = n_a + n_b;
+    let statistic = n_a.mul_add(-(n_a + 1.0) / 2.0, rank_sum);
+    let variance = n_a * n_b / 12.0 * (n + 1.0 - ties / (n * (n - 1.0)));
+    let deviation = (statistic - n_a * n_b / 2.0).abs();
+
+    let p_value = if variance > 0.0 && deviation > 0.5
+    {
+        2.0 * normal_cdf(-(deviation - 0.5) / variance.sqrt())
+    }
+    else
+    {
+        1.0
+    };
+    Some(HypothesisTest { statistic, p_value })
+}
@@ -0,0 +1,223 @@
+use std::{
+    ops::ControlFlow,
+    sync::{
+        Mutex, PoisonError,
+        mpsc::{self, Receiver, Sender},
+    },
+    time::Duration,
+};
+
+use bevy::{platform::time::Instant, prelude::*};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+use egui_plot::{Line, Plot};
+
+use crate::{
+    SampleAggregate,
+    plugins::{StepEndHooks, TimeSeriesData},
+    window::WindowPanels,
+};
+
+/// The minimum time between two frames sent to the dashboard, so that fast simulations
+/// do not spend their time copying data that will never be drawn.
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The height of each plot on the dashboard, in logical pixels.
+const PLOT_HEIGHT: f32 = 160.0;
+
+/// Reads the points of a plot on the dashboard from the simulation's world.
+type PlotSource = fn(&World) -> Vec<[f64; 2]>;
+
+/// Resource holding the plots shown on the dashboard, in addition to the built-in ones.
+#[derive(Resource, Default)]
+pub struct DashboardPlots(Vec<(&'static str, PlotSource)>);
+
+impl DashboardPlots
+{
+    pub fn push(&mut self, name: &'static str, source: PlotSource)
+    {
+        self.0.push((name, source));
+    }
+}
+
+/// Reads the points of the aggregate time series of `O` over components `C` from the world.
+///
+/// The series is expected to have been recorded, otherwise no points are returned.
+pub fn aggregate_time_series_points<C, O>(world: &World) -> Vec<[f64; 2]>
+where
+    C: SampleAggregate<O>,
+    O: Copy + Into<f64> + Send + Sync + 'static,
+{
+    world
+        .get_resource::<TimeSeriesData<C, (), O>>()
+        .map(|data| {
+            let series = data.collect();
+            #[allow(clippy::cast_precision_loss)]
+            series
+                .time_slice()
+                .iter()
+                .zip(series.values_slice())
+                .map(|(&step, &value)| [step as f64, value.into()])
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A snapshot of the simulation, sent to the dashboard at the end of a step.
+struct DashboardFrame
+{
+    step: usize,
+    entities: usize,
+    steps_per_second: f64,
+    plots: Vec<(&'static str, Vec<[f64; 2]>)>,
+}
+
+/// Adds the dashboard to the window of the simulation, and sets up the simulation to send
+/// snapshots to it at the end of its steps.
+pub fn add(app: &mut App)
+{
+    let (sender, receiver) = mpsc::channel();
+    let receiver = Mutex::new(receiver);
+
+    app.init_resource::<DashboardPlots>();
+    app.world_mut()
+        .get_resource_or_init::<StepEndHooks>()
+        .push(frame_sender(sender));
+    app.world_mut()
+        .get_resource_or_init::<WindowPanels>()
+        .push(move |app| {
+            app.insert_resource(DashboardState {
+                receiver,
+                latest: None,
+                entities: Vec::new(),
+                steps_per_second: Vec::new(),
+            })
+            .add_systems(EguiPrimaryContextPass, draw_dashboard);
+        });
+}
+
+/// Creates a step-end hook that periodically sends frames through the given `sender`.
+///
+/// The hook never stops the simulation, even if the dashboard window is closed.
+fn frame_sender(
+    sender: Sender<DashboardFrame>,
+) -> impl FnMut(&World, usize) -> ControlFlow<()> + Send + Sync
+{
+    let sender = Mutex::new(sender);
+    let mut last_frame: Option<(Instant, usize)> = None;
+
+    move |world, step| {
+        let now = Instant::now();
+        let steps_per_second = match last_frame
+        {
+            Some((time, _)) if now.duration_since(time) < FRAME_INTERVAL =>
+            {
+                return ControlFlow::Continue(());
+            }
+            #[allow(clippy::cast_precision_loss)]
+            Some((time, last_step)) =>
+            {
+                step.saturating_sub(last_step) as f64 / now.duration_since(time).as_secs_f64()
+            }
+            None => 0.0,
+        };
+        last_frame = Some((now, step));
+
+        let plots = world
+            .get_resource::<DashboardPlots>()
+            .map(|plots| {
+                plots
+                    .0
+                    .iter()
+                    .map(|&(name, source)| (name, source(world)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let frame = DashboardFrame {
+            step,
+            entities: world.entities().len() as usize,
+            steps_per_second,
+            plots,
+        };
+
+        // the window may have been closed, in which case the frame is simply dropped
+        let _ = sender
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .send(frame);
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// The state of the dashboard window, accumulated from the frames it received.
+#[derive(Resource)]
+struct DashboardState
+{
+    receiver: Mutex<Receiver<DashboardFrame>>,
+    latest: Option<DashboardFrame>,
+    entities: Vec<[f64; 2]>,
+    steps_per_second: Vec<[f64; 2]>,
+}
+
+impl DashboardState
+{
+    /// Takes in all frames received since the last call.
+    #[allow(clippy::cast_precision_loss)]
+    fn receive(&mut self)
+    {
+        let frames = self
+            .receiver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_iter()
+            .collect::<Vec<_>>();
+
+        for frame in frames
+        {
+            let step = frame.step as f64;
+            self.entities.push([step, frame.entities as f64]);
+            self.steps_per_second.push([step, frame.steps_per_second]);
+            self.latest = Some(frame);
+        }
+    }
+}
+
+/// Draws the plots of the dashboard from the frames received so far.
+fn draw_dashboard(mut contexts: EguiContexts, mut state: ResMut<DashboardState>) -> Result
+{
+    state.receive();
+
+    egui::Window::new("dashboard").show(contexts.ctx_mut()?, |ui| {
+        let Some(latest) = &state.latest
+        else
+        {
+            ui.label("waiting for the simulation to start...");
+            return;
+        };
+
+        ui.heading(format!("step {}", latest.step));
+        ui.label(format!(
+            "{} entities, {:.1} steps/s",
+            latest.entities, latest.steps_per_second
+        ));
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let builtin = [
+                ("entities", &state.entities),
+                ("steps/s", &state.steps_per_second),
+            ];
+            let plots = latest.plots.iter().map(|(name, points)| (*name, points));
+
+            for (name, points) in builtin.into_iter().chain(plots)
+            {
+                ui.label(name);
+                Plot::new(name).height(PLOT_HEIGHT).show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(name, points.clone()));
+                });
+            }
+        });
+    });
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use polars::prelude::{Column, DataFrame};
+
+use crate::{
+    Identifier, Sample, Simulation, SimulationError, TimeSeries, error::ExportError,
+    traits::PolarsValue,
+};
+
+macro_rules! impl_polars_value {
+    ($t:ty) => {
+        impl PolarsValue for $t
+        {
+            fn into_column(name: &str, values: Vec<Self>) -> Column
+            {
+                Column::new(name.into(), values)
+            }
+        }
+    };
+}
+
+impl_polars_value!(bool);
+impl_polars_value!(i8);
+impl_polars_value!(i16);
+impl_polars_value!(i32);
+impl_polars_value!(i64);
+impl_polars_value!(u8);
+impl_polars_value!(u16);
+impl_polars_value!(u32);
+impl_polars_value!(u64);
+impl_polars_value!(f32);
+impl_polars_value!(f64);
+impl_polars_value!(String);
+
+impl PolarsValue for usize
+{
+    fn into_column(name: &str, values: Vec<Self>) -> Column
+    {
+        u64::into_column(name, values.into_iter().map(|v| v as u64).collect())
+    }
+}
+
+impl<T: PolarsValue + Clone> TimeSeries<'_, T>
+{
+    /// Converts the time series into a Polars data frame.
+    ///
+    /// The data frame has two columns, `step` with the simulation step of each sample,
+    /// and `value` with the sampled values.
+    ///
+    /// # Errors
+    ///
+    /// - [`ExportError::Polars`]
+    pub fn to_dataframe(&self) -> Result<DataFrame, ExportError>
+    {
+        DataFrame::new(vec![
+            usize::into_column("step", self.time.to_vec()),
+            T::into_column("value", self.values.to_vec()),
+        ])
+        .map_err(|_| ExportError::Polars)
+    }
+}
+
+impl Simulation
+{
+    /// Samples values of type `O` from all components `C` into a Polars data frame,
+    /// with one row per entity.
+    ///
+    /// The data frame has two columns, `id` with the [`Identifier`] of each entity, and `value` with
+    /// the values sampled from its component. Entities without an identifier `Id` are skipped.
+    ///
+    /// See [`Self::export_columns_with_ids`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`crate::SamplingError::ComponentDoesNotExist`]
+    /// - [`ExportError::Polars`]
+    pub fn sample_table<C, Id, O>(&self) -> Result<DataFrame, SimulationError>
+    where
+        C: Sample<O> + Component,
+        Id: Identifier + Clone + PolarsValue,
+        O: PolarsValue,
+    {
+        let (ids, values) = self.export_columns_with_ids::<C, Id, O>()?;
+
+        Ok(DataFrame::new(vec![
+            Id::into_column("id", ids),
+            O::into_column("value", values),
+        ])
+        .map_err(|_| ExportError::Polars)?)
+    }
+}
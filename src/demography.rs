@@ -0,0 +1,385 @@
+//! A prefab demographic model of births, aging and deaths.
+//!
+//! It can be combined with other models, such as those of the [`crate::epidemic`] or
+//! [`crate::evolution`] modules, to give them a changing population.
+//! Every individual has an [`AgeStructure`] component holding its age, counted in steps, so that a
+//! step stands for the unit of time the hazards are given in, e.g. a year.
+//! The model is added to a simulation with [`crate::SimulationBuilder::add_module`], through a
+//! [`DemographyModule`] configured with age-specific [`AgeSchedule`]s of the probabilities of
+//! dying and giving birth on every step, and optionally a carrying capacity which the births
+//! slow down towards.
+//!
+//! Every birth spawns a newborn with an age of `0` and is reported with a [`Birth`] event, so that
+//! user-defined systems can give the newborn any other components, such as a compartment in an
+//! epidemic.
+//! Every death is reported with a [`Death`] event, and the individual is despawned.
+//! The size of the population, along with its age pyramid, is recorded on every step in the
+//! [`DemographyOutputs`] resource.
+//!
+//! Example of a population growing towards its carrying capacity:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::demography::{AgeSchedule, DemographyModule, DemographyOutputs};
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .set_seed(42)
+//!     .add_module(
+//!         DemographyModule::new()
+//!             .with_mortality(AgeSchedule::new([(0, 0.01), (60, 0.05), (80, 0.2)]))
+//!             .with_fertility(AgeSchedule::new([(0, 0.0), (18, 0.1), (45, 0.0)]))
+//!             .with_carrying_capacity(2000)
+//!             .with_population(500, 80),
+//!     )
+//!     .build();
+//!
+//! simulation.run(200);
+//! let outputs = simulation.get_resource::<DemographyOutputs>().unwrap();
+//! let latest = outputs.latest().unwrap();
+//! assert!(latest.population > 500 && latest.population < 2000);
+//! ```
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{MetricsHistory, Module, SimRng, SimulationBuilder, StepNumber};
+
+/// Component holding the age of an individual in a [`DemographyModule`], counted in steps.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct AgeStructure
+{
+    age: usize,
+}
+
+impl AgeStructure
+{
+    /// An individual of the given age.
+    #[must_use]
+    pub const fn new(age: usize) -> Self
+    {
+        Self { age }
+    }
+
+    /// The age of the individual, which grows by `1` at the end of every step.
+    #[must_use]
+    pub const fn age(&self) -> usize
+    {
+        self.age
+    }
+}
+
+/// The probability of an event on every step, as a piecewise constant function of age.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgeSchedule
+{
+    /// The probability from every age on, in increasing order of age.
+    bands: Vec<(usize, f64)>,
+}
+
+impl AgeSchedule
+{
+    /// A schedule in which the probability is `probability` from age `from` on, until the age
+    /// of the next band.
+    ///
+    /// The probability is `0` below the age of the first band.
+    ///
+    /// # Panics
+    ///
+    /// If the ages are not strictly increasing, or any probability is not within `[0, 1]`.
+    #[must_use]
+    pub fn new(bands: impl IntoIterator<Item = (usize, f64)>) -> Self
+    {
+        let bands = bands.into_iter().collect::<Vec<_>>();
+        assert!(
+            bands.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "the ages of an age schedule must be strictly increasing"
+        );
+        for (age, probability) in &bands
+        {
+            assert!(
+                (0.0..=1.0).contains(probability),
+                "invalid probability at age {age}: {probability}"
+            );
+        }
+        Self { bands }
+    }
+
+    /// A schedule with the same probability at every age.
+    ///
+    /// # Panics
+    ///
+    /// If `probability` is not within `[0, 1]`.
+    #[must_use]
+    pub fn constant(probability: f64) -> Self
+    {
+        Self::new([(0, probability)])
+    }
+
+    /// The probability at the given age.
+    #[must_use]
+    pub fn probability(&self, age: usize) -> f64
+    {
+        let band = self.bands.partition_point(|(from, _)| *from <= age);
+        band.checked_sub(1).map_or(0.0, |band| self.bands[band].1)
+    }
+}
+
+/// A prefab model of the births and deaths of individuals with an [`AgeStructure`].
+///
+/// On every step, which runs alongside the user-defined systems:
+/// - Every individual dies with the probability of its mortality schedule at its age, or surely
+///   if it has reached the maximum age.
+/// - Every individual that survives gives birth with the probability of its fertility schedule
+///   at its age, scaled by `1 - N / K` for a population of `N` at the start of the step and a
+///   carrying capacity of `K`.
+/// - Every survivor grows one step older, while newborns start at age `0`.
+///
+/// The randomness is drawn from the simulation's [`SimRng`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemographyModule
+{
+    mortality: AgeSchedule,
+    fertility: AgeSchedule,
+    max_age: Option<usize>,
+    carrying_capacity: Option<usize>,
+    /// The width of every age class of the pyramid, and the number of classes.
+    pyramid: (usize, usize),
+    /// The number of individuals to spawn, and the maximum of their uniformly drawn ages.
+    population: Option<(usize, usize)>,
+}
+
+impl Default for DemographyModule
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl DemographyModule
+{
+    /// A model in which no one dies or is born, until the schedules are set with
+    /// [`Self::with_mortality`] and [`Self::with_fertility`].
+    ///
+    /// The age pyramid has ten classes of ten steps each by default.
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self {
+            mortality: AgeSchedule::constant(0.0),
+            fertility: AgeSchedule::constant(0.0),
+            max_age: None,
+            carrying_capacity: None,
+            pyramid: (10, 10),
+            population: None,
+        }
+    }
+
+    /// Sets the probability of an individual dying on every step, by age.
+    #[must_use]
+    pub fn with_mortality(mut self, mortality: AgeSchedule) -> Self
+    {
+        self.mortality = mortality;
+        self
+    }
+
+    /// Sets the probability of an individual giving birth on every step, by age.
+    #[must_use]
+    pub fn with_fertility(mut self, fertility: AgeSchedule) -> Self
+    {
+        self.fertility = fertility;
+        self
+    }
+
+    /// Sets the age at which individuals surely die.
+    #[must_use]
+    pub const fn with_max_age(mut self, max_age: usize) -> Self
+    {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Sets the size of the population at which births stop altogether.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is `0`.
+    #[must_use]
+    pub fn with_carrying_capacity(mut self, capacity: usize) -> Self
+    {
+        assert!(capacity > 0, "the carrying capacity must be positive");
+        self.carrying_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets the age classes of the pyramid recorded in the [`DemographyOutputs`], as `classes`
+    /// classes of `width` steps each, where the last class also holds all older individuals.
+    ///
+    /// # Panics
+    ///
+    /// If `width` or `classes` is `0`.
+    #[must_use]
+    pub fn with_age_classes(mut self, width: usize, classes: usize) -> Self
+    {
+        assert!(
+            width > 0 && classes > 0,
+            "the age pyramid must have at least one class of positive width"
+        );
+        self.pyramid = (width, classes);
+        self
+    }
+
+    /// Spawns `size` individuals at the start of the simulation, with ages drawn uniformly
+    /// from `0` to `max_age`.
+    #[must_use]
+    pub const fn with_population(mut self, size: usize, max_age: usize) -> Self
+    {
+        self.population = Some((size, max_age));
+        self
+    }
+
+    /// The age class of the pyramid that the given age falls into.
+    fn age_class(&self, age: usize) -> usize
+    {
+        let (width, classes) = self.pyramid;
+        (age / width).min(classes - 1)
+    }
+
+    /// The factor by which births are scaled in a population of the given size.
+    #[allow(clippy::cast_precision_loss)]
+    fn crowding(&self, population: usize) -> f64
+    {
+        self.carrying_capacity.map_or(1.0, |capacity| {
+            (1.0 - population as f64 / capacity as f64).max(0.0)
+        })
+    }
+}
+
+impl Module for DemographyModule
+{
+    fn build(self, mut builder: SimulationBuilder) -> SimulationBuilder
+    {
+        if let Some((size, max_age)) = self.population
+        {
+            builder = builder.add_seeded_entity_spawner(move |spawner, rng| {
+                for _ in 0..size
+                {
+                    spawner.spawn(AgeStructure::new(rng.random_range(0..=max_age)));
+                }
+            });
+        }
+
+        builder
+            .register_event::<Birth>()
+            .register_event::<Death>()
+            .add_resource(DemographyParameters(self))
+            .add_resource(DemographyOutputs::default())
+            .add_systems(demography_system)
+    }
+}
+
+/// Event emitted when an individual gives birth, right after the newborn is spawned.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Birth
+{
+    pub parent: Entity,
+    pub newborn: Entity,
+}
+
+/// Event emitted when an individual dies, right before it is despawned.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Death
+{
+    pub individual: Entity,
+    pub age: usize,
+}
+
+/// The state of the population at the end of a step.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DemographyMetrics
+{
+    /// The number of the step, as read from [`StepNumber`].
+    pub step: usize,
+
+    /// The number of individuals alive, including newborns.
+    pub population: usize,
+
+    pub births: usize,
+    pub deaths: usize,
+
+    /// The mean age of the individuals alive, or `None` if there are none.
+    pub mean_age: Option<f64>,
+
+    /// The number of individuals in every age class, from the youngest to the oldest.
+    pub pyramid: Vec<usize>,
+}
+
+/// Resource holding the [`DemographyMetrics`] of every step of a [`DemographyModule`].
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<DemographyOutputs>`] arguments.
+pub type DemographyOutputs = MetricsHistory<DemographyMetrics>;
+
+/// Resource holding the parameters of the [`DemographyModule`] added to the simulation.
+#[derive(Resource)]
+struct DemographyParameters(DemographyModule);
+
+/// System that applies the deaths, births and aging of a step, and records the metrics of it.
+#[allow(clippy::cast_precision_loss)]
+fn demography_system(
+    mut commands: Commands,
+    parameters: Res<DemographyParameters>,
+    mut outputs: ResMut<DemographyOutputs>,
+    mut query: Query<(Entity, &mut AgeStructure)>,
+    (mut births, mut deaths): (EventWriter<Birth>, EventWriter<Death>),
+    mut rng: ResMut<SimRng>,
+    step_number: Res<StepNumber>,
+)
+{
+    let parameters = &parameters.0;
+    let crowding = parameters.crowding(query.iter().len());
+
+    let mut metrics = DemographyMetrics {
+        step: **step_number,
+        pyramid: vec![0; parameters.pyramid.1],
+        ..default()
+    };
+    let mut total_age = 0;
+    for (entity, mut individual) in &mut query
+    {
+        let age = individual.age;
+        if parameters.max_age.is_some_and(|max_age| age >= max_age)
+            || rng.random_bool(parameters.mortality.probability(age))
+        {
+            deaths.write(Death {
+                individual: entity,
+                age,
+            });
+            commands.entity(entity).despawn();
+            metrics.deaths += 1;
+            continue;
+        }
+
+        let fertility = parameters.fertility.probability(age) * crowding;
+        if fertility > 0.0 && rng.random_bool(fertility)
+        {
+            let newborn = commands.spawn(AgeStructure::default()).id();
+            births.write(Birth {
+                parent: entity,
+                newborn,
+            });
+            metrics.births += 1;
+            metrics.pyramid[0] += 1;
+        }
+
+        individual.age += 1;
+        total_age += individual.age;
+        metrics.pyramid[parameters.age_class(individual.age)] += 1;
+    }
+
+    metrics.population = metrics.pyramid.iter().sum();
+    if metrics.population > 0
+    {
+        metrics.mean_age = Some(total_age as f64 / metrics.population as f64);
+    }
+    outputs.push(metrics.step, metrics);
+}
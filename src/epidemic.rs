@@ -0,0 +1,459 @@
+//! A prefab compartmental epidemic model, in which every individual is in one of the
+//! [`Compartment`]s of an SIR or SEIR model, and moves between them at random on every step.
+//!
+//! The model is added to a simulation with [`crate::SimulationBuilder::add_module`], and records
+//! the size of every compartment along with the incidence, prevalence and effective reproduction
+//! number on every step, in the [`EpidemicOutputs`] resource.
+//!
+//! Infections spread through one of the [`Transmission`] modes: between any two individuals in a
+//! well-mixed population, between neighbors on a spatial grid, along the edges of a network, or
+//! through several [`crate::ContactLayers`] at once.
+//!
+//! Example of an SEIR epidemic on a small-world contact network:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::epidemic::{Compartment, EpidemicModule, EpidemicOutputs, Transmission};
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_module(EpidemicModule::seir(0.1, 0.2, 0.1).with_transmission(Transmission::Network))
+//!     .add_seeded_entity_spawner(|spawner, rng| {
+//!         let people = (0..1000)
+//!             .map(|i| spawner.spawn(if i < 5 { Compartment::Infectious } else { Compartment::Susceptible }))
+//!             .collect::<Vec<_>>();
+//!         spawner.add_edges::<Compartment>(&people, topology::watts_strogatz(people.len(), 8, 0.1, rng));
+//!     })
+//!     .build();
+//!
+//! simulation.run(200);
+//! let outputs = simulation.get_resource::<EpidemicOutputs>().unwrap();
+//! let peak = outputs.history().iter().map(|counts| counts.infectious).max();
+//! ```
+
+use bevy::{ecs::entity::EntityHashSet, prelude::*};
+use rand::Rng;
+
+use crate::{
+    MetricsHistory, Module, SimRng, SimulationBuilder,
+    plugins::{ContactSources, GridBounds2D, StepNumber},
+};
+
+/// Component holding the compartment of an individual in an [`EpidemicModule`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compartment
+{
+    /// Not infected, and may become infected.
+    Susceptible,
+
+    /// Infected, but not yet infectious. Only used in SEIR models.
+    Exposed,
+
+    /// Infected, and may infect susceptible individuals.
+    Infectious,
+
+    /// No longer infectious, and immune until their immunity wanes, if ever.
+    Recovered,
+}
+
+/// How infections spread from infectious to susceptible individuals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transmission
+{
+    /// Every individual is equally likely to come into contact with any other.
+    ///
+    /// Each susceptible individual is infected with probability `1 - exp(-beta * I / N)`,
+    /// where `I` is the number of infectious individuals out of `N`, so `beta` is the rate of
+    /// effective contacts per step.
+    #[default]
+    WellMixed,
+
+    /// Individuals come into contact with those in the same and the adjacent cells of a
+    /// [`crate::prelude::SpatialGrid2D<Compartment>`], which is added with the given bounds.
+    ///
+    /// Each infectious contact infects a susceptible individual with probability `beta`.
+    /// Individuals need a [`crate::GridPosition`] to take part in transmission.
+    Spatial(Option<GridBounds2D>),
+
+    /// Individuals come into contact with their neighbors in a [`crate::Network<Compartment>`],
+    /// which is added to the simulation.
+    ///
+    /// Each infectious neighbor infects a susceptible individual with probability `beta`,
+    /// multiplied by the weight of the edge between them.
+    Network,
+
+    /// Individuals come into contact through every layer of the [`crate::ContactLayers<Compartment>`],
+    /// which must be added with [`crate::SimulationBuilder::add_contact_layers`].
+    ///
+    /// Each infectious contact infects a susceptible individual with probability `beta`,
+    /// multiplied by the modifier of the layer and the weight of the edge between them, if any.
+    Layered,
+}
+
+/// A compartmental epidemic model, added to a simulation with
+/// [`crate::SimulationBuilder::add_module`].
+///
+/// On every step, each individual with a [`Compartment`] moves to the next compartment at random,
+/// according to the state of the population at the start of the step:
+///
+/// - Susceptible individuals are infected through the [`Transmission`] mode, becoming exposed
+///   in SEIR models, or infectious in SIR models.
+/// - Exposed individuals become infectious with probability `sigma`.
+/// - Infectious individuals recover with probability `gamma`.
+/// - Recovered individuals become susceptible again with probability `omega`, which is `0`
+///   unless set with [`Self::with_waning`].
+///
+/// The randomness is drawn from the simulation's [`SimRng`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpidemicModule
+{
+    beta: f64,
+    /// The probability of exposed individuals becoming infectious, or `None` in SIR models.
+    sigma: Option<f64>,
+    gamma: f64,
+    omega: f64,
+    transmission: Transmission,
+    /// The number of individuals to spawn, and how many of them are initially infectious.
+    population: Option<(usize, usize)>,
+}
+
+impl EpidemicModule
+{
+    /// An SIR model, with the given transmission parameter `beta` and recovery probability `gamma`.
+    ///
+    /// # Panics
+    ///
+    /// If `beta` is negative or not finite, or `gamma` is not within `[0, 1]`.
+    #[must_use]
+    pub fn sir(beta: f64, gamma: f64) -> Self
+    {
+        assert!(
+            beta.is_finite() && beta >= 0.0,
+            "invalid transmission parameter: {beta}"
+        );
+        assert_probability(gamma);
+
+        Self {
+            beta,
+            sigma: None,
+            gamma,
+            omega: 0.0,
+            transmission: Transmission::default(),
+            population: None,
+        }
+    }
+
+    /// An SEIR model, with the given transmission parameter `beta`, probability `sigma` of
+    /// exposed individuals becoming infectious, and recovery probability `gamma`.
+    ///
+    /// # Panics
+    ///
+    /// If `beta` is negative or not finite, or `sigma` or `gamma` are not within `[0, 1]`.
+    #[must_use]
+    pub fn seir(beta: f64, sigma: f64, gamma: f64) -> Self
+    {
+        assert_probability(sigma);
+
+        Self {
+            sigma: Some(sigma),
+            ..Self::sir(beta, gamma)
+        }
+    }
+
+    /// Sets the probability `omega` of recovered individuals losing their immunity on every step,
+    /// turning an SIR model into an SIRS one.
+    ///
+    /// # Panics
+    ///
+    /// If `omega` is not within `[0, 1]`.
+    #[must_use]
+    pub fn with_waning(mut self, omega: f64) -> Self
+    {
+        assert_probability(omega);
+        self.omega = omega;
+        self
+    }
+
+    /// Sets how infections spread, which is [`Transmission::WellMixed`] by default.
+    #[must_use]
+    pub const fn with_transmission(mut self, transmission: Transmission) -> Self
+    {
+        self.transmission = transmission;
+        self
+    }
+
+    /// Spawns a population of `size` individuals when the simulation is built, of which
+    /// `infectious` are initially infectious and the rest susceptible.
+    ///
+    /// This is mostly useful for [`Transmission::WellMixed`] models, since the individuals
+    /// are spawned without positions or edges.
+    ///
+    /// # Panics
+    ///
+    /// If `infectious` is greater than `size`.
+    #[must_use]
+    pub fn with_population(mut self, size: usize, infectious: usize) -> Self
+    {
+        assert!(
+            infectious <= size,
+            "more infectious individuals than the population"
+        );
+        self.population = Some((size, infectious));
+        self
+    }
+
+    /// The basic reproduction number of a well-mixed population, `beta / gamma`.
+    #[must_use]
+    pub fn r0(&self) -> f64
+    {
+        self.beta / self.gamma
+    }
+
+    /// The probability of a susceptible individual becoming infected, given their contacts.
+    fn infection_probability(
+        &self,
+        entity: Entity,
+        infectious: &EntityHashSet,
+        well_mixed: f64,
+        sources: &ContactSources<Compartment>,
+        contacts: &mut Vec<(Entity, f64)>,
+    ) -> f64
+    {
+        let grid = sources.grid.as_deref();
+        match self.transmission
+        {
+            Transmission::WellMixed => well_mixed,
+            Transmission::Spatial(_) =>
+            {
+                let Some((grid, position)) =
+                    grid.and_then(|grid| Some((grid, grid.position_of(entity)?)))
+                else
+                {
+                    return 0.0;
+                };
+
+                let contacts = grid
+                    .entities_at(&position)
+                    .chain(grid.neighbors_of(&position))
+                    .filter(|contact| infectious.contains(contact))
+                    .count();
+                1.0 - (1.0 - self.beta.min(1.0)).powi(i32::try_from(contacts).unwrap_or(i32::MAX))
+            }
+            Transmission::Network =>
+            {
+                let escape = sources
+                    .network
+                    .iter()
+                    .flat_map(|network| network.weighted_neighbors_of(entity))
+                    .filter(|(neighbor, _)| infectious.contains(neighbor))
+                    .map(|(_, weight)| 1.0 - (self.beta * weight).clamp(0.0, 1.0))
+                    .product::<f64>();
+                1.0 - escape
+            }
+            Transmission::Layered =>
+            {
+                let Some(layers) = &sources.layers
+                else
+                {
+                    return 0.0;
+                };
+
+                layers.contacts_of(entity, grid, contacts);
+                let escape = contacts
+                    .iter()
+                    .filter(|(contact, _)| infectious.contains(contact))
+                    .map(|(_, weight)| 1.0 - (self.beta * weight).clamp(0.0, 1.0))
+                    .product::<f64>();
+                1.0 - escape
+            }
+        }
+    }
+}
+
+impl Module for EpidemicModule
+{
+    fn build(self, mut builder: SimulationBuilder) -> SimulationBuilder
+    {
+        builder = match self.transmission
+        {
+            Transmission::WellMixed | Transmission::Layered => builder,
+            Transmission::Spatial(bounds) => builder.add_spatial_grid_2d::<Compartment>(bounds),
+            Transmission::Network => builder.add_network::<Compartment>(),
+        };
+
+        if let Some((size, infectious)) = self.population
+        {
+            builder = builder.add_entity_spawner(move |spawner| {
+                for i in 0..size
+                {
+                    spawner.spawn(
+                        if i < infectious
+                        {
+                            Compartment::Infectious
+                        }
+                        else
+                        {
+                            Compartment::Susceptible
+                        },
+                    );
+                }
+            });
+        }
+
+        builder
+            .add_resource(EpidemicParameters(self))
+            .add_resource(EpidemicOutputs::default())
+            .add_systems(epidemic_system)
+    }
+}
+
+/// The state of an epidemic at the end of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EpidemicCounts
+{
+    /// The number of the step, as read from [`StepNumber`].
+    pub step: usize,
+
+    pub susceptible: usize,
+    pub exposed: usize,
+    pub infectious: usize,
+    pub recovered: usize,
+
+    /// The number of individuals infected during the step.
+    pub incidence: usize,
+
+    /// The fraction of the population that is infectious.
+    pub prevalence: f64,
+
+    /// An estimate of the effective reproduction number, as the number of infections during the
+    /// step per infectious individual, times their average infectious period of `1 / gamma` steps.
+    ///
+    /// `None` if there were no infectious individuals at the start of the step,
+    /// or they never recover.
+    pub r_eff: Option<f64>,
+}
+
+/// Resource holding the [`EpidemicCounts`] of every step of an [`EpidemicModule`].
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<EpidemicOutputs>`] arguments.
+pub type EpidemicOutputs = MetricsHistory<EpidemicCounts>;
+
+impl EpidemicOutputs
+{
+    /// The total number of individuals infected since the start of the simulation,
+    /// not counting those initially infected.
+    #[must_use]
+    pub fn cumulative_incidence(&self) -> usize
+    {
+        self.history().iter().map(|counts| counts.incidence).sum()
+    }
+}
+
+/// Resource holding the parameters of the [`EpidemicModule`] added to the simulation.
+#[derive(Resource)]
+struct EpidemicParameters(EpidemicModule);
+
+/// System that moves every individual between compartments, and records the counts of the step.
+#[allow(clippy::cast_precision_loss)]
+fn epidemic_system(
+    parameters: Res<EpidemicParameters>,
+    mut outputs: ResMut<EpidemicOutputs>,
+    mut query: Query<(Entity, &mut Compartment)>,
+    mut rng: ResMut<SimRng>,
+    step_number: Res<StepNumber>,
+    sources: ContactSources<Compartment>,
+)
+{
+    let parameters = &parameters.0;
+
+    // transitions depend on the state at the start of the step, so that the order in which
+    // individuals are visited does not matter
+    let infectious = query
+        .iter()
+        .filter(|(_, compartment)| **compartment == Compartment::Infectious)
+        .map(|(entity, _)| entity)
+        .collect::<EntityHashSet>();
+    let population = query.iter().len();
+    let well_mixed = if population == 0
+    {
+        0.0
+    }
+    else
+    {
+        1.0 - (-parameters.beta * infectious.len() as f64 / population as f64).exp()
+    };
+
+    let mut contacts = Vec::new();
+    let mut counts = EpidemicCounts {
+        step: **step_number,
+        ..default()
+    };
+    for (entity, mut compartment) in &mut query
+    {
+        let next = match *compartment
+        {
+            Compartment::Susceptible =>
+            {
+                let probability = parameters.infection_probability(
+                    entity,
+                    &infectious,
+                    well_mixed,
+                    &sources,
+                    &mut contacts,
+                );
+                if probability > 0.0 && rng.random_bool(probability.min(1.0))
+                {
+                    counts.incidence += 1;
+                    if parameters.sigma.is_some()
+                    {
+                        Compartment::Exposed
+                    }
+                    else
+                    {
+                        Compartment::Infectious
+                    }
+                }
+                else
+                {
+                    Compartment::Susceptible
+                }
+            }
+            Compartment::Exposed if rng.random_bool(parameters.sigma.unwrap_or(1.0)) =>
+            {
+                Compartment::Infectious
+            }
+            Compartment::Infectious if rng.random_bool(parameters.gamma) => Compartment::Recovered,
+            Compartment::Recovered if rng.random_bool(parameters.omega) => Compartment::Susceptible,
+            current => current,
+        };
+
+        if next != *compartment
+        {
+            *compartment = next;
+        }
+        match next
+        {
+            Compartment::Susceptible => counts.susceptible += 1,
+            Compartment::Exposed => counts.exposed += 1,
+            Compartment::Infectious => counts.infectious += 1,
+            Compartment::Recovered => counts.recovered += 1,
+        }
+    }
+
+    if population > 0
+    {
+        counts.prevalence = counts.infectious as f64 / population as f64;
+    }
+    if !infectious.is_empty() && parameters.gamma > 0.0
+    {
+        counts.r_eff = Some(counts.incidence as f64 / (infectious.len() as f64 * parameters.gamma));
+    }
+    outputs.push(counts.step, counts);
+}
+
+fn assert_probability(probability: f64)
+{
+    assert!(
+        (0.0..=1.0).contains(&probability),
+        "invalid transition probability: {probability}"
+    );
+}
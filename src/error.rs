@@ -1,60 +1,777 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use bevy::prelude::Entity;
+
 /// Grouping of all other error types in the crate.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum SimulationError
 {
     Sampling(SamplingError),
     Builder(BuilderError),
+    Export(ExportError),
+    Dataset(DatasetError),
+    SpatialGrid(SpatialGridError),
+    NumericGuard(NumericGuardError),
+    #[cfg(feature = "sqlite")]
+    Store(StoreError),
+    #[cfg(feature = "gpu")]
+    Gpu(GpuError),
 }
 
 /// An error that occured when attempting to sample the value of a component.
+///
+/// Each variant holds the [`std::any::type_name`] of the types involved,
+/// to tell apart which of the many sampled types caused the error.
+/// Variants of the same nature are grouped together by [`Self::kind`].
+///
+/// With the `serde` feature enabled the error can be serialized, but since the type names
+/// are borrowed it can not be deserialized; [`SamplingErrorKind`] can be used for that instead.
+///
+/// Some variants only exist with certain cargo features enabled, so the enum is
+/// non-exhaustive.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
 pub enum SamplingError
 {
     /// The component type being sampled was not possible to query.
     /// This indicates that no entity with this component was ever spawned.
-    ComponentDoesNotExist,
+    ComponentDoesNotExist
+    {
+        component: &'static str
+    },
 
     /// Expected only a single entity with the given component type in the simulation
     /// from the call to [`crate::Simulation::sample_single`].
     /// This error indicates that no entities were found.
-    SingleNoEntities,
+    SingleNoEntities
+    {
+        component: &'static str
+    },
 
     /// Expected only a single entity with the given component type in the simulation
     /// from the call to [`crate::Simulation::sample_single`].
     /// This error indicates that more than one entity was found.
-    SingleMultipleEntities,
+    SingleMultipleEntities
+    {
+        component: &'static str
+    },
 
     /// Expected one or more entities with the given component type in the simulation
     /// for aggregate sampling.
-    /// From the call to [`crate::Simulation::sample_aggregate`] or [`crate::Simulation::sample_aggregate_filtered`].
-    AggregateNoEntities,
+    /// From the call to [`crate::Simulation::sample_aggregate`], [`crate::Simulation::sample_aggregate_filtered`]
+    /// or [`crate::Simulation::sample_density`].
+    AggregateNoEntities
+    {
+        component: &'static str
+    },
 
     /// The requested time series has not been recorded in the simulation.
-    /// This indicates that [`crate::Simulation::get_time_series`] was called without
-    /// first having called [`crate::SimulationBuilder::record_time_series`] or
-    /// [`crate::SimulationBuilder::record_time_series_filtered`].
-    TimeSeriesNotRecorded,
+    /// This indicates that [`crate::Simulation::get_time_series`] or
+    /// [`crate::Simulation::get_aggregate_time_series`] and its variants were called without
+    /// first having called the corresponding method of [`crate::SimulationBuilder`], such as
+    /// [`crate::SimulationBuilder::record_time_series`].
+    TimeSeriesNotRecorded
+    {
+        component: &'static str,
+        output: &'static str,
+    },
+
+    /// The requested event log has not been recorded in the simulation.
+    /// This indicates that [`crate::Simulation::event_log`] was called without
+    /// first having called [`crate::SimulationBuilder::record_events`].
+    EventsNotRecorded
+    {
+        event: &'static str
+    },
+
+    /// The requested incremental aggregate has not been set up in the simulation.
+    /// This indicates that [`crate::Simulation::incremental_aggregate`] was called without
+    /// first having called [`crate::SimulationBuilder::track_incremental_aggregate`].
+    AggregateNotTracked
+    {
+        component: &'static str,
+        output: &'static str,
+    },
+
+    /// The requested population ledger has not been set up in the simulation.
+    /// This indicates that [`crate::Simulation::ledger`] was called without
+    /// first having called [`crate::SimulationBuilder::track_population`].
+    PopulationNotTracked
+    {
+        component: &'static str
+    },
+
+    /// The requested grid frames have not been recorded in the simulation.
+    /// This indicates that [`crate::Simulation::write_frames_png`],
+    /// [`crate::Simulation::write_frames_gif`] or [`crate::Simulation::write_frames_netcdf`]
+    /// was called without first having called [`crate::SimulationBuilder::record_grid_frames`].
+    #[cfg(any(feature = "image", feature = "netcdf"))]
+    FramesNotRecorded
+    {
+        component: &'static str
+    },
 
     /// No entity was found in the simulation bearing the given value of the [`crate::Identifier`] component.
-    EntityIdentifierNotFound,
+    EntityIdentifierNotFound
+    {
+        identifier: &'static str
+    },
 
     /// More than one entity was found in the simulation with the same value of the [`crate::Identifier`] component.
-    EntityIdentifierNotUnique,
+    EntityIdentifierNotUnique
+    {
+        identifier: &'static str
+    },
+
+    /// The entity referred to by an [`crate::EntityHandle`] was not found in the simulation,
+    /// or does not have the sampled component.
+    /// This indicates that the entity has since been despawned, or the component removed from it.
+    EntityHandleNotFound
+    {
+        component: &'static str
+    },
+
+    /// The requested resource does not exist in the simulation.
+    /// This indicates that it was never added with [`crate::SimulationBuilder::add_resource`]
+    /// or [`crate::Simulation::insert_resource`].
+    ResourceDoesNotExist
+    {
+        resource: &'static str
+    },
+}
+
+/// The broad category of a [`SamplingError`], as returned by [`SamplingError::kind`].
+///
+/// This allows for handling errors of the same nature alike, regardless of the
+/// [`crate::Simulation`] method that returned them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SamplingErrorKind
+{
+    /// A type was never added to the simulation.
+    /// See [`SamplingError::ComponentDoesNotExist`] and [`SamplingError::ResourceDoesNotExist`].
+    TypeDoesNotExist,
+
+    /// The requested data was never set up to be recorded while building the simulation.
+    /// See [`SamplingError::TimeSeriesNotRecorded`], [`SamplingError::EventsNotRecorded`],
+    /// [`SamplingError::AggregateNotTracked`] and [`SamplingError::PopulationNotTracked`].
+    NotRecorded,
+
+    /// No entities matched, where at least one was expected.
+    /// See [`SamplingError::SingleNoEntities`] and [`SamplingError::AggregateNoEntities`].
+    NoEntities,
+
+    /// More than one entity matched, where exactly one was expected.
+    /// See [`SamplingError::SingleMultipleEntities`] and [`SamplingError::EntityIdentifierNotUnique`].
+    MultipleEntities,
+
+    /// The specific entity requested was not found.
+    /// See [`SamplingError::EntityIdentifierNotFound`] and [`SamplingError::EntityHandleNotFound`].
+    EntityNotFound,
+}
+
+impl SamplingError
+{
+    /// The broad category of this error.
+    #[must_use]
+    pub const fn kind(&self) -> SamplingErrorKind
+    {
+        match self
+        {
+            Self::ComponentDoesNotExist { .. } | Self::ResourceDoesNotExist { .. } =>
+            {
+                SamplingErrorKind::TypeDoesNotExist
+            }
+            Self::TimeSeriesNotRecorded { .. }
+            | Self::EventsNotRecorded { .. }
+            | Self::AggregateNotTracked { .. }
+            | Self::PopulationNotTracked { .. } => SamplingErrorKind::NotRecorded,
+            #[cfg(any(feature = "image", feature = "netcdf"))]
+            Self::FramesNotRecorded { .. } => SamplingErrorKind::NotRecorded,
+            Self::SingleNoEntities { .. } | Self::AggregateNoEntities { .. } =>
+            {
+                SamplingErrorKind::NoEntities
+            }
+            Self::SingleMultipleEntities { .. } | Self::EntityIdentifierNotUnique { .. } =>
+            {
+                SamplingErrorKind::MultipleEntities
+            }
+            Self::EntityIdentifierNotFound { .. } | Self::EntityHandleNotFound { .. } =>
+            {
+                SamplingErrorKind::EntityNotFound
+            }
+        }
+    }
 }
 
 /// An error that occured when building a simulation
+///
+/// The endpoints and logs that can fail to be set up depend on the enabled cargo features,
+/// so the enum is non-exhaustive.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum BuilderError
 {
     /// The time series for the given pair of component and out types
     /// has already been set up for recording.
     TimeSeriesRecordingConflict,
+
+    /// The metrics endpoint could not be bound to the given address.
+    /// This can be returned by [`crate::SimulationBuilder::serve_metrics`].
+    #[cfg(feature = "metrics")]
+    MetricsEndpointUnavailable,
+
+    /// The WebSocket endpoint could not be bound to the given address.
+    /// This can be returned by [`crate::SimulationBuilder::serve_websocket`].
+    #[cfg(feature = "websocket")]
+    StreamEndpointUnavailable,
+
+    /// The `TensorBoard` event file could not be created in the given directory.
+    /// This can be returned by [`crate::SimulationBuilder::log_tensorboard`].
+    #[cfg(feature = "tensorboard")]
+    LogUnavailable,
+
+    /// No GPU adapter that supports compute shaders, or no device on it, could be found.
+    /// This can be returned by [`crate::SimulationBuilder::add_gpu_automaton`].
+    #[cfg(feature = "gpu")]
+    GpuUnavailable,
+}
+
+/// An error that occured when exporting the state of a simulation.
+///
+/// Some variants only exist with certain cargo features enabled, so the enum is
+/// non-exhaustive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ExportError
+{
+    /// A component could not be serialized.
+    /// This typically indicates that the type of one of its fields has not been registered with
+    /// [`crate::SimulationBuilder::register_type`].
+    Serialization,
+
+    /// The exported data could not be converted to, or written in, the Arrow or Parquet format.
+    #[cfg(feature = "arrow")]
+    Arrow,
+
+    /// The exported data could not be converted to a Polars data frame.
+    #[cfg(feature = "polars")]
+    Polars,
+
+    /// The file to export to could not be created.
+    #[cfg(any(feature = "arrow", feature = "image", feature = "netcdf"))]
+    Io,
+
+    /// The exported frames could not be encoded as images.
+    #[cfg(feature = "image")]
+    Image,
 }
 
-unsafe impl Send for SamplingError {}
-unsafe impl Sync for SamplingError {}
-unsafe impl Send for BuilderError {}
-unsafe impl Sync for BuilderError {}
+/// An error that occured when spawning entities from an external dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DatasetError
+{
+    /// The dataset could not be opened or read.
+    Io,
+
+    /// A record in the dataset could not be parsed into the expected type.
+    /// Holds the line of the dataset on which the record was found, if known.
+    InvalidRecord
+    {
+        line: Option<u64>
+    },
+}
+
+/// An error that occured when updating a spatial grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpatialGridError
+{
+    /// The position is outside the bounds of the spatial grid.
+    /// This can be returned by [`crate::SpatialGrid::try_insert`].
+    OutOfBounds,
+}
+
+/// An error that occured when decoding a snapshot with [`crate::snapshot::decode`].
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SnapshotError
+{
+    /// The data does not start with the header of a snapshot.
+    InvalidHeader,
+
+    /// The snapshot was written in a different version of the format than the one supported.
+    UnsupportedVersion
+    {
+        version: u16
+    },
+
+    /// The contents of the snapshot could not be decoded into the expected type.
+    Malformed,
+}
+
+/// An error that occured when saving results to, or loading them from, a [`crate::ResultStore`].
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StoreError
+{
+    /// The database could not be opened, read or written.
+    Database,
+
+    /// No run with the given identifier exists in the store.
+    RunNotFound
+    {
+        run: i64
+    },
+
+    /// The run with the given identifier has no time series with the requested name.
+    SeriesNotFound
+    {
+        run: i64
+    },
+}
+
+/// An error that occured when updating a GPU automaton on a simulation step, see [`crate::gpu`].
+#[cfg(feature = "gpu")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GpuError
+{
+    /// The states of the cells could not be read back from the GPU.
+    ReadbackFailed,
+}
+
+/// An error that occured when running an experiment with the `incerto-run` binary, see [`crate::cli`].
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RunnerError
+{
+    /// The description of the experiment could not be read or parsed.
+    InvalidConfig
+    {
+        message: String
+    },
+
+    /// The plugin could not be loaded, or does not export a model registry.
+    PluginUnavailable
+    {
+        message: String
+    },
+
+    /// No model with the given name is registered in the plugin.
+    ModelNotFound
+    {
+        model: String
+    },
+
+    /// The results collected from a replica do not match the columns of the model.
+    InvalidResults
+    {
+        model: String
+    },
+
+    /// The results could not be written to the output file.
+    Output,
+}
+
+/// An error caught by a numeric guard, set up with [`crate::SimulationBuilder::add_numeric_guard`].
+///
+/// Returned by [`crate::Simulation::check_numeric_guards`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum NumericGuardError
+{
+    /// A value sampled from a component was `NaN`.
+    NotANumber
+    {
+        /// The [`std::any::type_name`] of the component.
+        component: &'static str,
+        /// The entity that the component belongs to.
+        entity: Entity,
+        /// The step at the end of which the value was sampled.
+        step: usize,
+    },
+
+    /// A value sampled from a component was infinite.
+    Infinite
+    {
+        /// The [`std::any::type_name`] of the component.
+        component: &'static str,
+        /// The entity that the component belongs to.
+        entity: Entity,
+        /// The step at the end of which the value was sampled.
+        step: usize,
+        /// Whether the value was negative infinity.
+        negative: bool,
+    },
+}
+
+/// A problem found in the setup of a simulation by [`crate::SimulationBuilder::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum CheckIssue
+{
+    /// Building the simulation panicked, with the given message.
+    /// This typically originates from a spawner.
+    BuildPanicked
+    {
+        message: String
+    },
+
+    /// Executing a step of the simulation panicked, with the given message.
+    /// This typically originates from a system, for example due to a missing resource.
+    StepPanicked
+    {
+        message: String
+    },
+
+    /// A component is recorded in a time series, but no entities with it were spawned.
+    NoRecordedEntities
+    {
+        /// The [`std::any::type_name`] of the component.
+        component: &'static str,
+    },
+}
+
+impl Display for SimulationError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::Sampling(err) => write!(f, "sampling error: {err}"),
+            Self::Builder(err) => write!(f, "builder error: {err}"),
+            Self::Export(err) => write!(f, "export error: {err}"),
+            Self::Dataset(err) => write!(f, "dataset error: {err}"),
+            Self::SpatialGrid(err) => write!(f, "spatial grid error: {err}"),
+            Self::NumericGuard(err) => write!(f, "numeric guard error: {err}"),
+            #[cfg(feature = "sqlite")]
+            Self::Store(err) => write!(f, "store error: {err}"),
+            #[cfg(feature = "gpu")]
+            Self::Gpu(err) => write!(f, "gpu error: {err}"),
+        }
+    }
+}
+
+impl Error for SimulationError
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)>
+    {
+        match self
+        {
+            Self::Sampling(err) => Some(err),
+            Self::Builder(err) => Some(err),
+            Self::Export(err) => Some(err),
+            Self::Dataset(err) => Some(err),
+            Self::SpatialGrid(err) => Some(err),
+            Self::NumericGuard(err) => Some(err),
+            #[cfg(feature = "sqlite")]
+            Self::Store(err) => Some(err),
+            #[cfg(feature = "gpu")]
+            Self::Gpu(err) => Some(err),
+        }
+    }
+}
+
+impl Display for SamplingError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::ComponentDoesNotExist { component } =>
+            {
+                write!(f, "no entity with component {component} was ever spawned")
+            }
+            Self::SingleNoEntities { component } =>
+            {
+                write!(
+                    f,
+                    "expected a single entity with component {component}, but found none"
+                )
+            }
+            Self::SingleMultipleEntities { component } => write!(
+                f,
+                "expected a single entity with component {component}, but found more than one"
+            ),
+            Self::AggregateNoEntities { component } => write!(
+                f,
+                "expected one or more entities with component {component} for aggregate sampling, but found none"
+            ),
+            Self::TimeSeriesNotRecorded { component, output } => write!(
+                f,
+                "the time series of {output} sampled from component {component} has not been recorded"
+            ),
+            Self::EventsNotRecorded { event } =>
+            {
+                write!(f, "events of type {event} have not been recorded")
+            }
+            Self::AggregateNotTracked { component, output } =>
+            {
+                write!(
+                    f,
+                    "an incremental aggregate of {output} from component {component} has not been tracked"
+                )
+            }
+            Self::PopulationNotTracked { component } =>
+            {
+                write!(
+                    f,
+                    "the population of component {component} has not been tracked"
+                )
+            }
+            #[cfg(any(feature = "image", feature = "netcdf"))]
+            Self::FramesNotRecorded { component } =>
+            {
+                write!(
+                    f,
+                    "frames of the grid of component {component} have not been recorded"
+                )
+            }
+            Self::EntityIdentifierNotFound { identifier } =>
+            {
+                write!(f, "no entity found with the given {identifier}")
+            }
+            Self::EntityIdentifierNotUnique { identifier } =>
+            {
+                write!(f, "more than one entity found with the given {identifier}")
+            }
+            Self::EntityHandleNotFound { component } => write!(
+                f,
+                "the entity handle does not refer to an entity with component {component}"
+            ),
+            Self::ResourceDoesNotExist { resource } =>
+            {
+                write!(f, "resource {resource} does not exist")
+            }
+        }
+    }
+}
+
+impl Error for SamplingError {}
+
+impl Display for BuilderError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        f.write_str(match self
+        {
+            Self::TimeSeriesRecordingConflict =>
+            {
+                "the time series for these component and output types is already being recorded"
+            }
+            #[cfg(feature = "metrics")]
+            Self::MetricsEndpointUnavailable =>
+            {
+                "the metrics endpoint could not be bound to the given address"
+            }
+            #[cfg(feature = "websocket")]
+            Self::StreamEndpointUnavailable =>
+            {
+                "the websocket endpoint could not be bound to the given address"
+            }
+            #[cfg(feature = "tensorboard")]
+            Self::LogUnavailable => "the event file could not be created in the given directory",
+            #[cfg(feature = "gpu")]
+            Self::GpuUnavailable => "no GPU adapter that supports compute shaders was found",
+        })
+    }
+}
+
+impl Error for BuilderError {}
+
+impl Display for ExportError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        f.write_str(match self
+        {
+            Self::Serialization => "a component could not be serialized",
+            #[cfg(feature = "arrow")]
+            Self::Arrow => "the data could not be converted to the arrow format",
+            #[cfg(feature = "polars")]
+            Self::Polars => "the data could not be converted to a polars data frame",
+            #[cfg(any(feature = "arrow", feature = "image", feature = "netcdf"))]
+            Self::Io => "the export file could not be created",
+            #[cfg(feature = "image")]
+            Self::Image => "the frames could not be encoded as images",
+        })
+    }
+}
+
+impl Error for ExportError {}
+
+impl Display for DatasetError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::Io => f.write_str("the dataset could not be read"),
+            Self::InvalidRecord { line: Some(line) } =>
+            {
+                write!(f, "invalid record on line {line} of the dataset")
+            }
+            Self::InvalidRecord { line: None } => f.write_str("invalid record in the dataset"),
+        }
+    }
+}
+
+impl Error for DatasetError {}
+
+impl Display for SpatialGridError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        f.write_str(match self
+        {
+            Self::OutOfBounds => "the position is outside the bounds of the spatial grid",
+        })
+    }
+}
+
+impl Error for SpatialGridError {}
+
+#[cfg(feature = "msgpack")]
+impl Display for SnapshotError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::InvalidHeader => f.write_str("the data is not a snapshot"),
+            Self::UnsupportedVersion { version } =>
+            {
+                write!(f, "unsupported version {version} of the snapshot format")
+            }
+            Self::Malformed => f.write_str("the snapshot could not be decoded"),
+        }
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl Error for SnapshotError {}
+
+#[cfg(feature = "sqlite")]
+impl Display for StoreError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::Database => f.write_str("the database could not be accessed"),
+            Self::RunNotFound { run } => write!(f, "no run {run} in the store"),
+            Self::SeriesNotFound { run } =>
+            {
+                write!(f, "run {run} has no time series with the given name")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Error for StoreError {}
+
+#[cfg(feature = "gpu")]
+impl Display for GpuError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        f.write_str(match self
+        {
+            Self::ReadbackFailed => "the cell states could not be read back from the GPU",
+        })
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl Error for GpuError {}
+
+#[cfg(feature = "cli")]
+impl Display for RunnerError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::InvalidConfig { message } => write!(f, "invalid experiment: {message}"),
+            Self::PluginUnavailable { message } => write!(f, "plugin unavailable: {message}"),
+            Self::ModelNotFound { model } => write!(f, "no model named '{model}' in the plugin"),
+            Self::InvalidResults { model } =>
+            {
+                write!(f, "the results of model '{model}' do not match its columns")
+            }
+            Self::Output => f.write_str("the results could not be written"),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Error for RunnerError {}
+
+impl Display for NumericGuardError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::NotANumber {
+                component,
+                entity,
+                step,
+            } => write!(
+                f,
+                "component {component} of entity {entity} was NaN at step {step}"
+            ),
+            Self::Infinite {
+                component,
+                entity,
+                step,
+                negative,
+            } =>
+            {
+                let sign = if *negative { "-" } else { "+" };
+                write!(
+                    f,
+                    "component {component} of entity {entity} was {sign}infinity at step {step}"
+                )
+            }
+        }
+    }
+}
+
+impl Error for NumericGuardError {}
+
+impl Display for CheckIssue
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::BuildPanicked { message } => write!(f, "building panicked: {message}"),
+            Self::StepPanicked { message } => write!(f, "executing a step panicked: {message}"),
+            Self::NoRecordedEntities { component } =>
+            {
+                write!(
+                    f,
+                    "component {component} is recorded, but was never spawned"
+                )
+            }
+        }
+    }
+}
+
+impl Error for CheckIssue {}
 
 impl From<SamplingError> for SimulationError
 {
@@ -71,3 +788,53 @@ impl From<BuilderError> for SimulationError
         Self::Builder(value)
     }
 }
+
+impl From<ExportError> for SimulationError
+{
+    fn from(value: ExportError) -> Self
+    {
+        Self::Export(value)
+    }
+}
+
+impl From<DatasetError> for SimulationError
+{
+    fn from(value: DatasetError) -> Self
+    {
+        Self::Dataset(value)
+    }
+}
+
+impl From<SpatialGridError> for SimulationError
+{
+    fn from(value: SpatialGridError) -> Self
+    {
+        Self::SpatialGrid(value)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<StoreError> for SimulationError
+{
+    fn from(value: StoreError) -> Self
+    {
+        Self::Store(value)
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl From<GpuError> for SimulationError
+{
+    fn from(value: GpuError) -> Self
+    {
+        Self::Gpu(value)
+    }
+}
+
+impl From<NumericGuardError> for SimulationError
+{
+    fn from(value: NumericGuardError) -> Self
+    {
+        Self::NumericGuard(value)
+    }
+}
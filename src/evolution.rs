@@ -0,0 +1,436 @@
+//! Evolutionary Monte Carlo experiments with genetic algorithms.
+//!
+//! A population of agents carrying a [`Genome`] component is evaluated over a number of steps,
+//! and then replaced by the offspring of its fittest members, generation after generation.
+//!
+//! The evolution is added to a simulation with [`crate::SimulationBuilder::add_evolution`],
+//! configured through an [`EvolutionPlugin`].
+//! The fitness of every individual is sampled from its genome component through its
+//! [`Sample<f64>`] implementation, so it may be computed from the genes directly, or accumulated
+//! by user-defined systems over the steps of a generation, in fields of the same component.
+//!
+//! At the end of the last step of every generation, parents are chosen with the configured
+//! [`Selection`], recombined with [`Genome::crossover`] and altered with [`Genome::mutate`],
+//! and the offspring replace the genome components of the population in place, keeping all
+//! other components of the agents.
+//! The fitness of every generation is summarized in the [`EvolutionOutputs`] resource, and the
+//! progress of the evolution is kept in the [`Evolution`] resource.
+//!
+//! The functions [`single_point_crossover`], [`uniform_crossover`], [`flip_bits`] and
+//! [`perturb_gaussian`] implement the common operators for genomes made of a sequence of genes.
+//!
+//! Example of evolving bit strings towards all ones:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::evolution::{self, EvolutionOutputs, EvolutionPlugin, Genome, Selection};
+//!
+//! #[derive(Component, Clone)]
+//! struct Bits(Vec<bool>);
+//!
+//! impl Genome for Bits
+//! {
+//!     fn crossover(&self, other: &Self, rng: &mut SimRng) -> Self
+//!     {
+//!         Self(evolution::single_point_crossover(&self.0, &other.0, rng))
+//!     }
+//!
+//!     fn mutate(&mut self, rate: f64, rng: &mut SimRng)
+//!     {
+//!         evolution::flip_bits(&mut self.0, rate, rng);
+//!     }
+//! }
+//!
+//! impl Sample<f64> for Bits
+//! {
+//!     fn sample(component: &Self) -> f64
+//!     {
+//!         component.0.iter().filter(|&&bit| bit).count() as f64
+//!     }
+//! }
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_evolution(EvolutionPlugin::<Bits>::new(1).with_selection(Selection::Tournament(3)))
+//!     .add_entity_spawner(|spawner| {
+//!         for _ in 0..100
+//!         {
+//!             spawner.spawn(Bits(vec![false; 32]));
+//!         }
+//!     })
+//!     .build();
+//!
+//! simulation.run(50);
+//! let outputs = simulation.get_resource::<EvolutionOutputs<Bits>>().unwrap();
+//! let best = outputs.latest().unwrap().best;
+//! ```
+
+use std::marker::PhantomData;
+
+use bevy::{ecs::component::Mutable, prelude::*};
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+use crate::{MetricsHistory, Sample, SimRng, StepNumber};
+
+/// Component holding the genes of an individual, which are passed on to its offspring.
+///
+/// The fitness of the individual is sampled through its [`Sample<f64>`] implementation,
+/// with higher values being fitter.
+pub trait Genome: Component<Mutability = Mutable> + Clone + Sample<f64>
+{
+    /// Recombines the genes of two parents into those of their offspring.
+    #[must_use]
+    fn crossover(&self, other: &Self, rng: &mut SimRng) -> Self;
+
+    /// Randomly alters the genes of an offspring, where `rate` is the probability of every
+    /// gene being altered.
+    fn mutate(&mut self, rate: f64, rng: &mut SimRng);
+
+    /// Resets any state accumulated over a generation, such as the fitness, of an individual
+    /// of the next generation, including those passed on unchanged through elitism.
+    ///
+    /// Does nothing by default.
+    fn reset(&mut self) {}
+}
+
+/// How the parents of every offspring are chosen from the population.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection
+{
+    /// The fittest of the given number of individuals, drawn uniformly at random.
+    Tournament(usize),
+
+    /// An individual drawn with probability proportional to its fitness, less the fitness of
+    /// the least fit individual in the population.
+    Roulette,
+
+    /// An individual drawn uniformly from the given fraction of the fittest of the population.
+    Truncation(f64),
+}
+
+impl Default for Selection
+{
+    fn default() -> Self
+    {
+        Self::Tournament(2)
+    }
+}
+
+impl Selection
+{
+    /// Chooses the index of a parent, out of a population sorted from the fittest to the least fit.
+    fn choose(self, fitness: &[f64], rng: &mut SimRng) -> usize
+    {
+        match self
+        {
+            Self::Tournament(size) => (0..size.max(1))
+                .map(|_| rng.random_range(0..fitness.len()))
+                .min()
+                .unwrap_or_default(),
+            Self::Roulette =>
+            {
+                let least = fitness.last().copied().unwrap_or_default();
+                let total: f64 = fitness.iter().map(|&f| f - least).sum();
+                if total <= 0.0
+                {
+                    return rng.random_range(0..fitness.len());
+                }
+
+                let mut pick = rng.random::<f64>() * total;
+                fitness
+                    .iter()
+                    .position(|&f| {
+                        pick -= f - least;
+                        pick < 0.0
+                    })
+                    .unwrap_or(fitness.len() - 1)
+            }
+            Self::Truncation(fraction) =>
+            {
+                #[allow(clippy::cast_possible_truncation)]
+                #[allow(clippy::cast_precision_loss)]
+                #[allow(clippy::cast_sign_loss)]
+                let fittest = ((fitness.len() as f64) * fraction).ceil() as usize;
+                rng.random_range(0..fittest.clamp(1, fitness.len()))
+            }
+        }
+    }
+}
+
+/// Configuration of the evolution of a population with genome `C`,
+/// added with [`crate::SimulationBuilder::add_evolution`].
+#[derive(Debug)]
+pub struct EvolutionPlugin<C>
+{
+    generation_length: usize,
+    selection: Selection,
+    crossover_rate: f64,
+    mutation_rate: f64,
+    elitism: usize,
+    _phantom: PhantomData<C>,
+}
+
+impl<C> Clone for EvolutionPlugin<C>
+{
+    fn clone(&self) -> Self
+    {
+        *self
+    }
+}
+
+impl<C> Copy for EvolutionPlugin<C> {}
+
+impl<C: Genome> EvolutionPlugin<C>
+{
+    /// Creates an evolution in which every generation lasts the given number of steps.
+    ///
+    /// By default, parents are chosen by [`Selection::Tournament`] between two individuals,
+    /// recombined with a rate of `0.9`, and every gene of their offspring mutates with
+    /// probability `0.01`, without any elitism.
+    ///
+    /// # Panics
+    ///
+    /// If `generation_length` is `0`.
+    #[must_use]
+    pub fn new(generation_length: usize) -> Self
+    {
+        assert!(
+            generation_length > 0,
+            "generations must last at least one step"
+        );
+
+        Self {
+            generation_length,
+            selection: Selection::default(),
+            crossover_rate: 0.9,
+            mutation_rate: 0.01,
+            elitism: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Sets how the parents of every offspring are chosen.
+    #[must_use]
+    pub const fn with_selection(mut self, selection: Selection) -> Self
+    {
+        self.selection = selection;
+        self
+    }
+
+    /// Sets the probability of an offspring being the crossover of two parents, rather than
+    /// a copy of a single one.
+    #[must_use]
+    pub const fn with_crossover_rate(mut self, rate: f64) -> Self
+    {
+        self.crossover_rate = rate;
+        self
+    }
+
+    /// Sets the probability of every gene of an offspring being mutated,
+    /// which is passed to [`Genome::mutate`].
+    #[must_use]
+    pub const fn with_mutation_rate(mut self, rate: f64) -> Self
+    {
+        self.mutation_rate = rate;
+        self
+    }
+
+    /// Sets the number of the fittest individuals of every generation, that are passed on
+    /// unchanged to the next one.
+    #[must_use]
+    pub const fn with_elitism(mut self, count: usize) -> Self
+    {
+        self.elitism = count;
+        self
+    }
+}
+
+impl<C: Genome> Plugin for EvolutionPlugin<C>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.insert_resource(Evolution::<C> {
+            config: *self,
+            generation: 0,
+            fittest: None,
+        })
+        .insert_resource(EvolutionOutputs::<C>::new(self.generation_length))
+        .add_systems(PostUpdate, evolution_system::<C>);
+    }
+}
+
+/// Summary of the fitness of a generation, as evaluated at its end.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GenerationStats
+{
+    /// The number of the generation, starting from `1`.
+    pub generation: usize,
+
+    /// The fitness of the fittest individual.
+    pub best: f64,
+
+    /// The mean fitness of the population.
+    pub mean: f64,
+
+    /// The fitness of the least fit individual.
+    pub worst: f64,
+}
+
+/// Resource holding the progress of the evolution of a population with genome `C`.
+#[derive(Resource)]
+pub struct Evolution<C: Genome>
+{
+    config: EvolutionPlugin<C>,
+    generation: usize,
+    fittest: Option<C>,
+}
+
+/// Resource holding the [`GenerationStats`] of every completed generation of a population with
+/// genome `C`, recorded at the last step of the generation.
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<EvolutionOutputs<C>>`] arguments.
+pub type EvolutionOutputs<C> = MetricsHistory<GenerationStats, C>;
+
+impl<C: Genome> Evolution<C>
+{
+    /// The number of generations completed so far.
+    #[must_use]
+    pub const fn generation(&self) -> usize
+    {
+        self.generation
+    }
+
+    /// The genome of the fittest individual of the latest completed generation.
+    #[must_use]
+    pub const fn fittest(&self) -> Option<&C>
+    {
+        self.fittest.as_ref()
+    }
+}
+
+/// System that replaces the population with its offspring at the end of every generation.
+fn evolution_system<C: Genome>(
+    mut evolution: ResMut<Evolution<C>>,
+    mut outputs: ResMut<EvolutionOutputs<C>>,
+    mut query: Query<&mut C>,
+    mut rng: ResMut<SimRng>,
+    step_number: Res<StepNumber>,
+)
+{
+    let config = evolution.config;
+    if !step_number.is_multiple_of(config.generation_length) || query.is_empty()
+    {
+        return;
+    }
+
+    // rank the population from the fittest to the least fit
+    let mut ranked: Vec<(f64, C)> = query
+        .iter()
+        .map(|genome| (C::sample(genome), genome.clone()))
+        .collect();
+    ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    let fitness: Vec<f64> = ranked.iter().map(|&(fitness, _)| fitness).collect();
+
+    #[allow(clippy::cast_precision_loss)]
+    let mean = fitness.iter().sum::<f64>() / fitness.len() as f64;
+    evolution.generation += 1;
+    let stats = GenerationStats {
+        generation: evolution.generation,
+        best: fitness[0],
+        mean,
+        worst: fitness[fitness.len() - 1],
+    };
+    outputs.push(**step_number, stats);
+    evolution.fittest = Some(ranked[0].1.clone());
+
+    let rng = &mut *rng;
+    for (i, mut genome) in query.iter_mut().enumerate()
+    {
+        *genome = if i < config.elitism
+        {
+            ranked[i].1.clone()
+        }
+        else
+        {
+            let first = &ranked[config.selection.choose(&fitness, rng)].1;
+            let mut offspring = if rng.random_bool(config.crossover_rate.clamp(0.0, 1.0))
+            {
+                let second = &ranked[config.selection.choose(&fitness, rng)].1;
+                first.crossover(second, rng)
+            }
+            else
+            {
+                first.clone()
+            };
+            offspring.mutate(config.mutation_rate, rng);
+            offspring
+        };
+        genome.reset();
+    }
+}
+
+/// Recombines two sequences of genes by taking those of `first` up to a random point,
+/// and those of `second` after it.
+///
+/// The offspring has the length of the shorter of the two.
+#[must_use]
+pub fn single_point_crossover<T: Clone>(first: &[T], second: &[T], rng: &mut impl Rng) -> Vec<T>
+{
+    let len = first.len().min(second.len());
+    let point = rng.random_range(0..=len);
+    first[..point]
+        .iter()
+        .chain(&second[point..len])
+        .cloned()
+        .collect()
+}
+
+/// Recombines two sequences of genes by taking each gene from either of them with equal probability.
+///
+/// The offspring has the length of the shorter of the two.
+#[must_use]
+pub fn uniform_crossover<T: Clone>(first: &[T], second: &[T], rng: &mut impl Rng) -> Vec<T>
+{
+    first
+        .iter()
+        .zip(second)
+        .map(|(a, b)| if rng.random_bool(0.5) { a } else { b }.clone())
+        .collect()
+}
+
+/// Flips every bit with probability `rate`.
+pub fn flip_bits(genes: &mut [bool], rate: f64, rng: &mut impl Rng)
+{
+    let rate = rate.clamp(0.0, 1.0);
+    for gene in genes
+    {
+        if rng.random_bool(rate)
+        {
+            *gene = !*gene;
+        }
+    }
+}
+
+/// Adds normally distributed noise with the given standard deviation to every gene,
+/// with probability `rate`.
+///
+/// # Panics
+///
+/// If `std_dev` is negative or not finite.
+pub fn perturb_gaussian(genes: &mut [f64], rate: f64, std_dev: f64, rng: &mut impl Rng)
+{
+    let Ok(noise) = Normal::new(0.0, std_dev)
+    else
+    {
+        panic!("invalid standard deviation: {std_dev}");
+    };
+
+    let rate = rate.clamp(0.0, 1.0);
+    for gene in genes
+    {
+        if rng.random_bool(rate)
+        {
+            *gene += noise.sample(rng);
+        }
+    }
+}
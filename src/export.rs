@@ -0,0 +1,119 @@
+use bevy::{
+    ecs::reflect::AppTypeRegistry,
+    prelude::*,
+    reflect::{TypeRegistry, serde::TypedReflectSerializer},
+};
+use serde::{Serialize, Serializer, ser::SerializeMap};
+
+use crate::{SimulationMeta, StepNumber, error::ExportError};
+
+/// Serializable snapshot of the full state of a simulation's world.
+#[derive(Serialize)]
+struct WorldState<'a>
+{
+    meta: &'a SimulationMeta,
+    step: usize,
+    entities: Vec<EntityState<'a>>,
+}
+
+/// Serializable snapshot of a single entity, and all of its registered components.
+#[derive(Serialize)]
+struct EntityState<'a>
+{
+    entity: u64,
+    components: ComponentsState<'a>,
+}
+
+/// The registered components of a single entity, serialized as a map from the
+/// component's type path to its value.
+struct ComponentsState<'a>
+{
+    components: Vec<(&'static str, &'a dyn Reflect)>,
+    registry: &'a TypeRegistry,
+}
+
+impl Serialize for ComponentsState<'_>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        let mut map = serializer.serialize_map(Some(self.components.len()))?;
+        for &(type_path, value) in &self.components
+        {
+            map.serialize_entry(
+                type_path,
+                &TypedReflectSerializer::new(value.as_partial_reflect(), self.registry),
+            )?;
+        }
+        map.end()
+    }
+}
+
+/// Serializes all entities in the world, along with those of their components whose
+/// types have been registered for reflection, as JSON.
+///
+/// Entities are ordered by their id, and components by their type path, so that the exported
+/// state of two worlds can be diffed directly.
+/// Entities without any registered components are omitted.
+pub fn export_state(world: &World) -> Result<String, ExportError>
+{
+    with_state(world, |state| {
+        serde_json::to_string_pretty(state).map_err(|_| ExportError::Serialization)
+    })
+}
+
+/// Serializes the state of the world in the same way as [`export_state`], as a binary snapshot.
+#[cfg(feature = "msgpack")]
+pub fn export_state_snapshot(world: &World) -> Result<Vec<u8>, ExportError>
+{
+    with_state(world, |state| crate::snapshot::encode(state))
+}
+
+/// Takes a snapshot of the state of the world and passes it to the given `serialize` function.
+fn with_state<R>(
+    world: &World,
+    serialize: impl FnOnce(&WorldState) -> Result<R, ExportError>,
+) -> Result<R, ExportError>
+{
+    let registry = world.resource::<AppTypeRegistry>().read();
+
+    let mut entities = world
+        .iter_entities()
+        .filter_map(|entity_ref| {
+            let mut components = entity_ref
+                .archetype()
+                .components()
+                .filter_map(|component_id| world.components().get_info(component_id)?.type_id())
+                .filter_map(|type_id| registry.get(type_id))
+                .filter_map(|registration| {
+                    let value = registration
+                        .data::<ReflectComponent>()?
+                        .reflect(entity_ref)?;
+                    Some((registration.type_info().type_path(), value))
+                })
+                .collect::<Vec<_>>();
+
+            if components.is_empty()
+            {
+                return None;
+            }
+            components.sort_unstable_by_key(|&(type_path, _)| type_path);
+
+            Some(EntityState {
+                entity: entity_ref.id().to_bits(),
+                components: ComponentsState {
+                    components,
+                    registry: &registry,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    entities.sort_unstable_by_key(|entity| entity.entity);
+
+    let state = WorldState {
+        meta: world.resource::<SimulationMeta>(),
+        step: world.resource::<StepNumber>().get() - 1,
+        entities,
+    };
+
+    serialize(&state)
+}
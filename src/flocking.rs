@@ -0,0 +1,584 @@
+//! Flocking of agents through the steering behaviors of boids.
+//!
+//! Boids keep their separation from those too close, align with the heading of nearby
+//! flockmates, move towards their center, and avoid predators.
+//!
+//! Every agent of a flock is an entity with a [`Boid`] component, holding its position and
+//! velocity in continuous space, along with the component `C` that tells which flock it belongs
+//! to, so that several species can flock independently.
+//! The flock is added to a simulation with [`crate::SimulationBuilder::add_flocking`],
+//! configured through a [`FlockingPlugin<C>`], and on every step each boid steers according to
+//! the flockmates within its perception radius, as they were at the start of the step.
+//! Entities with a [`Predator<C>`] component and a [`Boid`] are avoided by the flock `C`, and may
+//! themselves flock, or be moved by user-defined systems.
+//!
+//! Boids on a grid also move their [`GridPosition2D`], if they have one, to the cell nearest to
+//! their position, so that they can be tracked by a [`crate::SpatialGrid`].
+//! The order of the flock on every step is recorded in the [`FlockOutputs<C>`] resource.
+//!
+//! Example of a school of fish aligning from random headings:
+//! ```
+//! # use incerto::prelude::*;
+//! use bevy::math::DVec2;
+//! use incerto::flocking::{Boid, Boundary, FlockOutputs, FlockingPlugin};
+//! use rand::Rng;
+//!
+//! #[derive(Component)]
+//! struct Fish;
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .set_seed(1)
+//!     .add_flocking(
+//!         FlockingPlugin::<Fish>::new(5.0).with_boundary(Boundary::Wrap {
+//!             min: DVec2::ZERO,
+//!             max: DVec2::splat(30.0),
+//!         }),
+//!     )
+//!     .add_seeded_entity_spawner(|spawner, rng| {
+//!         for _ in 0..100
+//!         {
+//!             let position = DVec2::new(rng.random_range(0.0..30.0), rng.random_range(0.0..30.0));
+//!             let velocity = DVec2::from_angle(rng.random_range(0.0..std::f64::consts::TAU));
+//!             spawner.spawn((Fish, Boid::new(position, velocity)));
+//!         }
+//!     })
+//!     .build();
+//!
+//! simulation.run(200);
+//! let outputs = simulation.get_resource::<FlockOutputs<Fish>>().unwrap();
+//! assert!(outputs.latest().unwrap().polarization > 0.9);
+//! ```
+
+use std::marker::PhantomData;
+
+use bevy::{math::DVec2, platform::collections::HashMap, prelude::*};
+
+use crate::{
+    MetricsHistory, StepNumber,
+    plugins::{GridPosition, GridPosition2D},
+};
+
+/// Component holding the position and velocity of an agent that flocks, in continuous space.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Boid
+{
+    position: DVec2,
+    velocity: DVec2,
+}
+
+impl Boid
+{
+    /// A boid at `position`, moving by `velocity` on every step.
+    #[must_use]
+    pub const fn new(position: DVec2, velocity: DVec2) -> Self
+    {
+        Self { position, velocity }
+    }
+
+    /// A boid at the center of a grid cell, moving by `velocity` on every step.
+    #[must_use]
+    pub fn at_cell(cell: GridPosition2D, velocity: DVec2) -> Self
+    {
+        Self::new(cell.0.as_dvec2(), velocity)
+    }
+
+    /// The current position of the boid.
+    #[must_use]
+    pub const fn position(&self) -> DVec2
+    {
+        self.position
+    }
+
+    /// The distance the boid moves on every step, and its heading.
+    #[must_use]
+    pub const fn velocity(&self) -> DVec2
+    {
+        self.velocity
+    }
+
+    /// Moves the boid to a new position, e.g. when it is controlled by user-defined systems.
+    pub const fn set_position(&mut self, position: DVec2)
+    {
+        self.position = position;
+    }
+
+    /// Sets the velocity of the boid, which the flock steers from on the next step.
+    pub const fn set_velocity(&mut self, velocity: DVec2)
+    {
+        self.velocity = velocity;
+    }
+}
+
+/// Component marking a [`Boid`] as a predator that the flock `C` avoids.
+#[derive(Component, Debug)]
+pub struct Predator<C>(PhantomData<C>);
+
+impl<C> Default for Predator<C>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+/// The edges of the space that boids move in.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Boundary
+{
+    /// The space extends without limits.
+    #[default]
+    Open,
+
+    /// Boids leaving the rectangle from `min` to `max` come back in on the opposite edge, and
+    /// sense flockmates across the edges, as on a torus.
+    Wrap
+    {
+        min: DVec2, max: DVec2
+    },
+
+    /// Boids bounce off the edges of the rectangle from `min` to `max`.
+    Reflect
+    {
+        min: DVec2, max: DVec2
+    },
+}
+
+impl Boundary
+{
+    /// The displacement from `from` to `to`, which is the shortest one across the edges when
+    /// wrapping.
+    fn displacement(self, from: DVec2, to: DVec2) -> DVec2
+    {
+        let displacement = to - from;
+        match self
+        {
+            Self::Wrap { min, max } =>
+            {
+                let size = max - min;
+                displacement - size * (displacement / size).round()
+            }
+            Self::Open | Self::Reflect { .. } => displacement,
+        }
+    }
+
+    /// Moves a boid back within the edges.
+    fn confine(self, boid: &mut Boid)
+    {
+        match self
+        {
+            Self::Open =>
+            {}
+            Self::Wrap { min, max } =>
+            {
+                boid.position = min + (boid.position - min).rem_euclid(max - min);
+            }
+            Self::Reflect { min, max } =>
+            {
+                for axis in 0..2
+                {
+                    if boid.position[axis] < min[axis]
+                    {
+                        boid.position[axis] = 2.0f64.mul_add(min[axis], -boid.position[axis]);
+                        boid.velocity[axis] = boid.velocity[axis].abs();
+                    }
+                    else if boid.position[axis] > max[axis]
+                    {
+                        boid.position[axis] = 2.0f64.mul_add(max[axis], -boid.position[axis]);
+                        boid.velocity[axis] = -boid.velocity[axis].abs();
+                    }
+                }
+                boid.position = boid.position.clamp(min, max);
+            }
+        }
+    }
+}
+
+/// The flocking of the boids with the component `C`, added to a simulation with
+/// [`crate::SimulationBuilder::add_flocking`].
+///
+/// On every step, each boid is accelerated by the weighted sum of its steering behaviors, and then
+/// moves by its velocity, limited to the maximum speed.
+/// Every behavior steers towards a desired direction at the maximum speed, with a force limited
+/// to the maximum force:
+/// - Separation steers away from the flockmates within the separation radius, more strongly from
+///   the closest ones.
+/// - Alignment steers towards the mean heading of the flockmates within the perception radius.
+/// - Cohesion steers towards the center of the flockmates within the perception radius.
+/// - Avoidance steers away from the [`Predator<C>`]s within the avoidance radius, more strongly
+///   from the closest ones.
+///
+/// The boids move alongside the user-defined systems.
+#[derive(Debug)]
+pub struct FlockingPlugin<C>
+{
+    perception: f64,
+    separation: (f64, f64),
+    alignment: f64,
+    cohesion: f64,
+    avoidance: (f64, f64),
+    max_speed: f64,
+    max_force: f64,
+    boundary: Boundary,
+    marker: PhantomData<C>,
+}
+
+impl<C> Clone for FlockingPlugin<C>
+{
+    fn clone(&self) -> Self
+    {
+        Self {
+            marker: PhantomData,
+            ..*self
+        }
+    }
+}
+
+impl<C: Component> FlockingPlugin<C>
+{
+    /// A flock in which boids sense the flockmates within the `perception` radius.
+    ///
+    /// By default, boids keep a separation radius of a fifth of their perception, with a weight
+    /// of `1.5`, the alignment and cohesion have a weight of `1`, and predators are avoided
+    /// within the perception radius with a weight of `3`.
+    /// The speed is limited to `1`, the steering force to `0.1`, and the space is
+    /// [`Boundary::Open`].
+    ///
+    /// # Panics
+    ///
+    /// If `perception` is not positive and finite.
+    #[must_use]
+    pub fn new(perception: f64) -> Self
+    {
+        assert!(
+            perception.is_finite() && perception > 0.0,
+            "invalid perception radius: {perception}"
+        );
+
+        Self {
+            perception,
+            separation: (1.5, perception / 5.0),
+            alignment: 1.0,
+            cohesion: 1.0,
+            avoidance: (3.0, perception),
+            max_speed: 1.0,
+            max_force: 0.1,
+            boundary: Boundary::Open,
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets the weight of separation, and the radius within which flockmates are too close.
+    ///
+    /// # Panics
+    ///
+    /// If `radius` is greater than the perception radius.
+    #[must_use]
+    pub fn with_separation(mut self, weight: f64, radius: f64) -> Self
+    {
+        assert!(
+            (0.0..=self.perception).contains(&radius),
+            "invalid separation radius: {radius}"
+        );
+        self.separation = (weight, radius);
+        self
+    }
+
+    /// Sets the weight of alignment.
+    #[must_use]
+    pub const fn with_alignment(mut self, weight: f64) -> Self
+    {
+        self.alignment = weight;
+        self
+    }
+
+    /// Sets the weight of cohesion.
+    #[must_use]
+    pub const fn with_cohesion(mut self, weight: f64) -> Self
+    {
+        self.cohesion = weight;
+        self
+    }
+
+    /// Sets the weight of avoiding predators, and the radius within which they are sensed.
+    ///
+    /// # Panics
+    ///
+    /// If `radius` is negative or not finite.
+    #[must_use]
+    pub fn with_avoidance(mut self, weight: f64, radius: f64) -> Self
+    {
+        assert!(
+            radius.is_finite() && radius >= 0.0,
+            "invalid avoidance radius: {radius}"
+        );
+        self.avoidance = (weight, radius);
+        self
+    }
+
+    /// Sets the largest distance a boid moves on a step, and the largest change to its velocity.
+    ///
+    /// # Panics
+    ///
+    /// If either limit is negative or not finite.
+    #[must_use]
+    pub fn with_limits(mut self, max_speed: f64, max_force: f64) -> Self
+    {
+        assert!(
+            max_speed.is_finite() && max_speed >= 0.0 && max_force.is_finite() && max_force >= 0.0,
+            "invalid flocking limits: speed {max_speed}, force {max_force}"
+        );
+        self.max_speed = max_speed;
+        self.max_force = max_force;
+        self
+    }
+
+    /// Sets the edges of the space that boids move in.
+    #[must_use]
+    pub const fn with_boundary(mut self, boundary: Boundary) -> Self
+    {
+        self.boundary = boundary;
+        self
+    }
+
+    /// The acceleration of a boid, given the displacements and velocities of the flockmates and
+    /// predators it senses.
+    #[allow(clippy::cast_precision_loss)]
+    fn steer(&self, boid: &Boid, neighbors: &[(DVec2, DVec2)], predators: &[DVec2]) -> DVec2
+    {
+        // every behavior steers towards the velocity at full speed in its own direction
+        let towards = |direction: DVec2| {
+            if direction == DVec2::ZERO
+            {
+                return DVec2::ZERO;
+            }
+            (direction.normalize() * self.max_speed - boid.velocity)
+                .clamp_length_max(self.max_force)
+        };
+
+        let mut steering =
+            self.avoidance.0 * towards(repulsion(predators.iter().copied(), self.avoidance.1));
+        if !neighbors.is_empty()
+        {
+            let heading = neighbors
+                .iter()
+                .map(|(_, velocity)| *velocity)
+                .sum::<DVec2>();
+            let center =
+                neighbors.iter().map(|(offset, _)| *offset).sum::<DVec2>() / neighbors.len() as f64;
+            let separation = repulsion(
+                neighbors.iter().map(|(offset, _)| *offset),
+                self.separation.1,
+            );
+
+            steering += self.separation.0 * towards(separation)
+                + self.alignment * towards(heading)
+                + self.cohesion * towards(center);
+        }
+        steering
+    }
+}
+
+/// The sum of the directions away from every displacement within `radius`, each weighted by the
+/// inverse of its length.
+fn repulsion(displacements: impl Iterator<Item = DVec2>, radius: f64) -> DVec2
+{
+    displacements
+        .filter(|offset| *offset != DVec2::ZERO && offset.length() <= radius)
+        .map(|offset| -offset / offset.length_squared())
+        .sum()
+}
+
+impl<C: Component> Plugin for FlockingPlugin<C>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.insert_resource(FlockingParameters(self.clone()))
+            .init_resource::<FlockOutputs<C>>()
+            .add_systems(Update, flocking_system::<C>);
+    }
+}
+
+/// The order of a flock at the end of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FlockMetrics
+{
+    /// The number of the step, as read from [`StepNumber`].
+    pub step: usize,
+
+    /// The number of boids in the flock.
+    pub boids: usize,
+
+    /// The length of the mean heading of the boids, from `0` when they head in all directions
+    /// or stand still, to `1` when they all head the same way.
+    pub polarization: f64,
+
+    pub mean_speed: f64,
+
+    /// The mean number of flockmates each boid sensed.
+    pub mean_neighbors: f64,
+}
+
+/// Resource holding the [`FlockMetrics`] of every step for the flock `C`.
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<FlockOutputs<C>>`] arguments.
+pub type FlockOutputs<C> = MetricsHistory<FlockMetrics, C>;
+
+/// Resource holding the parameters of the flock `C`.
+#[derive(Resource)]
+struct FlockingParameters<C>(FlockingPlugin<C>);
+
+/// The boids of a flock binned into square cells as wide as the perception radius, so that the
+/// flockmates of a boid are found among those in the surrounding cells.
+struct Bins
+{
+    cells: HashMap<IVec2, Vec<usize>>,
+    size: f64,
+    /// The number of cells along each axis when wrapping, in which case cells wrap around too.
+    wrap: Option<(DVec2, IVec2)>,
+}
+
+impl Bins
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn new(boids: &[(Entity, Boid)], size: f64, boundary: Boundary) -> Self
+    {
+        let wrap = match boundary
+        {
+            Boundary::Wrap { min, max } =>
+            {
+                Some((min, ((max - min) / size).floor().as_ivec2().max(IVec2::ONE)))
+            }
+            Boundary::Open | Boundary::Reflect { .. } => None,
+        };
+        let mut bins = Self {
+            cells: HashMap::default(),
+            size,
+            wrap,
+        };
+        for (index, (_, boid)) in boids.iter().enumerate()
+        {
+            let cell = bins.cell_of(boid.position);
+            bins.cells.entry(cell).or_default().push(index);
+        }
+        bins
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn cell_of(&self, position: DVec2) -> IVec2
+    {
+        match self.wrap
+        {
+            Some((min, counts)) =>
+            {
+                let cell = ((position - min) / self.size).floor().as_ivec2();
+                cell.rem_euclid(counts)
+            }
+            None => (position / self.size).floor().as_ivec2(),
+        }
+    }
+
+    /// The indices of the boids in the cells around the cell of `position`.
+    fn around(&self, position: DVec2, buffer: &mut Vec<usize>)
+    {
+        buffer.clear();
+        let center = self.cell_of(position);
+        let mut cells = Vec::with_capacity(9);
+        for x in -1..=1
+        {
+            for y in -1..=1
+            {
+                let mut cell = center + IVec2::new(x, y);
+                if let Some((_, counts)) = self.wrap
+                {
+                    cell = cell.rem_euclid(counts);
+                }
+                if !cells.contains(&cell)
+                {
+                    cells.push(cell);
+                }
+            }
+        }
+        for cell in cells
+        {
+            buffer.extend(self.cells.get(&cell).into_iter().flatten());
+        }
+    }
+}
+
+/// System that steers and moves every boid of the flock `C`, and records the metrics of the step.
+#[allow(clippy::cast_precision_loss)]
+fn flocking_system<C: Component>(
+    parameters: Res<FlockingParameters<C>>,
+    mut outputs: ResMut<FlockOutputs<C>>,
+    mut flock: Query<(Entity, &mut Boid, Option<&mut GridPosition2D>), With<C>>,
+    predators: Query<&Boid, (With<Predator<C>>, Without<C>)>,
+    step_number: Res<StepNumber>,
+)
+{
+    let parameters = &parameters.0;
+    let boundary = parameters.boundary;
+
+    // boids steer according to the flock at the start of the step, so that the order in which
+    // they are visited does not matter
+    let boids = flock
+        .iter()
+        .map(|(entity, boid, _)| (entity, *boid))
+        .collect::<Vec<_>>();
+    let predators = predators.iter().map(Boid::position).collect::<Vec<_>>();
+    let bins = Bins::new(&boids, parameters.perception, boundary);
+
+    let mut candidates = Vec::new();
+    let mut neighbors = Vec::new();
+    let mut threats = Vec::new();
+    let mut metrics = FlockMetrics {
+        step: **step_number,
+        boids: boids.len(),
+        ..default()
+    };
+    let mut heading = DVec2::ZERO;
+    for (entity, mut boid, grid_position) in &mut flock
+    {
+        bins.around(boid.position, &mut candidates);
+        neighbors.clear();
+        for &index in &candidates
+        {
+            let (other, mate) = &boids[index];
+            let offset = boundary.displacement(boid.position, mate.position);
+            if *other != entity && offset.length() <= parameters.perception
+            {
+                neighbors.push((offset, mate.velocity));
+            }
+        }
+        threats.clear();
+        threats.extend(
+            predators
+                .iter()
+                .map(|predator| boundary.displacement(boid.position, *predator)),
+        );
+
+        let acceleration = parameters.steer(&boid, &neighbors, &threats);
+        boid.velocity = (boid.velocity + acceleration).clamp_length_max(parameters.max_speed);
+        let velocity = boid.velocity;
+        boid.position += velocity;
+        boundary.confine(&mut boid);
+
+        if let Some(mut grid_position) = grid_position
+        {
+            grid_position.set_if_neq(GridPosition(boid.position.round().as_ivec2()));
+        }
+
+        metrics.mean_neighbors += neighbors.len() as f64;
+        metrics.mean_speed += boid.velocity.length();
+        heading += boid.velocity.normalize_or_zero();
+    }
+
+    if metrics.boids > 0
+    {
+        let count = metrics.boids as f64;
+        metrics.mean_neighbors /= count;
+        metrics.mean_speed /= count;
+        metrics.polarization = heading.length() / count;
+    }
+    outputs.push(metrics.step, metrics);
+}
@@ -0,0 +1,510 @@
+//! Stocks of commodities held by entities, such as energy, money or goods, and the flows that
+//! move them between entities on every step.
+//!
+//! Every commodity is identified by a marker type `R`, and is set up with
+//! [`crate::SimulationBuilder::add_commodity`].
+//! Entities hold an amount of the commodity in a [`Stock<R>`] component, while a [`Flow<R>`]
+//! moves a given amount per step from one stock to another, into a stock from outside the system,
+//! or out of a stock to outside of it.
+//! Flows are components of entities of their own, so that they may be spawned, updated and
+//! despawned as the model runs, and the same agent may take part in any number of them.
+//!
+//! At the end of every step, after all user-defined systems have run, all flows are applied
+//! at once.
+//! A stock never goes negative: when the flows out of it ask for more than it holds, they are
+//! all scaled down in proportion.
+//! The amounts moved are recorded in the [`FlowOutputs<R>`] resource, while the
+//! [`CommodityLedger<R>`] resource holds the fluxes of the latest step, and checks that the
+//! commodity is conserved, i.e. that the total held in all stocks only changes through
+//! flows in and out of the system, or stocks being spawned and despawned.
+//! User-defined systems may also move amounts between stocks directly, with [`Stock::withdraw`]
+//! and [`Stock::deposit`], while any change that is not matched in another stock breaks
+//! conservation, and is reported with a [`ConservationViolation`] event.
+//!
+//! Example of the energy budget of plants and herbivores:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::flow::{Flow, FlowOutputs, Stock};
+//!
+//! struct Energy;
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_commodity::<Energy>()
+//!     .add_entity_spawner(|spawner| {
+//!         let plant = spawner.spawn(Stock::<Energy>::new(100.0)).entity();
+//!         let herbivore = spawner.spawn(Stock::<Energy>::new(10.0)).entity();
+//!
+//!         // sunlight, grazing and respiration
+//!         spawner.spawn(Flow::<Energy>::inflow(plant, 5.0));
+//!         spawner.spawn(Flow::<Energy>::between(plant, herbivore, 3.0));
+//!         spawner.spawn(Flow::<Energy>::outflow(herbivore, 1.0));
+//!     })
+//!     .build();
+//!
+//! simulation.run(100);
+//! let outputs = simulation.get_resource::<FlowOutputs<Energy>>().unwrap();
+//! let total = outputs.latest().unwrap().total;
+//! ```
+
+use std::{any::type_name, marker::PhantomData};
+
+use bevy::{ecs::entity::EntityHashMap, platform::collections::HashMap, prelude::*};
+
+use crate::{MetricsHistory, StepNumber, StrictnessPolicy};
+
+/// A commodity held in stocks, typically a unit struct used as a marker.
+///
+/// Automatically implemented for any type that is [`Send`] + [`Sync`] + `'static`.
+pub trait Commodity: Send + Sync + 'static {}
+
+impl<R> Commodity for R where R: Send + Sync + 'static {}
+
+/// Component holding the amount of the commodity `R` held by an entity.
+///
+/// The amount is changed by [`Flow<R>`]s, or directly by moving amounts between stocks,
+/// see the [module documentation](self).
+#[derive(Component)]
+pub struct Stock<R: Commodity>
+{
+    amount: f64,
+    _phantom: PhantomData<R>,
+}
+
+impl<R: Commodity> Stock<R>
+{
+    /// Creates a stock holding the given amount.
+    ///
+    /// # Panics
+    ///
+    /// If `amount` is negative or not finite.
+    #[must_use]
+    pub fn new(amount: f64) -> Self
+    {
+        assert!(
+            amount.is_finite() && amount >= 0.0,
+            "invalid stock amount: {amount}"
+        );
+
+        Self {
+            amount,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The amount held.
+    #[must_use]
+    pub const fn amount(&self) -> f64
+    {
+        self.amount
+    }
+
+    /// Adds the given amount to the stock.
+    ///
+    /// Unless matched by a [`Self::withdraw`] from another stock in the same step, this breaks
+    /// the conservation of the commodity.
+    ///
+    /// # Panics
+    ///
+    /// If `amount` is negative or not finite.
+    pub fn deposit(&mut self, amount: f64)
+    {
+        assert!(
+            amount.is_finite() && amount >= 0.0,
+            "invalid deposit amount: {amount}"
+        );
+        self.amount += amount;
+    }
+
+    /// Removes up to the given amount from the stock, returning the amount actually removed.
+    ///
+    /// Unless matched by a [`Self::deposit`] to another stock in the same step, this breaks
+    /// the conservation of the commodity.
+    ///
+    /// # Panics
+    ///
+    /// If `amount` is negative or not finite.
+    pub fn withdraw(&mut self, amount: f64) -> f64
+    {
+        assert!(
+            amount.is_finite() && amount >= 0.0,
+            "invalid withdrawal amount: {amount}"
+        );
+        let withdrawn = amount.min(self.amount);
+        self.amount -= withdrawn;
+        withdrawn
+    }
+}
+
+impl<R: Commodity> From<&Stock<R>> for f64
+{
+    fn from(stock: &Stock<R>) -> Self
+    {
+        stock.amount
+    }
+}
+
+/// Component of a flow moving an amount of the commodity `R` per step between two [`Stock<R>`]s,
+/// or between a stock and the outside of the system.
+///
+/// Flows from or to an entity without a stock of the commodity move nothing.
+#[derive(Component)]
+pub struct Flow<R: Commodity>
+{
+    from: Option<Entity>,
+    to: Option<Entity>,
+    rate: f64,
+    flux: f64,
+    _phantom: PhantomData<R>,
+}
+
+impl<R: Commodity> Flow<R>
+{
+    /// Creates a flow moving `rate` per step from the stock of the entity `from` to that of `to`.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is negative or not finite.
+    #[must_use]
+    pub fn between(from: Entity, to: Entity, rate: f64) -> Self
+    {
+        Self::new(Some(from), Some(to), rate)
+    }
+
+    /// Creates a flow adding `rate` per step to the stock of the entity `to`, from outside the system.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is negative or not finite.
+    #[must_use]
+    pub fn inflow(to: Entity, rate: f64) -> Self
+    {
+        Self::new(None, Some(to), rate)
+    }
+
+    /// Creates a flow removing `rate` per step from the stock of the entity `from`, to outside
+    /// the system.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is negative or not finite.
+    #[must_use]
+    pub fn outflow(from: Entity, rate: f64) -> Self
+    {
+        Self::new(Some(from), None, rate)
+    }
+
+    fn new(from: Option<Entity>, to: Option<Entity>, rate: f64) -> Self
+    {
+        let mut flow = Self {
+            from,
+            to,
+            rate: 0.0,
+            flux: 0.0,
+            _phantom: PhantomData,
+        };
+        flow.set_rate(rate);
+        flow
+    }
+
+    /// The entity whose stock the flow draws from, or `None` for flows into the system.
+    #[must_use]
+    pub const fn from(&self) -> Option<Entity>
+    {
+        self.from
+    }
+
+    /// The entity whose stock the flow adds to, or `None` for flows out of the system.
+    #[must_use]
+    pub const fn to(&self) -> Option<Entity>
+    {
+        self.to
+    }
+
+    /// The amount the flow asks to move per step.
+    #[must_use]
+    pub const fn rate(&self) -> f64
+    {
+        self.rate
+    }
+
+    /// Sets the amount the flow asks to move per step, from the next time flows are applied.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is negative or not finite.
+    pub fn set_rate(&mut self, rate: f64)
+    {
+        assert!(rate.is_finite() && rate >= 0.0, "invalid flow rate: {rate}");
+        self.rate = rate;
+    }
+
+    /// The amount actually moved by the flow in the latest step, which is less than its rate
+    /// when the stock it draws from ran short.
+    #[must_use]
+    pub const fn flux(&self) -> f64
+    {
+        self.flux
+    }
+}
+
+/// The amounts of a commodity moved in a step, and the total held at its end.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FluxRecord
+{
+    /// The number of the step, as read from [`StepNumber`].
+    pub step: usize,
+
+    /// The total amount held in all stocks at the end of the step.
+    pub total: f64,
+
+    /// The amount that flowed into stocks from outside the system.
+    pub inflow: f64,
+
+    /// The amount that flowed out of stocks to outside the system.
+    pub outflow: f64,
+
+    /// The amount that flowed between stocks.
+    pub transferred: f64,
+
+    /// The amount held by stocks that were spawned during the step.
+    pub created: f64,
+
+    /// The amount held by stocks that were despawned during the step.
+    pub destroyed: f64,
+
+    /// The change in the total that is not accounted for by any of the above, which is `0`
+    /// as long as the commodity is conserved.
+    pub imbalance: f64,
+}
+
+/// Resource holding the [`FluxRecord`] of every step for the commodity `R`.
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<FlowOutputs<R>>`] arguments.
+pub type FlowOutputs<R> = MetricsHistory<FluxRecord, R>;
+
+/// Resource holding the fluxes between every pair of stocks in the latest step for the
+/// commodity `R`.
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<CommodityLedger<R>>`] arguments.
+#[derive(Resource)]
+pub struct CommodityLedger<R: Commodity>
+{
+    fluxes: HashMap<(Option<Entity>, Option<Entity>), f64>,
+    pending: FluxRecord,
+    expected_total: f64,
+    _phantom: PhantomData<R>,
+}
+
+impl<R: Commodity> Default for CommodityLedger<R>
+{
+    fn default() -> Self
+    {
+        Self {
+            fluxes: HashMap::default(),
+            pending: FluxRecord::default(),
+            expected_total: 0.0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<R: Commodity> CommodityLedger<R>
+{
+    /// The total amount moved from the stock of `from` to that of `to` in the latest step,
+    /// where `None` stands for the outside of the system.
+    #[must_use]
+    pub fn flux_between(&self, from: Option<Entity>, to: Option<Entity>) -> f64
+    {
+        self.fluxes.get(&(from, to)).copied().unwrap_or_default()
+    }
+
+    /// Iterates over the total amount moved between every pair of stocks in the latest step,
+    /// as in an input-output table, where `None` stands for the outside of the system.
+    pub fn fluxes(&self) -> impl Iterator<Item = (Option<Entity>, Option<Entity>, f64)>
+    {
+        self.fluxes
+            .iter()
+            .map(|(&(from, to), &flux)| (from, to, flux))
+    }
+}
+
+/// Event sent when the total amount of a commodity held in all stocks changed by more than
+/// the flows, spawns and despawns of the step account for.
+///
+/// This is caused by changing a [`Stock`] outside of [`Flow`]s.
+/// To panic instead, set the [`crate::StrictnessPolicy`] of the simulation to strict.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ConservationViolation
+{
+    /// The [`std::any::type_name`] of the commodity.
+    pub commodity: &'static str,
+
+    /// The step in which the violation was found.
+    pub step: usize,
+
+    /// The total amount that should have been held in all stocks.
+    pub expected: f64,
+
+    /// The total amount actually held in all stocks.
+    pub actual: f64,
+}
+
+/// Plugin that applies the flows of the commodity `R` on every step.
+pub(crate) struct CommodityPlugin<R>(PhantomData<R>);
+
+impl<R> Default for CommodityPlugin<R>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+impl<R: Commodity> Plugin for CommodityPlugin<R>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<CommodityLedger<R>>()
+            .init_resource::<FlowOutputs<R>>()
+            .add_event::<ConservationViolation>()
+            .add_observer(stock_spawned::<R>)
+            .add_observer(stock_despawned::<R>)
+            .add_systems(PostUpdate, flow_system::<R>);
+    }
+}
+
+fn stock_spawned<R: Commodity>(
+    trigger: Trigger<OnAdd, Stock<R>>,
+    stocks: Query<&Stock<R>>,
+    mut ledger: ResMut<CommodityLedger<R>>,
+)
+{
+    if let Ok(stock) = stocks.get(trigger.target())
+    {
+        ledger.pending.created += stock.amount;
+        ledger.expected_total += stock.amount;
+    }
+}
+
+fn stock_despawned<R: Commodity>(
+    trigger: Trigger<OnRemove, Stock<R>>,
+    stocks: Query<&Stock<R>>,
+    mut ledger: ResMut<CommodityLedger<R>>,
+)
+{
+    if let Ok(stock) = stocks.get(trigger.target())
+    {
+        ledger.pending.destroyed += stock.amount;
+        ledger.expected_total -= stock.amount;
+    }
+}
+
+/// System that checks the conservation of the commodity since the previous step, applies
+/// all flows, and records the amounts they moved.
+fn flow_system<R: Commodity>(
+    mut ledger: ResMut<CommodityLedger<R>>,
+    mut outputs: ResMut<FlowOutputs<R>>,
+    mut stocks: Query<(Entity, &mut Stock<R>)>,
+    mut flows: Query<&mut Flow<R>>,
+    mut violations: EventWriter<ConservationViolation>,
+    strictness: Res<StrictnessPolicy>,
+    step_number: Res<StepNumber>,
+)
+{
+    let ledger = &mut *ledger;
+    let step = step_number.get();
+
+    let mut amounts: EntityHashMap<f64> = stocks
+        .iter()
+        .map(|(entity, stock)| (entity, stock.amount))
+        .collect();
+
+    let total: f64 = amounts.values().sum();
+    let expected = ledger.expected_total;
+    if (total - expected).abs() > 1e-9 * expected.abs().max(1.0)
+    {
+        assert!(
+            !strictness.is_strict(),
+            "{} not conserved in step {step}: expected {expected}, found {total}",
+            type_name::<R>()
+        );
+
+        ledger.pending.imbalance += total - expected;
+        violations.write(ConservationViolation {
+            commodity: type_name::<R>(),
+            step,
+            expected,
+            actual: total,
+        });
+    }
+
+    let is_valid = |flow: &Flow<R>| {
+        flow.rate > 0.0
+            && flow.from.is_none_or(|from| amounts.contains_key(&from))
+            && flow.to.is_none_or(|to| amounts.contains_key(&to))
+    };
+
+    // the total that every stock is asked for, to scale down the flows out of those running short
+    let mut demands: EntityHashMap<f64> = EntityHashMap::default();
+    for flow in flows.iter().filter(|flow| is_valid(flow))
+    {
+        if let Some(from) = flow.from
+        {
+            *demands.entry(from).or_default() += flow.rate;
+        }
+    }
+
+    ledger.fluxes.clear();
+    let record = &mut ledger.pending;
+    for mut flow in &mut flows
+    {
+        let flux = match flow.from
+        {
+            _ if !is_valid(&flow) => 0.0,
+            Some(from) => flow.rate * (amounts[&from] / demands[&from]).min(1.0),
+            None => flow.rate,
+        };
+        flow.flux = flux;
+
+        if flux > 0.0
+        {
+            *ledger.fluxes.entry((flow.from, flow.to)).or_default() += flux;
+            match (flow.from, flow.to)
+            {
+                (Some(_), Some(_)) => record.transferred += flux,
+                (None, Some(_)) => record.inflow += flux,
+                (Some(_), None) => record.outflow += flux,
+                (None, None) =>
+                {}
+            }
+        }
+    }
+
+    // the flows out of every stock add up to at most its amount at the start,
+    // so the order in which they are applied does not matter
+    for flow in &flows
+    {
+        if let Some(from) = flow.from
+            && let Some(amount) = amounts.get_mut(&from)
+        {
+            *amount = (*amount - flow.flux).max(0.0);
+        }
+        if let Some(to) = flow.to
+            && let Some(amount) = amounts.get_mut(&to)
+        {
+            *amount += flow.flux;
+        }
+    }
+
+    for (entity, mut stock) in &mut stocks
+    {
+        stock.amount = amounts[&entity];
+    }
+
+    record.step = step;
+    record.total = amounts.values().sum();
+    ledger.expected_total = record.total;
+    outputs.push(step, std::mem::take(record));
+}
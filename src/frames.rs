@@ -0,0 +1,401 @@
+#[cfg(feature = "image")]
+use std::time::Duration;
+use std::{any::type_name, fs, path::Path};
+
+use bevy::prelude::*;
+#[cfg(feature = "image")]
+use image::{
+    Delay, Frame, Rgba, RgbaImage,
+    codecs::gif::{GifEncoder, Repeat},
+    imageops::{self, FilterType},
+};
+
+use crate::{
+    SamplingError, Simulation, SimulationError,
+    error::ExportError,
+    plugins::{DeferredFlush, GridBounds2D, GridPosition2D, StepNumber},
+};
+
+/// The stops of the color map that values are rasterized with, from lowest to highest.
+#[cfg(feature = "image")]
+const COLOR_MAP: [[u8; 3]; 5] = [
+    [68, 1, 84],
+    [59, 82, 139],
+    [33, 145, 140],
+    [94, 201, 98],
+    [253, 231, 37],
+];
+
+/// The color of cells whose statistic is not a finite number.
+#[cfg(feature = "image")]
+const MISSING: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+type Statistic<C> = Box<dyn Fn(&[&C]) -> f64 + Send + Sync>;
+
+/// Resource holding the frames rasterized from the grid of components `C`.
+///
+/// Each frame holds the statistic of every cell within the bounds, in rows from the top
+/// of the grid to the bottom, so that it can be turned into an image as is.
+#[derive(Resource)]
+pub struct GridFrames<C: Component>
+{
+    bounds: GridBounds2D,
+    interval: usize,
+    statistic: Statistic<C>,
+    steps: Vec<usize>,
+    frames: Vec<Vec<f64>>,
+}
+
+impl<C: Component> GridFrames<C>
+{
+    pub fn new(
+        bounds: GridBounds2D,
+        interval: usize,
+        statistic: impl Fn(&[&C]) -> f64 + Send + Sync + 'static,
+    ) -> Self
+    {
+        Self {
+            bounds,
+            interval,
+            statistic: Box::new(statistic),
+            steps: Vec::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    const fn size(&self) -> (u32, u32)
+    {
+        let size = IVec2::new(
+            self.bounds.max.x - self.bounds.min.x + 1,
+            self.bounds.max.y - self.bounds.min.y + 1,
+        );
+        (size.x as u32, size.y as u32)
+    }
+
+    /// The index of a cell in a frame, or `None` if it is outside the bounds.
+    #[allow(clippy::cast_sign_loss)]
+    fn index_of(&self, position: IVec2) -> Option<usize>
+    {
+        if !self.bounds.contains(&position)
+        {
+            return None;
+        }
+
+        let (width, _) = self.size();
+        let x = (position.x - self.bounds.min.x) as usize;
+        let y = (self.bounds.max.y - position.y) as usize;
+        Some(y * width as usize + x)
+    }
+
+    /// Adds the resource to the simulation, along with the system recording its frames.
+    pub fn add(self, app: &mut App)
+    {
+        app.insert_resource(self);
+        app.add_systems(PostUpdate, Self::record.after(DeferredFlush));
+    }
+
+    fn record(
+        mut frames: ResMut<Self>,
+        query: Query<(&GridPosition2D, &C)>,
+        step_number: Res<StepNumber>,
+    )
+    {
+        if !step_number.is_multiple_of(frames.interval)
+        {
+            return;
+        }
+
+        let (width, height) = frames.size();
+        let mut cells = vec![Vec::new(); width as usize * height as usize];
+        for (position, component) in &query
+        {
+            if let Some(index) = frames.index_of(position.0)
+            {
+                cells[index].push(component);
+            }
+        }
+
+        let frame = cells
+            .iter()
+            .map(|components| (frames.statistic)(components))
+            .collect();
+        frames.steps.push(step_number.get());
+        frames.frames.push(frame);
+    }
+
+    /// Rasterizes all frames recorded so far, scaling each cell to `cell_size` pixels.
+    ///
+    /// The values are mapped to colors on a common scale across all frames,
+    /// so that the images of an animation are comparable to each other.
+    #[cfg(feature = "image")]
+    fn images(&self, cell_size: u32) -> impl Iterator<Item = RgbaImage> + '_
+    {
+        let (min, max) = self
+            .frames
+            .iter()
+            .flatten()
+            .filter(|value| value.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+        let (width, height) = self.size();
+
+        self.frames.iter().map(move |frame| {
+            let image = RgbaImage::from_fn(width, height, |x, y| {
+                let value = frame[(y * width + x) as usize];
+                if !value.is_finite()
+                {
+                    return MISSING;
+                }
+
+                let t = if max > min
+                {
+                    (value - min) / (max - min)
+                }
+                else
+                {
+                    0.5
+                };
+                color_map(t)
+            });
+
+            imageops::resize(
+                &image,
+                width * cell_size,
+                height * cell_size,
+                FilterType::Nearest,
+            )
+        })
+    }
+}
+
+#[cfg(feature = "netcdf")]
+impl<C: Component> GridFrames<C>
+{
+    /// Builds a dataset holding all frames recorded so far as the given `variable`,
+    /// over the dimensions `(time, x, y)`.
+    fn dataset(&self, variable: &str) -> crate::netcdf::Dataset
+    {
+        use crate::netcdf::{Dataset, Values, Variable};
+
+        let (width, height) = self.size();
+        let (width, height) = (width as usize, height as usize);
+
+        // frames hold their rows from the top of the grid, while `y` ascends
+        let values = self
+            .frames
+            .iter()
+            .flat_map(|frame| {
+                (0..width).flat_map(move |x| {
+                    (0..height).map(move |y| frame[(height - 1 - y) * width + x])
+                })
+            })
+            .collect();
+
+        #[allow(clippy::cast_precision_loss)]
+        let steps = self.steps.iter().map(|&step| step as f64).collect();
+
+        Dataset {
+            dimensions: vec![
+                (String::from("time"), self.frames.len()),
+                (String::from("x"), width),
+                (String::from("y"), height),
+            ],
+            variables: vec![
+                Variable {
+                    name: String::from("time"),
+                    dimensions: vec![0],
+                    attributes: vec![("long_name", String::from("simulation step"))],
+                    values: Values::Double(steps),
+                },
+                Variable {
+                    name: String::from("x"),
+                    dimensions: vec![1],
+                    attributes: vec![("long_name", String::from("grid column"))],
+                    values: Values::Int((self.bounds.min.x..=self.bounds.max.x).collect()),
+                },
+                Variable {
+                    name: String::from("y"),
+                    dimensions: vec![2],
+                    attributes: vec![("long_name", String::from("grid row"))],
+                    values: Values::Int((self.bounds.min.y..=self.bounds.max.y).collect()),
+                },
+                Variable {
+                    name: variable.to_string(),
+                    dimensions: vec![0, 1, 2],
+                    attributes: vec![("source", type_name::<C>().to_string())],
+                    values: Values::Double(values),
+                },
+            ],
+        }
+    }
+}
+
+/// Maps a value in `[0, 1]` to a color, by interpolating between the stops of [`COLOR_MAP`].
+#[cfg(feature = "image")]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn color_map(t: f64) -> Rgba<u8>
+{
+    let position = t.clamp(0.0, 1.0) * (COLOR_MAP.len() - 1) as f64;
+    let index = (position.floor() as usize).min(COLOR_MAP.len() - 2);
+    let fraction = position - index as f64;
+
+    let [from, to] = [COLOR_MAP[index], COLOR_MAP[index + 1]];
+    let channel = |i: usize| {
+        f64::from(to[i])
+            .mul_add(fraction, f64::from(from[i]) * (1.0 - fraction))
+            .round() as u8
+    };
+    Rgba([channel(0), channel(1), channel(2), 255])
+}
+
+impl Simulation
+{
+    fn grid_frames<C: Component>(&self) -> Result<&GridFrames<C>, SamplingError>
+    {
+        self.app
+            .world()
+            .get_resource::<GridFrames<C>>()
+            .ok_or_else(|| SamplingError::FramesNotRecorded {
+                component: type_name::<C>(),
+            })
+    }
+
+    /// Writes the frames recorded from the grid of components `C` as numbered PNG images
+    /// in the given directory, named `frame_00000.png`, `frame_00001.png` and so on.
+    ///
+    /// The directory is created if it does not exist, and each cell of the grid is scaled to
+    /// `cell_size` pixels. See [`crate::SimulationBuilder::record_grid_frames`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::FramesNotRecorded`]
+    /// - [`ExportError::Io`]
+    /// - [`ExportError::Image`]
+    #[cfg(feature = "image")]
+    pub fn write_frames_png<C: Component>(
+        &self,
+        dir: impl AsRef<Path>,
+        cell_size: u32,
+    ) -> Result<(), SimulationError>
+    {
+        let frames = self.grid_frames::<C>()?;
+        let dir = dir.as_ref();
+
+        fs::create_dir_all(dir).map_err(|_| ExportError::Io)?;
+        for (index, image) in frames.images(cell_size).enumerate()
+        {
+            image
+                .save(dir.join(format!("frame_{index:05}.png")))
+                .map_err(|_| ExportError::Image)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the frames recorded from the grid of components `C` as a looping animated GIF
+    /// at the given `path`, replacing it if it exists.
+    ///
+    /// Each cell of the grid is scaled to `cell_size` pixels, and every frame is shown for
+    /// `frame_delay`. See [`crate::SimulationBuilder::record_grid_frames`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::FramesNotRecorded`]
+    /// - [`ExportError::Io`]
+    /// - [`ExportError::Image`]
+    #[cfg(feature = "image")]
+    pub fn write_frames_gif<C: Component>(
+        &self,
+        path: impl AsRef<Path>,
+        cell_size: u32,
+        frame_delay: Duration,
+    ) -> Result<(), SimulationError>
+    {
+        let frames = self.grid_frames::<C>()?;
+        let file = fs::File::create(path).map_err(|_| ExportError::Io)?;
+        let delay = Delay::from_saturating_duration(frame_delay);
+
+        let mut encoder = GifEncoder::new(file);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|_| ExportError::Image)?;
+        encoder
+            .encode_frames(
+                frames
+                    .images(cell_size)
+                    .map(|image| Frame::from_parts(image, 0, 0, delay)),
+            )
+            .map_err(|_| ExportError::Image)?;
+
+        Ok(())
+    }
+
+    /// Writes the frames recorded from the grid of components `C` as a netCDF dataset
+    /// at the given `path`, replacing it if it exists.
+    ///
+    /// The statistics of the cells are stored in a variable with the given name, over the
+    /// dimensions `(time, x, y)`, along with the coordinate variables `time`, holding the step
+    /// at which each frame was recorded, and `x` and `y`, holding the positions of the cells.
+    /// The dataset is written in the classic netCDF format, which is read by all netCDF and
+    /// HDF5-aware tools such as `xarray`. See [`crate::SimulationBuilder::record_grid_frames`]
+    /// for details on the recording of the frames.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Heat(f64);
+    ///
+    /// let bounds = GridBounds2D {
+    ///     min: IVec2::new(0, 0),
+    ///     max: IVec2::new(99, 99),
+    /// };
+    /// let mut simulation = SimulationBuilder::new()
+    ///     .record_grid_frames::<Heat>(bounds, 10, |cells| cells.iter().map(|heat| heat.0).sum())
+    ///     .build();
+    /// simulation.run(1000);
+    ///
+    /// simulation
+    ///     .write_frames_netcdf::<Heat>("heat.nc", "temperature")
+    ///     .expect("failed to write dataset");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::FramesNotRecorded`]
+    /// - [`ExportError::Io`]
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `variable` is not a valid netCDF name, which must start with a letter or an
+    ///   underscore, and otherwise consist of alphanumeric characters and `_-.+@`.
+    /// - The `variable` is named `time`, `x` or `y`, which are reserved for the coordinates.
+    #[cfg(feature = "netcdf")]
+    pub fn write_frames_netcdf<C: Component>(
+        &self,
+        path: impl AsRef<Path>,
+        variable: &str,
+    ) -> Result<(), SimulationError>
+    {
+        assert!(crate::netcdf::is_valid_name(variable));
+        assert!(!matches!(variable, "time" | "x" | "y"));
+
+        let frames = self.grid_frames::<C>()?;
+        let file = fs::File::create(path).map_err(|_| ExportError::Io)?;
+
+        frames
+            .dataset(variable)
+            .write(std::io::BufWriter::new(file))
+            .map_err(|_| ExportError::Io)?;
+
+        Ok(())
+    }
+}
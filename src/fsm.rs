@@ -0,0 +1,340 @@
+//! Agent behavior defined declaratively as a finite-state machine, with the state of every agent
+//! held in an [`Fsm`] component and moving between states according to a table of [`Transitions`].
+//!
+//! The table is added to a simulation with [`crate::SimulationBuilder::add_state_machine`], and
+//! is evaluated for every agent once per step, before any user-defined systems run.
+//! Transitions may occur with a fixed probability per step, after spending a number of steps in
+//! a state, or whenever a condition on the agent holds.
+//! The number of agents in every state is counted at the end of every step, in the
+//! [`StateCounts`] resource.
+//!
+//! Example of agents catching a disease and recovering from it:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::fsm::{Fsm, StateCounts, Transitions};
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+//! enum Health
+//! {
+//!     Healthy,
+//!     Exposed,
+//!     Infectious,
+//!     Recovered,
+//! }
+//!
+//! #[derive(Component)]
+//! struct Vaccinated;
+//!
+//! let transitions = Transitions::new()
+//!     .with_probability(Health::Healthy, Health::Exposed, 0.01)
+//!     .after_steps(Health::Exposed, Health::Infectious, 3)
+//!     .with_probability(Health::Infectious, Health::Recovered, 0.1)
+//!     .when(Health::Infectious, Health::Recovered, |agent| agent.contains::<Vaccinated>());
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_state_machine(transitions)
+//!     .add_entity_spawner(|spawner| {
+//!         for _ in 0..1000
+//!         {
+//!             spawner.spawn(Fsm::new(Health::Healthy));
+//!         }
+//!     })
+//!     .build();
+//!
+//! simulation.run(100);
+//! let counts = simulation.get_resource::<StateCounts<Health>>().unwrap();
+//! let recovered = counts.get(&Health::Recovered);
+//! ```
+
+use std::{fmt::Debug, hash::Hash, marker::PhantomData};
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use rand::Rng;
+
+use crate::SimRng;
+
+/// The states of an [`Fsm`], typically an enum.
+///
+/// Automatically implemented for any type that is [`Clone`] + [`Eq`] + [`Hash`] + [`Debug`].
+pub trait FsmState: Clone + Eq + Hash + Debug + Send + Sync + 'static {}
+
+impl<S> FsmState for S where S: Clone + Eq + Hash + Debug + Send + Sync + 'static {}
+
+/// Condition on an agent, which guards a transition out of its state.
+type Guard = Box<dyn Fn(EntityRef) -> bool + Send + Sync>;
+
+/// Component holding the state of an agent's finite-state machine, see the
+/// [module documentation](self).
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct Fsm<S: FsmState>
+{
+    state: S,
+    steps_in_state: usize,
+}
+
+impl<S: FsmState> Fsm<S>
+{
+    /// Creates a state machine in the given initial state.
+    #[must_use]
+    pub const fn new(initial: S) -> Self
+    {
+        Self {
+            state: initial,
+            steps_in_state: 0,
+        }
+    }
+
+    /// The current state.
+    #[must_use]
+    pub const fn state(&self) -> &S
+    {
+        &self.state
+    }
+
+    /// The number of steps the agent has completed in its current state, including the step
+    /// in which it entered it.
+    #[must_use]
+    pub const fn steps_in_state(&self) -> usize
+    {
+        self.steps_in_state
+    }
+
+    /// Moves the agent to the given state, as if a transition had occurred.
+    ///
+    /// This can be used by user-defined systems for transitions caused by other agents,
+    /// such as infections.
+    pub fn set_state(&mut self, state: S)
+    {
+        self.state = state;
+        self.steps_in_state = 0;
+    }
+}
+
+/// What causes a transition to occur.
+enum Trigger
+{
+    Probability(f64),
+    AfterSteps(usize),
+    Guard(Guard),
+}
+
+struct Transition<S>
+{
+    to: S,
+    trigger: Trigger,
+}
+
+/// The table of transitions between the states `S` of a finite-state machine.
+///
+/// The transitions out of every state are evaluated in the order they were added, and the first
+/// one to occur is taken, so that each agent moves at most once per step.
+/// Probabilistic transitions out of the same state are mutually exclusive outcomes of a single
+/// random draw per step, so their probabilities may add up to at most `1`.
+#[derive(Resource)]
+pub struct Transitions<S: FsmState>
+{
+    table: HashMap<S, Vec<Transition<S>>>,
+}
+
+impl<S: FsmState> Default for Transitions<S>
+{
+    fn default() -> Self
+    {
+        Self {
+            table: HashMap::default(),
+        }
+    }
+}
+
+impl<S: FsmState> Transitions<S>
+{
+    /// Creates an empty table, in which every state is final.
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Adds a transition occurring with the given `probability` on every step.
+    ///
+    /// # Panics
+    ///
+    /// If `probability` is not in the range `[0, 1]`, or the probabilities of all transitions out
+    /// of the state `from` add up to more than `1`.
+    #[must_use]
+    pub fn with_probability(self, from: S, to: S, probability: f64) -> Self
+    {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "invalid transition probability: {probability}"
+        );
+
+        let transitions = self.add(from.clone(), to, Trigger::Probability(probability));
+        let total: f64 = transitions.table[&from]
+            .iter()
+            .filter_map(|transition| match transition.trigger
+            {
+                Trigger::Probability(probability) => Some(probability),
+                _ => None,
+            })
+            .sum();
+        assert!(
+            total <= 1.0 + f64::EPSILON,
+            "transition probabilities out of {from:?} add up to {total}"
+        );
+
+        transitions
+    }
+
+    /// Adds a transition occurring once the agent has spent the given number of steps
+    /// in the state `from`.
+    #[must_use]
+    pub fn after_steps(self, from: S, to: S, steps: usize) -> Self
+    {
+        self.add(from, to, Trigger::AfterSteps(steps))
+    }
+
+    /// Adds a transition occurring whenever the `condition` holds for the agent, which is
+    /// given access to all of its components.
+    #[must_use]
+    pub fn when(
+        self,
+        from: S,
+        to: S,
+        condition: impl Fn(EntityRef) -> bool + Send + Sync + 'static,
+    ) -> Self
+    {
+        self.add(from, to, Trigger::Guard(Box::new(condition)))
+    }
+
+    fn add(mut self, from: S, to: S, trigger: Trigger) -> Self
+    {
+        self.table
+            .entry(from)
+            .or_default()
+            .push(Transition { to, trigger });
+        self
+    }
+
+    /// Evaluates the transitions out of the agent's state, returning the state to move to, if any.
+    fn next(&self, fsm: &Fsm<S>, agent: EntityRef, rng: &mut impl Rng) -> Option<S>
+    {
+        let transitions = self.table.get(&fsm.state)?;
+
+        // drawn only when the first probabilistic transition is reached
+        let mut draw = None;
+        let mut cumulative = 0.0;
+
+        transitions
+            .iter()
+            .find(|transition| match &transition.trigger
+            {
+                Trigger::Probability(probability) =>
+                {
+                    cumulative += probability;
+                    *draw.get_or_insert_with(|| rng.random::<f64>()) < cumulative
+                }
+                Trigger::AfterSteps(steps) => fsm.steps_in_state >= *steps,
+                Trigger::Guard(condition) => condition(agent),
+            })
+            .map(|transition| transition.to.clone())
+    }
+}
+
+/// Resource holding the number of agents in every state `S`, as of the end of the latest step.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct StateCounts<S: FsmState>(HashMap<S, usize>);
+
+impl<S: FsmState> Default for StateCounts<S>
+{
+    fn default() -> Self
+    {
+        Self(HashMap::default())
+    }
+}
+
+impl<S: FsmState> StateCounts<S>
+{
+    /// The number of agents in the given state.
+    #[must_use]
+    pub fn get(&self, state: &S) -> usize
+    {
+        self.0.get(state).copied().unwrap_or_default()
+    }
+
+    /// The total number of agents.
+    #[must_use]
+    pub fn total(&self) -> usize
+    {
+        self.0.values().sum()
+    }
+
+    /// Iterates over every state with at least one agent in it, and the number of agents in it.
+    pub fn iter(&self) -> impl Iterator<Item = (&S, usize)>
+    {
+        self.0.iter().map(|(state, &count)| (state, count))
+    }
+}
+
+/// Plugin that evaluates the transitions of all state machines in the states `S` on every step.
+pub(crate) struct FsmPlugin<S: FsmState>(PhantomData<S>);
+
+impl<S: FsmState> Default for FsmPlugin<S>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+impl<S: FsmState> Plugin for FsmPlugin<S>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<Transitions<S>>()
+            .init_resource::<StateCounts<S>>()
+            .add_systems(PreUpdate, fsm_transition_system::<S>)
+            .add_systems(PostUpdate, fsm_step_end_system::<S>);
+    }
+}
+
+/// System that moves every agent along the first transition out of its state to occur.
+///
+/// All agents are evaluated on their state at the start of the step, before any of them moves.
+fn fsm_transition_system<S: FsmState>(world: &mut World, query: &mut QueryState<(Entity, &Fsm<S>)>)
+{
+    let moves: Vec<(Entity, S)> = world.resource_scope(|world, mut rng: Mut<SimRng>| {
+        let transitions = world.resource::<Transitions<S>>();
+
+        query
+            .iter(world)
+            .filter_map(|(entity, fsm)| {
+                transitions
+                    .next(fsm, world.entity(entity), &mut *rng)
+                    .map(|next| (entity, next))
+            })
+            .collect()
+    });
+
+    for (entity, next) in moves
+    {
+        if let Some(mut fsm) = world.get_mut::<Fsm<S>>(entity)
+        {
+            fsm.set_state(next);
+        }
+    }
+}
+
+/// System that counts the agents in every state, and the step they completed in it.
+fn fsm_step_end_system<S: FsmState>(
+    mut counts: ResMut<StateCounts<S>>,
+    mut query: Query<&mut Fsm<S>>,
+)
+{
+    counts.0.clear();
+    for mut fsm in &mut query
+    {
+        fsm.steps_in_state += 1;
+        *counts.0.entry(fsm.state.clone()).or_default() += 1;
+    }
+}
@@ -0,0 +1,371 @@
+//! Loaders of geographic data into [`GridLayer`] values, for seeding the spatial initial conditions
+//! of a simulation with real terrain, land cover or population density.
+//!
+//! Every cell of a grid is mapped to a square area of geographic coordinates through a
+//! [`GeoTransform`]. Rasters in the Esri ASCII grid format carry their own transform, which can
+//! then be reused to rasterize `GeoJSON` polygons onto the same grid, with the `serde` feature
+//! enabled.
+//!
+//! Example:
+//! ```no_run
+//! # use incerto::prelude::*;
+//! #[derive(Component)]
+//! struct Person;
+//!
+//! let (density, _) = geo::read_ascii_grid("density.asc").expect("failed to read raster");
+//! let simulation = SimulationBuilder::new()
+//!     .add_spatial_grid_2d::<Person>(Some(density.bounds()))
+//!     .add_seeded_entity_spawner(move |spawner, rng| {
+//!         for position in placement::weighted(&density, 10_000, rng)
+//!         {
+//!             spawner.spawn((position, Person));
+//!         }
+//!     })
+//!     .build();
+//! ```
+
+use std::{fs, path::Path};
+
+use bevy::{math::DVec2, prelude::*};
+#[cfg(feature = "serde")]
+use serde_json::Value;
+
+use crate::{
+    DatasetError, GridLayer,
+    plugins::{GridBounds2D, GridPosition, GridPosition2D},
+};
+
+/// The mapping between the cells of a 2D grid and geographic coordinates.
+///
+/// The cell at position `(0, 0)` covers the square whose lower-left corner is at [`Self::origin`],
+/// and every cell is [`Self::cell_size`] wide along both axes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoTransform
+{
+    /// The geographic coordinates of the lower-left corner of the cell at `(0, 0)`.
+    pub origin: DVec2,
+
+    /// The width and height of every cell, in geographic units.
+    pub cell_size: f64,
+}
+
+impl GeoTransform
+{
+    /// The geographic coordinates of the center of the cell at the given `position`.
+    #[must_use]
+    pub fn cell_center(&self, position: &GridPosition2D) -> DVec2
+    {
+        self.origin + (position.0.as_dvec2() + 0.5) * self.cell_size
+    }
+
+    /// The position of the cell containing the given geographic `coordinates`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn cell_at(&self, coordinates: DVec2) -> GridPosition2D
+    {
+        let cell = ((coordinates - self.origin) / self.cell_size).floor();
+        GridPosition2D::new(cell.x as i32, cell.y as i32)
+    }
+}
+
+/// Reads a raster in the Esri ASCII grid format into a layer, along with its geographic transform.
+///
+/// The layer spans from `(0, 0)` at the lower-left corner of the raster to
+/// `(ncols - 1, nrows - 1)` at its upper-right corner.
+/// Cells holding the `NODATA_value` of the raster are set to `NaN`.
+///
+/// # Errors
+///
+/// - [`DatasetError::Io`]
+/// - [`DatasetError::InvalidRecord`]
+pub fn read_ascii_grid(path: impl AsRef<Path>) -> Result<(GridLayer, GeoTransform), DatasetError>
+{
+    let contents = fs::read_to_string(path).map_err(|_| DatasetError::Io)?;
+    parse_ascii_grid(&contents)
+}
+
+/// Parses a raster in the Esri ASCII grid format, see [`read_ascii_grid`].
+///
+/// # Errors
+///
+/// - [`DatasetError::InvalidRecord`]
+pub fn parse_ascii_grid(contents: &str) -> Result<(GridLayer, GeoTransform), DatasetError>
+{
+    let mut lines = contents
+        .lines()
+        .zip(1..)
+        .filter(|(line, _)| !line.trim().is_empty());
+
+    let mut ncols = None;
+    let mut nrows = None;
+    let mut corner = (None, None);
+    let mut center = (None, None);
+    let mut cell_size = None;
+    let mut no_data = None;
+
+    let mut first_row = None;
+    for (line, number) in lines.by_ref()
+    {
+        let invalid = || DatasetError::InvalidRecord { line: Some(number) };
+        let mut fields = line.split_whitespace();
+        let key = fields.next().unwrap_or_default();
+        if key.parse::<f64>().is_ok()
+        {
+            // the header has ended, and this is the first row of values
+            first_row = Some((line, number));
+            break;
+        }
+        let value = fields
+            .next()
+            .and_then(|value| value.parse::<f64>().ok())
+            .ok_or_else(invalid)?;
+
+        match key.to_ascii_lowercase().as_str()
+        {
+            "ncols" => ncols = Some(value),
+            "nrows" => nrows = Some(value),
+            "xllcorner" => corner.0 = Some(value),
+            "yllcorner" => corner.1 = Some(value),
+            "xllcenter" => center.0 = Some(value),
+            "yllcenter" => center.1 = Some(value),
+            "cellsize" => cell_size = Some(value),
+            "nodata_value" => no_data = Some(value),
+            _ => return Err(invalid()),
+        }
+    }
+
+    let invalid_header = DatasetError::InvalidRecord { line: None };
+    let (Some(ncols), Some(nrows), Some(cell_size)) = (ncols, nrows, cell_size)
+    else
+    {
+        return Err(invalid_header);
+    };
+    let origin = match (corner, center)
+    {
+        ((Some(x), Some(y)), _) => DVec2::new(x, y),
+        (_, (Some(x), Some(y))) => DVec2::new(x, y) - cell_size / 2.0,
+        _ => return Err(invalid_header),
+    };
+    if ncols < 1.0 || nrows < 1.0 || ncols.fract() != 0.0 || nrows.fract() != 0.0
+    {
+        return Err(invalid_header);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    let bounds = GridBounds2D {
+        min: IVec2::ZERO,
+        max: IVec2::new(ncols as i32 - 1, nrows as i32 - 1),
+    };
+    let mut layer = GridLayer::new(bounds, f64::NAN);
+
+    // rows are listed from the top of the raster to the bottom
+    let mut position = IVec2::new(0, bounds.max.y);
+    for (line, number) in first_row.into_iter().chain(lines)
+    {
+        for value in line.split_whitespace()
+        {
+            let value = value
+                .parse::<f64>()
+                .map_err(|_| DatasetError::InvalidRecord { line: Some(number) })?;
+            let value = if Some(value) == no_data
+            {
+                f64::NAN
+            }
+            else
+            {
+                value
+            };
+
+            layer
+                .set(&GridPosition(position), value)
+                .map_err(|_| DatasetError::InvalidRecord { line: Some(number) })?;
+
+            position.x += 1;
+            if position.x > bounds.max.x
+            {
+                position = IVec2::new(0, position.y - 1);
+            }
+        }
+    }
+    if position.y >= 0
+    {
+        // the raster has fewer values than its header declares
+        return Err(DatasetError::InvalidRecord { line: None });
+    }
+
+    Ok((layer, GeoTransform { origin, cell_size }))
+}
+
+/// Reads the polygons of a `GeoJSON` file and rasterizes them onto a layer over the given `bounds`.
+///
+/// Every cell whose center, as mapped by the `transform`, lies within a `Polygon` or
+/// `MultiPolygon` feature is set to the numeric value of the given `property` of that feature.
+/// Where features overlap, the one listed last takes precedence.
+/// All other cells, along with those of features without a numeric `property`, are set to `NaN`.
+///
+/// # Errors
+///
+/// - [`DatasetError::Io`]
+/// - [`DatasetError::InvalidRecord`]
+#[cfg(feature = "serde")]
+pub fn read_geojson(
+    path: impl AsRef<Path>,
+    property: &str,
+    transform: &GeoTransform,
+    bounds: GridBounds2D,
+) -> Result<GridLayer, DatasetError>
+{
+    let contents = fs::read_to_string(path).map_err(|_| DatasetError::Io)?;
+    parse_geojson(&contents, property, transform, bounds)
+}
+
+/// Parses `GeoJSON` polygons and rasterizes them onto a layer, see [`read_geojson`].
+///
+/// # Errors
+///
+/// - [`DatasetError::InvalidRecord`]
+#[cfg(feature = "serde")]
+pub fn parse_geojson(
+    contents: &str,
+    property: &str,
+    transform: &GeoTransform,
+    bounds: GridBounds2D,
+) -> Result<GridLayer, DatasetError>
+{
+    let invalid = DatasetError::InvalidRecord { line: None };
+    let geojson: Value =
+        serde_json::from_str(contents).map_err(|err| DatasetError::InvalidRecord {
+            line: u64::try_from(err.line()).ok(),
+        })?;
+
+    let features = match geojson["type"].as_str()
+    {
+        Some("FeatureCollection") => geojson["features"]
+            .as_array()
+            .ok_or(invalid)?
+            .iter()
+            .collect(),
+        Some("Feature") => vec![&geojson],
+        _ => return Err(invalid),
+    };
+
+    let mut layer = GridLayer::new(bounds, f64::NAN);
+    for feature in features
+    {
+        let geometry = &feature["geometry"];
+        let polygons = match geometry["type"].as_str()
+        {
+            Some("Polygon") => vec![parse_polygon(&geometry["coordinates"])?],
+            Some("MultiPolygon") => geometry["coordinates"]
+                .as_array()
+                .ok_or(invalid)?
+                .iter()
+                .map(parse_polygon)
+                .collect::<Result<_, _>>()?,
+            _ => continue,
+        };
+        let Some(value) = feature["properties"][property].as_f64()
+        else
+        {
+            continue;
+        };
+
+        for polygon in &polygons
+        {
+            rasterize_polygon(&mut layer, polygon, transform, value);
+        }
+    }
+
+    Ok(layer)
+}
+
+/// A polygon, as a list of rings of coordinates, the first being its exterior and the rest its holes.
+#[cfg(feature = "serde")]
+type Polygon = Vec<Vec<DVec2>>;
+
+#[cfg(feature = "serde")]
+fn parse_polygon(coordinates: &Value) -> Result<Polygon, DatasetError>
+{
+    let invalid = DatasetError::InvalidRecord { line: None };
+
+    coordinates
+        .as_array()
+        .ok_or(invalid)?
+        .iter()
+        .map(|ring| {
+            ring.as_array()
+                .ok_or(invalid)?
+                .iter()
+                .map(|point| match (point[0].as_f64(), point[1].as_f64())
+                {
+                    (Some(x), Some(y)) => Ok(DVec2::new(x, y)),
+                    _ => Err(invalid),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Sets every cell of the `layer` whose center lies within the `polygon` to `value`.
+#[cfg(feature = "serde")]
+fn rasterize_polygon(layer: &mut GridLayer, polygon: &Polygon, transform: &GeoTransform, value: f64)
+{
+    let Some((min, max)) = polygon.first().and_then(|exterior| {
+        exterior.iter().fold(None, |extent, &point| match extent
+        {
+            None => Some((point, point)),
+            Some((min, max)) => Some((min.min(point), max.max(point))),
+        })
+    })
+    else
+    {
+        return;
+    };
+
+    // only the cells within the extent of the polygon are tested
+    let bounds = layer.bounds();
+    let extent = GridBounds2D {
+        min: transform.cell_at(min).0.max(bounds.min),
+        max: transform.cell_at(max).0.min(bounds.max),
+    };
+    if extent.min.cmpgt(extent.max).any()
+    {
+        return;
+    }
+
+    for position in extent.positions()
+    {
+        let center = transform.cell_center(&position);
+
+        // by the even-odd rule, a point within a hole crosses the edges of both the hole
+        // and the exterior, and is therefore outside the polygon
+        let inside = polygon
+            .iter()
+            .filter(|ring| crosses_odd(ring, center))
+            .count()
+            % 2
+            == 1;
+        if inside
+        {
+            // the position is within the bounds of the layer
+            let _ = layer.set(&position, value);
+        }
+    }
+}
+
+/// Returns `true` if a ray cast from the `point` towards positive `x` crosses the edges of the
+/// `ring` an odd number of times.
+#[cfg(feature = "serde")]
+fn crosses_odd(ring: &[DVec2], point: DVec2) -> bool
+{
+    let edges = ring.iter().zip(ring.iter().cycle().skip(1));
+
+    edges
+        .filter(|&(a, b)| {
+            (a.y > point.y) != (b.y > point.y)
+                && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        })
+        .count()
+        % 2
+        == 1
+}
@@ -0,0 +1,365 @@
+//! Continuous-time simulation with Gillespie's stochastic simulation algorithm.
+//!
+//! This is meant for models such as chemical kinetics and birth–death processes, where events
+//! happen at random times rather than on fixed steps.
+//!
+//! Each reaction is registered with [`crate::SimulationBuilder::add_reaction`] as a pair of
+//! systems: a propensity, returning the rate at which the reaction occurs in the current state
+//! of the simulation, and an effect, applying the reaction to the entities it involves.
+//! Every step of the simulation then represents a single event: the time until the next event
+//! is drawn from an exponential distribution with the total propensity of all reactions as its
+//! rate, and the reaction that occurs is chosen with probability proportional to its propensity.
+//! The elapsed time is kept in the [`SimulationTime`] resource, and the simulation can be run
+//! up to a given point in time with [`crate::Simulation::run_until`].
+//!
+//! Values sampled from the simulation can be recorded against the continuous time as a
+//! [`Trajectory`], with [`crate::SimulationBuilder::record_trajectory`].
+//!
+//! Example of a birth–death process:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::gillespie::SimulationTime;
+//!
+//! #[derive(Component)]
+//! struct Population(u32);
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_entity_spawner(|spawner| {
+//!         spawner.spawn(Population(10));
+//!     })
+//!     // births at a rate of 2.0
+//!     .add_reaction(
+//!         || 2.0,
+//!         |mut population: Single<&mut Population>| population.0 += 1,
+//!     )
+//!     // deaths at a rate of 0.1 per individual
+//!     .add_reaction(
+//!         |population: Single<&Population>| 0.1 * f64::from(population.0),
+//!         |mut population: Single<&mut Population>| population.0 -= 1,
+//!     )
+//!     .build();
+//!
+//! simulation.run_until(100.0);
+//! let time = simulation.get_resource::<SimulationTime>().unwrap().get();
+//! assert!(time >= 100.0);
+//! ```
+
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemId, prelude::*};
+use rand::Rng;
+use rand_distr::Exp1;
+
+use crate::{SampleAggregate, SimRng};
+
+/// Resource holding the continuous time elapsed in the simulation, as advanced by the
+/// reactions added with [`crate::SimulationBuilder::add_reaction`].
+///
+/// This resource can be accessed in user-defined systems using [`Res<SimulationTime>`] arguments.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct SimulationTime
+{
+    time: f64,
+    events: usize,
+    exhausted: bool,
+}
+
+impl SimulationTime
+{
+    /// Returns the time at which the latest event occurred.
+    #[must_use]
+    pub const fn get(&self) -> f64
+    {
+        self.time
+    }
+
+    /// Returns the number of events that have occurred so far.
+    #[must_use]
+    pub const fn events(&self) -> usize
+    {
+        self.events
+    }
+
+    /// Returns `true` if no reaction could occur on the latest step, because the propensities
+    /// of all reactions were zero.
+    ///
+    /// The time does not advance on such steps.
+    #[must_use]
+    pub const fn is_exhausted(&self) -> bool
+    {
+        self.exhausted
+    }
+}
+
+/// Values of type `O` recorded against the continuous time of the simulation,
+/// see [`crate::SimulationBuilder::record_trajectory`].
+///
+/// The values change only when events occur, so the trajectory holds one point for the
+/// initial state and one for every event, and is constant in between.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory<O>
+{
+    times: Vec<f64>,
+    values: Vec<O>,
+}
+
+impl<O> Default for Trajectory<O>
+{
+    fn default() -> Self
+    {
+        Self {
+            times: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<O> Trajectory<O>
+{
+    /// The number of points in the trajectory.
+    #[must_use]
+    pub const fn len(&self) -> usize
+    {
+        self.values.len()
+    }
+
+    /// Returns `true` if the trajectory has no points.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool
+    {
+        self.values.is_empty()
+    }
+
+    /// The times of the points in the trajectory, in increasing order.
+    #[must_use]
+    pub fn times(&self) -> &[f64]
+    {
+        &self.times
+    }
+
+    /// The values of the points in the trajectory, with the value at every index
+    /// recorded at the time found at the same index of [`Self::times`].
+    #[must_use]
+    pub fn values(&self) -> &[O]
+    {
+        &self.values
+    }
+
+    /// Iterates over each time-value point in the trajectory.
+    pub fn iter(&self) -> impl Iterator<Item = (f64, &O)>
+    {
+        self.times.iter().copied().zip(&self.values)
+    }
+
+    /// Returns the value held at the given `time`, which is the value of the latest point
+    /// recorded at or before it.
+    ///
+    /// Returns `None` if `time` is before the first point.
+    #[must_use]
+    pub fn value_at(&self, time: f64) -> Option<&O>
+    {
+        let idx = self.times.partition_point(|&t| t <= time);
+        idx.checked_sub(1).map(|idx| &self.values[idx])
+    }
+
+    fn push(&mut self, time: f64, value: O)
+    {
+        self.times.push(time);
+        self.values.push(value);
+    }
+}
+
+impl<O: Clone> Trajectory<O>
+{
+    /// Samples the trajectory at regular intervals of `dt`, starting from its first point
+    /// and up to `until`, so that it can be compared with other series or plotted.
+    ///
+    /// # Panics
+    ///
+    /// If `dt` is not positive and finite.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn resample(&self, dt: f64, until: f64) -> Vec<O>
+    {
+        assert!(dt.is_finite() && dt > 0.0, "invalid time interval: {dt}");
+
+        let Some(&start) = self.times.first()
+        else
+        {
+            return Vec::new();
+        };
+
+        if until < start
+        {
+            return Vec::new();
+        }
+
+        let count = ((until - start) / dt).floor() as usize + 1;
+        (0..count)
+            .filter_map(|i| self.value_at((i as f64).mul_add(dt, start)).cloned())
+            .collect()
+    }
+}
+
+/// A reaction registered in the simulation, with the ids of its propensity and effect systems.
+struct Reaction
+{
+    propensity: SystemId<(), f64>,
+    effect: SystemId,
+}
+
+/// Resource holding all reactions registered in the simulation.
+#[derive(Resource, Default)]
+pub(crate) struct Reactions(Vec<Reaction>);
+
+impl Reactions
+{
+    pub(crate) fn push(&mut self, propensity: SystemId<(), f64>, effect: SystemId)
+    {
+        self.0.push(Reaction { propensity, effect });
+    }
+}
+
+/// Resource holding a trajectory of `O` sampled from components `C`.
+#[derive(Resource)]
+pub(crate) struct TrajectoryData<C, O>
+{
+    pub(crate) trajectory: Trajectory<O>,
+    _phantom: PhantomData<C>,
+}
+
+/// Plugin that fires one reaction on every step of the simulation.
+pub(crate) struct GillespiePlugin;
+
+impl Plugin for GillespiePlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<SimulationTime>()
+            .init_resource::<Reactions>()
+            .add_systems(PreUpdate, gillespie_system);
+    }
+}
+
+/// Plugin that records the trajectory of `O` sampled from components `C`.
+pub(crate) struct TrajectoryPlugin<C, O>(PhantomData<(C, O)>);
+
+impl<C, O> Default for TrajectoryPlugin<C, O>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+impl<C, O> Plugin for TrajectoryPlugin<C, O>
+where
+    C: SampleAggregate<O>,
+    O: Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App)
+    {
+        app.insert_resource(TrajectoryData::<C, O> {
+            trajectory: Trajectory::default(),
+            _phantom: PhantomData,
+        });
+
+        // the initial state is recorded before the first event, and every event after it fires
+        app.add_systems(
+            PreUpdate,
+            trajectory_sample_system::<C, O>.before(gillespie_system),
+        );
+        app.add_systems(PostUpdate, trajectory_sample_system::<C, O>);
+    }
+}
+
+/// System that draws the time of the next event and fires the reaction that occurs in it,
+/// before any user-defined systems run.
+fn gillespie_system(world: &mut World)
+{
+    world.resource_scope(|world, reactions: Mut<Reactions>| {
+        let propensities: Vec<f64> = reactions
+            .0
+            .iter()
+            .map(|reaction| {
+                let Ok(propensity) = world.run_system(reaction.propensity)
+                else
+                {
+                    panic!("failed to evaluate the propensity of a reaction");
+                };
+                assert!(
+                    propensity.is_finite() && propensity >= 0.0,
+                    "invalid propensity: {propensity}"
+                );
+                propensity
+            })
+            .collect();
+
+        let total: f64 = propensities.iter().sum();
+        if total <= 0.0
+        {
+            world.resource_mut::<SimulationTime>().exhausted = true;
+            return;
+        }
+
+        let (wait, mut pick) = {
+            let mut rng = world.resource_mut::<SimRng>();
+            let wait: f64 = rng.sample(Exp1);
+            (wait / total, rng.random::<f64>() * total)
+        };
+
+        // choose the reaction with probability proportional to its propensity,
+        // falling back to the last possible one in case of rounding errors
+        let chosen = propensities
+            .iter()
+            .position(|&propensity| {
+                pick -= propensity;
+                propensity > 0.0 && pick < 0.0
+            })
+            .or_else(|| {
+                propensities
+                    .iter()
+                    .rposition(|&propensity| propensity > 0.0)
+            })
+            .unwrap_or_default();
+
+        let mut clock = world.resource_mut::<SimulationTime>();
+        clock.time += wait;
+        clock.events += 1;
+        clock.exhausted = false;
+
+        assert!(
+            world.run_system(reactions.0[chosen].effect).is_ok(),
+            "failed to apply the effect of a reaction"
+        );
+    });
+}
+
+/// System that records the current value of a trajectory, if the time has advanced since
+/// its latest point.
+fn trajectory_sample_system<C, O>(
+    mut data: ResMut<TrajectoryData<C, O>>,
+    clock: Res<SimulationTime>,
+    query: Query<&C>,
+) where
+    C: SampleAggregate<O>,
+    O: Send + Sync + 'static,
+{
+    let time = clock.get();
+    if data
+        .trajectory
+        .times
+        .last()
+        .is_some_and(|&latest| latest >= time)
+    {
+        return;
+    }
+
+    let components: Vec<&C> = query.iter().collect();
+    if !components.is_empty()
+    {
+        data.trajectory.push(time, C::sample_aggregate(&components));
+    }
+}
@@ -0,0 +1,884 @@
+//! An experimental backend that updates the cells of lattice automata on the GPU, with a compute
+//! shader run through bevy's renderer.
+//!
+//! Regular per-cell update rules, such as those of forest fires, the Ising model or diffusion,
+//! are embarrassingly parallel, so on large lattices they run much faster on the GPU than on the
+//! CPU. The automaton is added to a simulation with
+//! [`crate::SimulationBuilder::add_gpu_automaton`], configured through a [`GpuAutomatonPlugin`]
+//! with one of the built-in [`GpuRule`]s, or a custom rule written in WGSL.
+//!
+//! Cells are entities with a [`GridPosition2D`] and a state component that implements
+//! [`GpuCell`], which encodes the state as a single `f32` on the GPU. On every step, before any
+//! user-defined systems run, the states of all cells are uploaded to the GPU, updated there
+//! synchronously, and synced back to their components, so that they can be sampled and changed
+//! by user-defined systems as usual.
+//!
+//! The backend is enabled with the `gpu` feature, and needs a GPU adapter that supports compute
+//! shaders; without one, [`crate::SimulationBuilder::add_gpu_automaton`] returns
+//! [`crate::BuilderError::GpuUnavailable`]. If the cell states can not be read back from the GPU
+//! on a later step, the system that updates the automaton fails with a [`crate::GpuError`], which
+//! is passed to bevy's error handler, and panics by default.
+//!
+//! The randomness of the rules is drawn from the simulation's [`SimRng`], so runs with the same
+//! seed on the same GPU are reproducible, but the results are not bit-for-bit identical to those
+//! of the CPU modules, nor across GPUs.
+//!
+//! Example of a forest fire on a torus:
+//! ```no_run
+//! # use incerto::prelude::*;
+//! use incerto::gpu::{GpuAutomatonPlugin, GpuCell, GpuRule};
+//!
+//! #[derive(Component, Clone, Copy, PartialEq)]
+//! enum Forest
+//! {
+//!     Empty,
+//!     Tree,
+//!     Burning,
+//! }
+//!
+//! impl GpuCell for Forest
+//! {
+//!     fn to_gpu(&self) -> f32
+//!     {
+//!         *self as u8 as f32
+//!     }
+//!
+//!     fn from_gpu(value: f32) -> Self
+//!     {
+//!         [Self::Empty, Self::Tree, Self::Burning][value as usize]
+//!     }
+//! }
+//!
+//! let bounds = GridBounds2D {
+//!     min: IVec2::new(0, 0),
+//!     max: IVec2::new(1023, 1023),
+//! };
+//! let rule = GpuRule::ForestFire {
+//!     growth: 0.01,
+//!     lightning: 1e-5,
+//! };
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_gpu_automaton(GpuAutomatonPlugin::<Forest>::new(bounds, rule).with_wrapping())
+//!     .expect("no GPU available")
+//!     .add_entity_spawner(move |spawner| {
+//!         spawner.spawn_grid(bounds, |_| Some(Forest::Empty));
+//!     })
+//!     .build();
+//!
+//! simulation.run(1000);
+//! let trees = simulation.iter::<Forest>().filter(|&&cell| cell == Forest::Tree).count();
+//! ```
+
+use std::{
+    marker::PhantomData,
+    sync::{Arc, mpsc},
+};
+
+use bevy::{
+    ecs::component::Mutable,
+    prelude::*,
+    render::{
+        render_resource::{
+            BindGroup, BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry, BindingType, Buffer,
+            BufferBindingType, BufferDescriptor, BufferUsages, CommandEncoderDescriptor,
+            ComputePassDescriptor, ComputePipeline, DownlevelFlags, Maintain, MapMode,
+            PipelineCompilationOptions, PipelineLayoutDescriptor, RawComputePipelineDescriptor,
+            ShaderModuleDescriptor, ShaderSource, ShaderStages,
+        },
+        renderer::{RenderDevice, RenderQueue, WgpuWrapper},
+        settings::WgpuSettings,
+    },
+    tasks::block_on,
+};
+use rand::Rng;
+
+use crate::{
+    BuilderError, GpuError, SimRng,
+    cellular::Neighborhood,
+    plugins::{GridBounds2D, GridCoordinates, GridPosition2D},
+    spin::{Ising, Spin},
+};
+
+/// The number of cells along each axis of a workgroup of the compute shader.
+const WORKGROUP_SIZE: u32 = 8;
+
+/// The state of a cell in a [`GpuAutomatonPlugin`], encoded as a single `f32` on the GPU.
+pub trait GpuCell: Component<Mutability = Mutable> + PartialEq
+{
+    /// Encodes the state as the value of the cell on the GPU.
+    fn to_gpu(&self) -> f32;
+
+    /// Decodes the state from the value of the cell on the GPU.
+    fn from_gpu(value: f32) -> Self;
+}
+
+impl GpuCell for Spin
+{
+    fn to_gpu(&self) -> f32
+    {
+        match self
+        {
+            Self::Up => 1.0,
+            Self::Down => -1.0,
+        }
+    }
+
+    fn from_gpu(value: f32) -> Self
+    {
+        if value < 0.0 { Self::Down } else { Self::Up }
+    }
+}
+
+/// The rule with which a [`GpuAutomatonPlugin`] updates its cells on the GPU.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpuRule
+{
+    /// The discrete diffusion of a concentration, in which every cell exchanges `rate` times the
+    /// difference of their values with each of its four orthogonally adjacent cells, so that the
+    /// total is conserved.
+    ///
+    /// Stable for rates of at most `0.25`.
+    Diffusion
+    {
+        rate: f64
+    },
+
+    /// The Drossel-Schwabl forest fire model, with cells that are empty (`0`), hold a tree (`1`)
+    /// or are burning (`2`).
+    ///
+    /// A burning cell becomes empty, a tree catches fire if any of its neighbors is burning, or
+    /// is struck by lightning with probability `lightning`, and a tree grows on an empty cell
+    /// with probability `growth`.
+    ForestFire
+    {
+        growth: f64, lightning: f64
+    },
+
+    /// The Metropolis algorithm for the [`Ising`] model, with spins of `-1` or `1`, as with the
+    /// [`Spin`] component, at the given `temperature`.
+    ///
+    /// Every step is one sweep of the lattice, updating the sites of a checkerboard pattern in
+    /// two halves, so that no two adjacent sites are flipped at once.
+    Ising
+    {
+        model: Ising, temperature: f64
+    },
+
+    /// A custom rule, as WGSL source that defines the function `fn rule(cell: f32) -> f32`,
+    /// which returns the next value of a cell from its current value.
+    ///
+    /// The rule may call the following functions:
+    /// - `state(offset: vec2<i32>) -> f32`, the value of the cell at the given offset, or `0`
+    ///   if there is none,
+    /// - `has_cell(offset: vec2<i32>) -> bool`, whether there is a cell at the given offset,
+    /// - `neighbor(k: u32) -> vec2<i32>` and `neighbor_count() -> u32`, the offsets of the
+    ///   neighbors of the configured [`Neighborhood`],
+    /// - `position() -> vec2<i32>`, the position of the cell on the grid,
+    /// - `random() -> f32`, a uniform random number in `[0, 1)`.
+    ///
+    /// The `parameters: vec4<f32>` of the rule are available as `params.parameters`.
+    Wgsl
+    {
+        source: String,
+        parameters: [f32; 4],
+    },
+}
+
+impl GpuRule
+{
+    /// The WGSL source of the rule function.
+    fn source(&self) -> &str
+    {
+        match self
+        {
+            Self::Diffusion { .. } => DIFFUSION_RULE,
+            Self::ForestFire { .. } => FOREST_FIRE_RULE,
+            Self::Ising { .. } => ISING_RULE,
+            Self::Wgsl { source, .. } => source,
+        }
+    }
+
+    /// The parameters of the rule, as passed to the shader.
+    #[allow(clippy::cast_possible_truncation)]
+    const fn parameters(&self) -> [f32; 4]
+    {
+        match self
+        {
+            Self::Diffusion { rate } => [*rate as f32, 0.0, 0.0, 0.0],
+            Self::ForestFire { growth, lightning } => [*growth as f32, *lightning as f32, 0.0, 0.0],
+            Self::Ising { model, temperature } => [
+                model.exchange as f32,
+                model.external_field as f32,
+                *temperature as f32,
+                0.0,
+            ],
+            Self::Wgsl { parameters, .. } => *parameters,
+        }
+    }
+
+    /// The number of passes of every step, each updating either all cells, or one half of a
+    /// checkerboard pattern.
+    const fn passes(&self) -> &'static [u32]
+    {
+        match self
+        {
+            Self::Ising { .. } => &[0, 1],
+            _ => &[ALL_CELLS],
+        }
+    }
+}
+
+/// The parity of a pass that updates all cells, rather than one half of a checkerboard pattern.
+const ALL_CELLS: u32 = 2;
+
+/// Plugin that updates the cells with state `C` of a bounded grid on the GPU on every step,
+/// added to a simulation with [`crate::SimulationBuilder::add_gpu_automaton`].
+///
+/// Cells are entities with a [`GridPosition2D`] and a `C` component, with at most one cell on
+/// every position; cells outside the bounds are not updated, and positions without a cell are
+/// skipped as neighbors.
+/// The cells are updated once per step, before any user-defined systems run.
+pub struct GpuAutomatonPlugin<C: GpuCell>
+{
+    bounds: GridBounds2D,
+    neighborhood: Neighborhood,
+    wrapping: bool,
+    rule: GpuRule,
+    _phantom: PhantomData<C>,
+}
+
+impl<C: GpuCell> GpuAutomatonPlugin<C>
+{
+    /// Creates an automaton on the given `bounds`, in which every cell is updated with the `rule`.
+    ///
+    /// By default, the neighbors of a cell are those of the [`Neighborhood::Moore`], and the
+    /// edges of the grid do not wrap around. The [`GpuRule::Diffusion`] and [`GpuRule::Ising`]
+    /// rules always use the four orthogonally adjacent cells.
+    ///
+    /// # Panics
+    ///
+    /// If the parameters of a built-in rule are invalid.
+    #[must_use]
+    pub fn new(bounds: GridBounds2D, rule: GpuRule) -> Self
+    {
+        match rule
+        {
+            GpuRule::Diffusion { rate } =>
+            {
+                assert!(
+                    (0.0..=0.25).contains(&rate),
+                    "unstable diffusion rate: {rate}"
+                );
+            }
+            GpuRule::ForestFire { growth, lightning } =>
+            {
+                assert!((0.0..=1.0).contains(&growth), "invalid growth: {growth}");
+                assert!(
+                    (0.0..=1.0).contains(&lightning),
+                    "invalid lightning: {lightning}"
+                );
+            }
+            GpuRule::Ising { temperature, .. } =>
+            {
+                assert!(
+                    temperature.is_finite() && temperature >= 0.0,
+                    "invalid temperature: {temperature}"
+                );
+            }
+            GpuRule::Wgsl { .. } =>
+            {}
+        }
+
+        Self {
+            bounds,
+            neighborhood: Neighborhood::Moore,
+            wrapping: false,
+            rule,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Sets which of the surrounding cells are the neighbors of a cell.
+    #[must_use]
+    pub const fn with_neighborhood(mut self, neighborhood: Neighborhood) -> Self
+    {
+        self.neighborhood = neighborhood;
+        self
+    }
+
+    /// Makes the edges of the grid wrap around, so that the cells on opposite edges are
+    /// neighbors, as on a torus.
+    #[must_use]
+    pub const fn with_wrapping(mut self) -> Self
+    {
+        self.wrapping = true;
+        self
+    }
+}
+
+impl<C: GpuCell> Plugin for GpuAutomatonPlugin<C>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(PreUpdate, gpu_automaton_system::<C>);
+    }
+}
+
+/// Sets up the GPU device, if not already set up by another automaton, and the pipeline and
+/// buffers of the automaton of the cells with state `C`, before adding its `plugin`.
+pub(crate) fn add<C: GpuCell>(
+    app: &mut App,
+    plugin: GpuAutomatonPlugin<C>,
+) -> Result<(), BuilderError>
+{
+    if !app.world().contains_resource::<GpuDevice>()
+    {
+        let device = GpuDevice::new()?;
+        app.insert_resource(device);
+    }
+    let automaton = GpuAutomaton::<C>::new(app.world().resource::<GpuDevice>(), &plugin)?;
+
+    app.insert_resource(automaton).add_plugins(plugin);
+    Ok(())
+}
+
+/// Resource holding the GPU device shared by all automata of a simulation.
+#[derive(Resource)]
+struct GpuDevice
+{
+    device: RenderDevice,
+    queue: RenderQueue,
+}
+
+impl GpuDevice
+{
+    /// Requests a GPU adapter that supports compute shaders, and a device on it.
+    fn new() -> Result<Self, BuilderError>
+    {
+        let settings = WgpuSettings::default();
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: settings.backends.unwrap_or(wgpu::Backends::all()),
+            flags: settings.instance_flags,
+            ..default()
+        });
+        let options = wgpu::RequestAdapterOptions {
+            power_preference: settings.power_preference,
+            ..default()
+        };
+        let adapter =
+            block_on(instance.request_adapter(&options)).ok_or(BuilderError::GpuUnavailable)?;
+
+        let capabilities = adapter.get_downlevel_capabilities();
+        if !capabilities.flags.contains(DownlevelFlags::COMPUTE_SHADERS)
+        {
+            return Err(BuilderError::GpuUnavailable);
+        }
+
+        let descriptor = wgpu::DeviceDescriptor {
+            label: Some("automaton device"),
+            required_limits: adapter.limits(),
+            ..default()
+        };
+        let (device, queue) = block_on(adapter.request_device(&descriptor, None))
+            .map_err(|_| BuilderError::GpuUnavailable)?;
+
+        Ok(Self {
+            device: RenderDevice::from(device),
+            queue: RenderQueue(Arc::new(WgpuWrapper::new(queue))),
+        })
+    }
+}
+
+/// Resource holding the pipeline and buffers of the automaton of the cells with state `C`.
+#[derive(Resource)]
+struct GpuAutomaton<C>
+{
+    device: RenderDevice,
+    queue: RenderQueue,
+    pipeline: ComputePipeline,
+    /// The bind groups reading from one of the two state buffers and writing to the other.
+    bind_groups: [BindGroup; 2],
+    states: [Buffer; 2],
+    present: Buffer,
+    params: Buffer,
+    readback: Buffer,
+    bounds: GridBounds2D,
+    wrapping: bool,
+    neighborhood: Neighborhood,
+    rule: GpuRule,
+    /// The values and presence of the cells, kept between steps to reuse their allocation.
+    values: Vec<f32>,
+    mask: Vec<u32>,
+    _phantom: PhantomData<C>,
+}
+
+impl<C: GpuCell> GpuAutomaton<C>
+{
+    /// Creates the pipeline and buffers of the automaton, and checks that the cell states can be
+    /// read back from the GPU.
+    fn new(gpu: &GpuDevice, plugin: &GpuAutomatonPlugin<C>) -> Result<Self, BuilderError>
+    {
+        let device = &gpu.device;
+        let cells = IVec2::cell_count(&plugin.bounds);
+        let size = (cells * size_of::<f32>()) as u64;
+
+        let storage = |label, usage| {
+            device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let states = [
+            storage(
+                "cell states",
+                BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            ),
+            storage(
+                "next cell states",
+                BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            ),
+        ];
+        let present = storage(
+            "cell presence",
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        );
+        let readback = storage(
+            "cell readback",
+            BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        );
+        let params = device.create_buffer(&BufferDescriptor {
+            label: Some("automaton parameters"),
+            size: PARAMS_SIZE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = bind_group_layout(device);
+        let bind_group = |from: &Buffer, to: &Buffer| {
+            device.create_bind_group(
+                "automaton bind group",
+                &layout,
+                &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: from.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: to.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: present.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: params.as_entire_binding(),
+                    },
+                ],
+            )
+        };
+        let bind_groups = [
+            bind_group(&states[0], &states[1]),
+            bind_group(&states[1], &states[0]),
+        ];
+
+        let pipeline = compute_pipeline(device, &layout, &plugin.rule);
+
+        let mut automaton = Self {
+            device: device.clone(),
+            queue: gpu.queue.clone(),
+            pipeline,
+            bind_groups,
+            states,
+            present,
+            params,
+            readback,
+            bounds: plugin.bounds,
+            wrapping: plugin.wrapping,
+            neighborhood: plugin.neighborhood,
+            rule: plugin.rule.clone(),
+            values: Vec::new(),
+            mask: Vec::new(),
+            _phantom: PhantomData,
+        };
+        automaton
+            .read_back()
+            .map_err(|_| BuilderError::GpuUnavailable)?;
+
+        Ok(automaton)
+    }
+
+    /// The parameters of a pass, laid out as the `Params` struct of the shader.
+    #[allow(clippy::cast_sign_loss)]
+    fn params(&self, parity: u32, seed: u32) -> Vec<u8>
+    {
+        let size = self.bounds.max - self.bounds.min + IVec2::ONE;
+        let neighborhood = match self.neighborhood
+        {
+            Neighborhood::Moore => 0,
+            Neighborhood::VonNeumann => 1,
+        };
+        let words = [
+            self.bounds.min.x as u32,
+            self.bounds.min.y as u32,
+            size.x as u32,
+            size.y as u32,
+            u32::from(self.wrapping),
+            parity,
+            seed,
+            neighborhood,
+        ];
+
+        words
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .chain(
+                self.rule
+                    .parameters()
+                    .into_iter()
+                    .flat_map(f32::to_le_bytes),
+            )
+            .collect()
+    }
+
+    /// Uploads the values of the cells, runs the passes of one step, and reads back the new
+    /// values of the cells.
+    #[allow(clippy::cast_sign_loss)]
+    fn step(&mut self, rng: &mut SimRng) -> Result<(), GpuError>
+    {
+        self.queue.write_buffer(
+            &self.states[0],
+            0,
+            &to_bytes(self.values.iter().map(|v| v.to_bits())),
+        );
+        self.queue
+            .write_buffer(&self.present, 0, &to_bytes(self.mask.iter().copied()));
+
+        let size = self.bounds.max - self.bounds.min + IVec2::ONE;
+        let workgroups = (
+            (size.x as u32).div_ceil(WORKGROUP_SIZE),
+            (size.y as u32).div_ceil(WORKGROUP_SIZE),
+        );
+
+        // every pass reads the output of the previous one, and the uniform parameters are
+        // written before every submission, so every pass is submitted on its own
+        let passes = self.rule.passes();
+        for (i, &parity) in passes.iter().enumerate()
+        {
+            self.queue
+                .write_buffer(&self.params, 0, &self.params(parity, rng.random()));
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&CommandEncoderDescriptor::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &*self.bind_groups[i % 2], &[]);
+                pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+            }
+            self.queue.submit([encoder.finish()]);
+        }
+
+        let result = &self.states[passes.len() % 2];
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(result, 0, &self.readback, 0, self.readback.size());
+        self.queue.submit([encoder.finish()]);
+
+        self.read_back()
+    }
+
+    /// Maps the readback buffer, and copies the values of the cells from it.
+    fn read_back(&mut self) -> Result<(), GpuError>
+    {
+        let (sender, receiver) = mpsc::channel();
+        let slice = self.readback.slice(..);
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(Maintain::Wait);
+        if !matches!(receiver.try_recv(), Ok(Ok(())))
+        {
+            return Err(GpuError::ReadbackFailed);
+        }
+
+        {
+            let bytes = slice.get_mapped_range();
+            for (value, chunk) in self.values.iter_mut().zip(bytes.chunks_exact(4))
+            {
+                *value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            }
+        }
+        self.readback.unmap();
+        Ok(())
+    }
+}
+
+/// The layout of the bindings of the shader.
+fn bind_group_layout(device: &RenderDevice) -> BindGroupLayout
+{
+    let storage_entry = |binding, read_only| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(
+        "automaton bind group layout",
+        &[
+            storage_entry(0, true),
+            storage_entry(1, false),
+            storage_entry(2, true),
+            BindGroupLayoutEntry {
+                binding: 3,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+    )
+}
+
+/// The pipeline that runs the shader with the given `rule`.
+fn compute_pipeline(
+    device: &RenderDevice,
+    layout: &BindGroupLayout,
+    rule: &GpuRule,
+) -> ComputePipeline
+{
+    let source = format!("{SHADER}\n{}", rule.source());
+    let module = device.create_and_validate_shader_module(ShaderModuleDescriptor {
+        label: Some("automaton shader"),
+        source: ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("automaton pipeline layout"),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    device.create_compute_pipeline(&RawComputePipelineDescriptor {
+        label: Some("automaton pipeline"),
+        layout: Some(&pipeline_layout),
+        module: &module,
+        entry_point: Some("main"),
+        compilation_options: PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
+
+/// The size in bytes of the `Params` struct of the shader.
+const PARAMS_SIZE: u64 = 48;
+
+fn to_bytes(words: impl Iterator<Item = u32>) -> Vec<u8>
+{
+    words.flat_map(u32::to_le_bytes).collect()
+}
+
+/// System that uploads the states of the cells to the GPU, updates them there, and syncs the
+/// new states back to their components.
+fn gpu_automaton_system<C: GpuCell>(
+    mut automaton: ResMut<GpuAutomaton<C>>,
+    mut query: Query<(&GridPosition2D, &mut C)>,
+    mut rng: ResMut<SimRng>,
+) -> Result
+{
+    let automaton = &mut *automaton;
+    let bounds = automaton.bounds;
+    let cells = IVec2::cell_count(&bounds);
+
+    automaton.values.clear();
+    automaton.values.resize(cells, 0.0);
+    automaton.mask.clear();
+    automaton.mask.resize(cells, 0);
+    for (position, state) in &query
+    {
+        if bounds.contains(&position.0)
+        {
+            let index = position.0.cell_index(&bounds);
+            automaton.values[index] = state.to_gpu();
+            automaton.mask[index] = 1;
+        }
+    }
+    if automaton.mask.iter().all(|&present| present == 0)
+    {
+        return Ok(());
+    }
+
+    automaton.step(&mut rng)?;
+
+    for (position, mut state) in &mut query
+    {
+        if bounds.contains(&position.0)
+        {
+            let value = automaton.values[position.0.cell_index(&bounds)];
+            state.set_if_neq(C::from_gpu(value));
+        }
+    }
+
+    Ok(())
+}
+
+/// The bindings, parameters and helper functions shared by all rules, and the entry point that
+/// calls the rule on every cell.
+///
+/// Cells are indexed as with [`GridCoordinates::cell_index`], i.e. column by column.
+const SHADER: &str = r"
+struct Params {
+    min: vec2<i32>,
+    size: vec2<i32>,
+    wrapping: u32,
+    parity: u32,
+    seed: u32,
+    neighborhood: u32,
+    parameters: vec4<f32>,
+}
+
+@group(0) @binding(0) var<storage, read> current: array<f32>;
+@group(0) @binding(1) var<storage, read_write> next: array<f32>;
+@group(0) @binding(2) var<storage, read> present: array<u32>;
+@group(0) @binding(3) var<uniform> params: Params;
+
+var<private> cell_position: vec2<i32>;
+var<private> rng_state: u32;
+
+var<private> moore: array<vec2<i32>, 8> = array(
+    vec2(-1, -1), vec2(0, -1), vec2(1, -1), vec2(-1, 0),
+    vec2(1, 0), vec2(-1, 1), vec2(0, 1), vec2(1, 1),
+);
+var<private> von_neumann: array<vec2<i32>, 4> = array(
+    vec2(0, -1), vec2(-1, 0), vec2(1, 0), vec2(0, 1),
+);
+
+fn cell_index(offset: vec2<i32>) -> i32 {
+    var target_position = cell_position + offset;
+    if params.wrapping != 0u {
+        target_position = ((target_position % params.size) + params.size) % params.size;
+    } else if any(target_position < vec2(0)) || any(target_position >= params.size) {
+        return -1;
+    }
+    return target_position.x * params.size.y + target_position.y;
+}
+
+fn has_cell(offset: vec2<i32>) -> bool {
+    let index = cell_index(offset);
+    return index >= 0 && present[index] != 0u;
+}
+
+fn state(offset: vec2<i32>) -> f32 {
+    if !has_cell(offset) {
+        return 0.0;
+    }
+    return current[cell_index(offset)];
+}
+
+fn neighbor_count() -> u32 {
+    if params.neighborhood == 0u {
+        return 8u;
+    }
+    return 4u;
+}
+
+fn neighbor(k: u32) -> vec2<i32> {
+    if params.neighborhood == 0u {
+        return moore[k];
+    }
+    return von_neumann[k];
+}
+
+fn position() -> vec2<i32> {
+    return params.min + cell_position;
+}
+
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random() -> f32 {
+    rng_state = hash(rng_state);
+    return f32(rng_state >> 8u) / 16777216.0;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    cell_position = vec2<i32>(id.xy);
+    if any(cell_position >= params.size) {
+        return;
+    }
+    let index = cell_index(vec2(0));
+    let cell = current[index];
+    if present[index] == 0u
+        || (params.parity < 2u && u32(cell_position.x + cell_position.y) % 2u != params.parity) {
+        next[index] = cell;
+        return;
+    }
+
+    rng_state = hash(params.seed ^ hash(u32(index)));
+    next[index] = rule(cell);
+}
+";
+
+const DIFFUSION_RULE: &str = r"
+fn rule(cell: f32) -> f32 {
+    var flux = 0.0;
+    for (var k = 0u; k < 4u; k++) {
+        if has_cell(von_neumann[k]) {
+            flux += state(von_neumann[k]) - cell;
+        }
+    }
+    return cell + params.parameters.x * flux;
+}
+";
+
+const FOREST_FIRE_RULE: &str = r"
+fn rule(cell: f32) -> f32 {
+    let growth = params.parameters.x;
+    let lightning = params.parameters.y;
+    let current_state = u32(round(cell));
+    if current_state == 2u {
+        return 0.0;
+    }
+    if current_state == 0u {
+        return select(0.0, 1.0, random() < growth);
+    }
+
+    for (var k = 0u; k < neighbor_count(); k++) {
+        if u32(round(state(neighbor(k)))) == 2u {
+            return 2.0;
+        }
+    }
+    return select(1.0, 2.0, random() < lightning);
+}
+";
+
+const ISING_RULE: &str = r"
+fn rule(cell: f32) -> f32 {
+    let exchange = params.parameters.x;
+    let field = params.parameters.y;
+    let temperature = params.parameters.z;
+
+    var sum = 0.0;
+    for (var k = 0u; k < 4u; k++) {
+        sum += state(von_neumann[k]);
+    }
+    let delta = 2.0 * cell * (exchange * sum + field);
+    if delta <= 0.0 || (temperature > 0.0 && random() < exp(-delta / temperature)) {
+        return -cell;
+    }
+    return cell;
+}
+";
@@ -0,0 +1,291 @@
+//! Interaction kernels, which weigh the interactions between two individuals by the distance
+//! between them, such as the probability of an infection or the strength of an influence.
+//!
+//! An [`InteractionKernel`] describes the weight as a function of distance, under a [`Metric`]
+//! and up to a cutoff radius beyond which there is no interaction at all, so that the
+//! distance-dependent parameters of a model are given as data, and can be swept over like any
+//! other parameter.
+//! The kernels can weigh any displacement directly, or find the weighted contacts of a position
+//! on a [`crate::prelude::SpatialGrid2D`] with [`InteractionKernel::contacts`].
+//!
+//! Example of infections within two cells, which are less likely further away:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::kernel::{InteractionKernel, Metric};
+//!
+//! #[derive(Component)]
+//! struct Infectious;
+//!
+//! let kernel = InteractionKernel::table([0.15, 0.15, 0.05]).with_metric(Metric::Manhattan);
+//! assert_eq!(kernel.weight(IVec2::new(1, 1)), 0.05);
+//! assert_eq!(kernel.weight(IVec2::new(2, 1)), 0.0);
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_spatial_grid_2d::<Infectious>(None)
+//!     .add_entity_spawner(|spawner| {
+//!         spawner.spawn((Infectious, GridPosition2D::new(0, 0)));
+//!         spawner.spawn((Infectious, GridPosition2D::new(1, 0)));
+//!         spawner.spawn((Infectious, GridPosition2D::new(3, 3)));
+//!     })
+//!     .add_systems(move |grid: Res<SpatialGrid2D<Infectious>>| {
+//!         let mut contacts = Vec::new();
+//!         kernel.contacts(&grid, GridPosition2D::new(0, 1), &mut contacts);
+//!         let pressure: f64 = contacts.iter().map(|(_, weight)| weight).sum();
+//!         assert!((pressure - 0.2).abs() < 1e-12);
+//!     })
+//!     .build();
+//!
+//! simulation.run(1);
+//! ```
+
+use bevy::prelude::*;
+
+use crate::plugins::{GridPosition2D, SpatialGrid2D};
+
+/// How the distance between two positions is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Metric
+{
+    /// The straight-line distance.
+    #[default]
+    Euclidean,
+
+    /// The sum of the distances along each axis, i.e. the number of steps between orthogonally
+    /// adjacent cells.
+    Manhattan,
+
+    /// The largest of the distances along each axis, i.e. the number of steps between cells that
+    /// are adjacent orthogonally or diagonally.
+    Chebyshev,
+}
+
+impl Metric
+{
+    /// The length of the given displacement under this metric.
+    #[must_use]
+    pub fn length(self, displacement: IVec2) -> f64
+    {
+        let displacement = displacement.abs().as_dvec2();
+        match self
+        {
+            Self::Euclidean => displacement.length(),
+            Self::Manhattan => displacement.element_sum(),
+            Self::Chebyshev => displacement.max_element(),
+        }
+    }
+}
+
+/// The shape of the weight of an [`InteractionKernel`] as a function of distance.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape
+{
+    Uniform(f64),
+    Exponential
+    {
+        weight: f64,
+        scale: f64,
+    },
+    Gravity
+    {
+        weight: f64,
+        exponent: f64,
+    },
+    Table(Vec<f64>),
+}
+
+/// The weight of the interactions between two individuals as a function of the distance
+/// between them.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractionKernel
+{
+    shape: Shape,
+    radius: f64,
+    metric: Metric,
+}
+
+impl InteractionKernel
+{
+    /// A kernel with the same `weight` at every distance up to `radius`.
+    ///
+    /// # Panics
+    ///
+    /// If `weight` or `radius` is negative or not finite.
+    #[must_use]
+    pub fn uniform(weight: f64, radius: f64) -> Self
+    {
+        Self::new(Shape::Uniform(weight), radius)
+    }
+
+    /// A kernel whose weight decays exponentially with distance, as `weight * exp(-d / scale)`,
+    /// up to `radius`.
+    ///
+    /// # Panics
+    ///
+    /// If `weight` or `radius` is negative or not finite, or `scale` is not positive and finite.
+    #[must_use]
+    pub fn exponential(weight: f64, scale: f64, radius: f64) -> Self
+    {
+        assert!(
+            scale.is_finite() && scale > 0.0,
+            "invalid kernel length scale: {scale}"
+        );
+        Self::new(Shape::Exponential { weight, scale }, radius)
+    }
+
+    /// A kernel whose weight decays as a power of distance, as `weight / d^exponent`, up to
+    /// `radius`, where distances below `1` count as `1`.
+    ///
+    /// This is the distance term of gravity models, in which the interactions between two
+    /// places are also proportional to the product of their masses, such as their populations,
+    /// by which the weights can be multiplied.
+    ///
+    /// # Panics
+    ///
+    /// If `weight` or `radius` is negative or not finite, or `exponent` is negative or not
+    /// finite.
+    #[must_use]
+    pub fn gravity(weight: f64, exponent: f64, radius: f64) -> Self
+    {
+        assert!(
+            exponent.is_finite() && exponent >= 0.0,
+            "invalid kernel exponent: {exponent}"
+        );
+        Self::new(Shape::Gravity { weight, exponent }, radius)
+    }
+
+    /// A kernel with the given weight at every whole distance, starting from `0`, with
+    /// fractional distances rounded up and no interactions beyond the last entry.
+    ///
+    /// # Panics
+    ///
+    /// If there are no weights, or any of them is negative or not finite.
+    #[must_use]
+    pub fn table(weights: impl IntoIterator<Item = f64>) -> Self
+    {
+        let weights = weights.into_iter().collect::<Vec<_>>();
+        assert!(
+            !weights.is_empty(),
+            "a kernel table needs at least one weight"
+        );
+        for weight in &weights
+        {
+            assert!(
+                weight.is_finite() && *weight >= 0.0,
+                "invalid kernel weight: {weight}"
+            );
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let radius = (weights.len() - 1) as f64;
+        Self::new(Shape::Table(weights), radius)
+    }
+
+    fn new(shape: Shape, radius: f64) -> Self
+    {
+        if let Shape::Uniform(weight)
+        | Shape::Exponential { weight, .. }
+        | Shape::Gravity { weight, .. } = shape
+        {
+            assert!(
+                weight.is_finite() && weight >= 0.0,
+                "invalid kernel weight: {weight}"
+            );
+        }
+        assert!(
+            radius.is_finite() && radius >= 0.0,
+            "invalid kernel radius: {radius}"
+        );
+
+        Self {
+            shape,
+            radius,
+            metric: Metric::default(),
+        }
+    }
+
+    /// Sets the metric that distances are measured with, which is [`Metric::Euclidean`] by
+    /// default.
+    #[must_use]
+    pub const fn with_metric(mut self, metric: Metric) -> Self
+    {
+        self.metric = metric;
+        self
+    }
+
+    /// The distance beyond which there are no interactions.
+    #[must_use]
+    pub const fn radius(&self) -> f64
+    {
+        self.radius
+    }
+
+    /// The metric that distances are measured with.
+    #[must_use]
+    pub const fn metric(&self) -> Metric
+    {
+        self.metric
+    }
+
+    /// The weight of the interactions at the given distance.
+    #[must_use]
+    pub fn weight_at(&self, distance: f64) -> f64
+    {
+        if !(0.0..=self.radius).contains(&distance)
+        {
+            return 0.0;
+        }
+
+        match &self.shape
+        {
+            Shape::Uniform(weight) => *weight,
+            Shape::Exponential { weight, scale } => weight * (-distance / scale).exp(),
+            Shape::Gravity { weight, exponent } => weight / distance.max(1.0).powf(*exponent),
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            Shape::Table(weights) => weights[distance.ceil() as usize],
+        }
+    }
+
+    /// The weight of the interactions between two cells that are `displacement` apart.
+    #[must_use]
+    pub fn weight(&self, displacement: IVec2) -> f64
+    {
+        self.weight_at(self.metric.length(displacement))
+    }
+
+    /// The displacements of all cells within the radius of the kernel, along with their
+    /// weights, skipping those with a weight of `0`.
+    ///
+    /// This includes the displacement of `0` for contacts within the same cell.
+    pub fn offsets(&self) -> impl Iterator<Item = (IVec2, f64)> + '_
+    {
+        #[allow(clippy::cast_possible_truncation)]
+        let reach = self.radius.floor() as i32;
+        (-reach..=reach)
+            .flat_map(move |x| (-reach..=reach).map(move |y| IVec2::new(x, y)))
+            .map(|offset| (offset, self.weight(offset)))
+            .filter(|(_, weight)| *weight > 0.0)
+    }
+
+    /// Collects the entities of a spatial grid within the radius of the kernel around
+    /// `position` into the `buffer`, along with the weight of their interactions with an
+    /// individual at that position.
+    ///
+    /// Entities at `position` itself are included, so an individual looking for its own
+    /// contacts should skip itself.
+    pub fn contacts<C: Component>(
+        &self,
+        grid: &SpatialGrid2D<C>,
+        position: GridPosition2D,
+        buffer: &mut Vec<(Entity, f64)>,
+    )
+    {
+        buffer.clear();
+        for (offset, weight) in self.offsets()
+        {
+            let cell = GridPosition2D::new(position.x() + offset.x, position.y() + offset.y);
+            buffer.extend(grid.entities_at(&cell).map(|entity| (entity, weight)));
+        }
+    }
+}
@@ -13,22 +13,96 @@
 //! All relevant types should be in the [`prelude`].
 //! The primary type used to run experiments is [`Simulation`].
 
+pub mod calendar;
+pub mod cellular;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod comparison;
+pub mod demography;
+pub mod epidemic;
+pub mod evolution;
+pub mod flocking;
+pub mod flow;
+pub mod fsm;
+pub mod geo;
+pub mod gillespie;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod kernel;
+pub mod market;
+pub mod opinion;
+pub mod placement;
+pub mod policy;
 pub mod prelude;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod queueing;
+pub mod random_walk;
+pub mod reaction_diffusion;
+pub mod sde;
+#[cfg(feature = "msgpack")]
+pub mod snapshot;
+pub mod spin;
+pub mod topology;
+pub mod traffic;
 
+#[cfg(feature = "arrow")]
+mod arrow;
+
+#[cfg(not(target_family = "wasm"))]
+mod background;
+#[cfg(feature = "dashboard")]
+mod dashboard;
+#[cfg(feature = "polars")]
+mod dataframe;
 mod error;
+#[cfg(feature = "serde")]
+mod export;
+#[cfg(any(feature = "image", feature = "netcdf"))]
+mod frames;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "netcdf")]
+mod netcdf;
 mod plugins;
 mod simulation;
 mod simulation_builder;
 mod spawner;
+mod statistics;
+#[cfg(feature = "sqlite")]
+mod store;
+#[cfg(feature = "tensorboard")]
+mod tensorboard;
+mod trace;
 mod traits;
 mod types;
 mod util;
+#[cfg(feature = "visualize")]
+mod visualize;
+#[cfg(feature = "websocket")]
+mod websocket;
+#[cfg(any(feature = "dashboard", feature = "visualize"))]
+mod window;
 
+#[cfg(feature = "arrow")]
+pub use arrow::{write_feather, write_parquet};
+#[cfg(not(target_family = "wasm"))]
+pub use background::{BackgroundSimulation, SimulationStatus};
 pub use error::*;
-pub use plugins::{GridBounds, GridPosition, SpatialGrid, StepNumber};
+pub use plugins::{
+    BoundsViolation, ContactLayers, DeferredDespawn, DeferredFlush, DeferredSpawn, EventLog,
+    FutureEvents, GridBounds, GridPosition, IncrementalAggregate, Network, PopulationLedger,
+    SpatialGrid, StepNumber,
+};
+#[cfg(feature = "polars")]
+pub use polars;
 pub use simulation::Simulation;
 pub use simulation_builder::SimulationBuilder;
-pub use spawner::Spawner;
+pub use spawner::{ChildSpawner, EntityHandle, Spawner, WeightedSpawner};
+#[cfg(feature = "sqlite")]
+pub use store::{ResultStore, StoredRun};
 pub use traits::*;
 pub use types::*;
 pub use util::*;
+#[cfg(feature = "arrow")]
+pub use {arrow_array, arrow_ipc, arrow_schema};
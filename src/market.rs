@@ -0,0 +1,508 @@
+//! Order-book markets, in which the price of an asset forms through the orders submitted by agents.
+//!
+//! Agents submit limit orders by spawning entities with an [`Order`] component, bidding to buy or
+//! asking to sell a quantity of the asset at a limit price.
+//! At the end of every step, after all user-defined systems have run, the new orders are matched
+//! against those resting in the [`OrderBook`] resource with price-time priority, at the price of
+//! the resting order.
+//! Every match emits a [`Trade`] event, while orders that are not filled right away rest in the
+//! book, and may be cancelled by despawning them.
+//!
+//! The book keeps a [`Bar`] with the open, high, low and close prices and the traded volume of
+//! every step in which trades took place, which makes up the price series of the asset.
+//!
+//! Example of traders placing random orders around the latest price:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::market::{Order, OrderBook};
+//! use rand::Rng;
+//!
+//! #[derive(Component)]
+//! struct Trader;
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_market()
+//!     .add_entity_spawner(|spawner| {
+//!         for _ in 0..100
+//!         {
+//!             spawner.spawn(Trader);
+//!         }
+//!     })
+//!     .add_systems(
+//!         |mut commands: Commands, traders: Query<Entity, With<Trader>>, book: Res<OrderBook>, mut rng: ResMut<SimRng>| {
+//!             let reference = book.last_price().unwrap_or(100.0);
+//!             for trader in &traders
+//!             {
+//!                 let price = reference * rng.random_range(0.95..1.05);
+//!                 let order = if rng.random_bool(0.5) { Order::bid(price, 1) } else { Order::ask(price, 1) };
+//!                 commands.spawn(order.by(trader));
+//!             }
+//!         },
+//!     )
+//!     .build();
+//!
+//! simulation.run(100);
+//! let book = simulation.get_resource::<OrderBook>().unwrap();
+//! let closing_prices: Vec<f64> = book.bars().iter().map(|bar| bar.close).collect();
+//! ```
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
+};
+
+use bevy::prelude::*;
+
+use crate::StepNumber;
+
+/// The side of the market an [`Order`] is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side
+{
+    /// An order to buy.
+    Bid,
+
+    /// An order to sell.
+    Ask,
+}
+
+impl Side
+{
+    /// The other side of the market.
+    #[must_use]
+    pub const fn opposite(self) -> Self
+    {
+        match self
+        {
+            Self::Bid => Self::Ask,
+            Self::Ask => Self::Bid,
+        }
+    }
+}
+
+/// Component of a limit order submitted to the [`OrderBook`].
+///
+/// The order is filled at its limit price or better, in one or more [`Trade`]s.
+/// Once filled completely it is despawned, while until then it rests in the book with its
+/// remaining quantity, and may be cancelled by despawning it.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Order
+{
+    side: Side,
+    price: f64,
+    quantity: u64,
+    remaining: u64,
+    trader: Option<Entity>,
+    /// The position of the order among all orders submitted, stamped when it is added.
+    sequence: u64,
+}
+
+impl Order
+{
+    /// Creates an order to buy the given `quantity` at a price of at most `price`.
+    #[must_use]
+    pub const fn bid(price: f64, quantity: u64) -> Self
+    {
+        Self::new(Side::Bid, price, quantity)
+    }
+
+    /// Creates an order to sell the given `quantity` at a price of at least `price`.
+    #[must_use]
+    pub const fn ask(price: f64, quantity: u64) -> Self
+    {
+        Self::new(Side::Ask, price, quantity)
+    }
+
+    const fn new(side: Side, price: f64, quantity: u64) -> Self
+    {
+        Self {
+            side,
+            price,
+            quantity,
+            remaining: quantity,
+            trader: None,
+            sequence: 0,
+        }
+    }
+
+    /// Sets the entity of the trader placing the order, which is reported in its [`Trade`]s.
+    #[must_use]
+    pub const fn by(mut self, trader: Entity) -> Self
+    {
+        self.trader = Some(trader);
+        self
+    }
+
+    /// The side of the market the order is on.
+    #[must_use]
+    pub const fn side(&self) -> Side
+    {
+        self.side
+    }
+
+    /// The limit price of the order.
+    #[must_use]
+    pub const fn price(&self) -> f64
+    {
+        self.price
+    }
+
+    /// The quantity the order was placed for.
+    #[must_use]
+    pub const fn quantity(&self) -> u64
+    {
+        self.quantity
+    }
+
+    /// The quantity not yet filled.
+    #[must_use]
+    pub const fn remaining(&self) -> u64
+    {
+        self.remaining
+    }
+
+    /// The entity of the trader placing the order, if set.
+    #[must_use]
+    pub const fn trader(&self) -> Option<Entity>
+    {
+        self.trader
+    }
+}
+
+/// Event emitted whenever two orders are matched in the [`OrderBook`].
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct Trade
+{
+    /// The step in which the trade took place.
+    pub step: usize,
+
+    /// The price of the trade, which is the limit price of the resting order.
+    pub price: f64,
+
+    /// The quantity traded.
+    pub quantity: u64,
+
+    /// The trader of the bid, if set.
+    pub buyer: Option<Entity>,
+
+    /// The trader of the ask, if set.
+    pub seller: Option<Entity>,
+}
+
+/// The prices and volume of the trades that took place in a step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar
+{
+    /// The step in which the trades took place.
+    pub step: usize,
+
+    /// The price of the first trade.
+    pub open: f64,
+
+    /// The highest price traded.
+    pub high: f64,
+
+    /// The lowest price traded.
+    pub low: f64,
+
+    /// The price of the last trade.
+    pub close: f64,
+
+    /// The total quantity traded.
+    pub volume: u64,
+}
+
+impl Bar
+{
+    const fn new(trade: &Trade) -> Self
+    {
+        Self {
+            step: trade.step,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: trade.quantity,
+        }
+    }
+
+    const fn add(&mut self, trade: &Trade)
+    {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += trade.quantity;
+    }
+}
+
+/// A price level in the book, ordered by value.
+#[derive(Debug, Clone, Copy)]
+struct Price(f64);
+
+impl PartialEq for Price
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering>
+    {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price
+{
+    fn cmp(&self, other: &Self) -> Ordering
+    {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+type Levels = BTreeMap<Price, VecDeque<Entity>>;
+
+/// Resource holding the orders resting in the market, and the price series formed by its trades.
+#[derive(Resource, Debug, Default)]
+pub struct OrderBook
+{
+    bids: Levels,
+    asks: Levels,
+    bars: Vec<Bar>,
+    /// The sequence number of the next order to be submitted.
+    next_sequence: u64,
+}
+
+impl OrderBook
+{
+    /// The highest price of the resting bids.
+    #[must_use]
+    pub fn best_bid(&self) -> Option<f64>
+    {
+        self.bids.keys().next_back().map(|price| price.0)
+    }
+
+    /// The lowest price of the resting asks.
+    #[must_use]
+    pub fn best_ask(&self) -> Option<f64>
+    {
+        self.asks.keys().next().map(|price| price.0)
+    }
+
+    /// The average of the best bid and ask prices.
+    #[must_use]
+    pub fn mid_price(&self) -> Option<f64>
+    {
+        Some(f64::midpoint(self.best_bid()?, self.best_ask()?))
+    }
+
+    /// The difference between the best ask and bid prices.
+    #[must_use]
+    pub fn spread(&self) -> Option<f64>
+    {
+        Some(self.best_ask()? - self.best_bid()?)
+    }
+
+    /// The price of the latest trade.
+    #[must_use]
+    pub fn last_price(&self) -> Option<f64>
+    {
+        self.bars.last().map(|bar| bar.close)
+    }
+
+    /// The number of orders resting on the given side of the book.
+    #[must_use]
+    pub fn depth(&self, side: Side) -> usize
+    {
+        self.levels(side).values().map(VecDeque::len).sum()
+    }
+
+    /// The prices and volume of every step in which trades took place, in order.
+    #[must_use]
+    pub fn bars(&self) -> &[Bar]
+    {
+        &self.bars
+    }
+
+    const fn levels(&self, side: Side) -> &Levels
+    {
+        match side
+        {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    const fn levels_mut(&mut self, side: Side) -> &mut Levels
+    {
+        match side
+        {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    /// The best resting order against which an order on the given side may be matched,
+    /// if its limit price crosses it.
+    fn best_opposite(&self, side: Side, limit: f64) -> Option<(Price, Entity)>
+    {
+        let (&price, queue) = match side
+        {
+            Side::Bid => self.asks.iter().next()?,
+            Side::Ask => self.bids.iter().next_back()?,
+        };
+        let crosses = match side
+        {
+            Side::Bid => price.0 <= limit,
+            Side::Ask => price.0 >= limit,
+        };
+
+        crosses.then(|| queue.front().map(|&order| (price, order)))?
+    }
+
+    /// Removes the order at the front of the given price level.
+    fn pop_front(&mut self, side: Side, price: Price)
+    {
+        let levels = self.levels_mut(side);
+        if let Some(queue) = levels.get_mut(&price)
+        {
+            queue.pop_front();
+            if queue.is_empty()
+            {
+                levels.remove(&price);
+            }
+        }
+    }
+
+    fn record(&mut self, trade: &Trade)
+    {
+        match self.bars.last_mut()
+        {
+            Some(bar) if bar.step == trade.step => bar.add(trade),
+            _ => self.bars.push(Bar::new(trade)),
+        }
+    }
+}
+
+/// Plugin that matches the orders submitted on every step.
+pub(crate) struct MarketPlugin;
+
+impl Plugin for MarketPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<OrderBook>()
+            .add_event::<Trade>()
+            .add_systems(PostUpdate, matching_system)
+            .add_observer(order_added);
+    }
+}
+
+/// Observer that stamps every new order with its sequence number, as soon as it is added,
+/// so that orders are matched in the order they were submitted regardless of the order in
+/// which they are stored.
+fn order_added(
+    trigger: Trigger<OnAdd, Order>,
+    mut orders: Query<&mut Order>,
+    mut book: ResMut<OrderBook>,
+)
+{
+    if let Ok(mut order) = orders.get_mut(trigger.target())
+    {
+        order.sequence = book.next_sequence;
+        book.next_sequence += 1;
+    }
+}
+
+/// System that matches every new order against the book, in the order they were submitted,
+/// and adds what remains of it to the book.
+fn matching_system(
+    mut commands: Commands,
+    mut book: ResMut<OrderBook>,
+    mut orders: Query<(Entity, &mut Order)>,
+    mut trades: EventWriter<Trade>,
+    step_number: Res<StepNumber>,
+)
+{
+    let mut new_orders: Vec<(u64, Entity)> = orders
+        .iter_mut()
+        .filter(|(_, order)| order.is_added())
+        .map(|(entity, order)| (order.sequence, entity))
+        .collect();
+    new_orders.sort_unstable();
+
+    for (_, incoming) in new_orders
+    {
+        let Ok((_, order)) = orders.get(incoming)
+        else
+        {
+            continue;
+        };
+        let (side, limit, placed_by) = (order.side, order.price, order.trader);
+        let mut remaining = order.remaining;
+
+        while remaining > 0
+        {
+            let Some((price, resting)) = book.best_opposite(side, limit)
+            else
+            {
+                break;
+            };
+
+            // orders that were cancelled are dropped from the book
+            let Ok((_, mut resting_order)) = orders.get_mut(resting)
+            else
+            {
+                book.pop_front(side.opposite(), price);
+                continue;
+            };
+
+            let quantity = remaining.min(resting_order.remaining);
+            resting_order.remaining -= quantity;
+            remaining -= quantity;
+
+            let resting_trader = resting_order.trader;
+            if resting_order.remaining == 0
+            {
+                book.pop_front(side.opposite(), price);
+                commands.entity(resting).despawn();
+            }
+
+            let (buyer, seller) = match side
+            {
+                Side::Bid => (placed_by, resting_trader),
+                Side::Ask => (resting_trader, placed_by),
+            };
+            let trade = Trade {
+                step: step_number.get(),
+                price: price.0,
+                quantity,
+                buyer,
+                seller,
+            };
+            book.record(&trade);
+            trades.write(trade);
+        }
+
+        if let Ok((_, mut order)) = orders.get_mut(incoming)
+        {
+            order.remaining = remaining;
+        }
+        if remaining == 0
+        {
+            commands.entity(incoming).despawn();
+        }
+        else
+        {
+            book.levels_mut(side)
+                .entry(Price(limit))
+                .or_default()
+                .push_back(incoming);
+        }
+    }
+}
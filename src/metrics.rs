@@ -0,0 +1,182 @@
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    ops::ControlFlow,
+    sync::{Arc, Mutex, PoisonError, Weak},
+    thread,
+    time::Duration,
+};
+
+use bevy::{platform::time::Instant, prelude::*};
+
+use crate::{SampleAggregate, error::BuilderError, plugins::StepEndHooks};
+
+/// The minimum time between two refreshes of the metrics served on the endpoint,
+/// so that fast simulations do not spend their time formatting values that will never be scraped.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reads the value of a gauge from the simulation's world, if it can be sampled.
+type GaugeSource = fn(&World) -> Option<f64>;
+
+/// Resource holding the gauges served on the metrics endpoint, in addition to the built-in ones.
+#[derive(Resource, Default)]
+pub struct MetricGauges(Vec<(&'static str, GaugeSource)>);
+
+impl MetricGauges
+{
+    pub fn push(&mut self, name: &'static str, source: GaugeSource)
+    {
+        self.0.push((name, source));
+    }
+}
+
+/// Returns `true` if the given `name` is a valid Prometheus metric name.
+pub fn is_valid_name(name: &str) -> bool
+{
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+/// Samples the aggregate of `O` over all components `C` in the world.
+///
+/// Returns `None` if there are no such components, in which case the gauge is omitted.
+pub fn aggregate_gauge<C, O>(world: &World) -> Option<f64>
+where
+    C: SampleAggregate<O>,
+    O: Into<f64>,
+{
+    let mut query = world.try_query::<&C>()?;
+    let components = query.iter(world).collect::<Vec<_>>();
+
+    (!components.is_empty()).then(|| C::sample_aggregate(&components).into())
+}
+
+/// Binds the metrics endpoint to the given address, and sets up the simulation to refresh
+/// the metrics served on it at the end of its steps.
+///
+/// The endpoint is served from a background thread, which stops accepting connections
+/// once the simulation has been dropped.
+///
+/// # Errors
+///
+/// - [`BuilderError::MetricsEndpointUnavailable`]
+pub fn add(app: &mut App, address: impl ToSocketAddrs) -> Result<(), BuilderError>
+{
+    let listener =
+        TcpListener::bind(address).map_err(|_| BuilderError::MetricsEndpointUnavailable)?;
+    let body = Arc::new(Mutex::new(String::new()));
+
+    app.init_resource::<MetricGauges>();
+    app.world_mut()
+        .get_resource_or_init::<StepEndHooks>()
+        .push(refresher(Arc::clone(&body)));
+
+    let body = Arc::downgrade(&body);
+    thread::spawn(move || serve(&listener, &body));
+
+    Ok(())
+}
+
+/// Creates a step-end hook that periodically formats the metrics of the simulation into `body`.
+///
+/// The metrics are always refreshed after the first step, and the hook never stops the simulation.
+fn refresher(body: Arc<Mutex<String>>)
+-> impl FnMut(&World, usize) -> ControlFlow<()> + Send + Sync
+{
+    let mut last_refresh: Option<(Instant, usize)> = None;
+
+    move |world, step| {
+        let now = Instant::now();
+        let steps_per_second = match last_refresh
+        {
+            Some((time, _)) if now.duration_since(time) < REFRESH_INTERVAL =>
+            {
+                return ControlFlow::Continue(());
+            }
+            #[allow(clippy::cast_precision_loss)]
+            Some((time, last_step)) =>
+            {
+                step.saturating_sub(last_step) as f64 / now.duration_since(time).as_secs_f64()
+            }
+            None => 0.0,
+        };
+        last_refresh = Some((now, step));
+
+        let mut metrics = String::new();
+        let mut gauge = |name: &str, value: f64| {
+            // writing to a string never fails
+            let _ = write!(metrics, "# TYPE {name} gauge\n{name} {value}\n");
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        {
+            gauge("incerto_step", step as f64);
+            gauge("incerto_entities", f64::from(world.entities().len()));
+        }
+        gauge("incerto_steps_per_second", steps_per_second);
+        if let Some(gauges) = world.get_resource::<MetricGauges>()
+        {
+            for &(name, source) in &gauges.0
+            {
+                if let Some(value) = source(world)
+                {
+                    gauge(name, value);
+                }
+            }
+        }
+
+        *body.lock().unwrap_or_else(PoisonError::into_inner) = metrics;
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// Serves the latest metrics in `body` to every connection made to the `listener`,
+/// until the body is dropped along with the simulation.
+fn serve(listener: &TcpListener, body: &Weak<Mutex<String>>)
+{
+    for stream in listener.incoming()
+    {
+        let Some(body) = body.upgrade()
+        else
+        {
+            return;
+        };
+        let Ok(stream) = stream
+        else
+        {
+            continue;
+        };
+
+        let body = body.lock().unwrap_or_else(PoisonError::into_inner).clone();
+
+        // a client that disconnects early simply misses this scrape
+        let _ = respond(stream, &body);
+    }
+}
+
+/// Writes the `body` as the response to the request on the given `stream`,
+/// regardless of the path that was requested.
+fn respond(stream: TcpStream, body: &str) -> std::io::Result<()>
+{
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line != "\r\n"
+    {
+        line.clear();
+    }
+
+    write!(
+        reader.get_mut(),
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    reader.get_mut().flush()
+}
@@ -0,0 +1,192 @@
+use std::io::{self, Write};
+
+const MAGIC: [u8; 4] = *b"CDF\x02";
+
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+
+const NC_CHAR: u32 = 2;
+const NC_INT: u32 = 4;
+const NC_DOUBLE: u32 = 6;
+
+/// The values of a variable, in row-major order of its dimensions.
+pub enum Values
+{
+    Int(Vec<i32>),
+    Double(Vec<f64>),
+}
+
+impl Values
+{
+    const fn nc_type(&self) -> u32
+    {
+        match self
+        {
+            Self::Int(_) => NC_INT,
+            Self::Double(_) => NC_DOUBLE,
+        }
+    }
+
+    const fn size(&self) -> usize
+    {
+        match self
+        {
+            Self::Int(values) => values.len() * size_of::<i32>(),
+            Self::Double(values) => values.len() * size_of::<f64>(),
+        }
+    }
+}
+
+/// A named array of values over one or more dimensions of a [`Dataset`].
+pub struct Variable
+{
+    pub name: String,
+
+    /// The indices of the dimensions of the variable in [`Dataset::dimensions`].
+    pub dimensions: Vec<usize>,
+
+    /// Text attributes of the variable, such as its `units` or `long_name`.
+    pub attributes: Vec<(&'static str, String)>,
+
+    pub values: Values,
+}
+
+/// A set of named dimensions, and variables defined over them.
+///
+/// Datasets are written in the 64-bit offset variant of the netCDF classic format, which is
+/// simple enough to not require `libnetcdf` or `libhdf5`, and is read by every netCDF library.
+/// Only fixed-size dimensions and text attributes are supported.
+pub struct Dataset
+{
+    /// The name and length of every dimension.
+    pub dimensions: Vec<(String, usize)>,
+
+    pub variables: Vec<Variable>,
+}
+
+impl Dataset
+{
+    /// Writes the dataset in the netCDF classic format.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()>
+    {
+        // the header holds the offset of each variable's data, which follows the header,
+        // so it is first encoded once to find its length
+        let header_len = self.header(0).len();
+        writer.write_all(&self.header(header_len))?;
+
+        for variable in &self.variables
+        {
+            let mut bytes = Vec::with_capacity(padded(variable.values.size()));
+            match &variable.values
+            {
+                Values::Int(values) =>
+                {
+                    bytes.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+                }
+                Values::Double(values) =>
+                {
+                    bytes.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+                }
+            }
+            bytes.resize(padded(bytes.len()), 0);
+            writer.write_all(&bytes)?;
+        }
+
+        writer.flush()
+    }
+
+    /// Encodes the header of the dataset, with the data starting at the given `offset`.
+    fn header(&self, mut offset: usize) -> Vec<u8>
+    {
+        let mut header = Vec::new();
+        header.extend_from_slice(&MAGIC);
+
+        // the number of records, since no dimension is unlimited
+        put_u32(&mut header, 0);
+
+        put_u32(&mut header, NC_DIMENSION);
+        put_len(&mut header, self.dimensions.len());
+        for (name, len) in &self.dimensions
+        {
+            put_name(&mut header, name);
+            put_len(&mut header, *len);
+        }
+
+        // there are no global attributes
+        put_u32(&mut header, 0);
+        put_u32(&mut header, 0);
+
+        put_u32(&mut header, NC_VARIABLE);
+        put_len(&mut header, self.variables.len());
+        for variable in &self.variables
+        {
+            put_name(&mut header, &variable.name);
+            put_len(&mut header, variable.dimensions.len());
+            for &dimension in &variable.dimensions
+            {
+                put_len(&mut header, dimension);
+            }
+
+            if variable.attributes.is_empty()
+            {
+                put_u32(&mut header, 0);
+                put_u32(&mut header, 0);
+            }
+            else
+            {
+                put_u32(&mut header, NC_ATTRIBUTE);
+                put_len(&mut header, variable.attributes.len());
+                for (name, value) in &variable.attributes
+                {
+                    put_name(&mut header, name);
+                    put_u32(&mut header, NC_CHAR);
+                    put_name(&mut header, value);
+                }
+            }
+
+            let size = padded(variable.values.size());
+            put_u32(&mut header, variable.values.nc_type());
+            // variables too large for their size to be stored are marked as such
+            put_u32(&mut header, u32::try_from(size).unwrap_or(u32::MAX));
+            header.extend_from_slice(&(offset as u64).to_be_bytes());
+            offset += size;
+        }
+
+        header
+    }
+}
+
+/// The given length, rounded up to a multiple of 4 bytes.
+const fn padded(len: usize) -> usize
+{
+    len.next_multiple_of(4)
+}
+
+fn put_u32(header: &mut Vec<u8>, value: u32)
+{
+    header.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_len(header: &mut Vec<u8>, len: usize)
+{
+    put_u32(header, u32::try_from(len).unwrap_or(u32::MAX));
+}
+
+/// Puts a string, preceded by its length and padded with zeros.
+fn put_name(header: &mut Vec<u8>, name: &str)
+{
+    put_len(header, name.len());
+    header.extend_from_slice(name.as_bytes());
+    header.resize(padded(header.len()), 0);
+}
+
+/// Returns `true` if the given `name` is valid for a dimension or variable of a netCDF dataset.
+pub fn is_valid_name(name: &str) -> bool
+{
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+' | '@'))
+}
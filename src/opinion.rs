@@ -0,0 +1,392 @@
+//! Prefab models of opinion dynamics, in which every agent holds an [`Opinion`] and updates it
+//! on every step according to the opinions of its neighbors.
+//!
+//! The model is added to a simulation with [`crate::SimulationBuilder::add_module`], and records
+//! the mean, spread and polarization of the opinions on every step in the [`OpinionOutputs`]
+//! resource, along with the step at which the population first reached consensus.
+//!
+//! Agents interact through one of the [`Interaction`] modes: with the agents in the same and the
+//! adjacent cells of a spatial grid, with their neighbors in a network, or through several
+//! [`crate::ContactLayers`] at once.
+//!
+//! Example of the voter model on a lattice:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::opinion::{Interaction, Opinion, OpinionModule, OpinionOutputs};
+//! use rand::Rng;
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_module(OpinionModule::voter().with_interaction(Interaction::Spatial(None)))
+//!     .add_seeded_entity_spawner(|spawner, rng| {
+//!         for x in 0..10
+//!         {
+//!             for y in 0..10
+//!             {
+//!                 let opinion = if rng.random_bool(0.5) { 1.0 } else { 0.0 };
+//!                 spawner.spawn((Opinion(opinion), GridPosition(IVec2::new(x, y))));
+//!             }
+//!         }
+//!     })
+//!     .build();
+//!
+//! simulation.run(1000);
+//! let outputs = simulation.get_resource::<OpinionOutputs>().unwrap();
+//! let consensus_time = outputs.consensus_time();
+//! ```
+
+use bevy::{ecs::entity::EntityHashMap, prelude::*};
+use rand::{Rng, seq::IndexedRandom};
+
+use crate::{
+    MetricsHistory, Module, SimRng, SimulationBuilder,
+    plugins::{ContactSources, GridBounds2D, StepNumber},
+};
+
+/// Component holding the opinion of an agent in an [`OpinionModule`].
+///
+/// Opinions are typically within `[0, 1]`, or either `0` or `1` in the voter model.
+#[derive(Component, Debug, Clone, Copy, PartialEq, PartialOrd, Default, Deref, DerefMut)]
+pub struct Opinion(pub f64);
+
+impl From<&Opinion> for f64
+{
+    fn from(opinion: &Opinion) -> Self
+    {
+        opinion.0
+    }
+}
+
+/// Which agents influence the opinion of each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interaction
+{
+    /// Agents interact with those in the same and the adjacent cells of a
+    /// [`crate::prelude::SpatialGrid2D<Opinion>`], which is added with the given bounds.
+    ///
+    /// Agents need a [`crate::GridPosition`] to take part in interactions.
+    Spatial(Option<GridBounds2D>),
+
+    /// Agents interact with their neighbors in a [`crate::Network<Opinion>`], which is added to the
+    /// simulation, with their influence weighted by the weight of the edge between them.
+    #[default]
+    Network,
+
+    /// Agents interact through every layer of the [`crate::ContactLayers<Opinion>`], which must be added
+    /// with [`crate::SimulationBuilder::add_contact_layers`], with their influence weighted by
+    /// the modifier of the layer and the weight of the edge between them, if any.
+    Layered,
+}
+
+/// How agents update their opinions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rule
+{
+    Voter,
+    DeGroot,
+    BoundedConfidence
+    {
+        epsilon: f64,
+        mu: f64,
+    },
+}
+
+/// A model of opinion dynamics, added to a simulation with [`crate::SimulationBuilder::add_module`].
+///
+/// On every step, each agent with an [`Opinion`] updates it according to the opinions of its
+/// neighbors at the start of the step, with the rule of the model:
+///
+/// - In the voter model, the agent adopts the opinion of a random neighbor.
+/// - In `DeGroot` averaging, the agent adopts the weighted average of its own opinion and those of
+///   its neighbors.
+/// - In the bounded confidence model of Deffuant and Weisbuch, the agent picks a random neighbor,
+///   and moves towards its opinion by a fraction `mu` of their difference, unless the difference
+///   is greater than `epsilon`.
+///
+/// Agents without neighbors keep their opinion.
+/// The randomness is drawn from the simulation's [`SimRng`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpinionModule
+{
+    rule: Rule,
+    interaction: Interaction,
+    tolerance: f64,
+}
+
+impl OpinionModule
+{
+    const fn new(rule: Rule) -> Self
+    {
+        Self {
+            rule,
+            interaction: Interaction::Network,
+            tolerance: 1e-6,
+        }
+    }
+
+    /// The voter model, in which agents adopt the opinion of a random neighbor.
+    #[must_use]
+    pub const fn voter() -> Self
+    {
+        Self::new(Rule::Voter)
+    }
+
+    /// `DeGroot` averaging, in which agents adopt the average opinion of themselves and their neighbors.
+    #[must_use]
+    pub const fn degroot() -> Self
+    {
+        Self::new(Rule::DeGroot)
+    }
+
+    /// The bounded confidence model, in which agents move towards the opinion of a random neighbor
+    /// by a fraction `mu` of their difference, if it is at most `epsilon`.
+    ///
+    /// # Panics
+    ///
+    /// If `epsilon` is negative, or `mu` is not within `[0, 1]`.
+    #[must_use]
+    pub fn bounded_confidence(epsilon: f64, mu: f64) -> Self
+    {
+        assert!(epsilon >= 0.0, "invalid confidence bound: {epsilon}");
+        assert!(
+            (0.0..=1.0).contains(&mu),
+            "invalid convergence parameter: {mu}"
+        );
+
+        Self::new(Rule::BoundedConfidence { epsilon, mu })
+    }
+
+    /// Sets which agents interact, which is [`Interaction::Network`] by default.
+    #[must_use]
+    pub const fn with_interaction(mut self, interaction: Interaction) -> Self
+    {
+        self.interaction = interaction;
+        self
+    }
+
+    /// Sets the largest difference between the opinions of any two agents, at which the
+    /// population is considered to have reached consensus, which is `1e-6` by default.
+    #[must_use]
+    pub const fn with_consensus_tolerance(mut self, tolerance: f64) -> Self
+    {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Collects the neighbors of an agent, along with the weight of their influence.
+    fn neighbors(
+        &self,
+        entity: Entity,
+        sources: &ContactSources<Opinion>,
+        buffer: &mut Vec<(Entity, f64)>,
+    )
+    {
+        let grid = sources.grid.as_deref();
+        buffer.clear();
+        match self.interaction
+        {
+            Interaction::Spatial(_) =>
+            {
+                let Some((grid, position)) =
+                    grid.and_then(|grid| Some((grid, grid.position_of(entity)?)))
+                else
+                {
+                    return;
+                };
+
+                buffer.extend(
+                    grid.entities_at(&position)
+                        .chain(grid.neighbors_of(&position))
+                        .filter(|&contact| contact != entity)
+                        .map(|contact| (contact, 1.0)),
+                );
+            }
+            Interaction::Network =>
+            {
+                buffer.extend(
+                    sources
+                        .network
+                        .iter()
+                        .flat_map(|network| network.weighted_neighbors_of(entity)),
+                );
+            }
+            Interaction::Layered =>
+            {
+                if let Some(layers) = &sources.layers
+                {
+                    layers.contacts_of(entity, grid, buffer);
+                }
+            }
+        }
+    }
+
+    /// The updated opinion of an agent, given the opinions of its neighbors.
+    fn update(&self, own: f64, neighbors: &[(f64, f64)], rng: &mut impl Rng) -> f64
+    {
+        match self.rule
+        {
+            Rule::Voter => neighbors.choose(rng).map_or(own, |&(opinion, _)| opinion),
+            Rule::DeGroot =>
+            {
+                let (sum, weights) =
+                    neighbors
+                        .iter()
+                        .fold((own, 1.0), |(sum, weights), &(opinion, weight)| {
+                            (opinion.mul_add(weight, sum), weights + weight)
+                        });
+                sum / weights
+            }
+            Rule::BoundedConfidence { epsilon, mu } => match neighbors.choose(rng)
+            {
+                Some(&(opinion, _)) if (opinion - own).abs() <= epsilon =>
+                {
+                    mu.mul_add(opinion - own, own)
+                }
+                _ => own,
+            },
+        }
+    }
+}
+
+impl Module for OpinionModule
+{
+    fn build(self, builder: SimulationBuilder) -> SimulationBuilder
+    {
+        let builder = match self.interaction
+        {
+            Interaction::Spatial(bounds) => builder.add_spatial_grid_2d::<Opinion>(bounds),
+            Interaction::Network => builder.add_network::<Opinion>(),
+            Interaction::Layered => builder,
+        };
+
+        builder
+            .add_resource(OpinionParameters(self))
+            .add_resource(OpinionOutputs::default())
+            .add_systems(opinion_system)
+    }
+}
+
+/// The distribution of opinions at the end of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OpinionMetrics
+{
+    /// The number of the step, as read from [`StepNumber`].
+    pub step: usize,
+
+    /// The mean opinion.
+    pub mean: f64,
+
+    /// The variance of the opinions.
+    pub variance: f64,
+
+    /// The difference between the largest and the smallest opinion.
+    pub spread: f64,
+
+    /// The mean absolute difference between the opinions of any two agents, which is `0` at
+    /// consensus, and largest when the population is split evenly between two extremes.
+    pub polarization: f64,
+
+    /// Whether the spread is within the consensus tolerance,
+    /// see [`OpinionModule::with_consensus_tolerance`].
+    pub consensus: bool,
+}
+
+/// Resource holding the [`OpinionMetrics`] of every step of an [`OpinionModule`].
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<OpinionOutputs>`] arguments.
+pub type OpinionOutputs = MetricsHistory<OpinionMetrics>;
+
+impl OpinionOutputs
+{
+    /// The first step at the end of which the population had reached consensus,
+    /// see [`OpinionModule::with_consensus_tolerance`].
+    #[must_use]
+    pub fn consensus_time(&self) -> Option<usize>
+    {
+        self.history()
+            .iter()
+            .find(|metrics| metrics.consensus)
+            .map(|metrics| metrics.step)
+    }
+}
+
+/// Resource holding the parameters of the [`OpinionModule`] added to the simulation.
+#[derive(Resource)]
+struct OpinionParameters(OpinionModule);
+
+/// System that updates the opinion of every agent, and records the metrics of the step.
+fn opinion_system(
+    parameters: Res<OpinionParameters>,
+    mut outputs: ResMut<OpinionOutputs>,
+    mut query: Query<(Entity, &mut Opinion)>,
+    mut rng: ResMut<SimRng>,
+    step_number: Res<StepNumber>,
+    sources: ContactSources<Opinion>,
+)
+{
+    let parameters = &parameters.0;
+
+    // updates depend on the opinions at the start of the step, so that the order in which
+    // agents are visited does not matter
+    let current = query
+        .iter()
+        .map(|(entity, opinion)| (entity, opinion.0))
+        .collect::<EntityHashMap<f64>>();
+
+    let mut contacts = Vec::new();
+    let mut neighbors = Vec::new();
+    let mut opinions = Vec::with_capacity(current.len());
+    for (entity, mut opinion) in &mut query
+    {
+        parameters.neighbors(entity, &sources, &mut contacts);
+        neighbors.clear();
+        neighbors.extend(
+            contacts
+                .iter()
+                .filter_map(|(contact, weight)| Some((*current.get(contact)?, *weight))),
+        );
+
+        let next = parameters.update(opinion.0, &neighbors, &mut *rng);
+        opinion.set_if_neq(Opinion(next));
+        opinions.push(next);
+    }
+
+    if opinions.is_empty()
+    {
+        return;
+    }
+
+    let mut metrics = OpinionMetrics {
+        step: **step_number,
+        ..metrics(&mut opinions)
+    };
+    metrics.consensus = metrics.spread <= parameters.tolerance;
+    outputs.push(metrics.step, metrics);
+}
+
+/// Computes the metrics of a non-empty set of opinions.
+#[allow(clippy::cast_precision_loss)]
+fn metrics(opinions: &mut [f64]) -> OpinionMetrics
+{
+    opinions.sort_by(f64::total_cmp);
+
+    let n = opinions.len() as f64;
+    let mean = opinions.iter().sum::<f64>() / n;
+    let variance = opinions.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+
+    // the sum of the differences between all ordered pairs, from the sorted opinions
+    let differences: f64 = opinions
+        .iter()
+        .enumerate()
+        .map(|(i, x)| x * 2.0f64.mul_add(i as f64, 1.0 - n))
+        .sum();
+
+    OpinionMetrics {
+        step: 0,
+        mean,
+        variance,
+        spread: opinions[opinions.len() - 1] - opinions[0],
+        polarization: 2.0 * differences / (n * n),
+        consensus: false,
+    }
+}
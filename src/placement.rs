@@ -0,0 +1,247 @@
+//! Generators of positions on a 2D grid, for setting up the spatial initial conditions of a simulation.
+//!
+//! All generators produce [`GridPosition2D`] values within the given [`GridBounds2D`].
+//! The random ones draw from the given generator, which would typically be the simulation's
+//! [`crate::SimRng`] passed to a spawner added with [`crate::SimulationBuilder::add_seeded_entity_spawner`],
+//! so that the placement is reproducible.
+//!
+//! Example:
+//! ```
+//! # use incerto::prelude::*;
+//! #[derive(Component)]
+//! struct Cow;
+//!
+//! let bounds = GridBounds2D {
+//!     min: IVec2::new(0, 0),
+//!     max: IVec2::new(99, 99),
+//! };
+//! let simulation = SimulationBuilder::new()
+//!     .add_spatial_grid_2d::<Cow>(Some(bounds))
+//!     .add_seeded_entity_spawner(move |spawner, rng| {
+//!         // three herds of cows
+//!         for position in placement::clustered(&bounds, 3, 100, 4.0, rng)
+//!         {
+//!             spawner.spawn((position, Cow));
+//!         }
+//!     })
+//!     .build();
+//! ```
+
+use std::f32::consts::TAU;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use rand::Rng;
+use rand_distr::{Distribution, Normal, weighted::WeightedIndex};
+
+use crate::{
+    GridLayer,
+    plugins::{GridBounds2D, GridPosition, GridPosition2D},
+};
+
+/// The number of candidates tried around each point by [`poisson_disk`] before giving up on it.
+const POISSON_DISK_ATTEMPTS: usize = 30;
+
+/// Generates `count` positions, each chosen uniformly at random within `bounds`.
+///
+/// The same position may be generated more than once.
+pub fn uniform(bounds: &GridBounds2D, count: usize, rng: &mut impl Rng) -> Vec<GridPosition2D>
+{
+    (0..count).map(|_| random_position(bounds, rng)).collect()
+}
+
+/// Generates `count` positions, grouped in `num_clusters` Gaussian blobs.
+///
+/// The center of each cluster is chosen uniformly at random within `bounds`.
+/// Each position is then generated by choosing one of the clusters at random, and offsetting its
+/// center along each axis by a normally distributed amount with the given `std_dev`.
+/// Positions falling outside of `bounds` are discarded and generated again.
+///
+/// The same position may be generated more than once.
+///
+/// # Panics
+///
+/// If `num_clusters` is `0`, or `std_dev` is negative or not finite.
+pub fn clustered(
+    bounds: &GridBounds2D,
+    num_clusters: usize,
+    count: usize,
+    std_dev: f32,
+    rng: &mut impl Rng,
+) -> Vec<GridPosition2D>
+{
+    assert!(num_clusters > 0);
+    let Ok(offset) = Normal::new(0.0, std_dev)
+    else
+    {
+        panic!("invalid cluster standard deviation: {std_dev}");
+    };
+
+    let centers = uniform(bounds, num_clusters, rng);
+
+    (0..count)
+        .map(|_| {
+            let center = centers[rng.random_range(0..num_clusters)].0.as_vec2();
+            loop
+            {
+                let position =
+                    Vec2::new(center.x + offset.sample(rng), center.y + offset.sample(rng))
+                        .round()
+                        .as_ivec2();
+
+                if bounds.contains(&position)
+                {
+                    break GridPosition(position);
+                }
+            }
+        })
+        .collect()
+}
+
+/// Generates positions within `bounds` that are randomly spread, but no closer than
+/// `min_distance` to each other.
+///
+/// This produces an even, natural-looking placement, like that of trees in a forest or of towns
+/// in a region, using Bridson's algorithm.
+/// The number of positions generated depends on the size of `bounds` and on `min_distance`.
+///
+/// # Panics
+///
+/// If `min_distance` is not larger than `0`.
+pub fn poisson_disk(
+    bounds: &GridBounds2D,
+    min_distance: f32,
+    rng: &mut impl Rng,
+) -> Vec<GridPosition2D>
+{
+    assert!(min_distance > 0.0);
+
+    // with cells as large as the minimum distance, any point closer than that to a candidate
+    // can only lie in the candidate's cell or in one of its immediate neighbors
+    let cell_of = |position: IVec2| (position.as_vec2() / min_distance).floor().as_ivec2();
+    let mut cells: HashMap<IVec2, Vec<IVec2>> = HashMap::default();
+    let is_far_enough = |cells: &HashMap<IVec2, Vec<IVec2>>, candidate: IVec2| {
+        let cell = cell_of(candidate);
+        (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| cell + IVec2::new(dx, dy)))
+            .filter_map(|neighbor| cells.get(&neighbor))
+            .flatten()
+            .all(|&other| candidate.as_vec2().distance(other.as_vec2()) >= min_distance)
+    };
+
+    let first = random_position(bounds, rng).0;
+    cells.entry(cell_of(first)).or_default().push(first);
+    let mut positions = vec![first];
+    let mut active = vec![first];
+
+    while !active.is_empty()
+    {
+        let idx = rng.random_range(0..active.len());
+        let origin = active[idx].as_vec2();
+
+        // try candidates in the annulus between min_distance and 2 * min_distance around the origin
+        let candidate = (0..POISSON_DISK_ATTEMPTS)
+            .map(|_| {
+                let angle = rng.random_range(0.0..TAU);
+                let distance = rng.random_range(min_distance..2.0 * min_distance);
+                (origin + Vec2::from_angle(angle) * distance)
+                    .round()
+                    .as_ivec2()
+            })
+            .find(|&candidate| bounds.contains(&candidate) && is_far_enough(&cells, candidate));
+
+        if let Some(candidate) = candidate
+        {
+            cells.entry(cell_of(candidate)).or_default().push(candidate);
+            positions.push(candidate);
+            active.push(candidate);
+        }
+        else
+        {
+            active.swap_remove(idx);
+        }
+    }
+
+    positions.into_iter().map(GridPosition).collect()
+}
+
+/// Generates up to `count` positions evenly spaced along a ring with the given `center` and `radius`.
+///
+/// Positions are generated in order of their angle around the center.
+/// Positions falling outside of `bounds` are omitted, as are duplicates resulting from the
+/// rounding of positions to the grid, such as when `count` exceeds the circumference of the ring.
+pub fn ring(
+    bounds: &GridBounds2D,
+    center: GridPosition2D,
+    radius: f32,
+    count: usize,
+) -> Vec<GridPosition2D>
+{
+    let center = center.0.as_vec2();
+
+    #[allow(clippy::cast_precision_loss)]
+    let mut positions = (0..count)
+        .map(|i| TAU * i as f32 / count as f32)
+        .map(|angle| {
+            (center + Vec2::from_angle(angle) * radius)
+                .round()
+                .as_ivec2()
+        })
+        .filter(|position| bounds.contains(position))
+        .map(GridPosition)
+        .collect::<Vec<_>>();
+    positions.dedup();
+    if positions.len() > 1 && positions.first() == positions.last()
+    {
+        // the ring wrapped around onto its first position
+        positions.pop();
+    }
+
+    positions
+}
+
+/// Generates `count` positions within the cells of a `layer`, each chosen at random with
+/// probability proportional to the value of its cell, such as the density of the population.
+///
+/// Cells whose value is negative or not finite are never chosen.
+/// The same position may be generated more than once.
+///
+/// # Panics
+///
+/// If `count` is larger than `0` and no cell of the layer has a positive value.
+pub fn weighted(layer: &GridLayer, count: usize, rng: &mut impl Rng) -> Vec<GridPosition2D>
+{
+    if count == 0
+    {
+        return Vec::new();
+    }
+
+    let (positions, weights): (Vec<_>, Vec<_>) = layer
+        .iter()
+        .map(|(position, value)| {
+            let weight = if value.is_finite()
+            {
+                value.max(0.0)
+            }
+            else
+            {
+                0.0
+            };
+            (position, weight)
+        })
+        .unzip();
+    let Ok(cells) = WeightedIndex::new(&weights)
+    else
+    {
+        panic!("no cell of the layer has a positive value");
+    };
+
+    (0..count).map(|_| positions[cells.sample(rng)]).collect()
+}
+
+fn random_position(bounds: &GridBounds2D, rng: &mut impl Rng) -> GridPosition2D
+{
+    GridPosition2D::new(
+        rng.random_range(bounds.min.x..=bounds.max.x),
+        rng.random_range(bounds.min.y..=bounds.max.y),
+    )
+}
@@ -0,0 +1,323 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::plugins::{GridBounds2D, Network, SpatialGrid2D};
+
+/// A named layer of contacts, along with the modifier of the rate of interactions through it.
+struct Layer<C: Component>
+{
+    name: &'static str,
+    modifier: f64,
+    kind: LayerKind<C>,
+}
+
+enum LayerKind<C: Component>
+{
+    /// Contacts between entities in the same and the adjacent cells of a [`SpatialGrid2D<C>`].
+    Spatial(Option<GridBounds2D>),
+
+    /// Contacts along the edges of a network.
+    Network(Network<C>),
+}
+
+/// Resource holding several layers of contacts between entities with the component `C`,
+/// through which they interact simultaneously.
+///
+/// This is the multiplex structure of realistic epidemic and information-diffusion models,
+/// where people come into contact with those nearby, as well as with the members of their
+/// household and their workplace, each at a different rate.
+/// Every layer is either spatial, covering the entities in the same and the adjacent cells of a
+/// [`SpatialGrid2D<C>`], or a network of its own, and has a modifier that scales the rate of
+/// interactions through it, which can be changed as the simulation runs, e.g. to model the
+/// closure of workplaces.
+///
+/// Set up with [`crate::SimulationBuilder::add_contact_layers`], after which the edges of network
+/// layers are added by spawners with [`crate::Spawner::add_layer_edge`], or by systems through a
+/// [`ResMut<ContactLayers<C>>`](ResMut) argument. When an entity is despawned, or loses its
+/// component `C`, all of its edges are removed at the start of the next step.
+///
+/// Example of a population with spatial, household and workplace contacts:
+/// ```
+/// # use incerto::prelude::*;
+/// #[derive(Component)]
+/// struct Person;
+///
+/// let layers = ContactLayers::<Person>::new()
+///     .with_spatial("community", None, 0.1)
+///     .with_network("household", 1.0)
+///     .with_network("workplace", 0.5);
+///
+/// let simulation = SimulationBuilder::new()
+///     .add_contact_layers(layers)
+///     .add_entity_spawner(|spawner| {
+///         let alice = spawner.spawn((Person, GridPosition2D::new(0, 0)));
+///         let bob = spawner.spawn((Person, GridPosition2D::new(5, 5)));
+///         spawner.add_layer_edge::<Person>("household", alice, bob);
+///     })
+///     .build();
+/// ```
+#[derive(Resource)]
+pub struct ContactLayers<C: Component>
+{
+    layers: Vec<Layer<C>>,
+}
+
+impl<C: Component> Default for ContactLayers<C>
+{
+    fn default() -> Self
+    {
+        Self { layers: Vec::new() }
+    }
+}
+
+impl<C: Component> ContactLayers<C>
+{
+    /// Creates a structure without any layers.
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Adds a spatial layer, in which entities with a [`crate::GridPosition`] come into contact
+    /// with those in the same and the adjacent cells of a [`SpatialGrid2D<C>`], which is added
+    /// to the simulation with the given bounds.
+    ///
+    /// # Panics
+    ///
+    /// If a layer with the same name already exists, a spatial layer has already been added,
+    /// or `modifier` is negative or not finite.
+    #[must_use]
+    pub fn with_spatial(
+        mut self,
+        name: &'static str,
+        bounds: Option<GridBounds2D>,
+        modifier: f64,
+    ) -> Self
+    {
+        assert!(
+            !self.has_spatial(),
+            "only one spatial contact layer can be added"
+        );
+
+        self.add(name, modifier, LayerKind::Spatial(bounds));
+        self
+    }
+
+    /// Adds an empty network layer, in which entities come into contact with their neighbors,
+    /// with the rate of interactions scaled by the weight of the edge between them.
+    ///
+    /// # Panics
+    ///
+    /// If a layer with the same name already exists, or `modifier` is negative or not finite.
+    #[must_use]
+    pub fn with_network(mut self, name: &'static str, modifier: f64) -> Self
+    {
+        self.add(name, modifier, LayerKind::Network(Network::new()));
+        self
+    }
+
+    fn add(&mut self, name: &'static str, modifier: f64, kind: LayerKind<C>)
+    {
+        assert!(
+            self.layer(name).is_none(),
+            "duplicate contact layer: {name}"
+        );
+        assert_modifier(modifier);
+
+        self.layers.push(Layer {
+            name,
+            modifier,
+            kind,
+        });
+    }
+
+    fn layer(&self, name: &str) -> Option<&Layer<C>>
+    {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    fn layer_mut(&mut self, name: &str) -> Option<&mut Layer<C>>
+    {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// The names of all layers, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_
+    {
+        self.layers.iter().map(|layer| layer.name)
+    }
+
+    /// The modifier of the rate of interactions through the given layer, if it exists.
+    #[must_use]
+    pub fn modifier(&self, name: &str) -> Option<f64>
+    {
+        self.layer(name).map(|layer| layer.modifier)
+    }
+
+    /// Sets the modifier of the rate of interactions through the given layer, where `0`
+    /// disables it.
+    ///
+    /// # Panics
+    ///
+    /// If there is no layer with the given name, or `modifier` is negative or not finite.
+    pub fn set_modifier(&mut self, name: &str, modifier: f64)
+    {
+        assert_modifier(modifier);
+
+        let Some(layer) = self.layer_mut(name)
+        else
+        {
+            panic!("no contact layer named {name}");
+        };
+        layer.modifier = modifier;
+    }
+
+    /// The network of the given layer, if it exists and is not spatial.
+    #[must_use]
+    pub fn network(&self, name: &str) -> Option<&Network<C>>
+    {
+        match &self.layer(name)?.kind
+        {
+            LayerKind::Network(network) => Some(network),
+            LayerKind::Spatial(_) => None,
+        }
+    }
+
+    /// The network of the given layer, if it exists and is not spatial, for adding or removing
+    /// edges.
+    #[must_use]
+    pub fn network_mut(&mut self, name: &str) -> Option<&mut Network<C>>
+    {
+        match &mut self.layer_mut(name)?.kind
+        {
+            LayerKind::Network(network) => Some(network),
+            LayerKind::Spatial(_) => None,
+        }
+    }
+
+    /// Checks whether a spatial layer has been added.
+    pub(crate) fn has_spatial(&self) -> bool
+    {
+        self.layers
+            .iter()
+            .any(|layer| matches!(layer.kind, LayerKind::Spatial(_)))
+    }
+
+    /// The bounds of the spatial grid, if a spatial layer with bounds has been added.
+    pub(crate) fn spatial_bounds(&self) -> Option<GridBounds2D>
+    {
+        self.layers.iter().find_map(|layer| match layer.kind
+        {
+            LayerKind::Spatial(bounds) => bounds,
+            LayerKind::Network(_) => None,
+        })
+    }
+
+    /// Collects the contacts of an entity across all layers into the `buffer`, along with the
+    /// weight of every contact, which is the modifier of its layer multiplied by the weight of
+    /// the edge in network layers.
+    ///
+    /// Entities in contact through several layers appear once for each of them, while layers
+    /// with a modifier of `0` are skipped.
+    /// The `grid` is the [`SpatialGrid2D<C>`] of the simulation, used by the spatial layer.
+    pub fn contacts_of(
+        &self,
+        entity: Entity,
+        grid: Option<&SpatialGrid2D<C>>,
+        buffer: &mut Vec<(Entity, f64)>,
+    )
+    {
+        buffer.clear();
+        for layer in self.layers.iter().filter(|layer| layer.modifier > 0.0)
+        {
+            match &layer.kind
+            {
+                LayerKind::Spatial(_) =>
+                {
+                    let Some((grid, position)) =
+                        grid.and_then(|grid| Some((grid, grid.position_of(entity)?)))
+                    else
+                    {
+                        continue;
+                    };
+
+                    buffer.extend(
+                        grid.entities_at(&position)
+                            .chain(grid.neighbors_of(&position))
+                            .filter(|&contact| contact != entity)
+                            .map(|contact| (contact, layer.modifier)),
+                    );
+                }
+                LayerKind::Network(network) =>
+                {
+                    buffer.extend(
+                        network
+                            .weighted_neighbors_of(entity)
+                            .map(|(contact, weight)| (contact, layer.modifier * weight)),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn assert_modifier(modifier: f64)
+{
+    assert!(
+        modifier.is_finite() && modifier >= 0.0,
+        "invalid contact layer modifier: {modifier}"
+    );
+}
+
+/// The structures through which entities with the component `C` may come into contact,
+/// for the prefab modules that support several of them.
+#[derive(SystemParam)]
+pub struct ContactSources<'w, C: Component>
+{
+    pub grid: Option<Res<'w, SpatialGrid2D<C>>>,
+    pub network: Option<Res<'w, Network<C>>>,
+    pub layers: Option<Res<'w, ContactLayers<C>>>,
+}
+
+/// Plugin that maintains the [`ContactLayers`] between entities with the component `C`.
+pub struct ContactLayersPlugin<C: Component>
+{
+    _phantom: std::marker::PhantomData<C>,
+}
+
+impl<C: Component> Default for ContactLayersPlugin<C>
+{
+    fn default() -> Self
+    {
+        Self {
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<C: Component> Plugin for ContactLayersPlugin<C>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<ContactLayers<C>>();
+        app.add_systems(PreUpdate, contact_layers_cleanup_system::<C>);
+    }
+}
+
+/// System that removes entities from the network layers when they no longer have the component `C`.
+fn contact_layers_cleanup_system<C: Component>(
+    mut layers: ResMut<ContactLayers<C>>,
+    mut removed: RemovedComponents<C>,
+)
+{
+    for entity in removed.read()
+    {
+        for layer in &mut layers.layers
+        {
+            if let LayerKind::Network(network) = &mut layer.kind
+            {
+                network.remove_node(entity);
+            }
+        }
+    }
+}
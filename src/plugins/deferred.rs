@@ -0,0 +1,165 @@
+use bevy::{ecs::bundle::NoBundleEffect, prelude::*};
+
+use crate::trace::trace_span;
+
+/// System set in which deferred spawns and despawns are applied, at the beginning of [`PostUpdate`].
+///
+/// This happens after all user-defined systems have run, and before the recording of time series.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeferredFlush;
+
+/// Resource collecting entities to be despawned in a single batch.
+///
+/// Set up using [`crate::SimulationBuilder::add_deferred_despawn`], and accessible in user-defined
+/// systems using [`ResMut<DeferredDespawn>`] arguments.
+///
+/// All entities pushed during a step are despawned together in the [`DeferredFlush`] set,
+/// directly on the world. This avoids the overhead of queueing and applying a separate command
+/// for each entity, which adds up in simulations where large numbers of entities die every step.
+///
+/// Entities that have already been despawned by the time of the flush are ignored.
+#[derive(Resource, Debug, Default)]
+pub struct DeferredDespawn(Vec<Entity>);
+
+impl DeferredDespawn
+{
+    /// Schedules the `entity` to be despawned.
+    pub fn push(&mut self, entity: Entity)
+    {
+        self.0.push(entity);
+    }
+
+    /// The number of entities scheduled to be despawned.
+    #[must_use]
+    pub const fn len(&self) -> usize
+    {
+        self.0.len()
+    }
+
+    /// Returns `true` if no entities are scheduled to be despawned.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool
+    {
+        self.0.is_empty()
+    }
+
+    fn flush(world: &mut World)
+    {
+        trace_span!(world, DEBUG, "deferred_despawn");
+
+        let mut entities = std::mem::take(&mut world.resource_mut::<Self>().0);
+        for &entity in &entities
+        {
+            // the entity may have been pushed more than once, or despawned by other means
+            world.try_despawn(entity).ok();
+        }
+        entities.clear();
+
+        // hand the allocation back, so that it can be reused in the next step
+        world.resource_mut::<Self>().0 = entities;
+    }
+}
+
+impl Extend<Entity> for DeferredDespawn
+{
+    fn extend<I: IntoIterator<Item = Entity>>(&mut self, iter: I)
+    {
+        self.0.extend(iter);
+    }
+}
+
+/// Resource collecting bundles of type `B` to be spawned in a single batch.
+///
+/// Set up using [`crate::SimulationBuilder::add_deferred_spawn`], and accessible in user-defined
+/// systems using [`ResMut<DeferredSpawn<B>>`] arguments.
+///
+/// All bundles pushed during a step are spawned together in the [`DeferredFlush`] set,
+/// using [`World::spawn_batch`], which allocates the storage for all of them at once.
+#[derive(Resource, Debug)]
+pub struct DeferredSpawn<B: Bundle>(Vec<B>);
+
+impl<B: Bundle> Default for DeferredSpawn<B>
+{
+    fn default() -> Self
+    {
+        Self(Vec::new())
+    }
+}
+
+impl<B> DeferredSpawn<B>
+where
+    B: Bundle<Effect: NoBundleEffect>,
+{
+    /// Schedules an entity with the given `bundle` to be spawned.
+    pub fn push(&mut self, bundle: B)
+    {
+        self.0.push(bundle);
+    }
+
+    /// The number of entities scheduled to be spawned.
+    #[must_use]
+    pub const fn len(&self) -> usize
+    {
+        self.0.len()
+    }
+
+    /// Returns `true` if no entities are scheduled to be spawned.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool
+    {
+        self.0.is_empty()
+    }
+
+    fn flush(world: &mut World)
+    {
+        trace_span!(world, DEBUG, "deferred_spawn");
+
+        let bundles = std::mem::take(&mut world.resource_mut::<Self>().0);
+        if !bundles.is_empty()
+        {
+            world.spawn_batch(bundles);
+        }
+    }
+}
+
+impl<B: Bundle> Extend<B> for DeferredSpawn<B>
+{
+    fn extend<I: IntoIterator<Item = B>>(&mut self, iter: I)
+    {
+        self.0.extend(iter);
+    }
+}
+
+pub struct DeferredDespawnPlugin;
+
+impl Plugin for DeferredDespawnPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<DeferredDespawn>();
+
+        app.add_systems(PostUpdate, DeferredDespawn::flush.in_set(DeferredFlush));
+    }
+}
+
+pub struct DeferredSpawnPlugin<B: Bundle>(std::marker::PhantomData<B>);
+
+impl<B: Bundle> Default for DeferredSpawnPlugin<B>
+{
+    fn default() -> Self
+    {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<B> Plugin for DeferredSpawnPlugin<B>
+where
+    B: Bundle<Effect: NoBundleEffect>,
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<DeferredSpawn<B>>();
+
+        app.add_systems(PostUpdate, DeferredSpawn::<B>::flush.in_set(DeferredFlush));
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+
+use crate::plugins::{StepNumber, step_number::step_counter_increment};
+
+/// A log of all events of type `E` that were emitted during a simulation,
+/// along with the step in which each one was emitted.
+///
+/// Recorded using [`crate::SimulationBuilder::record_events`], and retrieved using
+/// [`crate::Simulation::event_log`].
+/// A log can then be replayed in a new simulation using [`crate::SimulationBuilder::replay_events`].
+///
+/// With the `serde` feature enabled, the log may also be serialized for later use, if `E`
+/// implements `Serialize`.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EventLog<E>
+{
+    events: Vec<(usize, E)>,
+}
+
+impl<E> Default for EventLog<E>
+{
+    fn default() -> Self
+    {
+        Self { events: Vec::new() }
+    }
+}
+
+impl<E> EventLog<E>
+{
+    /// The number of events in the log.
+    #[must_use]
+    pub const fn len(&self) -> usize
+    {
+        self.events.len()
+    }
+
+    /// Returns `true` if the log has no events.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool
+    {
+        self.events.is_empty()
+    }
+
+    /// Iterates over each event in the log, along with the step in which it was emitted.
+    ///
+    /// Events are iterated in the order in which they were emitted.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &E)>
+    {
+        self.events.iter().map(|(step, event)| (*step, event))
+    }
+}
+
+impl<E: Event + Clone> EventLog<E>
+{
+    fn record(mut log: ResMut<Self>, mut events: EventReader<E>, step_number: Res<StepNumber>)
+    {
+        log.events
+            .extend(events.read().map(|event| (**step_number, event.clone())));
+    }
+}
+
+pub struct EventRecorderPlugin<E>(std::marker::PhantomData<E>);
+
+impl<E> Default for EventRecorderPlugin<E>
+{
+    fn default() -> Self
+    {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<E: Event + Clone> Plugin for EventRecorderPlugin<E>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_event::<E>();
+        app.insert_resource(EventLog::<E>::default());
+
+        // record at the very end of the step, so that events emitted at any point are included
+        app.add_systems(Last, EventLog::<E>::record.before(step_counter_increment));
+    }
+}
+
+/// Resource holding an event log that is being replayed, and the position in it.
+#[derive(Resource)]
+struct EventReplay<E>
+{
+    log: EventLog<E>,
+    cursor: usize,
+}
+
+impl<E: Event + Clone> EventReplay<E>
+{
+    fn replay(mut replay: ResMut<Self>, mut events: EventWriter<E>, step_number: Res<StepNumber>)
+    {
+        let replay = &mut *replay;
+        while let Some((step, event)) = replay.log.events.get(replay.cursor)
+        {
+            if *step > **step_number
+            {
+                break;
+            }
+
+            events.write(event.clone());
+            replay.cursor += 1;
+        }
+    }
+}
+
+pub struct EventReplayPlugin<E>(EventLog<E>);
+
+impl<E> EventReplayPlugin<E>
+{
+    pub const fn new(log: EventLog<E>) -> Self
+    {
+        Self(log)
+    }
+}
+
+impl<E: Event + Clone> Plugin for EventReplayPlugin<E>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_event::<E>();
+        app.insert_resource(EventReplay {
+            log: self.0.clone(),
+            cursor: 0,
+        });
+
+        // replay before any user-defined systems, so that the events can be read in the same step
+        app.add_systems(PreUpdate, EventReplay::<E>::replay);
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::plugins::StepNumber;
+
+/// Resource holding events of type `E` that are scheduled to be emitted at a future step.
+///
+/// Set up using [`crate::SimulationBuilder::add_future_events`], and accessible in user-defined
+/// systems using [`ResMut<FutureEvents<E>>`] arguments.
+///
+/// Scheduled events are emitted at the beginning of their step, before any user-defined systems
+/// run, so that they can be read with an [`EventReader<E>`] in the same step. Events scheduled
+/// for the same step are emitted in the order they were scheduled.
+///
+/// This replaces the countdown timers that would otherwise be stored in components and
+/// decremented on every step, such as incubation periods or quarantine durations.
+#[derive(Resource, Debug)]
+pub struct FutureEvents<E>
+{
+    /// The events waiting to be emitted, by the step in which they will be.
+    pending: BTreeMap<usize, Vec<E>>,
+    /// The number of the current step.
+    step: usize,
+}
+
+impl<E> FutureEvents<E>
+{
+    const fn new(step: usize) -> Self
+    {
+        Self {
+            pending: BTreeMap::new(),
+            step,
+        }
+    }
+
+    /// Schedules the `event` to be emitted `delay` steps after the current one.
+    ///
+    /// Events scheduled during the setup of the simulation, such as in entity spawners,
+    /// count their delay from before the first step, so that a delay of `1` emits them in it.
+    ///
+    /// A `delay` of `0` emits the event at the beginning of the next step, since that of the
+    /// current one has already passed.
+    pub fn schedule_in(&mut self, delay: usize, event: E)
+    {
+        self.schedule_at(self.step + delay, event);
+    }
+
+    /// Schedules the `event` to be emitted at the given step, as read from [`StepNumber`].
+    ///
+    /// Events scheduled for the current or a past step are emitted at the beginning of the next step.
+    pub fn schedule_at(&mut self, step: usize, event: E)
+    {
+        self.pending
+            .entry(step.max(self.step + 1))
+            .or_default()
+            .push(event);
+    }
+
+    /// The number of events waiting to be emitted.
+    #[must_use]
+    pub fn len(&self) -> usize
+    {
+        self.pending.values().map(Vec::len).sum()
+    }
+
+    /// Returns `true` if no events are waiting to be emitted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool
+    {
+        self.pending.is_empty()
+    }
+
+    /// The step in which the next scheduled events will be emitted, if there are any.
+    #[must_use]
+    pub fn next_step(&self) -> Option<usize>
+    {
+        self.pending.keys().next().copied()
+    }
+}
+
+impl<E: Event> FutureEvents<E>
+{
+    fn emit(mut future: ResMut<Self>, mut events: EventWriter<E>, step_number: Res<StepNumber>)
+    {
+        let future = &mut *future;
+        future.step = **step_number;
+
+        let later = future.pending.split_off(&(future.step + 1));
+        for (_, due) in std::mem::replace(&mut future.pending, later)
+        {
+            events.write_batch(due);
+        }
+    }
+}
+
+pub struct FutureEventsPlugin<E>(std::marker::PhantomData<E>);
+
+impl<E> Default for FutureEventsPlugin<E>
+{
+    fn default() -> Self
+    {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<E: Event> Plugin for FutureEventsPlugin<E>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_event::<E>();
+
+        let step = app.world().resource::<StepNumber>().get();
+        app.insert_resource(FutureEvents::<E>::new(step.saturating_sub(1)));
+
+        // emit before any user-defined systems, so that the events can be read in the same step
+        app.add_systems(PreUpdate, FutureEvents::<E>::emit);
+    }
+}
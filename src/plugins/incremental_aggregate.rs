@@ -0,0 +1,127 @@
+use std::marker::PhantomData;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{AsF64, Sample, plugins::DeferredFlush};
+
+/// Resource maintaining a running sum, count and mean of the values of type `O`
+/// sampled from components `C`.
+///
+/// Set up using [`crate::SimulationBuilder::track_incremental_aggregate`], and retrieved using
+/// [`crate::Simulation::incremental_aggregate`].
+///
+/// Instead of scanning all entities whenever it is sampled, the aggregate is updated at the end
+/// of every step only from the components that were added, changed or removed during it.
+/// This makes it much cheaper than [`crate::Simulation::sample_aggregate`] in simulations where
+/// only a small fraction of the entities change per step.
+/// Note that components added or changed outside of a step, such as by the spawners when
+/// building the simulation, are only accounted for at the end of the next step.
+///
+/// The values are accumulated as [`f64`], so the sum is exact for integer values as long as it
+/// stays below 2<sup>53</sup>, while floating point rounding errors may accumulate over
+/// very long runs.
+#[derive(Resource, Debug)]
+pub struct IncrementalAggregate<C, O>
+{
+    values: HashMap<Entity, f64>,
+    sum: f64,
+    _phantom: PhantomData<(C, O)>,
+}
+
+impl<C, O> IncrementalAggregate<C, O>
+{
+    fn new() -> Self
+    {
+        Self {
+            values: HashMap::default(),
+            sum: 0.0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The sum of the values of all components `C`.
+    #[must_use]
+    pub const fn sum(&self) -> f64
+    {
+        self.sum
+    }
+
+    /// The number of components `C`.
+    #[must_use]
+    pub fn count(&self) -> usize
+    {
+        self.values.len()
+    }
+
+    /// The mean of the values of all components `C`, or `None` if there are none.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean(&self) -> Option<f64>
+    {
+        (!self.values.is_empty()).then(|| self.sum / self.values.len() as f64)
+    }
+
+    fn set(&mut self, entity: Entity, value: f64)
+    {
+        let previous = self.values.insert(entity, value).unwrap_or(0.0);
+        self.sum += value - previous;
+    }
+
+    fn remove(&mut self, entity: Entity)
+    {
+        if let Some(previous) = self.values.remove(&entity)
+        {
+            self.sum -= previous;
+        }
+    }
+}
+
+pub struct IncrementalAggregatePlugin<C, O>(PhantomData<(C, O)>);
+
+impl<C, O> Default for IncrementalAggregatePlugin<C, O>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+impl<C, O> Plugin for IncrementalAggregatePlugin<C, O>
+where
+    C: Sample<O>,
+    O: AsF64 + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App)
+    {
+        app.insert_resource(IncrementalAggregate::<C, O>::new());
+
+        app.add_systems(
+            PostUpdate,
+            incremental_aggregate_update::<C, O>.after(DeferredFlush),
+        )
+        .add_observer(incremental_aggregate_removed::<C, O>);
+    }
+}
+
+fn incremental_aggregate_update<C, O>(
+    mut aggregate: ResMut<IncrementalAggregate<C, O>>,
+    query: Query<(Entity, &C), Changed<C>>,
+) where
+    C: Sample<O>,
+    O: AsF64 + Send + Sync + 'static,
+{
+    for (entity, component) in &query
+    {
+        aggregate.set(entity, C::sample(component).as_f64());
+    }
+}
+
+fn incremental_aggregate_removed<C, O>(
+    trigger: Trigger<OnRemove, C>,
+    mut aggregate: ResMut<IncrementalAggregate<C, O>>,
+) where
+    C: Sample<O>,
+    O: AsF64 + Send + Sync + 'static,
+{
+    aggregate.remove(trigger.target());
+}
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+use crate::MemoryReport;
+
+type MemoryReporter = fn(&World, &mut MemoryReport);
+
+/// Resource holding a function for each plugin whose memory usage is included in a [`MemoryReport`].
+#[derive(Resource, Default)]
+pub struct MemoryReporters(Vec<MemoryReporter>);
+
+impl MemoryReporters
+{
+    /// Registers a function that adds the memory usage of some subsystem to a report.
+    pub fn register(app: &mut App, reporter: MemoryReporter)
+    {
+        app.world_mut()
+            .get_resource_or_init::<Self>()
+            .0
+            .push(reporter);
+    }
+
+    /// Compiles a report of the memory used by the simulation in the given world.
+    pub fn report(world: &World) -> MemoryReport
+    {
+        let mut report = MemoryReport {
+            entities: world.entities().len() as usize,
+            archetypes: world.archetypes().len(),
+            ..default()
+        };
+
+        if let Some(reporters) = world.get_resource::<Self>()
+        {
+            for reporter in &reporters.0
+            {
+                reporter(world, &mut report);
+            }
+        }
+
+        report
+    }
+}
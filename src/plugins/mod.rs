@@ -1,13 +1,53 @@
 mod step_number;
-pub use step_number::{StepNumber, StepNumberPlugin};
+pub use step_number::{StepNumber, StepNumberPlugin, step_counter_increment};
+
+mod contact_layers;
+pub use contact_layers::{ContactLayers, ContactLayersPlugin, ContactSources};
+
+mod deferred;
+pub use deferred::{
+    DeferredDespawn, DeferredDespawnPlugin, DeferredFlush, DeferredSpawn, DeferredSpawnPlugin,
+};
+
+mod event_log;
+pub use event_log::{EventLog, EventRecorderPlugin, EventReplayPlugin};
+
+mod future_events;
+pub use future_events::{FutureEvents, FutureEventsPlugin};
+
+mod incremental_aggregate;
+pub use incremental_aggregate::{IncrementalAggregate, IncrementalAggregatePlugin};
+
+mod memory_reporters;
+pub use memory_reporters::MemoryReporters;
+
+mod network;
+pub use network::{Network, NetworkPlugin};
+
+mod numeric_guard;
+pub use numeric_guard::{NumericGuardPlugin, NumericGuardViolation};
+
+mod population_ledger;
+pub use population_ledger::{PopulationLedger, PopulationLedgerPlugin};
+
+mod scheduled_spawners;
+pub use scheduled_spawners::{ScheduledSpawners, ScheduledSpawnersPlugin, SpawnSchedule};
+
+mod refill_spawners;
+pub use refill_spawners::{RefillSpawners, RefillSpawnersPlugin};
+
+mod step_hooks;
+pub use step_hooks::{StepEndHooks, run_step_end_hooks};
 
 mod time_series;
+#[cfg(any(feature = "tensorboard", feature = "websocket"))]
+pub use time_series::aggregate_time_series_points;
 pub use time_series::{
     AggregateTimeSeriesPlugin, SampleInterval, TimeSeriesData, TimeSeriesPlugin,
 };
 
 mod spatial_grid;
 pub use spatial_grid::{
-    GridBounds, GridBounds2D, GridBounds3D, GridCoordinates, GridPosition, GridPosition2D,
-    GridPosition3D, SpatialGrid, SpatialGrid2D, SpatialGrid3D, SpatialGridPlugin,
+    BoundsViolation, GridBounds, GridBounds2D, GridBounds3D, GridCoordinates, GridPosition,
+    GridPosition2D, GridPosition3D, SpatialGrid, SpatialGrid2D, SpatialGrid3D, SpatialGridPlugin,
 };
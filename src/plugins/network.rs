@@ -0,0 +1,278 @@
+use bevy::{ecs::entity::EntityHashMap, prelude::*};
+
+use crate::{MemoryReport, plugins::MemoryReporters};
+
+/// Resource that maintains an undirected network of relations between entities with the
+/// component `C`.
+///
+/// Networks represent interactions that do not follow the geometry of a grid, such as the
+/// contacts of people or the trade links between firms.
+/// Multiple networks can coexist, one for each component type `C`.
+///
+/// Every edge connects two distinct entities and carries a weight, which is `1.0` unless set
+/// otherwise. The neighbors of an entity are listed in the order their edges were added,
+/// so that simulations iterating over them remain reproducible.
+///
+/// Edges are added by spawners with [`crate::Spawner::add_edge`], or by systems through
+/// a [`ResMut<Network<C>>`](ResMut) argument. When an entity is despawned, or loses its
+/// component `C`, all of its edges are removed at the start of the next step.
+#[derive(Resource)]
+pub struct Network<C: Component>
+{
+    /// Maps every entity to its neighbors, along with the weights of the edges to them.
+    adjacency: EntityHashMap<Vec<(Entity, f64)>>,
+    /// The total number of edges in the network.
+    num_edges: usize,
+    /// Phantom data to maintain type association with component C.
+    _phantom: std::marker::PhantomData<C>,
+}
+
+impl<C: Component> Default for Network<C>
+{
+    fn default() -> Self
+    {
+        Self {
+            adjacency: EntityHashMap::default(),
+            num_edges: 0,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<C: Component> Network<C>
+{
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Adds the number of nodes and the estimated bytes allocated by the network to a memory report.
+    fn report_memory(world: &World, report: &mut MemoryReport)
+    {
+        let Some(network) = world.get_resource::<Self>()
+        else
+        {
+            return;
+        };
+
+        report.network_nodes += network.adjacency.len();
+        report.network_bytes += network.adjacency.capacity()
+            * size_of::<(Entity, Vec<(Entity, f64)>)>()
+            + network
+                .adjacency
+                .values()
+                .map(|edges| edges.capacity() * size_of::<(Entity, f64)>())
+                .sum::<usize>();
+    }
+
+    /// Adds an edge of weight `1.0` between two entities, see [`Self::add_weighted_edge`].
+    pub fn add_edge(&mut self, a: Entity, b: Entity)
+    {
+        self.add_weighted_edge(a, b, 1.0);
+    }
+
+    /// Adds an edge with the given `weight` between two entities,
+    /// or updates the weight of the edge if it already exists.
+    ///
+    /// Edges from an entity to itself are ignored.
+    pub fn add_weighted_edge(&mut self, a: Entity, b: Entity, weight: f64)
+    {
+        if a == b
+        {
+            return;
+        }
+
+        if let Some(edge) = self.edge_mut(a, b)
+        {
+            edge.1 = weight;
+            if let Some(edge) = self.edge_mut(b, a)
+            {
+                edge.1 = weight;
+            }
+            return;
+        }
+
+        self.adjacency.entry(a).or_default().push((b, weight));
+        self.adjacency.entry(b).or_default().push((a, weight));
+        self.num_edges += 1;
+    }
+
+    /// Removes the edge between two entities.
+    ///
+    /// Returns the weight of the edge, if it existed.
+    pub fn remove_edge(&mut self, a: Entity, b: Entity) -> Option<f64>
+    {
+        let weight = self.detach(a, b)?;
+        self.detach(b, a);
+        self.num_edges -= 1;
+        Some(weight)
+    }
+
+    /// Removes an entity from the network, along with all of its edges.
+    ///
+    /// This is done automatically for entities that are despawned or lose their component `C`.
+    pub fn remove_node(&mut self, entity: Entity)
+    {
+        let Some(edges) = self.adjacency.remove(&entity)
+        else
+        {
+            return;
+        };
+
+        for (neighbor, _) in &edges
+        {
+            self.detach(*neighbor, entity);
+        }
+        self.num_edges -= edges.len();
+    }
+
+    /// Checks whether there is an edge between two entities.
+    #[must_use]
+    pub fn has_edge(&self, a: Entity, b: Entity) -> bool
+    {
+        self.weight(a, b).is_some()
+    }
+
+    /// Returns the weight of the edge between two entities, if there is one.
+    #[must_use]
+    pub fn weight(&self, a: Entity, b: Entity) -> Option<f64>
+    {
+        self.edges(a)
+            .iter()
+            .find(|(neighbor, _)| *neighbor == b)
+            .map(|(_, weight)| *weight)
+    }
+
+    /// Get all neighbors of an entity, which are the entities it shares an edge with.
+    pub fn neighbors_of(&self, entity: Entity) -> impl Iterator<Item = Entity> + '_
+    {
+        self.edges(entity).iter().map(|(neighbor, _)| *neighbor)
+    }
+
+    /// Get all neighbors of an entity, along with the weights of the edges to them.
+    pub fn weighted_neighbors_of(&self, entity: Entity)
+    -> impl Iterator<Item = (Entity, f64)> + '_
+    {
+        self.edges(entity).iter().copied()
+    }
+
+    /// Returns the number of edges of an entity.
+    #[must_use]
+    pub fn degree(&self, entity: Entity) -> usize
+    {
+        self.edges(entity).len()
+    }
+
+    /// Returns the sum of the weights of the edges of an entity, also known as its strength.
+    #[must_use]
+    pub fn weighted_degree(&self, entity: Entity) -> f64
+    {
+        self.edges(entity).iter().map(|(_, weight)| weight).sum()
+    }
+
+    /// Returns the number of entities with each degree, indexed by the degree,
+    /// among the entities with at least one edge.
+    #[must_use]
+    pub fn degree_distribution(&self) -> Vec<usize>
+    {
+        let mut distribution = Vec::new();
+        for edges in self.adjacency.values()
+        {
+            if distribution.len() <= edges.len()
+            {
+                distribution.resize(edges.len() + 1, 0);
+            }
+            distribution[edges.len()] += 1;
+        }
+        distribution
+    }
+
+    /// Iterates over all entities with at least one edge, in no particular order.
+    pub fn nodes(&self) -> impl Iterator<Item = Entity> + '_
+    {
+        self.adjacency.keys().copied()
+    }
+
+    /// Get total number of entities with at least one edge.
+    #[must_use]
+    pub fn num_nodes(&self) -> usize
+    {
+        self.adjacency.len()
+    }
+
+    /// Get total number of edges in the network.
+    #[must_use]
+    pub const fn num_edges(&self) -> usize
+    {
+        self.num_edges
+    }
+
+    fn edges(&self, entity: Entity) -> &[(Entity, f64)]
+    {
+        self.adjacency.get(&entity).map_or(&[], Vec::as_slice)
+    }
+
+    fn edge_mut(&mut self, a: Entity, b: Entity) -> Option<&mut (Entity, f64)>
+    {
+        self.adjacency
+            .get_mut(&a)?
+            .iter_mut()
+            .find(|(neighbor, _)| *neighbor == b)
+    }
+
+    /// Removes `b` from the neighbors of `a`, returning the weight of the edge if it existed.
+    ///
+    /// Entities left without any edges are removed from the network.
+    fn detach(&mut self, a: Entity, b: Entity) -> Option<f64>
+    {
+        let edges = self.adjacency.get_mut(&a)?;
+        let index = edges.iter().position(|(neighbor, _)| *neighbor == b)?;
+        let (_, weight) = edges.remove(index);
+
+        if edges.is_empty()
+        {
+            self.adjacency.remove(&a);
+        }
+        Some(weight)
+    }
+}
+
+/// Plugin that maintains a [`Network`] between entities with the component `C`.
+pub struct NetworkPlugin<C: Component>
+{
+    _phantom: std::marker::PhantomData<C>,
+}
+
+impl<C: Component> Default for NetworkPlugin<C>
+{
+    fn default() -> Self
+    {
+        Self {
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<C: Component> Plugin for NetworkPlugin<C>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<Network<C>>();
+        MemoryReporters::register(app, Network::<C>::report_memory);
+
+        app.add_systems(PreUpdate, network_cleanup_system::<C>);
+    }
+}
+
+/// System that removes entities from the network when they no longer have the component `C`.
+fn network_cleanup_system<C: Component>(
+    mut network: ResMut<Network<C>>,
+    mut removed: RemovedComponents<C>,
+)
+{
+    for entity in removed.read()
+    {
+        network.remove_node(entity);
+    }
+}
@@ -0,0 +1,100 @@
+use std::{any::type_name, marker::PhantomData, ops::ControlFlow};
+
+use bevy::prelude::*;
+
+use crate::{
+    NumericGuardError, Sample,
+    plugins::{StepEndHooks, StepNumber, step_number::step_counter_increment},
+};
+
+/// Resource holding the first violation caught by any numeric guard in the simulation.
+#[derive(Resource, Default)]
+pub struct NumericGuardViolation(pub Option<NumericGuardError>);
+
+/// Plugin that checks the values of type `O` sampled from components `C` at the end of every
+/// step, and stops the simulation as soon as any of them is `NaN` or infinite.
+pub struct NumericGuardPlugin<C, O>(PhantomData<(C, O)>);
+
+impl<C, O> Default for NumericGuardPlugin<C, O>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+impl<C, O> NumericGuardPlugin<C, O>
+where
+    C: Sample<O>,
+    O: Into<f64> + Send + Sync + 'static,
+{
+    fn check(
+        query: Query<(Entity, &C)>,
+        step_number: Res<StepNumber>,
+        mut violation: ResMut<NumericGuardViolation>,
+    )
+    {
+        if violation.0.is_some()
+        {
+            return;
+        }
+
+        let component = type_name::<C>();
+        let step = step_number.get();
+
+        violation.0 = query.iter().find_map(|(entity, c)| {
+            let value: f64 = C::sample(c).into();
+            if value.is_nan()
+            {
+                Some(NumericGuardError::NotANumber {
+                    component,
+                    entity,
+                    step,
+                })
+            }
+            else if value.is_infinite()
+            {
+                Some(NumericGuardError::Infinite {
+                    component,
+                    entity,
+                    step,
+                    negative: value.is_sign_negative(),
+                })
+            }
+            else
+            {
+                None
+            }
+        });
+    }
+}
+
+impl<C, O> Plugin for NumericGuardPlugin<C, O>
+where
+    C: Sample<O>,
+    O: Into<f64> + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App)
+    {
+        if !app.world().contains_resource::<NumericGuardViolation>()
+        {
+            app.init_resource::<NumericGuardViolation>();
+
+            // stop the run as soon as any guard catches a violation
+            app.world_mut()
+                .get_resource_or_init::<StepEndHooks>()
+                .push(|world, _| {
+                    if world.resource::<NumericGuardViolation>().0.is_some()
+                    {
+                        ControlFlow::Break(())
+                    }
+                    else
+                    {
+                        ControlFlow::Continue(())
+                    }
+                });
+        }
+
+        app.add_systems(Last, Self::check.before(step_counter_increment));
+    }
+}
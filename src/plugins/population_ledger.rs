@@ -0,0 +1,90 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+/// Resource tracking the cumulative number of entities carrying the component `C`
+/// that have been spawned and despawned in the simulation.
+///
+/// Set up using [`crate::SimulationBuilder::track_population`], and retrieved using
+/// [`crate::Simulation::ledger`].
+///
+/// Note that adding or removing the component `C` to or from an existing entity also counts
+/// as a spawn or despawn respectively.
+#[derive(Resource, Debug)]
+pub struct PopulationLedger<C: Component>
+{
+    spawned: usize,
+    despawned: usize,
+    _phantom: PhantomData<C>,
+}
+
+impl<C: Component> PopulationLedger<C>
+{
+    const fn new() -> Self
+    {
+        Self {
+            spawned: 0,
+            despawned: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The total number of entities with the component `C` that have been spawned,
+    /// including those spawned initially with [`crate::SimulationBuilder::add_entity_spawner`].
+    #[must_use]
+    pub const fn spawned(&self) -> usize
+    {
+        self.spawned
+    }
+
+    /// The total number of entities with the component `C` that have been despawned.
+    #[must_use]
+    pub const fn despawned(&self) -> usize
+    {
+        self.despawned
+    }
+
+    /// The number of entities with the component `C` currently alive in the simulation.
+    #[must_use]
+    pub const fn alive(&self) -> usize
+    {
+        self.spawned - self.despawned
+    }
+}
+
+pub struct PopulationLedgerPlugin<C: Component>(PhantomData<C>);
+
+impl<C: Component> Default for PopulationLedgerPlugin<C>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+impl<C: Component> Plugin for PopulationLedgerPlugin<C>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.insert_resource(PopulationLedger::<C>::new());
+
+        app.add_observer(population_ledger_spawned::<C>)
+            .add_observer(population_ledger_despawned::<C>);
+    }
+}
+
+fn population_ledger_spawned<C: Component>(
+    _trigger: Trigger<OnAdd, C>,
+    mut ledger: ResMut<PopulationLedger<C>>,
+)
+{
+    ledger.spawned += 1;
+}
+
+fn population_ledger_despawned<C: Component>(
+    _trigger: Trigger<OnRemove, C>,
+    mut ledger: ResMut<PopulationLedger<C>>,
+)
+{
+    ledger.despawned += 1;
+}
@@ -0,0 +1,97 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{SimRng, plugins::ScheduledSpawners, spawner::Spawner, trace::trace_span};
+
+type RefillSpawnFn = Box<dyn FnMut(&mut Spawner, usize, &mut SimRng) + Send + Sync>;
+
+/// A spawner that tops up the population of entities with component `C`.
+struct RefillSpawner
+{
+    threshold: usize,
+    target: usize,
+    spawn_fn: RefillSpawnFn,
+}
+
+/// Resource holding the spawners that refill the population of entities with component `C`.
+#[derive(Resource)]
+pub struct RefillSpawners<C: Component>
+{
+    spawners: Vec<RefillSpawner>,
+    _phantom: PhantomData<C>,
+}
+
+impl<C: Component> Default for RefillSpawners<C>
+{
+    fn default() -> Self
+    {
+        Self {
+            spawners: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<C: Component> RefillSpawners<C>
+{
+    pub fn push(
+        &mut self,
+        threshold: usize,
+        target: usize,
+        spawn_fn: impl FnMut(&mut Spawner, usize, &mut SimRng) + Send + Sync + 'static,
+    )
+    {
+        self.spawners.push(RefillSpawner {
+            threshold,
+            target,
+            spawn_fn: Box::new(spawn_fn),
+        });
+    }
+
+    fn run(world: &mut World)
+    {
+        trace_span!(world, DEBUG, "refill_spawners");
+
+        let mut population = world.query_filtered::<(), With<C>>();
+
+        world.resource_scope(|world, mut spawners: Mut<Self>| {
+            world.resource_scope(|world, mut rng: Mut<SimRng>| {
+                for refill in &mut spawners.spawners
+                {
+                    // count again for every spawner, since previous ones may have refilled already
+                    let count = population.iter(world).count();
+                    if count < refill.threshold
+                    {
+                        let mut spawner = Spawner::new(world);
+                        (refill.spawn_fn)(&mut spawner, refill.target - count, &mut rng);
+                    }
+                }
+            });
+        });
+    }
+}
+
+pub struct RefillSpawnersPlugin<C: Component>(PhantomData<C>);
+
+impl<C: Component> Default for RefillSpawnersPlugin<C>
+{
+    fn default() -> Self
+    {
+        Self(PhantomData)
+    }
+}
+
+impl<C: Component> Plugin for RefillSpawnersPlugin<C>
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<RefillSpawners<C>>();
+
+        // refill at the very beginning of the step, so that the new entities take part in it
+        app.add_systems(
+            First,
+            RefillSpawners::<C>::run.after(ScheduledSpawners::run),
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+
+use crate::{SimRng, plugins::StepNumber, spawner::Spawner, trace::trace_span};
+
+type ScheduledSpawnFn = Box<dyn FnMut(&mut Spawner, &mut SimRng) + Send + Sync>;
+
+/// When a scheduled spawner is due to be invoked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnSchedule
+{
+    /// Once, at the beginning of the given step.
+    At(usize),
+
+    /// At the beginning of every step that is a multiple of the given interval.
+    Every(usize),
+}
+
+impl SpawnSchedule
+{
+    const fn is_due(self, step: usize) -> bool
+    {
+        match self
+        {
+            Self::At(at_step) => step == at_step,
+            Self::Every(interval) => step.is_multiple_of(interval),
+        }
+    }
+}
+
+/// Resource holding the spawners that get invoked while the simulation runs.
+#[derive(Resource, Default)]
+pub struct ScheduledSpawners(Vec<(SpawnSchedule, ScheduledSpawnFn)>);
+
+impl ScheduledSpawners
+{
+    pub fn push(
+        &mut self,
+        schedule: SpawnSchedule,
+        spawn_fn: impl FnMut(&mut Spawner, &mut SimRng) + Send + Sync + 'static,
+    )
+    {
+        self.0.push((schedule, Box::new(spawn_fn)));
+    }
+
+    pub fn run(world: &mut World)
+    {
+        trace_span!(world, DEBUG, "scheduled_spawners");
+
+        let step = world.resource::<StepNumber>().get();
+
+        world.resource_scope(|world, mut spawners: Mut<Self>| {
+            world.resource_scope(|world, mut rng: Mut<SimRng>| {
+                let mut spawner = Spawner::new(world);
+                for (_, spawn_fn) in spawners
+                    .0
+                    .iter_mut()
+                    .filter(|(schedule, _)| schedule.is_due(step))
+                {
+                    spawn_fn(&mut spawner, &mut rng);
+                }
+            });
+        });
+    }
+}
+
+pub struct ScheduledSpawnersPlugin;
+
+impl Plugin for ScheduledSpawnersPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<ScheduledSpawners>();
+
+        // spawn at the very beginning of the step, so that the new entities take part in it
+        app.add_systems(First, ScheduledSpawners::run);
+    }
+}
@@ -1,11 +1,13 @@
-use std::{fmt::Debug, hash::Hash};
+use std::{fmt::Debug, hash::Hash, slice};
 
 use bevy::{
     ecs::entity::EntityHashMap,
-    platform::collections::{HashMap, HashSet},
+    platform::collections::{HashMap, HashSet, hash_set},
     prelude::*,
 };
 
+use crate::{MemoryReport, SpatialGridError, StrictnessPolicy, plugins::MemoryReporters};
+
 // Direction constants for 2D grid movement
 const NORTH: IVec2 = IVec2::new(0, -1);
 const SOUTH: IVec2 = IVec2::new(0, 1);
@@ -34,6 +36,22 @@ pub trait GridCoordinates:
     fn neighbors_orthogonal(&self) -> impl Iterator<Item = Self>;
 
     fn in_bounds(&self, bounds: &GridBounds<Self>) -> bool;
+
+    fn iter_bounds(bounds: &GridBounds<Self>) -> impl Iterator<Item = Self>;
+
+    /// The number of cells within the given bounds.
+    ///
+    /// By default the cells are counted by iterating over them with [`Self::iter_bounds`].
+    fn cell_count(bounds: &GridBounds<Self>) -> usize
+    {
+        Self::iter_bounds(bounds).count()
+    }
+
+    /// The index of these coordinates among the cells within the given bounds,
+    /// in the order of [`Self::iter_bounds`].
+    ///
+    /// The coordinates are assumed to be within the bounds.
+    fn cell_index(&self, bounds: &GridBounds<Self>) -> usize;
 }
 
 /// Describes the bounds of a grid.
@@ -43,6 +61,7 @@ pub trait GridCoordinates:
 ///
 /// Note that [`GridBounds2D`] and [`GridBounds3D`] may be used as shorthand types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GridBounds<T: GridCoordinates>
 {
     /// The bottom-left corner of the grid.
@@ -72,6 +91,27 @@ impl GridCoordinates for IVec2
     {
         bounds.contains(self)
     }
+
+    fn iter_bounds(bounds: &GridBounds<Self>) -> impl Iterator<Item = Self>
+    {
+        let GridBounds { min, max } = *bounds;
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| Self::new(x, y)))
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn cell_count(bounds: &GridBounds<Self>) -> usize
+    {
+        let size = bounds.max - bounds.min + Self::ONE;
+        size.x as usize * size.y as usize
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn cell_index(&self, bounds: &GridBounds<Self>) -> usize
+    {
+        let size = bounds.max - bounds.min + Self::ONE;
+        let offset = self - bounds.min;
+        offset.x as usize * size.y as usize + offset.y as usize
+    }
 }
 
 impl GridCoordinates for IVec3
@@ -106,6 +146,30 @@ impl GridCoordinates for IVec3
     {
         bounds.contains(self)
     }
+
+    fn iter_bounds(bounds: &GridBounds<Self>) -> impl Iterator<Item = Self>
+    {
+        let GridBounds { min, max } = *bounds;
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| Self::new(x, y, z)))
+        })
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn cell_count(bounds: &GridBounds<Self>) -> usize
+    {
+        let size = bounds.max - bounds.min + Self::ONE;
+        size.x as usize * size.y as usize * size.z as usize
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn cell_index(&self, bounds: &GridBounds<Self>) -> usize
+    {
+        let size = bounds.max - bounds.min + Self::ONE;
+        let offset = self - bounds.min;
+        (offset.x as usize * size.y as usize + offset.y as usize) * size.z as usize
+            + offset.z as usize
+    }
 }
 
 /// Component representing a position in the spatial grid.
@@ -115,6 +179,7 @@ impl GridCoordinates for IVec3
 ///
 /// Note that [`GridPosition2D`] and [`GridPosition3D`] may be used as shorthand types.
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GridPosition<T: GridCoordinates>(pub T);
 
 // Convenience methods for 2D positions
@@ -189,13 +254,114 @@ impl GridPosition<IVec3>
     }
 }
 
+/// Storage of the entities at each position of a [`SpatialGrid`].
+enum GridCells<T: GridCoordinates>
+{
+    /// Only occupied positions are stored, which suits sparse or unbounded grids.
+    Sparse(HashMap<GridPosition<T>, HashSet<Entity>>),
+
+    /// Every position within the bounds is stored in a flat array indexed by cell,
+    /// which avoids hashing in dense grids.
+    Dense
+    {
+        bounds: GridBounds<T>,
+        cells: Vec<Vec<Entity>>,
+    },
+}
+
+impl<T: GridCoordinates> GridCells<T>
+{
+    fn insert(&mut self, position: GridPosition<T>, entity: Entity)
+    {
+        match self
+        {
+            Self::Sparse(cells) =>
+            {
+                cells.entry(position).or_default().insert(entity);
+            }
+            Self::Dense { bounds, cells } => cells[position.0.cell_index(bounds)].push(entity),
+        }
+    }
+
+    fn remove(&mut self, position: GridPosition<T>, entity: Entity)
+    {
+        match self
+        {
+            Self::Sparse(cells) =>
+            {
+                let Some(entities_at_position) = cells.get_mut(&position)
+                else
+                {
+                    panic!("entity found in one hashmap but not the other?");
+                };
+
+                entities_at_position.remove(&entity);
+                if entities_at_position.is_empty()
+                {
+                    cells.remove(&position);
+                }
+            }
+            Self::Dense { bounds, cells } =>
+            {
+                let entities_at_position = &mut cells[position.0.cell_index(bounds)];
+                if let Some(idx) = entities_at_position.iter().position(|&e| e == entity)
+                {
+                    entities_at_position.swap_remove(idx);
+                }
+            }
+        }
+    }
+
+    fn get(&self, position: &GridPosition<T>) -> CellEntities<'_>
+    {
+        match self
+        {
+            Self::Sparse(cells) => cells
+                .get(position)
+                .map_or(CellEntities::Empty, |set| CellEntities::Sparse(set.iter())),
+            Self::Dense { bounds, cells } if position.0.in_bounds(bounds) =>
+            {
+                CellEntities::Dense(cells[position.0.cell_index(bounds)].iter())
+            }
+            Self::Dense { .. } => CellEntities::Empty,
+        }
+    }
+}
+
+/// Iterator over the entities at a single position of a [`SpatialGrid`].
+enum CellEntities<'a>
+{
+    Sparse(hash_set::Iter<'a, Entity>),
+    Dense(slice::Iter<'a, Entity>),
+    Empty,
+}
+
+impl Iterator for CellEntities<'_>
+{
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity>
+    {
+        match self
+        {
+            Self::Sparse(iter) => iter.next().copied(),
+            Self::Dense(iter) => iter.next().copied(),
+            Self::Empty => None,
+        }
+    }
+}
+
 /// Component that maintains a spatial index for efficient neighbor queries.
 /// Generic over coordinate types that implement the `GridCoordinate` trait and component types.
+///
+/// By default, only the occupied positions of the grid are stored in a hash map.
+/// Bounded grids where most positions are occupied, such as the lattices of cellular automata,
+/// can instead be backed by a flat array with a cell for every position, see [`Self::new_dense`].
 #[derive(Resource)]
 pub struct SpatialGrid<T: GridCoordinates, C: Component>
 {
     /// Maps grid positions to entities at those positions.
-    position_to_entities: HashMap<GridPosition<T>, HashSet<Entity>>,
+    position_to_entities: GridCells<T>,
     /// Maps entities to their grid positions for fast lookups (optimized for Entity keys).
     entity_to_position: EntityHashMap<GridPosition<T>>,
     /// Grid bounds for validation and iteration.
@@ -204,6 +370,17 @@ pub struct SpatialGrid<T: GridCoordinates, C: Component>
     _phantom: std::marker::PhantomData<C>,
 }
 
+impl<T: GridCoordinates> GridBounds<T>
+{
+    /// Iterates over every position within these bounds, inclusive of both [`Self::min`] and [`Self::max`].
+    ///
+    /// Positions are iterated in lexicographic order of their coordinates, i.e. `x` first, then `y`, and so on.
+    pub fn positions(&self) -> impl Iterator<Item = GridPosition<T>>
+    {
+        T::iter_bounds(self).map(GridPosition)
+    }
+}
+
 /// Specific implementations for 2D bounds
 impl GridBounds<IVec2>
 {
@@ -247,13 +424,86 @@ impl<T: GridCoordinates, C: Component> SpatialGrid<T, C>
     pub fn new(bounds: Option<GridBounds<T>>) -> Self
     {
         Self {
-            position_to_entities: HashMap::default(),
+            position_to_entities: GridCells::Sparse(HashMap::default()),
             entity_to_position: EntityHashMap::default(),
             bounds,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Creates a spatial grid backed by a flat array with a cell for every position within `bounds`.
+    ///
+    /// This removes hashing from position lookups, at the cost of memory proportional to
+    /// the area (or volume) of the grid, so it pays off when most positions are occupied.
+    ///
+    /// # Panics
+    ///
+    /// If [`GridBounds::min`] is larger than [`GridBounds::max`] along any axis.
+    #[must_use]
+    pub fn new_dense(bounds: GridBounds<T>) -> Self
+    {
+        assert!(
+            T::iter_bounds(&bounds).next().is_some(),
+            "invalid grid bounds: {:?} is larger than {:?} along some axis",
+            bounds.min,
+            bounds.max
+        );
+
+        Self {
+            position_to_entities: GridCells::Dense {
+                bounds,
+                cells: vec![Vec::new(); T::cell_count(&bounds)],
+            },
+            entity_to_position: EntityHashMap::default(),
+            bounds: Some(bounds),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Adds the number of cells and the estimated bytes allocated by the grid to a memory report.
+    fn report_memory(world: &World, report: &mut MemoryReport)
+    {
+        let Some(grid) = world.get_resource::<Self>()
+        else
+        {
+            return;
+        };
+
+        let (cells, cell_bytes) = match &grid.position_to_entities
+        {
+            GridCells::Sparse(cells) => (
+                cells.len(),
+                cells.capacity() * size_of::<(GridPosition<T>, HashSet<Entity>)>()
+                    + cells
+                        .values()
+                        .map(|set| set.capacity() * size_of::<Entity>())
+                        .sum::<usize>(),
+            ),
+            GridCells::Dense { cells, .. } => (
+                cells.len(),
+                cells.capacity() * size_of::<Vec<Entity>>()
+                    + cells
+                        .iter()
+                        .map(|cell| cell.capacity() * size_of::<Entity>())
+                        .sum::<usize>(),
+            ),
+        };
+
+        report.spatial_grid_cells += cells;
+        report.spatial_grid_bytes += cell_bytes
+            + grid.entity_to_position.capacity() * size_of::<(Entity, GridPosition<T>)>();
+    }
+
+    /// Reserves capacity for at least `additional` more entities to be added to the grid.
+    pub fn reserve(&mut self, additional: usize)
+    {
+        if let GridCells::Sparse(cells) = &mut self.position_to_entities
+        {
+            cells.reserve(additional);
+        }
+        self.entity_to_position.reserve(additional);
+    }
+
     #[must_use]
     pub const fn bounds(&self) -> Option<GridBounds<T>>
     {
@@ -270,23 +520,38 @@ impl<T: GridCoordinates, C: Component> SpatialGrid<T, C>
             .is_none_or(|bounds| position.0.in_bounds(&bounds))
     }
 
-    /// Add an entity at a specific grid position.
-    fn insert_or_update(&mut self, entity: Entity, position: GridPosition<T>)
-    {
-        assert!(
-            self.in_bounds(position),
-            "entity at position {position:?} outside spatial grid bounds"
-        );
+    /// Adds an entity at a specific grid position, or moves it there if it is already in the grid.
+    ///
+    /// If the position is outside the bounds of the grid, the entity is removed from the grid instead.
+    /// If the entity is already at the given position, the grid is left untouched.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpatialGridError::OutOfBounds`]
+    pub fn try_insert(
+        &mut self,
+        entity: Entity,
+        position: GridPosition<T>,
+    ) -> Result<(), SpatialGridError>
+    {
+        // Positions are often reassigned without actually moving, so avoid churning the index
+        if self.entity_to_position.get(&entity) == Some(&position)
+        {
+            return Ok(());
+        }
 
         // Remove entity from old position if it exists
         self.remove(entity);
 
+        if !self.in_bounds(position)
+        {
+            return Err(SpatialGridError::OutOfBounds);
+        }
+
         // Insert at new position
-        self.position_to_entities
-            .entry(position)
-            .or_default()
-            .insert(entity);
+        self.position_to_entities.insert(position, entity);
         self.entity_to_position.insert(entity, position);
+        Ok(())
     }
 
     /// Remove an entity from the spatial index.
@@ -295,28 +560,14 @@ impl<T: GridCoordinates, C: Component> SpatialGrid<T, C>
     fn remove(&mut self, entity: Entity) -> Option<GridPosition<T>>
     {
         let position = self.entity_to_position.remove(&entity)?;
-
-        let Some(entities_at_position) = self.position_to_entities.get_mut(&position)
-        else
-        {
-            panic!("entity found in one hashmap but not the other?");
-        };
-
-        entities_at_position.remove(&entity);
-        if entities_at_position.is_empty()
-        {
-            self.position_to_entities.remove(&position);
-        }
+        self.position_to_entities.remove(position, entity);
         Some(position)
     }
 
     /// Get all entities at a specific position.
     pub fn entities_at(&self, position: &GridPosition<T>) -> impl Iterator<Item = Entity> + '_
     {
-        self.position_to_entities
-            .get(position)
-            .into_iter()
-            .flat_map(|set| set.iter().copied())
+        self.position_to_entities.get(position)
     }
 
     /// Get the position of an entity.
@@ -339,12 +590,7 @@ impl<T: GridCoordinates, C: Component> SpatialGrid<T, C>
                     .is_none_or(|bounds| neighbor_pos.in_bounds(&bounds))
             })
             .map(|p| GridPosition(p))
-            .flat_map(|neighbor_pos| {
-                self.position_to_entities
-                    .get(&neighbor_pos)
-                    .into_iter()
-                    .flat_map(|set| set.iter().copied())
-            })
+            .flat_map(|neighbor_pos| self.position_to_entities.get(&neighbor_pos))
     }
 
     /// Get all entities in the orthogonal neighborhood of a position (Von Neumann neighborhood).
@@ -363,21 +609,51 @@ impl<T: GridCoordinates, C: Component> SpatialGrid<T, C>
                     .is_none_or(|bounds| neighbor_pos.in_bounds(&bounds))
             })
             .map(|p| GridPosition(p))
-            .flat_map(|neighbor_pos| {
-                self.position_to_entities
-                    .get(&neighbor_pos)
-                    .into_iter()
-                    .flat_map(|set| set.iter().copied())
-            })
+            .flat_map(|neighbor_pos| self.position_to_entities.get(&neighbor_pos))
+    }
+
+    /// Appends all entities at a specific position to `buffer`.
+    ///
+    /// Like [`Self::entities_at`], but writes into a caller-provided buffer, which can be reused
+    /// across calls to avoid allocating in systems that query the grid many times per step.
+    /// Note that `buffer` is not cleared beforehand.
+    pub fn entities_at_into(&self, position: &GridPosition<T>, buffer: &mut Vec<Entity>)
+    {
+        buffer.extend(self.position_to_entities.get(position));
+    }
+
+    /// Appends all entities in the neighborhood of a position (Moore neighborhood) to `buffer`.
+    ///
+    /// Like [`Self::neighbors_of`], but writes into a caller-provided buffer, which can be reused
+    /// across calls to avoid allocating in systems that query the grid many times per step.
+    /// Note that `buffer` is not cleared beforehand.
+    pub fn neighbors_of_into(&self, position: &GridPosition<T>, buffer: &mut Vec<Entity>)
+    {
+        for neighbor_pos in position.0.neighbors()
+        {
+            buffer.extend(self.position_to_entities.get(&GridPosition(neighbor_pos)));
+        }
+    }
+
+    /// Appends all entities in the orthogonal neighborhood of a position (Von Neumann neighborhood)
+    /// to `buffer`.
+    ///
+    /// Like [`Self::orthogonal_neighbors_of`], but writes into a caller-provided buffer, which can
+    /// be reused across calls to avoid allocating in systems that query the grid many times per step.
+    /// Note that `buffer` is not cleared beforehand.
+    pub fn orthogonal_neighbors_of_into(&self, position: &GridPosition<T>, buffer: &mut Vec<Entity>)
+    {
+        for neighbor_pos in position.0.neighbors_orthogonal()
+        {
+            buffer.extend(self.position_to_entities.get(&GridPosition(neighbor_pos)));
+        }
     }
 
     /// Check if a position is empty (has no entities).
     #[must_use]
     pub fn is_empty(&self, position: &GridPosition<T>) -> bool
     {
-        self.position_to_entities
-            .get(position)
-            .is_none_or(HashSet::is_empty)
+        self.position_to_entities.get(position).next().is_none()
     }
 
     /// Get total number of entities in the grid.
@@ -388,11 +664,26 @@ impl<T: GridCoordinates, C: Component> SpatialGrid<T, C>
     }
 }
 
+/// Event sent when an entity is moved outside the bounds of a [`SpatialGrid`].
+///
+/// The entity is removed from the spatial grid until it is moved back within its bounds.
+/// To panic instead, set the [`crate::StrictnessPolicy`] of the simulation to strict.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundsViolation<T: GridCoordinates>
+{
+    /// The entity that was moved out of bounds.
+    pub entity: Entity,
+
+    /// The out of bounds position that the entity was moved to.
+    pub position: GridPosition<T>,
+}
+
 /// Plugin that maintains a spatial index for entities with `GridPosition` components.
 /// Generic over coordinate types that implement the `GridCoordinate` trait and component types.
 pub struct SpatialGridPlugin<T: GridCoordinates, C: Component>
 {
     bounds: Option<GridBounds<T>>,
+    dense: bool,
     _phantom: std::marker::PhantomData<(T, C)>,
 }
 
@@ -402,6 +693,17 @@ impl<T: GridCoordinates, C: Component> SpatialGridPlugin<T, C>
     {
         Self {
             bounds,
+            dense: false,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Creates a plugin for a spatial grid backed by a flat array, see [`SpatialGrid::new_dense`].
+    pub const fn new_dense(bounds: GridBounds<T>) -> Self
+    {
+        Self {
+            bounds: Some(bounds),
+            dense: true,
             _phantom: std::marker::PhantomData,
         }
     }
@@ -411,8 +713,14 @@ impl<T: GridCoordinates, C: Component> Plugin for SpatialGridPlugin<T, C>
 {
     fn build(&self, app: &mut App)
     {
-        let spatial_grid = SpatialGrid::<T, C>::new(self.bounds);
+        let spatial_grid = match self.bounds
+        {
+            Some(bounds) if self.dense => SpatialGrid::<T, C>::new_dense(bounds),
+            bounds => SpatialGrid::<T, C>::new(bounds),
+        };
         app.insert_resource(spatial_grid);
+        app.add_event::<BoundsViolation<T>>();
+        MemoryReporters::register(app, SpatialGrid::<T, C>::report_memory);
 
         // System to maintain the spatial index
         app.add_systems(
@@ -431,14 +739,30 @@ type GridPositionQuery<'world, 'state, T, C> =
     Query<'world, 'state, (Entity, &'static GridPosition<T>), (Changed<GridPosition<T>>, With<C>)>;
 
 /// System that updates the spatial grid when entities with `GridPosition` are added or moved.
+///
+/// Since it relies on change detection, an entity that moved several times during a step
+/// is only updated once, at its final position.
 fn spatial_grid_update_system<T: GridCoordinates, C: Component>(
     mut spatial_grid: ResMut<SpatialGrid<T, C>>,
     query: GridPositionQuery<T, C>,
+    strictness: Res<StrictnessPolicy>,
+    mut violations: EventWriter<BoundsViolation<T>>,
 )
 {
     for (entity, position) in &query
     {
-        spatial_grid.insert_or_update(entity, *position);
+        if spatial_grid.try_insert(entity, *position).is_err()
+        {
+            assert!(
+                !strictness.is_strict(),
+                "entity at position {position:?} outside spatial grid bounds"
+            );
+
+            violations.write(BoundsViolation {
+                entity,
+                position: *position,
+            });
+        }
     }
 }
 
@@ -0,0 +1,50 @@
+use std::ops::ControlFlow;
+
+use bevy::prelude::*;
+
+use crate::plugins::StepNumber;
+
+type StepEndHook = Box<dyn FnMut(&World, usize) -> ControlFlow<()> + Send + Sync>;
+
+/// Resource holding the user-defined hooks that get invoked at the end of every step.
+#[derive(Resource, Default)]
+pub struct StepEndHooks(Vec<StepEndHook>);
+
+impl StepEndHooks
+{
+    pub fn push(
+        &mut self,
+        hook: impl FnMut(&World, usize) -> ControlFlow<()> + Send + Sync + 'static,
+    )
+    {
+        self.0.push(Box::new(hook));
+    }
+}
+
+/// Invokes all step-end hooks in the world, in the order in which they were added.
+///
+/// This is meant to be called right after each update of the simulation.
+/// Every hook is always invoked, but if any of them returns [`ControlFlow::Break`]
+/// so will this function, signaling that the simulation should stop.
+pub fn run_step_end_hooks(world: &mut World) -> ControlFlow<()>
+{
+    if !world.contains_resource::<StepEndHooks>()
+    {
+        return ControlFlow::Continue(());
+    }
+
+    // the step counter has already been incremented at this point
+    let step = world.resource::<StepNumber>().get() - 1;
+
+    world.resource_scope(|world, mut hooks: Mut<StepEndHooks>| {
+        let mut flow = ControlFlow::Continue(());
+        for hook in &mut hooks.0
+        {
+            if hook(world, step).is_break()
+            {
+                flow = ControlFlow::Break(());
+            }
+        }
+        flow
+    })
+}
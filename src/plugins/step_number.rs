@@ -1,8 +1,24 @@
 use bevy::prelude::*;
 
+/// Resource holding the number of the simulation step currently being executed.
+///
+/// Steps are numbered starting from `1`, meaning that during the `n`-th step of the
+/// simulation, all user-defined systems will read the value `n`.
+///
+/// This resource can be accessed in user-defined systems using [`Res<StepNumber>`] arguments.
 #[derive(Resource, Default, Debug, Deref)]
 pub struct StepNumber(usize);
 
+impl StepNumber
+{
+    /// Returns the number of the simulation step currently being executed.
+    #[must_use]
+    pub const fn get(&self) -> usize
+    {
+        self.0
+    }
+}
+
 pub struct StepNumberPlugin;
 
 impl Plugin for StepNumberPlugin
@@ -17,7 +33,7 @@ impl Plugin for StepNumberPlugin
     }
 }
 
-fn step_counter_increment(mut step_counter: ResMut<StepNumber>)
+pub fn step_counter_increment(mut step_counter: ResMut<StepNumber>)
 {
     step_counter.0 += 1;
 }
@@ -2,7 +2,10 @@ use std::marker::PhantomData;
 
 use bevy::{ecs::query::QueryFilter, prelude::*};
 
-use crate::{Identifier, Sample, SampleAggregate, TimeSeries, plugins::step_number::StepNumber};
+use crate::{
+    Identifier, MemoryReport, ParallelSampling, Sample, SampleAggregate, TimeSeries,
+    plugins::{DeferredFlush, MemoryReporters, step_number::StepNumber},
+};
 
 #[derive(Component, Resource, Default)]
 pub struct TimeSeriesData<C, F, O>
@@ -25,20 +28,60 @@ impl<C, F, O> TimeSeriesData<C, F, O>
         }
     }
 
+    /// Reserves capacity for all the samples that will be recorded over the given number of `steps`.
+    pub fn reserve_steps(&mut self, steps: usize)
+    {
+        let samples = steps / self.sample_interval;
+        self.values.reserve(samples);
+        self.time.reserve(samples);
+    }
+
+    /// Adds the number of samples and the estimated bytes allocated by the time series to a memory report.
+    const fn add_to_report(&self, report: &mut MemoryReport)
+    {
+        report.time_series_samples += self.values.len();
+        report.time_series_bytes +=
+            self.values.capacity() * size_of::<O>() + self.time.capacity() * size_of::<usize>();
+    }
+
     #[must_use]
     pub fn collect(&self) -> TimeSeries<'_, O>
     {
-        let values = self.values.iter().by_ref().collect();
-        let time = self.time.clone();
-
         TimeSeries {
-            values,
-            time,
+            values: &self.values,
+            time: &self.time,
             sample_interval: self.sample_interval,
         }
     }
 }
 
+/// Reads the points of the aggregate time series of `O` over components `C` from the world,
+/// starting from the given index, along with the total number of points in it.
+///
+/// If the series has fewer points than `start`, it has been cleared by a reset of the simulation,
+/// and all of its points are returned instead.
+/// The series is expected to have been recorded, otherwise no points are returned.
+#[cfg(any(feature = "tensorboard", feature = "websocket"))]
+pub fn aggregate_time_series_points<C, O>(world: &World, start: usize) -> (Vec<(usize, f64)>, usize)
+where
+    C: SampleAggregate<O>,
+    O: Copy + Into<f64> + Send + Sync + 'static,
+{
+    world
+        .get_resource::<TimeSeriesData<C, (), O>>()
+        .map(|data| {
+            let series = data.collect();
+            let start = if start > series.len() { 0 } else { start };
+            let points = series.time_slice()[start..]
+                .iter()
+                .zip(&series.values_slice()[start..])
+                .map(|(&step, &value)| (step, value.into()))
+                .collect();
+            (points, series.len())
+        })
+        .unwrap_or_default()
+}
+
 #[derive(Default)]
 pub struct AggregateTimeSeriesPlugin<C, F, O>
 where
@@ -69,6 +112,7 @@ where
         mut time_series: ResMut<TimeSeriesData<C, F, O>>,
         step_number: Res<StepNumber>,
         query: Query<&C, F>,
+        parallel: Option<Res<ParallelSampling>>,
     )
     {
         // only get new samples once every 'sample_interval' steps
@@ -78,7 +122,8 @@ where
 
             if !component_values.is_empty()
             {
-                let sample = C::sample_aggregate(&component_values);
+                let sample =
+                    ParallelSampling::sample_aggregate(parallel.as_deref(), &component_values);
 
                 time_series.values.push(sample);
                 time_series.time.push(**step_number);
@@ -96,13 +141,37 @@ where
     fn build(&self, app: &mut App)
     {
         app.insert_resource(TimeSeriesData::<C, F, O>::new(self.sample_interval));
+        MemoryReporters::register(app, |world, report| {
+            if let Some(time_series) = world.get_resource::<TimeSeriesData<C, F, O>>()
+            {
+                time_series.add_to_report(report);
+            }
+        });
 
-        app.add_systems(PostUpdate, Self::time_series_sample);
+        app.add_systems(PostUpdate, Self::time_series_sample.after(DeferredFlush));
     }
 }
 
 #[derive(Resource, Deref)]
-pub struct SampleInterval<C, F, O>(#[deref] usize, PhantomData<(C, F, O)>);
+pub struct SampleInterval<C, F, O>
+{
+    #[deref]
+    interval: usize,
+
+    /// The number of steps to reserve capacity for in each new time series.
+    expected_steps: usize,
+
+    _phantom: PhantomData<(C, F, O)>,
+}
+
+impl<C, F, O> SampleInterval<C, F, O>
+{
+    /// Reserves capacity for the given number of `steps` in every time series created from now on.
+    pub const fn reserve_steps(&mut self, steps: usize)
+    {
+        self.expected_steps = steps;
+    }
+}
 
 #[derive(Default)]
 pub struct TimeSeriesPlugin<C, I, O>
@@ -138,9 +207,9 @@ where
     {
         for entity in &query
         {
-            commands
-                .entity(entity)
-                .insert(TimeSeriesData::<C, I, O>::new(**sample_interval));
+            let mut time_series = TimeSeriesData::<C, I, O>::new(**sample_interval);
+            time_series.reserve_steps(sample_interval.expected_steps);
+            commands.entity(entity).insert(time_series);
         }
     }
 
@@ -170,11 +239,26 @@ where
 {
     fn build(&self, app: &mut App)
     {
-        app.insert_resource(SampleInterval::<C, I, O>(self.sample_interval, PhantomData));
+        app.insert_resource(SampleInterval::<C, I, O> {
+            interval: self.sample_interval,
+            expected_steps: 0,
+            _phantom: PhantomData,
+        });
+        MemoryReporters::register(app, |world, report| {
+            if let Some(mut query) = world.try_query::<&TimeSeriesData<C, I, O>>()
+            {
+                for time_series in query.iter(world)
+                {
+                    time_series.add_to_report(report);
+                }
+            }
+        });
 
         app.add_systems(
             PostUpdate,
-            (Self::time_series_init, Self::time_series_sample).chain(),
+            (Self::time_series_init, Self::time_series_sample)
+                .chain()
+                .after(DeferredFlush),
         );
     }
 }
@@ -0,0 +1,202 @@
+//! Decisions of simulated actors delegated to an external policy, such as a reinforcement-learning
+//! agent being trained, or a human in the loop.
+//!
+//! A [`Policy<O, A>`] maps observations of type `O` to actions of type `A`, and is added to a
+//! simulation with [`crate::SimulationBuilder::add_policy`].
+//! User-defined systems build an observation for an actor, pass it to [`Policy::decide`] through
+//! a [`ResMut<Policy<O, A>>`] argument, and apply the action they get back.
+//!
+//! The policy is either a function called in place, see [`Policy::from_fn`], or the other end of
+//! a pair of channels, see [`Policy::channel`], in which case the observations are sent to a
+//! [`PolicyEndpoint`] that may be moved to another thread, and every decision waits for the
+//! action sent back from it.
+//! This keeps any machine learning framework or user interface out of the simulation itself.
+//!
+//! Example of a policy on another thread steering an agent towards the origin:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::policy::Policy;
+//!
+//! #[derive(Component)]
+//! struct Position(i64);
+//!
+//! let (policy, endpoint) = Policy::<i64, i64>::channel();
+//! let trainer = std::thread::spawn(move || endpoint.serve(|position| -position.signum()));
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_policy(policy)
+//!     .add_entity_spawner(|spawner| {
+//!         spawner.spawn(Position(10));
+//!     })
+//!     .add_systems(|mut agent: Single<&mut Position>, mut policy: ResMut<Policy<i64, i64>>| {
+//!         if let Some(step) = policy.decide(agent.0)
+//!         {
+//!             agent.0 += step;
+//!         }
+//!     })
+//!     .build();
+//!
+//! simulation.run(20);
+//! assert_eq!(simulation.iter::<Position>().next().unwrap().0, 0);
+//!
+//! // dropping the simulation disconnects the endpoint
+//! drop(simulation);
+//! trainer.join().unwrap();
+//! ```
+
+use std::sync::{
+    Mutex, PoisonError,
+    mpsc::{self, Receiver, Sender},
+};
+
+use bevy::prelude::*;
+
+/// How a [`Policy`] makes its decisions.
+enum Decide<O, A>
+{
+    Function(Mutex<Box<dyn FnMut(O) -> A + Send>>),
+    Channel
+    {
+        observations: Sender<O>,
+        actions: Mutex<Receiver<A>>,
+    },
+}
+
+/// Resource holding an external policy that decides on actions `A` given observations `O`,
+/// see the [module documentation](self).
+///
+/// This resource can be accessed in user-defined systems using [`ResMut<Policy<O, A>>`] arguments.
+#[derive(Resource)]
+pub struct Policy<O, A>
+where
+    O: Send + 'static,
+    A: Send + 'static,
+{
+    decide: Decide<O, A>,
+    decisions: usize,
+}
+
+impl<O, A> Policy<O, A>
+where
+    O: Send + 'static,
+    A: Send + 'static,
+{
+    /// Creates a policy that calls the given function on every decision.
+    #[must_use]
+    pub fn from_fn(decide: impl FnMut(O) -> A + Send + 'static) -> Self
+    {
+        Self {
+            decide: Decide::Function(Mutex::new(Box::new(decide))),
+            decisions: 0,
+        }
+    }
+
+    /// Creates a policy that sends every observation to the returned [`PolicyEndpoint`],
+    /// and waits for it to send back the action.
+    #[must_use]
+    pub fn channel() -> (Self, PolicyEndpoint<O, A>)
+    {
+        let (observations, observation_receiver) = mpsc::channel();
+        let (action_sender, actions) = mpsc::channel();
+
+        let policy = Self {
+            decide: Decide::Channel {
+                observations,
+                actions: Mutex::new(actions),
+            },
+            decisions: 0,
+        };
+        let endpoint = PolicyEndpoint {
+            observations: observation_receiver,
+            actions: action_sender,
+        };
+
+        (policy, endpoint)
+    }
+
+    /// Decides on the action to take given the `observation`.
+    ///
+    /// For a policy created with [`Self::channel`], this blocks until the endpoint sends back
+    /// the action, and returns `None` if the endpoint has been dropped.
+    pub fn decide(&mut self, observation: O) -> Option<A>
+    {
+        let action = match &mut self.decide
+        {
+            Decide::Function(decide) =>
+            {
+                let decide = decide.get_mut().unwrap_or_else(PoisonError::into_inner);
+                Some(decide(observation))
+            }
+            Decide::Channel {
+                observations,
+                actions,
+            } =>
+            {
+                observations.send(observation).ok()?;
+                actions
+                    .get_mut()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .recv()
+                    .ok()
+            }
+        };
+
+        if action.is_some()
+        {
+            self.decisions += 1;
+        }
+        action
+    }
+
+    /// The number of decisions made so far.
+    #[must_use]
+    pub const fn decisions(&self) -> usize
+    {
+        self.decisions
+    }
+}
+
+/// The external end of a [`Policy`] created with [`Policy::channel`], which receives the
+/// observations of the simulation and sends back the actions to take.
+///
+/// Every observation must be answered with exactly one action, in the order they are received.
+pub struct PolicyEndpoint<O, A>
+{
+    observations: Receiver<O>,
+    actions: Sender<A>,
+}
+
+impl<O, A> PolicyEndpoint<O, A>
+{
+    /// Waits for the next observation that needs a decision.
+    ///
+    /// Returns `None` once the simulation holding the policy has been dropped.
+    #[must_use]
+    pub fn next_observation(&self) -> Option<O>
+    {
+        self.observations.recv().ok()
+    }
+
+    /// Sends back the action to take for the latest observation.
+    ///
+    /// Returns `false` if the simulation holding the policy has been dropped.
+    pub fn respond(&self, action: A) -> bool
+    {
+        self.actions.send(action).is_ok()
+    }
+
+    /// Answers every observation with the action returned by `decide`, until the simulation
+    /// holding the policy is dropped.
+    ///
+    /// This is meant to be called on a thread of its own.
+    pub fn serve(self, mut decide: impl FnMut(O) -> A)
+    {
+        while let Some(observation) = self.next_observation()
+        {
+            if !self.respond(decide(observation))
+            {
+                break;
+            }
+        }
+    }
+}
@@ -1,17 +1,34 @@
+#[cfg(feature = "visualize")]
+pub use bevy::prelude::Color;
 pub use bevy::prelude::{
-    Added, Bundle, Changed, Commands, Component, Entity, Event, IVec2, IntoScheduleConfigs, Or,
-    Query, Res, ResMut, Resource, With, Without, default,
+    Added, Bundle, Changed, ChildOf, Children, Commands, Component, Entity, Event, EventReader,
+    EventWriter, IVec2, IntoScheduleConfigs, Or, Query, Reflect, ReflectComponent, Res, ResMut,
+    Resource, Single, With, Without, World, default,
 };
 
+#[cfg(not(target_family = "wasm"))]
+pub use super::background::{BackgroundSimulation, SimulationStatus};
+#[cfg(feature = "gpu")]
+pub use super::gpu;
+#[cfg(feature = "msgpack")]
+pub use super::snapshot;
+#[cfg(feature = "sqlite")]
+pub use super::store::{ResultStore, StoredRun};
 pub use super::{
+    calendar, cellular, comparison, demography, epidemic,
     error::*,
+    evolution, flocking, flow, fsm, geo, gillespie, kernel, market, opinion, placement,
     plugins::{
+        BoundsViolation, ContactLayers, DeferredDespawn, DeferredSpawn, EventLog, FutureEvents,
         GridBounds, GridBounds2D, GridBounds3D, GridCoordinates, GridPosition, GridPosition2D,
-        GridPosition3D, SpatialGrid, SpatialGrid2D, SpatialGrid3D, StepNumber,
+        GridPosition3D, IncrementalAggregate, Network, PopulationLedger, SpatialGrid,
+        SpatialGrid2D, SpatialGrid3D, StepNumber,
     },
+    policy, queueing, random_walk, reaction_diffusion, sde,
     simulation::Simulation,
     simulation_builder::SimulationBuilder,
-    spawner::Spawner,
+    spawner::{ChildSpawner, EntityHandle, Spawner, WeightedSpawner},
+    spin, topology, traffic,
     traits::*,
     types::*,
     util::*,
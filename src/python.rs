@@ -0,0 +1,218 @@
+//! Bindings for driving experiments from Python, enabled with the `python` feature.
+//!
+//! Experiments are registered on the Rust side in an [`ExperimentRegistry`], each with a function
+//! that sets up the simulation from a set of named parameters, and a function that collects the
+//! results of a finished replica as a row of numbers.
+//! The registry is then added to a [`pyo3`] extension module, built for example with `maturin`,
+//! through which the experiments can be run from Python with the results returned as numpy arrays.
+//!
+//! Example:
+//! ```no_run
+//! # use incerto::{prelude::*, python::ExperimentRegistry};
+//! # use pyo3::prelude::*;
+//! #[derive(Component)]
+//! struct Cash(f64);
+//!
+//! #[pymodule]
+//! fn traders(module: &Bound<'_, PyModule>) -> PyResult<()>
+//! {
+//!     ExperimentRegistry::new()
+//!         .register(
+//!             "compound_interest",
+//!             |parameters| {
+//!                 let rate = parameters.get("rate").copied().unwrap_or(0.01);
+//!                 SimulationBuilder::new()
+//!                     .add_entity_spawner(|spawner| {
+//!                         spawner.spawn(Cash(100.0));
+//!                     })
+//!                     .add_systems(move |mut query: Query<&mut Cash>| {
+//!                         for mut cash in &mut query
+//!                         {
+//!                             cash.0 *= 1.0 + rate;
+//!                         }
+//!                     })
+//!             },
+//!             |simulation| simulation.iter::<Cash>().map(|cash| cash.0).collect(),
+//!         )
+//!         .add_to_module(module)
+//! }
+//! ```
+//!
+//! And then in Python:
+//! ```python
+//! import traders
+//!
+//! results = traders.experiments.run("compound_interest", replicas=100, steps=365, parameters={"rate": 0.02})
+//! sweep = traders.experiments.sweep("compound_interest", 100, 365, [{"rate": r} for r in (0.01, 0.02)])
+//! ```
+
+use std::collections::HashMap;
+
+use numpy::PyArray2;
+use pyo3::{
+    exceptions::{PyKeyError, PyValueError},
+    prelude::*,
+};
+
+use crate::{Simulation, SimulationBuilder};
+
+/// The named parameters of a single run of an experiment.
+pub type Parameters = HashMap<String, f64>;
+
+type BuildFn = Box<dyn Fn(&Parameters) -> SimulationBuilder + Send + Sync>;
+type CollectFn = Box<dyn Fn(&Simulation) -> Vec<f64> + Send + Sync>;
+
+/// An experiment registered in an [`ExperimentRegistry`].
+struct Experiment
+{
+    build: BuildFn,
+    collect: CollectFn,
+}
+
+/// A collection of named experiments, which can be exposed to Python.
+///
+/// See the [module-level documentation](self) for an example.
+#[pyclass(frozen, name = "Experiments")]
+#[derive(Default)]
+pub struct ExperimentRegistry
+{
+    experiments: HashMap<String, Experiment>,
+}
+
+impl ExperimentRegistry
+{
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Registers an experiment under the given `name`, replacing any registered before it.
+    ///
+    /// The function `build` is called once for every replica, with the parameters of the run,
+    /// and shall return the builder of the simulation.
+    /// Once the replica has finished running, the function `collect` is called to extract its
+    /// results, which become one row of the returned array.
+    #[must_use]
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        build: impl Fn(&Parameters) -> SimulationBuilder + Send + Sync + 'static,
+        collect: impl Fn(&Simulation) -> Vec<f64> + Send + Sync + 'static,
+    ) -> Self
+    {
+        self.experiments.insert(
+            name.into(),
+            Experiment {
+                build: Box::new(build),
+                collect: Box::new(collect),
+            },
+        );
+        self
+    }
+
+    /// Adds the registry to a Python module, as an attribute named `experiments`.
+    ///
+    /// # Errors
+    ///
+    /// If the attribute could not be added to the module.
+    pub fn add_to_module(self, module: &Bound<'_, PyModule>) -> PyResult<()>
+    {
+        module.add("experiments", self)
+    }
+
+    /// Runs a number of replicas of an experiment in parallel, and collects their results
+    /// in order of their index.
+    ///
+    /// If a `seed` is given, the replicas are seeded from it as with [`Simulation::replicate_seeded`].
+    ///
+    /// Returns `None` if no experiment is registered under the given `name`.
+    #[must_use]
+    pub fn run_experiment(
+        &self,
+        name: &str,
+        replicas: usize,
+        steps: usize,
+        parameters: &Parameters,
+        seed: Option<u64>,
+    ) -> Option<Vec<Vec<f64>>>
+    {
+        let experiment = self.experiments.get(name)?;
+        let build_replica = |_| (experiment.build)(parameters);
+
+        let simulations = seed.map_or_else(
+            || Simulation::replicate(replicas, steps, build_replica),
+            |seed| Simulation::replicate_seeded(seed, replicas, steps, build_replica),
+        );
+
+        Some(
+            simulations
+                .iter()
+                .map(|simulation| (experiment.collect)(simulation))
+                .collect(),
+        )
+    }
+}
+
+/// Converts the rows of results into a 2D numpy array, raising a `ValueError` if they are ragged.
+fn to_array<'py>(py: Python<'py>, rows: &[Vec<f64>]) -> PyResult<Bound<'py, PyArray2<f64>>>
+{
+    PyArray2::from_vec2(py, rows).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+#[pymethods]
+impl ExperimentRegistry
+{
+    /// The names of all registered experiments.
+    fn names(&self) -> Vec<String>
+    {
+        let mut names = self.experiments.keys().cloned().collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// Runs a number of replicas of an experiment, returning an array with a row for each replica.
+    #[pyo3(name = "run", signature = (name, replicas, steps, parameters = None, seed = None))]
+    fn py_run<'py>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+        replicas: usize,
+        steps: usize,
+        parameters: Option<Parameters>,
+        seed: Option<u64>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>>
+    {
+        let parameters = parameters.unwrap_or_default();
+        let rows = py
+            .allow_threads(|| self.run_experiment(name, replicas, steps, &parameters, seed))
+            .ok_or_else(|| PyKeyError::new_err(name.to_string()))?;
+
+        to_array(py, &rows)
+    }
+
+    /// Runs a number of replicas of an experiment for each set of parameters,
+    /// returning one array per set.
+    #[pyo3(name = "sweep", signature = (name, replicas, steps, parameters, seed = None))]
+    fn py_sweep<'py>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+        replicas: usize,
+        steps: usize,
+        parameters: Vec<Parameters>,
+        seed: Option<u64>,
+    ) -> PyResult<Vec<Bound<'py, PyArray2<f64>>>>
+    {
+        parameters
+            .iter()
+            .map(|parameters| {
+                let rows = py
+                    .allow_threads(|| self.run_experiment(name, replicas, steps, parameters, seed))
+                    .ok_or_else(|| PyKeyError::new_err(name.to_string()))?;
+
+                to_array(py, &rows)
+            })
+            .collect()
+    }
+}
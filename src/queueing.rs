@@ -0,0 +1,435 @@
+//! Components for queueing models, such as call centers, checkouts or hospital wards,
+//! in which customers arrive at random, wait in line, and are served by one of several servers.
+//!
+//! A [`Queue`] entity holds the waiting customers, who arrive according to its [`Arrivals`].
+//! Each [`Server`] entity takes the customer at the front of its queue whenever idle,
+//! and is then busy for a random number of steps drawn from its [`ServiceTime`].
+//! Time is measured in simulation steps, so arrival rates are per step, and service times last
+//! whole steps.
+//!
+//! These are advanced once per step by the simulation, when set up with
+//! [`crate::SimulationBuilder::add_queueing`], drawing from its [`crate::SimRng`].
+//! The waiting times, queue lengths and utilization of the servers can then be recorded as
+//! time series with the [`WaitingTime`], [`QueueLength`] and [`Utilization`] values.
+//!
+//! Example of an M/M/c call center, with 3 operators and an average call of 5 steps:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::queueing::{Arrivals, Queue, Server, ServiceTime, Utilization, WaitingTime};
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_queueing()
+//!     .add_entity_spawner(|spawner| {
+//!         let calls = spawner.spawn((Queue::new(), Arrivals::poisson(0.5)));
+//!         for _ in 0..3
+//!         {
+//!             spawner.spawn(Server::new(calls.entity(), ServiceTime::Exponential { mean: 5.0 }));
+//!         }
+//!     })
+//!     .record_aggregate_time_series::<Server, Utilization>(10)
+//!     .expect("failed to record utilization")
+//!     .build();
+//!
+//! simulation.run(1000);
+//! let WaitingTime(wait) = simulation.sample_aggregate::<Queue, WaitingTime>().unwrap();
+//! ```
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rand::Rng;
+use rand_distr::{Distribution, Exp, Poisson};
+
+use crate::{SampleAggregate, SimRng, plugins::StepNumber};
+
+/// Component of an entity holding customers waiting in line, in the order they arrived.
+///
+/// Customers are added by the entity's [`Arrivals`] if it has any, or by user-defined systems
+/// with [`Self::arrive`], and taken from the front by the [`Server`] entities serving the queue.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Queue
+{
+    /// The step in which each waiting customer arrived.
+    waiting: VecDeque<usize>,
+    /// The maximum number of waiting customers, if limited.
+    capacity: Option<usize>,
+    arrived: usize,
+    rejected: usize,
+    served: usize,
+    /// The total number of steps waited by the customers who have been served.
+    total_wait: usize,
+}
+
+impl Queue
+{
+    /// Creates an empty queue, in which any number of customers may wait.
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Creates an empty queue, in which at most `capacity` customers may wait.
+    ///
+    /// Customers arriving while the queue is full are turned away, as with blocked calls.
+    #[must_use]
+    pub fn bounded(capacity: usize) -> Self
+    {
+        Self {
+            capacity: Some(capacity),
+            ..default()
+        }
+    }
+
+    /// Adds a customer arriving at the given step to the back of the queue.
+    ///
+    /// Returns `false` if the queue is full, in which case the customer is turned away.
+    pub fn arrive(&mut self, step: usize) -> bool
+    {
+        self.arrived += 1;
+        if self
+            .capacity
+            .is_some_and(|capacity| self.waiting.len() >= capacity)
+        {
+            self.rejected += 1;
+            return false;
+        }
+
+        self.waiting.push_back(step);
+        true
+    }
+
+    /// Takes the customer at the front of the queue into service at the given step,
+    /// recording the time they waited.
+    fn serve(&mut self, step: usize) -> bool
+    {
+        let Some(arrival) = self.waiting.pop_front()
+        else
+        {
+            return false;
+        };
+
+        self.served += 1;
+        self.total_wait += step - arrival;
+        true
+    }
+
+    /// The number of customers currently waiting.
+    #[must_use]
+    pub fn len(&self) -> usize
+    {
+        self.waiting.len()
+    }
+
+    /// Returns `true` if no customers are waiting.
+    #[must_use]
+    pub fn is_empty(&self) -> bool
+    {
+        self.waiting.is_empty()
+    }
+
+    /// The total number of customers that have arrived, including those turned away.
+    #[must_use]
+    pub const fn arrived(&self) -> usize
+    {
+        self.arrived
+    }
+
+    /// The number of customers that were turned away because the queue was full.
+    #[must_use]
+    pub const fn rejected(&self) -> usize
+    {
+        self.rejected
+    }
+
+    /// The number of customers that have left the queue to be served.
+    #[must_use]
+    pub const fn served(&self) -> usize
+    {
+        self.served
+    }
+
+    /// The average number of steps waited by the customers who have been served,
+    /// or `None` if there are none yet.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean_wait(&self) -> Option<f64>
+    {
+        (self.served > 0).then(|| self.total_wait as f64 / self.served as f64)
+    }
+}
+
+/// Component adding customers to the [`Queue`] of the same entity, as a Poisson process.
+#[derive(Component, Debug, Clone)]
+pub struct Arrivals
+{
+    rate: f64,
+    distribution: Option<Poisson<f64>>,
+}
+
+impl Arrivals
+{
+    /// Customers arriving at an average of `rate` per step, independently of each other.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` is negative or not finite.
+    #[must_use]
+    pub fn poisson(rate: f64) -> Self
+    {
+        assert!(
+            rate.is_finite() && rate >= 0.0,
+            "invalid arrival rate: {rate}"
+        );
+
+        Self {
+            rate,
+            // a rate of zero has no distribution, as no customers ever arrive
+            distribution: Poisson::new(rate).ok(),
+        }
+    }
+
+    /// The average number of customers arriving per step.
+    #[must_use]
+    pub const fn rate(&self) -> f64
+    {
+        self.rate
+    }
+
+    /// Draws the number of customers arriving in a step.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn sample(&self, rng: &mut impl Rng) -> usize
+    {
+        self.distribution
+            .map_or(0, |distribution| distribution.sample(rng) as usize)
+    }
+}
+
+/// The distribution of the number of steps a [`Server`] takes to serve a customer.
+///
+/// The drawn durations are rounded up to whole steps, so that every service takes at least one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServiceTime
+{
+    /// Every service takes the same duration.
+    Constant(f64),
+
+    /// Durations are exponentially distributed with the given mean, as in M/M/c models.
+    Exponential
+    {
+        mean: f64
+    },
+
+    /// Durations are uniformly distributed within `[min, max)`.
+    Uniform
+    {
+        min: f64, max: f64
+    },
+}
+
+impl ServiceTime
+{
+    /// Draws the number of steps of a service.
+    ///
+    /// # Panics
+    ///
+    /// If the parameters of the distribution are invalid, such as a non-positive mean.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn sample(&self, rng: &mut impl Rng) -> usize
+    {
+        let duration = match *self
+        {
+            Self::Constant(duration) => duration,
+            Self::Exponential { mean } =>
+            {
+                let Ok(exp) = Exp::new(1.0 / mean)
+                else
+                {
+                    panic!("invalid mean service time: {mean}");
+                };
+                exp.sample(rng)
+            }
+            Self::Uniform { min, max } => rng.random_range(min..max),
+        };
+
+        (duration.ceil() as usize).max(1)
+    }
+}
+
+/// Component of an entity serving the customers of a [`Queue`], one at a time.
+///
+/// Multiple servers may serve the same queue, as the operators of a call center.
+/// The servers that are idle at the start of a step take the customers at the front of their
+/// queues in turn, in a fixed order.
+#[derive(Component, Debug, Clone)]
+pub struct Server
+{
+    queue: Entity,
+    service: ServiceTime,
+    /// The number of steps left in the current service, or `0` if idle.
+    remaining: usize,
+    served: usize,
+    busy_steps: usize,
+    steps: usize,
+}
+
+impl Server
+{
+    /// Creates an idle server of the [`Queue`] of the given entity.
+    #[must_use]
+    pub const fn new(queue: Entity, service: ServiceTime) -> Self
+    {
+        Self {
+            queue,
+            service,
+            remaining: 0,
+            served: 0,
+            busy_steps: 0,
+            steps: 0,
+        }
+    }
+
+    /// The entity with the [`Queue`] served.
+    #[must_use]
+    pub const fn queue(&self) -> Entity
+    {
+        self.queue
+    }
+
+    /// Returns `true` if the server is currently serving a customer.
+    #[must_use]
+    pub const fn is_busy(&self) -> bool
+    {
+        self.remaining > 0
+    }
+
+    /// The number of customers that the server has started serving.
+    #[must_use]
+    pub const fn served(&self) -> usize
+    {
+        self.served
+    }
+
+    /// The fraction of steps in which the server has been busy, since it was spawned.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn utilization(&self) -> f64
+    {
+        if self.steps == 0
+        {
+            return 0.0;
+        }
+        self.busy_steps as f64 / self.steps as f64
+    }
+}
+
+/// The average number of steps waited by all customers served so far, over all queues,
+/// sampled from [`Queue`] components.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct WaitingTime(pub f64);
+
+/// The total number of customers currently waiting, over all queues,
+/// sampled from [`Queue`] components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct QueueLength(pub usize);
+
+/// The fraction of servers that are currently busy, sampled from [`Server`] components.
+///
+/// Averaged over time, this is the utilization of the servers.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Utilization(pub f64);
+
+impl SampleAggregate<WaitingTime> for Queue
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn sample_aggregate(components: &[&Self]) -> WaitingTime
+    {
+        let (total_wait, served) = components
+            .iter()
+            .fold((0, 0), |(total_wait, served), queue| {
+                (total_wait + queue.total_wait, served + queue.served)
+            });
+
+        if served == 0
+        {
+            return WaitingTime(0.0);
+        }
+        WaitingTime(total_wait as f64 / served as f64)
+    }
+}
+
+impl SampleAggregate<QueueLength> for Queue
+{
+    fn sample_aggregate(components: &[&Self]) -> QueueLength
+    {
+        QueueLength(components.iter().map(|queue| queue.len()).sum())
+    }
+}
+
+impl SampleAggregate<Utilization> for Server
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn sample_aggregate(components: &[&Self]) -> Utilization
+    {
+        let busy = components.iter().filter(|server| server.is_busy()).count();
+        Utilization(busy as f64 / components.len() as f64)
+    }
+}
+
+/// Plugin that advances the queues and servers of the simulation on every step.
+pub(crate) struct QueueingPlugin;
+
+impl Plugin for QueueingPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        // advance before any user-defined systems, so that they see the state of the current step
+        app.add_systems(PreUpdate, (arrival_system, service_system).chain());
+    }
+}
+
+/// System that adds the customers arriving in the current step to their queues.
+fn arrival_system(
+    mut query: Query<(&mut Queue, &Arrivals)>,
+    mut rng: ResMut<SimRng>,
+    step_number: Res<StepNumber>,
+)
+{
+    for (mut queue, arrivals) in &mut query
+    {
+        for _ in 0..arrivals.sample(&mut *rng)
+        {
+            queue.arrive(**step_number);
+        }
+    }
+}
+
+/// System that completes the services ending in the current step, and has idle servers take
+/// the next customers from their queues.
+fn service_system(
+    mut servers: Query<&mut Server>,
+    mut queues: Query<&mut Queue>,
+    mut rng: ResMut<SimRng>,
+    step_number: Res<StepNumber>,
+)
+{
+    for mut server in &mut servers
+    {
+        server.remaining = server.remaining.saturating_sub(1);
+
+        if server.remaining == 0
+            && queues
+                .get_mut(server.queue)
+                .is_ok_and(|mut queue| queue.serve(**step_number))
+        {
+            server.remaining = server.service.sample(&mut *rng);
+            server.served += 1;
+        }
+
+        server.steps += 1;
+        if server.is_busy()
+        {
+            server.busy_steps += 1;
+        }
+    }
+}
@@ -0,0 +1,562 @@
+//! Random walks on grids of one to three dimensions, or in continuous space, for the many
+//! Monte Carlo problems built on them, such as diffusion, the gambler's ruin or polymer chains.
+//!
+//! Every entity with a [`RandomWalker<P>`] component takes one random [`Step`] on each step of the
+//! simulation, when set up with [`crate::SimulationBuilder::add_random_walks`], drawing from the
+//! simulation's [`crate::SimRng`]. The space the walker moves in is given by its position type `P`,
+//! see [`WalkSpace`], and may be limited by a [`Boundary`] that reflects, wraps or absorbs it.
+//!
+//! Walkers record their first passage, which is the first step in which they reach their target,
+//! or are absorbed by their boundary. The distribution of first-passage times and the mean squared
+//! displacement of all walkers can be sampled with [`FirstPassageTimes`] and
+//! [`MeanSquaredDisplacement`].
+//!
+//! Walkers on 2D and 3D grids also move their [`GridPosition`], if they have one,
+//! so that they can be tracked by a [`crate::SpatialGrid`].
+//!
+//! Example of the gambler's ruin, starting with 3 coins and playing until either broke or 10 coins:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::random_walk::{Boundary, FirstPassageTimes, RandomWalker, Step};
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_random_walks::<i32>()
+//!     .add_entity_spawner(|spawner| {
+//!         for _ in 0..1000
+//!         {
+//!             spawner.spawn(RandomWalker::new(3, Step::Lattice).with_boundary(Boundary::Absorb { min: 0, max: 10 }));
+//!         }
+//!     })
+//!     .build();
+//!
+//! simulation.run(500);
+//! let FirstPassageTimes(durations) = simulation.sample_aggregate::<RandomWalker<i32>, _>().unwrap();
+//! let won = simulation.iter::<RandomWalker<i32>>().filter(|walker| walker.position() == 10).count();
+//! ```
+
+use std::fmt::Debug;
+
+use bevy::{
+    math::{DVec2, DVec3},
+    prelude::*,
+};
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+use crate::{
+    SampleAggregate, SimRng,
+    plugins::{GridCoordinates, GridPosition},
+};
+
+/// A sealed trait for the positions of a [`RandomWalker`], one for each space it can walk in.
+///
+/// Implemented for the grid coordinates [`i32`], [`IVec2`] and [`IVec3`], and the continuous
+/// coordinates [`f64`], [`DVec2`] and [`DVec3`].
+pub trait WalkSpace: private::Sealed + Copy + Debug + PartialEq + Send + Sync + 'static
+{
+    /// The number of coordinates of a position.
+    const DIMENSIONS: usize;
+
+    /// Whether positions are limited to the cells of a grid.
+    const DISCRETE: bool;
+
+    /// The coordinate of the position along the given axis.
+    fn coordinate(&self, axis: usize) -> f64;
+
+    /// Sets the coordinate of the position along the given axis,
+    /// rounded to the nearest cell on grids.
+    fn set_coordinate(&mut self, axis: usize, value: f64);
+
+    /// Adds the systems that move the walkers in this space to the simulation.
+    fn add_systems(app: &mut App)
+    {
+        app.add_systems(Update, random_walk_system::<Self>);
+    }
+}
+
+/// The distribution of the moves of a [`RandomWalker`] on every step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step
+{
+    /// Moves by one unit along a uniformly random axis, in either direction with equal probability,
+    /// as in the simple random walk.
+    Lattice,
+
+    /// Moves by one unit along a uniformly random axis, in the positive direction with the given
+    /// probability, and in the negative direction otherwise.
+    Biased(f64),
+
+    /// Moves along every axis by an independent, normally distributed amount,
+    /// rounded to whole cells on grids.
+    Gaussian
+    {
+        std_dev: f64
+    },
+}
+
+/// The limits of the space a [`RandomWalker`] moves in, and what happens when it reaches them.
+///
+/// The limits `min` and `max` are inclusive on every axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Boundary<P>
+{
+    /// The walker may move arbitrarily far.
+    #[default]
+    Unbounded,
+
+    /// Moves beyond the limits are mirrored back into them.
+    Reflect
+    {
+        min: P, max: P
+    },
+
+    /// Moves beyond a limit continue from the opposite one, as on a torus.
+    Wrap
+    {
+        min: P, max: P
+    },
+
+    /// The walker stops for good when it reaches a limit, which counts as its first passage.
+    Absorb
+    {
+        min: P, max: P
+    },
+}
+
+/// Component of an entity taking a random walk, see the [module documentation](self).
+#[derive(Component, Debug, Clone)]
+pub struct RandomWalker<P: WalkSpace>
+{
+    position: P,
+    origin: P,
+    step: Step,
+    boundary: Boundary<P>,
+    /// The position to reach, and the distance from it at which it counts as reached.
+    target: Option<(P, f64)>,
+    steps: usize,
+    first_passage: Option<usize>,
+    absorbed: bool,
+}
+
+impl<P: WalkSpace> RandomWalker<P>
+{
+    /// Creates an unbounded walker starting at the given position.
+    #[must_use]
+    pub const fn new(start: P, step: Step) -> Self
+    {
+        Self {
+            position: start,
+            origin: start,
+            step,
+            boundary: Boundary::Unbounded,
+            target: None,
+            steps: 0,
+            first_passage: None,
+            absorbed: false,
+        }
+    }
+
+    /// Sets the boundary of the space the walker moves in.
+    #[must_use]
+    pub const fn with_boundary(mut self, boundary: Boundary<P>) -> Self
+    {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Sets a target position, whose first passage is recorded when the walker comes within
+    /// `radius` of it. A `radius` of `0` requires reaching the exact cell on grids.
+    ///
+    /// The walker keeps walking after reaching its target.
+    #[must_use]
+    pub const fn with_target(mut self, target: P, radius: f64) -> Self
+    {
+        self.target = Some((target, radius));
+        self
+    }
+
+    /// The current position of the walker.
+    #[must_use]
+    pub const fn position(&self) -> P
+    {
+        self.position
+    }
+
+    /// The position the walker started from.
+    #[must_use]
+    pub const fn origin(&self) -> P
+    {
+        self.origin
+    }
+
+    /// The number of steps the walker has taken, not counting those after being absorbed.
+    #[must_use]
+    pub const fn steps(&self) -> usize
+    {
+        self.steps
+    }
+
+    /// The number of steps the walker took to first reach its target, or be absorbed by its
+    /// boundary, if it has.
+    #[must_use]
+    pub const fn first_passage(&self) -> Option<usize>
+    {
+        self.first_passage
+    }
+
+    /// Returns `true` if the walker has been stopped by an absorbing boundary.
+    #[must_use]
+    pub const fn is_absorbed(&self) -> bool
+    {
+        self.absorbed
+    }
+
+    /// The squared distance between the walker's position and its origin.
+    ///
+    /// On wrapping boundaries, this is measured within the bounds, rather than along the path taken.
+    #[must_use]
+    pub fn squared_displacement(&self) -> f64
+    {
+        squared_distance(&self.position, &self.origin)
+    }
+
+    /// Takes a random step, and applies the boundary to the new position.
+    fn walk(&mut self, rng: &mut impl Rng)
+    {
+        if self.absorbed
+        {
+            return;
+        }
+
+        match self.step
+        {
+            Step::Lattice => self.move_along_axis(0.5, rng),
+            Step::Biased(forward) => self.move_along_axis(forward, rng),
+            Step::Gaussian { std_dev } =>
+            {
+                let Ok(normal) = Normal::new(0.0, std_dev)
+                else
+                {
+                    panic!("invalid step standard deviation: {std_dev}");
+                };
+                for axis in 0..P::DIMENSIONS
+                {
+                    let value = self.position.coordinate(axis) + normal.sample(rng);
+                    self.position.set_coordinate(axis, value);
+                }
+            }
+        }
+        self.steps += 1;
+
+        self.apply_boundary();
+        if self.first_passage.is_none()
+            && (self.absorbed
+                || self.target.is_some_and(|(target, radius)| {
+                    squared_distance(&self.position, &target) <= radius * radius
+                }))
+        {
+            self.first_passage = Some(self.steps);
+        }
+    }
+
+    fn move_along_axis(&mut self, forward: f64, rng: &mut impl Rng)
+    {
+        let axis = rng.random_range(0..P::DIMENSIONS);
+        let direction = if rng.random_bool(forward) { 1.0 } else { -1.0 };
+        self.position
+            .set_coordinate(axis, self.position.coordinate(axis) + direction);
+    }
+
+    fn apply_boundary(&mut self)
+    {
+        let (min, max) = match self.boundary
+        {
+            Boundary::Unbounded => return,
+            Boundary::Reflect { min, max }
+            | Boundary::Wrap { min, max }
+            | Boundary::Absorb { min, max } => (min, max),
+        };
+
+        for axis in 0..P::DIMENSIONS
+        {
+            let (min, max) = (min.coordinate(axis), max.coordinate(axis));
+            let mut value = self.position.coordinate(axis);
+
+            match self.boundary
+            {
+                Boundary::Reflect { .. } if max > min =>
+                {
+                    // steps larger than the bounds may need to be mirrored more than once
+                    while value < min || value > max
+                    {
+                        value = if value < min
+                        {
+                            min + (min - value)
+                        }
+                        else
+                        {
+                            max - (value - max)
+                        };
+                    }
+                }
+                Boundary::Wrap { .. } =>
+                {
+                    // on grids, the cells at both limits are distinct
+                    let width = if P::DISCRETE
+                    {
+                        max - min + 1.0
+                    }
+                    else
+                    {
+                        max - min
+                    };
+                    if width > 0.0
+                    {
+                        value = min + (value - min).rem_euclid(width);
+                    }
+                }
+                Boundary::Absorb { .. } if value <= min || value >= max =>
+                {
+                    value = value.clamp(min, max);
+                    self.absorbed = true;
+                }
+                _ => value = value.clamp(min, max),
+            }
+
+            self.position.set_coordinate(axis, value);
+        }
+    }
+}
+
+fn squared_distance<P: WalkSpace>(a: &P, b: &P) -> f64
+{
+    (0..P::DIMENSIONS)
+        .map(|axis| (a.coordinate(axis) - b.coordinate(axis)).powi(2))
+        .sum()
+}
+
+/// The mean of the squared displacements of all walkers from their origins,
+/// sampled from [`RandomWalker`] components.
+///
+/// For unbounded walks, this grows linearly with the number of steps, as in diffusion.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct MeanSquaredDisplacement(pub f64);
+
+/// The first-passage times of all walkers that have had their first passage, in ascending order,
+/// sampled from [`RandomWalker`] components.
+///
+/// This is a sample of the distribution of the first-passage time, conditioned on it being
+/// at most the number of steps simulated.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FirstPassageTimes(pub Vec<usize>);
+
+impl<P: WalkSpace> SampleAggregate<MeanSquaredDisplacement> for RandomWalker<P>
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn sample_aggregate(components: &[&Self]) -> MeanSquaredDisplacement
+    {
+        let total = components
+            .iter()
+            .map(|walker| walker.squared_displacement())
+            .sum::<f64>();
+        MeanSquaredDisplacement(total / components.len() as f64)
+    }
+}
+
+impl<P: WalkSpace> SampleAggregate<FirstPassageTimes> for RandomWalker<P>
+{
+    fn sample_aggregate(components: &[&Self]) -> FirstPassageTimes
+    {
+        let mut times = components
+            .iter()
+            .filter_map(|walker| walker.first_passage)
+            .collect::<Vec<_>>();
+        times.sort_unstable();
+        FirstPassageTimes(times)
+    }
+}
+
+/// Plugin that moves all walkers in the space `P` on every step.
+pub(crate) struct RandomWalkPlugin<P>(std::marker::PhantomData<P>);
+
+impl<P> Default for RandomWalkPlugin<P>
+{
+    fn default() -> Self
+    {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<P: WalkSpace> Plugin for RandomWalkPlugin<P>
+{
+    fn build(&self, app: &mut App)
+    {
+        P::add_systems(app);
+    }
+}
+
+/// System that has every walker take a step.
+fn random_walk_system<P: WalkSpace>(mut query: Query<&mut RandomWalker<P>>, mut rng: ResMut<SimRng>)
+{
+    for mut walker in &mut query
+    {
+        if !walker.absorbed
+        {
+            walker.walk(&mut *rng);
+        }
+    }
+}
+
+/// Query for walkers on a grid that have moved, along with their grid positions.
+type MovedWalkerQuery<'world, 'state, T> = Query<
+    'world,
+    'state,
+    (&'static RandomWalker<T>, &'static mut GridPosition<T>),
+    Changed<RandomWalker<T>>,
+>;
+
+/// System that moves the grid positions of walkers on a grid to their new positions.
+fn grid_position_sync_system<T: WalkSpace + GridCoordinates>(mut query: MovedWalkerQuery<T>)
+{
+    for (walker, mut position) in &mut query
+    {
+        position.set_if_neq(GridPosition(walker.position));
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn round(value: f64) -> i32
+{
+    value.round() as i32
+}
+
+impl WalkSpace for i32
+{
+    const DIMENSIONS: usize = 1;
+    const DISCRETE: bool = true;
+
+    fn coordinate(&self, _: usize) -> f64
+    {
+        f64::from(*self)
+    }
+
+    fn set_coordinate(&mut self, _: usize, value: f64)
+    {
+        *self = round(value);
+    }
+}
+
+impl WalkSpace for IVec2
+{
+    const DIMENSIONS: usize = 2;
+    const DISCRETE: bool = true;
+
+    fn coordinate(&self, axis: usize) -> f64
+    {
+        f64::from(self[axis])
+    }
+
+    fn set_coordinate(&mut self, axis: usize, value: f64)
+    {
+        self[axis] = round(value);
+    }
+
+    fn add_systems(app: &mut App)
+    {
+        app.add_systems(
+            Update,
+            (
+                random_walk_system::<Self>,
+                grid_position_sync_system::<Self>,
+            )
+                .chain(),
+        );
+    }
+}
+
+impl WalkSpace for IVec3
+{
+    const DIMENSIONS: usize = 3;
+    const DISCRETE: bool = true;
+
+    fn coordinate(&self, axis: usize) -> f64
+    {
+        f64::from(self[axis])
+    }
+
+    fn set_coordinate(&mut self, axis: usize, value: f64)
+    {
+        self[axis] = round(value);
+    }
+
+    fn add_systems(app: &mut App)
+    {
+        app.add_systems(
+            Update,
+            (
+                random_walk_system::<Self>,
+                grid_position_sync_system::<Self>,
+            )
+                .chain(),
+        );
+    }
+}
+
+impl WalkSpace for f64
+{
+    const DIMENSIONS: usize = 1;
+    const DISCRETE: bool = false;
+
+    fn coordinate(&self, _: usize) -> f64
+    {
+        *self
+    }
+
+    fn set_coordinate(&mut self, _: usize, value: f64)
+    {
+        *self = value;
+    }
+}
+
+impl WalkSpace for DVec2
+{
+    const DIMENSIONS: usize = 2;
+    const DISCRETE: bool = false;
+
+    fn coordinate(&self, axis: usize) -> f64
+    {
+        self[axis]
+    }
+
+    fn set_coordinate(&mut self, axis: usize, value: f64)
+    {
+        self[axis] = value;
+    }
+}
+
+impl WalkSpace for DVec3
+{
+    const DIMENSIONS: usize = 3;
+    const DISCRETE: bool = false;
+
+    fn coordinate(&self, axis: usize) -> f64
+    {
+        self[axis]
+    }
+
+    fn set_coordinate(&mut self, axis: usize, value: f64)
+    {
+        self[axis] = value;
+    }
+}
+
+mod private
+{
+    pub trait Sealed {}
+    impl Sealed for i32 {}
+    impl Sealed for bevy::prelude::IVec2 {}
+    impl Sealed for bevy::prelude::IVec3 {}
+    impl Sealed for f64 {}
+    impl Sealed for bevy::math::DVec2 {}
+    impl Sealed for bevy::math::DVec3 {}
+}
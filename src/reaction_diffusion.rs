@@ -0,0 +1,428 @@
+//! Reaction-diffusion models, in which coupled scalar fields over a bounded grid, such as the
+//! concentrations of nutrients, pollutants or morphogens, diffuse and react with each other on
+//! every step.
+//!
+//! The solver is added to a simulation with [`crate::SimulationBuilder::add_reaction_diffusion`],
+//! configured through a [`ReactionDiffusionPlugin`] with the diffusion coefficient of every field
+//! and an optional reaction term, which gives the rate of change of every field in a cell from
+//! their values in that cell.
+//! Every step advances the fields by one unit of time, using the explicit Euler method over a
+//! number of substeps, and the 5-point stencil of the Laplacian with a grid spacing of `1`.
+//!
+//! The fields are kept in the [`ScalarFields`] resource, through which agents with a
+//! [`crate::GridPosition`] read and write the values in their cells, making for hybrid
+//! agent-field models. The fields are updated before any user-defined systems run, so that the
+//! agents see the fields of the current step, and their changes diffuse from the next one.
+//!
+//! Example of bacteria consuming a diffusing nutrient:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::reaction_diffusion::{ReactionDiffusionPlugin, ScalarFields};
+//!
+//! #[derive(Component)]
+//! struct Bacterium
+//! {
+//!     energy: f64,
+//! }
+//!
+//! let bounds = GridBounds2D {
+//!     min: IVec2::new(0, 0),
+//!     max: IVec2::new(31, 31),
+//! };
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_reaction_diffusion(
+//!         ReactionDiffusionPlugin::new(bounds)
+//!             .with_field("nutrient", 0.2, 1.0)
+//!             // the nutrient is replenished towards its initial level
+//!             .with_reaction(|values, rates| rates[0] = 0.01 * (1.0 - values[0])),
+//!     )
+//!     .add_entity_spawner(|spawner| {
+//!         spawner.spawn((Bacterium { energy: 0.0 }, GridPosition2D::new(16, 16)));
+//!     })
+//!     .add_systems(|mut fields: ResMut<ScalarFields>, mut query: Query<(&mut Bacterium, &GridPosition2D)>| {
+//!         for (mut bacterium, position) in &mut query
+//!         {
+//!             let available = fields.get("nutrient", position.0).unwrap_or(0.0);
+//!             let eaten = available.min(0.5);
+//!             fields.add("nutrient", position.0, -eaten);
+//!             bacterium.energy += eaten;
+//!         }
+//!     })
+//!     .build();
+//!
+//! simulation.run(100);
+//! let fields = simulation.get_resource::<ScalarFields>().unwrap();
+//! assert!(fields.get("nutrient", IVec2::new(16, 16)).unwrap() < 1.0);
+//! ```
+
+use std::sync::Arc;
+
+use bevy::{math::DVec2, prelude::*};
+
+use crate::plugins::{GridBounds2D, GridCoordinates};
+
+/// The reaction term, computing the rates of change of all fields in a cell from their values.
+type Reaction = Arc<dyn Fn(&[f64], &mut [f64]) + Send + Sync>;
+
+/// How the fields behave at the edges of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Boundary
+{
+    /// Nothing flows across the edges, so that diffusion alone conserves the total of every field.
+    #[default]
+    NoFlux,
+
+    /// The edges wrap around, so that the cells on opposite edges are adjacent, as on a torus.
+    Periodic,
+
+    /// The fields are held at `0` outside the edges, so that they diffuse out of the grid.
+    Absorbing,
+}
+
+/// A scalar field, along with its diffusion coefficient.
+struct Field
+{
+    name: &'static str,
+    diffusion: f64,
+    values: Vec<f64>,
+}
+
+/// Plugin that solves a reaction-diffusion model over a bounded grid, added to a simulation with
+/// [`crate::SimulationBuilder::add_reaction_diffusion`].
+pub struct ReactionDiffusionPlugin
+{
+    bounds: GridBounds2D,
+    boundary: Boundary,
+    substeps: usize,
+    fields: Vec<(&'static str, f64, f64)>,
+    reaction: Option<Reaction>,
+}
+
+impl ReactionDiffusionPlugin
+{
+    /// Creates a solver on the given `bounds`, without any fields.
+    ///
+    /// By default, the edges of the grid are [`Boundary::NoFlux`], and every step is a single
+    /// explicit substep.
+    #[must_use]
+    pub const fn new(bounds: GridBounds2D) -> Self
+    {
+        Self {
+            bounds,
+            boundary: Boundary::NoFlux,
+            substeps: 1,
+            fields: Vec::new(),
+            reaction: None,
+        }
+    }
+
+    /// Adds a field with the given `diffusion` coefficient, with the same `initial` value in
+    /// every cell.
+    ///
+    /// Fields are passed to the reaction term in the order they are added.
+    ///
+    /// # Panics
+    ///
+    /// If a field with the same name already exists, or `diffusion` is negative or not finite.
+    #[must_use]
+    pub fn with_field(mut self, name: &'static str, diffusion: f64, initial: f64) -> Self
+    {
+        assert!(
+            self.fields.iter().all(|(existing, _, _)| *existing != name),
+            "duplicate field: {name}"
+        );
+        assert!(
+            diffusion.is_finite() && diffusion >= 0.0,
+            "invalid diffusion coefficient: {diffusion}"
+        );
+
+        self.fields.push((name, diffusion, initial));
+        self
+    }
+
+    /// Sets the reaction term, which is called for every cell with the values of all fields in
+    /// it, and writes the rate of change of every field into the second slice, which is zeroed
+    /// beforehand.
+    #[must_use]
+    pub fn with_reaction(
+        mut self,
+        reaction: impl Fn(&[f64], &mut [f64]) + Send + Sync + 'static,
+    ) -> Self
+    {
+        self.reaction = Some(Arc::new(reaction));
+        self
+    }
+
+    /// Sets how the fields behave at the edges of the grid.
+    #[must_use]
+    pub const fn with_boundary(mut self, boundary: Boundary) -> Self
+    {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Sets the number of explicit substeps that every step is divided into.
+    ///
+    /// The explicit method is only stable when the diffusion coefficient of every field, divided
+    /// by the number of substeps, is at most `0.25`, so fast diffusion needs more substeps.
+    ///
+    /// # Panics
+    ///
+    /// If `substeps` is `0`.
+    #[must_use]
+    pub const fn with_substeps(mut self, substeps: usize) -> Self
+    {
+        assert!(substeps > 0, "the number of substeps must be positive");
+        self.substeps = substeps;
+        self
+    }
+}
+
+impl Plugin for ReactionDiffusionPlugin
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn build(&self, app: &mut App)
+    {
+        let dt = 1.0 / self.substeps as f64;
+        for &(name, diffusion, _) in &self.fields
+        {
+            assert!(
+                diffusion * dt <= 0.25,
+                "unstable diffusion of field {name}: use at least {} substeps",
+                (diffusion * 4.0).ceil()
+            );
+        }
+
+        let cells = IVec2::cell_count(&self.bounds);
+        app.insert_resource(ScalarFields {
+            bounds: self.bounds,
+            boundary: self.boundary,
+            substeps: self.substeps,
+            reaction: self.reaction.clone(),
+            fields: self
+                .fields
+                .iter()
+                .map(|&(name, diffusion, initial)| Field {
+                    name,
+                    diffusion,
+                    values: vec![initial; cells],
+                })
+                .collect(),
+            buffer: Vec::new(),
+        })
+        .add_systems(PreUpdate, reaction_diffusion_system);
+    }
+}
+
+/// Resource holding the scalar fields of a reaction-diffusion model, see the
+/// [module documentation](self).
+///
+/// This resource can be accessed in user-defined systems using [`Res<ScalarFields>`] or
+/// [`ResMut<ScalarFields>`] arguments, to read or write the fields.
+#[derive(Resource)]
+pub struct ScalarFields
+{
+    bounds: GridBounds2D,
+    boundary: Boundary,
+    substeps: usize,
+    reaction: Option<Reaction>,
+    fields: Vec<Field>,
+
+    /// The values of the fields at the start of the current substep.
+    buffer: Vec<Vec<f64>>,
+}
+
+impl ScalarFields
+{
+    /// The bounds of the grid the fields are defined on.
+    #[must_use]
+    pub const fn bounds(&self) -> GridBounds2D
+    {
+        self.bounds
+    }
+
+    /// The names of all fields, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_
+    {
+        self.fields.iter().map(|field| field.name)
+    }
+
+    fn field(&self, name: &str) -> Option<&Field>
+    {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// The values of a field in every cell, in the order of [`GridCoordinates::iter_bounds`].
+    #[must_use]
+    pub fn values(&self, name: &str) -> Option<&[f64]>
+    {
+        self.field(name).map(|field| field.values.as_slice())
+    }
+
+    /// The value of a field in the cell at the given position, if both exist.
+    #[must_use]
+    pub fn get(&self, name: &str, position: IVec2) -> Option<f64>
+    {
+        if !self.bounds.contains(&position)
+        {
+            return None;
+        }
+
+        self.field(name)
+            .map(|field| field.values[position.cell_index(&self.bounds)])
+    }
+
+    /// A mutable reference to the value of a field in the cell at the given position, if both
+    /// exist.
+    pub fn get_mut(&mut self, name: &str, position: IVec2) -> Option<&mut f64>
+    {
+        if !self.bounds.contains(&position)
+        {
+            return None;
+        }
+
+        let index = position.cell_index(&self.bounds);
+        self.fields
+            .iter_mut()
+            .find(|field| field.name == name)
+            .map(|field| &mut field.values[index])
+    }
+
+    /// Sets the value of a field in the cell at the given position.
+    ///
+    /// Returns `false` if the field or the position do not exist.
+    pub fn set(&mut self, name: &str, position: IVec2, value: f64) -> bool
+    {
+        self.get_mut(name, position)
+            .map(|current| *current = value)
+            .is_some()
+    }
+
+    /// Adds the `amount` to the value of a field in the cell at the given position, which may be
+    /// negative to remove from it.
+    ///
+    /// Returns `false` if the field or the position do not exist.
+    pub fn add(&mut self, name: &str, position: IVec2, amount: f64) -> bool
+    {
+        self.get_mut(name, position)
+            .map(|current| *current += amount)
+            .is_some()
+    }
+
+    /// The sum of the values of a field over all cells.
+    #[must_use]
+    pub fn total(&self, name: &str) -> Option<f64>
+    {
+        self.field(name).map(|field| field.values.iter().sum())
+    }
+
+    /// The gradient of a field at the given position, from the central differences of its
+    /// adjacent cells, pointing towards higher values.
+    ///
+    /// This is the direction that agents performing chemotaxis move in.
+    #[must_use]
+    pub fn gradient(&self, name: &str, position: IVec2) -> Option<DVec2>
+    {
+        let field = self.field(name)?;
+        if !self.bounds.contains(&position)
+        {
+            return None;
+        }
+
+        let value = field.values[position.cell_index(&self.bounds)];
+        let at = |offset: IVec2| {
+            neighbor_value(
+                &self.bounds,
+                self.boundary,
+                &field.values,
+                position + offset,
+                value,
+            )
+        };
+        Some(DVec2::new(
+            (at(IVec2::X) - at(IVec2::NEG_X)) / 2.0,
+            (at(IVec2::Y) - at(IVec2::NEG_Y)) / 2.0,
+        ))
+    }
+
+    /// Advances all fields by one explicit substep of length `dt`.
+    fn substep(&mut self, dt: f64)
+    {
+        // the buffers are kept between steps to reuse their allocations
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.resize_with(self.fields.len(), Vec::new);
+        for (copy, field) in buffer.iter_mut().zip(&self.fields)
+        {
+            copy.clone_from(&field.values);
+        }
+
+        let mut values = vec![0.0; self.fields.len()];
+        let mut rates = vec![0.0; self.fields.len()];
+        for (index, position) in IVec2::iter_bounds(&self.bounds).enumerate()
+        {
+            for (value, copy) in values.iter_mut().zip(&buffer)
+            {
+                *value = copy[index];
+            }
+            rates.fill(0.0);
+            if let Some(reaction) = &self.reaction
+            {
+                reaction(&values, &mut rates);
+            }
+
+            for ((field, copy), rate) in self.fields.iter_mut().zip(&buffer).zip(&rates)
+            {
+                let own = copy[index];
+                let laplacian = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+                    .into_iter()
+                    .map(|offset| {
+                        neighbor_value(&self.bounds, self.boundary, copy, position + offset, own)
+                            - own
+                    })
+                    .sum::<f64>();
+                field.values[index] = field.diffusion.mul_add(laplacian, *rate).mul_add(dt, own);
+            }
+        }
+
+        self.buffer = buffer;
+    }
+}
+
+/// The value of a field in a cell adjacent to a cell with value `own`, according to the
+/// `boundary` if the cell is outside the grid.
+fn neighbor_value(
+    bounds: &GridBounds2D,
+    boundary: Boundary,
+    values: &[f64],
+    position: IVec2,
+    own: f64,
+) -> f64
+{
+    if bounds.contains(&position)
+    {
+        return values[position.cell_index(bounds)];
+    }
+
+    match boundary
+    {
+        Boundary::NoFlux => own,
+        Boundary::Absorbing => 0.0,
+        Boundary::Periodic =>
+        {
+            let size = bounds.max - bounds.min + IVec2::ONE;
+            let position = bounds.min + (position - bounds.min).rem_euclid(size);
+            values[position.cell_index(bounds)]
+        }
+    }
+}
+
+/// System that advances all fields by one unit of time.
+#[allow(clippy::cast_precision_loss)]
+fn reaction_diffusion_system(mut fields: ResMut<ScalarFields>)
+{
+    let dt = 1.0 / fields.substeps as f64;
+    for _ in 0..fields.substeps
+    {
+        fields.substep(dt);
+    }
+}
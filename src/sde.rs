@@ -0,0 +1,232 @@
+//! Numerical integration of stochastic differential equations, for components whose state
+//! evolves continuously under random noise, such as stock prices, interest rates or temperatures.
+//!
+//! Every entity with a [`StochasticProcess`] component advances its value by one time increment
+//! on each step of the simulation, when set up with
+//! [`crate::SimulationBuilder::add_stochastic_processes`]. The value `x` follows the equation
+//! `dx = drift(t, x) dt + diffusion(t, x) dW`, defined by its [`Process`], and is integrated with
+//! the Euler–Maruyama scheme, drawing the increments of the Wiener process `W` from the
+//! simulation's [`crate::SimRng`].
+//!
+//! The value of a process can be sampled as an [`f64`], so that it can be recorded with
+//! [`crate::SimulationBuilder::record_time_series`].
+//!
+//! Example of a stock price following a geometric Brownian motion:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::sde::{Process, StochasticProcess};
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_stochastic_processes()
+//!     .add_entity_spawner(|spawner| {
+//!         spawner.spawn(StochasticProcess::new(100.0, Process::GeometricBrownian { mu: 0.05, sigma: 0.2 }).with_dt(1.0 / 365.0));
+//!     })
+//!     .build();
+//!
+//! simulation.run(365);
+//! let price = simulation.sample_single::<StochasticProcess, f64>().unwrap();
+//! ```
+
+use bevy::prelude::*;
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::SimRng;
+
+/// The drift or diffusion coefficient of a user-defined process, as a function of the time `t`
+/// and the value `x` of the process.
+pub type Coefficient = fn(t: f64, x: f64) -> f64;
+
+/// The stochastic differential equation followed by a [`StochasticProcess`].
+#[derive(Debug, Clone, Copy)]
+pub enum Process
+{
+    /// Brownian motion with constant drift `mu` and volatility `sigma`,
+    /// where `dx = mu dt + sigma dW`.
+    Brownian
+    {
+        mu: f64, sigma: f64
+    },
+
+    /// Geometric Brownian motion, where `dx = mu x dt + sigma x dW`.
+    ///
+    /// The value grows at the rate `mu` on average, with relative fluctuations of size `sigma`,
+    /// which is the standard model of stock prices.
+    GeometricBrownian
+    {
+        mu: f64, sigma: f64
+    },
+
+    /// The Ornstein–Uhlenbeck process, where `dx = theta (mean - x) dt + sigma dW`.
+    ///
+    /// The value reverts towards `mean` at the rate `theta`, as in models of interest rates.
+    OrnsteinUhlenbeck
+    {
+        theta: f64, mean: f64, sigma: f64
+    },
+
+    /// A process with user-defined drift and diffusion coefficients,
+    /// where `dx = drift(t, x) dt + diffusion(t, x) dW`.
+    Custom
+    {
+        drift: Coefficient,
+        diffusion: Coefficient,
+    },
+}
+
+impl Process
+{
+    /// The drift coefficient of the process at time `t` and value `x`.
+    #[must_use]
+    pub fn drift(&self, t: f64, x: f64) -> f64
+    {
+        match *self
+        {
+            Self::Brownian { mu, .. } => mu,
+            Self::GeometricBrownian { mu, .. } => mu * x,
+            Self::OrnsteinUhlenbeck { theta, mean, .. } => theta * (mean - x),
+            Self::Custom { drift, .. } => drift(t, x),
+        }
+    }
+
+    /// The diffusion coefficient of the process at time `t` and value `x`.
+    #[must_use]
+    pub fn diffusion(&self, t: f64, x: f64) -> f64
+    {
+        match *self
+        {
+            Self::Brownian { sigma, .. } | Self::OrnsteinUhlenbeck { sigma, .. } => sigma,
+            Self::GeometricBrownian { sigma, .. } => sigma * x,
+            Self::Custom { diffusion, .. } => diffusion(t, x),
+        }
+    }
+}
+
+/// Component holding the value of a stochastic process, see the [module documentation](self).
+#[derive(Component, Debug, Clone)]
+pub struct StochasticProcess
+{
+    value: f64,
+    process: Process,
+    /// The time increment of every step.
+    dt: f64,
+    /// The time elapsed since the process was spawned.
+    time: f64,
+    /// The value at or below which the process stops.
+    lower_bound: Option<f64>,
+    absorbed: bool,
+}
+
+impl StochasticProcess
+{
+    /// Creates a process starting at the given value, advancing by a time increment of `1.0`
+    /// on every step.
+    #[must_use]
+    pub const fn new(initial: f64, process: Process) -> Self
+    {
+        Self {
+            value: initial,
+            process,
+            dt: 1.0,
+            time: 0.0,
+            lower_bound: None,
+            absorbed: false,
+        }
+    }
+
+    /// Sets the time increment of every step.
+    ///
+    /// Smaller increments integrate the process more accurately, at the cost of more steps to
+    /// cover the same time span.
+    ///
+    /// # Panics
+    ///
+    /// If `dt` is not positive and finite.
+    #[must_use]
+    pub fn with_dt(mut self, dt: f64) -> Self
+    {
+        assert!(dt.is_finite() && dt > 0.0, "invalid time increment: {dt}");
+        self.dt = dt;
+        self
+    }
+
+    /// Sets a lower bound, on reaching which the process stops for good at that value,
+    /// as with a company going bankrupt.
+    #[must_use]
+    pub const fn with_lower_bound(mut self, bound: f64) -> Self
+    {
+        self.lower_bound = Some(bound);
+        self
+    }
+
+    /// The current value of the process.
+    #[must_use]
+    pub const fn value(&self) -> f64
+    {
+        self.value
+    }
+
+    /// The time elapsed since the process was spawned, not counting the time after being absorbed.
+    #[must_use]
+    pub const fn time(&self) -> f64
+    {
+        self.time
+    }
+
+    /// Returns `true` if the process has reached its lower bound, and no longer changes.
+    #[must_use]
+    pub const fn is_absorbed(&self) -> bool
+    {
+        self.absorbed
+    }
+
+    /// Advances the process by one time increment, with the Euler–Maruyama scheme.
+    fn integrate(&mut self, rng: &mut impl Rng)
+    {
+        let noise = rng.sample::<f64, _>(StandardNormal) * self.dt.sqrt();
+        let (t, x) = (self.time, self.value);
+
+        self.value = self
+            .process
+            .diffusion(t, x)
+            .mul_add(noise, self.process.drift(t, x).mul_add(self.dt, x));
+        self.time += self.dt;
+
+        if let Some(bound) = self.lower_bound.filter(|&bound| self.value <= bound)
+        {
+            self.value = bound;
+            self.absorbed = true;
+        }
+    }
+}
+
+impl From<&StochasticProcess> for f64
+{
+    fn from(process: &StochasticProcess) -> Self
+    {
+        process.value
+    }
+}
+
+/// Plugin that integrates all stochastic processes on every step.
+pub(crate) struct StochasticProcessPlugin;
+
+impl Plugin for StochasticProcessPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(Update, sde_integration_system);
+    }
+}
+
+/// System that advances every process that has not been absorbed by one time increment.
+fn sde_integration_system(mut query: Query<&mut StochasticProcess>, mut rng: ResMut<SimRng>)
+{
+    for mut process in &mut query
+    {
+        if !process.absorbed
+        {
+            process.integrate(&mut *rng);
+        }
+    }
+}
@@ -1,10 +1,37 @@
+#[cfg(not(target_family = "wasm"))]
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc,
+};
+use std::{any::type_name, ops::ControlFlow};
+#[cfg(not(any(feature = "rayon", target_family = "wasm")))]
+use std::{num::NonZero, panic, sync::atomic::AtomicUsize, thread};
+
 use bevy::{
-    ecs::query::{QueryFilter, QuerySingleError},
+    app::{MainScheduleOrder, SubApp},
+    ecs::{
+        component::Mutable,
+        query::{QueryFilter, QuerySingleError},
+        schedule::ScheduleLabel,
+    },
+    platform::time::Instant,
     prelude::*,
 };
 
+#[cfg(feature = "serde")]
+use crate::error::ExportError;
 use crate::{
-    Identifier, Sample, TimeSeries, error::SamplingError, plugins::TimeSeriesData,
+    Allocation, BenchmarkReport, ControlVariate, EnsembleResult, EntityHandle, Identifier,
+    KernelDensity, MemoryReport, ParallelSampling, ReplicationPlan, Sample, SimRng,
+    SimulationBuilder, SimulationMeta, StepNumber, Stratification, StratifiedResult,
+    StrictnessPolicy, TimeSeries,
+    error::{NumericGuardError, SamplingError},
+    gillespie::{SimulationTime, Trajectory, TrajectoryData},
+    plugins::{
+        EventLog, IncrementalAggregate, MemoryReporters, NumericGuardViolation, PopulationLedger,
+        StepEndHooks, TimeSeriesData, run_step_end_hooks,
+    },
+    trace::trace_span,
     traits::SampleAggregate,
 };
 
@@ -21,13 +48,743 @@ pub struct Simulation
 
 impl Simulation
 {
+    /// Reconstructs a simulation from the main sub-app of another.
+    pub(crate) fn from_main(main: SubApp) -> Self
+    {
+        let mut app = App::empty();
+        *app.main_mut() = main;
+
+        Self { app }
+    }
+
+    /// Deconstructs the simulation into its main sub-app, which holds the simulation's
+    /// world and schedules, and can be sent across threads.
+    pub(crate) fn into_main(mut self) -> SubApp
+    {
+        std::mem::take(self.app.main_mut())
+    }
+
+    /// Builds and runs a number of independent replicas of a simulation in parallel.
+    ///
+    /// The function `build_replica` is called once for each replica with its index,
+    /// in the range `0..num_replicas`, and shall return the builder of that replica.
+    /// Replicas whose builder does not set a seed are seeded independently, each with its own
+    /// stream derived from a common random seed with [`SimRng::derive_seed`]. The seed of every
+    /// replica is recorded in its [`SimulationMeta`]. To make the whole batch reproducible,
+    /// see [`Self::replicate_seeded`].
+    ///
+    /// Each replica is then built and run for `num_steps` steps on a pool of worker threads,
+    /// one for each available core, or on rayon's global thread pool with the `rayon` feature enabled.
+    /// Since the replicas already keep every core busy, each of them runs its systems on its
+    /// worker thread, see [`SimulationBuilder::single_threaded`], instead of contending for the
+    /// compute task pool shared by all simulations in the process.
+    /// The replicas are returned in order of their index, once all of them have completed.
+    ///
+    /// # Panics
+    ///
+    /// If any system in any of the replicas panicked, the panic is propagated to the caller.
+    pub fn replicate<F>(num_replicas: usize, num_steps: usize, build_replica: F) -> Vec<Self>
+    where
+        F: Fn(usize) -> SimulationBuilder + Sync,
+    {
+        let seed = rand::random();
+        let run_replica = |idx: usize| {
+            let mut simulation = build_replica(idx)
+                .set_default_seed(SimRng::derive_seed(seed, idx as u64))
+                .single_threaded(true)
+                .build();
+            simulation.run(num_steps);
+            simulation.into_main()
+        };
+
+        Self::run_replicas(num_replicas, run_replica)
+            .into_iter()
+            .map(Self::from_main)
+            .collect()
+    }
+
+    /// Builds and runs a number of independent replicas of a simulation in parallel,
+    /// each seeded differently from the common `seed`.
+    ///
+    /// The seed of each replica is derived from `seed` and its index using [`SimRng::derive_seed`],
+    /// overriding any seed set by `build_replica`. The results of the whole batch are thus
+    /// reproducible given the same `seed`, regardless of the order in which the replicas run.
+    ///
+    /// See [`Self::replicate`] for details.
+    ///
+    /// # Panics
+    ///
+    /// If any system in any of the replicas panicked, the panic is propagated to the caller.
+    pub fn replicate_seeded<F>(
+        seed: u64,
+        num_replicas: usize,
+        num_steps: usize,
+        build_replica: F,
+    ) -> Vec<Self>
+    where
+        F: Fn(usize) -> SimulationBuilder + Sync,
+    {
+        Self::replicate(num_replicas, num_steps, |idx| {
+            build_replica(idx).set_seed(SimRng::derive_seed(seed, idx as u64))
+        })
+    }
+
+    /// Builds and runs a number of independent replicas of a simulation in parallel, like
+    /// [`Self::replicate`], reporting the estimate of a scalar outcome as each of them completes.
+    ///
+    /// Once a replica has run for `num_steps` steps, the function `outcome` samples its value,
+    /// and `on_estimate` is called on the calling thread with the [`EnsembleResult`] of all
+    /// replicas completed so far, in the order they completed.
+    /// Its running mean, standard error and [`EnsembleResult::additional_runs`] can be used to
+    /// watch the estimate converge.
+    /// Returning [`ControlFlow::Break`] aborts the experiment early, e.g. once the estimate is
+    /// precise enough, or the configuration is deemed hopeless. No more replicas are started
+    /// then, and the outcomes of those that were still running are discarded.
+    ///
+    /// Replicas whose builder does not set a seed are seeded independently, as in
+    /// [`Self::replicate`].
+    ///
+    /// Returns the outcomes of the completed replicas, in order of their index, so that the
+    /// result of a batch that runs to the end is reproducible regardless of the order in which
+    /// the replicas complete.
+    /// If the replicas hold a [`crate::ControlVariate`], it is collected along with every
+    /// outcome, as in [`EnsembleResult::from_replicas`].
+    ///
+    /// # Panics
+    ///
+    /// If any system in any of the replicas panicked, or any of the outcomes is not finite,
+    /// the panic is propagated to the caller.
+    pub fn replicate_monitored<F, O>(
+        num_replicas: usize,
+        num_steps: usize,
+        build_replica: F,
+        outcome: O,
+        mut on_estimate: impl FnMut(&EnsembleResult) -> ControlFlow<()>,
+    ) -> EnsembleResult
+    where
+        F: Fn(usize) -> SimulationBuilder + Sync,
+        O: Fn(&Self) -> f64 + Sync,
+    {
+        let seed = rand::random();
+        let run_replica = |idx: usize| {
+            let mut simulation = build_replica(idx)
+                .set_default_seed(SimRng::derive_seed(seed, idx as u64))
+                .single_threaded(true)
+                .build();
+            simulation.run(num_steps);
+            let control = simulation.get_resource::<ControlVariate>().ok().copied();
+            (outcome(&simulation), control)
+        };
+
+        let mut completed = Vec::new();
+        let mut estimate = EnsembleResult::default();
+        Self::run_replicas_until(num_replicas, run_replica, |idx, (value, control)| {
+            completed.push((idx, value, control));
+            estimate.push(value, control.as_ref());
+            on_estimate(&estimate)
+        });
+
+        completed.sort_unstable_by_key(|&(idx, ..)| idx);
+        let mut ensemble = EnsembleResult::default();
+        for (_, value, control) in completed
+        {
+            ensemble.push(value, control.as_ref());
+        }
+        ensemble
+    }
+
+    /// Builds and runs a number of independent replicas of a simulation in parallel, like
+    /// [`Self::replicate`], allocated among the strata of the given [`Stratification`].
+    ///
+    /// The function `build_replica` is called once for each replica with the index of its
+    /// stratum and its own index, in the range `0..num_replicas`, and shall return the builder
+    /// of that replica, drawing its initial conditions from the stratum.
+    /// Once a replica has run for `num_steps` steps, the function `outcome` samples its value,
+    /// along with its [`crate::ControlVariate`] if it holds one, as in
+    /// [`EnsembleResult::from_replicas`].
+    ///
+    /// With [`crate::Allocation::Neyman`], the pilot replicas of every stratum are run first,
+    /// and the rest are allocated given the outcomes of the pilot.
+    /// Replicas whose builder does not set a seed are seeded independently, with a seed derived
+    /// from a common random seed, the index of their stratum and their own index.
+    ///
+    /// # Panics
+    ///
+    /// If `num_replicas` is less than the number of replicas that every stratum needs at least,
+    /// i.e. two or the pilot of Neyman allocation, times the number of strata.
+    /// If any system in any of the replicas panicked, or any of the outcomes is not finite,
+    /// the panic is propagated to the caller.
+    pub fn replicate_stratified<F, O>(
+        stratification: &Stratification,
+        num_replicas: usize,
+        num_steps: usize,
+        build_replica: F,
+        outcome: O,
+    ) -> StratifiedResult
+    where
+        F: Fn(usize, usize) -> SimulationBuilder + Sync,
+        O: Fn(&Self) -> f64 + Sync,
+    {
+        let minimum = stratification.minimum() * stratification.len();
+        assert!(
+            num_replicas >= minimum,
+            "a stratified experiment of {} strata needs at least {minimum} replicas",
+            stratification.len()
+        );
+
+        let seed = rand::random();
+        let mut strata = vec![EnsembleResult::default(); stratification.len()];
+        let run_batch = |strata: &mut [EnsembleResult], counts: &[usize]| {
+            let offset = strata.iter().map(EnsembleResult::len).sum::<usize>();
+            let batch = counts
+                .iter()
+                .enumerate()
+                .flat_map(|(stratum, count)| std::iter::repeat_n(stratum, *count))
+                .collect::<Vec<_>>();
+
+            let outcomes = Self::run_replicas(batch.len(), |idx| {
+                let (stratum, idx) = (batch[idx], offset + idx);
+                let stratum_seed = SimRng::derive_seed(seed, stratum as u64);
+                let mut simulation = build_replica(stratum, idx)
+                    .set_default_seed(SimRng::derive_seed(stratum_seed, idx as u64))
+                    .single_threaded(true)
+                    .build();
+                simulation.run(num_steps);
+                let control = simulation.get_resource::<ControlVariate>().ok().copied();
+                (outcome(&simulation), control)
+            });
+            for (stratum, (value, control)) in batch.into_iter().zip(outcomes)
+            {
+                strata[stratum].push(value, control.as_ref());
+            }
+        };
+
+        if let Allocation::Neyman { pilot } = stratification.allocation()
+        {
+            run_batch(&mut strata, &vec![pilot; stratification.len()]);
+        }
+        let remaining = stratification
+            .allocate(num_replicas, &strata)
+            .into_iter()
+            .zip(&strata)
+            .map(|(count, stratum)| count - stratum.len())
+            .collect::<Vec<_>>();
+        run_batch(&mut strata, &remaining);
+
+        StratifiedResult::new(stratification, strata)
+    }
+
+    /// Builds and runs replicas of a simulation in parallel, like [`Self::replicate`], until the
+    /// estimate of a scalar outcome is as precise as the given [`ReplicationPlan`].
+    ///
+    /// The pilot of the plan is run first. Then, as long as the target is not met, the number of
+    /// replications it requires is estimated from the outcomes so far, and the rest of them are
+    /// run, until the target is met or the limit of the plan is reached.
+    /// The function `build_replica` is called once for each replica with its index, counting up
+    /// from `0` across the batches, so that seeding every replica by its index, e.g. with
+    /// [`SimRng::derive_seed`], makes the whole experiment reproducible.
+    /// Replicas whose builder does not set a seed are seeded independently, as in
+    /// [`Self::replicate`], with the same index.
+    /// Once a replica has run for `num_steps` steps, the function `outcome` samples its value,
+    /// along with its [`crate::ControlVariate`] if it holds one, as in
+    /// [`EnsembleResult::from_replicas`].
+    ///
+    /// Returns the outcomes of all replicas in order of their index. Whether the target was met
+    /// before the limit can be checked with [`ReplicationPlan::is_met`].
+    ///
+    /// # Panics
+    ///
+    /// If any system in any of the replicas panicked, or any of the outcomes is not finite,
+    /// the panic is propagated to the caller.
+    pub fn replicate_planned<F, O>(
+        plan: &ReplicationPlan,
+        num_steps: usize,
+        build_replica: F,
+        outcome: O,
+    ) -> EnsembleResult
+    where
+        F: Fn(usize) -> SimulationBuilder + Sync,
+        O: Fn(&Self) -> f64 + Sync,
+    {
+        let seed = rand::random();
+        let mut ensemble = EnsembleResult::default();
+        loop
+        {
+            let batch = plan.next_batch(&ensemble);
+            if batch == 0
+            {
+                return ensemble;
+            }
+
+            let offset = ensemble.len();
+            let outcomes = Self::run_replicas(batch, |idx| {
+                let idx = offset + idx;
+                let mut simulation = build_replica(idx)
+                    .set_default_seed(SimRng::derive_seed(seed, idx as u64))
+                    .single_threaded(true)
+                    .build();
+                simulation.run(num_steps);
+                let control = simulation.get_resource::<ControlVariate>().ok().copied();
+                (outcome(&simulation), control)
+            });
+            for (value, control) in outcomes
+            {
+                ensemble.push(value, control.as_ref());
+            }
+        }
+    }
+
+    /// Runs `run_replica` for every index in `0..num_replicas` in parallel,
+    /// returning the results in order of their index.
+    fn run_replicas<T: Send>(
+        num_replicas: usize,
+        run_replica: impl Fn(usize) -> T + Send + Sync,
+    ) -> Vec<T>
+    {
+        let mut replicas = Vec::with_capacity(num_replicas);
+        Self::run_replicas_until(num_replicas, run_replica, |idx, replica| {
+            replicas.push((idx, replica));
+            ControlFlow::Continue(())
+        });
+
+        replicas.sort_unstable_by_key(|&(idx, _)| idx);
+        replicas.into_iter().map(|(_, replica)| replica).collect()
+    }
+
+    /// Runs `run_replica` for every index in `0..num_replicas` on rayon's global thread pool,
+    /// passing each result to `on_complete` on the calling thread as soon as it is available.
+    ///
+    /// Once `on_complete` returns [`ControlFlow::Break`], no more replicas are started,
+    /// and the results of those still running are dropped.
+    #[cfg(feature = "rayon")]
+    fn run_replicas_until<T: Send>(
+        num_replicas: usize,
+        run_replica: impl Fn(usize) -> T + Send + Sync,
+        mut on_complete: impl FnMut(usize, T) -> ControlFlow<()>,
+    )
+    {
+        let stopped = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+
+        rayon::in_place_scope(|scope| {
+            for idx in 0..num_replicas
+            {
+                let sender = sender.clone();
+                let (run_replica, stopped) = (&run_replica, &stopped);
+                scope.spawn(move |_| {
+                    if !stopped.load(Ordering::Relaxed)
+                    {
+                        // the receiving end is gone once the run has been aborted
+                        let _ = sender.send((idx, run_replica(idx)));
+                    }
+                });
+            }
+            drop(sender);
+
+            for (idx, replica) in &receiver
+            {
+                if on_complete(idx, replica).is_break()
+                {
+                    stopped.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Runs `run_replica` for every index in `0..num_replicas` one after the other,
+    /// since threads can not be spawned on the web.
+    #[cfg(all(not(feature = "rayon"), target_family = "wasm"))]
+    fn run_replicas_until<T: Send>(
+        num_replicas: usize,
+        run_replica: impl Fn(usize) -> T + Send + Sync,
+        mut on_complete: impl FnMut(usize, T) -> ControlFlow<()>,
+    )
+    {
+        for idx in 0..num_replicas
+        {
+            if on_complete(idx, run_replica(idx)).is_break()
+            {
+                break;
+            }
+        }
+    }
+
+    /// Runs `run_replica` for every index in `0..num_replicas` on a pool of worker threads,
+    /// one for each available core, passing each result to `on_complete` on the calling
+    /// thread as soon as it is available.
+    ///
+    /// Once `on_complete` returns [`ControlFlow::Break`], no more replicas are started,
+    /// and the results of those still running are dropped.
+    #[cfg(not(any(feature = "rayon", target_family = "wasm")))]
+    fn run_replicas_until<T: Send>(
+        num_replicas: usize,
+        run_replica: impl Fn(usize) -> T + Send + Sync,
+        mut on_complete: impl FnMut(usize, T) -> ControlFlow<()>,
+    )
+    {
+        let num_threads = thread::available_parallelism()
+            .map_or(1, NonZero::get)
+            .min(num_replicas);
+        let next_replica = AtomicUsize::new(0);
+        let stopped = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            let workers = (0..num_threads)
+                .map(|_| {
+                    let sender = sender.clone();
+                    let (run_replica, next_replica, stopped) =
+                        (&run_replica, &next_replica, &stopped);
+                    scope.spawn(move || {
+                        while !stopped.load(Ordering::Relaxed)
+                        {
+                            let idx = next_replica.fetch_add(1, Ordering::Relaxed);
+                            if idx >= num_replicas || sender.send((idx, run_replica(idx))).is_err()
+                            {
+                                break;
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            drop(sender);
+
+            for (idx, replica) in &receiver
+            {
+                if on_complete(idx, replica).is_break()
+                {
+                    stopped.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+
+            for worker in workers
+            {
+                worker
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err));
+            }
+        });
+    }
+
     /// Run a number of steps of the simulation.
+    ///
+    /// The run will end early if any of the hooks added with [`Self::on_step_end`]
+    /// returns [`ControlFlow::Break`].
     pub fn run(&mut self, num_steps: usize)
     {
         for _ in 0..num_steps
         {
+            trace_span!(
+                self.app.world(),
+                INFO,
+                "step",
+                step = self.app.world().resource::<StepNumber>().get()
+            );
+
             self.app.update();
+
+            if run_step_end_hooks(self.app.world_mut()).is_break()
+            {
+                break;
+            }
+        }
+    }
+
+    /// Run the steps of a continuous-time simulation, until the time of the latest event
+    /// has reached the given `time`.
+    ///
+    /// The run will end early if no more reactions can occur, see
+    /// [`crate::gillespie::SimulationTime::is_exhausted`], or if any of the hooks added with
+    /// [`Self::on_step_end`] returns [`ControlFlow::Break`].
+    ///
+    /// # Panics
+    ///
+    /// If no reactions were added to the simulation with [`crate::SimulationBuilder::add_reaction`].
+    pub fn run_until(&mut self, time: f64)
+    {
+        loop
+        {
+            let Some(clock) = self.app.world().get_resource::<SimulationTime>()
+            else
+            {
+                panic!("no reactions were added to the simulation");
+            };
+            if clock.get() >= time || clock.is_exhausted()
+            {
+                break;
+            }
+
+            trace_span!(
+                self.app.world(),
+                INFO,
+                "step",
+                step = self.app.world().resource::<StepNumber>().get()
+            );
+
+            self.app.update();
+
+            if run_step_end_hooks(self.app.world_mut()).is_break()
+            {
+                break;
+            }
+        }
+    }
+
+    /// Run a number of steps of the simulation, measuring the time spent in each phase of every step.
+    ///
+    /// The steps are run just like with [`Self::run`], including ending early if any hook
+    /// returns [`ControlFlow::Break`], so the simulation may continue normally afterwards.
+    /// This can be used to quantify the impact of changes to a model, without having to set up
+    /// a benchmarking framework. For meaningful results build with optimizations enabled.
+    pub fn benchmark(&mut self, num_steps: usize) -> BenchmarkReport
+    {
+        let mut report = BenchmarkReport::default();
+        let index_maintenance = PreUpdate.intern();
+        let user_systems = Update.intern();
+        let sampling = PostUpdate.intern();
+
+        let started = Instant::now();
+        for _ in 0..num_steps
+        {
+            let world = self.app.world_mut();
+
+            // mirror the main schedule, timing each of its sub-schedules
+            world.resource_scope(|world, order: Mut<MainScheduleOrder>| {
+                for &label in &order.labels
+                {
+                    let schedule_started = Instant::now();
+                    let _ = world.try_run_schedule(label);
+                    let elapsed = schedule_started.elapsed();
+
+                    match label
+                    {
+                        label if label == index_maintenance => report.index_maintenance += elapsed,
+                        label if label == user_systems => report.user_systems += elapsed,
+                        label if label == sampling => report.sampling += elapsed,
+                        _ => (),
+                    }
+                }
+            });
+            world.clear_trackers();
+            report.steps += 1;
+
+            if run_step_end_hooks(world).is_break()
+            {
+                break;
+            }
+        }
+
+        report.total = started.elapsed();
+        report.other = report
+            .total
+            .saturating_sub(report.user_systems + report.index_maintenance + report.sampling);
+        report
+    }
+
+    /// Add a hook that will be invoked at the end of every simulation step.
+    ///
+    /// The hook is called after all systems of the step have run, with read-only access to
+    /// the simulation's [`World`] and the number of the step that was just completed.
+    /// This can be used for custom logging or live plotting, without the need for a dedicated system.
+    ///
+    /// If the hook returns [`ControlFlow::Break`], the ongoing call to [`Self::run`] will end
+    /// early after the current step.
+    ///
+    /// This method can be called multiple times.
+    /// Hooks are invoked in the order in which they were added.
+    pub fn on_step_end(
+        &mut self,
+        hook: impl FnMut(&World, usize) -> ControlFlow<()> + Send + Sync + 'static,
+    )
+    {
+        self.app
+            .world_mut()
+            .get_resource_or_init::<StepEndHooks>()
+            .push(hook);
+    }
+
+    /// Run a number of steps of the simulation on a background thread.
+    ///
+    /// The returned [`crate::BackgroundSimulation`] handle can be used to pause, resume or stop
+    /// the run, and receives a [`crate::SimulationStatus`] snapshot once every
+    /// `status_interval` steps.
+    /// The simulation is handed back once the run is over, through
+    /// [`crate::BackgroundSimulation::join`] or [`crate::BackgroundSimulation::stop`].
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `status_interval` is `0`.
+    ///
+    /// Not available on the web, where threads can not be spawned.
+    #[cfg(not(target_family = "wasm"))]
+    #[must_use]
+    pub fn spawn_background(
+        self,
+        num_steps: usize,
+        status_interval: usize,
+    ) -> crate::BackgroundSimulation
+    {
+        crate::BackgroundSimulation::spawn(self, num_steps, status_interval)
+    }
+
+    /// Sends an event into the simulation.
+    ///
+    /// The event will be delivered on the next simulation step, to any user-defined systems
+    /// reading it with an [`EventReader<E>`] argument.
+    /// This may be used between calls to [`Self::run`] to inject shocks, commands, or
+    /// external data into an ongoing simulation.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The event type `E` has not been registered with [`crate::SimulationBuilder::register_event`].
+    pub fn send_event<E: Event>(&mut self, event: E)
+    {
+        let world = self.app.world_mut();
+        assert!(
+            world.contains_resource::<Events<E>>(),
+            "event type {} has not been registered in the simulation",
+            std::any::type_name::<E>()
+        );
+
+        world.send_event(event);
+    }
+
+    /// Adds a bevy [`Resource`] to the simulation, replacing any existing value of the same type.
+    ///
+    /// This may be used between calls to [`Self::run`] to adjust global parameters of an
+    /// ongoing simulation.
+    pub fn insert_resource<R: Resource>(&mut self, resource: R)
+    {
+        self.app.insert_resource(resource);
+    }
+
+    /// Retrieves a bevy [`Resource`] from the simulation.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::ResourceDoesNotExist`]
+    pub fn get_resource<R: Resource>(&self) -> Result<&R, SamplingError>
+    {
+        self.app
+            .world()
+            .get_resource::<R>()
+            .ok_or_else(|| SamplingError::ResourceDoesNotExist {
+                resource: type_name::<R>(),
+            })
+    }
+
+    /// Provides mutable access to a bevy [`Resource`] in the simulation.
+    ///
+    /// The given function `f` is called once with a mutable reference to the resource,
+    /// and its return value is passed on to the caller.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::ResourceDoesNotExist`]
+    pub fn resource_scope<R: Resource, T>(
+        &mut self,
+        f: impl FnOnce(&mut R) -> T,
+    ) -> Result<T, SamplingError>
+    {
+        let mut resource = self
+            .app
+            .world_mut()
+            .get_resource_mut::<R>()
+            .ok_or_else(|| SamplingError::ResourceDoesNotExist {
+                resource: type_name::<R>(),
+            })?;
+
+        Ok(f(&mut resource))
+    }
+
+    /// Returns the descriptive metadata of the simulation, as set up during its construction.
+    #[must_use]
+    pub fn meta(&self) -> &SimulationMeta
+    {
+        self.app.world().resource::<SimulationMeta>()
+    }
+
+    /// Checks whether any of the numeric guards set up with
+    /// [`SimulationBuilder::add_numeric_guard`] has caught a non-finite value.
+    ///
+    /// When that happens, the run is stopped at the end of the offending step.
+    ///
+    /// # Errors
+    ///
+    /// - [`NumericGuardError::NotANumber`]
+    /// - [`NumericGuardError::Infinite`]
+    pub fn check_numeric_guards(&self) -> Result<(), NumericGuardError>
+    {
+        self.app
+            .world()
+            .get_resource::<NumericGuardViolation>()
+            .and_then(|violation| violation.0)
+            .map_or(Ok(()), Err)
+    }
+
+    /// Summarizes the memory used by the simulation.
+    ///
+    /// The report includes the number of entities and archetypes, as well as estimates of the
+    /// memory allocated by every spatial grid and time series in the simulation.
+    /// This may be used when tuning heavyweight runs, to find which of them grows the most.
+    #[must_use]
+    pub fn memory_report(&self) -> MemoryReport
+    {
+        MemoryReporters::report(self.app.world())
+    }
+
+    /// Returns the number of simulation steps that have been run so far.
+    ///
+    /// Note that this is one less than the value of the [`StepNumber`] resource
+    /// that user-defined systems read during a step, since that refers to the step
+    /// currently being executed.
+    #[must_use]
+    pub fn current_step(&self) -> usize
+    {
+        self.app.world().resource::<StepNumber>().get() - 1
+    }
+
+    /// Spawns a single entity in the simulation.
+    ///
+    /// This may be used between calls to [`Self::run`] to inject new entities into
+    /// an ongoing simulation, for example to seed a second wave of infections.
+    /// To set up the initial state of a simulation use [`crate::SimulationBuilder::add_entity_spawner`].
+    ///
+    /// Returns a handle to the spawned entity.
+    pub fn spawn(&mut self, entity: impl Bundle) -> EntityHandle
+    {
+        EntityHandle(self.app.world_mut().spawn(entity).id())
+    }
+
+    /// Despawns all entities in the simulation that can be selected with a given filter `F`.
+    ///
+    /// The filter is a query filter, meaning it shall use selectors like
+    /// [`With`] and [`Without`].
+    ///
+    /// Returns the number of entities that were despawned.
+    pub fn despawn_where<F: QueryFilter>(&mut self) -> usize
+    {
+        let world = self.app.world_mut();
+        let Some(mut query) = world.try_query_filtered::<Entity, F>()
+        else
+        {
+            // none of the components in the filter exist, so no entities can match it
+            return 0;
+        };
+
+        let entities = query.iter(world).collect::<Vec<_>>();
+        for &entity in &entities
+        {
+            world.despawn(entity);
         }
+
+        entities.len()
     }
 
     /// Fetch the value from a specific entity's component in the simulation.
@@ -40,30 +797,180 @@ impl Simulation
     /// - [`SamplingError::ComponentDoesNotExist`]
     /// - [`SamplingError::EntityIdentifierNotFound`]
     /// - [`SamplingError::EntityIdentifierNotUnique`]
+    ///
+    /// # Panics
+    ///
+    /// If more than one entity has the identifier `id`, and the simulation is set to
+    /// [`StrictnessPolicy::Strict`].
     pub fn sample<C: Sample<Out>, Id: Identifier, Out>(&self, id: &Id)
     -> Result<Out, SamplingError>
     {
+        trace_span!(
+            self.app.world(),
+            DEBUG,
+            "sample",
+            component = type_name::<C>()
+        );
         let world = self.app.world();
-        let mut query = world
-            .try_query::<(&C, &Id)>()
-            .ok_or(SamplingError::ComponentDoesNotExist)?;
+        let mut query =
+            world
+                .try_query::<(&C, &Id)>()
+                .ok_or_else(|| SamplingError::ComponentDoesNotExist {
+                    component: type_name::<C>(),
+                })?;
 
         let mut result_iter = query.iter(world).filter(|&(_, entity_id)| entity_id == id);
 
         // sample the component of the first entity
-        let (component, _) = result_iter
-            .next()
-            .ok_or(SamplingError::EntityIdentifierNotFound)?;
+        let (component, _) =
+            result_iter
+                .next()
+                .ok_or_else(|| SamplingError::EntityIdentifierNotFound {
+                    identifier: type_name::<Id>(),
+                })?;
 
         // there should not be any more entities with the same ID
         if result_iter.next().is_some()
         {
-            return Err(SamplingError::EntityIdentifierNotUnique);
+            return Err(anomaly(
+                world,
+                SamplingError::EntityIdentifierNotUnique {
+                    identifier: type_name::<Id>(),
+                },
+            ));
         }
 
         Ok(C::sample(component))
     }
 
+    /// Modify a specific entity's component in the simulation.
+    ///
+    /// The entity is identified by `id`, and the given function `f` is called once
+    /// with a mutable reference to its component `C`.
+    ///
+    /// This may be used between calls to [`Self::run`] to tweak the state of individual
+    /// entities without the need for a dedicated system.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::ComponentDoesNotExist`]
+    /// - [`SamplingError::EntityIdentifierNotFound`]
+    /// - [`SamplingError::EntityIdentifierNotUnique`]
+    ///
+    /// # Panics
+    ///
+    /// If more than one entity has the identifier `id`, and the simulation is set to
+    /// [`StrictnessPolicy::Strict`].
+    pub fn modify<C, Id>(&mut self, id: &Id, f: impl FnOnce(&mut C)) -> Result<(), SamplingError>
+    where
+        C: Component<Mutability = Mutable>,
+        Id: Identifier,
+    {
+        let world = self.app.world_mut();
+        let mut query = world
+            .try_query_filtered::<(Entity, &Id), With<C>>()
+            .ok_or_else(|| SamplingError::ComponentDoesNotExist {
+                component: type_name::<C>(),
+            })?;
+
+        let entity = {
+            let mut result_iter = query.iter(world).filter(|&(_, entity_id)| entity_id == id);
+
+            // modify the component of the first entity
+            let (entity, _) =
+                result_iter
+                    .next()
+                    .ok_or_else(|| SamplingError::EntityIdentifierNotFound {
+                        identifier: type_name::<Id>(),
+                    })?;
+
+            // there should not be any more entities with the same ID
+            if result_iter.next().is_some()
+            {
+                return Err(anomaly(
+                    world,
+                    SamplingError::EntityIdentifierNotUnique {
+                        identifier: type_name::<Id>(),
+                    },
+                ));
+            }
+
+            entity
+        };
+
+        let mut component =
+            world
+                .get_mut::<C>(entity)
+                .ok_or_else(|| SamplingError::EntityIdentifierNotFound {
+                    identifier: type_name::<Id>(),
+                })?;
+        f(&mut component);
+
+        Ok(())
+    }
+
+    /// Modify the components of all entities in the simulation.
+    ///
+    /// The given function `f` is called once for every component `C` in the simulation.
+    ///
+    /// This may be used between calls to [`Self::run`] to tweak the state of all entities
+    /// at once, without the need for a dedicated system.
+    ///
+    /// Returns the number of components that were modified.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::ComponentDoesNotExist`]
+    pub fn modify_all<C>(&mut self, mut f: impl FnMut(&mut C)) -> Result<usize, SamplingError>
+    where
+        C: Component<Mutability = Mutable>,
+    {
+        let world = self.app.world_mut();
+        let mut query =
+            world
+                .try_query::<&mut C>()
+                .ok_or_else(|| SamplingError::ComponentDoesNotExist {
+                    component: type_name::<C>(),
+                })?;
+
+        let mut count = 0;
+        for mut component in query.iter_mut(world)
+        {
+            f(&mut component);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Fetch the value from a specific entity's component in the simulation.
+    ///
+    /// This method uses the [`Sample<O>`] implementation to extract a single value
+    /// of type `O` from the entity referred to by `handle` and return it.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::EntityHandleNotFound`]
+    pub fn sample_entity<C: Sample<Out>, Out>(
+        &self,
+        handle: EntityHandle,
+    ) -> Result<Out, SamplingError>
+    {
+        trace_span!(
+            self.app.world(),
+            DEBUG,
+            "sample",
+            component = type_name::<C>()
+        );
+        let component = self.app.world().get::<C>(handle.0).ok_or_else(|| {
+            SamplingError::EntityHandleNotFound {
+                component: type_name::<C>(),
+            }
+        })?;
+
+        Ok(C::sample(component))
+    }
+
     /// Sample a single entity's component in the simulation.
     ///
     /// This method expects that exactly one entity exists in the simulation with
@@ -78,15 +985,28 @@ impl Simulation
     /// - [`SamplingError::SingleMultipleEntities`]
     pub fn sample_single<C: Sample<Out>, Out>(&self) -> Result<Out, SamplingError>
     {
+        trace_span!(
+            self.app.world(),
+            DEBUG,
+            "sample",
+            component = type_name::<C>()
+        );
         let world = self.app.world();
-        let mut query = world
-            .try_query::<&C>()
-            .ok_or(SamplingError::ComponentDoesNotExist)?;
+        let mut query =
+            world
+                .try_query::<&C>()
+                .ok_or_else(|| SamplingError::ComponentDoesNotExist {
+                    component: type_name::<C>(),
+                })?;
 
         let component = query.single(world).map_err(|e| match e
         {
-            QuerySingleError::NoEntities(_) => SamplingError::SingleNoEntities,
-            QuerySingleError::MultipleEntities(_) => SamplingError::SingleMultipleEntities,
+            QuerySingleError::NoEntities(_) => SamplingError::SingleNoEntities {
+                component: type_name::<C>(),
+            },
+            QuerySingleError::MultipleEntities(_) => SamplingError::SingleMultipleEntities {
+                component: type_name::<C>(),
+            },
         })?;
 
         Ok(C::sample(component))
@@ -104,20 +1024,44 @@ impl Simulation
     ///
     /// - [`SamplingError::ComponentDoesNotExist`]
     /// - [`SamplingError::AggregateNoEntities`]
-    pub fn sample_aggregate<C: SampleAggregate<Out>, Out>(&self) -> Result<Out, SamplingError>
+    ///
+    /// # Panics
+    ///
+    /// If no entities are found, and the simulation is set to [`StrictnessPolicy::Strict`].
+    pub fn sample_aggregate<C, Out>(&self) -> Result<Out, SamplingError>
+    where
+        C: SampleAggregate<Out>,
+        Out: Send + 'static,
     {
+        trace_span!(
+            self.app.world(),
+            DEBUG,
+            "sample",
+            component = type_name::<C>()
+        );
         let world = self.app.world();
-        let mut query = world
-            .try_query::<&C>()
-            .ok_or(SamplingError::ComponentDoesNotExist)?;
+        let mut query =
+            world
+                .try_query::<&C>()
+                .ok_or_else(|| SamplingError::ComponentDoesNotExist {
+                    component: type_name::<C>(),
+                })?;
 
         let results = query.iter(world).collect::<Vec<_>>();
         if results.is_empty()
         {
-            return Err(SamplingError::AggregateNoEntities);
+            return Err(anomaly(
+                world,
+                SamplingError::AggregateNoEntities {
+                    component: type_name::<C>(),
+                },
+            ));
         }
 
-        Ok(C::sample_aggregate(&results))
+        Ok(ParallelSampling::sample_aggregate(
+            world.get_resource::<ParallelSampling>(),
+            &results,
+        ))
     }
 
     /// Fetch the value from a multiple entities' components in the simulation.
@@ -133,22 +1077,159 @@ impl Simulation
     ///
     /// - [`SamplingError::ComponentDoesNotExist`]
     /// - [`SamplingError::AggregateNoEntities`]
-    pub fn sample_aggregate_filtered<C: SampleAggregate<Out>, F: QueryFilter, Out>(
-        &self,
-    ) -> Result<Out, SamplingError>
+    ///
+    /// # Panics
+    ///
+    /// If no entities are found, and the simulation is set to [`StrictnessPolicy::Strict`].
+    pub fn sample_aggregate_filtered<C, F, Out>(&self) -> Result<Out, SamplingError>
+    where
+        C: SampleAggregate<Out>,
+        F: QueryFilter,
+        Out: Send + 'static,
     {
+        trace_span!(
+            self.app.world(),
+            DEBUG,
+            "sample",
+            component = type_name::<C>()
+        );
         let world = self.app.world();
-        let mut query = world
-            .try_query_filtered::<&C, F>()
-            .ok_or(SamplingError::ComponentDoesNotExist)?;
+        let mut query = world.try_query_filtered::<&C, F>().ok_or_else(|| {
+            SamplingError::ComponentDoesNotExist {
+                component: type_name::<C>(),
+            }
+        })?;
 
         let results = query.iter(world).collect::<Vec<_>>();
         if results.is_empty()
         {
-            return Err(SamplingError::AggregateNoEntities);
+            return Err(anomaly(
+                world,
+                SamplingError::AggregateNoEntities {
+                    component: type_name::<C>(),
+                },
+            ));
         }
 
-        Ok(C::sample_aggregate(&results))
+        Ok(ParallelSampling::sample_aggregate(
+            world.get_resource::<ParallelSampling>(),
+            &results,
+        ))
+    }
+
+    /// Iterates over all components `C` in the simulation.
+    ///
+    /// This can be used to inspect the state of the simulation ad hoc, without having to implement
+    /// [`Sample`] or [`SampleAggregate`] for the component.
+    ///
+    /// The components are iterated in arbitrary order.
+    /// If no entity with the component `C` was ever spawned, the iterator will be empty.
+    ///
+    /// The iterator is not lazy: references to all matching components are first collected
+    /// into a vector, since the query that finds them does not outlive this call. On hot paths
+    /// over many entities, prefer sampling with [`Self::sample_aggregate`] or a custom system.
+    pub fn iter<C: Component>(&self) -> impl Iterator<Item = &C>
+    {
+        self.iter_with::<C, ()>()
+    }
+
+    /// Iterates over the components `C` on all entities selected with the filter `F`.
+    ///
+    /// The filter is a query filter, meaning it shall use selectors like
+    /// [`With`] and [`Without`].
+    ///
+    /// See [`Self::iter`] for details.
+    pub fn iter_with<C: Component, F: QueryFilter>(&self) -> impl Iterator<Item = &C>
+    {
+        let world = self.app.world();
+        let components = world
+            .try_query_filtered::<&C, F>()
+            .map(|mut query| query.iter(world).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        components.into_iter()
+    }
+
+    /// Exports the values of type `O` sampled from all components `C` into a contiguous vector.
+    ///
+    /// This uses the [`Sample<O>`] implementation of the component, and fills the vector in a
+    /// single pass over the entities. The result can be handed off cheaply to external analysis,
+    /// for example by wrapping it in an `ndarray` array or a `polars` series.
+    ///
+    /// The values are in arbitrary order.
+    /// To know which entity each value came from, use [`Self::export_columns_with_ids`].
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::ComponentDoesNotExist`]
+    pub fn export_columns<C: Sample<O>, O>(&self) -> Result<Vec<O>, SamplingError>
+    {
+        let world = self.app.world();
+        let mut query =
+            world
+                .try_query::<&C>()
+                .ok_or_else(|| SamplingError::ComponentDoesNotExist {
+                    component: type_name::<C>(),
+                })?;
+
+        Ok(query.iter(world).map(C::sample).collect())
+    }
+
+    /// Exports the values of type `O` sampled from all components `C` into a contiguous vector,
+    /// along with a parallel vector of the [`Identifier`] of each entity.
+    ///
+    /// The value at every index of the second vector was sampled from the entity with the
+    /// identifier at the same index of the first one. Entities without an identifier `Id` are skipped.
+    ///
+    /// See [`Self::export_columns`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::ComponentDoesNotExist`]
+    pub fn export_columns_with_ids<C, Id, O>(&self) -> Result<(Vec<Id>, Vec<O>), SamplingError>
+    where
+        C: Sample<O>,
+        Id: Identifier + Clone,
+    {
+        let world = self.app.world();
+        let mut query =
+            world
+                .try_query::<(&Id, &C)>()
+                .ok_or_else(|| SamplingError::ComponentDoesNotExist {
+                    component: type_name::<C>(),
+                })?;
+
+        Ok(query
+            .iter(world)
+            .map(|(id, component)| (id.clone(), C::sample(component)))
+            .unzip())
+    }
+
+    /// Estimates the distribution of the values of type `f64` sampled from all components `C`,
+    /// using the [`Sample<f64>`] implementation of the component, with a [`KernelDensity`].
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::ComponentDoesNotExist`]
+    /// - [`SamplingError::AggregateNoEntities`]
+    ///
+    /// # Panics
+    ///
+    /// If no entities are found, and the simulation is set to [`StrictnessPolicy::Strict`].
+    pub fn sample_density<C: Sample<f64>>(&self) -> Result<KernelDensity, SamplingError>
+    {
+        let samples = self.export_columns::<C, f64>()?;
+        if samples.is_empty()
+        {
+            return Err(anomaly(
+                self.app.world(),
+                SamplingError::AggregateNoEntities {
+                    component: type_name::<C>(),
+                },
+            ));
+        }
+
+        Ok(KernelDensity::new(samples))
     }
 
     /// Counts the number of entities in the simulation that can be selected
@@ -163,15 +1244,107 @@ impl Simulation
     pub fn count<F: QueryFilter>(&self) -> Result<usize, SamplingError>
     {
         let world = self.app.world();
-        let mut query = world
-            .try_query_filtered::<(), F>()
-            .ok_or(SamplingError::ComponentDoesNotExist)?;
+        let mut query = world.try_query_filtered::<(), F>().ok_or_else(|| {
+            SamplingError::ComponentDoesNotExist {
+                component: type_name::<F>(),
+            }
+        })?;
 
         let count = query.iter(world).count();
 
         Ok(count)
     }
 
+    /// Retrieve the log of all events of type `E` emitted during the simulation.
+    ///
+    /// This is possible only after having called [`crate::SimulationBuilder::record_events`]
+    /// during the construction of the simulation.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::EventsNotRecorded`]
+    pub fn event_log<E: Event>(&self) -> Result<&EventLog<E>, SamplingError>
+    {
+        self.app
+            .world()
+            .get_resource::<EventLog<E>>()
+            .ok_or_else(|| SamplingError::EventsNotRecorded {
+                event: type_name::<E>(),
+            })
+    }
+
+    /// Retrieve the incremental aggregate of values of type `O` sampled from components `C`.
+    ///
+    /// This is possible only after having called [`crate::SimulationBuilder::track_incremental_aggregate`]
+    /// during the construction of the simulation.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::AggregateNotTracked`]
+    pub fn incremental_aggregate<C, O>(&self) -> Result<&IncrementalAggregate<C, O>, SamplingError>
+    where
+        C: Sample<O>,
+        O: Send + Sync + 'static,
+    {
+        self.app
+            .world()
+            .get_resource::<IncrementalAggregate<C, O>>()
+            .ok_or_else(|| SamplingError::AggregateNotTracked {
+                component: type_name::<C>(),
+                output: type_name::<O>(),
+            })
+    }
+
+    /// Retrieve the population ledger of entities with the component `C`.
+    ///
+    /// This is possible only after having called [`crate::SimulationBuilder::track_population`]
+    /// during the construction of the simulation.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::PopulationNotTracked`]
+    pub fn ledger<C: Component>(&self) -> Result<&PopulationLedger<C>, SamplingError>
+    {
+        self.app
+            .world()
+            .get_resource::<PopulationLedger<C>>()
+            .ok_or_else(|| SamplingError::PopulationNotTracked {
+                component: type_name::<C>(),
+            })
+    }
+
+    /// Exports the full state of the simulation as JSON.
+    ///
+    /// All entities are included, along with those of their components whose types have been
+    /// registered for reflection with [`crate::SimulationBuilder::register_type`].
+    /// Entities are ordered by their id, and components by their type path, so that the
+    /// exported states of two runs can be diffed directly.
+    ///
+    /// This is primarily meant for debugging, and for building external visualizations.
+    ///
+    /// # Errors
+    ///
+    /// - [`ExportError::Serialization`]
+    #[cfg(feature = "serde")]
+    pub fn export_state(&self) -> Result<String, ExportError>
+    {
+        crate::export::export_state(self.app.world())
+    }
+
+    /// Exports the full state of the simulation as a compact binary snapshot.
+    ///
+    /// The state holds the same data as that of [`Self::export_state`], and can be read back
+    /// with [`crate::snapshot::decode`]. See the [`crate::snapshot`] module for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`ExportError::Serialization`]
+    #[cfg(feature = "msgpack")]
+    pub fn export_state_snapshot(&self) -> Result<Vec<u8>, ExportError>
+    {
+        crate::export::export_state_snapshot(self.app.world())
+    }
+
     /// Retrieve the values of a time series that was recorded during the simulation on
     /// a specific entity identified by `id`.
     ///
@@ -180,9 +1353,14 @@ impl Simulation
     ///
     /// # Errors
     ///
-    /// - [`SamplingError::ComponentDoesNotExist`]
+    /// - [`SamplingError::TimeSeriesNotRecorded`]
     /// - [`SamplingError::EntityIdentifierNotFound`]
     /// - [`SamplingError::EntityIdentifierNotUnique`]
+    ///
+    /// # Panics
+    ///
+    /// If more than one entity has the identifier `id`, and the simulation is set to
+    /// [`StrictnessPolicy::Strict`].
     pub fn get_time_series<C, Id, Out>(
         &'_ self,
         id: &Id,
@@ -195,19 +1373,30 @@ impl Simulation
         let world = self.app.world();
         let mut query = world
             .try_query::<(&TimeSeriesData<C, Id, Out>, &Id)>()
-            .ok_or(SamplingError::ComponentDoesNotExist)?;
+            .ok_or_else(|| SamplingError::TimeSeriesNotRecorded {
+                component: type_name::<C>(),
+                output: type_name::<Out>(),
+            })?;
 
         let mut result_iter = query.iter(world).filter(|&(_, entity_id)| entity_id == id);
 
         // fetch the time series of the first entity
-        let (time_series, _) = result_iter
-            .next()
-            .ok_or(SamplingError::EntityIdentifierNotFound)?;
+        let (time_series, _) =
+            result_iter
+                .next()
+                .ok_or_else(|| SamplingError::EntityIdentifierNotFound {
+                    identifier: type_name::<Id>(),
+                })?;
 
         // there should not be any more entities with the same ID
         if result_iter.next().is_some()
         {
-            return Err(SamplingError::EntityIdentifierNotUnique);
+            return Err(anomaly(
+                world,
+                SamplingError::EntityIdentifierNotUnique {
+                    identifier: type_name::<Id>(),
+                },
+            ));
         }
 
         let time_series = time_series.collect();
@@ -250,10 +1439,50 @@ impl Simulation
         let world = self.app.world();
         let time_series = world
             .get_resource::<TimeSeriesData<C, Filter, Out>>()
-            .ok_or(SamplingError::TimeSeriesNotRecorded)?;
+            .ok_or_else(|| SamplingError::TimeSeriesNotRecorded {
+                component: type_name::<C>(),
+                output: type_name::<Out>(),
+            })?;
 
         let time_series = time_series.collect();
 
         Ok(time_series)
     }
+
+    /// Retrieve a trajectory that was recorded against the continuous time of the simulation.
+    ///
+    /// This is possible only after having called [`crate::SimulationBuilder::record_trajectory`]
+    /// during the construction of the simulation.
+    ///
+    /// # Errors
+    ///
+    /// - [`SamplingError::TimeSeriesNotRecorded`]
+    pub fn get_trajectory<C, Out>(&self) -> Result<&Trajectory<Out>, SamplingError>
+    where
+        C: SampleAggregate<Out>,
+        Out: Send + Sync + 'static,
+    {
+        self.app
+            .world()
+            .get_resource::<TrajectoryData<C, Out>>()
+            .map(|data| &data.trajectory)
+            .ok_or_else(|| SamplingError::TimeSeriesNotRecorded {
+                component: type_name::<C>(),
+                output: type_name::<Out>(),
+            })
+    }
+}
+
+/// Handles a recoverable anomaly according to the [`StrictnessPolicy`] of the simulation,
+/// by either panicking or returning the `error` to be surfaced to the caller.
+fn anomaly(world: &World, error: SamplingError) -> SamplingError
+{
+    if world
+        .get_resource::<StrictnessPolicy>()
+        .is_some_and(|policy| policy.is_strict())
+    {
+        panic!("{error}");
+    }
+
+    error
 }
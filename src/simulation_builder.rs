@@ -1,31 +1,108 @@
+use std::{
+    any::{Any, type_name},
+    hash::Hash,
+    ops::ControlFlow,
+    panic::{self, AssertUnwindSafe},
+    time::SystemTime,
+};
+
 use bevy::{
     app::ScheduleRunnerPlugin,
-    ecs::{query::QueryFilter, system::ScheduleSystem},
+    ecs::{
+        bundle::NoBundleEffect,
+        query::QueryFilter,
+        schedule::{ExecutorKind, LogLevel, ScheduleBuildSettings},
+        system::ScheduleSystem,
+        world::CommandQueue,
+    },
     prelude::*,
+    reflect::GetTypeRegistration,
 };
 
+#[cfg(feature = "gpu")]
+use crate::gpu::{GpuAutomatonPlugin, GpuCell};
+#[cfg(feature = "trace")]
+use crate::trace::TraceLevel;
 use crate::{
-    BuilderError, Identifier, Sample, SampleAggregate,
+    AsF64, BuilderError, CheckIssue, ControlVariate, Identifier, Module, ParallelSampling, Sample,
+    SampleAggregate, SimRng, SimulationMeta, StrictnessPolicy,
+    calendar::{ClockPlugin, SimClock},
+    cellular::{CellState, CellularAutomatonPlugin},
+    evolution::{EvolutionPlugin, Genome},
+    flocking::FlockingPlugin,
+    flow::{Commodity, CommodityPlugin},
+    fsm::{FsmPlugin, FsmState, Transitions},
+    gillespie::{GillespiePlugin, Reactions, TrajectoryData, TrajectoryPlugin},
+    market::MarketPlugin,
     plugins::{
-        AggregateTimeSeriesPlugin, GridBounds, GridCoordinates, SampleInterval, SpatialGridPlugin,
-        StepNumberPlugin, TimeSeriesData, TimeSeriesPlugin,
+        AggregateTimeSeriesPlugin, ContactLayers, ContactLayersPlugin, DeferredDespawn,
+        DeferredDespawnPlugin, DeferredSpawn, DeferredSpawnPlugin, EventLog, EventRecorderPlugin,
+        EventReplayPlugin, FutureEvents, FutureEventsPlugin, GridBounds, GridCoordinates,
+        IncrementalAggregate, IncrementalAggregatePlugin, NetworkPlugin, NumericGuardPlugin,
+        PopulationLedger, PopulationLedgerPlugin, RefillSpawners, RefillSpawnersPlugin,
+        SampleInterval, ScheduledSpawners, ScheduledSpawnersPlugin, SpatialGrid, SpatialGridPlugin,
+        SpawnSchedule, StepEndHooks, StepNumber, StepNumberPlugin, TimeSeriesData,
+        TimeSeriesPlugin,
     },
+    policy::Policy,
     prelude::{GridBounds2D, GridBounds3D},
+    queueing::QueueingPlugin,
+    random_walk::{RandomWalkPlugin, WalkSpace},
+    reaction_diffusion::ReactionDiffusionPlugin,
+    sde::StochasticProcessPlugin,
     simulation::Simulation,
-    spawner::Spawner,
+    spawner::{CapacityReservers, Spawner},
+    trace::trace_span,
+    traffic::TrafficPlugin,
 };
 
-type SpawnFn = Box<dyn Fn(&mut Spawner)>;
+type SpawnFn = Box<dyn Fn(&mut World, &mut SimRng)>;
+type DependentSpawnFn = Box<dyn Fn(&mut Spawner, &World)>;
 
 /// Builder type used to construct a [`Simulation`] object.
 ///
 /// The builder is used to logically separate the construction of a simulation with its execution.
 /// Once built, a [`Simulation`] object may be reused in order to intermitently run simulation steps,
 /// restart the simulation from the beginning, collect results and so on.
+///
+/// The storage of components can not be chosen through the builder, since bevy fixes it at
+/// compile time for every component type, through its `Component` derive.
+/// Marker components that are frequently added to and removed from entities, such as an
+/// `Infected` tag, are better stored in sparse sets, with the
+/// `#[component(storage = "SparseSet")]` attribute on the component itself.
 pub struct SimulationBuilder
 {
     app: App,
     spawners: Vec<SpawnFn>,
+    dependent_spawners: Vec<DependentSpawnFn>,
+    expected_entities: usize,
+    expected_steps: usize,
+    time_series_reservers: Vec<fn(&mut World, usize)>,
+    check_ambiguities: bool,
+    single_threaded: bool,
+    recorded_components: Vec<RecordedComponent>,
+}
+
+/// A component whose values are recorded in a time series.
+struct RecordedComponent
+{
+    name: &'static str,
+    count: fn(&World) -> usize,
+}
+
+impl RecordedComponent
+{
+    fn new<C: Component>() -> Self
+    {
+        Self {
+            name: type_name::<C>(),
+            count: |world| {
+                world
+                    .try_query_filtered::<(), With<C>>()
+                    .map_or(0, |mut query| query.iter(world).count())
+            },
+        }
+    }
 }
 
 impl Default for SimulationBuilder
@@ -48,13 +125,71 @@ impl SimulationBuilder
             .add_plugins(StepNumberPlugin);
 
         app.update();
+        app.init_resource::<SimulationMeta>();
+        app.init_resource::<StrictnessPolicy>();
 
         Self {
             app,
             spawners: Vec::new(),
+            dependent_spawners: Vec::new(),
+            expected_entities: 0,
+            expected_steps: 0,
+            time_series_reservers: Vec::new(),
+            check_ambiguities: false,
+            single_threaded: false,
+            recorded_components: Vec::new(),
         }
     }
 
+    /// Sets the name of the simulation in its [`SimulationMeta`].
+    #[must_use]
+    pub fn set_name(mut self, name: impl Into<String>) -> Self
+    {
+        self.meta_mut().name = name.into();
+        self
+    }
+
+    /// Sets the description of the simulation in its [`SimulationMeta`].
+    #[must_use]
+    pub fn set_description(mut self, description: impl Into<String>) -> Self
+    {
+        self.meta_mut().description = description.into();
+        self
+    }
+
+    /// Sets the seed of the simulation in its [`SimulationMeta`].
+    ///
+    /// This is the seed of the simulation's [`SimRng`].
+    #[must_use]
+    pub fn set_seed(mut self, seed: u64) -> Self
+    {
+        self.meta_mut().seed = Some(seed);
+        self
+    }
+
+    /// Sets the seed of the simulation, unless one was already set with [`Self::set_seed`].
+    pub(crate) fn set_default_seed(mut self, seed: u64) -> Self
+    {
+        self.meta_mut().seed.get_or_insert(seed);
+        self
+    }
+
+    /// Sets the parameter hash of the simulation in its [`SimulationMeta`],
+    /// by hashing the given `parameters`.
+    ///
+    /// See [`SimulationMeta::hash_parameters`] for details.
+    #[must_use]
+    pub fn set_parameters<P: Hash + ?Sized>(mut self, parameters: &P) -> Self
+    {
+        self.meta_mut().parameter_hash = Some(SimulationMeta::hash_parameters(parameters));
+        self
+    }
+
+    fn meta_mut(&mut self) -> Mut<'_, SimulationMeta>
+    {
+        self.app.world_mut().resource_mut::<SimulationMeta>()
+    }
+
     /// Add systems to the simulation.
     ///
     /// These are [`bevy systems`](https://bevy-cheatbook.github.io/programming/systems.html).
@@ -82,6 +217,46 @@ impl SimulationBuilder
         self
     }
 
+    /// Register a type for reflection in the simulation.
+    ///
+    /// With the `serde` feature enabled, components registered here are included in
+    /// `Simulation::export_state`. For this to work, the component needs to derive [`Reflect`] and be annotated
+    /// with `#[reflect(Component)]`.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component, Reflect)]
+    /// #[reflect(Component)]
+    /// struct Health(f32);
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .register_type::<Health>()
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn register_type<T: GetTypeRegistration>(mut self) -> Self
+    {
+        self.app.register_type::<T>();
+        self
+    }
+
+    /// Add a hook that will be invoked at the end of every simulation step.
+    ///
+    /// See [`Simulation::on_step_end`] for details.
+    #[must_use]
+    pub fn on_step_end(
+        mut self,
+        hook: impl FnMut(&World, usize) -> ControlFlow<()> + Send + Sync + 'static,
+    ) -> Self
+    {
+        self.app
+            .world_mut()
+            .get_resource_or_init::<StepEndHooks>()
+            .push(hook);
+        self
+    }
+
     /// Add a spatial grid for a specific component type to the simulation.
     ///
     /// This creates a spatial index for entities that have both [`super::GridPosition<T>`] and the specified component `C`.
@@ -89,8 +264,8 @@ impl SimulationBuilder
     ///
     /// The spatial grid can be access by the user using the [`super::SpatialGrid<T, C>`] bevy resource.
     ///
-    /// Optionally, if `Some(bounds)` are given, a panic will be raised if an entity has a [`super::GridPosition`]
-    /// outside the bounds.
+    /// Optionally, if `Some(bounds)` are given, entities with a [`super::GridPosition`] outside the bounds
+    /// are handled according to the [`StrictnessPolicy`] of the simulation.
     ///
     /// Example:
     /// ```
@@ -112,195 +287,1698 @@ impl SimulationBuilder
     /// ```
     #[must_use]
     pub fn add_spatial_grid<T: GridCoordinates, C: Component>(
-        mut self,
+        self,
         bounds: Option<GridBounds<T>>,
     ) -> Self
     {
-        self.app.add_plugins(SpatialGridPlugin::<T, C>::new(bounds));
-        self
+        self.add_spatial_grid_plugin(SpatialGridPlugin::<T, C>::new(bounds))
     }
 
-    /// Adds a 2D spatial grid for a specific component type to the simulation.
+    /// Adds a spatial grid for a specific component type to the simulation, backed by a flat array
+    /// with a cell for every position within `bounds`.
+    ///
+    /// This avoids hashing positions on every lookup, which makes it considerably faster for grids
+    /// where most positions are occupied, such as the lattices of cellular automata.
+    /// The memory used is however proportional to the area (or volume) of the grid, regardless of
+    /// the number of entities in it.
     ///
     /// See [`Self::add_spatial_grid`] for details.
+    ///
+    /// # Panics
+    ///
+    /// If [`GridBounds::min`] is larger than [`GridBounds::max`] along any axis, when the
+    /// simulation is built.
     #[must_use]
-    pub fn add_spatial_grid_2d<C: Component>(self, bounds: Option<GridBounds2D>) -> Self
+    pub fn add_dense_spatial_grid<T: GridCoordinates, C: Component>(
+        self,
+        bounds: GridBounds<T>,
+    ) -> Self
     {
-        self.add_spatial_grid::<IVec2, C>(bounds)
+        self.add_spatial_grid_plugin(SpatialGridPlugin::<T, C>::new_dense(bounds))
     }
 
-    /// Adds a 3D spatial grid for a specific component type to the simulation.
-    ///
-    /// See [`Self::add_spatial_grid`] for details.
-    #[must_use]
-    pub fn add_spatial_grid_3d<C: Component>(self, bounds: Option<GridBounds3D>) -> Self
+    fn add_spatial_grid_plugin<T: GridCoordinates, C: Component>(
+        mut self,
+        plugin: SpatialGridPlugin<T, C>,
+    ) -> Self
     {
-        self.add_spatial_grid::<IVec3, C>(bounds)
+        self.app.add_plugins(plugin);
+        self.app
+            .world_mut()
+            .get_resource_or_init::<CapacityReservers>()
+            .0
+            .push(|world, additional| {
+                world
+                    .resource_mut::<SpatialGrid<T, C>>()
+                    .reserve(additional);
+            });
+        self
     }
 
-    /// Add an entity spawner function to the simulation.
+    /// Adds a network of relations between entities with the component `C` to the simulation,
+    /// for interactions that do not follow the geometry of a grid, such as the contacts of people
+    /// in an epidemic or the links between firms in a trade network.
     ///
-    /// In the beginning of every simulation, each of the spawner functions added here
-    /// will get called once. The [`Spawner`] that is passed as an argument shall be used
-    /// to spawn entities into the simulation.
+    /// Edges can be added while spawning with [`crate::Spawner::add_edge`], and the network can
+    /// be accessed by the user using the [`crate::Network<C>`] bevy resource.
+    /// Multiple networks can coexist, one for each component type `C`.
     ///
-    /// This method may be called multiple times.
-    /// The order in which the given functions are called is the same as the order in
-    /// which they are added to the builder.
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Person
+    /// {
+    ///     infected: bool,
+    /// }
     ///
-    /// Spawners shall be used to set up the initial state of a simulation.
-    /// Additional entities can be spawned in an ongoing simulation using [Commands](https://bevy-cheatbook.github.io/programming/commands.html).
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_network::<Person>()
+    ///     .add_entity_spawner(|spawner| {
+    ///         let people = (0..10)
+    ///             .map(|i| spawner.spawn(Person { infected: i == 0 }))
+    ///             .collect::<Vec<_>>();
+    ///         for pair in people.windows(2)
+    ///         {
+    ///             spawner.add_edge::<Person>(pair[0], pair[1]);
+    ///         }
+    ///     })
+    ///     .add_systems(|network: Res<Network<Person>>, mut query: Query<(Entity, &mut Person)>| {
+    ///         let infected = query
+    ///             .iter()
+    ///             .filter(|(_, person)| person.infected)
+    ///             .map(|(entity, _)| entity)
+    ///             .collect::<Vec<_>>();
+    ///         for entity in infected
+    ///         {
+    ///             for contact in network.neighbors_of(entity)
+    ///             {
+    ///                 if let Ok((_, mut person)) = query.get_mut(contact)
+    ///                 {
+    ///                     person.infected = true;
+    ///                 }
+    ///             }
+    ///         }
+    ///     })
+    ///     .build();
+    /// ```
     #[must_use]
-    pub fn add_entity_spawner(mut self, entity_spawner: impl Fn(&mut Spawner) + 'static) -> Self
+    pub fn add_network<C: Component>(mut self) -> Self
     {
-        self.spawners.push(Box::new(entity_spawner));
+        self.app.add_plugins(NetworkPlugin::<C>::default());
         self
     }
 
-    /// Sets up the recording of a time series.
+    /// Adds several layers of contacts between entities with the component `C`, such as a spatial
+    /// layer of nearby entities along with household and workplace networks, each with its own
+    /// modifier of the rate of interactions.
     ///
-    /// The values in the time series will be values of type `O`
-    /// sampled from components `C`, on any entities identified by `I`
-    /// in the simulation according to the implementation of [`Sample<O>`] for `C`.
+    /// If the layers include a spatial one, a [`crate::prelude::SpatialGrid2D<C>`] with its bounds
+    /// is added as well, unless one already exists.
+    /// Edges of network layers can be added while spawning with
+    /// [`crate::Spawner::add_layer_edge`], and the layers can be accessed by the user using the
+    /// [`ContactLayers<C>`] bevy resource.
+    /// See [`ContactLayers`] for an example.
     ///
-    /// The sampling will occur once every `sample_interval` steps.
-    /// Specifically at the end of the step, after all user-defined systems have run.
+    /// Calling this method again for the same component replaces its layers.
+    #[must_use]
+    pub fn add_contact_layers<C: Component>(mut self, layers: ContactLayers<C>) -> Self
+    {
+        if layers.has_spatial()
+            && !self
+                .app
+                .world()
+                .contains_resource::<SpatialGrid<IVec2, C>>()
+        {
+            self = self.add_spatial_grid_2d::<C>(layers.spatial_bounds());
+        }
+        if !self.app.is_plugin_added::<ContactLayersPlugin<C>>()
+        {
+            self.app.add_plugins(ContactLayersPlugin::<C>::default());
+        }
+        self.app.insert_resource(layers);
+        self
+    }
+
+    /// Sets up an order-book market, matching the [`crate::market::Order`]s submitted on every
+    /// step at its end, after all user-defined systems have run.
     ///
-    /// Note that it is currently not allowed to record more than one time series
-    /// with the same pair of component (`C`),value (`O`), and identifier (`I`).
+    /// Every match emits a [`crate::market::Trade`] event, and the resting orders and price series
+    /// are kept in the [`crate::market::OrderBook`] resource.
+    /// See the [`crate::market`] module for an example.
     ///
-    /// For more details see the documentation of:
-    /// * [`Sample`]
-    /// * [`Identifier`]
+    /// Calling this method more than once has no additional effect.
+    #[must_use]
+    pub fn add_market(mut self) -> Self
+    {
+        if !self.app.is_plugin_added::<MarketPlugin>()
+        {
+            self.app.add_plugins(MarketPlugin);
+        }
+        self
+    }
+
+    /// Sets up the simulation of queueing models, advancing all [`crate::queueing::Queue`],
+    /// [`crate::queueing::Arrivals`] and [`crate::queueing::Server`] components on every step,
+    /// before any user-defined systems run.
     ///
-    /// # Errors
+    /// The random arrivals and service times are drawn from the simulation's [`SimRng`].
+    /// See the [`crate::queueing`] module for an example.
+    #[must_use]
+    pub fn add_queueing(mut self) -> Self
+    {
+        if !self.app.is_plugin_added::<QueueingPlugin>()
+        {
+            self.app.add_plugins(QueueingPlugin);
+        }
+        self
+    }
+
+    /// Sets up the [`crate::random_walk::RandomWalker<P>`] components in the space `P` to take
+    /// a random step on every step of the simulation.
     ///
-    /// - [`SimulationBuildError::TimeSeriesRecordingConflict`]
+    /// The steps are drawn from the simulation's [`SimRng`].
+    /// See the [`crate::random_walk`] module for an example.
     ///
-    /// # Panics
+    /// Calling this method more than once for the same space has no additional effect.
+    #[must_use]
+    pub fn add_random_walks<P: WalkSpace>(mut self) -> Self
+    {
+        if !self.app.is_plugin_added::<RandomWalkPlugin<P>>()
+        {
+            self.app.add_plugins(RandomWalkPlugin::<P>::default());
+        }
+        self
+    }
+
+    /// Sets up the flocking of the [`crate::flocking::Boid`]s with the component `C`, which steer
+    /// and move on every step of the simulation.
     ///
-    /// This method will panic if:
+    /// The order of the flock is recorded in the [`crate::flocking::FlockOutputs<C>`] resource.
+    /// See the [`crate::flocking`] module for an example.
     ///
-    /// - The given `sample_interval` is `0`.
-    pub fn record_time_series<C, I, O>(
-        mut self,
-        sample_interval: usize,
-    ) -> Result<Self, BuilderError>
-    where
-        C: Sample<O>,
-        I: Identifier,
-        O: Send + Sync + 'static,
+    /// Calling this method more than once for the same component has no additional effect.
+    #[must_use]
+    pub fn add_flocking<C: Component>(mut self, plugin: FlockingPlugin<C>) -> Self
     {
-        assert!(sample_interval > 0);
-
-        let world = self.app.world();
-        if world.get_resource::<SampleInterval<C, I, O>>().is_some()
+        if !self.app.is_plugin_added::<FlockingPlugin<C>>()
         {
-            // More than one time series recording for the same C, F, O is not possible.
-            return Err(BuilderError::TimeSeriesRecordingConflict);
+            self.app.add_plugins(plugin);
         }
-
-        self.app
-            .add_plugins(TimeSeriesPlugin::<C, I, O>::new(sample_interval));
-        Ok(self)
+        self
     }
 
-    /// Sets up the recording of an aggregate time series.
-    ///
-    /// The values in the time series will be values of type `O`
-    /// sampled from all components `C` in the simulation according to the
-    /// implementation of [`SampleAggregate<O>`] for `C`.
+    /// Sets up the [`crate::sde::StochasticProcess`] components to advance by one time increment
+    /// on every step of the simulation.
     ///
-    /// The sampling will occur once every `sample_interval` steps.
-    /// Specifically at the end of the step, after all user-defined systems have run.
+    /// The noise is drawn from the simulation's [`SimRng`].
+    /// See the [`crate::sde`] module for an example.
+    #[must_use]
+    pub fn add_stochastic_processes(mut self) -> Self
+    {
+        if !self.app.is_plugin_added::<StochasticProcessPlugin>()
+        {
+            self.app.add_plugins(StochasticProcessPlugin);
+        }
+        self
+    }
+
+    /// Sets up a cellular automaton, updating the states `C` of the cells on a bounded grid
+    /// synchronously on every step, before any user-defined systems run.
     ///
-    /// Note that it is currently not possible to record more than one time series
-    /// with the same pair of component (`C`),value (`O`), and filter (`F`).
+    /// See the [`crate::cellular`] module for an example.
     ///
-    /// # Errors
+    /// Calling this method more than once for the same cell state has no additional effect.
+    #[must_use]
+    pub fn add_cellular_automaton<C: CellState>(
+        mut self,
+        plugin: CellularAutomatonPlugin<C>,
+    ) -> Self
+    {
+        if !self.app.is_plugin_added::<CellularAutomatonPlugin<C>>()
+        {
+            self.app.add_plugins(plugin);
+        }
+        self
+    }
+
+    /// Sets up a cellular automaton that updates the states `C` of the cells on a bounded grid on
+    /// the GPU, with a compute shader, on every step, before any user-defined systems run.
     ///
-    /// - [`SimulationBuildError::TimeSeriesRecordingConflict`]
+    /// See the [`crate::gpu`] module for an example.
     ///
-    /// # Panics
+    /// Calling this method more than once for the same cell state has no additional effect.
     ///
-    /// This method will panic if:
+    /// # Errors
     ///
-    /// - The given `sample_interval` is `0`.
-    #[inline]
-    pub fn record_aggregate_time_series<C, O>(
-        self,
-        sample_interval: usize,
+    /// - [`BuilderError::GpuUnavailable`]
+    #[cfg(feature = "gpu")]
+    pub fn add_gpu_automaton<C: GpuCell>(
+        mut self,
+        plugin: GpuAutomatonPlugin<C>,
     ) -> Result<Self, BuilderError>
-    where
-        C: SampleAggregate<O>,
-        O: Send + Sync + 'static,
     {
-        self.record_aggregate_time_series_filtered::<C, (), O>(sample_interval)
+        if !self.app.is_plugin_added::<GpuAutomatonPlugin<C>>()
+        {
+            crate::gpu::add(&mut self.app, plugin)?;
+        }
+        Ok(self)
     }
 
-    /// Sets up the recording of an aggregate time series.
-    ///
-    /// The values in the time series will be values of type `O`
-    /// sampled from components `C`, from all entities selected by the filter `F`
-    /// in the simulation according to the implementation of [`SampleAggregate<O>`] for `C`.
-    ///
-    /// The sampling will occur once every `sample_interval` steps.
-    /// Specifically at the end of the step, after all user-defined systems have run.
-    ///
-    /// Note that it is currently not allowed to record more than one time series
-    /// with the same pair of component (`C`),value (`O`), and filter (`F`).
+    /// Sets up a reaction-diffusion model of scalar fields over a bounded grid, advancing them by
+    /// one unit of time on every step, before any user-defined systems run.
     ///
-    /// # Errors
+    /// The fields are kept in the [`crate::reaction_diffusion::ScalarFields`] resource.
+    /// See the [`crate::reaction_diffusion`] module for an example.
     ///
-    /// - [`SimulationBuildError::TimeSeriesRecordingConflict`]
+    /// Calling this method more than once has no additional effect.
     ///
     /// # Panics
     ///
-    /// This method will panic if:
-    ///
-    /// - The given `sample_interval` is `0`.
-    pub fn record_aggregate_time_series_filtered<C, F, O>(
-        mut self,
-        sample_interval: usize,
-    ) -> Result<Self, BuilderError>
-    where
-        C: SampleAggregate<O>,
-        F: QueryFilter + Send + Sync + 'static,
-        O: Send + Sync + 'static,
+    /// If the diffusion of any field is too fast to be stable with the number of substeps of
+    /// the plugin.
+    #[must_use]
+    pub fn add_reaction_diffusion(mut self, plugin: ReactionDiffusionPlugin) -> Self
     {
-        assert!(sample_interval > 0);
-
-        let world = self.app.world();
-        if world.get_resource::<TimeSeriesData<C, F, O>>().is_some()
+        if !self.app.is_plugin_added::<ReactionDiffusionPlugin>()
         {
-            // More than one time series recording for the same C, F, O is not possible.
-            return Err(BuilderError::TimeSeriesRecordingConflict);
+            self.app.add_plugins(plugin);
         }
-
-        self.app
-            .add_plugins(AggregateTimeSeriesPlugin::<C, F, O>::new(sample_interval));
-        Ok(self)
+        self
     }
 
-    /// Adds a bevy [`Resource`] to the simulation.
+    /// Sets up the stocks and flows of the commodity `R`, applying every [`crate::flow::Flow<R>`]
+    /// at the end of every step, after all user-defined systems have run.
     ///
-    /// This can later be accessed in user-defined systems using [`Res<R>`] and [`ResMut<R>`] arguments.
+    /// The amounts moved and the total held in all stocks are recorded in the
+    /// [`crate::flow::FlowOutputs<R>`] resource, and any change to the total not accounted for
+    /// by them is reported with a [`crate::flow::ConservationViolation`] event, or panics if the
+    /// simulation is [`StrictnessPolicy::Strict`].
+    /// See the [`crate::flow`] module for an example.
+    ///
+    /// Calling this method more than once for the same commodity has no additional effect.
     #[must_use]
-    pub fn add_resource<R: Resource>(mut self, resource: R) -> Self
+    pub fn add_commodity<R: Commodity>(mut self) -> Self
     {
-        self.app.insert_resource(resource);
+        if !self.app.is_plugin_added::<CommodityPlugin<R>>()
+        {
+            self.app.add_plugins(CommodityPlugin::<R>::default());
+        }
         self
     }
 
+    /// Sets up traffic on road networks, moving every [`crate::traffic::Vehicle`] over the
+    /// [`crate::traffic::Road`]s at the end of every step, after all user-defined systems have run.
+    ///
+    /// Every arrival is reported with a [`crate::traffic::VehicleArrived`] event, and the
+    /// congestion of every step is recorded in the [`crate::traffic::TrafficOutputs`] resource.
+    /// See the [`crate::traffic`] module for an example.
+    ///
+    /// Calling this method more than once has no additional effect.
+    #[must_use]
+    pub fn add_traffic(mut self) -> Self
+    {
+        if !self.app.is_plugin_added::<TrafficPlugin>()
+        {
+            self.app.add_plugins(TrafficPlugin);
+        }
+        self
+    }
+
+    /// Adds an external `policy` deciding on actions `A` given observations `O`, which
+    /// user-defined systems can consult using [`ResMut<crate::policy::Policy<O, A>>`] arguments.
+    ///
+    /// See the [`crate::policy`] module for an example.
+    ///
+    /// Calling this method again for the same observations and actions replaces the policy.
+    #[must_use]
+    pub fn add_policy<O, A>(mut self, policy: Policy<O, A>) -> Self
+    where
+        O: Send + 'static,
+        A: Send + 'static,
+    {
+        self.app.insert_resource(policy);
+        self
+    }
+
+    /// Sets up the evolution of a population of agents with genome `C`, which is replaced by the
+    /// offspring of its fittest members at the end of every generation.
+    ///
+    /// The progress of the evolution is summarized in the [`crate::evolution::Evolution<C>`] resource.
+    /// See the [`crate::evolution`] module for an example.
+    ///
+    /// Calling this method more than once for the same genome has no additional effect.
+    #[must_use]
+    pub fn add_evolution<C: Genome>(mut self, plugin: EvolutionPlugin<C>) -> Self
+    {
+        if !self.app.is_plugin_added::<EvolutionPlugin<C>>()
+        {
+            self.app.add_plugins(plugin);
+        }
+        self
+    }
+
+    /// Sets up the [`crate::fsm::Fsm<S>`] components to move between the states `S` according
+    /// to the given table of `transitions`, evaluated for every agent on every step, before any
+    /// user-defined systems run.
+    ///
+    /// The random transitions are drawn from the simulation's [`SimRng`], and the number of
+    /// agents in every state is counted in the [`crate::fsm::StateCounts<S>`] resource.
+    /// See the [`crate::fsm`] module for an example.
+    ///
+    /// Calling this method again for the same states replaces their table of transitions.
+    #[must_use]
+    pub fn add_state_machine<S: FsmState>(mut self, transitions: Transitions<S>) -> Self
+    {
+        if !self.app.is_plugin_added::<FsmPlugin<S>>()
+        {
+            self.app.add_plugins(FsmPlugin::<S>::default());
+        }
+        self.app.insert_resource(transitions);
+        self
+    }
+
+    /// Adds a reaction to the continuous-time simulation of the model with Gillespie's algorithm,
+    /// in which every step of the simulation represents a single event.
+    ///
+    /// The `propensity` is a system returning the rate at which the reaction occurs in the
+    /// current state of the simulation, and must be non-negative.
+    /// The `effect` is a system applying the reaction, run whenever it is the one to occur.
+    /// The times of the events are drawn from the simulation's [`SimRng`], and the elapsed time
+    /// is kept in the [`crate::gillespie::SimulationTime`] resource.
+    /// See the [`crate::gillespie`] module for an example.
+    ///
+    /// This method can be called multiple times, once for each reaction.
+    #[must_use]
+    pub fn add_reaction<M1, M2>(
+        mut self,
+        propensity: impl IntoSystem<(), f64, M1> + 'static,
+        effect: impl IntoSystem<(), (), M2> + 'static,
+    ) -> Self
+    {
+        if !self.app.is_plugin_added::<GillespiePlugin>()
+        {
+            self.app.add_plugins(GillespiePlugin);
+        }
+
+        let world = self.app.world_mut();
+        let propensity = world.register_system(propensity);
+        let effect = world.register_system(effect);
+        world.resource_mut::<Reactions>().push(propensity, effect);
+        self
+    }
+
+    /// Sets whether recoverable anomalies in the simulation should panic, or be surfaced
+    /// as errors and events.
+    ///
+    /// By default, the simulation is [`StrictnessPolicy::Lenient`], so that long batch runs are
+    /// not aborted by a single agent stepping off the map.
+    /// Setting it to [`StrictnessPolicy::Strict`] is useful during development, to catch such
+    /// mistakes early. See [`StrictnessPolicy`] for the anomalies affected.
+    #[must_use]
+    pub fn set_strictness(mut self, policy: StrictnessPolicy) -> Self
+    {
+        self.app.insert_resource(policy);
+        self
+    }
+
+    /// Adds a 2D spatial grid for a specific component type to the simulation.
+    ///
+    /// See [`Self::add_spatial_grid`] for details.
+    #[must_use]
+    pub fn add_spatial_grid_2d<C: Component>(self, bounds: Option<GridBounds2D>) -> Self
+    {
+        self.add_spatial_grid::<IVec2, C>(bounds)
+    }
+
+    /// Adds a 3D spatial grid for a specific component type to the simulation.
+    ///
+    /// See [`Self::add_spatial_grid`] for details.
+    #[must_use]
+    pub fn add_spatial_grid_3d<C: Component>(self, bounds: Option<GridBounds3D>) -> Self
+    {
+        self.add_spatial_grid::<IVec3, C>(bounds)
+    }
+
+    /// Add an entity spawner function to the simulation.
+    ///
+    /// In the beginning of every simulation, each of the spawner functions added here
+    /// will get called once. The [`Spawner`] that is passed as an argument shall be used
+    /// to spawn entities into the simulation.
+    ///
+    /// This method may be called multiple times.
+    /// The order in which the given functions are called is the same as the order in
+    /// which they are added to the builder.
+    ///
+    /// Spawners shall be used to set up the initial state of a simulation.
+    /// Additional entities can be spawned in an ongoing simulation using [Commands](https://bevy-cheatbook.github.io/programming/commands.html).
+    #[must_use]
+    pub fn add_entity_spawner(self, entity_spawner: impl Fn(&mut Spawner) + 'static) -> Self
+    {
+        self.add_seeded_entity_spawner(move |spawner, _| entity_spawner(spawner))
+    }
+
+    /// Add an entity spawner function to the simulation, which draws its randomness
+    /// from the simulation's seeded [`SimRng`].
+    ///
+    /// Using this instead of an unseeded generator like `rand::rng()` makes the initial state of
+    /// the simulation reproducible, given the same seed.
+    ///
+    /// See [`Self::add_entity_spawner`] for details.
+    #[must_use]
+    pub fn add_seeded_entity_spawner(
+        mut self,
+        entity_spawner: impl Fn(&mut Spawner, &mut SimRng) + 'static,
+    ) -> Self
+    {
+        self.spawners.push(Box::new(move |world, rng| {
+            entity_spawner(&mut Spawner::new(world), rng);
+        }));
+        self
+    }
+
+    /// Add an entity spawner function to the simulation, which has read access to the
+    /// resource `R`.
+    ///
+    /// This allows the initial state of the simulation to be configured through a parameters
+    /// resource added with [`Self::add_resource`], instead of compile-time constants.
+    ///
+    /// See [`Self::add_entity_spawner`] for details.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Resource)]
+    /// struct Params
+    /// {
+    ///     population: usize,
+    /// }
+    ///
+    /// #[derive(Component)]
+    /// struct Person;
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_resource(Params { population: 100 })
+    ///     .add_entity_spawner_with_resource(|spawner, params: &Params| {
+    ///         for _ in 0..params.population
+    ///         {
+    ///             spawner.spawn(Person);
+    ///         }
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When the simulation is built, if the resource `R` does not exist in it.
+    #[must_use]
+    pub fn add_entity_spawner_with_resource<R: Resource>(
+        mut self,
+        entity_spawner: impl Fn(&mut Spawner, &R) + 'static,
+    ) -> Self
+    {
+        self.spawners.push(Box::new(move |world, _| {
+            assert!(
+                world.contains_resource::<R>(),
+                "the resource {} read by a spawner does not exist",
+                type_name::<R>()
+            );
+            world.resource_scope(|world, resource: Mut<R>| {
+                entity_spawner(&mut Spawner::new(world), &resource);
+            });
+        }));
+        self
+    }
+
+    /// Add an entity spawner function to the simulation, which has read access to the
+    /// entities spawned by all other spawners.
+    ///
+    /// These spawners are invoked after all spawners added with [`Self::add_entity_spawner`]
+    /// and its variants, in the order in which they were added.
+    /// The entities spawned by a dependent spawner are only visible in the [`World`] to dependent
+    /// spawners added after it.
+    ///
+    /// This allows for initialization that depends on previously spawned entities,
+    /// such as spawning one household for every group of people, or linking traders to stocks.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Stock(usize);
+    ///
+    /// #[derive(Component)]
+    /// struct Trader
+    /// {
+    ///     favorite_stock: Entity,
+    /// }
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_entity_spawner(|spawner| {
+    ///         for i in 0..10
+    ///         {
+    ///             spawner.spawn(Stock(i));
+    ///         }
+    ///     })
+    ///     .add_dependent_entity_spawner(|spawner, world| {
+    ///         let mut stocks = world.try_query_filtered::<Entity, With<Stock>>().unwrap();
+    ///         for favorite_stock in stocks.iter(world)
+    ///         {
+    ///             spawner.spawn(Trader { favorite_stock });
+    ///         }
+    ///     })
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn add_dependent_entity_spawner(
+        mut self,
+        entity_spawner: impl Fn(&mut Spawner, &World) + 'static,
+    ) -> Self
+    {
+        self.dependent_spawners.push(Box::new(entity_spawner));
+        self
+    }
+
+    /// Add an entity spawner function to the simulation, which will be invoked once
+    /// at the beginning of step `at_step`.
+    ///
+    /// Steps are numbered starting from `1`, as in [`super::StepNumber`].
+    /// The entities spawned will take part in that step.
+    ///
+    /// This can be used to express events such as the listing of a new stock,
+    /// without the need for a dedicated system.
+    #[must_use]
+    pub fn add_scheduled_spawner(
+        self,
+        at_step: usize,
+        entity_spawner: impl FnMut(&mut Spawner, &mut SimRng) + Send + Sync + 'static,
+    ) -> Self
+    {
+        self.add_spawner_with_schedule(SpawnSchedule::At(at_step), entity_spawner)
+    }
+
+    /// Add an entity spawner function to the simulation, which will be invoked
+    /// at the beginning of every `interval` steps.
+    ///
+    /// The entities spawned will take part in the step in which they are spawned.
+    ///
+    /// This can be used to express recurring events such as immigration or seasonal births,
+    /// without the need for a dedicated system.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `interval` is `0`.
+    #[must_use]
+    pub fn add_recurring_spawner(
+        self,
+        interval: usize,
+        entity_spawner: impl FnMut(&mut Spawner, &mut SimRng) + Send + Sync + 'static,
+    ) -> Self
+    {
+        assert!(interval > 0);
+
+        self.add_spawner_with_schedule(SpawnSchedule::Every(interval), entity_spawner)
+    }
+
+    /// Add an entity spawner function to the simulation, which keeps the population of entities
+    /// with component `C` topped up while the simulation runs.
+    ///
+    /// At the beginning of every step, if the number of entities with `C` has dropped below
+    /// `threshold`, the spawner is invoked with the number of entities missing to reach `target`.
+    /// It is expected to spawn that many entities with `C`, which will take part in that step.
+    ///
+    /// This can be used to model open systems with a constant inflow, such as customers arriving
+    /// at a store as others leave, or particles injected into a chamber.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Customer;
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     // whenever fewer than 80 customers remain, new ones arrive to bring them back to 100
+    ///     .add_refill_spawner::<Customer>(80, 100, |spawner, missing, _rng| {
+    ///         for _ in 0..missing
+    ///         {
+    ///             spawner.spawn(Customer);
+    ///         }
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `threshold` is larger than `target`.
+    #[must_use]
+    pub fn add_refill_spawner<C: Component>(
+        mut self,
+        threshold: usize,
+        target: usize,
+        entity_spawner: impl FnMut(&mut Spawner, usize, &mut SimRng) + Send + Sync + 'static,
+    ) -> Self
+    {
+        assert!(threshold <= target);
+
+        if !self.app.world().contains_resource::<RefillSpawners<C>>()
+        {
+            self.app.add_plugins(RefillSpawnersPlugin::<C>::default());
+        }
+
+        self.app
+            .world_mut()
+            .resource_mut::<RefillSpawners<C>>()
+            .push(threshold, target, entity_spawner);
+        self
+    }
+
+    /// Verifies the setup of the simulation with a dry run, reporting any problems found.
+    ///
+    /// This builds the simulation, spawning all of its initial entities, and executes a single step.
+    /// The simulation is then discarded, so in order to also perform the real run, the builder
+    /// would typically be constructed by a function that can be called twice.
+    ///
+    /// The problems reported include:
+    /// - Panics while building the simulation, such as in spawners or when checking ambiguities
+    ///   with [`Self::check_ambiguities`].
+    /// - Panics while executing the step, such as when a system requires a resource that was
+    ///   never added to the simulation.
+    /// - Components recorded in time series, but never spawned.
+    ///
+    /// Note that the panics are still printed by the panic hook as usual.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Resource)]
+    /// struct Market;
+    ///
+    /// fn trade(_market: Res<Market>) {}
+    ///
+    /// let issues = SimulationBuilder::new()
+    ///     .add_systems(trade)
+    ///     .check()
+    ///     .unwrap_err();
+    /// assert!(matches!(issues[0], CheckIssue::StepPanicked { .. }));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - [`CheckIssue::BuildPanicked`]
+    /// - [`CheckIssue::StepPanicked`]
+    /// - [`CheckIssue::NoRecordedEntities`]
+    pub fn check(mut self) -> Result<(), Vec<CheckIssue>>
+    {
+        let recorded_components = std::mem::take(&mut self.recorded_components);
+
+        let mut simulation =
+            panic::catch_unwind(AssertUnwindSafe(|| self.build())).map_err(|payload| {
+                vec![CheckIssue::BuildPanicked {
+                    message: panic_message(payload.as_ref()),
+                }]
+            })?;
+
+        let mut issues = recorded_components
+            .into_iter()
+            .filter(|recorded| (recorded.count)(simulation.app.world()) == 0)
+            .map(|recorded| CheckIssue::NoRecordedEntities {
+                component: recorded.name,
+            })
+            .collect::<Vec<_>>();
+
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| simulation.run(1)))
+        {
+            issues.push(CheckIssue::StepPanicked {
+                message: panic_message(payload.as_ref()),
+            });
+        }
+
+        if issues.is_empty()
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(issues)
+        }
+    }
+
+    fn assert_no_ambiguities(&mut self)
+    {
+        let world = self.app.world_mut();
+        let labels = world
+            .resource::<Schedules>()
+            .iter()
+            .map(|(_, schedule)| schedule.label())
+            .collect::<Vec<_>>();
+
+        for label in labels
+        {
+            world.schedule_scope(label, |world, schedule| {
+                schedule.set_build_settings(ScheduleBuildSettings {
+                    ambiguity_detection: LogLevel::Error,
+                    ..schedule.get_build_settings()
+                });
+
+                if let Err(err) = schedule.initialize(world)
+                {
+                    panic!("{err}");
+                }
+            });
+        }
+    }
+
+    fn add_spawner_with_schedule(
+        mut self,
+        schedule: SpawnSchedule,
+        entity_spawner: impl FnMut(&mut Spawner, &mut SimRng) + Send + Sync + 'static,
+    ) -> Self
+    {
+        if !self.app.world().contains_resource::<ScheduledSpawners>()
+        {
+            self.app.add_plugins(ScheduledSpawnersPlugin);
+        }
+
+        self.app
+            .world_mut()
+            .resource_mut::<ScheduledSpawners>()
+            .push(schedule, entity_spawner);
+        self
+    }
+
+    /// Sets up the recording of a time series.
+    ///
+    /// The values in the time series will be values of type `O`
+    /// sampled from components `C`, on any entities identified by `I`
+    /// in the simulation according to the implementation of [`Sample<O>`] for `C`.
+    ///
+    /// The sampling will occur once every `sample_interval` steps.
+    /// Specifically at the end of the step, after all user-defined systems have run.
+    ///
+    /// Note that it is currently not allowed to record more than one time series
+    /// with the same pair of component (`C`),value (`O`), and identifier (`I`).
+    ///
+    /// For more details see the documentation of:
+    /// * [`Sample`]
+    /// * [`Identifier`]
+    ///
+    /// # Errors
+    ///
+    /// - [`SimulationBuildError::TimeSeriesRecordingConflict`]
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `sample_interval` is `0`.
+    pub fn record_time_series<C, I, O>(
+        mut self,
+        sample_interval: usize,
+    ) -> Result<Self, BuilderError>
+    where
+        C: Sample<O>,
+        I: Identifier,
+        O: Send + Sync + 'static,
+    {
+        assert!(sample_interval > 0);
+
+        let world = self.app.world();
+        if world.get_resource::<SampleInterval<C, I, O>>().is_some()
+        {
+            // More than one time series recording for the same C, F, O is not possible.
+            return Err(BuilderError::TimeSeriesRecordingConflict);
+        }
+
+        self.app
+            .add_plugins(TimeSeriesPlugin::<C, I, O>::new(sample_interval));
+        self.recorded_components.push(RecordedComponent::new::<C>());
+        self.time_series_reservers.push(|world, steps| {
+            world
+                .resource_mut::<SampleInterval<C, I, O>>()
+                .reserve_steps(steps);
+        });
+        Ok(self)
+    }
+
+    /// Sets up the recording of an aggregate time series.
+    ///
+    /// The values in the time series will be values of type `O`
+    /// sampled from all components `C` in the simulation according to the
+    /// implementation of [`SampleAggregate<O>`] for `C`.
+    ///
+    /// The sampling will occur once every `sample_interval` steps.
+    /// Specifically at the end of the step, after all user-defined systems have run.
+    ///
+    /// Note that it is currently not possible to record more than one time series
+    /// with the same pair of component (`C`),value (`O`), and filter (`F`).
+    ///
+    /// # Errors
+    ///
+    /// - [`SimulationBuildError::TimeSeriesRecordingConflict`]
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `sample_interval` is `0`.
+    #[inline]
+    pub fn record_aggregate_time_series<C, O>(
+        self,
+        sample_interval: usize,
+    ) -> Result<Self, BuilderError>
+    where
+        C: SampleAggregate<O>,
+        O: Send + Sync + 'static,
+    {
+        self.record_aggregate_time_series_filtered::<C, (), O>(sample_interval)
+    }
+
+    /// Sets up the recording of an aggregate time series.
+    ///
+    /// The values in the time series will be values of type `O`
+    /// sampled from components `C`, from all entities selected by the filter `F`
+    /// in the simulation according to the implementation of [`SampleAggregate<O>`] for `C`.
+    ///
+    /// The sampling will occur once every `sample_interval` steps.
+    /// Specifically at the end of the step, after all user-defined systems have run.
+    ///
+    /// Note that it is currently not allowed to record more than one time series
+    /// with the same pair of component (`C`),value (`O`), and filter (`F`).
+    ///
+    /// # Errors
+    ///
+    /// - [`SimulationBuildError::TimeSeriesRecordingConflict`]
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `sample_interval` is `0`.
+    pub fn record_aggregate_time_series_filtered<C, F, O>(
+        mut self,
+        sample_interval: usize,
+    ) -> Result<Self, BuilderError>
+    where
+        C: SampleAggregate<O>,
+        F: QueryFilter + Send + Sync + 'static,
+        O: Send + Sync + 'static,
+    {
+        assert!(sample_interval > 0);
+
+        let world = self.app.world();
+        if world.get_resource::<TimeSeriesData<C, F, O>>().is_some()
+        {
+            // More than one time series recording for the same C, F, O is not possible.
+            return Err(BuilderError::TimeSeriesRecordingConflict);
+        }
+
+        self.app
+            .add_plugins(AggregateTimeSeriesPlugin::<C, F, O>::new(sample_interval));
+        self.recorded_components.push(RecordedComponent::new::<C>());
+        self.time_series_reservers.push(|world, steps| {
+            world
+                .resource_mut::<TimeSeriesData<C, F, O>>()
+                .reserve_steps(steps);
+        });
+        Ok(self)
+    }
+
+    /// Sets up the recording of a [`crate::gillespie::Trajectory`] against the continuous time
+    /// of a simulation with reactions, see [`Self::add_reaction`].
+    ///
+    /// The values in the trajectory will be values of type `O` sampled from all components `C`
+    /// in the simulation according to the implementation of [`SampleAggregate<O>`] for `C`.
+    /// They are sampled once before the first event, and then after every event,
+    /// at the end of the step.
+    ///
+    /// Calling this method more than once for the same component and value has no additional effect.
+    #[must_use]
+    pub fn record_trajectory<C, O>(mut self) -> Self
+    where
+        C: SampleAggregate<O>,
+        O: Send + Sync + 'static,
+    {
+        if !self.app.is_plugin_added::<GillespiePlugin>()
+        {
+            self.app.add_plugins(GillespiePlugin);
+        }
+        if !self.app.world().contains_resource::<TrajectoryData<C, O>>()
+        {
+            self.app.add_plugins(TrajectoryPlugin::<C, O>::default());
+            self.recorded_components.push(RecordedComponent::new::<C>());
+        }
+        self
+    }
+
+    /// Sets up the recording of all events of type `E` emitted during the simulation.
+    ///
+    /// The events are recorded in an [`EventLog<E>`], which can be retrieved with
+    /// [`Simulation::event_log`] and later replayed with [`Self::replay_events`].
+    /// This also registers the event type, as in [`Self::register_event`].
+    ///
+    /// Calling this method more than once for the same event type has no additional effect.
+    #[must_use]
+    pub fn record_events<E: Event + Clone>(mut self) -> Self
+    {
+        if !self.app.world().contains_resource::<EventLog<E>>()
+        {
+            self.app.add_plugins(EventRecorderPlugin::<E>::default());
+        }
+        self
+    }
+
+    /// Sets up the replaying of a previously recorded [`EventLog<E>`].
+    ///
+    /// Every event in the log will be emitted again at the beginning of the same step in which it
+    /// was originally emitted, before any user-defined systems run.
+    /// This also registers the event type, as in [`Self::register_event`].
+    ///
+    /// Combined with [`Self::record_events`], this allows for a specific run of a simulation to be
+    /// reproduced exactly, as long as all of its decisions are communicated through events of type `E`.
+    /// Note that the systems that originally emitted the events will typically need to be left out
+    /// of the replaying simulation, so that the events are not emitted twice.
+    #[must_use]
+    pub fn replay_events<E: Event + Clone>(mut self, log: EventLog<E>) -> Self
+    {
+        self.app.add_plugins(EventReplayPlugin::new(log));
+        self
+    }
+
+    /// Adds the [`SimClock`] resource, which maps every step to a time of day, a day of the week
+    /// and a season, and can be read in user-defined systems using [`Res<SimClock>`] arguments or
+    /// through the run conditions of the [`crate::calendar`] module.
+    ///
+    /// See the [`crate::calendar`] module for an example.
+    ///
+    /// Calling this method again replaces the clock.
+    #[must_use]
+    pub fn add_clock(mut self, mut clock: SimClock) -> Self
+    {
+        if !self.app.is_plugin_added::<ClockPlugin>()
+        {
+            self.app.add_plugins(ClockPlugin);
+        }
+        clock.synchronize(self.app.world().resource::<StepNumber>());
+        self.app.insert_resource(clock);
+        self
+    }
+
+    /// Adds the [`ControlVariate`] resource with the given known `expectation`, which
+    /// user-defined systems sample into using [`ResMut<ControlVariate>`] arguments.
+    ///
+    /// The control variate of every replica is then collected by
+    /// [`crate::EnsembleResult::from_replicas`] and [`crate::Simulation::replicate_monitored`]
+    /// to reduce the variance of their estimates.
+    /// See [`ControlVariate`] for an example.
+    ///
+    /// Calling this method again replaces the control variate.
+    ///
+    /// # Panics
+    ///
+    /// If `expectation` is not finite.
+    #[must_use]
+    pub fn add_control_variate(mut self, expectation: f64) -> Self
+    {
+        self.app.insert_resource(ControlVariate::new(expectation));
+        self
+    }
+
+    /// Sets up the [`FutureEvents<E>`] resource, through which systems can schedule events of
+    /// type `E` to be emitted at a future step.
+    ///
+    /// This also registers the event type, as in [`Self::register_event`].
+    /// Calling this method more than once for the same event type has no additional effect.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Infected;
+    ///
+    /// #[derive(Event)]
+    /// struct Recover(Entity);
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_future_events::<Recover>()
+    ///     .add_systems(|query: Query<Entity, Added<Infected>>, mut future: ResMut<FutureEvents<Recover>>| {
+    ///         for entity in &query
+    ///         {
+    ///             future.schedule_in(5, Recover(entity));
+    ///         }
+    ///     })
+    ///     .add_systems(|mut commands: Commands, mut events: EventReader<Recover>| {
+    ///         for Recover(entity) in events.read()
+    ///         {
+    ///             commands.entity(*entity).remove::<Infected>();
+    ///         }
+    ///     })
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn add_future_events<E: Event>(mut self) -> Self
+    {
+        if !self.app.world().contains_resource::<FutureEvents<E>>()
+        {
+            self.app.add_plugins(FutureEventsPlugin::<E>::default());
+        }
+        self
+    }
+
+    /// Sets up the [`DeferredDespawn`] resource, which collects entities to be despawned
+    /// in a single batch during every step.
+    ///
+    /// This is considerably cheaper than despawning each entity with [`Commands`] in simulations
+    /// where large numbers of entities die every step.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_deferred_despawn()
+    ///     .add_systems(|query: Query<(Entity, &Health)>, mut deferred: ResMut<DeferredDespawn>| {
+    ///         deferred.extend(query.iter().filter(|(_, health)| health.0 == 0).map(|(entity, _)| entity));
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// Calling this method more than once has no additional effect.
+    #[must_use]
+    pub fn add_deferred_despawn(mut self) -> Self
+    {
+        if !self.app.world().contains_resource::<DeferredDespawn>()
+        {
+            self.app.add_plugins(DeferredDespawnPlugin);
+        }
+        self
+    }
+
+    /// Sets up the [`DeferredSpawn<B>`] resource, which collects bundles of type `B` to be spawned
+    /// in a single batch during every step.
+    ///
+    /// This is considerably cheaper than spawning each entity with [`Commands`] in simulations
+    /// where large numbers of entities are born every step.
+    ///
+    /// Calling this method more than once for the same bundle type has no additional effect.
+    #[must_use]
+    pub fn add_deferred_spawn<B>(mut self) -> Self
+    where
+        B: Bundle<Effect: NoBundleEffect>,
+    {
+        if !self.app.world().contains_resource::<DeferredSpawn<B>>()
+        {
+            self.app.add_plugins(DeferredSpawnPlugin::<B>::default());
+        }
+        self
+    }
+
+    /// Sets up the tracking of the population of entities with the component `C`.
+    ///
+    /// The cumulative number of such entities spawned and despawned over the course of the
+    /// simulation will be recorded in a [`PopulationLedger<C>`] resource, which can be retrieved
+    /// with [`Simulation::ledger`].
+    ///
+    /// Calling this method more than once for the same component has no additional effect.
+    #[must_use]
+    pub fn track_population<C: Component>(mut self) -> Self
+    {
+        if !self.app.world().contains_resource::<PopulationLedger<C>>()
+        {
+            self.app.add_plugins(PopulationLedgerPlugin::<C>::default());
+        }
+        self
+    }
+
+    /// Sets up the incremental aggregation of values of type `O` sampled from components `C`.
+    ///
+    /// A running sum, count and mean of the values is maintained in an [`IncrementalAggregate<C, O>`]
+    /// resource, which can be retrieved with [`Simulation::incremental_aggregate`].
+    /// It is updated only from the components that change during each step, instead of
+    /// re-scanning all entities, which pays off when only a small fraction of them change per step.
+    ///
+    /// Calling this method more than once for the same component and out type has no additional effect.
+    #[must_use]
+    pub fn track_incremental_aggregate<C, O>(mut self) -> Self
+    where
+        C: Sample<O>,
+        O: AsF64 + Send + Sync + 'static,
+    {
+        if !self
+            .app
+            .world()
+            .contains_resource::<IncrementalAggregate<C, O>>()
+        {
+            self.app
+                .add_plugins(IncrementalAggregatePlugin::<C, O>::default());
+        }
+        self
+    }
+
+    /// Sets up a guard against non-finite values of type `O` sampled from components `C`.
+    ///
+    /// At the end of every step, the values are sampled from all entities with `C`, according to
+    /// the implementation of [`Sample<O>`] for `C`. As soon as any of them is `NaN` or infinite,
+    /// the simulation stops, and the offending component, entity and step are reported by
+    /// [`Simulation::check_numeric_guards`].
+    ///
+    /// This helps catch numeric bugs where they occur, instead of much later when aggregating
+    /// the results, such as with [`crate::Median`] which panics on `NaN` values.
+    ///
+    /// Calling this method more than once for the same component and out type has no additional effect.
+    #[must_use]
+    pub fn add_numeric_guard<C, O>(mut self) -> Self
+    where
+        C: Sample<O>,
+        O: Into<f64> + Send + Sync + 'static,
+    {
+        if !self.app.is_plugin_added::<NumericGuardPlugin<C, O>>()
+        {
+            self.app.add_plugins(NumericGuardPlugin::<C, O>::default());
+        }
+        self
+    }
+
+    /// Enables the parallel sampling of aggregates over populations larger than `threshold`.
+    ///
+    /// This applies to [`Simulation::sample_aggregate`] as well as to the recording of aggregate
+    /// time series, for aggregates that can be merged from the aggregates of smaller batches,
+    /// such as [`crate::Minimum`], [`crate::Maximum`] and floating point [`crate::Mean`].
+    /// See [`ParallelSampling`] for details.
+    ///
+    /// Parallel sampling is disabled by default, as it only pays off for populations in the
+    /// hundreds of thousands of entities or more.
+    ///
+    /// # Panics
+    ///
+    /// If `threshold` is `0`.
+    #[must_use]
+    pub fn parallel_sampling_threshold(mut self, threshold: usize) -> Self
+    {
+        assert!(threshold > 0);
+
+        self.app.insert_resource(ParallelSampling { threshold });
+        self
+    }
+
+    /// Hints the number of entities that the simulation is expected to start with.
+    ///
+    /// When building the simulation, capacity for this many entities is reserved up front
+    /// in the crate's own data structures, such as the spatial grids, the same way as with
+    /// [`crate::Spawner::reserve`]. This can considerably reduce reallocations when spawning
+    /// millions of entities. Bevy's entity and component storage still grows on demand.
+    #[must_use]
+    pub const fn expected_entities(mut self, count: usize) -> Self
+    {
+        self.expected_entities = count;
+        self
+    }
+
+    /// Hints the number of steps that the simulation is expected to run for.
+    ///
+    /// When building the simulation, capacity for all the samples expected over this many steps
+    /// is reserved up front in every recorded time series, according to its sample interval.
+    /// This avoids repeatedly growing the storage of long and fine-grained recordings.
+    #[must_use]
+    pub const fn expected_steps(mut self, steps: usize) -> Self
+    {
+        self.expected_steps = steps;
+        self
+    }
+
+    /// Sets whether to check the simulation's systems for ambiguous ordering when building it.
+    ///
+    /// Two systems are ambiguous when they have conflicting access to the same data, such as one
+    /// writing to a component that the other reads, but no order defined between them.
+    /// The order in which such systems run may differ between steps and between runs, which
+    /// silently changes the results of the simulation even when it is seeded.
+    ///
+    /// When enabled, [`Self::build`] will panic with a report of all ambiguous pairs of systems,
+    /// including any conflicts between user-defined systems and the crate's internal ones.
+    /// Ambiguities can then be resolved by ordering the systems, for example with
+    /// [`IntoScheduleConfigs::chain`] or [`IntoScheduleConfigs::before`].
+    #[must_use]
+    pub const fn check_ambiguities(mut self, enabled: bool) -> Self
+    {
+        self.check_ambiguities = enabled;
+        self
+    }
+
+    /// Sets whether to run all systems of the simulation on the calling thread.
+    ///
+    /// By default, systems that do not conflict with each other are run in parallel on the
+    /// compute task pool, which is shared by all simulations in the process.
+    /// When many simulations run concurrently, such as with [`Simulation::replicate`], the
+    /// simulations themselves already keep every core busy. Running each of them on a single thread
+    /// then avoids oversubscribing the shared pool, with every simulation contending for its threads.
+    #[must_use]
+    pub const fn single_threaded(mut self, enabled: bool) -> Self
+    {
+        self.single_threaded = enabled;
+        self
+    }
+
+    /// Sets the maximum verbosity of the tracing spans emitted by the simulation.
+    ///
+    /// With the `trace` feature enabled, spans are emitted around the building of the simulation
+    /// and every step at [`tracing::Level::INFO`], around spawning and sampling at
+    /// [`tracing::Level::DEBUG`], as well as around every system by bevy itself.
+    /// These can then be collected with any [`tracing`] subscriber, to diagnose performance
+    /// and logic issues in headless runs.
+    ///
+    /// By default, spans of all levels are emitted, leaving any filtering to the subscriber.
+    #[cfg(feature = "trace")]
+    #[must_use]
+    pub fn set_trace_level(mut self, level: tracing::Level) -> Self
+    {
+        self.app.insert_resource(TraceLevel(level));
+        self
+    }
+
+    /// Opens a window showing live plots of the simulation while it runs.
+    ///
+    /// The dashboard shows the number of entities and the rate of steps per second,
+    /// along with any time series added with [`Self::add_dashboard_plot`].
+    /// It is refreshed at the end of simulation steps, at most every 50 milliseconds.
+    ///
+    /// The window is opened when the simulation is built, and closing it does not stop the simulation.
+    #[cfg(feature = "dashboard")]
+    #[must_use]
+    pub fn with_dashboard(mut self) -> Self
+    {
+        crate::dashboard::add(&mut self.app);
+        self
+    }
+
+    /// Adds a live plot of the aggregate time series of `O` over components `C` to the dashboard.
+    ///
+    /// The time series should be recorded with [`Self::record_aggregate_time_series`],
+    /// and the dashboard opened with [`Self::with_dashboard`].
+    #[cfg(feature = "dashboard")]
+    #[must_use]
+    pub fn add_dashboard_plot<C, O>(mut self) -> Self
+    where
+        C: SampleAggregate<O>,
+        O: Copy + Into<f64> + Send + Sync + 'static,
+    {
+        self.app
+            .world_mut()
+            .get_resource_or_init::<crate::dashboard::DashboardPlots>()
+            .push(
+                type_name::<C>(),
+                crate::dashboard::aggregate_time_series_points::<C, O>,
+            );
+        self
+    }
+
+    /// Serves metrics of the simulation in the Prometheus text format on an HTTP endpoint
+    /// at the given `address`, so that long-running simulations can be monitored while they run.
+    ///
+    /// The endpoint reports the number of steps run, the number of entities and the rate of
+    /// steps per second as gauges, along with any gauges added with [`Self::add_metric`].
+    /// The metrics are refreshed at the end of simulation steps, at most once every second.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use incerto::prelude::*;
+    /// let simulation = SimulationBuilder::new()
+    ///     .serve_metrics("0.0.0.0:9091")
+    ///     .expect("port in use")
+    ///     .build();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - [`BuilderError::MetricsEndpointUnavailable`]
+    #[cfg(feature = "metrics")]
+    pub fn serve_metrics(
+        mut self,
+        address: impl std::net::ToSocketAddrs,
+    ) -> Result<Self, BuilderError>
+    {
+        crate::metrics::add(&mut self.app, address)?;
+        Ok(self)
+    }
+
+    /// Adds a gauge with the given `name` to the metrics endpoint, reporting the aggregate of `O`
+    /// over all components `C` in the simulation.
+    ///
+    /// The gauge is omitted while there are no components `C` in the simulation,
+    /// while the endpoint itself should be served with [`Self::serve_metrics`].
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `name` is not a valid Prometheus metric name.
+    #[cfg(feature = "metrics")]
+    #[must_use]
+    pub fn add_metric<C, O>(mut self, name: &'static str) -> Self
+    where
+        C: SampleAggregate<O>,
+        O: Into<f64> + 'static,
+    {
+        assert!(crate::metrics::is_valid_name(name));
+
+        self.app
+            .world_mut()
+            .get_resource_or_init::<crate::metrics::MetricGauges>()
+            .push(name, crate::metrics::aggregate_gauge::<C, O>);
+        self
+    }
+
+    /// Streams the time series of the simulation to WebSocket clients connecting to the given
+    /// `address`, so that a separate front-end can visualize the simulation while it runs.
+    ///
+    /// At the end of every step, the points newly recorded in each time series added with
+    /// [`Self::add_websocket_stream`] are sent to all connected clients as a JSON text message,
+    /// of the form `{"series": "<name>", "points": [[<step>, <value>], ...]}`.
+    /// Clients only receive the points recorded after they connected.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use incerto::prelude::*;
+    /// let simulation = SimulationBuilder::new()
+    ///     .serve_websocket("127.0.0.1:9001")
+    ///     .expect("port in use")
+    ///     .build();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - [`BuilderError::StreamEndpointUnavailable`]
+    #[cfg(feature = "websocket")]
+    pub fn serve_websocket(
+        mut self,
+        address: impl std::net::ToSocketAddrs,
+    ) -> Result<Self, BuilderError>
+    {
+        crate::websocket::add(&mut self.app, address)?;
+        Ok(self)
+    }
+
+    /// Adds the aggregate time series of `O` over components `C` to the streams sent to
+    /// WebSocket clients, named after the type of the component.
+    ///
+    /// The time series should be recorded with [`Self::record_aggregate_time_series`],
+    /// and the endpoint served with [`Self::serve_websocket`].
+    #[cfg(feature = "websocket")]
+    #[must_use]
+    pub fn add_websocket_stream<C, O>(mut self) -> Self
+    where
+        C: SampleAggregate<O>,
+        O: Copy + Into<f64> + Send + Sync + 'static,
+    {
+        self.app
+            .world_mut()
+            .get_resource_or_init::<crate::websocket::StreamedSeries>()
+            .push(
+                type_name::<C>(),
+                crate::plugins::aggregate_time_series_points::<C, O>,
+            );
+        self
+    }
+
+    /// Logs the time series of the simulation as `TensorBoard` scalars, to an event file created
+    /// in the given directory, so that they can be monitored alongside other experiments.
+    ///
+    /// At the end of every step, the points newly recorded in each time series added with
+    /// [`Self::add_tensorboard_scalar`] are appended to the event file, with the step at which
+    /// they were recorded. The directory is created if it does not exist, and can be viewed with
+    /// `tensorboard --logdir <dir>` while the simulation runs.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Infected(bool);
+    ///
+    /// impl SampleAggregate<u32> for Infected
+    /// {
+    ///     fn sample_aggregate(components: &[&Self]) -> u32
+    ///     {
+    ///         components.iter().filter(|infected| infected.0).count() as u32
+    ///     }
+    /// }
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .record_aggregate_time_series::<Infected, u32>(1)
+    ///     .expect("already recorded")
+    ///     .log_tensorboard("runs/epidemic")
+    ///     .expect("failed to create event file")
+    ///     .add_tensorboard_scalar::<Infected, u32>("infected")
+    ///     .build();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - [`BuilderError::LogUnavailable`]
+    #[cfg(feature = "tensorboard")]
+    pub fn log_tensorboard(mut self, dir: impl AsRef<std::path::Path>)
+    -> Result<Self, BuilderError>
+    {
+        crate::tensorboard::add(&mut self.app, dir)?;
+        Ok(self)
+    }
+
+    /// Adds the aggregate time series of `O` over components `C` to the scalars logged for
+    /// `TensorBoard`, under the given `tag`.
+    ///
+    /// The time series should be recorded with [`Self::record_aggregate_time_series`],
+    /// and the log set up with [`Self::log_tensorboard`].
+    #[cfg(feature = "tensorboard")]
+    #[must_use]
+    pub fn add_tensorboard_scalar<C, O>(mut self, tag: &'static str) -> Self
+    where
+        C: SampleAggregate<O>,
+        O: Copy + Into<f64> + Send + Sync + 'static,
+    {
+        self.app
+            .world_mut()
+            .get_resource_or_init::<crate::tensorboard::LoggedScalars>()
+            .push(tag, crate::plugins::aggregate_time_series_points::<C, O>);
+        self
+    }
+
+    /// Opens a window rendering the 2D spatial grid of components `C` as colored cells,
+    /// updated at the end of every step.
+    ///
+    /// Each occupied cell is drawn with the color returned by `color_fn` for the component `C`
+    /// of the entity in it, while the spatial grid itself should be added with
+    /// [`Self::add_spatial_grid_2d`]. See [`Self::with_grid_view_every`] for details.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// enum Tree
+    /// {
+    ///     Healthy,
+    ///     Burning,
+    /// }
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_spatial_grid_2d::<Tree>(None)
+    ///     .with_grid_view::<Tree>(|tree| match tree
+    ///     {
+    ///         Tree::Healthy => Color::srgb(0.1, 0.6, 0.1),
+    ///         Tree::Burning => Color::srgb(0.9, 0.3, 0.0),
+    ///     })
+    ///     .build();
+    /// ```
+    #[cfg(feature = "visualize")]
+    #[must_use]
+    pub fn with_grid_view<C: Component>(
+        self,
+        color_fn: impl Fn(&C) -> Color + Send + Sync + 'static,
+    ) -> Self
+    {
+        self.with_grid_view_every(1, color_fn)
+    }
+
+    /// Opens a window rendering the 2D spatial grid of components `C` as colored cells,
+    /// updated at the end of every `interval` steps.
+    ///
+    /// Each occupied cell is drawn with the color returned by `color_fn` for the component `C`
+    /// of the entity in it. If the grid has no bounds, the view covers the occupied cells.
+    /// Views of multiple grids, and the dashboard, are shown side by side in the same window.
+    ///
+    /// The window is opened when the simulation is built, and closing it does not stop the simulation.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `interval` is `0`.
+    #[cfg(feature = "visualize")]
+    #[must_use]
+    pub fn with_grid_view_every<C: Component>(
+        mut self,
+        interval: usize,
+        color_fn: impl Fn(&C) -> Color + Send + Sync + 'static,
+    ) -> Self
+    {
+        assert!(interval > 0);
+
+        crate::visualize::add(&mut self.app, interval, color_fn);
+        self
+    }
+
+    /// Sets up the recording of frames from the 2D grid of components `C`, to be exported as
+    /// images or datasets without opening a window.
+    ///
+    /// Once every `interval` steps, at the end of the step, the `statistic` is computed for every
+    /// cell within the `bounds` from the components `C` of the entities in it, which may be none.
+    /// When exported with [`Simulation::write_frames_png`] or [`Simulation::write_frames_gif`],
+    /// the statistics are mapped to colors on a common scale across all frames,
+    /// while cells whose statistic is not a finite number are drawn black.
+    /// When exported with [`Simulation::write_frames_netcdf`], the statistics are stored as is.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Infected(bool);
+    ///
+    /// let bounds = GridBounds2D {
+    ///     min: IVec2::new(0, 0),
+    ///     max: IVec2::new(99, 99),
+    /// };
+    /// let simulation = SimulationBuilder::new()
+    ///     .record_grid_frames::<Infected>(bounds, 10, |people| {
+    ///         people.iter().filter(|infected| infected.0).count() as f64
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The given `interval` is `0`.
+    #[cfg(any(feature = "image", feature = "netcdf"))]
+    #[must_use]
+    pub fn record_grid_frames<C: Component>(
+        mut self,
+        bounds: GridBounds2D,
+        interval: usize,
+        statistic: impl Fn(&[&C]) -> f64 + Send + Sync + 'static,
+    ) -> Self
+    {
+        assert!(interval > 0);
+
+        crate::frames::GridFrames::new(bounds, interval, statistic).add(&mut self.app);
+        self
+    }
+
+    /// Adds a [`Module`] to the simulation, which sets up its own part of the simulation,
+    /// such as the compartments and transmission of a [`crate::epidemic::EpidemicModule`].
+    #[must_use]
+    pub fn add_module(self, module: impl Module) -> Self
+    {
+        module.build(self)
+    }
+
+    /// Adds a bevy [`Resource`] to the simulation.
+    ///
+    /// This can later be accessed in user-defined systems using [`Res<R>`] and [`ResMut<R>`] arguments.
+    #[must_use]
+    pub fn add_resource<R: Resource>(mut self, resource: R) -> Self
+    {
+        self.app.insert_resource(resource);
+        self
+    }
+
+    /// Builds the simulation, spawning all of its initial entities.
+    ///
+    /// # Panics
+    ///
+    /// If ambiguity checking is enabled with [`Self::check_ambiguities`] and any
+    /// ambiguously ordered systems are found.
     pub fn build(mut self) -> Simulation
     {
+        trace_span!(self.app.world(), INFO, "build");
+
+        if self.check_ambiguities
+        {
+            self.assert_no_ambiguities();
+        }
+        if self.single_threaded
+        {
+            for (_, schedule) in self.app.world_mut().resource_mut::<Schedules>().iter_mut()
+            {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            }
+        }
+
+        let mut meta = self.meta_mut();
+        meta.created_at = now();
+        let seed = *meta.seed.get_or_insert_with(rand::random);
+        let mut rng = SimRng::from_seed(seed);
+
         // spawn all entities
-        let mut spawner = Spawner(self.app.world_mut());
+        let world = self.app.world_mut();
+        if self.expected_entities > 0
+        {
+            Spawner::new(world).reserve(self.expected_entities);
+        }
+        if self.expected_steps > 0
+        {
+            for reserve in &self.time_series_reservers
+            {
+                reserve(world, self.expected_steps);
+            }
+        }
         for spawn_fn in &self.spawners
         {
-            spawn_fn(&mut spawner);
+            spawn_fn(world, &mut rng);
+        }
+
+        // spawn entities that depend on the ones already spawned
+        for spawn_fn in &self.dependent_spawners
+        {
+            let mut queue = CommandQueue::default();
+            spawn_fn(&mut Spawner::deferred(&mut queue, world), world);
+            queue.apply(world);
         }
 
+        self.app.insert_resource(rng);
+
+        #[cfg(any(feature = "dashboard", feature = "visualize"))]
+        crate::window::open(&mut self.app);
+
         Simulation { app: self.app }
     }
 }
+
+fn panic_message(payload: &(dyn Any + Send)) -> String
+{
+    payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
+
+/// Reads the current time of the system.
+///
+/// In the browser, where the standard library has no access to a clock, the time is read
+/// through JavaScript instead.
+fn now() -> SystemTime
+{
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    {
+        let since_epoch = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
+            .unwrap_or_default();
+        SystemTime::UNIX_EPOCH + since_epoch
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    SystemTime::now()
+}
@@ -0,0 +1,87 @@
+//! A compact binary format for checkpoints of simulations and their results,
+//! enabled with the `msgpack` feature.
+//!
+//! A snapshot consists of a short header, holding the bytes [`MAGIC`] followed by the
+//! [`VERSION`] of the format as a little-endian `u16`, and then the value itself encoded
+//! as `MessagePack` with named fields. Snapshots are much faster to write and smaller on disk than
+//! JSON, which makes them suitable for checkpointing simulations of millions of entities.
+//!
+//! The state of a simulation is written with [`crate::Simulation::export_state_snapshot`],
+//! while any other serializable value, such as an [`crate::OwnedTimeSeries`] with the `serde`
+//! feature enabled, can be written with [`encode`]. Both are read back with [`decode`].
+//!
+//! Example:
+//! ```
+//! # use incerto::prelude::*;
+//! let simulation = SimulationBuilder::new().build();
+//!
+//! let bytes = simulation.export_state_snapshot().expect("failed to export state");
+//! let state: serde_json::Value = snapshot::decode(&bytes).expect("failed to decode state");
+//! assert_eq!(state["step"], 0);
+//! ```
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::error::{ExportError, SnapshotError};
+
+/// The bytes every snapshot starts with.
+pub const MAGIC: [u8; 4] = *b"ICSN";
+
+/// The version of the snapshot format written by this version of the crate.
+///
+/// It is increased whenever the layout of the snapshots changes, so that snapshots of
+/// an older format are rejected by [`decode`] instead of being misread.
+pub const VERSION: u16 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + size_of::<u16>();
+
+/// Encodes the given `value` as a snapshot.
+///
+/// # Errors
+///
+/// - [`ExportError::Serialization`]
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, ExportError>
+{
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+
+    rmp_serde::encode::write_named(&mut bytes, value).map_err(|_| ExportError::Serialization)?;
+
+    Ok(bytes)
+}
+
+/// Decodes a value of type `T` from the given snapshot.
+///
+/// # Errors
+///
+/// - [`SnapshotError::InvalidHeader`]
+/// - [`SnapshotError::UnsupportedVersion`]
+/// - [`SnapshotError::Malformed`]
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SnapshotError>
+{
+    let version = version(bytes)?;
+    if version != VERSION
+    {
+        return Err(SnapshotError::UnsupportedVersion { version });
+    }
+
+    rmp_serde::from_slice(&bytes[HEADER_LEN..]).map_err(|_| SnapshotError::Malformed)
+}
+
+/// Reads the version of the format that the given snapshot was written in.
+///
+/// # Errors
+///
+/// - [`SnapshotError::InvalidHeader`]
+pub fn version(bytes: &[u8]) -> Result<u16, SnapshotError>
+{
+    match bytes
+    {
+        [m0, m1, m2, m3, v0, v1, ..] if [*m0, *m1, *m2, *m3] == MAGIC =>
+        {
+            Ok(u16::from_le_bytes([*v0, *v1]))
+        }
+        _ => Err(SnapshotError::InvalidHeader),
+    }
+}
@@ -1,12 +1,580 @@
-use bevy::prelude::*;
+use bevy::{ecs::world::CommandQueue, prelude::*};
+use rand::{
+    Rng,
+    distr::{Distribution, weighted::WeightedIndex},
+};
 
-pub struct Spawner<'a>(pub(crate) &'a mut World);
+#[cfg(feature = "csv")]
+use crate::DatasetError;
+use crate::plugins::{ContactLayers, GridBounds, GridCoordinates, GridPosition, Network};
+
+/// A handle to a specific entity in the simulation.
+///
+/// Returned when spawning entities, and may be used to target them later on,
+/// for example with [`crate::Simulation::sample_entity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityHandle(pub(crate) Entity);
+
+impl EntityHandle
+{
+    /// The bevy [`Entity`] that this handle refers to.
+    #[must_use]
+    pub const fn entity(&self) -> Entity
+    {
+        self.0
+    }
+}
+
+/// Where a [`Spawner`] spawns entities into.
+enum SpawnTarget<'a>
+{
+    /// Entities are spawned into the world directly.
+    World(&'a mut World),
+
+    /// Entities are spawned into the world once the commands are applied,
+    /// which allows for the world to be read while spawning.
+    Commands(Commands<'a, 'a>),
+}
+
+pub struct Spawner<'a>(SpawnTarget<'a>);
+
+impl<'a> Spawner<'a>
+{
+    /// Creates a spawner that spawns entities into the `world` directly.
+    pub(crate) const fn new(world: &'a mut World) -> Self
+    {
+        Self(SpawnTarget::World(world))
+    }
+
+    /// Creates a spawner that pushes spawn commands into the `queue`, to be applied
+    /// to the `world` later on.
+    pub(crate) fn deferred(queue: &'a mut CommandQueue, world: &'a World) -> Self
+    {
+        Self(SpawnTarget::Commands(Commands::new(queue, world)))
+    }
+}
 
 impl Spawner<'_>
 {
     /// Spawns a single entity in the simulation.
-    pub fn spawn(&mut self, entity: impl Bundle)
+    ///
+    /// Returns a handle to the spawned entity.
+    pub fn spawn(&mut self, entity: impl Bundle) -> EntityHandle
+    {
+        let entity = match &mut self.0
+        {
+            SpawnTarget::World(world) => world.spawn(entity).id(),
+            SpawnTarget::Commands(commands) => commands.spawn(entity).id(),
+        };
+
+        EntityHandle(entity)
+    }
+
+    /// Spawns an entity along with a hierarchy of children.
+    ///
+    /// The function `f` receives a [`ChildSpawner`], and every entity spawned through it
+    /// becomes a child of the spawned entity, using bevy's [`ChildOf`] relationship.
+    /// The hierarchy can then be navigated in systems using the [`ChildOf`] and [`Children`] components.
+    ///
+    /// Returns a handle to the parent entity.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Household;
+    ///
+    /// #[derive(Component)]
+    /// struct Person;
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_entity_spawner(|spawner| {
+    ///         spawner.spawn_with_children(Household, |household| {
+    ///             household.spawn(Person);
+    ///             household.spawn(Person);
+    ///         });
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn spawn_with_children(
+        &mut self,
+        entity: impl Bundle,
+        f: impl FnOnce(&mut ChildSpawner<'_, '_>),
+    ) -> EntityHandle
+    {
+        let parent = self.spawn(entity);
+        f(&mut ChildSpawner {
+            spawner: self,
+            parent: parent.0,
+        });
+
+        parent
+    }
+
+    /// Reserves capacity for at least `additional` more entities to be spawned.
+    ///
+    /// This is only a hint, which can reduce the reallocations when spawning a large number
+    /// of entities. Capacity is reserved in the crate's own data structures, such as the
+    /// spatial grids, while bevy's entity and component storage still grows on demand.
+    pub fn reserve(&mut self, additional: usize)
+    {
+        match &mut self.0
+        {
+            SpawnTarget::World(world) => reserve_entities(world, additional),
+            SpawnTarget::Commands(commands) =>
+            {
+                commands.queue(move |world: &mut World| reserve_entities(world, additional));
+            }
+        }
+    }
+
+    /// Spawns one entity for every bundle in the given iterator.
+    pub fn spawn_from_iter<B: Bundle>(&mut self, bundles: impl IntoIterator<Item = B>)
+    {
+        for bundle in bundles
+        {
+            self.spawn(bundle);
+        }
+    }
+
+    /// Spawns one entity for every record in a CSV file.
+    ///
+    /// The first line of the file is expected to be a header.
+    /// Every other line is deserialized into a record of type `R`, which the function `f`
+    /// then turns into the bundle of the entity to spawn.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use incerto::prelude::*;
+    /// #[derive(serde::Deserialize)]
+    /// struct Record
+    /// {
+    ///     age: u8,
+    ///     income: f64,
+    /// }
+    ///
+    /// #[derive(Component)]
+    /// struct Person
+    /// {
+    ///     age: u8,
+    ///     income: f64,
+    /// }
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_entity_spawner(|spawner| {
+    ///         spawner
+    ///             .spawn_from_csv("census.csv", |record: Record| Person {
+    ///                 age: record.age,
+    ///                 income: record.income,
+    ///             })
+    ///             .expect("failed to read census data");
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - [`DatasetError::Io`]
+    /// - [`DatasetError::InvalidRecord`]
+    #[cfg(feature = "csv")]
+    pub fn spawn_from_csv<R, B>(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        mut f: impl FnMut(R) -> B,
+    ) -> Result<(), DatasetError>
+    where
+        R: serde::de::DeserializeOwned,
+        B: Bundle,
+    {
+        let mut reader = csv::Reader::from_path(path).map_err(|_| DatasetError::Io)?;
+
+        for record in reader.deserialize()
+        {
+            let record = record.map_err(|err| match err.kind()
+            {
+                csv::ErrorKind::Io(_) => DatasetError::Io,
+                _ => DatasetError::InvalidRecord {
+                    line: err.position().map(csv::Position::line),
+                },
+            })?;
+
+            self.spawn(f(record));
+        }
+
+        Ok(())
+    }
+
+    /// Spawns entities on every position of a grid.
+    ///
+    /// The function `f` is called once for every position within `bounds`, see [`GridBounds::positions`].
+    /// If it returns `Some(bundle)`, an entity is spawned with that bundle and a [`GridPosition`]
+    /// component set to the position.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Tree;
+    ///
+    /// let bounds = GridBounds2D {
+    ///     min: IVec2::new(0, 0),
+    ///     max: IVec2::new(99, 99),
+    /// };
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_spatial_grid_2d::<Tree>(Some(bounds))
+    ///     .add_entity_spawner(move |spawner| {
+    ///         // plant a tree on every other row
+    ///         spawner.spawn_grid(bounds, |pos| (pos.y() % 2 == 0).then_some(Tree));
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn spawn_grid<T, B>(
+        &mut self,
+        bounds: GridBounds<T>,
+        mut f: impl FnMut(GridPosition<T>) -> Option<B>,
+    ) where
+        T: GridCoordinates,
+        B: Bundle,
+    {
+        for position in bounds.positions()
+        {
+            if let Some(bundle) = f(position)
+            {
+                self.spawn((position, bundle));
+            }
+        }
+    }
+
+    /// Adds an edge of weight `1.0` between two spawned entities in the [`Network`] of
+    /// components `C`, see [`Self::add_weighted_edge`].
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The network has not been added with [`crate::SimulationBuilder::add_network`].
+    pub fn add_edge<C: Component>(&mut self, a: EntityHandle, b: EntityHandle)
+    {
+        self.add_weighted_edge::<C>(a, b, 1.0);
+    }
+
+    /// Adds an edge with the given `weight` between two spawned entities in the [`Network`] of
+    /// components `C`, or updates the weight of the edge if it already exists.
+    ///
+    /// Example:
+    /// ```
+    /// # use incerto::prelude::*;
+    /// #[derive(Component)]
+    /// struct Person;
+    ///
+    /// let simulation = SimulationBuilder::new()
+    ///     .add_network::<Person>()
+    ///     .add_entity_spawner(|spawner| {
+    ///         let alice = spawner.spawn(Person);
+    ///         let bob = spawner.spawn(Person);
+    ///         spawner.add_weighted_edge::<Person>(alice, bob, 0.5);
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The network has not been added with [`crate::SimulationBuilder::add_network`].
+    pub fn add_weighted_edge<C: Component>(&mut self, a: EntityHandle, b: EntityHandle, weight: f64)
+    {
+        match &mut self.0
+        {
+            SpawnTarget::World(world) =>
+            {
+                world
+                    .resource_mut::<Network<C>>()
+                    .add_weighted_edge(a.0, b.0, weight);
+            }
+            SpawnTarget::Commands(commands) =>
+            {
+                commands.queue(move |world: &mut World| {
+                    world
+                        .resource_mut::<Network<C>>()
+                        .add_weighted_edge(a.0, b.0, weight);
+                });
+            }
+        }
+    }
+
+    /// Adds edges of weight `1.0` in the [`Network`] of components `C`, between pairs of spawned
+    /// entities given by their indices in `nodes`.
+    ///
+    /// This is meant for the edges produced by the generators in [`crate::topology`].
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The network has not been added with [`crate::SimulationBuilder::add_network`].
+    /// - Any index is out of bounds of `nodes`.
+    pub fn add_edges<C: Component>(
+        &mut self,
+        nodes: &[EntityHandle],
+        edges: impl IntoIterator<Item = (usize, usize)>,
+    )
+    {
+        let edges = edges
+            .into_iter()
+            .map(|(a, b)| (nodes[a].0, nodes[b].0))
+            .collect::<Vec<_>>();
+
+        let add = move |world: &mut World| {
+            let mut network = world.resource_mut::<Network<C>>();
+            for (a, b) in edges
+            {
+                network.add_edge(a, b);
+            }
+        };
+
+        match &mut self.0
+        {
+            SpawnTarget::World(world) => add(world),
+            SpawnTarget::Commands(commands) => commands.queue(add),
+        }
+    }
+
+    /// Adds an edge of weight `1.0` between two spawned entities in the network layer named
+    /// `layer` of the [`ContactLayers`] of components `C`, see [`Self::add_weighted_layer_edge`].
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The layers have not been added with [`crate::SimulationBuilder::add_contact_layers`].
+    /// - There is no network layer named `layer`.
+    pub fn add_layer_edge<C: Component>(
+        &mut self,
+        layer: &'static str,
+        a: EntityHandle,
+        b: EntityHandle,
+    )
+    {
+        self.add_weighted_layer_edge::<C>(layer, a, b, 1.0);
+    }
+
+    /// Adds an edge with the given `weight` between two spawned entities in the network layer
+    /// named `layer` of the [`ContactLayers`] of components `C`, or updates the weight of the
+    /// edge if it already exists.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The layers have not been added with [`crate::SimulationBuilder::add_contact_layers`].
+    /// - There is no network layer named `layer`.
+    pub fn add_weighted_layer_edge<C: Component>(
+        &mut self,
+        layer: &'static str,
+        a: EntityHandle,
+        b: EntityHandle,
+        weight: f64,
+    )
+    {
+        self.with_layer::<C>(layer, move |network| {
+            network.add_weighted_edge(a.0, b.0, weight);
+        });
+    }
+
+    /// Adds edges of weight `1.0` in the network layer named `layer` of the [`ContactLayers`] of
+    /// components `C`, between pairs of spawned entities given by their indices in `nodes`.
+    ///
+    /// This is meant for the edges produced by the generators in [`crate::topology`].
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if:
+    ///
+    /// - The layers have not been added with [`crate::SimulationBuilder::add_contact_layers`].
+    /// - There is no network layer named `layer`.
+    /// - Any index is out of bounds of `nodes`.
+    pub fn add_layer_edges<C: Component>(
+        &mut self,
+        layer: &'static str,
+        nodes: &[EntityHandle],
+        edges: impl IntoIterator<Item = (usize, usize)>,
+    )
+    {
+        let edges = edges
+            .into_iter()
+            .map(|(a, b)| (nodes[a].0, nodes[b].0))
+            .collect::<Vec<_>>();
+
+        self.with_layer::<C>(layer, move |network| {
+            for (a, b) in edges
+            {
+                network.add_edge(a, b);
+            }
+        });
+    }
+
+    /// Applies `f` to the network of the given contact layer, now or once the commands are applied.
+    fn with_layer<C: Component>(
+        &mut self,
+        layer: &'static str,
+        f: impl FnOnce(&mut Network<C>) + Send + 'static,
+    )
+    {
+        let apply = move |world: &mut World| {
+            let mut layers = world.resource_mut::<ContactLayers<C>>();
+            let Some(network) = layers.network_mut(layer)
+            else
+            {
+                panic!("no contact network layer named {layer}");
+            };
+            f(network);
+        };
+
+        match &mut self.0
+        {
+            SpawnTarget::World(world) => apply(world),
+            SpawnTarget::Commands(commands) => commands.queue(apply),
+        }
+    }
+}
+
+/// Functions that reserve capacity for more entities in the crate's own data structures,
+/// registered by the builder for every plugin that keeps such a structure.
+#[derive(Resource, Default, Clone)]
+pub struct CapacityReservers(pub Vec<fn(&mut World, usize)>);
+
+fn reserve_entities(world: &mut World, additional: usize)
+{
+    let Some(CapacityReservers(reservers)) = world.get_resource::<CapacityReservers>().cloned()
+    else
+    {
+        return;
+    };
+    for reserve in reservers
     {
-        self.0.spawn(entity);
+        reserve(world, additional);
+    }
+}
+
+/// Spawns the children of an entity, as part of [`Spawner::spawn_with_children`].
+pub struct ChildSpawner<'s, 'a>
+{
+    spawner: &'s mut Spawner<'a>,
+    parent: Entity,
+}
+
+impl ChildSpawner<'_, '_>
+{
+    /// The parent entity that children are spawned under.
+    #[must_use]
+    pub const fn parent(&self) -> EntityHandle
+    {
+        EntityHandle(self.parent)
+    }
+
+    /// Spawns a single child entity.
+    ///
+    /// Returns a handle to the spawned entity.
+    pub fn spawn(&mut self, entity: impl Bundle) -> EntityHandle
+    {
+        self.spawner.spawn((entity, ChildOf(self.parent)))
+    }
+
+    /// Spawns a child entity along with a hierarchy of its own children.
+    ///
+    /// See [`Spawner::spawn_with_children`].
+    pub fn spawn_with_children(
+        &mut self,
+        entity: impl Bundle,
+        f: impl FnOnce(&mut ChildSpawner<'_, '_>),
+    ) -> EntityHandle
+    {
+        self.spawner
+            .spawn_with_children((entity, ChildOf(self.parent)), f)
+    }
+}
+
+type BundleConstructor<'a> = Box<dyn FnMut(&mut Spawner) + 'a>;
+
+/// Spawns entities drawn at random from a weighted mixture of bundles.
+///
+/// Each bundle constructor is registered along with its weight using [`Self::add`].
+/// Every entity spawned with [`Self::spawn`] is then created by one of the constructors,
+/// chosen with probability proportional to its weight.
+///
+/// Example:
+/// ```
+/// # use incerto::prelude::*;
+/// #[derive(Component)]
+/// enum Aircraft
+/// {
+///     Commercial,
+///     Private,
+///     Cargo,
+/// }
+///
+/// let simulation = SimulationBuilder::new()
+///     .add_seeded_entity_spawner(|spawner, rng| {
+///         WeightedSpawner::new()
+///             .add(0.7, || Aircraft::Commercial)
+///             .add(0.2, || Aircraft::Private)
+///             .add(0.1, || Aircraft::Cargo)
+///             .spawn(spawner, 1000, rng);
+///     })
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct WeightedSpawner<'a>
+{
+    weights: Vec<f64>,
+    constructors: Vec<BundleConstructor<'a>>,
+}
+
+impl<'a> WeightedSpawner<'a>
+{
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Registers a bundle constructor, to be chosen with a probability proportional to `weight`.
+    ///
+    /// # Panics
+    ///
+    /// If `weight` is negative or not finite.
+    #[must_use]
+    pub fn add<B: Bundle>(mut self, weight: f64, mut f: impl FnMut() -> B + 'a) -> Self
+    {
+        assert!(weight.is_finite() && weight >= 0.0);
+
+        self.weights.push(weight);
+        self.constructors.push(Box::new(move |spawner| {
+            spawner.spawn(f());
+        }));
+        self
+    }
+
+    /// Spawns `count` entities, choosing the constructor of each one at random using `rng`.
+    ///
+    /// To keep the simulation reproducible, `rng` would typically be the [`crate::SimRng`]
+    /// passed to a spawner added with [`crate::SimulationBuilder::add_seeded_entity_spawner`].
+    ///
+    /// # Panics
+    ///
+    /// If no constructors have been registered, or if all of their weights are zero.
+    pub fn spawn(&mut self, spawner: &mut Spawner, count: usize, rng: &mut impl Rng)
+    {
+        let Ok(distribution) = WeightedIndex::new(&self.weights)
+        else
+        {
+            panic!("weighted spawner needs at least one constructor with a positive weight");
+        };
+
+        for _ in 0..count
+        {
+            (self.constructors[distribution.sample(rng)])(spawner);
+        }
     }
 }
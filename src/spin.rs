@@ -0,0 +1,436 @@
+//! Spin-lattice models of statistical physics, such as the Ising model, sampled with the
+//! Metropolis-Hastings algorithm.
+//!
+//! Every site of a bounded grid holds a [`Spin`], and the energy of the lattice is given by a
+//! [`Hamiltonian`] over the bonds between adjacent sites and the sites themselves.
+//! The model is added to a simulation with [`crate::SimulationBuilder::add_module`], through a
+//! [`MetropolisModule`], and every step is one sweep of the lattice, in which as many single-spin
+//! flips are proposed as there are spins, each accepted with probability `min(1, exp(-dE / T))`.
+//!
+//! The temperature `T` is kept in the [`Temperature`] resource, so that it can be given as a
+//! parameter of every run in a sweep, or changed while the simulation runs, e.g. for annealing.
+//! The magnetization and energy per spin of every step are recorded in the [`SpinOutputs`]
+//! resource.
+//!
+//! Example of the 2D Ising model below its critical temperature, ordering from a random start:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::spin::{Ising, MetropolisModule, SpinOutputs};
+//!
+//! let bounds = GridBounds2D {
+//!     min: IVec2::new(0, 0),
+//!     max: IVec2::new(31, 31),
+//! };
+//! let mut simulation = SimulationBuilder::new()
+//!     .set_seed(7)
+//!     .add_module(
+//!         MetropolisModule::new(bounds, Ising::new(1.0), 1.5)
+//!             .with_wrapping()
+//!             .with_lattice(None),
+//!     )
+//!     .build();
+//!
+//! simulation.run(500);
+//! let outputs = simulation.get_resource::<SpinOutputs>().unwrap();
+//! let latest = outputs.latest().unwrap();
+//! assert!(latest.magnetization.abs() > 0.8);
+//! ```
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    MetricsHistory, Module, SimRng, SimulationBuilder,
+    plugins::{GridBounds2D, GridCoordinates, GridPosition2D, StepNumber},
+};
+
+/// Component holding the spin of a site in a [`MetropolisModule`].
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Spin
+{
+    Up,
+    Down,
+}
+
+impl Spin
+{
+    /// The value of the spin, which is `1` for [`Spin::Up`] and `-1` for [`Spin::Down`].
+    #[must_use]
+    pub const fn value(self) -> f64
+    {
+        match self
+        {
+            Self::Up => 1.0,
+            Self::Down => -1.0,
+        }
+    }
+
+    /// The opposite spin.
+    #[must_use]
+    pub const fn flipped(self) -> Self
+    {
+        match self
+        {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+        }
+    }
+}
+
+/// The energy function of a spin lattice, as a sum over the bonds between adjacent sites and
+/// over the sites themselves.
+pub trait Hamiltonian: Send + Sync + 'static
+{
+    /// The energy of the bond between two adjacent spins.
+    fn bond_energy(&self, a: Spin, b: Spin) -> f64;
+
+    /// The energy of a single spin, such as its interaction with an external field.
+    fn site_energy(&self, _spin: Spin) -> f64
+    {
+        0.0
+    }
+}
+
+/// The Hamiltonian of the Ising model, `H = -J * sum(s_i * s_j) - h * sum(s_i)`, where the first
+/// sum is over all pairs of adjacent sites.
+///
+/// The model is ferromagnetic for a positive exchange coupling `J`, and antiferromagnetic for a
+/// negative one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ising
+{
+    /// The exchange coupling `J` between adjacent spins.
+    pub exchange: f64,
+
+    /// The external magnetic field `h`.
+    pub external_field: f64,
+}
+
+impl Ising
+{
+    /// The Ising model with the given exchange coupling, and no external field.
+    #[must_use]
+    pub const fn new(exchange: f64) -> Self
+    {
+        Self {
+            exchange,
+            external_field: 0.0,
+        }
+    }
+
+    /// Sets the external magnetic field.
+    #[must_use]
+    pub const fn with_field(mut self, external_field: f64) -> Self
+    {
+        self.external_field = external_field;
+        self
+    }
+}
+
+impl Hamiltonian for Ising
+{
+    fn bond_energy(&self, a: Spin, b: Spin) -> f64
+    {
+        -self.exchange * a.value() * b.value()
+    }
+
+    fn site_energy(&self, spin: Spin) -> f64
+    {
+        -self.external_field * spin.value()
+    }
+}
+
+/// Resource holding the temperature of a [`MetropolisModule`], in units where the Boltzmann
+/// constant is `1`.
+///
+/// At a temperature of `0`, only flips that do not increase the energy are accepted.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Temperature(pub f64);
+
+/// A spin lattice on the sites of a bounded grid, sampled with the Metropolis-Hastings algorithm,
+/// and added to a simulation with [`crate::SimulationBuilder::add_module`].
+///
+/// Sites are entities with a [`GridPosition2D`] and a [`Spin`], with at most one site on every
+/// position; sites outside the bounds are not updated.
+/// The neighbors of a site are the four orthogonally adjacent ones, and every step is one sweep
+/// of the lattice.
+///
+/// The randomness is drawn from the simulation's [`SimRng`].
+pub struct MetropolisModule<H: Hamiltonian>
+{
+    bounds: GridBounds2D,
+    hamiltonian: H,
+    temperature: f64,
+    wrapping: bool,
+    /// Whether to spawn a site on every cell when the simulation is built.
+    spawn_lattice: bool,
+    /// The initial spin of the spawned sites, or `None` for random spins.
+    initial: Option<Spin>,
+}
+
+impl<H: Hamiltonian> MetropolisModule<H>
+{
+    /// A lattice on the given `bounds`, with energy given by the `hamiltonian`, at the initial
+    /// `temperature`.
+    ///
+    /// By default, the edges of the grid do not wrap around.
+    ///
+    /// # Panics
+    ///
+    /// If `temperature` is negative or not finite.
+    #[must_use]
+    pub fn new(bounds: GridBounds2D, hamiltonian: H, temperature: f64) -> Self
+    {
+        assert_temperature(temperature);
+
+        Self {
+            bounds,
+            hamiltonian,
+            temperature,
+            wrapping: false,
+            spawn_lattice: false,
+            initial: None,
+        }
+    }
+
+    /// Makes the edges of the grid wrap around, so that the sites on opposite edges are adjacent,
+    /// as on a torus, which avoids the effects of the edges on small lattices.
+    #[must_use]
+    pub const fn with_wrapping(mut self) -> Self
+    {
+        self.wrapping = true;
+        self
+    }
+
+    /// Spawns a site on every cell of the grid when the simulation is built, all with the given
+    /// spin, or with random spins if `None`.
+    #[must_use]
+    pub const fn with_lattice(mut self, initial: Option<Spin>) -> Self
+    {
+        self.spawn_lattice = true;
+        self.initial = initial;
+        self
+    }
+}
+
+impl<H: Hamiltonian> Module for MetropolisModule<H>
+{
+    fn build(self, mut builder: SimulationBuilder) -> SimulationBuilder
+    {
+        if self.spawn_lattice
+        {
+            let (bounds, initial) = (self.bounds, self.initial);
+            builder = builder.add_seeded_entity_spawner(move |spawner, rng| {
+                spawner.spawn_grid(bounds, |_| {
+                    let spin = initial.unwrap_or_else(|| {
+                        if rng.random_bool(0.5)
+                        {
+                            Spin::Up
+                        }
+                        else
+                        {
+                            Spin::Down
+                        }
+                    });
+                    Some(spin)
+                });
+            });
+        }
+
+        builder
+            .add_resource(Temperature(self.temperature))
+            .add_resource(SpinOutputs::default())
+            .add_resource(SpinLattice {
+                bounds: self.bounds,
+                hamiltonian: self.hamiltonian,
+                wrapping: self.wrapping,
+                spins: Vec::new(),
+                sites: Vec::new(),
+            })
+            .add_systems(metropolis_system::<H>)
+    }
+}
+
+/// The state of a spin lattice at the end of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SpinMetrics
+{
+    /// The number of the step, as read from [`StepNumber`].
+    pub step: usize,
+
+    /// The temperature during the step.
+    pub temperature: f64,
+
+    /// The mean value of all spins, between `-1` and `1`.
+    pub magnetization: f64,
+
+    /// The energy of the lattice per spin.
+    pub energy: f64,
+
+    /// The fraction of proposed flips that were accepted during the step.
+    pub acceptance: f64,
+}
+
+/// Resource holding the [`SpinMetrics`] of every step of a [`MetropolisModule`].
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<SpinOutputs>`] arguments.
+pub type SpinOutputs = MetricsHistory<SpinMetrics>;
+
+/// Resource holding the Hamiltonian of the lattice, and the buffer with the spins of the sites
+/// during a sweep.
+#[derive(Resource)]
+struct SpinLattice<H: Hamiltonian>
+{
+    bounds: GridBounds2D,
+    hamiltonian: H,
+    wrapping: bool,
+    spins: Vec<Option<Spin>>,
+    /// The positions of all sites within the bounds.
+    sites: Vec<IVec2>,
+}
+
+impl<H: Hamiltonian> SpinLattice<H>
+{
+    /// The index in the buffer of the site at the given position, wrapping it around the edges
+    /// of the grid if enabled.
+    fn index(&self, position: IVec2) -> Option<usize>
+    {
+        let position = if self.wrapping
+        {
+            let size = self.bounds.max - self.bounds.min + IVec2::ONE;
+            self.bounds.min + (position - self.bounds.min).rem_euclid(size)
+        }
+        else
+        {
+            position
+        };
+
+        self.bounds
+            .contains(&position)
+            .then(|| position.cell_index(&self.bounds))
+    }
+
+    /// The spins of the sites adjacent to the given position, excluding itself on grids so
+    /// narrow that it wraps around onto itself.
+    fn neighbors(&self, position: IVec2) -> impl Iterator<Item = Spin> + '_
+    {
+        let own = self.index(position);
+        [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+            .into_iter()
+            .filter_map(move |offset| self.index(position + offset))
+            .filter(move |&index| Some(index) != own)
+            .filter_map(|index| self.spins[index])
+    }
+
+    /// The energy of a site with the given spin, along with its bonds to its neighbors, counting
+    /// each bond `bond_share` times.
+    fn local_energy(&self, position: IVec2, spin: Spin, bond_share: f64) -> f64
+    {
+        let bonds = self
+            .neighbors(position)
+            .map(|neighbor| self.hamiltonian.bond_energy(spin, neighbor))
+            .sum::<f64>();
+        bond_share.mul_add(bonds, self.hamiltonian.site_energy(spin))
+    }
+}
+
+/// System that performs one Metropolis sweep of the lattice, and records the metrics of the step.
+#[allow(clippy::cast_precision_loss)]
+fn metropolis_system<H: Hamiltonian>(
+    mut lattice: ResMut<SpinLattice<H>>,
+    mut outputs: ResMut<SpinOutputs>,
+    mut query: Query<(&GridPosition2D, &mut Spin)>,
+    mut rng: ResMut<SimRng>,
+    temperature: Res<Temperature>,
+    step_number: Res<StepNumber>,
+)
+{
+    let lattice = &mut *lattice;
+    let bounds = lattice.bounds;
+
+    // the buffers are kept between steps to reuse their allocations
+    lattice.spins.clear();
+    lattice
+        .spins
+        .resize_with(IVec2::cell_count(&bounds), || None);
+    lattice.sites.clear();
+    for (position, spin) in &query
+    {
+        if bounds.contains(&position.0)
+        {
+            lattice.spins[position.0.cell_index(&bounds)] = Some(*spin);
+            lattice.sites.push(position.0);
+        }
+    }
+    if lattice.sites.is_empty()
+    {
+        return;
+    }
+
+    let temperature = temperature.0;
+    let mut accepted = 0_u32;
+    for _ in 0..lattice.sites.len()
+    {
+        let position = lattice.sites[rng.random_range(0..lattice.sites.len())];
+        let index = position.cell_index(&bounds);
+        let Some(spin) = lattice.spins[index]
+        else
+        {
+            continue;
+        };
+
+        let delta = lattice.local_energy(position, spin.flipped(), 1.0)
+            - lattice.local_energy(position, spin, 1.0);
+        if delta <= 0.0 || (temperature > 0.0 && rng.random::<f64>() < (-delta / temperature).exp())
+        {
+            lattice.spins[index] = Some(spin.flipped());
+            accepted += 1;
+        }
+    }
+
+    for (position, mut spin) in &mut query
+    {
+        if let Some(&Some(next)) = bounds
+            .contains(&position.0)
+            .then(|| &lattice.spins[position.0.cell_index(&bounds)])
+        {
+            spin.set_if_neq(next);
+        }
+    }
+
+    let sites = lattice.sites.len() as f64;
+    let (magnetization, energy) =
+        lattice
+            .sites
+            .iter()
+            .fold((0.0, 0.0), |(magnetization, energy), &position| {
+                let Some(spin) = lattice.spins[position.cell_index(&bounds)]
+                else
+                {
+                    return (magnetization, energy);
+                };
+                // every bond is shared by the two sites at its ends
+                (
+                    magnetization + spin.value(),
+                    energy + lattice.local_energy(position, spin, 0.5),
+                )
+            });
+
+    let metrics = SpinMetrics {
+        step: **step_number,
+        temperature,
+        magnetization: magnetization / sites,
+        energy: energy / sites,
+        acceptance: f64::from(accepted) / sites,
+    };
+    outputs.push(metrics.step, metrics);
+}
+
+fn assert_temperature(temperature: f64)
+{
+    assert!(
+        temperature.is_finite() && temperature >= 0.0,
+        "invalid temperature: {temperature}"
+    );
+}
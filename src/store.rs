@@ -0,0 +1,419 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use rusqlite::{Connection, OptionalExtension, Row, params};
+
+use crate::{OwnedTimeSeries, Simulation, SimulationMeta, TimeSeries, error::StoreError};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT NOT NULL,
+        seed INTEGER,
+        parameter_hash INTEGER,
+        created_at REAL NOT NULL,
+        steps INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS parameters (
+        run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        value REAL NOT NULL,
+        PRIMARY KEY (run, name)
+    );
+    CREATE TABLE IF NOT EXISTS series (
+        run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        sample_interval INTEGER NOT NULL,
+        PRIMARY KEY (run, name)
+    );
+    CREATE TABLE IF NOT EXISTS points (
+        run INTEGER NOT NULL,
+        series TEXT NOT NULL,
+        step INTEGER NOT NULL,
+        value REAL NOT NULL,
+        FOREIGN KEY (run, series) REFERENCES series(run, name) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS points_by_series ON points (run, series, step);
+";
+
+/// A run of a simulation persisted in a [`ResultStore`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredRun
+{
+    /// The identifier of the run in the store, as returned by [`ResultStore::save_run`].
+    pub id: i64,
+
+    /// The metadata of the simulation.
+    pub meta: SimulationMeta,
+
+    /// The number of steps that had been run when the run was saved.
+    pub steps: usize,
+
+    /// The named parameters the simulation was configured with, sorted by name.
+    pub parameters: Vec<(String, f64)>,
+}
+
+/// A local `SQLite` database persisting the results of simulations,
+/// enabled with the `sqlite` feature.
+///
+/// Every run is saved along with the [`SimulationMeta`] of its simulation and the parameters
+/// it was configured with, after which any number of recorded time series can be attached to it.
+/// Runs can then be looked up by name or by the value of a parameter, which keeps the results of
+/// large parameter sweeps organized without an external pipeline.
+///
+/// Example:
+/// ```
+/// # use incerto::prelude::*;
+/// #[derive(Component)]
+/// struct Cash(f64);
+///
+/// impl SampleAggregate<f64> for Cash
+/// {
+///     fn sample_aggregate(components: &[&Self]) -> f64
+///     {
+///         components.iter().map(|cash| cash.0).sum()
+///     }
+/// }
+///
+/// let store = ResultStore::open_in_memory().expect("failed to open store");
+/// for rate in [0.01, 0.02]
+/// {
+///     let mut simulation = SimulationBuilder::new()
+///         .set_name("compound_interest")
+///         .add_entity_spawner(|spawner| {
+///             spawner.spawn(Cash(100.0));
+///         })
+///         .add_systems(move |mut query: Query<&mut Cash>| {
+///             for mut cash in &mut query
+///             {
+///                 cash.0 *= 1.0 + rate;
+///             }
+///         })
+///         .record_aggregate_time_series::<Cash, f64>(1)
+///         .expect("time series already recorded")
+///         .build();
+///     simulation.run(365);
+///
+///     let run = store
+///         .save_run(&simulation, &[("rate", rate)])
+///         .expect("failed to save run");
+///     let series = simulation
+///         .get_aggregate_time_series::<Cash, f64>()
+///         .expect("time series not recorded");
+///     store
+///         .save_time_series(run, "cash", &series)
+///         .expect("failed to save time series");
+/// }
+///
+/// let runs = store
+///     .runs_with_parameter("rate", 0.02)
+///     .expect("failed to query runs");
+/// let cash = store
+///     .time_series(runs[0].id, "cash")
+///     .expect("failed to load time series");
+/// ```
+pub struct ResultStore
+{
+    connection: Connection,
+}
+
+impl ResultStore
+{
+    /// Opens the store in the database file at the given `path`,
+    /// creating the file and its tables if they do not exist.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::Database`]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError>
+    {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Opens a store held in memory, which is discarded when dropped.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::Database`]
+    pub fn open_in_memory() -> Result<Self, StoreError>
+    {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<Self, StoreError>
+    {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Saves a run of the given `simulation`, along with the named `parameters` it was
+    /// configured with.
+    ///
+    /// The metadata of the simulation and the number of steps run so far are saved with it.
+    /// Returns the identifier of the run in the store, to which time series can then be attached
+    /// with [`Self::save_time_series`].
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::Database`]
+    pub fn save_run(
+        &self,
+        simulation: &Simulation,
+        parameters: &[(&str, f64)],
+    ) -> Result<i64, StoreError>
+    {
+        let meta = simulation.meta();
+        let created_at = meta
+            .created_at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        let transaction = self.connection.unchecked_transaction()?;
+        #[allow(clippy::cast_possible_wrap)]
+        transaction.execute(
+            "INSERT INTO runs (name, description, seed, parameter_hash, created_at, steps)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                meta.name,
+                meta.description,
+                meta.seed.map(|seed| seed as i64),
+                meta.parameter_hash.map(|hash| hash as i64),
+                created_at,
+                simulation.current_step() as i64,
+            ],
+        )?;
+        let run = transaction.last_insert_rowid();
+        {
+            let mut insert = transaction.prepare(
+                "INSERT OR REPLACE INTO parameters (run, name, value) VALUES (?1, ?2, ?3)",
+            )?;
+            for (name, value) in parameters
+            {
+                insert.execute(params![run, name, value])?;
+            }
+        }
+        transaction.commit()?;
+
+        Ok(run)
+    }
+
+    /// Attaches a time series to the given `run` under the given `name`,
+    /// replacing any series of the run with the same name.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::RunNotFound`]
+    /// - [`StoreError::Database`]
+    pub fn save_time_series<T>(
+        &self,
+        run: i64,
+        name: &str,
+        series: &TimeSeries<'_, T>,
+    ) -> Result<(), StoreError>
+    where
+        T: Copy + Into<f64>,
+    {
+        if self.run(run)?.is_none()
+        {
+            return Err(StoreError::RunNotFound { run });
+        }
+
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute(
+            "DELETE FROM series WHERE run = ?1 AND name = ?2",
+            params![run, name],
+        )?;
+        #[allow(clippy::cast_possible_wrap)]
+        transaction.execute(
+            "INSERT INTO series (run, name, sample_interval) VALUES (?1, ?2, ?3)",
+            params![run, name, series.sample_interval() as i64],
+        )?;
+        {
+            let mut insert = transaction
+                .prepare("INSERT INTO points (run, series, step, value) VALUES (?1, ?2, ?3, ?4)")?;
+            #[allow(clippy::cast_possible_wrap)]
+            for (step, value) in series.enumerate_copied()
+            {
+                insert.execute(params![run, name, step as i64, value.into()])?;
+            }
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Loads the time series attached to the given `run` under the given `name`.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::SeriesNotFound`]
+    /// - [`StoreError::Database`]
+    pub fn time_series(&self, run: i64, name: &str) -> Result<OwnedTimeSeries<f64>, StoreError>
+    {
+        let sample_interval: i64 = self
+            .connection
+            .query_row(
+                "SELECT sample_interval FROM series WHERE run = ?1 AND name = ?2",
+                params![run, name],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(StoreError::SeriesNotFound { run })?;
+
+        let mut select = self.connection.prepare(
+            "SELECT step, value FROM points WHERE run = ?1 AND series = ?2 ORDER BY step",
+        )?;
+        let (time, values) = select
+            .query_map(params![run, name], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .map(|(step, value)| (to_usize(step), value))
+            .unzip();
+
+        Ok(OwnedTimeSeries {
+            values,
+            time,
+            sample_interval: to_usize(sample_interval),
+        })
+    }
+
+    /// Looks up the run with the given identifier.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::Database`]
+    pub fn run(&self, id: i64) -> Result<Option<StoredRun>, StoreError>
+    {
+        Ok(self
+            .query_runs("SELECT * FROM runs WHERE id = ?1", params![id])?
+            .pop())
+    }
+
+    /// Lists all runs in the store, in the order in which they were saved.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::Database`]
+    pub fn runs(&self) -> Result<Vec<StoredRun>, StoreError>
+    {
+        self.query_runs("SELECT * FROM runs ORDER BY id", params![])
+    }
+
+    /// Lists the runs of simulations with the given [`SimulationMeta::name`],
+    /// in the order in which they were saved.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::Database`]
+    pub fn runs_named(&self, name: &str) -> Result<Vec<StoredRun>, StoreError>
+    {
+        self.query_runs(
+            "SELECT * FROM runs WHERE name = ?1 ORDER BY id",
+            params![name],
+        )
+    }
+
+    /// Lists the runs whose `parameter` was set to the given `value`,
+    /// in the order in which they were saved.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::Database`]
+    pub fn runs_with_parameter(
+        &self,
+        parameter: &str,
+        value: f64,
+    ) -> Result<Vec<StoredRun>, StoreError>
+    {
+        self.query_runs(
+            "SELECT runs.* FROM runs JOIN parameters ON parameters.run = runs.id
+             WHERE parameters.name = ?1 AND parameters.value = ?2 ORDER BY runs.id",
+            params![parameter, value],
+        )
+    }
+
+    /// Deletes the given `run` from the store, along with its parameters and time series.
+    ///
+    /// # Errors
+    ///
+    /// - [`StoreError::RunNotFound`]
+    /// - [`StoreError::Database`]
+    pub fn delete_run(&self, run: i64) -> Result<(), StoreError>
+    {
+        match self
+            .connection
+            .execute("DELETE FROM runs WHERE id = ?1", params![run])?
+        {
+            0 => Err(StoreError::RunNotFound { run }),
+            _ => Ok(()),
+        }
+    }
+
+    fn query_runs(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<StoredRun>, StoreError>
+    {
+        let mut select = self.connection.prepare(sql)?;
+        let mut runs = select
+            .query_map(params, stored_run)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut select = self
+            .connection
+            .prepare("SELECT name, value FROM parameters WHERE run = ?1 ORDER BY name")?;
+        for run in &mut runs
+        {
+            run.parameters = select
+                .query_map(params![run.id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok(runs)
+    }
+}
+
+impl From<rusqlite::Error> for StoreError
+{
+    fn from(_: rusqlite::Error) -> Self
+    {
+        Self::Database
+    }
+}
+
+/// Reads a run from a row of the `runs` table, without its parameters.
+#[allow(clippy::cast_sign_loss)]
+fn stored_run(row: &Row<'_>) -> rusqlite::Result<StoredRun>
+{
+    let created_at: f64 = row.get("created_at")?;
+
+    Ok(StoredRun {
+        id: row.get("id")?,
+        meta: SimulationMeta {
+            name: row.get("name")?,
+            description: row.get("description")?,
+            seed: row.get::<_, Option<i64>>("seed")?.map(|seed| seed as u64),
+            parameter_hash: row
+                .get::<_, Option<i64>>("parameter_hash")?
+                .map(|hash| hash as u64),
+            created_at: SystemTime::UNIX_EPOCH
+                + Duration::try_from_secs_f64(created_at).unwrap_or_default(),
+        },
+        steps: to_usize(row.get("steps")?),
+        parameters: Vec::new(),
+    })
+}
+
+/// Converts an integer read from the database back into the `usize` it was saved from.
+fn to_usize(value: i64) -> usize
+{
+    usize::try_from(value).unwrap_or_default()
+}
@@ -0,0 +1,207 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    ops::ControlFlow,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::{error::BuilderError, plugins::StepEndHooks};
+
+/// Reads the points of a logged time series from the simulation's world, starting from
+/// the given index in the series, along with the total number of points in it.
+type ScalarSource = fn(&World, usize) -> (Vec<(usize, f64)>, usize);
+
+/// The version of the event format, written as the first event of every file.
+const FILE_VERSION: &str = "brain.Event:2";
+
+/// Resource holding the time series logged as `TensorBoard` scalars, by their tag.
+#[derive(Resource, Default)]
+pub struct LoggedScalars(Vec<(&'static str, ScalarSource)>);
+
+impl LoggedScalars
+{
+    pub fn push(&mut self, tag: &'static str, source: ScalarSource)
+    {
+        self.0.push((tag, source));
+    }
+}
+
+/// Creates an event file in the given directory, and sets up the simulation to append
+/// the newly recorded points of its logged time series to it at the end of its steps.
+///
+/// Failures to write to the file after it has been created are ignored,
+/// so that logging never interrupts the simulation.
+///
+/// # Errors
+///
+/// - [`BuilderError::LogUnavailable`]
+pub fn add(app: &mut App, dir: impl AsRef<Path>) -> Result<(), BuilderError>
+{
+    let dir = dir.as_ref();
+    let file_name = format!(
+        "events.out.tfevents.{}.incerto.{}",
+        wall_time().floor(),
+        std::process::id()
+    );
+
+    let mut writer = fs::create_dir_all(dir)
+        .and_then(|()| File::create(dir.join(file_name)))
+        .map(BufWriter::new)
+        .map_err(|_| BuilderError::LogUnavailable)?;
+    write_record(&mut writer, &version_event())
+        .and_then(|()| writer.flush())
+        .map_err(|_| BuilderError::LogUnavailable)?;
+
+    app.init_resource::<LoggedScalars>();
+    app.world_mut()
+        .get_resource_or_init::<StepEndHooks>()
+        .push(event_writer(writer));
+
+    Ok(())
+}
+
+/// Creates a step-end hook that writes the newly recorded points of every logged time series
+/// to the given `writer`, as scalar events.
+///
+/// The hook never stops the simulation.
+fn event_writer(
+    mut writer: BufWriter<File>,
+) -> impl FnMut(&World, usize) -> ControlFlow<()> + Send + Sync
+{
+    let mut written = Vec::new();
+
+    move |world, _| {
+        let Some(scalars) = world.get_resource::<LoggedScalars>()
+        else
+        {
+            return ControlFlow::Continue(());
+        };
+        written.resize(scalars.0.len(), 0);
+
+        let wall_time = wall_time();
+        let mut any_written = false;
+        for (&(tag, source), written) in scalars.0.iter().zip(&mut written)
+        {
+            let (points, len) = source(world, *written);
+            *written = len;
+
+            for (step, value) in points
+            {
+                let _ = write_record(&mut writer, &scalar_event(wall_time, step, tag, value));
+                any_written = true;
+            }
+        }
+        if any_written
+        {
+            let _ = writer.flush();
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// The number of seconds since the Unix epoch.
+fn wall_time() -> f64
+{
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// Encodes the event declaring the version of the event format, as a `tensorflow.Event` message.
+fn version_event() -> Vec<u8>
+{
+    let mut event = Vec::new();
+    put_double(&mut event, 1, wall_time());
+    put_bytes(&mut event, 3, FILE_VERSION.as_bytes());
+    event
+}
+
+/// Encodes an event holding a single scalar value, as a `tensorflow.Event` message.
+#[allow(clippy::cast_possible_truncation)]
+fn scalar_event(wall_time: f64, step: usize, tag: &str, value: f64) -> Vec<u8>
+{
+    // the fields of `Summary.Value` are the tag and the value, stored in single precision
+    let mut summary_value = Vec::new();
+    put_bytes(&mut summary_value, 1, tag.as_bytes());
+    put_key(&mut summary_value, 2, 5);
+    summary_value.extend_from_slice(&(value as f32).to_le_bytes());
+
+    let mut summary = Vec::new();
+    put_bytes(&mut summary, 1, &summary_value);
+
+    let mut event = Vec::new();
+    put_double(&mut event, 1, wall_time);
+    put_key(&mut event, 2, 0);
+    put_varint(&mut event, step as u64);
+    put_bytes(&mut event, 5, &summary);
+    event
+}
+
+/// Writes the given `data` as a record of the `TFRecord` format, which frames it with its length
+/// and the checksums of both.
+fn write_record(writer: &mut impl Write, data: &[u8]) -> io::Result<()>
+{
+    let len = (data.len() as u64).to_le_bytes();
+
+    writer.write_all(&len)?;
+    writer.write_all(&masked_crc(&len).to_le_bytes())?;
+    writer.write_all(data)?;
+    writer.write_all(&masked_crc(data).to_le_bytes())
+}
+
+/// The CRC-32C checksum of the given `data`, masked as required by the `TFRecord` format.
+fn masked_crc(data: &[u8]) -> u32
+{
+    const POLYNOMIAL: u32 = 0x82F6_3B78;
+
+    let crc = !data.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            if crc & 1 == 1
+            {
+                (crc >> 1) ^ POLYNOMIAL
+            }
+            else
+            {
+                crc >> 1
+            }
+        })
+    });
+
+    crc.rotate_right(15).wrapping_add(0xA282_EAD8)
+}
+
+/// Puts the key of a protobuf field, with the given number and wire type.
+fn put_key(message: &mut Vec<u8>, field: u64, wire_type: u64)
+{
+    put_varint(message, (field << 3) | wire_type);
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn put_varint(message: &mut Vec<u8>, mut value: u64)
+{
+    while value >= 0x80
+    {
+        message.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    message.push(value as u8);
+}
+
+fn put_double(message: &mut Vec<u8>, field: u64, value: f64)
+{
+    put_key(message, field, 1);
+    message.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Puts a length-delimited field, such as a string or an embedded message.
+fn put_bytes(message: &mut Vec<u8>, field: u64, bytes: &[u8])
+{
+    put_key(message, field, 2);
+    put_varint(message, bytes.len() as u64);
+    message.extend_from_slice(bytes);
+}
@@ -0,0 +1,212 @@
+//! Generators of random network topologies, for setting up the relations between entities
+//! in a [`crate::Network`].
+//!
+//! All generators produce the edges of an undirected network over `n` nodes, as pairs of
+//! indices into the list of entities to connect, without self-loops or duplicate edges.
+//! The edges can then be added to a network with [`crate::Spawner::add_edges`].
+//! They draw from the given generator, which would typically be the simulation's
+//! [`crate::SimRng`] passed to a spawner added with [`crate::SimulationBuilder::add_seeded_entity_spawner`],
+//! so that the network is reproducible.
+//!
+//! Example:
+//! ```
+//! # use incerto::prelude::*;
+//! #[derive(Component)]
+//! struct Person;
+//!
+//! let simulation = SimulationBuilder::new()
+//!     .add_network::<Person>()
+//!     .add_seeded_entity_spawner(|spawner, rng| {
+//!         let people = (0..1000)
+//!             .map(|_| spawner.spawn(Person))
+//!             .collect::<Vec<_>>();
+//!
+//!         // a small world, where everyone knows their 10 closest neighbors and a few strangers
+//!         spawner.add_edges::<Person>(&people, topology::watts_strogatz(people.len(), 10, 0.05, rng));
+//!     })
+//!     .build();
+//! ```
+
+use bevy::platform::collections::HashSet;
+use rand::Rng;
+
+/// Generates an Erdős–Rényi random network, where each of the possible edges between
+/// the `n` nodes exists independently with probability `p`.
+///
+/// The expected number of edges is `p * n * (n - 1) / 2`. Rather than testing every possible
+/// edge, the gaps between consecutive edges are drawn from a geometric distribution,
+/// so that sparse networks are generated in time proportional to their number of edges.
+///
+/// # Panics
+///
+/// If `p` is not within `[0, 1]`.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn erdos_renyi(n: usize, p: f64, rng: &mut impl Rng) -> Vec<(usize, usize)>
+{
+    assert!((0.0..=1.0).contains(&p), "invalid edge probability: {p}");
+
+    if p <= 0.0
+    {
+        return Vec::new();
+    }
+    if p >= 1.0
+    {
+        return (0..n).flat_map(|v| (0..v).map(move |w| (w, v))).collect();
+    }
+
+    // the possible edges (w, v) with w < v are visited in order, skipping over the ones
+    // that do not exist
+    let log_q = (1.0 - p).ln();
+    let mut edges = Vec::new();
+    let mut v = 1;
+    let mut w = 0;
+    loop
+    {
+        let skip = ((1.0 - rng.random::<f64>()).ln() / log_q).floor();
+        w += if skip < (n * n) as f64
+        {
+            skip as usize
+        }
+        else
+        {
+            n * n
+        };
+
+        while v < n && w >= v
+        {
+            w -= v;
+            v += 1;
+        }
+        if v >= n
+        {
+            return edges;
+        }
+
+        edges.push((w, v));
+        w += 1;
+    }
+}
+
+/// Generates a Watts–Strogatz small-world network over `n` nodes.
+///
+/// The nodes are first arranged on a ring, with each connected to its `k / 2` closest neighbors
+/// on either side. Then each edge of the ring, going around it once, is rewired with probability
+/// `beta` to connect its first node to another node chosen uniformly at random instead.
+/// This keeps the high clustering of the ring, while the few rewired edges act as shortcuts
+/// that make the average distance between nodes short.
+///
+/// # Panics
+///
+/// If `k` is odd or not less than `n`, or `beta` is not within `[0, 1]`.
+pub fn watts_strogatz(n: usize, k: usize, beta: f64, rng: &mut impl Rng) -> Vec<(usize, usize)>
+{
+    assert!(
+        k.is_multiple_of(2),
+        "the number of neighbors must be even: {k}"
+    );
+    assert!(
+        k < n,
+        "the number of neighbors must be less than the number of nodes"
+    );
+    assert!(
+        (0.0..=1.0).contains(&beta),
+        "invalid rewiring probability: {beta}"
+    );
+
+    let mut edges = (1..=k / 2)
+        .flat_map(|offset| (0..n).map(move |node| (node, (node + offset) % n)))
+        .collect::<Vec<_>>();
+    let mut existing = edges
+        .iter()
+        .map(|&edge| sorted(edge))
+        .collect::<HashSet<_>>();
+    let mut degree = vec![k; n];
+
+    for edge in &mut edges
+    {
+        let (node, neighbor) = *edge;
+        if !rng.random_bool(beta) || degree[node] >= n - 1
+        {
+            continue;
+        }
+
+        let target = loop
+        {
+            let target = rng.random_range(0..n);
+            if target != node && !existing.contains(&sorted((node, target)))
+            {
+                break target;
+            }
+        };
+
+        existing.remove(&sorted(*edge));
+        existing.insert(sorted((node, target)));
+        degree[neighbor] -= 1;
+        degree[target] += 1;
+        *edge = (node, target);
+    }
+
+    edges
+}
+
+/// Generates a Barabási–Albert scale-free network over `n` nodes, by preferential attachment.
+///
+/// The network starts from the first `m + 1` nodes all connected to each other. Every other node
+/// is then added in turn, with edges to `m` distinct nodes already in the network, each chosen
+/// with probability proportional to its degree. This produces a few highly connected hubs,
+/// with degrees following a power law.
+///
+/// # Panics
+///
+/// If `m` is `0`, or not less than `n`.
+pub fn barabasi_albert(n: usize, m: usize, rng: &mut impl Rng) -> Vec<(usize, usize)>
+{
+    assert!(m > 0, "every node must attach to at least one other");
+    assert!(
+        m < n,
+        "the number of attachments must be less than the number of nodes"
+    );
+
+    let mut edges = (0..=m)
+        .flat_map(|v| (0..v).map(move |w| (w, v)))
+        .collect::<Vec<_>>();
+
+    // every node appears once for every edge it has, so that sampling from this list
+    // chooses nodes with probability proportional to their degree
+    let mut endpoints = edges
+        .iter()
+        .flat_map(|&edge| <[usize; 2]>::from(edge))
+        .collect::<Vec<_>>();
+    let mut targets = Vec::with_capacity(m);
+
+    for node in m + 1..n
+    {
+        targets.clear();
+        while targets.len() < m
+        {
+            let target = endpoints[rng.random_range(0..endpoints.len())];
+            if !targets.contains(&target)
+            {
+                targets.push(target);
+            }
+        }
+
+        for &target in &targets
+        {
+            edges.push((target, node));
+            endpoints.extend([target, node]);
+        }
+    }
+
+    edges
+}
+
+/// The edge `(a, b)` with its nodes in ascending order, for looking up undirected edges.
+fn sorted((a, b): (usize, usize)) -> (usize, usize)
+{
+    (a.min(b), a.max(b))
+}
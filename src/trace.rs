@@ -0,0 +1,33 @@
+#[cfg(feature = "trace")]
+use bevy::prelude::*;
+
+/// The maximum verbosity of the tracing spans emitted by the crate.
+///
+/// Set using [`crate::SimulationBuilder::set_trace_level`].
+/// When absent, spans of all levels are emitted, leaving any filtering to the subscriber.
+#[cfg(feature = "trace")]
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct TraceLevel(pub tracing::Level);
+
+/// Returns `true` if spans of the given `level` should be emitted.
+#[cfg(feature = "trace")]
+pub fn is_enabled(world: &World, level: tracing::Level) -> bool
+{
+    world
+        .get_resource::<TraceLevel>()
+        .is_none_or(|max| level <= max.0)
+}
+
+/// Enters a tracing span until the end of the enclosing scope.
+///
+/// This expands to nothing, unless the `trace` feature is enabled and the level of the span is
+/// within the verbosity of the simulation in `world`.
+macro_rules! trace_span {
+    ($world:expr, $level:ident, $name:literal $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "trace")]
+        let _span = $crate::trace::is_enabled($world, tracing::Level::$level).then(|| {
+            tracing::span!(tracing::Level::$level, $name $(, $($fields)*)?).entered()
+        });
+    };
+}
+pub(crate) use trace_span;
@@ -0,0 +1,591 @@
+//! Traffic on road networks, in which vehicles travel between intersections along the shortest
+//! routes under the current congestion.
+//!
+//! Roads are set up with [`crate::SimulationBuilder::add_traffic`], and connect intersections,
+//! which are any entities, such as those spawned for the nodes of a [`crate::topology`]
+//! generator.
+//! Every [`Road`] is a one-way link of its own entity, with a free-flow travel time and a
+//! capacity, and its travel time grows with the number of vehicles on it, following the BPR
+//! function `t = t0 * (1 + 0.15 * (v / c)^4)`, where `t0` is the free-flow time, `v` the number
+//! of vehicles on the road, and `c` its capacity.
+//! Roads may be despawned, or closed by setting their capacity to `0`, e.g. to study the impact
+//! of incidents.
+//!
+//! Every [`Vehicle`] travels from its origin to its destination, advancing by one unit of time
+//! per step, at the end of every step after all user-defined systems have run.
+//! At every intersection it takes the first road on the fastest route to its destination, given
+//! the travel times at that moment, so that vehicles avoid congested and closed roads, and wait
+//! at the intersection while there is no route at all.
+//! The travel time of a road is fixed as a vehicle enters it.
+//! When a vehicle reaches its destination, a [`VehicleArrived`] event is emitted, and the vehicle
+//! is despawned.
+//!
+//! The congestion of the network on every step is recorded in the [`TrafficOutputs`] resource.
+//!
+//! Example of vehicles choosing between a short road and a long detour:
+//! ```
+//! # use incerto::prelude::*;
+//! use incerto::traffic::{Road, TrafficOutputs, Vehicle};
+//!
+//! #[derive(Component)]
+//! struct Intersection;
+//!
+//! let mut simulation = SimulationBuilder::new()
+//!     .add_traffic()
+//!     .add_entity_spawner(|spawner| {
+//!         let [home, work, detour] = [(); 3].map(|()| spawner.spawn(Intersection).entity());
+//!         spawner.spawn(Road::new(home, work, 5.0, 10.0));
+//!         spawner.spawn(Road::new(home, detour, 4.0, 50.0));
+//!         spawner.spawn(Road::new(detour, work, 4.0, 50.0));
+//!         for _ in 0..40
+//!         {
+//!             spawner.spawn(Vehicle::new(home, work));
+//!         }
+//!     })
+//!     .build();
+//!
+//! simulation.run(20);
+//! let outputs = simulation.get_resource::<TrafficOutputs>().unwrap();
+//! let arrivals: usize = outputs.history().iter().map(|metrics| metrics.arrivals).sum();
+//! assert_eq!(arrivals, 40);
+//! ```
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+use bevy::{ecs::entity::EntityHashMap, prelude::*};
+
+use crate::{MetricsHistory, StepNumber};
+
+/// Component of a one-way road from one intersection to another.
+///
+/// See the [module documentation](self) for how the travel time depends on congestion.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Road
+{
+    from: Entity,
+    to: Entity,
+    free_flow_time: f64,
+    capacity: f64,
+    occupancy: usize,
+    entered: usize,
+}
+
+impl Road
+{
+    /// Creates a road from the intersection `from` to `to`, which takes `free_flow_time` to
+    /// travel without any congestion, and fits `capacity` vehicles before its travel time
+    /// grows sharply.
+    ///
+    /// # Panics
+    ///
+    /// If `free_flow_time` is not positive and finite, or `capacity` is negative or not finite.
+    #[must_use]
+    pub fn new(from: Entity, to: Entity, free_flow_time: f64, capacity: f64) -> Self
+    {
+        assert!(
+            free_flow_time.is_finite() && free_flow_time > 0.0,
+            "invalid free-flow travel time: {free_flow_time}"
+        );
+
+        let mut road = Self {
+            from,
+            to,
+            free_flow_time,
+            capacity: 0.0,
+            occupancy: 0,
+            entered: 0,
+        };
+        road.set_capacity(capacity);
+        road
+    }
+
+    /// Creates the two roads of a two-way street between the intersections `a` and `b`,
+    /// to be spawned as entities of their own.
+    ///
+    /// # Panics
+    ///
+    /// If `free_flow_time` is not positive and finite, or `capacity` is negative or not finite.
+    #[must_use]
+    pub fn two_way(a: Entity, b: Entity, free_flow_time: f64, capacity: f64) -> [Self; 2]
+    {
+        [
+            Self::new(a, b, free_flow_time, capacity),
+            Self::new(b, a, free_flow_time, capacity),
+        ]
+    }
+
+    /// The intersection the road starts from.
+    #[must_use]
+    pub const fn from(&self) -> Entity
+    {
+        self.from
+    }
+
+    /// The intersection the road leads to.
+    #[must_use]
+    pub const fn to(&self) -> Entity
+    {
+        self.to
+    }
+
+    /// The time it takes to travel the road without any congestion.
+    #[must_use]
+    pub const fn free_flow_time(&self) -> f64
+    {
+        self.free_flow_time
+    }
+
+    /// The number of vehicles the road fits before its travel time grows sharply.
+    #[must_use]
+    pub const fn capacity(&self) -> f64
+    {
+        self.capacity
+    }
+
+    /// Sets the capacity of the road, where `0` closes it.
+    ///
+    /// Vehicles already on a closed road still reach its end.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is negative or not finite.
+    pub fn set_capacity(&mut self, capacity: f64)
+    {
+        assert!(
+            capacity.is_finite() && capacity >= 0.0,
+            "invalid road capacity: {capacity}"
+        );
+        self.capacity = capacity;
+    }
+
+    /// Checks whether the road is closed, i.e. its capacity is `0`.
+    #[must_use]
+    pub fn is_closed(&self) -> bool
+    {
+        self.capacity == 0.0
+    }
+
+    /// The number of vehicles on the road at the end of the latest step.
+    #[must_use]
+    pub const fn occupancy(&self) -> usize
+    {
+        self.occupancy
+    }
+
+    /// The number of vehicles that entered the road during the latest step.
+    #[must_use]
+    pub const fn entered(&self) -> usize
+    {
+        self.entered
+    }
+
+    /// The ratio of the number of vehicles on the road to its capacity, which is infinite for
+    /// closed roads.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn volume_capacity_ratio(&self) -> f64
+    {
+        self.occupancy as f64 / self.capacity
+    }
+
+    /// The time it takes to travel the road given its current occupancy, which is infinite for
+    /// closed roads.
+    #[must_use]
+    pub fn travel_time(&self) -> f64
+    {
+        self.travel_time_with(self.occupancy)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn travel_time_with(&self, occupancy: usize) -> f64
+    {
+        if self.is_closed()
+        {
+            return f64::INFINITY;
+        }
+
+        let ratio = occupancy as f64 / self.capacity;
+        self.free_flow_time * 0.15f64.mul_add(ratio.powi(4), 1.0)
+    }
+}
+
+/// Component of a vehicle travelling from an origin to a destination over the roads.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Vehicle
+{
+    origin: Entity,
+    destination: Entity,
+    location: Entity,
+    road: Option<Entity>,
+    /// The time left until the end of the current road.
+    remaining: f64,
+    /// The roads of the planned route, starting from the current one.
+    route: Vec<Entity>,
+    elapsed: f64,
+}
+
+impl Vehicle
+{
+    /// Creates a vehicle at the intersection `origin`, which starts travelling to the
+    /// intersection `destination` on the next step.
+    #[must_use]
+    pub const fn new(origin: Entity, destination: Entity) -> Self
+    {
+        Self {
+            origin,
+            destination,
+            location: origin,
+            road: None,
+            remaining: 0.0,
+            route: Vec::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    /// The intersection the vehicle started from.
+    #[must_use]
+    pub const fn origin(&self) -> Entity
+    {
+        self.origin
+    }
+
+    /// The intersection the vehicle is travelling to.
+    #[must_use]
+    pub const fn destination(&self) -> Entity
+    {
+        self.destination
+    }
+
+    /// The latest intersection the vehicle has passed through.
+    #[must_use]
+    pub const fn location(&self) -> Entity
+    {
+        self.location
+    }
+
+    /// The road the vehicle is on, or `None` if it is waiting at an intersection.
+    #[must_use]
+    pub const fn road(&self) -> Option<Entity>
+    {
+        self.road
+    }
+
+    /// The roads of the route the vehicle planned at the latest intersection, starting from
+    /// the current one, which may change at the next intersection.
+    #[must_use]
+    pub fn route(&self) -> &[Entity]
+    {
+        &self.route
+    }
+
+    /// The time the vehicle has been travelling for, including any time spent waiting.
+    #[must_use]
+    pub const fn elapsed(&self) -> f64
+    {
+        self.elapsed
+    }
+}
+
+/// Event emitted when a [`Vehicle`] reaches its destination, right before it is despawned.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct VehicleArrived
+{
+    pub vehicle: Entity,
+    pub origin: Entity,
+    pub destination: Entity,
+
+    /// The time the vehicle took to reach its destination, including any time spent waiting.
+    pub travel_time: f64,
+}
+
+/// The state of the road network at the end of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TrafficMetrics
+{
+    /// The number of the step, as read from [`StepNumber`].
+    pub step: usize,
+
+    /// The number of vehicles on roads.
+    pub en_route: usize,
+
+    /// The number of vehicles waiting at an intersection without any route to their destination.
+    pub stranded: usize,
+
+    /// The number of vehicles that reached their destination during the step.
+    pub arrivals: usize,
+
+    /// The mean travel time of the vehicles that reached their destination during the step,
+    /// or `None` if there were none.
+    pub mean_travel_time: Option<f64>,
+
+    /// The number of open roads with more vehicles than their capacity.
+    pub congested_roads: usize,
+
+    /// The largest ratio of vehicles to capacity over all open roads.
+    pub max_volume_capacity_ratio: f64,
+}
+
+/// Resource holding the [`TrafficMetrics`] of every step.
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<TrafficOutputs>`] arguments.
+pub type TrafficOutputs = MetricsHistory<TrafficMetrics>;
+
+/// Plugin that moves the vehicles over the roads, added with
+/// [`crate::SimulationBuilder::add_traffic`].
+pub(crate) struct TrafficPlugin;
+
+impl Plugin for TrafficPlugin
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<TrafficOutputs>()
+            .add_event::<VehicleArrived>()
+            .add_systems(PostUpdate, traffic_system);
+    }
+}
+
+/// A travel time ordered for the queue of the shortest path search.
+#[derive(Debug, Clone, Copy)]
+struct Time(f64);
+
+impl PartialEq for Time
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Time {}
+
+impl PartialOrd for Time
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering>
+    {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Time
+{
+    fn cmp(&self, other: &Self) -> Ordering
+    {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// The roads of the network during a step, along with their current occupancy.
+struct Roads
+{
+    roads: EntityHashMap<(Road, usize)>,
+    outgoing: EntityHashMap<Vec<Entity>>,
+}
+
+impl Roads
+{
+    fn travel_time(&self, road: Entity) -> f64
+    {
+        let (road, occupancy) = &self.roads[&road];
+        road.travel_time_with(*occupancy)
+    }
+
+    /// The roads of the fastest route between two intersections under the current travel times,
+    /// found with Dijkstra's algorithm, or `None` if there is no open route.
+    fn fastest_route(&self, from: Entity, to: Entity) -> Option<Vec<Entity>>
+    {
+        let mut times: EntityHashMap<f64> = EntityHashMap::default();
+        let mut previous: EntityHashMap<Entity> = EntityHashMap::default();
+        let mut queue = BinaryHeap::new();
+        times.insert(from, 0.0);
+        queue.push(Reverse((Time(0.0), from)));
+
+        while let Some(Reverse((Time(time), node))) = queue.pop()
+        {
+            if node == to
+            {
+                break;
+            }
+            if time > times[&node]
+            {
+                continue;
+            }
+
+            for &road in self.outgoing.get(&node).into_iter().flatten()
+            {
+                let next = self.roads[&road].0.to;
+                let arrival = time + self.travel_time(road);
+                if arrival.is_finite() && times.get(&next).is_none_or(|&best| arrival < best)
+                {
+                    times.insert(next, arrival);
+                    previous.insert(next, road);
+                    queue.push(Reverse((Time(arrival), next)));
+                }
+            }
+        }
+
+        let mut route = Vec::new();
+        let mut node = to;
+        while node != from
+        {
+            let road = *previous.get(&node)?;
+            route.push(road);
+            node = self.roads[&road].0.from;
+        }
+        route.reverse();
+        Some(route)
+    }
+}
+
+/// Where a vehicle is at the end of a step.
+enum Progress
+{
+    EnRoute,
+    Stranded,
+    Arrived,
+}
+
+impl Roads
+{
+    /// Advances a vehicle by one unit of time, moving it onto the next road of its route at
+    /// every intersection it reaches.
+    fn advance(&mut self, vehicle: &mut Vehicle, entered: &mut EntityHashMap<usize>) -> Progress
+    {
+        let mut budget = 1.0;
+        loop
+        {
+            if let Some(road) = vehicle.road
+            {
+                if vehicle.remaining > budget
+                {
+                    vehicle.remaining -= budget;
+                    vehicle.elapsed += budget;
+                    return Progress::EnRoute;
+                }
+
+                budget -= vehicle.remaining;
+                vehicle.elapsed += vehicle.remaining;
+                if let Some((road, occupancy)) = self.roads.get_mut(&road)
+                {
+                    *occupancy -= 1;
+                    vehicle.location = road.to;
+                }
+                vehicle.road = None;
+                vehicle.remaining = 0.0;
+                vehicle.route.clear();
+            }
+
+            if vehicle.location == vehicle.destination
+            {
+                return Progress::Arrived;
+            }
+
+            let Some(route) = self.fastest_route(vehicle.location, vehicle.destination)
+            else
+            {
+                vehicle.elapsed += budget;
+                return Progress::Stranded;
+            };
+
+            let next = route[0];
+            vehicle.remaining = self.travel_time(next);
+            vehicle.road = Some(next);
+            vehicle.route = route;
+            if let Some((_, occupancy)) = self.roads.get_mut(&next)
+            {
+                *occupancy += 1;
+            }
+            *entered.entry(next).or_default() += 1;
+        }
+    }
+}
+
+/// System that advances every vehicle by one unit of time, and records the metrics of the step.
+#[allow(clippy::cast_precision_loss)]
+fn traffic_system(
+    mut commands: Commands,
+    mut roads: Query<(Entity, &mut Road)>,
+    mut vehicles: Query<(Entity, &mut Vehicle)>,
+    mut outputs: ResMut<TrafficOutputs>,
+    mut arrivals: EventWriter<VehicleArrived>,
+    step_number: Res<StepNumber>,
+)
+{
+    let mut network = Roads {
+        roads: EntityHashMap::default(),
+        outgoing: EntityHashMap::default(),
+    };
+    for (entity, road) in &roads
+    {
+        network.roads.insert(entity, (road.clone(), 0));
+        network.outgoing.entry(road.from).or_default().push(entity);
+    }
+    for (_, vehicle) in &vehicles
+    {
+        if let Some(road) = vehicle.road
+            && let Some((_, occupancy)) = network.roads.get_mut(&road)
+        {
+            *occupancy += 1;
+        }
+    }
+
+    let mut metrics = TrafficMetrics {
+        step: **step_number,
+        ..default()
+    };
+    let mut entered = EntityHashMap::default();
+    let mut total_travel_time = 0.0;
+    for (entity, mut vehicle) in &mut vehicles
+    {
+        // vehicles on despawned roads are back at the intersection they left from
+        if vehicle
+            .road
+            .is_some_and(|road| !network.roads.contains_key(&road))
+        {
+            vehicle.road = None;
+            vehicle.remaining = 0.0;
+        }
+
+        match network.advance(&mut vehicle, &mut entered)
+        {
+            Progress::EnRoute => metrics.en_route += 1,
+            Progress::Stranded => metrics.stranded += 1,
+            Progress::Arrived =>
+            {
+                arrivals.write(VehicleArrived {
+                    vehicle: entity,
+                    origin: vehicle.origin,
+                    destination: vehicle.destination,
+                    travel_time: vehicle.elapsed,
+                });
+                commands.entity(entity).despawn();
+                metrics.arrivals += 1;
+                total_travel_time += vehicle.elapsed;
+            }
+        }
+    }
+
+    for (entity, mut road) in &mut roads
+    {
+        road.occupancy = network.roads[&entity].1;
+        road.entered = entered.get(&entity).copied().unwrap_or_default();
+        if !road.is_closed()
+        {
+            let ratio = road.volume_capacity_ratio();
+            if ratio > 1.0
+            {
+                metrics.congested_roads += 1;
+            }
+            metrics.max_volume_capacity_ratio = metrics.max_volume_capacity_ratio.max(ratio);
+        }
+    }
+
+    if metrics.arrivals > 0
+    {
+        metrics.mean_travel_time = Some(total_travel_time / metrics.arrivals as f64);
+    }
+    outputs.push(metrics.step, metrics);
+}
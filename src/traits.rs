@@ -17,8 +17,19 @@ pub trait SampleAggregate<Out>: Component + Sized
     /// * Guaranteed to not be empty.
     /// * In random arbitrary order.
     fn sample_aggregate(components: &[&Self]) -> Out;
+
+    /// Merges the aggregates of disjoint batches of components into the aggregate of all of them.
+    ///
+    /// Each partial aggregate is given along with the number of components in its batch,
+    /// with the batches in a fixed order.
+    /// When provided, large populations may be sampled in parallel, see [`ParallelSampling`].
+    /// Defaults to `None`, in which case all components are always sampled on a single thread.
+    const COMBINE: Option<Combiner<Out>> = None;
 }
 
+/// Function merging partial aggregates of type `Out`, see [`SampleAggregate::COMBINE`].
+pub type Combiner<Out> = fn(Vec<(Out, usize)>) -> Out;
+
 /// Implements the sampling of a value from a component in the simulation.
 ///
 /// Needed for:
@@ -44,6 +55,20 @@ pub trait Sample<Out>: Component + Sized
 /// Automatically implemented for any type that is [`Component`] + [`Eq`] + [`Hash`]
 pub trait Identifier: Component + Eq + Hash {}
 
+/// A reusable part of a simulation, such as a prefab model, which sets up its own resources,
+/// spawners, systems and outputs on a [`SimulationBuilder`].
+///
+/// Needed for:
+/// * [`SimulationBuilder::add_module`]
+///
+/// See [`crate::epidemic::EpidemicModule`] for an example.
+pub trait Module
+{
+    /// Adds the module to the simulation being built.
+    #[must_use]
+    fn build(self, builder: SimulationBuilder) -> SimulationBuilder;
+}
+
 // ===========================================================
 //              Blanket implementations
 // ===========================================================
@@ -87,3 +112,69 @@ blanket_impl_sample!(i64);
 blanket_impl_sample!(i128);
 blanket_impl_sample!(f32);
 blanket_impl_sample!(f64);
+
+/// Implements the conversion of sampled values into [`f64`], for accumulating them.
+///
+/// Implemented for all primitive numeric types, with an `as` cast, so that integer values beyond
+/// 2<sup>53</sup> are rounded.
+///
+/// Needed for:
+/// * [`SimulationBuilder::track_incremental_aggregate`]
+pub trait AsF64
+{
+    /// Converts the value into an [`f64`].
+    fn as_f64(&self) -> f64;
+}
+
+macro_rules! blanket_impl_as_f64 {
+    ($($t: ty),*) => {
+        $(
+            impl AsF64 for $t
+            {
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+                fn as_f64(&self) -> f64
+                {
+                    *self as f64
+                }
+            }
+        )*
+    };
+}
+blanket_impl_as_f64!(
+    usize, u8, u16, u32, u64, u128, isize, i8, i16, i32, i64, i128, f32, f64
+);
+
+/// Implements the conversion of sampled values into a column of an Arrow record batch.
+///
+/// Implemented for all primitive numeric types, [`bool`] and [`String`].
+/// Identifier types may implement it as well, typically by converting to one of these,
+/// in order to be exported with [`Simulation::export_record_batch`].
+///
+/// Needed for:
+/// * [`Simulation::export_record_batch`]
+/// * [`TimeSeries::to_record_batch`]
+#[cfg(feature = "arrow")]
+pub trait ArrowValue: Sized
+{
+    /// The Arrow data type of the column.
+    fn data_type() -> arrow_schema::DataType;
+
+    /// Converts the values into an Arrow array of type [`Self::data_type`].
+    fn into_array(values: Vec<Self>) -> arrow_array::ArrayRef;
+}
+
+/// Implements the conversion of sampled values into a column of a Polars data frame.
+///
+/// Implemented for all primitive numeric types, [`bool`] and [`String`].
+/// Identifier types may implement it as well, typically by converting to one of these,
+/// in order to be exported with [`Simulation::sample_table`].
+///
+/// Needed for:
+/// * [`Simulation::sample_table`]
+/// * [`TimeSeries::to_dataframe`]
+#[cfg(feature = "polars")]
+pub trait PolarsValue: Sized
+{
+    /// Converts the values into a Polars column with the given `name`.
+    fn into_column(name: &str, values: Vec<Self>) -> polars::prelude::Column;
+}
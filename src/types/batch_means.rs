@@ -0,0 +1,254 @@
+use super::ensemble_result::assert_level;
+use crate::{ConfidenceInterval, statistics::student_t_quantile};
+
+/// The number of batches that the batch size is first chosen for.
+const MAX_BATCHES: usize = 64;
+
+/// The number of batches that the batch size is never grown beyond.
+const MIN_BATCHES: usize = 10;
+
+/// The lag-one autocorrelation of the batch means below which they are taken as independent.
+const AUTOCORRELATION_THRESHOLD: f64 = 0.2;
+
+/// The size of the batches over which MSER looks for the end of the initial transient.
+const MSER_BATCH_SIZE: usize = 5;
+
+/// A batch-means estimate of the steady-state value of a single long run, such as one of its
+/// recorded time series.
+///
+/// The consecutive values of a time series are correlated, so their plain standard error
+/// understates the uncertainty of their mean. Instead, the series is split into batches that are
+/// long enough for their means to be nearly independent, and the confidence interval of the
+/// steady-state value is taken from the batch means, as if every batch were a replication.
+///
+/// Before that, the initial transient, during which the run has not yet reached its steady state,
+/// is detected with the MSER-5 rule and left out: the truncation point is the one that minimizes
+/// the standard error of the mean of the rest of the series, as estimated from batches of five.
+///
+/// The batch size is then chosen automatically, starting from the size that splits the rest of
+/// the series into 64 batches, and doubling it as long as the batch means are correlated and at
+/// least 10 batches remain.
+///
+/// Constructed with [`Self::new`], or from a [`crate::TimeSeries`] with
+/// [`crate::TimeSeries::batch_means`].
+///
+/// ```
+/// # use incerto::prelude::*;
+/// use rand::{Rng, SeedableRng};
+///
+/// // a queue that starts empty, then fluctuates around 10 with a lot of autocorrelation
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+/// let mut length = 0.0_f64;
+/// let series = (0..5000).map(|_| {
+///     length = length.mul_add(0.9, 1.0 + rng.random_range(-1.0..1.0));
+///     length
+/// });
+///
+/// let means = BatchMeans::new(series).unwrap();
+/// assert!(means.truncation() > 0 && means.truncation() < 500);
+///
+/// let interval = means.confidence_interval(0.99);
+/// assert!(interval.lower < 10.0 && 10.0 < interval.upper);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchMeans
+{
+    truncation: usize,
+    batch_size: usize,
+    means: Vec<f64>,
+}
+
+impl BatchMeans
+{
+    /// Computes the batch-means estimate of the given series, with its initial transient
+    /// detected by MSER-5 and the batch size chosen automatically.
+    ///
+    /// Returns `None` if fewer than 10 values remain after the initial transient.
+    ///
+    /// # Panics
+    ///
+    /// If any of the values is not finite.
+    #[must_use]
+    pub fn new(values: impl IntoIterator<Item = f64>) -> Option<Self>
+    {
+        let values = values.into_iter().collect::<Vec<_>>();
+        for value in &values
+        {
+            assert!(value.is_finite(), "invalid value: {value}");
+        }
+
+        let truncation = Self::mser_truncation(&values);
+        let steady = &values[truncation..];
+        if steady.len() < MIN_BATCHES
+        {
+            return None;
+        }
+
+        let mut batch_size = (steady.len() / MAX_BATCHES).max(1);
+        let mut batch_means = batch(steady, batch_size);
+        while lag_one_autocorrelation(&batch_means) >= AUTOCORRELATION_THRESHOLD
+            && steady.len() / (2 * batch_size) >= MIN_BATCHES
+        {
+            batch_size *= 2;
+            batch_means = batch(steady, batch_size);
+        }
+
+        Some(Self {
+            truncation,
+            batch_size,
+            means: batch_means,
+        })
+    }
+
+    /// The number of values at the start of `values` that make up the initial transient,
+    /// according to the MSER-5 rule.
+    ///
+    /// The truncation point is searched for within the first half of the values, and is a
+    /// multiple of `5`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mser_truncation(values: &[f64]) -> usize
+    {
+        let batches = batch(
+            &values[..values.len() - values.len() % MSER_BATCH_SIZE],
+            MSER_BATCH_SIZE,
+        );
+        if batches.len() < 2
+        {
+            return 0;
+        }
+
+        // the sums and the sums of squares of the batches from every one onwards
+        let mut sum = 0.0;
+        let mut squares = 0.0;
+        let mut best = (f64::INFINITY, 0);
+        for (start, value) in batches.iter().enumerate().rev()
+        {
+            sum += value;
+            squares += value * value;
+            if start > batches.len() / 2
+            {
+                continue;
+            }
+
+            let count = (batches.len() - start) as f64;
+            let statistic = (squares - sum * sum / count) / (count * count);
+            if statistic <= best.0
+            {
+                best = (statistic, start);
+            }
+        }
+        best.1 * MSER_BATCH_SIZE
+    }
+
+    /// The number of values left out as the initial transient.
+    ///
+    /// For a [`crate::TimeSeries`], the steady state is reached at the step found at this index
+    /// of [`crate::TimeSeries::time_slice`].
+    #[must_use]
+    pub const fn truncation(&self) -> usize
+    {
+        self.truncation
+    }
+
+    /// The number of values in every batch.
+    #[must_use]
+    pub const fn batch_size(&self) -> usize
+    {
+        self.batch_size
+    }
+
+    /// The mean of every batch, in order.
+    ///
+    /// The batches end at the last value of the series, so values that do not fill a batch
+    /// right after the initial transient are left out.
+    #[must_use]
+    pub fn batch_means(&self) -> &[f64]
+    {
+        &self.means
+    }
+
+    /// The lag-one autocorrelation of the batch means, which is close to `0` when the batches
+    /// are long enough to be independent.
+    #[must_use]
+    pub fn autocorrelation(&self) -> f64
+    {
+        lag_one_autocorrelation(&self.means)
+    }
+
+    /// The estimate of the steady-state value, i.e. the mean of the batch means.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean(&self) -> f64
+    {
+        self.means.iter().sum::<f64>() / self.means.len() as f64
+    }
+
+    /// The standard error of [`Self::mean`], from the variance of the batch means.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn standard_error(&self) -> f64
+    {
+        let mean = self.mean();
+        let count = self.means.len() as f64;
+        let squares = self.means.iter().map(|value| (value - mean).powi(2));
+        (squares.sum::<f64>() / (count - 1.0) / count).sqrt()
+    }
+
+    /// The confidence interval of the steady-state value at the given `level`, e.g. `0.95`,
+    /// from the Student's t-distribution with one degree of freedom less than the number of
+    /// batches.
+    ///
+    /// # Panics
+    ///
+    /// If `level` is not within `(0, 1)`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn confidence_interval(&self, level: f64) -> ConfidenceInterval
+    {
+        assert_level(level);
+        let estimate = self.mean();
+        let degrees_of_freedom = (self.means.len() - 1) as f64;
+        let margin =
+            student_t_quantile(0.5 + level / 2.0, degrees_of_freedom) * self.standard_error();
+
+        ConfidenceInterval {
+            estimate,
+            lower: estimate - margin,
+            upper: estimate + margin,
+            level,
+        }
+    }
+}
+
+/// The means of consecutive batches of `size` values, aligned to the end of `values`.
+#[allow(clippy::cast_precision_loss)]
+fn batch(values: &[f64], size: usize) -> Vec<f64>
+{
+    let aligned = &values[values.len() % size..];
+    aligned
+        .chunks_exact(size)
+        .map(|chunk| chunk.iter().sum::<f64>() / size as f64)
+        .collect()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn lag_one_autocorrelation(values: &[f64]) -> f64
+{
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>();
+    if variance == 0.0
+    {
+        return 0.0;
+    }
+
+    let covariance = values
+        .windows(2)
+        .map(|pair| (pair[0] - mean) * (pair[1] - mean))
+        .sum::<f64>();
+    covariance / variance
+}
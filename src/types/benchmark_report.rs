@@ -0,0 +1,74 @@
+use std::{fmt, time::Duration};
+
+/// The timings of a simulation run, as returned by [`crate::Simulation::benchmark`].
+///
+/// The time spent in each step is broken down into the phases of the step, so that the
+/// impact of changes to a model can be attributed to the systems responsible for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchmarkReport
+{
+    /// The number of steps that were run.
+    pub steps: usize,
+
+    /// The total time spent running the steps.
+    pub total: Duration,
+
+    /// The time spent in user-defined systems, added with [`crate::SimulationBuilder::add_systems`].
+    pub user_systems: Duration,
+
+    /// The time spent maintaining indices, such as those of spatial grids, before the user systems.
+    pub index_maintenance: Duration,
+
+    /// The time spent after the user systems, recording time series and other samples.
+    pub sampling: Duration,
+
+    /// The time spent in the rest of each step, e.g. advancing the step counter and running
+    /// step-end hooks.
+    pub other: Duration,
+}
+
+impl BenchmarkReport
+{
+    /// The average number of steps that were run per second.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn steps_per_second(&self) -> f64
+    {
+        self.steps as f64 / self.total.as_secs_f64()
+    }
+
+    /// The average time it took to run a single step.
+    ///
+    /// Returns [`Duration::ZERO`] if no steps were run.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean_step_latency(&self) -> Duration
+    {
+        if self.steps == 0
+        {
+            return Duration::ZERO;
+        }
+
+        self.total.div_f64(self.steps as f64)
+    }
+}
+
+impl fmt::Display for BenchmarkReport
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        writeln!(
+            f,
+            "{} steps in {:?} ({:.1} steps/s, {:?} per step)",
+            self.steps,
+            self.total,
+            self.steps_per_second(),
+            self.mean_step_latency()
+        )?;
+        writeln!(f, "user systems: {:?}", self.user_systems)?;
+        writeln!(f, "index maintenance: {:?}", self.index_maintenance)?;
+        writeln!(f, "sampling: {:?}", self.sampling)?;
+        write!(f, "other: {:?}", self.other)
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+/// Resource holding a control variate of a simulation run, i.e. a quantity sampled during the
+/// run whose expectation is known exactly, such as the mean of the random draws that drive it.
+///
+/// Added with [`crate::SimulationBuilder::add_control_variate`], and recorded by user-defined
+/// systems using [`ResMut<ControlVariate>`] arguments.
+/// When the outcomes of replicas that hold a control variate are collected into an
+/// [`crate::EnsembleResult`], the control variates are collected along with them, and used to
+/// reduce the variance of the estimate.
+/// The more the control variate correlates with the outcome, the larger the reduction.
+///
+/// ```
+/// # use incerto::prelude::*;
+/// use rand::Rng;
+///
+/// #[derive(Component)]
+/// struct Stock(f64);
+///
+/// let replicas = Simulation::replicate_seeded(42, 50, 100, |_| {
+///     SimulationBuilder::new()
+///         // the sum of 100 standard normal shocks has an expectation of 0
+///         .add_control_variate(0.0)
+///         .add_entity_spawner(|spawner| {
+///             spawner.spawn(Stock(100.0));
+///         })
+///         .add_systems(
+///             |mut stocks: Query<&mut Stock>,
+///              mut rng: ResMut<SimRng>,
+///              mut control: ResMut<ControlVariate>| {
+///                 let shock: f64 = rng.sample(rand_distr::StandardNormal);
+///                 control.add(shock);
+///                 for mut stock in &mut stocks
+///                 {
+///                     stock.0 *= 1.0 + 0.01 * shock;
+///                 }
+///             },
+///         )
+/// });
+///
+/// let ensemble = EnsembleResult::from_replicas(&replicas, |simulation| {
+///     simulation.iter::<Stock>().next().unwrap().0
+/// });
+/// let plain = EnsembleResult::new(ensemble.values().iter().copied());
+/// assert!(ensemble.standard_error().unwrap() < plain.standard_error().unwrap());
+/// ```
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlVariate
+{
+    expectation: f64,
+    value: f64,
+}
+
+impl ControlVariate
+{
+    /// Creates a control variate with the given known `expectation`, and a value of `0`.
+    ///
+    /// # Panics
+    ///
+    /// If `expectation` is not finite.
+    #[must_use]
+    pub fn new(expectation: f64) -> Self
+    {
+        assert!(
+            expectation.is_finite(),
+            "invalid control variate expectation: {expectation}"
+        );
+        Self {
+            expectation,
+            value: 0.0,
+        }
+    }
+
+    /// The known expectation of the control variate.
+    #[must_use]
+    pub const fn expectation(&self) -> f64
+    {
+        self.expectation
+    }
+
+    /// The value sampled in this run so far.
+    #[must_use]
+    pub const fn value(&self) -> f64
+    {
+        self.value
+    }
+
+    /// Sets the value sampled in this run.
+    pub const fn set(&mut self, value: f64)
+    {
+        self.value = value;
+    }
+
+    /// Adds to the value sampled in this run, e.g. to sum up the random draws of every step.
+    pub fn add(&mut self, amount: f64)
+    {
+        self.value += amount;
+    }
+}
@@ -0,0 +1,190 @@
+use crate::Simulation;
+
+/// The Gelman-Rubin diagnostic of whether the time series of several replications of an
+/// experiment have settled into the same steady state.
+///
+/// Each series is split in half, and the variance of the values within every half is compared
+/// to the variance between the means of the halves. When all the replications have reached the
+/// same steady state the two agree, and the potential scale reduction factor, [`Self::r_hat`],
+/// is close to `1`. A replication that is still drifting, or stuck in a different state from the
+/// others, inflates the variance between the halves, and with it the factor. Splitting the
+/// series also catches a trend that all the replications share.
+///
+/// A factor below `1.01` is a strict sign of convergence, and below `1.1` a lenient one. The
+/// warm-up period before the steady state should be left out of the series, e.g. by discarding
+/// their first half.
+///
+/// Constructed with [`Self::new`], or from the time series of the replicas returned by
+/// [`Simulation::replicate`] with [`Self::from_replicas`].
+///
+/// ```
+/// # use incerto::prelude::*;
+/// // four replications that settle at the same level, and one that settles elsewhere
+/// let settled = |offset: f64| (0..100).map(move |step| offset + (f64::from(step) * 1.3).sin());
+/// let chains = [0.0, 0.1, -0.1, 0.05].map(|offset| settled(offset).collect::<Vec<_>>());
+///
+/// let diagnostic = ConvergenceDiagnostic::new(chains.clone()).unwrap();
+/// assert!(diagnostic.has_converged(1.1));
+///
+/// let stuck = chains.into_iter().chain([settled(3.0).collect()]);
+/// let diagnostic = ConvergenceDiagnostic::new(stuck).unwrap();
+/// assert!(!diagnostic.has_converged(1.1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConvergenceDiagnostic
+{
+    r_hat: f64,
+    within_variance: f64,
+    between_variance: f64,
+    chains: usize,
+    length: usize,
+}
+
+impl ConvergenceDiagnostic
+{
+    /// Computes the diagnostic over the given series, one for every replication.
+    ///
+    /// Series of different lengths are truncated to the shortest one.
+    ///
+    /// Returns `None` if there are fewer than two series, or the shortest one has fewer than
+    /// four values.
+    ///
+    /// # Panics
+    ///
+    /// If any of the values is not finite.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(series: impl IntoIterator<Item = impl IntoIterator<Item = f64>>) -> Option<Self>
+    {
+        let series = series
+            .into_iter()
+            .map(|values| values.into_iter().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for value in series.iter().flatten()
+        {
+            assert!(value.is_finite(), "invalid value: {value}");
+        }
+
+        let shortest = series.iter().map(Vec::len).min().unwrap_or_default();
+        if series.len() < 2 || shortest < 4
+        {
+            return None;
+        }
+
+        // the first and last halves of every series, leaving out the middle value of odd lengths
+        let length = shortest / 2;
+        let halves = series
+            .iter()
+            .flat_map(|values| [&values[..length], &values[shortest - length..shortest]])
+            .collect::<Vec<_>>();
+
+        let n = length as f64;
+        let chains = halves.len() as f64;
+        let means = halves
+            .iter()
+            .map(|half| half.iter().sum::<f64>() / n)
+            .collect::<Vec<_>>();
+        let grand_mean = means.iter().sum::<f64>() / chains;
+
+        let between_variance = n * means
+            .iter()
+            .map(|mean| (mean - grand_mean).powi(2))
+            .sum::<f64>()
+            / (chains - 1.0);
+        let within_variance = halves
+            .iter()
+            .zip(&means)
+            .map(|(half, mean)| {
+                half.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (n - 1.0)
+            })
+            .sum::<f64>()
+            / chains;
+
+        let pooled_variance = (n - 1.0).mul_add(within_variance, between_variance) / n;
+        let r_hat = if within_variance > 0.0
+        {
+            (pooled_variance / within_variance).sqrt()
+        }
+        else if between_variance > 0.0
+        {
+            // constant series at different levels
+            f64::INFINITY
+        }
+        else
+        {
+            1.0
+        };
+
+        Some(Self {
+            r_hat,
+            within_variance,
+            between_variance,
+            chains: series.len(),
+            length: shortest,
+        })
+    }
+
+    /// Computes the diagnostic over the series that `series` returns for every one of the given
+    /// replicas, such as one of their recorded time series.
+    ///
+    /// See [`Self::new`].
+    ///
+    /// # Panics
+    ///
+    /// If any of the values is not finite.
+    #[must_use]
+    pub fn from_replicas(
+        replicas: &[Simulation],
+        series: impl Fn(&Simulation) -> Vec<f64>,
+    ) -> Option<Self>
+    {
+        Self::new(replicas.iter().map(series))
+    }
+
+    /// The potential scale reduction factor, i.e. the factor by which the spread of the values
+    /// could shrink if the series were run for longer.
+    ///
+    /// Tends to `1` as the series converge to the same steady state.
+    #[must_use]
+    pub const fn r_hat(&self) -> f64
+    {
+        self.r_hat
+    }
+
+    /// Whether [`Self::r_hat`] is below the given `threshold`.
+    #[must_use]
+    pub fn has_converged(&self, threshold: f64) -> bool
+    {
+        self.r_hat < threshold
+    }
+
+    /// The mean of the variances of the values within every half series.
+    #[must_use]
+    pub const fn within_variance(&self) -> f64
+    {
+        self.within_variance
+    }
+
+    /// The variance between the means of the half series, scaled by their length.
+    #[must_use]
+    pub const fn between_variance(&self) -> f64
+    {
+        self.between_variance
+    }
+
+    /// The number of series.
+    #[must_use]
+    pub const fn chains(&self) -> usize
+    {
+        self.chains
+    }
+
+    /// The number of values of every series that were used, i.e. the length of the shortest
+    /// one.
+    #[must_use]
+    pub const fn length(&self) -> usize
+    {
+        self.length
+    }
+}
@@ -0,0 +1,556 @@
+use std::fmt;
+
+use rand::Rng;
+
+use crate::{
+    ControlVariate, KernelDensity, PeaksOverThreshold, Simulation, statistics::student_t_quantile,
+};
+
+/// The values of a scalar outcome across the replications of an experiment, such as the final
+/// size of an epidemic in each replica returned by [`Simulation::replicate`].
+///
+/// Summarizes the values with an estimate of the expected outcome and its uncertainty, so that
+/// the estimate is reported along with a [`ConfidenceInterval`], either from the Student's
+/// t-distribution with [`Self::confidence_interval`], or by resampling with
+/// [`Self::bootstrap_confidence_interval`].
+/// The estimate can also be watched as the replicas complete, with
+/// [`Simulation::replicate_monitored`].
+///
+/// The estimate is the mean of the values, unless a [`ControlVariate`] was sampled along with
+/// every value, in which case it is the control variate estimator: the mean corrected by the
+/// deviation of the control variates from their known expectation, scaled by their estimated
+/// regression coefficient on the outcome.
+///
+/// ```
+/// # use incerto::prelude::*;
+/// use rand::Rng;
+///
+/// #[derive(Component)]
+/// struct Rabbit;
+///
+/// let replicas = Simulation::replicate_seeded(42, 20, 10, |_| {
+///     SimulationBuilder::new().add_seeded_entity_spawner(|spawner, rng| {
+///         for _ in 0..rng.random_range(0..100)
+///         {
+///             spawner.spawn(Rabbit);
+///         }
+///     })
+/// });
+///
+/// let ensemble = EnsembleResult::from_replicas(&replicas, |simulation| {
+///     simulation.iter::<Rabbit>().count() as f64
+/// });
+/// let interval = ensemble.confidence_interval(0.95).unwrap();
+/// assert!(interval.lower < interval.estimate && interval.estimate < interval.upper);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnsembleResult
+{
+    values: Vec<f64>,
+    control: Option<Controls>,
+}
+
+/// The control variates sampled along with the values of an [`EnsembleResult`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Controls
+{
+    samples: Vec<f64>,
+    expectation: f64,
+}
+
+/// The estimate of the expected outcome, and its standard error if there are enough values.
+struct Estimator
+{
+    estimate: f64,
+    /// The standard error, and the degrees of freedom it was estimated with.
+    standard_error: Option<(f64, usize)>,
+}
+
+impl EnsembleResult
+{
+    /// Creates a result from the value of the outcome in every replication.
+    ///
+    /// # Panics
+    ///
+    /// If any of the values is not finite.
+    #[must_use]
+    pub fn new(values: impl IntoIterator<Item = f64>) -> Self
+    {
+        let values = values.into_iter().collect::<Vec<_>>();
+        for value in &values
+        {
+            assert!(value.is_finite(), "invalid ensemble value: {value}");
+        }
+        Self {
+            values,
+            control: None,
+        }
+    }
+
+    /// Creates a result from the value of `outcome` in every one of the given replicas.
+    ///
+    /// If the replicas hold a [`ControlVariate`], its value in every replica is collected as
+    /// well, as in [`Self::with_control_variate`].
+    ///
+    /// # Panics
+    ///
+    /// If any of the values is not finite, or only some of the replicas hold a control variate,
+    /// or not all of them with the same expectation.
+    #[must_use]
+    pub fn from_replicas(replicas: &[Simulation], outcome: impl Fn(&Simulation) -> f64) -> Self
+    {
+        let mut ensemble = Self::default();
+        for replica in replicas
+        {
+            let control = replica.get_resource::<ControlVariate>().ok();
+            ensemble.push(outcome(replica), control);
+        }
+        ensemble
+    }
+
+    /// Sets the control variate sampled along with the value of every replication, in the same
+    /// order, along with its known `expectation`.
+    ///
+    /// # Panics
+    ///
+    /// If the number of samples differs from the number of values, or any of the samples or the
+    /// expectation is not finite.
+    #[must_use]
+    pub fn with_control_variate(
+        mut self,
+        samples: impl IntoIterator<Item = f64>,
+        expectation: f64,
+    ) -> Self
+    {
+        let samples = samples.into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            samples.len(),
+            self.len(),
+            "there must be one control variate sample per value"
+        );
+        for sample in samples.iter().chain([&expectation])
+        {
+            assert!(sample.is_finite(), "invalid control variate: {sample}");
+        }
+        self.control = Some(Controls {
+            samples,
+            expectation,
+        });
+        self
+    }
+
+    /// Adds the value of the outcome in another replication, along with its control variate.
+    #[allow(clippy::float_cmp)]
+    pub(crate) fn push(&mut self, value: f64, control: Option<&ControlVariate>)
+    {
+        assert!(value.is_finite(), "invalid ensemble value: {value}");
+        match (&mut self.control, control)
+        {
+            (None, None) =>
+            {}
+            (None, Some(control)) if self.values.is_empty() =>
+            {
+                self.control = Some(Controls {
+                    samples: vec![control.value()],
+                    expectation: control.expectation(),
+                });
+            }
+            (Some(controls), Some(control)) =>
+            {
+                assert!(
+                    controls.expectation == control.expectation(),
+                    "the control variates of all replicas must have the same expectation"
+                );
+                controls.samples.push(control.value());
+            }
+            _ => panic!("either all replicas or none of them must hold a control variate"),
+        }
+        self.values.push(value);
+    }
+
+    /// The value of the outcome in every replication, in order.
+    #[must_use]
+    pub fn values(&self) -> &[f64]
+    {
+        &self.values
+    }
+
+    /// The number of replications.
+    #[must_use]
+    pub const fn len(&self) -> usize
+    {
+        self.values.len()
+    }
+
+    /// Checks whether there are no replications.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool
+    {
+        self.values.is_empty()
+    }
+
+    /// The mean of the values, or `None` if there are none.
+    #[must_use]
+    pub fn mean(&self) -> Option<f64>
+    {
+        mean(&self.values)
+    }
+
+    /// The unbiased sample variance of the values, or `None` if there are fewer than two.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn variance(&self) -> Option<f64>
+    {
+        let mean = self.mean()?;
+        (self.len() > 1).then(|| {
+            let squares = self.values.iter().map(|value| (value - mean).powi(2));
+            squares.sum::<f64>() / (self.len() - 1) as f64
+        })
+    }
+
+    /// The sample standard deviation of the values, or `None` if there are fewer than two.
+    #[must_use]
+    pub fn std_dev(&self) -> Option<f64>
+    {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// The kernel density estimate of the distribution of the values, or `None` if there are
+    /// none.
+    #[must_use]
+    pub fn kernel_density(&self) -> Option<KernelDensity>
+    {
+        (!self.is_empty()).then(|| KernelDensity::new(self.values.iter().copied()))
+    }
+
+    /// The fraction of the values that are greater than `threshold`, or `None` if there are
+    /// none.
+    ///
+    /// This is the empirical estimate, which is zero beyond the largest value. To extrapolate
+    /// further into the tail, use [`Self::peaks_over_threshold`].
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn exceedance_probability(&self, threshold: f64) -> Option<f64>
+    {
+        (!self.is_empty()).then(|| {
+            let exceeding = self.values.iter().filter(|value| **value > threshold);
+            exceeding.count() as f64 / self.len() as f64
+        })
+    }
+
+    /// The peaks-over-threshold model of the upper tail of the values above `threshold`, or
+    /// `None` if it cannot be fitted, see [`PeaksOverThreshold::fit`].
+    #[must_use]
+    pub fn peaks_over_threshold(&self, threshold: f64) -> Option<PeaksOverThreshold>
+    {
+        PeaksOverThreshold::fit(self.values.iter().copied(), threshold)
+    }
+
+    /// The estimated coefficient by which the deviations of the control variate from its
+    /// expectation are subtracted from the mean.
+    ///
+    /// Returns `None` if there is no control variate, or it does not vary across the
+    /// replications, in which case the estimate is the plain mean.
+    #[must_use]
+    pub fn control_coefficient(&self) -> Option<f64>
+    {
+        let controls = self.control.as_ref()?;
+        coefficient(&self.values, &controls.samples)
+    }
+
+    /// The estimate of the expected outcome, or `None` if there are no values.
+    ///
+    /// This is the mean of the values, corrected with the control variate if there is one.
+    #[must_use]
+    pub fn estimate(&self) -> Option<f64>
+    {
+        self.estimator().map(|estimator| estimator.estimate)
+    }
+
+    /// The standard error of the [`Self::estimate`], or `None` if there are fewer than two
+    /// values, or three with a control variate.
+    #[must_use]
+    pub fn standard_error(&self) -> Option<f64>
+    {
+        let (standard_error, _) = self.estimator()?.standard_error?;
+        Some(standard_error)
+    }
+
+    /// The standard error of the [`Self::estimate`], and the degrees of freedom it was
+    /// estimated with.
+    pub(crate) fn standard_error_with_df(&self) -> Option<(f64, usize)>
+    {
+        self.estimator()?.standard_error
+    }
+
+    fn estimator(&self) -> Option<Estimator>
+    {
+        let control = self
+            .control
+            .as_ref()
+            .map(|controls| (controls.samples.as_slice(), controls.expectation));
+        estimator(&self.values, control)
+    }
+
+    /// The confidence interval of the [`Self::estimate`] at the given `level`, e.g. `0.95`,
+    /// from the Student's t-distribution with `n - 1` degrees of freedom, or `n - 2` with a
+    /// control variate.
+    ///
+    /// This assumes that the estimate is approximately normally distributed, which holds for
+    /// enough replications of most outcomes.
+    /// Returns `None` if there are fewer than two values, or three with a control variate.
+    ///
+    /// # Panics
+    ///
+    /// If `level` is not within `(0, 1)`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn confidence_interval(&self, level: f64) -> Option<ConfidenceInterval>
+    {
+        assert_level(level);
+        let Estimator {
+            estimate,
+            standard_error,
+        } = self.estimator()?;
+        let (standard_error, degrees_of_freedom) = standard_error?;
+        let quantile = student_t_quantile(0.5 + level / 2.0, degrees_of_freedom as f64);
+
+        Some(ConfidenceInterval {
+            estimate,
+            lower: estimate - quantile * standard_error,
+            upper: estimate + quantile * standard_error,
+            level,
+        })
+    }
+
+    /// The number of replications needed, in addition to the current ones, for the
+    /// [`Self::confidence_interval`] at the given `level` to narrow down to a half-width of
+    /// at most `half_width`, assuming the standard deviation of the outcome stays the same.
+    ///
+    /// This is an estimate which improves with the number of replications, since both the
+    /// standard deviation and the quantile of the t-distribution are taken from the current ones.
+    /// Returns `None` if there are fewer than two values, or three with a control variate.
+    /// To run replications until the target is met, see [`crate::ReplicationPlan`].
+    ///
+    /// # Panics
+    ///
+    /// If `level` is not within `(0, 1)`, or `half_width` is not positive.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn additional_runs(&self, half_width: f64, level: f64) -> Option<usize>
+    {
+        assert_level(level);
+        assert!(half_width > 0.0, "invalid target half-width: {half_width}");
+        let (standard_error, degrees_of_freedom) = self.estimator()?.standard_error?;
+        let std_dev = standard_error * (self.len() as f64).sqrt();
+        let quantile = student_t_quantile(0.5 + level / 2.0, degrees_of_freedom as f64);
+
+        let required = (quantile * std_dev / half_width).powi(2).ceil() as usize;
+        Some(required.saturating_sub(self.len()))
+    }
+
+    /// The percentile bootstrap confidence interval of the [`Self::estimate`] at the given
+    /// `level`, e.g. `0.95`, from the estimates of `resamples` resamples of the replications,
+    /// drawn with replacement from `rng`.
+    ///
+    /// This makes no assumption about the distribution of the outcome, such as for skewed
+    /// outcomes over few replications, at the cost of drawing the resamples.
+    /// Returns `None` if there are fewer than two values.
+    ///
+    /// # Panics
+    ///
+    /// If `level` is not within `(0, 1)`, or `resamples` is `0`.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn bootstrap_confidence_interval(
+        &self,
+        level: f64,
+        resamples: usize,
+        rng: &mut impl Rng,
+    ) -> Option<ConfidenceInterval>
+    {
+        assert_level(level);
+        assert!(resamples > 0, "the bootstrap needs at least one resample");
+        let estimate = self.estimate()?;
+        if self.len() < 2
+        {
+            return None;
+        }
+
+        let mut values = Vec::with_capacity(self.len());
+        let mut samples = Vec::with_capacity(self.len());
+        let mut estimates = (0..resamples)
+            .filter_map(|_| {
+                values.clear();
+                samples.clear();
+                for _ in 0..self.len()
+                {
+                    let idx = rng.random_range(0..self.len());
+                    values.push(self.values[idx]);
+                    if let Some(controls) = &self.control
+                    {
+                        samples.push(controls.samples[idx]);
+                    }
+                }
+
+                let control = self
+                    .control
+                    .as_ref()
+                    .map(|controls| (samples.as_slice(), controls.expectation));
+                estimator(&values, control).map(|estimator| estimator.estimate)
+            })
+            .collect::<Vec<_>>();
+        estimates.sort_by(f64::total_cmp);
+
+        let tail = (1.0 - level) / 2.0;
+        let percentile =
+            |p: f64| estimates[((p * estimates.len() as f64) as usize).min(estimates.len() - 1)];
+
+        Some(ConfidenceInterval {
+            estimate,
+            lower: percentile(tail),
+            upper: percentile(1.0 - tail),
+            level,
+        })
+    }
+}
+
+impl FromIterator<f64> for EnsembleResult
+{
+    fn from_iter<T: IntoIterator<Item = f64>>(iter: T) -> Self
+    {
+        Self::new(iter)
+    }
+}
+
+/// An estimate along with the range that is expected to contain the true value, at a given
+/// confidence level, as returned by [`EnsembleResult::confidence_interval`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfidenceInterval
+{
+    /// The point estimate, i.e. [`EnsembleResult::estimate`].
+    pub estimate: f64,
+
+    pub lower: f64,
+    pub upper: f64,
+
+    /// The confidence level, e.g. `0.95`.
+    pub level: f64,
+}
+
+impl ConfidenceInterval
+{
+    /// Half of the width of the interval, i.e. the margin of error of a symmetric interval.
+    #[must_use]
+    pub fn half_width(&self) -> f64
+    {
+        (self.upper - self.lower) / 2.0
+    }
+
+    /// Checks whether the given value lies within the interval.
+    #[must_use]
+    pub fn contains(&self, value: f64) -> bool
+    {
+        (self.lower..=self.upper).contains(&value)
+    }
+}
+
+impl fmt::Display for ConfidenceInterval
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(
+            f,
+            "{} ({}% CI: {} to {})",
+            self.estimate,
+            self.level * 100.0,
+            self.lower,
+            self.upper
+        )
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn mean(values: &[f64]) -> Option<f64>
+{
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// The regression coefficient of `values` on `controls`, or `None` if the controls do not vary.
+fn coefficient(values: &[f64], controls: &[f64]) -> Option<f64>
+{
+    let (mean_value, mean_control) = (mean(values)?, mean(controls)?);
+    let (covariance, variance) =
+        values
+            .iter()
+            .zip(controls)
+            .fold((0.0, 0.0), |(covariance, variance), (value, control)| {
+                let deviation = control - mean_control;
+                (
+                    deviation.mul_add(value - mean_value, covariance),
+                    deviation.mul_add(deviation, variance),
+                )
+            });
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// The estimate of the expected value of `values`, with the control variates in `control`, if
+/// given along with their known expectation.
+#[allow(clippy::cast_precision_loss)]
+fn estimator(values: &[f64], control: Option<(&[f64], f64)>) -> Option<Estimator>
+{
+    let mean_value = mean(values)?;
+    let n = values.len();
+    let Some((controls, expectation, coefficient)) = control.and_then(|(controls, expectation)| {
+        coefficient(values, controls).map(|coefficient| (controls, expectation, coefficient))
+    })
+    else
+    {
+        let standard_error = (n > 1).then(|| {
+            let squares = values.iter().map(|value| (value - mean_value).powi(2));
+            let variance = squares.sum::<f64>() / (n - 1) as f64;
+            ((variance / n as f64).sqrt(), n - 1)
+        });
+        return Some(Estimator {
+            estimate: mean_value,
+            standard_error,
+        });
+    };
+
+    let mean_control = mean(controls)?;
+    let estimate = coefficient.mul_add(-(mean_control - expectation), mean_value);
+
+    // the residuals of the regression, one degree of freedom of which went to the coefficient
+    let standard_error = (n > 2).then(|| {
+        let residuals = values.iter().zip(controls).map(|(value, control)| {
+            coefficient
+                .mul_add(-(control - mean_control), value - mean_value)
+                .powi(2)
+        });
+        let variance = residuals.sum::<f64>() / (n - 2) as f64;
+        ((variance / n as f64).sqrt(), n - 2)
+    });
+    Some(Estimator {
+        estimate,
+        standard_error,
+    })
+}
+
+pub(super) fn assert_level(level: f64)
+{
+    assert!(
+        level > 0.0 && level < 1.0,
+        "invalid confidence level: {level}"
+    );
+}
@@ -0,0 +1,268 @@
+/// The generalized Pareto distribution, the limiting distribution of the excesses of a value
+/// over a high threshold, whatever the distribution of the value itself.
+///
+/// The `shape` sets how heavy the tail is: a positive shape is a heavy, power-law tail, a shape
+/// of zero is the exponential distribution, and a negative shape is a tail bounded at
+/// `-scale / shape`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeneralizedPareto
+{
+    shape: f64,
+    scale: f64,
+}
+
+/// Shapes closer to zero than this are treated as the exponential distribution, which is the
+/// limit of the formulas as the shape goes to zero.
+const EXPONENTIAL_SHAPE: f64 = 1e-9;
+
+impl GeneralizedPareto
+{
+    /// Creates the distribution with the given shape and scale.
+    ///
+    /// # Panics
+    ///
+    /// If the shape is not finite, or the scale is not positive and finite.
+    #[must_use]
+    pub fn new(shape: f64, scale: f64) -> Self
+    {
+        assert!(shape.is_finite(), "invalid shape: {shape}");
+        assert!(scale.is_finite() && scale > 0.0, "invalid scale: {scale}");
+        Self { shape, scale }
+    }
+
+    /// Fits the distribution to the given excesses over a threshold, by the method of
+    /// probability weighted moments, which is reliable for small samples and shapes below `1`.
+    ///
+    /// Returns `None` if there are fewer than two excesses, any of them is negative or not
+    /// finite, or they do not vary enough to fit a distribution.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fit(excesses: impl IntoIterator<Item = f64>) -> Option<Self>
+    {
+        let mut excesses = excesses.into_iter().collect::<Vec<_>>();
+        if excesses.len() < 2
+            || excesses
+                .iter()
+                .any(|excess| !excess.is_finite() || *excess < 0.0)
+        {
+            return None;
+        }
+        excesses.sort_by(f64::total_cmp);
+
+        // the unbiased estimates of E[Y] and E[Y (1 - F(Y))]
+        let n = excesses.len() as f64;
+        let first_moment = excesses.iter().sum::<f64>() / n;
+        let weighted = excesses
+            .iter()
+            .enumerate()
+            .map(|(rank, excess)| (n - 1.0 - rank as f64) / (n - 1.0) * excess)
+            .sum::<f64>()
+            / n;
+
+        let denominator = 2.0f64.mul_add(-weighted, first_moment);
+        let shape = 2.0 - first_moment / denominator;
+        let scale = 2.0 * first_moment * weighted / denominator;
+        (denominator > 0.0 && scale.is_finite() && scale > 0.0).then_some(Self { shape, scale })
+    }
+
+    /// The shape of the tail.
+    #[must_use]
+    pub const fn shape(&self) -> f64
+    {
+        self.shape
+    }
+
+    /// The scale of the excesses.
+    #[must_use]
+    pub const fn scale(&self) -> f64
+    {
+        self.scale
+    }
+
+    /// The probability of an excess greater than `excess`.
+    #[must_use]
+    pub fn survival(&self, excess: f64) -> f64
+    {
+        if excess <= 0.0
+        {
+            return 1.0;
+        }
+
+        let z = excess / self.scale;
+        if self.shape.abs() < EXPONENTIAL_SHAPE
+        {
+            return (-z).exp();
+        }
+
+        let base = self.shape.mul_add(z, 1.0);
+        if base <= 0.0
+        {
+            // beyond the upper bound of a negative shape
+            return 0.0;
+        }
+        base.powf(-1.0 / self.shape)
+    }
+
+    /// The probability of an excess of at most `excess`.
+    #[must_use]
+    pub fn cdf(&self, excess: f64) -> f64
+    {
+        1.0 - self.survival(excess)
+    }
+
+    /// The excess that is exceeded with probability `1 - p`.
+    ///
+    /// # Panics
+    ///
+    /// If `p` is not within `[0, 1)`.
+    #[must_use]
+    pub fn quantile(&self, p: f64) -> f64
+    {
+        assert!((0.0..1.0).contains(&p), "invalid probability: {p}");
+
+        let survival = 1.0 - p;
+        if self.shape.abs() < EXPONENTIAL_SHAPE
+        {
+            -self.scale * survival.ln()
+        }
+        else
+        {
+            self.scale / self.shape * (survival.powf(-self.shape) - 1.0)
+        }
+    }
+}
+
+/// A peaks-over-threshold model of the upper tail of a set of values, such as the outcomes of the
+/// replicas of an experiment.
+///
+/// The values above a high threshold are rare, so the empirical frequency with which they exceed
+/// some level is unreliable, and zero beyond the largest value. Instead, the excesses over the
+/// threshold are fitted with a [`GeneralizedPareto`] distribution, which extrapolates the
+/// probability of exceeding levels that were rarely or never reached, and the return levels that
+/// are exceeded once every so many replicas, e.g. the 1-in-100 outcome.
+///
+/// The threshold is a trade-off: it should be high enough for the excesses to follow the
+/// distribution, and low enough to leave enough of them to fit it. A quantile around `0.9` of
+/// the values is a common choice. For the lower tail, such as the probability of ruin, fit the
+/// negated values instead.
+///
+/// Constructed with [`Self::fit`], or from the outcomes of an [`crate::EnsembleResult`] with
+/// [`crate::EnsembleResult::peaks_over_threshold`].
+///
+/// ```
+/// # use incerto::prelude::*;
+/// // exponentially distributed losses, at evenly spaced quantiles
+/// let losses = (1..1000).map(|i| -(1.0 - f64::from(i) / 1000.0).ln());
+/// let tail = PeaksOverThreshold::fit(losses, 2.0).unwrap();
+///
+/// // the exponential distribution has no heavy tail, and one in a hundred losses exceeds ln(100)
+/// assert!(tail.distribution().shape().abs() < 0.05);
+/// assert!((tail.return_level(100.0) - 100.0_f64.ln()).abs() < 0.1);
+///
+/// // a level that was never reached still has a probability
+/// assert!(tail.exceedance_probability(10.0) > 0.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeaksOverThreshold
+{
+    threshold: f64,
+    distribution: GeneralizedPareto,
+    exceedances: usize,
+    total: usize,
+}
+
+impl PeaksOverThreshold
+{
+    /// Fits the tail of the given values above `threshold`.
+    ///
+    /// Returns `None` if fewer than two values exceed the threshold, or the
+    /// [`GeneralizedPareto`] distribution cannot be fitted to their excesses.
+    #[must_use]
+    pub fn fit(values: impl IntoIterator<Item = f64>, threshold: f64) -> Option<Self>
+    {
+        let mut total = 0;
+        let mut excesses = Vec::new();
+        for value in values
+        {
+            total += 1;
+            if value > threshold
+            {
+                excesses.push(value - threshold);
+            }
+        }
+
+        let exceedances = excesses.len();
+        let distribution = GeneralizedPareto::fit(excesses)?;
+        Some(Self {
+            threshold,
+            distribution,
+            exceedances,
+            total,
+        })
+    }
+
+    /// The threshold above which the tail is fitted.
+    #[must_use]
+    pub const fn threshold(&self) -> f64
+    {
+        self.threshold
+    }
+
+    /// The distribution fitted to the excesses over the threshold.
+    #[must_use]
+    pub const fn distribution(&self) -> GeneralizedPareto
+    {
+        self.distribution
+    }
+
+    /// The number of values that exceed the threshold.
+    #[must_use]
+    pub const fn exceedances(&self) -> usize
+    {
+        self.exceedances
+    }
+
+    /// The fraction of the values that exceed the threshold.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn exceedance_rate(&self) -> f64
+    {
+        self.exceedances as f64 / self.total as f64
+    }
+
+    /// The estimated probability of a value greater than `level`.
+    ///
+    /// # Panics
+    ///
+    /// If `level` is below the threshold, where the tail model does not apply.
+    #[must_use]
+    pub fn exceedance_probability(&self, level: f64) -> f64
+    {
+        assert!(
+            level >= self.threshold,
+            "level {level} is below the threshold {}",
+            self.threshold
+        );
+        self.exceedance_rate() * self.distribution.survival(level - self.threshold)
+    }
+
+    /// The return level for a period of `period` replicas, i.e. the level that is exceeded by one
+    /// in every `period` values on average.
+    ///
+    /// # Panics
+    ///
+    /// If the period is so short that the return level is below the threshold, i.e. `1 / period`
+    /// is greater than [`Self::exceedance_rate`].
+    #[must_use]
+    pub fn return_level(&self, period: f64) -> f64
+    {
+        let probability = period.recip() / self.exceedance_rate();
+        assert!(
+            probability > 0.0 && probability <= 1.0,
+            "the return level for a period of {period} is below the threshold"
+        );
+        self.threshold + self.distribution.quantile(1.0 - probability)
+    }
+}
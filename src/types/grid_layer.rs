@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+
+use crate::{
+    SpatialGridError,
+    plugins::{GridBounds2D, GridPosition, GridPosition2D},
+};
+
+/// A value for every cell of a 2D grid, such as the elevation of the terrain or the density of
+/// the population, typically used to set up the spatial initial conditions of a simulation.
+///
+/// Layers can be loaded from geographic data with the functions in [`crate::geo`],
+/// used to place entities with [`crate::placement::weighted`], or added to the simulation as a
+/// resource with [`crate::SimulationBuilder::add_resource`] to be read by its systems.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GridLayer
+{
+    bounds: GridBounds2D,
+    values: Vec<f64>,
+}
+
+impl GridLayer
+{
+    /// Creates a layer over the given `bounds`, with every cell set to `value`.
+    #[must_use]
+    pub fn new(bounds: GridBounds2D, value: f64) -> Self
+    {
+        Self {
+            bounds,
+            values: vec![value; Self::num_cells(&bounds)],
+        }
+    }
+
+    /// The bounds of the grid covered by the layer.
+    #[must_use]
+    pub const fn bounds(&self) -> GridBounds2D
+    {
+        self.bounds
+    }
+
+    /// Returns the value of the cell at the given `position`,
+    /// or `None` if it is outside the bounds of the layer.
+    #[must_use]
+    pub fn get(&self, position: &GridPosition2D) -> Option<f64>
+    {
+        self.index_of(*position).map(|index| self.values[index])
+    }
+
+    /// Sets the value of the cell at the given `position`.
+    ///
+    /// # Errors
+    ///
+    /// - [`SpatialGridError::OutOfBounds`]
+    pub fn set(&mut self, position: &GridPosition2D, value: f64) -> Result<(), SpatialGridError>
+    {
+        let index = self
+            .index_of(*position)
+            .ok_or(SpatialGridError::OutOfBounds)?;
+        self.values[index] = value;
+        Ok(())
+    }
+
+    /// Iterates over the position and value of every cell in the layer,
+    /// in the order of [`GridBounds2D::positions`].
+    pub fn iter(&self) -> impl Iterator<Item = (GridPosition2D, f64)> + '_
+    {
+        self.bounds
+            .positions()
+            .map(|position| (position, self.get(&position).unwrap_or(f64::NAN)))
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn num_cells(bounds: &GridBounds2D) -> usize
+    {
+        let size = (bounds.max - bounds.min + IVec2::ONE).max(IVec2::ZERO);
+        size.x as usize * size.y as usize
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn index_of(&self, GridPosition(position): GridPosition2D) -> Option<usize>
+    {
+        if !self.bounds.contains(&position)
+        {
+            return None;
+        }
+
+        let offset = position - self.bounds.min;
+        let width = self.bounds.max.x - self.bounds.min.x + 1;
+        Some(offset.y as usize * width as usize + offset.x as usize)
+    }
+}
@@ -0,0 +1,257 @@
+use crate::statistics::normal_cdf;
+
+/// How the bandwidth of a [`KernelDensity`] is chosen, which sets how smooth the density is.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Bandwidth
+{
+    /// Silverman's rule of thumb, `0.9 * min(σ, IQR / 1.34) * n^(-1/5)`, which is robust to
+    /// outliers and suits most unimodal distributions.
+    #[default]
+    Silverman,
+
+    /// Scott's rule of thumb, `1.06 * σ * n^(-1/5)`, which is optimal for normal distributions
+    /// but oversmooths multimodal ones.
+    Scott,
+
+    /// The given bandwidth.
+    Fixed(f64),
+}
+
+/// A kernel density estimate of the distribution of a set of samples, such as the outcomes of
+/// the replicas of an experiment or the values of a component across a population.
+///
+/// Every sample contributes a Gaussian bump of the same width, the bandwidth, to a smooth density,
+/// from which probabilities and quantiles can be read off beyond the moments of the samples, e.g.
+/// to tell apart a bimodal outcome from a unimodal one with the same mean and variance.
+///
+/// Constructed with [`Self::new`], or from the outcomes of an [`crate::EnsembleResult`] with
+/// [`crate::EnsembleResult::kernel_density`], or from the components of a simulation with
+/// [`crate::Simulation::sample_density`].
+///
+/// ```
+/// # use incerto::prelude::*;
+/// let samples = [1.0, 1.2, 0.8, 1.1, 0.9, 5.0, 5.2, 4.8, 5.1, 4.9];
+/// let density = KernelDensity::new(samples).with_bandwidth(Bandwidth::Fixed(0.3));
+///
+/// // two modes, with almost nothing in between
+/// assert!(density.density(1.0) > 10.0 * density.density(3.0));
+/// assert!(density.density(5.0) > 10.0 * density.density(3.0));
+/// assert!((density.quantile(0.5) - 3.0).abs() < 0.1);
+/// assert!((density.cdf(3.0) - 0.5).abs() < 1e-3);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelDensity
+{
+    /// The samples, in increasing order.
+    samples: Vec<f64>,
+    bandwidth: f64,
+}
+
+impl KernelDensity
+{
+    /// Creates the density estimate of the given samples, with a bandwidth chosen by
+    /// [`Bandwidth::Silverman`].
+    ///
+    /// # Panics
+    ///
+    /// If there are no samples, or any of them is not finite.
+    #[must_use]
+    pub fn new(samples: impl IntoIterator<Item = f64>) -> Self
+    {
+        let mut samples = samples.into_iter().collect::<Vec<_>>();
+        assert!(!samples.is_empty(), "a kernel density needs samples");
+        for sample in &samples
+        {
+            assert!(sample.is_finite(), "invalid sample: {sample}");
+        }
+        samples.sort_by(f64::total_cmp);
+
+        let mut density = Self {
+            samples,
+            bandwidth: 1.0,
+        };
+        density.bandwidth = density.select_bandwidth(Bandwidth::default());
+        density
+    }
+
+    /// Sets how the bandwidth is chosen.
+    ///
+    /// If the samples do not vary at all, the rules of thumb fall back to a bandwidth of `1`.
+    ///
+    /// # Panics
+    ///
+    /// If a [`Bandwidth::Fixed`] bandwidth is not positive and finite.
+    #[must_use]
+    pub fn with_bandwidth(mut self, bandwidth: Bandwidth) -> Self
+    {
+        self.bandwidth = self.select_bandwidth(bandwidth);
+        self
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn select_bandwidth(&self, bandwidth: Bandwidth) -> f64
+    {
+        let n = self.samples.len() as f64;
+        let mean = self.samples.iter().sum::<f64>() / n;
+        let variance = self
+            .samples
+            .iter()
+            .map(|sample| (sample - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.0).max(1.0);
+        let std_dev = variance.sqrt();
+
+        let bandwidth = match bandwidth
+        {
+            Bandwidth::Silverman =>
+            {
+                let iqr = self.sample_quantile(0.75) - self.sample_quantile(0.25);
+                let spread = if iqr > 0.0
+                {
+                    std_dev.min(iqr / 1.34)
+                }
+                else
+                {
+                    std_dev
+                };
+                0.9 * spread * n.powf(-0.2)
+            }
+            Bandwidth::Scott => 1.06 * std_dev * n.powf(-0.2),
+            Bandwidth::Fixed(bandwidth) =>
+            {
+                assert!(
+                    bandwidth.is_finite() && bandwidth > 0.0,
+                    "invalid bandwidth: {bandwidth}"
+                );
+                bandwidth
+            }
+        };
+        if bandwidth > 0.0 { bandwidth } else { 1.0 }
+    }
+
+    /// The quantile of the samples themselves at probability `p`, interpolated linearly.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn sample_quantile(&self, p: f64) -> f64
+    {
+        let position = p * (self.samples.len() - 1) as f64;
+        let below = position.floor() as usize;
+        let above = (below + 1).min(self.samples.len() - 1);
+        let fraction = position - position.floor();
+        fraction.mul_add(
+            self.samples[above] - self.samples[below],
+            self.samples[below],
+        )
+    }
+
+    /// The range of the samples, widened by the given number of bandwidths on either side.
+    fn span(&self, bandwidths: f64) -> (f64, f64)
+    {
+        let (first, last) = (self.samples[0], self.samples[self.samples.len() - 1]);
+        (
+            bandwidths.mul_add(-self.bandwidth, first),
+            bandwidths.mul_add(self.bandwidth, last),
+        )
+    }
+
+    /// The samples, in increasing order.
+    #[must_use]
+    pub fn samples(&self) -> &[f64]
+    {
+        &self.samples
+    }
+
+    /// The bandwidth, i.e. the standard deviation of the bump of every sample.
+    #[must_use]
+    pub const fn bandwidth(&self) -> f64
+    {
+        self.bandwidth
+    }
+
+    /// The estimated probability density at `x`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn density(&self, x: f64) -> f64
+    {
+        let normalization =
+            self.samples.len() as f64 * self.bandwidth * (2.0 * std::f64::consts::PI).sqrt();
+        let bumps = self.samples.iter().map(|sample| {
+            let z = (x - sample) / self.bandwidth;
+            (-0.5 * z * z).exp()
+        });
+        bumps.sum::<f64>() / normalization
+    }
+
+    /// The estimated probability of a value of at most `x`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cdf(&self, x: f64) -> f64
+    {
+        let total = self
+            .samples
+            .iter()
+            .map(|sample| normal_cdf((x - sample) / self.bandwidth))
+            .sum::<f64>();
+        total / self.samples.len() as f64
+    }
+
+    /// The estimated quantile at probability `p`, i.e. the value `x` at which [`Self::cdf`]
+    /// reaches `p`.
+    ///
+    /// # Panics
+    ///
+    /// If `p` is not within `(0, 1)`.
+    #[must_use]
+    pub fn quantile(&self, p: f64) -> f64
+    {
+        assert!(p > 0.0 && p < 1.0, "invalid probability: {p}");
+
+        // the bumps are practically zero beyond ten bandwidths
+        let (mut low, mut high) = self.span(10.0);
+        for _ in 0..100
+        {
+            let middle = f64::midpoint(low, high);
+            if self.cdf(middle) < p
+            {
+                low = middle;
+            }
+            else
+            {
+                high = middle;
+            }
+        }
+        f64::midpoint(low, high)
+    }
+
+    /// Evaluates the density at `points` evenly spaced points, spanning the samples along with
+    /// three bandwidths on either side, e.g. to plot it.
+    ///
+    /// Returns the pairs of every point and the density at it.
+    ///
+    /// # Panics
+    ///
+    /// If `points` is less than `2`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn evaluate(&self, points: usize) -> Vec<(f64, f64)>
+    {
+        assert!(
+            points >= 2,
+            "the density must be evaluated at two points or more"
+        );
+
+        let (start, end) = self.span(3.0);
+        let step = (end - start) / (points - 1) as f64;
+        (0..points)
+            .map(|point| {
+                let x = step.mul_add(point as f64, start);
+                (x, self.density(x))
+            })
+            .collect()
+    }
+}
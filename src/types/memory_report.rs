@@ -0,0 +1,75 @@
+use std::fmt;
+
+/// A summary of the memory used by a simulation, as returned by [`crate::Simulation::memory_report`].
+///
+/// Byte counts are estimates of the heap memory allocated by the crate's own data structures,
+/// based on their capacity. Memory owned indirectly by sampled values, such as the contents
+/// of a `String` in a time series, is not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryReport
+{
+    /// The number of entities in the simulation.
+    pub entities: usize,
+
+    /// The number of distinct archetypes, i.e. combinations of components, in the simulation.
+    pub archetypes: usize,
+
+    /// The number of cells stored across all spatial grids.
+    ///
+    /// For sparse grids this is the number of occupied positions,
+    /// while for dense grids it is the number of positions within their bounds.
+    pub spatial_grid_cells: usize,
+
+    /// The estimated number of bytes allocated by all spatial grids.
+    pub spatial_grid_bytes: usize,
+
+    /// The number of entities with at least one edge, across all networks.
+    pub network_nodes: usize,
+
+    /// The estimated number of bytes allocated by all networks.
+    pub network_bytes: usize,
+
+    /// The number of samples recorded across all time series.
+    pub time_series_samples: usize,
+
+    /// The estimated number of bytes allocated by all time series.
+    pub time_series_bytes: usize,
+}
+
+impl MemoryReport
+{
+    /// The estimated number of bytes allocated by all the subsystems covered by the report.
+    #[must_use]
+    pub const fn total_bytes(&self) -> usize
+    {
+        self.spatial_grid_bytes + self.network_bytes + self.time_series_bytes
+    }
+}
+
+impl fmt::Display for MemoryReport
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        writeln!(
+            f,
+            "entities: {} in {} archetypes",
+            self.entities, self.archetypes
+        )?;
+        writeln!(
+            f,
+            "spatial grids: {} cells, {} bytes",
+            self.spatial_grid_cells, self.spatial_grid_bytes
+        )?;
+        writeln!(
+            f,
+            "networks: {} nodes, {} bytes",
+            self.network_nodes, self.network_bytes
+        )?;
+        write!(
+            f,
+            "time series: {} samples, {} bytes",
+            self.time_series_samples, self.time_series_bytes
+        )
+    }
+}
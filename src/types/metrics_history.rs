@@ -0,0 +1,106 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::OwnedTimeSeries;
+
+/// Resource holding the metrics that a module records on every step, or at another fixed
+/// interval, such as the [`crate::epidemic::EpidemicCounts`] of an epidemic.
+///
+/// The modules of the crate expose their history under their own names, such as
+/// [`crate::epidemic::EpidemicOutputs`], which are aliases of this type. The `S` parameter tells
+/// apart the histories of the same metrics recorded by different instances of a module, e.g.
+/// one for every flock of boids.
+///
+/// Any single metric can be extracted as a [`crate::TimeSeries`] with [`Self::time_series`],
+/// to be analyzed or exported like the time series recorded with
+/// [`crate::SimulationBuilder::record_aggregate_time_series`].
+///
+/// Accessible with [`crate::Simulation::get_resource`], or in user-defined systems using
+/// [`Res<MetricsHistory<M, S>>`] arguments.
+#[derive(Resource, Debug, Clone)]
+pub struct MetricsHistory<M, S = ()>
+{
+    records: Vec<M>,
+    time: Vec<usize>,
+    sample_interval: usize,
+    _phantom: PhantomData<fn() -> S>,
+}
+
+impl<M, S> Default for MetricsHistory<M, S>
+{
+    fn default() -> Self
+    {
+        Self::new(1)
+    }
+}
+
+impl<M, S> MetricsHistory<M, S>
+{
+    /// Creates an empty history, for metrics recorded every `sample_interval` steps.
+    pub(crate) const fn new(sample_interval: usize) -> Self
+    {
+        Self {
+            records: Vec::new(),
+            time: Vec::new(),
+            sample_interval,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Appends the metrics recorded at the given simulation `step`.
+    pub(crate) fn push(&mut self, step: usize, metrics: M)
+    {
+        self.records.push(metrics);
+        self.time.push(step);
+    }
+
+    /// The metrics of every record so far, in order.
+    #[must_use]
+    pub fn history(&self) -> &[M]
+    {
+        &self.records
+    }
+
+    /// The metrics of the latest record, if any have been made.
+    #[must_use]
+    pub fn latest(&self) -> Option<&M>
+    {
+        self.records.last()
+    }
+
+    /// Extracts a single metric from every record as a time series, sampled at the steps at
+    /// which the records were made.
+    ///
+    /// ```
+    /// # use incerto::prelude::*;
+    /// use incerto::epidemic::{Compartment, EpidemicModule, EpidemicOutputs};
+    ///
+    /// let mut simulation = SimulationBuilder::new()
+    ///     .set_seed(3)
+    ///     .add_module(EpidemicModule::sir(0.3, 0.1))
+    ///     .add_entity_spawner(|spawner| {
+    ///         for i in 0..500
+    ///         {
+    ///             spawner.spawn(if i < 5 { Compartment::Infectious } else { Compartment::Susceptible });
+    ///         }
+    ///     })
+    ///     .build();
+    /// simulation.run(100);
+    ///
+    /// let outputs = simulation.get_resource::<EpidemicOutputs>().unwrap();
+    /// let prevalence = outputs.time_series(|counts| counts.prevalence);
+    /// let prevalence = prevalence.as_time_series();
+    /// assert_eq!(prevalence.len(), 100);
+    /// assert_eq!(prevalence.sample_interval(), 1);
+    /// ```
+    #[must_use]
+    pub fn time_series<T>(&self, metric: impl Fn(&M) -> T) -> OwnedTimeSeries<T>
+    {
+        OwnedTimeSeries {
+            values: self.records.iter().map(metric).collect(),
+            time: self.time.clone(),
+            sample_interval: self.sample_interval,
+        }
+    }
+}
@@ -1,2 +1,50 @@
+mod batch_means;
+pub use batch_means::BatchMeans;
+
+mod benchmark_report;
+pub use benchmark_report::BenchmarkReport;
+
+mod control_variate;
+pub use control_variate::ControlVariate;
+
+mod convergence_diagnostic;
+pub use convergence_diagnostic::ConvergenceDiagnostic;
+
+mod ensemble_result;
+pub use ensemble_result::{ConfidenceInterval, EnsembleResult};
+
+mod extreme_values;
+pub use extreme_values::{GeneralizedPareto, PeaksOverThreshold};
+
+mod grid_layer;
+pub use grid_layer::GridLayer;
+
+mod kernel_density;
+pub use kernel_density::{Bandwidth, KernelDensity};
+
+mod memory_report;
+pub use memory_report::MemoryReport;
+
+mod metrics_history;
+pub use metrics_history::MetricsHistory;
+
+mod parallel_sampling;
+pub use parallel_sampling::ParallelSampling;
+
+mod replication_plan;
+pub use replication_plan::ReplicationPlan;
+
+mod sim_rng;
+pub use sim_rng::SimRng;
+
+mod simulation_meta;
+pub use simulation_meta::SimulationMeta;
+
+mod stratification;
+pub use stratification::{Allocation, Stratification, StratifiedResult};
+
+mod strictness_policy;
+pub use strictness_policy::StrictnessPolicy;
+
 mod times_series;
-pub use times_series::TimeSeries;
+pub use times_series::{OwnedTimeSeries, TimeSeries};
@@ -0,0 +1,45 @@
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, ParallelSlice},
+};
+
+use crate::SampleAggregate;
+
+/// Enables the parallel sampling of aggregates over large populations.
+///
+/// Set using [`crate::SimulationBuilder::parallel_sampling_threshold`].
+///
+/// When sampling an aggregate whose [`SampleAggregate::COMBINE`] is provided, populations larger
+/// than `threshold` are split into batches of `threshold` components each, which are sampled in
+/// parallel and then merged.
+/// Since the batches do not depend on the number of threads, the results are reproducible.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelSampling
+{
+    /// The number of components above which sampling happens in parallel.
+    pub threshold: usize,
+}
+
+impl ParallelSampling
+{
+    /// Samples the aggregate of the given `components`, in parallel if `parallel` allows for it.
+    pub(crate) fn sample_aggregate<C, Out>(parallel: Option<&Self>, components: &[&C]) -> Out
+    where
+        C: SampleAggregate<Out>,
+        Out: Send + 'static,
+    {
+        match (parallel, C::COMBINE)
+        {
+            (Some(&Self { threshold }), Some(combine)) if components.len() > threshold =>
+            {
+                let partials =
+                    components.par_chunk_map(ComputeTaskPool::get(), threshold, |_, batch| {
+                        (C::sample_aggregate(batch), batch.len())
+                    });
+
+                combine(partials)
+            }
+            _ => C::sample_aggregate(components),
+        }
+    }
+}
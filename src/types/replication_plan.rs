@@ -0,0 +1,168 @@
+use super::ensemble_result::assert_level;
+use crate::EnsembleResult;
+
+/// A target precision for the estimate of an outcome, from which the number of replications
+/// that an experiment needs is planned.
+///
+/// The target is the half-width of the confidence interval of the estimate at a given level.
+/// The number of replications that reach it depends on the variance of the outcome, which is
+/// not known in advance, so it is estimated from a pilot batch of replications with
+/// [`Self::required_replicas`].
+///
+/// Used with [`crate::Simulation::replicate_planned`], which runs the pilot, then as many
+/// replications as planned, and plans again until the target is met, since the variance
+/// estimated by a small pilot is itself imprecise.
+///
+/// ```
+/// # use incerto::prelude::*;
+/// use rand::Rng;
+///
+/// #[derive(Component)]
+/// struct Dice(usize);
+///
+/// // the expected sum of ten dice, to within 0.5 at 95% confidence
+/// let plan = ReplicationPlan::new(0.5, 0.95).with_pilot(20);
+///
+/// let ensemble = Simulation::replicate_planned(
+///     &plan,
+///     1,
+///     |idx| {
+///         SimulationBuilder::new()
+///             .set_seed(idx as u64)
+///             .add_seeded_entity_spawner(|spawner, rng| {
+///                 spawner.spawn(Dice((0..10).map(|_| rng.random_range(1..=6)).sum()));
+///             })
+///     },
+///     |simulation| simulation.iter::<Dice>().next().unwrap().0 as f64,
+/// );
+///
+/// assert!(plan.is_met(&ensemble));
+/// assert!(ensemble.len() > 20);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplicationPlan
+{
+    half_width: f64,
+    level: f64,
+    pilot: usize,
+    max_replicas: Option<usize>,
+}
+
+impl ReplicationPlan
+{
+    /// Creates a plan for a confidence interval of at most `half_width` at the given `level`,
+    /// e.g. `0.95`, with a pilot of `10` replications and no limit on their number.
+    ///
+    /// # Panics
+    ///
+    /// If `level` is not within `(0, 1)`, or `half_width` is not positive and finite.
+    #[must_use]
+    pub fn new(half_width: f64, level: f64) -> Self
+    {
+        assert_level(level);
+        assert!(
+            half_width.is_finite() && half_width > 0.0,
+            "invalid target half-width: {half_width}"
+        );
+        Self {
+            half_width,
+            level,
+            pilot: 10,
+            max_replicas: None,
+        }
+    }
+
+    /// Sets the number of replications run first, to estimate the variance of the outcome.
+    ///
+    /// # Panics
+    ///
+    /// If `pilot` is less than `3`, the least that a control variate needs.
+    #[must_use]
+    pub fn with_pilot(mut self, pilot: usize) -> Self
+    {
+        assert!(pilot >= 3, "the pilot needs at least 3 replications");
+        self.pilot = pilot;
+        self
+    }
+
+    /// Sets the number of replications after which to stop, even if the target is not met.
+    ///
+    /// Without a limit, an outcome that varies a lot more than expected may take an
+    /// impractical number of replications.
+    #[must_use]
+    pub const fn with_max_replicas(mut self, max_replicas: usize) -> Self
+    {
+        self.max_replicas = Some(max_replicas);
+        self
+    }
+
+    /// The target half-width of the confidence interval.
+    #[must_use]
+    pub const fn half_width(&self) -> f64
+    {
+        self.half_width
+    }
+
+    /// The confidence level of the interval.
+    #[must_use]
+    pub const fn level(&self) -> f64
+    {
+        self.level
+    }
+
+    /// The number of replications of the pilot.
+    #[must_use]
+    pub const fn pilot(&self) -> usize
+    {
+        self.pilot
+    }
+
+    /// The number of replications after which to stop, if limited.
+    #[must_use]
+    pub const fn max_replicas(&self) -> Option<usize>
+    {
+        self.max_replicas
+    }
+
+    /// The estimated total number of replications, including those of `ensemble`, for which the
+    /// target is met, regardless of the limit on their number.
+    ///
+    /// Returns `None` if there are too few replications to estimate the variance, see
+    /// [`EnsembleResult::additional_runs`].
+    #[must_use]
+    pub fn required_replicas(&self, ensemble: &EnsembleResult) -> Option<usize>
+    {
+        let additional = ensemble.additional_runs(self.half_width, self.level)?;
+        Some(ensemble.len() + additional)
+    }
+
+    /// Whether the confidence interval of the estimate of `ensemble` is as narrow as the target.
+    #[must_use]
+    pub fn is_met(&self, ensemble: &EnsembleResult) -> bool
+    {
+        ensemble
+            .confidence_interval(self.level)
+            .is_some_and(|interval| interval.half_width() <= self.half_width)
+    }
+
+    /// The number of replications to run next, after those of `ensemble`, or `0` once the
+    /// target is met or the limit is reached.
+    pub(crate) fn next_batch(&self, ensemble: &EnsembleResult) -> usize
+    {
+        let limit = self.max_replicas.unwrap_or(usize::MAX);
+        if ensemble.len() >= limit || self.is_met(ensemble)
+        {
+            return 0;
+        }
+
+        // at least one more, since the estimate may fall just short of the target
+        let total = self
+            .required_replicas(ensemble)
+            .unwrap_or(self.pilot)
+            .max(self.pilot)
+            .max(ensemble.len() + 1)
+            .min(limit);
+        total - ensemble.len()
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+
+/// The seeded random number generator of a simulation.
+///
+/// It is seeded with [`crate::SimulationMeta::seed`], which can be set using
+/// [`crate::SimulationBuilder::set_seed`].
+/// If no seed is set, a random one is chosen when the simulation is built and recorded in the
+/// [`crate::SimulationMeta`], so that the run may be reproduced later.
+///
+/// The generator is passed to spawners added with [`crate::SimulationBuilder::add_seeded_entity_spawner`],
+/// and can be accessed in user-defined systems using [`ResMut<SimRng>`] arguments.
+/// Drawing all randomness from it makes a simulation reproducible, as long as its systems
+/// draw from it in a deterministic order.
+///
+/// Implements [`RngCore`], and thus all of the methods of [`rand::Rng`].
+#[derive(Resource, Debug, Clone)]
+pub struct SimRng(StdRng);
+
+impl SimRng
+{
+    /// Creates a new generator from the given `seed`.
+    #[must_use]
+    pub fn from_seed(seed: u64) -> Self
+    {
+        Self(StdRng::seed_from_u64(seed))
+    }
+
+    /// Derives the seed of one of many independent streams from a common `seed`.
+    ///
+    /// This is used to seed each replica of a simulation differently, yet reproducibly,
+    /// see [`crate::Simulation::replicate_seeded`].
+    #[must_use]
+    pub const fn derive_seed(seed: u64, stream: u64) -> u64
+    {
+        // one round of splitmix64, so that adjacent streams get uncorrelated seeds
+        let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl RngCore for SimRng
+{
+    fn next_u32(&mut self) -> u32
+    {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64
+    {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8])
+    {
+        self.0.fill_bytes(dst);
+    }
+}
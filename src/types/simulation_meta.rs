@@ -0,0 +1,63 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::SystemTime,
+};
+
+use bevy::prelude::*;
+
+/// Descriptive metadata of a simulation, used to trace its results back to its configuration.
+///
+/// Set up using the builder, e.g. with [`crate::SimulationBuilder::set_name`], and retrieved
+/// using [`crate::Simulation::meta`].
+/// With the `serde` feature enabled, the metadata is also embedded in the output of
+/// `Simulation::export_state`.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationMeta
+{
+    /// A short name for the simulation.
+    pub name: String,
+
+    /// A longer description of the simulation.
+    pub description: String,
+
+    /// The seed of the simulation's [`crate::SimRng`].
+    ///
+    /// If not set, a random seed is chosen and recorded here when the simulation is built.
+    pub seed: Option<u64>,
+
+    /// A hash of the parameters the simulation was configured with, if any.
+    pub parameter_hash: Option<u64>,
+
+    /// The time at which the simulation was built.
+    pub created_at: SystemTime,
+}
+
+impl Default for SimulationMeta
+{
+    fn default() -> Self
+    {
+        Self {
+            name: String::new(),
+            description: String::new(),
+            seed: None,
+            parameter_hash: None,
+            created_at: SystemTime::UNIX_EPOCH,
+        }
+    }
+}
+
+impl SimulationMeta
+{
+    /// Computes the hash of a simulation's parameters, as stored in [`Self::parameter_hash`].
+    ///
+    /// Note that the hash is only guaranteed to be stable between builds using the same
+    /// version of the Rust standard library.
+    #[must_use]
+    pub fn hash_parameters<P: Hash + ?Sized>(parameters: &P) -> u64
+    {
+        let mut hasher = DefaultHasher::new();
+        parameters.hash(&mut hasher);
+        hasher.finish()
+    }
+}
@@ -0,0 +1,369 @@
+use super::ensemble_result::assert_level;
+use crate::{ConfidenceInterval, EnsembleResult, statistics::student_t_quantile};
+
+/// How the replications of a [`Stratification`] are allocated to its strata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Allocation
+{
+    /// Replications are allocated in proportion to the weight of every stratum.
+    #[default]
+    Proportional,
+
+    /// Replications are allocated in proportion to the weight of every stratum times the
+    /// standard deviation of the outcome within it, which minimizes the variance of the
+    /// estimate for a given number of replications.
+    ///
+    /// Since the standard deviations are not known in advance, a pilot of `pilot` replications
+    /// is run in every stratum first, from which they are estimated.
+    Neyman
+    {
+        pilot: usize
+    },
+}
+
+/// A partition of the space of initial conditions of an experiment into strata, such as ranges
+/// of an uncertain parameter, along with the probability of every stratum.
+///
+/// Used with [`crate::Simulation::replicate_stratified`], which allocates the replications
+/// among the strata as set with [`Self::with_allocation`], and combines the outcomes of every
+/// stratum into a [`StratifiedResult`].
+/// When the outcome varies a lot more between strata than within them, the stratified estimate
+/// is much more precise than that of as many replications drawn from the whole space.
+///
+/// ```
+/// # use incerto::prelude::*;
+/// #[derive(Component)]
+/// struct Herd(usize);
+///
+/// // small herds are three times as common as large herds
+/// let stratification = Stratification::new([0.75, 0.25]);
+///
+/// let result = Simulation::replicate_stratified(
+///     &stratification,
+///     40,
+///     10,
+///     |stratum, _| {
+///         SimulationBuilder::new().add_entity_spawner(move |spawner| {
+///             spawner.spawn(Herd([10, 100][stratum]));
+///         })
+///     },
+///     |simulation| simulation.iter::<Herd>().next().unwrap().0 as f64,
+/// );
+///
+/// assert_eq!(result.strata()[0].len(), 30);
+/// assert_eq!(result.estimate(), Some(32.5));
+/// assert_eq!(result.standard_error(), Some(0.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stratification
+{
+    weights: Vec<f64>,
+    allocation: Allocation,
+}
+
+impl Stratification
+{
+    /// Creates a stratification with the given probability of every stratum, which are
+    /// normalized to sum up to `1`, and [`Allocation::Proportional`].
+    ///
+    /// # Panics
+    ///
+    /// If there are no weights, or any of them is not positive and finite.
+    #[must_use]
+    pub fn new(weights: impl IntoIterator<Item = f64>) -> Self
+    {
+        let mut weights = weights.into_iter().collect::<Vec<_>>();
+        assert!(
+            !weights.is_empty(),
+            "a stratification needs at least one stratum"
+        );
+        for weight in &weights
+        {
+            assert!(
+                weight.is_finite() && *weight > 0.0,
+                "invalid stratum weight: {weight}"
+            );
+        }
+
+        let total = weights.iter().sum::<f64>();
+        for weight in &mut weights
+        {
+            *weight /= total;
+        }
+
+        Self {
+            weights,
+            allocation: Allocation::default(),
+        }
+    }
+
+    /// Creates a stratification of `num_strata` equally likely strata.
+    ///
+    /// # Panics
+    ///
+    /// If `num_strata` is `0`.
+    #[must_use]
+    pub fn uniform(num_strata: usize) -> Self
+    {
+        Self::new(vec![1.0; num_strata])
+    }
+
+    /// Sets how the replications are allocated to the strata.
+    ///
+    /// # Panics
+    ///
+    /// If the pilot of an [`Allocation::Neyman`] has fewer than two replications, since the
+    /// standard deviations can not be estimated from fewer.
+    #[must_use]
+    pub fn with_allocation(mut self, allocation: Allocation) -> Self
+    {
+        if let Allocation::Neyman { pilot } = allocation
+        {
+            assert!(
+                pilot >= 2,
+                "the pilot needs at least two replications per stratum"
+            );
+        }
+        self.allocation = allocation;
+        self
+    }
+
+    /// The probability of every stratum, which sum up to `1`.
+    #[must_use]
+    pub fn weights(&self) -> &[f64]
+    {
+        &self.weights
+    }
+
+    /// The number of strata.
+    #[must_use]
+    pub const fn len(&self) -> usize
+    {
+        self.weights.len()
+    }
+
+    /// Always `false`, since there is at least one stratum.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool
+    {
+        self.weights.is_empty()
+    }
+
+    /// How the replications are allocated to the strata.
+    #[must_use]
+    pub const fn allocation(&self) -> Allocation
+    {
+        self.allocation
+    }
+
+    /// The number of replications that every stratum gets at least, i.e. two with proportional
+    /// allocation, so that the variance of every stratum can be estimated, or the pilot with
+    /// Neyman allocation.
+    pub(crate) const fn minimum(&self) -> usize
+    {
+        match self.allocation
+        {
+            Allocation::Proportional => 2,
+            Allocation::Neyman { pilot } => pilot,
+        }
+    }
+
+    /// The total number of replications of every stratum, out of `num_replicas`, given the
+    /// results of the replications run so far, i.e. the pilot with Neyman allocation.
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn allocate(&self, num_replicas: usize, strata: &[EnsembleResult]) -> Vec<usize>
+    {
+        let shares = match self.allocation
+        {
+            Allocation::Proportional => self.weights.clone(),
+            Allocation::Neyman { .. } =>
+            {
+                let shares = self
+                    .weights
+                    .iter()
+                    .zip(strata)
+                    .map(|(weight, stratum)| {
+                        let std_dev =
+                            stratum.standard_error().unwrap_or(0.0) * (stratum.len() as f64).sqrt();
+                        weight * std_dev
+                    })
+                    .collect::<Vec<_>>();
+
+                // strata that do not vary at all leave nothing to go by
+                if shares.iter().any(|share| *share > 0.0)
+                {
+                    shares
+                }
+                else
+                {
+                    self.weights.clone()
+                }
+            }
+        };
+
+        apportion(num_replicas, &shares, self.minimum())
+    }
+}
+
+/// Splits `total` into whole parts in proportion to `shares`, using the largest remainder method,
+/// and then moves units from the largest parts to those below `minimum`.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn apportion(total: usize, shares: &[f64], minimum: usize) -> Vec<usize>
+{
+    let sum = shares.iter().sum::<f64>();
+    let quotas = shares
+        .iter()
+        .map(|share| total as f64 * share / sum)
+        .collect::<Vec<_>>();
+    let mut parts = quotas
+        .iter()
+        .map(|quota| quota.floor() as usize)
+        .collect::<Vec<_>>();
+
+    let mut remainders = (0..shares.len()).collect::<Vec<_>>();
+    remainders.sort_by(|a, b| quotas[*b].fract().total_cmp(&quotas[*a].fract()));
+    let left = total - parts.iter().sum::<usize>();
+    for part in remainders.into_iter().take(left)
+    {
+        parts[part] += 1;
+    }
+
+    while let Some(short) = parts.iter().position(|part| *part < minimum)
+    {
+        let largest = (0..parts.len())
+            .max_by_key(|part| parts[*part])
+            .unwrap_or_default();
+        parts[largest] -= 1;
+        parts[short] += 1;
+    }
+    parts
+}
+
+/// The outcomes of a stratified experiment, as returned by
+/// [`crate::Simulation::replicate_stratified`].
+///
+/// The estimate of the expected outcome is the sum of the estimates of every stratum, weighted
+/// by the probabilities of the strata, so that it is unbiased regardless of how many
+/// replications each of them got.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StratifiedResult
+{
+    weights: Vec<f64>,
+    strata: Vec<EnsembleResult>,
+}
+
+impl StratifiedResult
+{
+    /// Creates a result from the outcomes of every stratum of the given stratification.
+    ///
+    /// # Panics
+    ///
+    /// If the number of strata differs from that of the stratification.
+    #[must_use]
+    pub fn new(stratification: &Stratification, strata: Vec<EnsembleResult>) -> Self
+    {
+        assert_eq!(
+            strata.len(),
+            stratification.len(),
+            "there must be one result per stratum"
+        );
+        Self {
+            weights: stratification.weights.clone(),
+            strata,
+        }
+    }
+
+    /// The outcomes of every stratum, in order.
+    #[must_use]
+    pub fn strata(&self) -> &[EnsembleResult]
+    {
+        &self.strata
+    }
+
+    /// The probability of every stratum.
+    #[must_use]
+    pub fn weights(&self) -> &[f64]
+    {
+        &self.weights
+    }
+
+    /// The total number of replications across all strata.
+    #[must_use]
+    pub fn len(&self) -> usize
+    {
+        self.strata.iter().map(EnsembleResult::len).sum()
+    }
+
+    /// Checks whether there are no replications in any stratum.
+    #[must_use]
+    pub fn is_empty(&self) -> bool
+    {
+        self.strata.iter().all(EnsembleResult::is_empty)
+    }
+
+    /// The stratified estimate of the expected outcome, or `None` if any stratum has no
+    /// replications.
+    #[must_use]
+    pub fn estimate(&self) -> Option<f64>
+    {
+        self.weights
+            .iter()
+            .zip(&self.strata)
+            .map(|(weight, stratum)| Some(weight * stratum.estimate()?))
+            .sum()
+    }
+
+    /// The standard error of the [`Self::estimate`], or `None` if that of any stratum can not be
+    /// estimated.
+    #[must_use]
+    pub fn standard_error(&self) -> Option<f64>
+    {
+        self.standard_error_with_df()
+            .map(|(standard_error, _)| standard_error)
+    }
+
+    fn standard_error_with_df(&self) -> Option<(f64, usize)>
+    {
+        let mut variance = 0.0;
+        let mut degrees_of_freedom = 0;
+        for (weight, stratum) in self.weights.iter().zip(&self.strata)
+        {
+            let (standard_error, df) = stratum.standard_error_with_df()?;
+            variance += (weight * standard_error).powi(2);
+            degrees_of_freedom += df;
+        }
+        Some((variance.sqrt(), degrees_of_freedom))
+    }
+
+    /// The confidence interval of the [`Self::estimate`] at the given `level`, e.g. `0.95`,
+    /// from the Student's t-distribution with the degrees of freedom of all strata combined.
+    ///
+    /// Returns `None` if the standard error of any stratum can not be estimated.
+    ///
+    /// # Panics
+    ///
+    /// If `level` is not within `(0, 1)`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn confidence_interval(&self, level: f64) -> Option<ConfidenceInterval>
+    {
+        assert_level(level);
+        let estimate = self.estimate()?;
+        let (standard_error, degrees_of_freedom) = self.standard_error_with_df()?;
+        let quantile = student_t_quantile(0.5 + level / 2.0, degrees_of_freedom as f64);
+
+        Some(ConfidenceInterval {
+            estimate,
+            lower: estimate - quantile * standard_error,
+            upper: estimate + quantile * standard_error,
+            level,
+        })
+    }
+}
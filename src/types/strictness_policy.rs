@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+
+/// Decides how recoverable anomalies in a simulation are handled.
+///
+/// Set using [`crate::SimulationBuilder::set_strictness`], and accessible in user-defined systems
+/// using [`Res<StrictnessPolicy>`] arguments, so that custom checks may follow the same policy.
+///
+/// The anomalies affected are:
+/// - Aggregate sampling with no entities, i.e. [`crate::SamplingError::AggregateNoEntities`].
+/// - More than one entity sharing an identifier, i.e. [`crate::SamplingError::EntityIdentifierNotUnique`].
+/// - Entities moved outside the bounds of a spatial grid, i.e. [`crate::BoundsViolation`].
+/// - Commodities not conserved by their stocks, i.e. [`crate::flow::ConservationViolation`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StrictnessPolicy
+{
+    /// Anomalies are surfaced as errors or events, and the simulation carries on.
+    ///
+    /// This suits production batch runs, which should not be aborted by a single anomaly.
+    #[default]
+    Lenient,
+
+    /// Anomalies panic immediately.
+    ///
+    /// This suits development, where mistakes should be caught as early as possible.
+    Strict,
+}
+
+impl StrictnessPolicy
+{
+    /// Returns `true` if anomalies should panic.
+    #[must_use]
+    pub fn is_strict(self) -> bool
+    {
+        self == Self::Strict
+    }
+}
@@ -1,10 +1,46 @@
+use crate::BatchMeans;
+
+/// A view over a time series recorded in the simulation.
+///
+/// The series borrows the samples directly from the simulation's storage,
+/// so retrieving it is cheap regardless of its length, and it can be freely copied.
 pub struct TimeSeries<'a, T>
 {
-    pub(crate) values: Vec<&'a T>,
-    pub(crate) time: Vec<usize>,
+    pub(crate) values: &'a [T],
+    pub(crate) time: &'a [usize],
     pub(crate) sample_interval: usize,
 }
 
+impl<T> Clone for TimeSeries<'_, T>
+{
+    fn clone(&self) -> Self
+    {
+        *self
+    }
+}
+
+impl<T> Copy for TimeSeries<'_, T> {}
+
+impl<'a, T> TimeSeries<'a, T>
+{
+    /// Returns the values in the time series as a contiguous slice.
+    ///
+    /// The value at every index was sampled at the step found at the same index of [`Self::time_slice`].
+    #[must_use]
+    pub const fn values_slice(&self) -> &'a [T]
+    {
+        self.values
+    }
+
+    /// Returns the simulation steps at which the values in the time series were sampled,
+    /// as a contiguous slice.
+    #[must_use]
+    pub const fn time_slice(&self) -> &'a [usize]
+    {
+        self.time
+    }
+}
+
 impl<T> TimeSeries<'_, T>
 {
     /// The number of samples in the time series.
@@ -30,12 +66,7 @@ impl<T> TimeSeries<'_, T>
     #[must_use]
     pub fn duration(&self) -> usize
     {
-        if self.is_empty()
-        {
-            return 0;
-        }
-
-        self.time[self.time.len() - 1]
+        self.time.last().copied().unwrap_or_default()
     }
 
     /// Returns the sample interval with which this time series was sampled.
@@ -65,7 +96,18 @@ impl<T> TimeSeries<'_, T>
     /// no components with the sampled component existed in the simulation.
     pub fn values(&self) -> impl Iterator<Item = &T>
     {
-        self.values.iter().copied()
+        self.values.iter()
+    }
+
+    /// Returns the value that was sampled at the given simulation `step`.
+    ///
+    /// Returns `None` if no sample was taken at that step, for example when `step`
+    /// is not a multiple of the series' [`Self::sample_interval`].
+    #[must_use]
+    pub fn value_at(&self, step: usize) -> Option<&T>
+    {
+        let idx = self.time.binary_search(&step).ok()?;
+        Some(&self.values[idx])
     }
 
     /// Iterates over each time-value point in the time series.
@@ -75,6 +117,21 @@ impl<T> TimeSeries<'_, T>
     }
 }
 
+impl<T: Clone> TimeSeries<'_, T>
+{
+    /// Copies the samples of the time series into an [`OwnedTimeSeries`],
+    /// which no longer borrows from the simulation.
+    #[must_use]
+    pub fn into_owned(self) -> OwnedTimeSeries<T>
+    {
+        OwnedTimeSeries {
+            values: self.values.to_vec(),
+            time: self.time.to_vec(),
+            sample_interval: self.sample_interval,
+        }
+    }
+}
+
 impl<T> TimeSeries<'_, T>
 where
     T: Copy,
@@ -91,3 +148,48 @@ where
         self.time().zip(self.values_copied())
     }
 }
+
+impl TimeSeries<'_, f64>
+{
+    /// The batch-means estimate of the steady-state value of the series, with its initial
+    /// transient left out.
+    ///
+    /// Returns `None` if fewer than 10 samples remain after the initial transient.
+    /// See [`BatchMeans`] for details.
+    ///
+    /// # Panics
+    ///
+    /// If any of the samples is not finite.
+    #[must_use]
+    pub fn batch_means(&self) -> Option<BatchMeans>
+    {
+        BatchMeans::new(self.values_copied())
+    }
+}
+
+/// A time series that owns its samples, created with [`TimeSeries::into_owned`].
+///
+/// This can outlive the simulation it was recorded in, for example to be persisted
+/// or sent elsewhere with the `serde` feature enabled.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedTimeSeries<T>
+{
+    pub(crate) values: Vec<T>,
+    pub(crate) time: Vec<usize>,
+    pub(crate) sample_interval: usize,
+}
+
+impl<T> OwnedTimeSeries<T>
+{
+    /// Borrows the owned samples as a [`TimeSeries`], giving access to all of its methods.
+    #[must_use]
+    pub fn as_time_series(&self) -> TimeSeries<'_, T>
+    {
+        TimeSeries {
+            values: &self.values,
+            time: &self.time,
+            sample_interval: self.sample_interval,
+        }
+    }
+}
@@ -6,7 +6,7 @@ use std::{
 
 use bevy::prelude::Deref;
 
-use crate::{SampleAggregate, prelude::*};
+use crate::{Combiner, SampleAggregate, prelude::*};
 
 /// Utility aggregator that fetches the minimum value.
 ///
@@ -39,7 +39,8 @@ pub struct Maximum<T>(T);
 /// ```
 ///
 /// Note that computing float medians will panic if any of the samples being aggregated are not
-/// comparable (e.g `NaN`).
+/// comparable (e.g `NaN`). Such values can be caught as soon as they appear using
+/// [`SimulationBuilder::add_numeric_guard`].
 #[derive(Debug, Deref, Clone, Copy, PartialEq, Eq, PartialOrd)]
 pub struct Median<T>(T);
 
@@ -47,6 +48,11 @@ pub struct Median<T>(T);
 ///
 /// Implemented automatically for any numeric type `T` such as [`i16`], [`f32`], etc.
 ///
+/// The values are summed in a wider type, such as [`u128`] for unsigned integers and [`f64`] for
+/// [`f32`], so that the mean of even very large populations does not overflow.
+/// For [`u128`] and [`i128`], the sum falls back to [`f64`] if it would overflow, in which case
+/// the mean is approximate. Integer means are rounded towards zero.
+///
 /// ```ignore
 /// let mean = simulation.sample_aggregate::<MyComponent, Option<Mean<f32>>>().unwrap();
 /// ```
@@ -85,6 +91,15 @@ where
             .expect("sample_aggregate called with empty slice");
         Minimum(*min)
     }
+
+    const COMBINE: Option<Combiner<Minimum<O>>> = Some(|partials| {
+        let min = partials
+            .into_iter()
+            .map(|(Minimum(v), _)| sealed::Ordered(v))
+            .min()
+            .expect("combine called with no partial aggregates");
+        Minimum(*min)
+    });
 }
 
 impl<T, O> SampleAggregate<Maximum<O>> for T
@@ -102,6 +117,15 @@ where
             .expect("sample_aggregate called with empty slice");
         Maximum(*max)
     }
+
+    const COMBINE: Option<Combiner<Maximum<O>>> = Some(|partials| {
+        let max = partials
+            .into_iter()
+            .map(|(Maximum(v), _)| sealed::Ordered(v))
+            .max()
+            .expect("combine called with no partial aggregates");
+        Maximum(*max)
+    });
 }
 
 impl<O, const P: u8> Percentile<O, P>
@@ -155,6 +179,60 @@ where
 }
 
 macro_rules! blanket_impl_sample_aggr_mean {
+    // floating point means can also be merged, by weighting each partial mean by its count
+    ($t: tt as $wide: tt, weighted) => {
+        blanket_impl_sample_aggr_mean!(
+            $t as $wide,
+            Some(|partials| {
+                let cnt: usize = partials.iter().map(|&(_, cnt)| cnt).sum();
+                let sum: $wide = partials
+                    .into_iter()
+                    .map(|(Mean(mean), cnt)| mean as $wide * cnt as $wide)
+                    .sum();
+
+                Mean((sum / cnt as $wide) as $t)
+            })
+        );
+    };
+
+    // accumulate in a wider type, so that the sum cannot overflow
+    ($t: tt as $wide: tt) => {
+        blanket_impl_sample_aggr_mean!($t as $wide, None);
+    };
+
+    ($t: tt as $wide: tt, $combine: expr) => {
+        impl<T> SampleAggregate<Mean<$t>> for T
+        where
+            T: Sample<$t>,
+        {
+            #[allow(clippy::cast_lossless)]
+            #[allow(clippy::cast_precision_loss)]
+            #[allow(clippy::cast_possible_truncation)]
+            const COMBINE: Option<Combiner<Mean<$t>>> = $combine;
+
+            #[allow(clippy::cast_lossless)]
+            #[allow(clippy::cast_precision_loss)]
+            #[allow(clippy::cast_possible_wrap)]
+            #[allow(clippy::cast_possible_truncation)]
+            fn sample_aggregate(components: &[&Self]) -> Mean<$t>
+            {
+                assert!(!components.is_empty());
+
+                let sum: $wide = components
+                    .iter()
+                    .map(|&c| Sample::<$t>::sample(c) as $wide)
+                    .sum();
+                let cnt = components.len() as $wide;
+
+                // the mean is always within the range of the original type
+                let mean = sum / cnt;
+
+                Mean(mean as $t)
+            }
+        }
+    };
+
+    // there is no wider type, so fall back to floating point if the sum overflows
     ($t: tt) => {
         impl<T> SampleAggregate<Mean<$t>> for T
         where
@@ -163,33 +241,43 @@ macro_rules! blanket_impl_sample_aggr_mean {
             #[allow(clippy::cast_precision_loss)]
             #[allow(clippy::cast_possible_wrap)]
             #[allow(clippy::cast_possible_truncation)]
+            #[allow(clippy::cast_sign_loss)]
             fn sample_aggregate(components: &[&Self]) -> Mean<$t>
             {
                 assert!(!components.is_empty());
 
-                let sum: $t = components.iter().map(|&c| Sample::sample(c)).sum();
+                let values = components.iter().map(|&c| Sample::<$t>::sample(c));
                 let cnt = components.len() as $t;
 
-                let mean = sum / cnt;
+                let mean = values
+                    .clone()
+                    .try_fold(0 as $t, <$t>::checked_add)
+                    .map_or_else(
+                        || {
+                            let sum: f64 = values.map(|v| v as f64).sum();
+                            (sum / components.len() as f64) as $t
+                        },
+                        |sum| sum / cnt,
+                    );
 
                 Mean(mean)
             }
         }
     };
 }
-blanket_impl_sample_aggr_mean!(usize);
-blanket_impl_sample_aggr_mean!(u8);
-blanket_impl_sample_aggr_mean!(u16);
-blanket_impl_sample_aggr_mean!(u32);
-blanket_impl_sample_aggr_mean!(u64);
+blanket_impl_sample_aggr_mean!(usize as u128);
+blanket_impl_sample_aggr_mean!(u8 as u128);
+blanket_impl_sample_aggr_mean!(u16 as u128);
+blanket_impl_sample_aggr_mean!(u32 as u128);
+blanket_impl_sample_aggr_mean!(u64 as u128);
 blanket_impl_sample_aggr_mean!(u128);
-blanket_impl_sample_aggr_mean!(i8);
-blanket_impl_sample_aggr_mean!(i16);
-blanket_impl_sample_aggr_mean!(i32);
-blanket_impl_sample_aggr_mean!(i64);
+blanket_impl_sample_aggr_mean!(i8 as i128);
+blanket_impl_sample_aggr_mean!(i16 as i128);
+blanket_impl_sample_aggr_mean!(i32 as i128);
+blanket_impl_sample_aggr_mean!(i64 as i128);
 blanket_impl_sample_aggr_mean!(i128);
-blanket_impl_sample_aggr_mean!(f32);
-blanket_impl_sample_aggr_mean!(f64);
+blanket_impl_sample_aggr_mean!(f32 as f64, weighted);
+blanket_impl_sample_aggr_mean!(f64 as f64, weighted);
 
 mod sealed
 {
@@ -0,0 +1,175 @@
+use std::{
+    any::type_name,
+    marker::PhantomData,
+    ops::ControlFlow,
+    sync::{
+        Mutex, PoisonError,
+        mpsc::{self, Receiver},
+    },
+};
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::{
+    plugins::{GridBounds2D, GridPosition2D, SpatialGrid, StepEndHooks},
+    window::WindowPanels,
+};
+
+/// The size of the longest side of a grid view, in logical pixels.
+const VIEW_SIZE: f32 = 480.0;
+
+/// The color of the cells of a grid view that are not occupied by any entity.
+const BACKGROUND: egui::Color32 = egui::Color32::from_gray(24);
+
+/// A snapshot of the occupied cells of a spatial grid, sent to its view at the end of a step.
+struct GridFrame
+{
+    step: usize,
+    bounds: GridBounds2D,
+    cells: Vec<(IVec2, egui::Color32)>,
+}
+
+/// Adds a view of the 2D spatial grid of components `C` to the window of the simulation,
+/// and sets up the simulation to send the colored cells of the grid to it every `interval` steps.
+pub fn add<C: Component>(
+    app: &mut App,
+    interval: usize,
+    color_fn: impl Fn(&C) -> Color + Send + Sync + 'static,
+)
+{
+    let (sender, receiver) = mpsc::channel();
+    let (sender, receiver) = (Mutex::new(sender), Mutex::new(receiver));
+
+    app.world_mut()
+        .get_resource_or_init::<StepEndHooks>()
+        .push(move |world, step| {
+            if step % interval == 0
+                && let Some(frame) = grid_frame(world, step, &color_fn)
+            {
+                // the window may have been closed, in which case the frame is simply dropped
+                let _ = sender
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .send(frame);
+            }
+            ControlFlow::Continue(())
+        });
+    app.world_mut()
+        .get_resource_or_init::<WindowPanels>()
+        .push(move |app| {
+            app.insert_resource(GridViewState::<C> {
+                receiver,
+                latest: None,
+                _phantom: PhantomData,
+            })
+            .add_systems(EguiPrimaryContextPass, draw_grid_view::<C>);
+        });
+}
+
+/// Colors the occupied cells of the spatial grid of components `C`.
+///
+/// Returns `None` if the grid does not exist, or if it has no bounds and no entities.
+fn grid_frame<C: Component>(
+    world: &World,
+    step: usize,
+    color_fn: &impl Fn(&C) -> Color,
+) -> Option<GridFrame>
+{
+    let grid = world.get_resource::<SpatialGrid<IVec2, C>>()?;
+    let mut query = world.try_query::<(&GridPosition2D, &C)>()?;
+
+    // the grid itself is only updated at the start of the next step, so positions are read directly
+    let cells = query
+        .iter(world)
+        .filter(|(position, _)| grid.in_bounds(**position))
+        .map(|(position, component)| {
+            let [r, g, b, a] = color_fn(component).to_srgba().to_u8_array();
+            (
+                position.0,
+                egui::Color32::from_rgba_unmultiplied(r, g, b, a),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let bounds = grid.bounds().or_else(|| {
+        let positions = cells.iter().map(|&(position, _)| position);
+        Some(GridBounds2D {
+            min: positions.clone().reduce(IVec2::min)?,
+            max: positions.reduce(IVec2::max)?,
+        })
+    })?;
+
+    Some(GridFrame {
+        step,
+        bounds,
+        cells,
+    })
+}
+
+/// The state of the view of the spatial grid of components `C`.
+#[derive(Resource)]
+struct GridViewState<C: Component>
+{
+    receiver: Mutex<Receiver<GridFrame>>,
+    latest: Option<GridFrame>,
+    _phantom: PhantomData<C>,
+}
+
+/// Draws the latest frame received for the spatial grid of components `C`.
+///
+/// Frames that were received since the last draw but are already outdated are skipped.
+/// When multiple entities occupy the same cell, the one drawn last covers the others.
+#[allow(clippy::cast_precision_loss)]
+fn draw_grid_view<C: Component>(
+    mut contexts: EguiContexts,
+    mut state: ResMut<GridViewState<C>>,
+) -> Result
+{
+    let received = state
+        .receiver
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .try_iter()
+        .last();
+    if received.is_some()
+    {
+        state.latest = received;
+    }
+
+    egui::Window::new(type_name::<C>()).show(contexts.ctx_mut()?, |ui| {
+        let Some(frame) = &state.latest
+        else
+        {
+            ui.label("waiting for the simulation to start...");
+            return;
+        };
+
+        ui.label(format!("step {}", frame.step));
+
+        let size = (frame.bounds.max - frame.bounds.min + IVec2::ONE).as_vec2();
+        let cell_size = VIEW_SIZE / size.max_element();
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(size.x * cell_size, size.y * cell_size),
+            egui::Sense::hover(),
+        );
+        let origin = response.rect.min;
+
+        painter.rect_filled(response.rect, 0.0, BACKGROUND);
+        for &(position, color) in &frame.cells
+        {
+            // the y axis points up in the grid but down on the screen
+            let x = (position.x - frame.bounds.min.x) as f32;
+            let y = (frame.bounds.max.y - position.y) as f32;
+            let min = origin + egui::vec2(x * cell_size, y * cell_size);
+
+            painter.rect_filled(
+                egui::Rect::from_min_size(min, egui::vec2(cell_size, cell_size)),
+                0.0,
+                color,
+            );
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::{
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    ops::ControlFlow,
+    sync::{
+        Arc, Mutex, PoisonError, Weak,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+};
+
+use bevy::prelude::*;
+use serde_json::json;
+use tungstenite::{Message, WebSocket};
+
+use crate::{error::BuilderError, plugins::StepEndHooks};
+
+/// Reads the points of a streamed time series from the simulation's world, starting from
+/// the given index in the series, along with the total number of points in it.
+type StreamSource = fn(&World, usize) -> (Vec<(usize, f64)>, usize);
+
+type Clients = Mutex<Vec<WebSocket<TcpStream>>>;
+
+/// Resource holding the time series streamed to the clients of the WebSocket endpoint.
+#[derive(Resource, Default)]
+pub struct StreamedSeries(Vec<(&'static str, StreamSource)>);
+
+impl StreamedSeries
+{
+    pub fn push(&mut self, name: &'static str, source: StreamSource)
+    {
+        self.0.push((name, source));
+    }
+}
+
+/// Binds the WebSocket endpoint to the given address, and sets up the simulation to send
+/// the newly recorded points of its streamed time series at the end of its steps.
+///
+/// Clients are accepted on one background thread and the messages are sent on another,
+/// so that slow clients never hold back the simulation. Both stop once the simulation
+/// has been dropped.
+///
+/// # Errors
+///
+/// - [`BuilderError::StreamEndpointUnavailable`]
+pub fn add(app: &mut App, address: impl ToSocketAddrs) -> Result<(), BuilderError>
+{
+    let listener =
+        TcpListener::bind(address).map_err(|_| BuilderError::StreamEndpointUnavailable)?;
+    let clients = Arc::new(Clients::default());
+    let (sender, receiver) = mpsc::channel();
+
+    app.init_resource::<StreamedSeries>();
+    app.world_mut()
+        .get_resource_or_init::<StepEndHooks>()
+        .push(message_sender(sender));
+
+    let accepted = Arc::downgrade(&clients);
+    thread::spawn(move || accept(&listener, &accepted));
+    thread::spawn(move || broadcast(&receiver, &clients));
+
+    Ok(())
+}
+
+/// Creates a step-end hook that sends the newly recorded points of every streamed time series
+/// through the given `sender`, as JSON messages.
+///
+/// The hook never stops the simulation, even if no clients are connected.
+fn message_sender(
+    sender: Sender<String>,
+) -> impl FnMut(&World, usize) -> ControlFlow<()> + Send + Sync
+{
+    let sender = Mutex::new(sender);
+    let mut sent = Vec::new();
+
+    move |world, _| {
+        let Some(streams) = world.get_resource::<StreamedSeries>()
+        else
+        {
+            return ControlFlow::Continue(());
+        };
+        sent.resize(streams.0.len(), 0);
+
+        for (&(name, source), sent) in streams.0.iter().zip(&mut sent)
+        {
+            let (points, len) = source(world, *sent);
+            *sent = len;
+            if points.is_empty()
+            {
+                continue;
+            }
+
+            let message = json!({ "series": name, "points": points }).to_string();
+
+            // the broadcasting thread only stops along with the simulation
+            let _ = sender
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .send(message);
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// Completes the WebSocket handshake of every connection made to the `listener`, and adds it
+/// to the `clients`, until they are dropped along with the simulation.
+fn accept(listener: &TcpListener, clients: &Weak<Clients>)
+{
+    for stream in listener.incoming()
+    {
+        let Some(clients) = clients.upgrade()
+        else
+        {
+            return;
+        };
+
+        // connections that are not WebSocket requests are simply dropped
+        if let Ok(client) = stream
+            .map_err(drop)
+            .and_then(|s| tungstenite::accept(s).map_err(drop))
+        {
+            clients
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(client);
+        }
+    }
+}
+
+/// Sends every message received from the simulation to all connected `clients`,
+/// dropping the ones that have disconnected.
+fn broadcast(receiver: &Receiver<String>, clients: &Clients)
+{
+    for message in receiver
+    {
+        let message = Message::text(message);
+        clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain_mut(|client| client.send(message.clone()).is_ok());
+    }
+}
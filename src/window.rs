@@ -0,0 +1,69 @@
+use std::thread;
+
+use bevy::{
+    prelude::*,
+    winit::{WakeUp, WinitPlugin},
+};
+use bevy_egui::EguiPlugin;
+
+type PanelSetup = Box<dyn FnOnce(&mut App) + Send + Sync>;
+
+/// Resource holding the setup of the panels shown in the window opened alongside the simulation.
+///
+/// Since only one window event loop may exist per process, all panels, such as the dashboard
+/// and the grid views, are drawn as egui windows inside the same native window.
+#[derive(Resource, Default)]
+pub struct WindowPanels(Vec<PanelSetup>);
+
+impl WindowPanels
+{
+    /// Adds a panel, whose `setup` is called on the app of the window before it starts running.
+    pub fn push(&mut self, setup: impl FnOnce(&mut App) + Send + Sync + 'static)
+    {
+        self.0.push(Box::new(setup));
+    }
+}
+
+/// Opens the window on a separate thread, if any panels were added to the simulation.
+///
+/// The window runs until it is closed, independently of the simulation.
+pub fn open(app: &mut App)
+{
+    let Some(panels) = app.world_mut().remove_resource::<WindowPanels>()
+    else
+    {
+        return;
+    };
+
+    thread::spawn(move || run_window(panels));
+}
+
+fn run_window(panels: WindowPanels)
+{
+    let mut winit = WinitPlugin::<WakeUp>::default();
+    winit.run_on_any_thread = true;
+
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "incerto".to_string(),
+                    ..default()
+                }),
+                ..default()
+            })
+            .set(winit),
+    )
+    .add_plugins(EguiPlugin::default())
+    .add_systems(Startup, |mut commands: Commands| {
+        commands.spawn(Camera2d);
+    });
+
+    for setup in panels.0
+    {
+        setup(&mut app);
+    }
+
+    app.run();
+}
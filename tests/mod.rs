@@ -1,6 +1,43 @@
 #![allow(clippy::expect_used)]
 
 mod test_aggregates;
+mod test_batch_means;
 mod test_builder;
+mod test_calendar;
+mod test_cellular;
+mod test_comparison;
+mod test_contact_layers;
+mod test_convergence_diagnostic;
 mod test_counter;
+mod test_datasets;
+mod test_demography;
+mod test_ensemble;
+mod test_epidemic;
+mod test_evolution;
+mod test_extreme_values;
+mod test_flocking;
+mod test_flow;
+mod test_fsm;
+mod test_geo;
+mod test_gillespie;
+#[cfg(feature = "gpu")]
+mod test_gpu;
+mod test_kernel;
+mod test_kernel_density;
+mod test_market;
+mod test_network;
+mod test_opinion;
+mod test_placement;
+mod test_policy;
+mod test_queueing;
+mod test_random_walk;
+mod test_reaction_diffusion;
+mod test_replication_plan;
+mod test_sde;
+mod test_simulation;
 mod test_spatial_grid;
+mod test_spin;
+mod test_stratification;
+mod test_topology;
+mod test_trace;
+mod test_traffic;
@@ -88,3 +88,231 @@ fn test_aggregates_float() -> Result<(), SimulationError>
 
     Ok(())
 }
+
+#[test]
+fn test_mean_does_not_overflow() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Byte(u8);
+
+    impl Sample<u8> for Byte
+    {
+        fn sample(component: &Self) -> u8
+        {
+            component.0
+        }
+    }
+
+    #[derive(Component)]
+    struct Huge(i128);
+
+    impl Sample<i128> for Huge
+    {
+        fn sample(component: &Self) -> i128
+        {
+            component.0
+        }
+    }
+
+    let simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for i in 0..1000_u16
+            {
+                spawner.spawn(Byte(250 + u8::from(i % 2 == 0)));
+                spawner.spawn(Huge(i128::MAX - 1));
+            }
+        })
+        .build();
+
+    let mean = simulation.sample_aggregate::<Byte, Mean<_>>()?;
+    assert_eq!(*mean, 250);
+
+    // the sum overflows even i128, so the mean is approximated in floating point
+    let mean = *simulation.sample_aggregate::<Huge, Mean<_>>()?;
+    #[allow(clippy::cast_precision_loss)]
+    let mean = mean as f64 / i128::MAX as f64;
+    assert!((mean - 1.0).abs() < 1e-9);
+
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::cast_precision_loss)]
+fn test_parallel_aggregates() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Counted;
+
+    // counts the components, merging partial counts by summing them
+    impl SampleAggregate<usize> for Counted
+    {
+        fn sample_aggregate(components: &[&Self]) -> usize
+        {
+            components.len()
+        }
+
+        const COMBINE: Option<Combiner<usize>> =
+            Some(|partials| partials.into_iter().map(|(count, _)| count).sum());
+    }
+
+    fn spawn_items(spawner: &mut Spawner)
+    {
+        for i in 0..1000
+        {
+            spawner.spawn((Item(i), ItemFloat(i as f32 / 4.0), Counted));
+        }
+    }
+
+    let sequential = SimulationBuilder::new()
+        .add_entity_spawner(spawn_items)
+        .build();
+    let parallel = SimulationBuilder::new()
+        .parallel_sampling_threshold(64)
+        .add_entity_spawner(spawn_items)
+        .record_aggregate_time_series::<ItemFloat, Maximum<f32>>(1)?
+        .build();
+
+    assert_eq!(
+        parallel.sample_aggregate::<Item, Minimum<usize>>()?,
+        sequential.sample_aggregate::<Item, Minimum<usize>>()?
+    );
+    assert_eq!(
+        parallel.sample_aggregate::<Item, Maximum<usize>>()?,
+        sequential.sample_aggregate::<Item, Maximum<usize>>()?
+    );
+    assert_eq!(
+        parallel.sample_aggregate::<Item, Mean<usize>>()?,
+        sequential.sample_aggregate::<Item, Mean<usize>>()?
+    );
+    assert!(
+        (*parallel.sample_aggregate::<ItemFloat, Mean<f32>>()?
+            - *sequential.sample_aggregate::<ItemFloat, Mean<f32>>()?)
+        .abs()
+            < 1e-3
+    );
+    assert_eq!(parallel.sample_aggregate::<Counted, usize>()?, 1000);
+
+    let mut parallel = parallel;
+    parallel.run(1);
+    let time_series = parallel.get_aggregate_time_series::<ItemFloat, Maximum<f32>>()?;
+    assert_eq!(
+        time_series.values().map(|max| **max).collect::<Vec<_>>(),
+        vec![249.75]
+    );
+
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn test_incremental_aggregate() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Score(u32);
+
+    impl Sample<u32> for Score
+    {
+        fn sample(component: &Self) -> u32
+        {
+            component.0
+        }
+    }
+
+    // on step n, awards a bonus only to the score equal to n
+    fn award_bonus(mut query: Query<&mut Score>, step: Res<StepNumber>)
+    {
+        for mut score in &mut query
+        {
+            if u32::try_from(step.get()) == Ok(score.0)
+            {
+                score.0 += 100;
+            }
+        }
+    }
+
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for i in 1..=10
+            {
+                spawner.spawn(Score(i));
+            }
+        })
+        .add_systems(award_bonus)
+        .track_incremental_aggregate::<Score, u32>()
+        .build();
+
+    // nothing is accounted for before the first step
+    let aggregate = simulation.incremental_aggregate::<Score, u32>()?;
+    assert_eq!(aggregate.count(), 0);
+    assert_eq!(aggregate.mean(), None);
+
+    simulation.run(3);
+    let aggregate = simulation.incremental_aggregate::<Score, u32>()?;
+    assert_eq!(aggregate.count(), 10);
+    assert_eq!(aggregate.sum(), 355.0);
+    assert_eq!(
+        aggregate.sum(),
+        simulation
+            .iter::<Score>()
+            .map(|score| f64::from(score.0))
+            .sum::<f64>()
+    );
+
+    simulation.despawn_where::<With<Score>>();
+    let aggregate = simulation.incremental_aggregate::<Score, u32>()?;
+    assert_eq!(aggregate.count(), 0);
+    assert_eq!(aggregate.sum(), 0.0);
+
+    assert_eq!(
+        simulation
+            .incremental_aggregate::<ItemFloat, f32>()
+            .err()
+            .map(|err| err.kind()),
+        Some(SamplingErrorKind::NotRecorded)
+    );
+
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn test_incremental_aggregate_of_counts() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Offspring(usize);
+
+    impl Sample<usize> for Offspring
+    {
+        fn sample(component: &Self) -> usize
+        {
+            component.0
+        }
+    }
+
+    fn reproduce(mut query: Query<&mut Offspring>)
+    {
+        for mut offspring in &mut query
+        {
+            offspring.0 += 1;
+        }
+    }
+
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for i in 0..4
+            {
+                spawner.spawn(Offspring(i));
+            }
+        })
+        .add_systems(reproduce)
+        .track_incremental_aggregate::<Offspring, usize>()
+        .build();
+
+    simulation.run(2);
+    let aggregate = simulation.incremental_aggregate::<Offspring, usize>()?;
+    assert_eq!(aggregate.count(), 4);
+    assert_eq!(aggregate.sum(), 14.0);
+    assert_eq!(aggregate.mean(), Some(3.5));
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::prelude::*;
+use rand::{Rng, SeedableRng};
+
+#[derive(Component)]
+struct Queue(f64);
+
+impl SampleAggregate<f64> for Queue
+{
+    fn sample_aggregate(components: &[&Self]) -> f64
+    {
+        components.iter().map(|queue| queue.0).sum()
+    }
+}
+
+#[test]
+fn test_mser_truncation()
+{
+    // a transient of 50 values, followed by a constant steady state
+    let values = std::iter::repeat_n(100.0, 50)
+        .chain(std::iter::repeat_n(0.0, 500))
+        .collect::<Vec<_>>();
+    assert_eq!(BatchMeans::mser_truncation(&values), 50);
+
+    // the truncation is searched for within the first half
+    let values = std::iter::repeat_n(100.0, 300)
+        .chain(std::iter::repeat_n(0.0, 100))
+        .collect::<Vec<_>>();
+    assert!(BatchMeans::mser_truncation(&values) <= 200);
+
+    assert_eq!(BatchMeans::mser_truncation(&[1.0, 2.0, 3.0]), 0);
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+    let stationary = (0..1000)
+        .map(|_| rng.random_range(0.0..1.0))
+        .collect::<Vec<_>>();
+    assert!(BatchMeans::mser_truncation(&stationary) < 100);
+}
+
+#[test]
+fn test_independent_values()
+{
+    let mut rng = rand::rngs::StdRng::seed_from_u64(4);
+    let means = BatchMeans::new((0..6400).map(|_| rng.random_range(0.0..1.0))).expect("none");
+
+    // the batches are already independent at the initial size
+    assert_eq!(means.batch_size(), (6400 - means.truncation()) / 64);
+    assert_eq!(means.batch_means().len(), 64);
+    assert!(means.autocorrelation() < 0.2);
+    assert!(means.confidence_interval(0.99).contains(0.5));
+}
+
+#[test]
+fn test_correlated_values()
+{
+    let mut rng = rand::rngs::StdRng::seed_from_u64(6);
+    let mut value = 0.0_f64;
+    let series = (0..20_000)
+        .map(|_| {
+            value = value.mul_add(0.99, rng.random_range(-1.0..1.0));
+            value
+        })
+        .collect::<Vec<_>>();
+
+    // the batches grow until their means are independent, or too few remain
+    let means = BatchMeans::new(series.iter().copied()).expect("none");
+    assert!(means.batch_size() > (20_000 - means.truncation()) / 64);
+    assert!(means.batch_means().len() >= 10);
+    assert!(means.autocorrelation() < 0.2 || means.batch_means().len() < 20);
+
+    let interval = means.confidence_interval(0.95);
+    assert_eq!(interval.estimate, means.mean());
+    assert!(interval.contains(0.0));
+}
+
+#[test]
+fn test_constant_values()
+{
+    let means = BatchMeans::new([3.0; 100]).expect("none");
+    assert_eq!(means.truncation(), 0);
+    assert_eq!(means.mean(), 3.0);
+    assert_eq!(means.standard_error(), 0.0);
+    assert_eq!(means.autocorrelation(), 0.0);
+    assert_eq!(means.confidence_interval(0.95).half_width(), 0.0);
+
+    assert_eq!(BatchMeans::new([3.0; 9]), None);
+}
+
+#[test]
+#[should_panic(expected = "invalid value")]
+fn test_invalid_value()
+{
+    let _ = BatchMeans::new([1.0, f64::INFINITY]);
+}
+
+#[test]
+fn test_time_series_batch_means()
+{
+    // a queue that starts long, and drains to a noisy steady state around 5
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(3)
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Queue(100.0));
+        })
+        .add_systems(|mut queues: Query<&mut Queue>, mut rng: ResMut<SimRng>| {
+            for mut queue in &mut queues
+            {
+                queue.0 = queue.0.mul_add(0.9, 0.5 + rng.random_range(-1.0..1.0));
+            }
+        })
+        .record_aggregate_time_series::<Queue, f64>(2)
+        .expect("failed to record")
+        .build();
+    simulation.run(4000);
+
+    let series = simulation
+        .get_aggregate_time_series::<Queue, f64>()
+        .expect("no time series");
+    let means = series.batch_means().expect("none");
+
+    // the transient lasts for a few dozen steps
+    let steady_step = series.time_slice()[means.truncation()];
+    assert!(steady_step > 0 && steady_step < 200);
+    assert!(means.confidence_interval(0.99).contains(5.0));
+}
@@ -1,4 +1,5 @@
 #![allow(clippy::expect_used)]
+use bevy::ecs::system::ScheduleSystem;
 use incerto::prelude::*;
 
 #[derive(Component, Default)]
@@ -15,6 +16,14 @@ impl SampleAggregate<usize> for MyValue
     }
 }
 
+impl Sample<usize> for MyValue
+{
+    fn sample(component: &Self) -> usize
+    {
+        component.0
+    }
+}
+
 impl SampleAggregate<bool> for MyValue
 {
     fn sample_aggregate(components: &[&Self]) -> bool
@@ -70,3 +79,215 @@ fn test_record_time_series_same_component_same_out_different_filter()
         .record_aggregate_time_series_filtered::<MyValue, (), usize>(8)
         .expect("second recording is expected to succeed");
 }
+
+#[test]
+fn test_errors_compose_with_std_error()
+{
+    fn build() -> Result<Simulation, Box<dyn std::error::Error + Send + Sync>>
+    {
+        let builder = SimulationBuilder::new().record_aggregate_time_series::<MyValue, usize>(1)?;
+        let builder = builder.record_aggregate_time_series::<MyValue, usize>(1)?;
+        Ok(builder.build())
+    }
+
+    let err = build()
+        .err()
+        .expect("the second recording should have errored");
+    assert_eq!(
+        err.to_string(),
+        "the time series for these component and output types is already being recorded"
+    );
+
+    let err = SimulationError::from(SamplingError::ComponentDoesNotExist {
+        component: std::any::type_name::<MyMarker>(),
+    });
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "sampling error: no entity with component {} was ever spawned",
+            std::any::type_name::<MyMarker>()
+        )
+    );
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+fn increment(mut query: Query<&mut MyValue>)
+{
+    for mut value in &mut query
+    {
+        value.0 += 1;
+    }
+}
+
+fn double(mut query: Query<&mut MyValue>)
+{
+    for mut value in &mut query
+    {
+        value.0 *= 2;
+    }
+}
+
+fn build_with_systems<M>(systems: impl IntoScheduleConfigs<ScheduleSystem, M>) -> Simulation
+{
+    SimulationBuilder::new()
+        .add_spatial_grid_2d::<MyValue>(None)
+        .record_aggregate_time_series::<MyValue, usize>(1)
+        .expect("recording is expected to succeed")
+        .track_population::<MyMarker>()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((MyValue(1), MyMarker));
+        })
+        .add_systems(systems)
+        .check_ambiguities(true)
+        .build()
+}
+
+#[test]
+fn test_check_ambiguities()
+{
+    let mut simulation = build_with_systems((increment, double).chain());
+    simulation.run(2);
+    assert_eq!(simulation.sample_aggregate::<MyValue, usize>(), Ok(10));
+}
+
+#[test]
+#[should_panic(expected = "conflict")]
+fn test_check_ambiguities_conflict()
+{
+    let _ = build_with_systems((increment, double));
+}
+
+#[test]
+fn test_check()
+{
+    #[derive(Resource)]
+    struct Market;
+
+    #[derive(Component)]
+    struct Unspawned;
+
+    impl SampleAggregate<bool> for Unspawned
+    {
+        fn sample_aggregate(_: &[&Self]) -> bool
+        {
+            true
+        }
+    }
+
+    fn trade(_market: Res<Market>) {}
+
+    let builder = |with_market: bool| {
+        let builder = SimulationBuilder::new()
+            .add_entity_spawner(|spawner| {
+                spawner.spawn(MyValue(1));
+            })
+            .add_systems(trade)
+            .record_aggregate_time_series::<MyValue, usize>(1)
+            .expect("recording is expected to succeed")
+            .record_aggregate_time_series::<Unspawned, bool>(1)
+            .expect("recording is expected to succeed");
+
+        if with_market
+        {
+            builder.add_resource(Market)
+        }
+        else
+        {
+            builder
+        }
+    };
+
+    let issues = builder(false)
+        .check()
+        .expect_err("check should have found issues");
+    assert_eq!(issues.len(), 2);
+    assert_eq!(
+        issues[0],
+        CheckIssue::NoRecordedEntities {
+            component: std::any::type_name::<Unspawned>()
+        }
+    );
+    assert!(
+        matches!(&issues[1], CheckIssue::StepPanicked { message } if message.contains("Market"))
+    );
+
+    let issues = builder(true)
+        .check()
+        .expect_err("check should have found issues");
+    assert_eq!(issues.len(), 1);
+
+    let issues = SimulationBuilder::new()
+        .add_entity_spawner(|_| panic!("spawner failed"))
+        .check()
+        .expect_err("check should have found issues");
+    assert_eq!(
+        issues,
+        vec![CheckIssue::BuildPanicked {
+            message: String::from("spawner failed")
+        }]
+    );
+}
+
+#[test]
+fn test_expected_steps() -> Result<(), SimulationError>
+{
+    #[derive(Component, PartialEq, Eq, Hash)]
+    struct Id(usize);
+
+    // pre-allocating the time series must not change what gets recorded
+    let build = |expected_steps| {
+        SimulationBuilder::new()
+            .expected_steps(expected_steps)
+            .add_entity_spawner(|spawner| {
+                spawner.spawn((MyValue(0), Id(1)));
+                spawner.spawn((MyValue(5), Id(2)));
+            })
+            .add_systems(increment)
+            .record_aggregate_time_series::<MyValue, usize>(3)
+            .and_then(|builder| builder.record_time_series::<MyValue, Id, usize>(2))
+            .map(SimulationBuilder::build)
+    };
+
+    for expected_steps in [0, 1, 10, 1000]
+    {
+        let mut simulation = build(expected_steps)?;
+        simulation.run(10);
+
+        let aggregate = simulation.get_aggregate_time_series::<MyValue, usize>()?;
+        assert_eq!(
+            aggregate.values().copied().collect::<Vec<_>>(),
+            vec![11, 17, 23]
+        );
+        let single = simulation.get_time_series::<MyValue, Id, usize>(&Id(2))?;
+        assert_eq!(
+            single.values().copied().collect::<Vec<_>>(),
+            vec![7, 9, 11, 13, 15]
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_single_threaded()
+{
+    #[derive(Resource, Default)]
+    struct SystemThreads(Vec<std::thread::ThreadId>);
+
+    fn record_thread(mut threads: ResMut<SystemThreads>)
+    {
+        threads.0.push(std::thread::current().id());
+    }
+
+    let mut simulation = SimulationBuilder::new()
+        .single_threaded(true)
+        .add_resource(SystemThreads::default())
+        .add_systems(record_thread)
+        .build();
+    simulation.run(3);
+
+    let threads = simulation
+        .get_resource::<SystemThreads>()
+        .expect("missing resource");
+    assert_eq!(threads.0, vec![std::thread::current().id(); 3]);
+}
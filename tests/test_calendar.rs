@@ -0,0 +1,153 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use bevy::ecs::schedule::Condition;
+use incerto::{
+    calendar::{self, Season, SimClock, Weekday},
+    prelude::*,
+};
+
+fn current(simulation: &Simulation) -> &SimClock
+{
+    simulation
+        .get_resource::<SimClock>()
+        .expect("missing clock")
+}
+
+#[derive(Resource, Default)]
+struct Calls(Vec<usize>);
+
+/// Builds a simulation with the given clock, recording the steps on which the condition holds.
+fn recording<M>(clock: SimClock, condition: impl Condition<M>) -> Simulation
+{
+    SimulationBuilder::new()
+        .add_clock(clock)
+        .add_resource(Calls::default())
+        .add_systems(
+            (|step: Res<StepNumber>, mut calls: ResMut<Calls>| calls.0.push(step.get()))
+                .run_if(condition),
+        )
+        .build()
+}
+
+fn calls(simulation: &mut Simulation) -> Vec<usize>
+{
+    simulation
+        .resource_scope(|calls: &mut Calls| std::mem::take(&mut calls.0))
+        .expect("missing calls")
+}
+
+#[test]
+fn test_time_of_day()
+{
+    let mut simulation = SimulationBuilder::new().add_clock(SimClock::new(4)).build();
+
+    let clock = current(&simulation);
+    assert_eq!(clock.step(), 1);
+    assert_eq!(clock.day(), 0);
+    assert_eq!(clock.time_of_day(), 0.0);
+    assert!(clock.is_night());
+    assert!(clock.is_start_of_day());
+
+    // the clock reads the step to be run next, as the step number does
+    simulation.run(5);
+    let clock = current(&simulation);
+    assert_eq!(clock.step(), 6);
+    assert_eq!(clock.day(), 1);
+    assert_eq!(clock.step_of_day(), 1);
+    assert_eq!(clock.time_of_day(), 0.25);
+    assert_eq!(clock.hour(), 6);
+    assert!(clock.is_day());
+    assert!(!clock.is_start_of_day());
+    assert_eq!(clock.weekday(), Weekday::Tuesday);
+}
+
+#[test]
+fn test_daylight()
+{
+    let mut simulation = recording(SimClock::new(10).with_daylight(0.2, 0.5), calendar::is_day);
+    simulation.run(20);
+    assert_eq!(calls(&mut simulation), vec![3, 4, 5, 13, 14, 15]);
+
+    let mut simulation = recording(SimClock::new(2), calendar::is_night);
+    simulation.run(6);
+    assert_eq!(calls(&mut simulation), vec![1, 3, 5]);
+
+    let mut simulation = recording(SimClock::new(3), calendar::is_start_of_day);
+    simulation.run(9);
+    assert_eq!(calls(&mut simulation), vec![1, 4, 7]);
+}
+
+#[test]
+fn test_weekdays()
+{
+    assert_eq!(Weekday::Friday.after(3), Weekday::Monday);
+    assert_eq!(Weekday::Sunday.after(14), Weekday::Sunday);
+    assert!(Weekday::Saturday.is_weekend());
+    assert!(!Weekday::Friday.is_weekend());
+
+    // daily steps, starting on a Thursday
+    let clock = || SimClock::new(1).starting_on(Weekday::Thursday, 0);
+
+    let mut simulation = recording(clock(), calendar::is_weekend);
+    simulation.run(14);
+    assert_eq!(calls(&mut simulation), vec![3, 4, 10, 11]);
+
+    let mut simulation = recording(clock(), calendar::on(Weekday::Monday));
+    simulation.run(14);
+    assert_eq!(calls(&mut simulation), vec![5, 12]);
+
+    let mut simulation = recording(clock(), calendar::is_weekday);
+    simulation.run(7);
+    assert_eq!(calls(&mut simulation), vec![1, 2, 5, 6, 7]);
+}
+
+#[test]
+fn test_seasons()
+{
+    // daily steps, over a year of eight days that starts in the middle of the summer
+    let clock = SimClock::new(1)
+        .with_days_per_year(8)
+        .starting_on(Weekday::Monday, 3);
+    assert_eq!(clock.days_per_year(), 8);
+    assert_eq!(clock.day_of_year(), 3);
+    assert_eq!(clock.season(), Season::Summer);
+
+    let mut simulation = recording(clock, calendar::in_season(Season::Winter));
+    simulation.run(16);
+    assert_eq!(calls(&mut simulation), vec![4, 5, 12, 13]);
+
+    let clock = current(&simulation);
+    assert_eq!(clock.step(), 17);
+    assert_eq!(clock.day_of_year(), 3);
+    assert_eq!(clock.year(), 2);
+}
+
+#[test]
+fn test_replacing_the_clock()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_clock(SimClock::new(1))
+        .add_clock(SimClock::new(24).starting_on(Weekday::Sunday, 50))
+        .build();
+    simulation.run(24);
+
+    let clock = current(&simulation);
+    assert_eq!(clock.steps_per_day(), 24);
+    assert_eq!(clock.weekday(), Weekday::Monday);
+    assert_eq!(clock.day_of_year(), 51);
+    assert_eq!(clock.season(), Season::Spring);
+}
+
+#[test]
+#[should_panic(expected = "a year of 365 days has no day 365")]
+fn test_day_outside_the_year()
+{
+    let _ = SimClock::new(1).starting_on(Weekday::Monday, 365);
+}
+
+#[test]
+#[should_panic(expected = "invalid daylight from 0.6 to 0.4")]
+fn test_invalid_daylight()
+{
+    let _ = SimClock::new(1).with_daylight(0.6, 0.4);
+}
@@ -0,0 +1,252 @@
+#![allow(clippy::expect_used)]
+use std::collections::HashSet;
+
+use incerto::{
+    cellular::{CellularAutomatonPlugin, Neighborhood},
+    prelude::*,
+};
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+struct Alive(bool);
+
+impl From<bool> for Alive
+{
+    fn from(alive: bool) -> Self
+    {
+        Self(alive)
+    }
+}
+
+impl From<&Alive> for bool
+{
+    fn from(cell: &Alive) -> Self
+    {
+        cell.0
+    }
+}
+
+impl Sample<bool> for Alive
+{
+    fn sample(component: &Self) -> bool
+    {
+        component.0
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+struct Value(usize);
+
+impl Sample<usize> for Value
+{
+    fn sample(component: &Self) -> usize
+    {
+        component.0
+    }
+}
+
+const fn square(size: i32) -> GridBounds2D
+{
+    GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(size - 1, size - 1),
+    }
+}
+
+/// Builds a life-like automaton in which the given positions start alive.
+fn life(plugin: CellularAutomatonPlugin<Alive>, bounds: GridBounds2D, alive: &[IVec2])
+-> Simulation
+{
+    let alive: HashSet<IVec2> = alive.iter().copied().collect();
+    SimulationBuilder::new()
+        .add_cellular_automaton(plugin)
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |position| Some(Alive(alive.contains(&position.0))));
+        })
+        .build()
+}
+
+fn alive_cells(simulation: &Simulation) -> HashSet<IVec2>
+{
+    let (positions, alive) = simulation
+        .export_columns_with_ids::<Alive, GridPosition2D, bool>()
+        .expect("no cells");
+
+    positions
+        .into_iter()
+        .zip(alive)
+        .filter_map(|(position, alive)| alive.then_some(position.0))
+        .collect()
+}
+
+#[test]
+fn test_blinker_oscillates()
+{
+    let bounds = square(5);
+    let horizontal = [IVec2::new(1, 2), IVec2::new(2, 2), IVec2::new(3, 2)];
+    let vertical = [IVec2::new(2, 1), IVec2::new(2, 2), IVec2::new(2, 3)];
+
+    // the parts of the rule string may be given in either order and case
+    let plugin = CellularAutomatonPlugin::life_like(bounds, "s23/b3");
+    let mut simulation = life(plugin, bounds, &horizontal);
+
+    simulation.run(1);
+    assert_eq!(alive_cells(&simulation), vertical.into_iter().collect());
+
+    simulation.run(1);
+    assert_eq!(alive_cells(&simulation), horizontal.into_iter().collect());
+}
+
+#[test]
+fn test_glider_wraps_around_torus()
+{
+    let bounds = square(8);
+    let glider = [
+        IVec2::new(1, 0),
+        IVec2::new(2, 1),
+        IVec2::new(0, 2),
+        IVec2::new(1, 2),
+        IVec2::new(2, 2),
+    ];
+
+    let plugin = CellularAutomatonPlugin::life_like(bounds, "B3/S23").with_wrapping();
+    let mut simulation = life(plugin, bounds, &glider);
+
+    // every four generations the glider moves one cell diagonally
+    simulation.run(4);
+    let moved: HashSet<IVec2> = glider.iter().map(|&p| p + IVec2::ONE).collect();
+    assert_eq!(alive_cells(&simulation), moved);
+
+    // and after crossing the whole grid it is back where it started
+    simulation.run(28);
+    assert_eq!(alive_cells(&simulation), glider.into_iter().collect());
+}
+
+#[test]
+fn test_glider_dies_on_bounded_grid()
+{
+    let bounds = square(8);
+    let glider = [
+        IVec2::new(1, 0),
+        IVec2::new(2, 1),
+        IVec2::new(0, 2),
+        IVec2::new(1, 2),
+        IVec2::new(2, 2),
+    ];
+
+    let plugin = CellularAutomatonPlugin::life_like(bounds, "B3/S23");
+    let mut simulation = life(plugin, bounds, &glider);
+
+    // without wrapping, the glider turns into a block in the corner
+    simulation.run(32);
+    let block = [
+        IVec2::new(6, 6),
+        IVec2::new(6, 7),
+        IVec2::new(7, 6),
+        IVec2::new(7, 7),
+    ];
+    assert_eq!(alive_cells(&simulation), block.into_iter().collect());
+}
+
+#[test]
+fn test_updates_are_synchronous()
+{
+    let bounds = square(10);
+
+    // every cell copies the value of the cell to its left
+    let plugin = CellularAutomatonPlugin::new(bounds, |_: &Value, neighbors| {
+        neighbors
+            .get(IVec2::new(-1, 0))
+            .copied()
+            .unwrap_or(Value(0))
+    })
+    .with_wrapping();
+
+    let mut simulation = SimulationBuilder::new()
+        .add_cellular_automaton(plugin)
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |position| {
+                Some(Value(usize::from(position.x() == 0)))
+            });
+        })
+        .build();
+
+    // a hand-rolled update visiting cells in order would move the column across the whole row
+    simulation.run(3);
+    let (positions, values) = simulation
+        .export_columns_with_ids::<Value, GridPosition2D, usize>()
+        .expect("no cells");
+    for (position, value) in positions.into_iter().zip(values)
+    {
+        assert_eq!(value, usize::from(position.x() == 3), "at {position:?}");
+    }
+}
+
+#[test]
+fn test_neighborhoods()
+{
+    let count_neighbors = |neighborhood, wrapping| {
+        let bounds = square(3);
+        let mut plugin = CellularAutomatonPlugin::new(bounds, |_: &Value, neighbors| {
+            Value(neighbors.count(|_| true))
+        })
+        .with_neighborhood(neighborhood);
+        if wrapping
+        {
+            plugin = plugin.with_wrapping();
+        }
+
+        let mut simulation = SimulationBuilder::new()
+            .add_cellular_automaton(plugin)
+            .add_entity_spawner(move |spawner| {
+                spawner.spawn_grid(bounds, |_| Some(Value(0)));
+            })
+            .build();
+        simulation.run(1);
+
+        let (positions, values) = simulation
+            .export_columns_with_ids::<Value, GridPosition2D, usize>()
+            .expect("no cells");
+        let corner = positions
+            .iter()
+            .position(|position| position.0 == IVec2::ZERO)
+            .expect("no corner cell");
+        let center = positions
+            .iter()
+            .position(|position| position.0 == IVec2::ONE)
+            .expect("no center cell");
+        (values[corner], values[center])
+    };
+
+    assert_eq!(count_neighbors(Neighborhood::Moore, false), (3, 8));
+    assert_eq!(count_neighbors(Neighborhood::VonNeumann, false), (2, 4));
+    assert_eq!(count_neighbors(Neighborhood::Moore, true), (8, 8));
+    assert_eq!(count_neighbors(Neighborhood::VonNeumann, true), (4, 4));
+}
+
+#[test]
+fn test_missing_cells_are_not_neighbors()
+{
+    let bounds = square(3);
+    let plugin = CellularAutomatonPlugin::new(bounds, |_: &Value, neighbors| {
+        Value(neighbors.count(|_| true))
+    });
+
+    let mut simulation = SimulationBuilder::new()
+        .add_cellular_automaton(plugin)
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |position| (position.y() == 0).then_some(Value(0)));
+        })
+        .build();
+    simulation.run(1);
+
+    let mut values: Vec<usize> = simulation.iter::<Value>().map(|value| value.0).collect();
+    values.sort_unstable();
+    assert_eq!(values, vec![1, 1, 2]);
+}
+
+#[test]
+#[should_panic(expected = "invalid rule string")]
+fn test_invalid_rule_string()
+{
+    let _ = CellularAutomatonPlugin::<Alive>::life_like(square(3), "B9/S23");
+}
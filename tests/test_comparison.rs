@@ -0,0 +1,155 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::{
+    comparison::{Scenarios, mann_whitney_u, paired_t_test},
+    prelude::*,
+};
+use rand::Rng;
+
+#[test]
+fn test_paired_t_test()
+{
+    // t = 1.2 / sqrt(0.7 / 5) with 4 degrees of freedom
+    let test =
+        paired_t_test(&[1.0, 2.0, 3.0, 4.0, 5.0], &[2.0, 4.0, 3.0, 5.0, 7.0]).expect("no test");
+    assert!((test.statistic - 3.207_135).abs() < 1e-6);
+    assert!((test.p_value - 0.032_69).abs() < 1e-4);
+    assert!(test.is_significant(0.05) && !test.is_significant(0.01));
+
+    let shifted = paired_t_test(&[1.0, 2.0, 3.0], &[0.0, 1.0, 2.0]).expect("no test");
+    assert_eq!(
+        (shifted.statistic, shifted.p_value),
+        (f64::NEG_INFINITY, 0.0)
+    );
+
+    let same = paired_t_test(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]).expect("no test");
+    assert_eq!((same.statistic, same.p_value), (0.0, 1.0));
+
+    assert_eq!(paired_t_test(&[1.0], &[2.0]), None);
+}
+
+#[test]
+fn test_mann_whitney_u()
+{
+    let test =
+        mann_whitney_u(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0]).expect("no test");
+    assert_eq!(test.statistic, 0.0);
+    assert!((test.p_value - 0.012_19).abs() < 1e-4);
+
+    // the samples need not be paired, and ties share their ranks
+    let test = mann_whitney_u(&[1.0, 2.0, 2.0, 5.0], &[2.0, 3.0, 4.0]).expect("no test");
+    assert_eq!(test.statistic, 4.0);
+
+    let test = mann_whitney_u(&[1.0, 1.0, 1.0], &[1.0, 1.0, 1.0]).expect("no test");
+    assert_eq!(test.p_value, 1.0);
+
+    assert_eq!(mann_whitney_u(&[], &[1.0]), None);
+}
+
+#[derive(Component)]
+struct Wealth(f64);
+
+/// Agents with random wealth, which grows by `rate` on every step.
+fn economy(rate: f64) -> impl Fn(usize) -> SimulationBuilder + Sync
+{
+    move |_| {
+        SimulationBuilder::new()
+            .add_seeded_entity_spawner(|spawner, rng| {
+                for _ in 0..10
+                {
+                    spawner.spawn(Wealth(rng.random_range(0.0..100.0)));
+                }
+            })
+            .add_systems(move |mut agents: Query<&mut Wealth>| {
+                for mut agent in &mut agents
+                {
+                    agent.0 *= 1.0 + rate;
+                }
+            })
+    }
+}
+
+fn total(simulation: &Simulation) -> f64
+{
+    simulation.iter::<Wealth>().map(|wealth| wealth.0).sum()
+}
+
+#[test]
+fn test_scenarios()
+{
+    let comparison = Scenarios::new(7, 20, 5)
+        .add_scenario("baseline", economy(0.0))
+        .add_scenario("same", economy(0.0))
+        .add_scenario("growth", economy(0.01))
+        .add_output("total", total)
+        .add_output("richest", |simulation| {
+            simulation
+                .iter::<Wealth>()
+                .map(|wealth| wealth.0)
+                .fold(0.0, f64::max)
+        })
+        .run();
+
+    assert_eq!(comparison.scenarios(), ["baseline", "same", "growth"]);
+    assert_eq!(comparison.outputs(), ["total", "richest"]);
+    let baseline = comparison.result("baseline", "total").expect("no result");
+    assert_eq!(baseline.len(), 20);
+    assert_eq!(comparison.result("baseline", "unknown"), None);
+
+    // the paired seeds make identical scenarios identical
+    assert_eq!(comparison.result("same", "total"), Some(baseline));
+    let same = comparison
+        .paired_t_test("baseline", "same", "total")
+        .expect("no test");
+    assert_eq!(same.p_value, 1.0);
+
+    // and a small effect clear, which the unpaired test can hardly tell apart from chance
+    let growth = comparison
+        .paired_t_test("baseline", "growth", "total")
+        .expect("no test");
+    assert!(growth.statistic > 0.0 && growth.is_significant(1e-6));
+    let unpaired = comparison
+        .mann_whitney_u("baseline", "growth", "total")
+        .expect("no test");
+    assert!(!unpaired.is_significant(0.05));
+
+    let table = comparison.table();
+    assert_eq!(table.len(), 4);
+    let row = &table[1];
+    assert_eq!(
+        (row.output.as_str(), row.scenario.as_str()),
+        ("total", "growth")
+    );
+    assert_eq!(row.baseline, baseline.mean().expect("no mean"));
+    let difference = row.difference.expect("no difference");
+    assert!(difference.contains(row.mean - row.baseline));
+    assert!(difference.lower > 0.0);
+
+    let printed = comparison.to_string();
+    assert_eq!(printed.lines().count(), 5);
+    assert!(
+        printed
+            .lines()
+            .nth(2)
+            .is_some_and(|line| line.starts_with("total"))
+    );
+}
+
+#[test]
+#[should_panic(expected = "duplicate scenario: baseline")]
+fn test_duplicate_scenario()
+{
+    let _ = Scenarios::new(0, 2, 1)
+        .add_scenario("baseline", economy(0.0))
+        .add_scenario("baseline", economy(0.1));
+}
+
+#[test]
+#[should_panic(expected = "a comparison needs at least two scenarios")]
+fn test_single_scenario()
+{
+    let _ = Scenarios::new(0, 2, 1)
+        .add_scenario("baseline", economy(0.0))
+        .add_output("total", total)
+        .run();
+}
@@ -0,0 +1,212 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::{
+    epidemic::{Compartment, EpidemicModule, EpidemicOutputs, Transmission},
+    prelude::*,
+};
+
+#[derive(Component)]
+struct Person(&'static str);
+
+/// The contacts of every person on the latest step, as `(person, contact, weight)`.
+#[derive(Resource, Default)]
+struct Contacts(Vec<(&'static str, &'static str, f64)>);
+
+fn collect_contacts(
+    people: Query<(Entity, &Person)>,
+    layers: Res<ContactLayers<Person>>,
+    grid: Option<Res<SpatialGrid2D<Person>>>,
+    mut contacts: ResMut<Contacts>,
+)
+{
+    let mut buffer = Vec::new();
+    contacts.0.clear();
+    for (entity, person) in &people
+    {
+        layers.contacts_of(entity, grid.as_deref(), &mut buffer);
+        for &(contact, weight) in &buffer
+        {
+            let (_, other) = people.get(contact).expect("contact is not a person");
+            contacts.0.push((person.0, other.0, weight));
+        }
+    }
+    contacts.0.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+}
+
+/// Alice lives with Carol and works with Dave, while Bob is her next-door neighbor.
+fn town() -> Simulation
+{
+    let layers = ContactLayers::<Person>::new()
+        .with_spatial("community", None, 0.1)
+        .with_network("household", 1.0)
+        .with_network("workplace", 0.5);
+
+    SimulationBuilder::new()
+        .add_contact_layers(layers)
+        .add_resource(Contacts::default())
+        .add_entity_spawner(|spawner| {
+            let alice = spawner.spawn((Person("alice"), GridPosition2D::new(0, 0)));
+            spawner.spawn((Person("bob"), GridPosition2D::new(1, 1)));
+            let carol = spawner.spawn((Person("carol"), GridPosition2D::new(10, 10)));
+            let dave = spawner.spawn(Person("dave"));
+            spawner.add_layer_edge::<Person>("household", alice, carol);
+            spawner.add_weighted_layer_edge::<Person>("workplace", alice, dave, 0.4);
+        })
+        .add_systems(collect_contacts)
+        .build()
+}
+
+fn contacts_of(simulation: &Simulation, name: &str) -> Vec<(&'static str, f64)>
+{
+    simulation
+        .get_resource::<Contacts>()
+        .expect("no contacts")
+        .0
+        .iter()
+        .filter(|(person, _, _)| *person == name)
+        .map(|&(_, contact, weight)| (contact, weight))
+        .collect()
+}
+
+#[test]
+fn test_contacts_across_layers()
+{
+    let mut simulation = town();
+    simulation.run(1);
+
+    assert_eq!(
+        contacts_of(&simulation, "alice"),
+        vec![("bob", 0.1), ("carol", 1.0), ("dave", 0.2)]
+    );
+    assert_eq!(contacts_of(&simulation, "bob"), vec![("alice", 0.1)]);
+    assert_eq!(contacts_of(&simulation, "carol"), vec![("alice", 1.0)]);
+    assert_eq!(contacts_of(&simulation, "dave"), vec![("alice", 0.2)]);
+
+    let layers = simulation
+        .get_resource::<ContactLayers<Person>>()
+        .expect("no contact layers");
+    assert_eq!(
+        layers.names().collect::<Vec<_>>(),
+        vec!["community", "household", "workplace"]
+    );
+    assert_eq!(layers.modifier("workplace"), Some(0.5));
+    assert!(layers.network("community").is_none());
+    assert_eq!(
+        layers
+            .network("household")
+            .expect("no household layer")
+            .num_edges(),
+        1
+    );
+}
+
+#[test]
+fn test_disabled_layer()
+{
+    let mut simulation = town();
+    simulation.run(1);
+    simulation
+        .resource_scope(|layers: &mut ContactLayers<Person>| {
+            layers.set_modifier("workplace", 0.0);
+            layers.set_modifier("community", 0.2);
+        })
+        .expect("no contact layers");
+    simulation.run(1);
+
+    assert_eq!(
+        contacts_of(&simulation, "alice"),
+        vec![("bob", 0.2), ("carol", 1.0)]
+    );
+    assert!(contacts_of(&simulation, "dave").is_empty());
+}
+
+#[test]
+fn test_despawned_contacts_are_removed()
+{
+    let mut simulation = town();
+    simulation.run(1);
+    assert_eq!(simulation.despawn_where::<Without<GridPosition2D>>(), 1);
+    simulation.run(1);
+
+    assert_eq!(
+        contacts_of(&simulation, "alice"),
+        vec![("bob", 0.1), ("carol", 1.0)]
+    );
+    let layers = simulation
+        .get_resource::<ContactLayers<Person>>()
+        .expect("no contact layers");
+    assert_eq!(
+        layers
+            .network("workplace")
+            .expect("no workplace layer")
+            .num_edges(),
+        0
+    );
+}
+
+#[test]
+fn test_epidemic_through_layers()
+{
+    // the households are chains of infections, while the closed workplace links them
+    let layers = ContactLayers::<Compartment>::new()
+        .with_network("household", 1.0)
+        .with_network("workplace", 0.0);
+
+    let mut simulation = SimulationBuilder::new()
+        .add_contact_layers(layers)
+        .add_module(EpidemicModule::sir(1.0, 0.0).with_transmission(Transmission::Layered))
+        .add_entity_spawner(|spawner| {
+            let people = (0..6)
+                .map(|i| {
+                    spawner.spawn(
+                        if i == 0
+                        {
+                            Compartment::Infectious
+                        }
+                        else
+                        {
+                            Compartment::Susceptible
+                        },
+                    )
+                })
+                .collect::<Vec<_>>();
+            spawner.add_layer_edges::<Compartment>("household", &people, [(0, 1), (1, 2)]);
+            spawner.add_layer_edges::<Compartment>("household", &people, [(3, 4), (4, 5)]);
+            spawner.add_layer_edge::<Compartment>("workplace", people[2], people[3]);
+        })
+        .build();
+    simulation.run(5);
+
+    let outputs = simulation
+        .get_resource::<EpidemicOutputs>()
+        .expect("missing epidemic outputs");
+    let infectious = outputs
+        .history()
+        .iter()
+        .map(|counts| counts.infectious)
+        .collect::<Vec<_>>();
+    assert_eq!(infectious, [2, 3, 3, 3, 3]);
+}
+
+#[test]
+#[should_panic(expected = "duplicate contact layer")]
+fn test_duplicate_layer()
+{
+    let _ = ContactLayers::<Person>::new()
+        .with_network("household", 1.0)
+        .with_network("household", 0.5);
+}
+
+#[test]
+#[should_panic(expected = "no contact network layer named school")]
+fn test_unknown_layer()
+{
+    let _ = SimulationBuilder::new()
+        .add_contact_layers(ContactLayers::<Person>::new().with_network("household", 1.0))
+        .add_entity_spawner(|spawner| {
+            let alice = spawner.spawn(Person("alice"));
+            let bob = spawner.spawn(Person("bob"));
+            spawner.add_layer_edge::<Person>("school", alice, bob);
+        })
+        .build();
+}
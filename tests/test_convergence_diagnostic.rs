@@ -0,0 +1,136 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::prelude::*;
+use rand::{Rng, SeedableRng};
+
+#[derive(Component)]
+struct Level(f64);
+
+impl SampleAggregate<f64> for Level
+{
+    fn sample_aggregate(components: &[&Self]) -> f64
+    {
+        components.iter().map(|level| level.0).sum()
+    }
+}
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64)
+{
+    assert!(
+        (actual - expected).abs() < tolerance,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn test_diagnostic_values()
+{
+    // the halves are [1, 2], [3, 4], [2, 3] and [4, 5]
+    let diagnostic =
+        ConvergenceDiagnostic::new([[1.0, 2.0, 3.0, 4.0], [2.0, 3.0, 4.0, 5.0]]).expect("none");
+    assert_eq!(diagnostic.chains(), 2);
+    assert_eq!(diagnostic.length(), 4);
+    assert_close(diagnostic.within_variance(), 0.5, 1e-12);
+    assert_close(diagnostic.between_variance(), 10.0 / 3.0, 1e-12);
+    assert_close(diagnostic.r_hat(), (23.0_f64 / 6.0).sqrt(), 1e-12);
+    assert!(!diagnostic.has_converged(1.1));
+
+    // the longer series is truncated, and the middle value of odd lengths left out
+    let truncated = ConvergenceDiagnostic::new([
+        vec![1.0, 2.0, 100.0, 3.0, 4.0],
+        vec![2.0, 3.0, -100.0, 4.0, 5.0, 6.0],
+    ])
+    .expect("none");
+    assert_eq!(truncated.length(), 5);
+    assert_eq!(truncated.r_hat(), diagnostic.r_hat());
+}
+
+#[test]
+fn test_degenerate_series()
+{
+    assert_eq!(ConvergenceDiagnostic::new([[1.0, 2.0, 3.0, 4.0]]), None);
+    assert_eq!(
+        ConvergenceDiagnostic::new([vec![1.0, 2.0, 3.0, 4.0], vec![1.0, 2.0, 3.0]]),
+        None
+    );
+
+    let identical = ConvergenceDiagnostic::new([[2.0; 6], [2.0; 6]]).expect("none");
+    assert_eq!(identical.r_hat(), 1.0);
+    assert!(identical.has_converged(1.01));
+
+    let apart = ConvergenceDiagnostic::new([[2.0; 6], [3.0; 6]]).expect("none");
+    assert_eq!(apart.r_hat(), f64::INFINITY);
+}
+
+#[test]
+fn test_shared_trend()
+{
+    let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+
+    // every series drifts in the same way, which only the split halves reveal
+    let drifting = (0..4).map(|_| {
+        (0..200)
+            .map(|step| f64::from(step).mul_add(0.05, rng.random_range(-1.0..1.0)))
+            .collect::<Vec<_>>()
+    });
+    let diagnostic = ConvergenceDiagnostic::new(drifting.collect::<Vec<_>>()).expect("none");
+    assert!(!diagnostic.has_converged(1.1));
+
+    let stationary = (0..4).map(|_| {
+        (0..200)
+            .map(|_| rng.random_range(-1.0..1.0))
+            .collect::<Vec<_>>()
+    });
+    let diagnostic = ConvergenceDiagnostic::new(stationary.collect::<Vec<_>>()).expect("none");
+    assert!(diagnostic.has_converged(1.05));
+}
+
+#[test]
+#[should_panic(expected = "invalid value")]
+fn test_invalid_value()
+{
+    let _ = ConvergenceDiagnostic::new([[1.0, 2.0, 3.0, 4.0], [1.0, f64::NAN, 3.0, 4.0]]);
+}
+
+#[test]
+fn test_diagnostic_from_replicas()
+{
+    // every replica relaxes from zero to a noisy steady state around the level of its index
+    let replicate = |levels: [f64; 4]| {
+        Simulation::replicate_seeded(5, 4, 400, move |idx| {
+            let target = levels[idx];
+            SimulationBuilder::new()
+                .add_entity_spawner(|spawner| {
+                    spawner.spawn(Level(0.0));
+                })
+                .add_systems(
+                    move |mut levels: Query<&mut Level>, mut rng: ResMut<SimRng>| {
+                        for mut level in &mut levels
+                        {
+                            let deviation = level.0 - target;
+                            level.0 = deviation.mul_add(0.8, target + rng.random_range(-1.0..1.0));
+                        }
+                    },
+                )
+                .record_aggregate_time_series::<Level, f64>(1)
+                .expect("failed to record")
+        })
+    };
+
+    // leave out the first half as warm-up
+    let series = |replica: &Simulation| {
+        let series = replica
+            .get_aggregate_time_series::<Level, f64>()
+            .expect("no time series");
+        series.values_copied().skip(200).collect()
+    };
+
+    let replicas = replicate([10.0; 4]);
+    let settled = ConvergenceDiagnostic::from_replicas(&replicas, series).expect("none");
+    assert_eq!(settled.chains(), 4);
+    assert!(settled.has_converged(1.1));
+
+    let replicas = replicate([10.0, 10.0, 10.0, 12.0]);
+    let stuck = ConvergenceDiagnostic::from_replicas(&replicas, series).expect("none");
+    assert!(!stuck.has_converged(1.1));
+}
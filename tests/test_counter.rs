@@ -224,3 +224,70 @@ fn test_counter_with_id()
     assert_eq!(value_id_1, NUM_STEPS);
     assert_eq!(value_id_5, 5 * NUM_STEPS);
 }
+
+#[test]
+fn test_counter_step_number()
+{
+    const NUM_STEPS: usize = 30;
+
+    let builder = SimulationBuilder::new()
+        .add_systems(|mut query: Query<&mut MyCounter>, step: Res<StepNumber>| {
+            let mut counter = query.single_mut().expect("expect a single counter entity");
+
+            // the counter is incremented once per step, so it should always trail the step number by one
+            assert_eq!(counter.0 + 1, step.get());
+            counter.0 += 1;
+        })
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(MyCounter(0));
+        })
+        .record_aggregate_time_series::<MyCounter, usize>(10)
+        .expect("error building simulation");
+
+    let mut simulation = builder.build();
+    assert_eq!(simulation.current_step(), 0);
+
+    simulation.run(NUM_STEPS);
+    assert_eq!(simulation.current_step(), NUM_STEPS);
+
+    let time_series = simulation
+        .get_aggregate_time_series::<MyCounter, usize>()
+        .expect("time series not recorded");
+    assert_eq!(time_series.value_at(20), Some(&20));
+    assert_eq!(time_series.value_at(25), None);
+}
+
+#[test]
+fn test_counter_time_series_slices()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_systems(|mut query: Query<&mut MyCounter>| {
+            for mut counter in &mut query
+            {
+                counter.0 += 1;
+            }
+        })
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(MyCounter(0));
+        })
+        .record_aggregate_time_series::<MyCounter, usize>(10)
+        .expect("error building simulation")
+        .build();
+
+    simulation.run(30);
+
+    let time_series = simulation
+        .get_aggregate_time_series::<MyCounter, usize>()
+        .expect("time series not recorded");
+    assert_eq!(time_series.values_slice(), &[10, 20, 30]);
+    assert_eq!(time_series.time_slice(), &[10, 20, 30]);
+
+    // retrieving the series again borrows the same storage instead of copying it
+    let again = simulation
+        .get_aggregate_time_series::<MyCounter, usize>()
+        .expect("time series not recorded");
+    assert!(std::ptr::eq(
+        time_series.values_slice(),
+        again.values_slice()
+    ));
+}
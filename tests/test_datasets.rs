@@ -0,0 +1,63 @@
+#![allow(clippy::expect_used)]
+use incerto::prelude::*;
+
+#[derive(Component)]
+struct Ticker(String);
+
+#[test]
+fn test_spawn_from_iter()
+{
+    let tickers = ["AAPL", "MSFT", "NVDA"];
+
+    let simulation = SimulationBuilder::new()
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_from_iter(tickers.map(|t| Ticker(t.to_string())));
+        })
+        .build();
+
+    let mut spawned = simulation
+        .iter::<Ticker>()
+        .map(|t| t.0.as_str())
+        .collect::<Vec<_>>();
+    spawned.sort_unstable();
+    assert_eq!(spawned, tickers);
+}
+
+#[cfg(feature = "csv")]
+#[test]
+#[allow(clippy::float_cmp)]
+fn test_spawn_from_csv()
+{
+    #[derive(Component)]
+    struct Price(f64);
+
+    #[derive(serde::Deserialize)]
+    struct Record
+    {
+        ticker: String,
+        price: f64,
+    }
+
+    let path = std::env::temp_dir().join("incerto_test_spawn_from_csv.csv");
+    std::fs::write(&path, "ticker,price\nAAPL,230.5\nMSFT,410.0\n").expect("failed to write csv");
+
+    let simulation = SimulationBuilder::new()
+        .add_entity_spawner(move |spawner| {
+            spawner
+                .spawn_from_csv(&path, |record: Record| {
+                    (Ticker(record.ticker), Price(record.price))
+                })
+                .expect("failed to spawn from csv");
+
+            let err = spawner.spawn_from_csv(&path, |record: (String, u8)| Ticker(record.0));
+            assert_eq!(err, Err(DatasetError::InvalidRecord { line: Some(2) }));
+
+            let err = spawner
+                .spawn_from_csv("does_not_exist.csv", |record: Record| Ticker(record.ticker));
+            assert_eq!(err, Err(DatasetError::Io));
+        })
+        .build();
+
+    assert_eq!(simulation.iter::<Ticker>().count(), 2);
+    assert_eq!(simulation.iter::<Price>().map(|p| p.0).sum::<f64>(), 640.5);
+}
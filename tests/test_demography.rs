@@ -0,0 +1,183 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use bevy::prelude::Events;
+use incerto::{
+    demography::{AgeSchedule, AgeStructure, Birth, Death, DemographyModule, DemographyOutputs},
+    prelude::*,
+};
+
+fn outputs(simulation: &Simulation) -> &DemographyOutputs
+{
+    simulation
+        .get_resource::<DemographyOutputs>()
+        .expect("missing demography outputs")
+}
+
+fn ages(simulation: &Simulation) -> Vec<usize>
+{
+    let mut ages = simulation
+        .iter::<AgeStructure>()
+        .map(AgeStructure::age)
+        .collect::<Vec<_>>();
+    ages.sort_unstable();
+    ages
+}
+
+#[test]
+fn test_age_schedules()
+{
+    let schedule = AgeSchedule::new([(5, 0.1), (10, 0.5), (20, 0.0)]);
+    assert_eq!(schedule.probability(0), 0.0);
+    assert_eq!(schedule.probability(5), 0.1);
+    assert_eq!(schedule.probability(9), 0.1);
+    assert_eq!(schedule.probability(10), 0.5);
+    assert_eq!(schedule.probability(100), 0.0);
+    assert_eq!(AgeSchedule::constant(0.3).probability(42), 0.3);
+}
+
+#[test]
+fn test_aging_and_pyramid()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_module(DemographyModule::new().with_age_classes(5, 3))
+        .add_entity_spawner(|spawner| {
+            for age in [0, 5, 12]
+            {
+                spawner.spawn(AgeStructure::new(age));
+            }
+        })
+        .build();
+
+    simulation.run(3);
+
+    assert_eq!(ages(&simulation), vec![3, 8, 15]);
+    let history = outputs(&simulation).history();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].step, 1);
+    assert_eq!(history[0].pyramid, vec![1, 1, 1]);
+    let latest = &history[2];
+    assert_eq!(latest.population, 3);
+    assert_eq!((latest.births, latest.deaths), (0, 0));
+    assert_eq!(latest.pyramid, vec![1, 1, 1]);
+    assert_eq!(latest.mean_age, Some(26.0 / 3.0));
+
+    // the last class holds everyone older
+    simulation.run(2);
+    let latest = outputs(&simulation).latest().expect("no steps recorded");
+    assert_eq!(latest.pyramid, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_maximum_age()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_module(DemographyModule::new().with_max_age(10))
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(AgeStructure::new(8));
+            spawner.spawn(AgeStructure::new(9));
+        })
+        .build();
+
+    simulation.run(2);
+
+    let events = simulation
+        .get_resource::<Events<Death>>()
+        .expect("missing death events");
+    let deaths = events
+        .iter_current_update_events()
+        .map(|death| death.age)
+        .collect::<Vec<_>>();
+    assert_eq!(deaths, vec![10]);
+    assert_eq!(ages(&simulation), vec![10]);
+
+    simulation.run(1);
+    let history = outputs(&simulation).history();
+    assert_eq!(
+        history
+            .iter()
+            .map(|metrics| metrics.deaths)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 1]
+    );
+    assert_eq!(history[2].population, 0);
+    assert_eq!(history[2].mean_age, None);
+}
+
+#[test]
+fn test_births()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_module(DemographyModule::new().with_fertility(AgeSchedule::new([(0, 0.0), (2, 1.0)])))
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(AgeStructure::new(2));
+        })
+        .build();
+
+    simulation.run(4);
+
+    // newborns give birth from the age of two
+    let history = outputs(&simulation).history();
+    assert_eq!(
+        history
+            .iter()
+            .map(|metrics| (metrics.population, metrics.births))
+            .collect::<Vec<_>>(),
+        vec![(2, 1), (3, 1), (4, 1), (6, 2)]
+    );
+    assert_eq!(ages(&simulation), vec![0, 0, 1, 2, 3, 6]);
+    assert_eq!(history[3].pyramid, vec![6, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    let events = simulation
+        .get_resource::<Events<Birth>>()
+        .expect("missing birth events");
+    let births = events.iter_current_update_events().collect::<Vec<_>>();
+    assert_eq!(births.len(), 2);
+    assert_ne!(births[0].parent, births[1].parent);
+    assert_ne!(births[0].newborn, births[1].newborn);
+    assert!(
+        births
+            .iter()
+            .all(|birth| births.iter().all(|other| other.parent != birth.newborn))
+    );
+}
+
+#[test]
+fn test_carrying_capacity()
+{
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(1)
+        .add_module(
+            DemographyModule::new()
+                .with_mortality(AgeSchedule::constant(0.1))
+                .with_fertility(AgeSchedule::constant(0.5))
+                .with_carrying_capacity(500)
+                .with_population(20, 10),
+        )
+        .build();
+
+    assert!(ages(&simulation).iter().all(|age| *age <= 10));
+    simulation.run(200);
+
+    // the population settles where births balance deaths, at `K * (1 - 0.1 / 0.9 / 0.5)`
+    let history = outputs(&simulation).history();
+    let total = history[100..]
+        .iter()
+        .map(|metrics| metrics.population)
+        .sum::<usize>();
+    assert!((36_900..=40_900).contains(&total), "{total}");
+    assert!(history.iter().all(|metrics| metrics.population < 500));
+}
+
+#[test]
+#[should_panic(expected = "the ages of an age schedule must be strictly increasing")]
+fn test_unordered_schedule()
+{
+    let _ = AgeSchedule::new([(10, 0.1), (5, 0.2)]);
+}
+
+#[test]
+#[should_panic(expected = "invalid probability at age 5: 1.5")]
+fn test_invalid_probability()
+{
+    let _ = AgeSchedule::new([(0, 0.1), (5, 1.5)]);
+}
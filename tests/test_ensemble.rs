@@ -0,0 +1,315 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::prelude::*;
+use rand::Rng;
+
+fn assert_close(actual: f64, expected: f64)
+{
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn test_summary_statistics()
+{
+    let ensemble = EnsembleResult::new([1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(ensemble.len(), 5);
+    assert_eq!(ensemble.mean(), Some(3.0));
+    assert_eq!(ensemble.variance(), Some(2.5));
+    assert_close(ensemble.std_dev().expect("no std dev"), 2.5_f64.sqrt());
+    assert_close(ensemble.standard_error().expect("no error"), 0.5_f64.sqrt());
+
+    let single = std::iter::once(7.0).collect::<EnsembleResult>();
+    assert_eq!(single.mean(), Some(7.0));
+    assert_eq!(single.variance(), None);
+    assert_eq!(single.confidence_interval(0.95), None);
+
+    let empty = EnsembleResult::default();
+    assert!(empty.is_empty());
+    assert_eq!(empty.mean(), None);
+}
+
+#[test]
+fn test_confidence_interval()
+{
+    // t(0.975, 4) = 2.776445
+    let interval = EnsembleResult::new([1.0, 2.0, 3.0, 4.0, 5.0])
+        .confidence_interval(0.95)
+        .expect("no interval");
+    assert_eq!(interval.estimate, 3.0);
+    assert_eq!(interval.level, 0.95);
+    assert_close(interval.half_width(), 2.776_445 * 0.5_f64.sqrt());
+    assert_close(interval.lower + interval.upper, 6.0);
+    assert!(interval.contains(3.0) && !interval.contains(5.0));
+
+    // t(0.975, 1) = 12.706205, with a standard error of 1
+    let interval = EnsembleResult::new([0.0, 2.0])
+        .confidence_interval(0.95)
+        .expect("no interval");
+    assert_close(interval.upper, 1.0 + 12.706_205);
+
+    // t(0.95, 9) = 1.833113, with a standard error of 1/3
+    let values = (0..10).map(|i| if i % 2 == 0 { -1.0 } else { 1.0 });
+    let interval = EnsembleResult::new(values)
+        .confidence_interval(0.9)
+        .expect("no interval");
+    assert_close(interval.half_width(), 1.833_113 / 3.0);
+}
+
+#[test]
+fn test_confidence_interval_narrows()
+{
+    let ensemble = EnsembleResult::new([3.0, 5.0, 4.0, 6.0, 2.0, 4.0]);
+    let wide = ensemble.confidence_interval(0.99).expect("no interval");
+    let narrow = ensemble.confidence_interval(0.5).expect("no interval");
+    assert!(narrow.half_width() < wide.half_width());
+
+    let more = EnsembleResult::new([3.0, 5.0, 4.0, 6.0, 2.0, 4.0].repeat(10));
+    let interval = more.confidence_interval(0.99).expect("no interval");
+    assert!(interval.half_width() < wide.half_width());
+}
+
+#[test]
+fn test_bootstrap_confidence_interval()
+{
+    let mut rng = SimRng::from_seed(7);
+    let ensemble = EnsembleResult::new((0..50).map(f64::from));
+    let interval = ensemble
+        .bootstrap_confidence_interval(0.95, 2000, &mut rng)
+        .expect("no interval");
+    assert_eq!(interval.estimate, 24.5);
+    assert!(interval.contains(24.5));
+
+    // agrees roughly with the t-based interval for symmetric outcomes
+    let t_based = ensemble.confidence_interval(0.95).expect("no interval");
+    assert!((interval.half_width() - t_based.half_width()).abs() < 1.0);
+
+    // the same seed reproduces the same interval
+    let again = ensemble
+        .bootstrap_confidence_interval(0.95, 2000, &mut SimRng::from_seed(7))
+        .expect("no interval");
+    assert_eq!(again, interval);
+
+    let constant = EnsembleResult::new([2.0; 5])
+        .bootstrap_confidence_interval(0.95, 100, &mut rng)
+        .expect("no interval");
+    assert_eq!((constant.lower, constant.upper), (2.0, 2.0));
+}
+
+#[derive(Component)]
+struct Coin(bool);
+
+#[test]
+fn test_from_replicas()
+{
+    let replicas = Simulation::replicate_seeded(3, 40, 1, |_| {
+        SimulationBuilder::new().add_seeded_entity_spawner(|spawner, rng| {
+            for _ in 0..100
+            {
+                spawner.spawn(Coin(rng.random_bool(0.5)));
+            }
+        })
+    });
+
+    let ensemble = EnsembleResult::from_replicas(&replicas, |simulation| {
+        #[allow(clippy::cast_precision_loss)]
+        let heads = simulation.iter::<Coin>().filter(|coin| coin.0).count() as f64;
+        heads / 100.0
+    });
+    assert_eq!(ensemble.len(), 40);
+
+    let interval = ensemble.confidence_interval(0.99).expect("no interval");
+    assert!(interval.contains(0.5), "{interval}");
+    assert!(interval.half_width() < 0.05);
+}
+
+#[test]
+fn test_additional_runs()
+{
+    // (t(0.975, 4) * sqrt(2.5) / 0.5)^2 = 77.09
+    let ensemble = EnsembleResult::new([1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(ensemble.additional_runs(0.5, 0.95), Some(73));
+    assert_eq!(ensemble.additional_runs(2.0, 0.95), Some(0));
+    assert_eq!(EnsembleResult::new([1.0]).additional_runs(0.5, 0.95), None);
+}
+
+fn coin_flips(idx: usize) -> SimulationBuilder
+{
+    SimulationBuilder::new()
+        .set_seed(SimRng::derive_seed(11, idx as u64))
+        .add_seeded_entity_spawner(|spawner, rng| {
+            for _ in 0..100
+            {
+                spawner.spawn(Coin(rng.random_bool(0.5)));
+            }
+        })
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn heads(simulation: &Simulation) -> f64
+{
+    simulation.iter::<Coin>().filter(|coin| coin.0).count() as f64
+}
+
+#[test]
+fn test_replicate_monitored()
+{
+    let mut estimates = Vec::new();
+    let ensemble = Simulation::replicate_monitored(12, 1, coin_flips, heads, |estimate| {
+        estimates.push((estimate.len(), estimate.mean()));
+        std::ops::ControlFlow::Continue(())
+    });
+
+    // an estimate is reported after every replica
+    assert_eq!(
+        estimates.iter().map(|(len, _)| *len).collect::<Vec<_>>(),
+        (1..=12).collect::<Vec<_>>()
+    );
+    let last = estimates
+        .last()
+        .and_then(|(_, mean)| *mean)
+        .expect("no mean");
+    assert!((last - ensemble.mean().expect("no mean")).abs() < 1e-9);
+
+    // the outcomes are returned in order of the replicas
+    let replicas = Simulation::replicate(12, 1, coin_flips);
+    assert_eq!(ensemble, EnsembleResult::from_replicas(&replicas, heads));
+}
+
+#[test]
+fn test_replicate_monitored_abort()
+{
+    let ensemble = Simulation::replicate_monitored(1000, 1, coin_flips, heads, |estimate| {
+        if estimate.len() < 5
+        {
+            std::ops::ControlFlow::Continue(())
+        }
+        else
+        {
+            std::ops::ControlFlow::Break(())
+        }
+    });
+    assert_eq!(ensemble.len(), 5);
+}
+
+#[test]
+fn test_control_variate()
+{
+    // the outcome is twice the control, plus some noise
+    let controls = [1.0, 2.0, 3.0, 4.0, 5.0];
+    let noise = [0.1, -0.1, 0.0, 0.1, -0.1];
+    let values = controls.iter().zip(noise).map(|(x, e)| 2.0 * x + e);
+
+    let plain = EnsembleResult::new(values);
+    let ensemble = plain.clone().with_control_variate(controls, 2.0);
+    assert_eq!(ensemble.mean(), Some(6.0));
+    assert_eq!(plain.estimate(), Some(6.0));
+    assert_close(
+        ensemble.control_coefficient().expect("no coefficient"),
+        1.98,
+    );
+
+    // the mean of the controls overshoots their expectation by 1, and so does the outcome
+    assert_close(ensemble.estimate().expect("no estimate"), 4.02);
+    assert!(
+        ensemble.standard_error().expect("no error") * 10.0
+            < plain.standard_error().expect("no error")
+    );
+    let interval = ensemble.confidence_interval(0.95).expect("no interval");
+    assert_eq!(interval.estimate, ensemble.estimate().expect("no estimate"));
+
+    let bootstrap = ensemble
+        .bootstrap_confidence_interval(0.95, 500, &mut SimRng::from_seed(3))
+        .expect("no interval");
+    assert!(bootstrap.half_width() < 0.5);
+
+    // controls that do not vary are of no use
+    let constant = plain.with_control_variate([1.0; 5], 0.0);
+    assert_eq!(constant.control_coefficient(), None);
+    assert_eq!(constant.estimate(), Some(6.0));
+}
+
+fn draws(_: usize) -> SimulationBuilder
+{
+    SimulationBuilder::new()
+        // ten uniform draws in [0, 1)
+        .add_control_variate(5.0)
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Coin(false));
+        })
+        .add_systems(
+            |mut rng: ResMut<SimRng>, mut control: ResMut<ControlVariate>| {
+                control.add(rng.random::<f64>());
+            },
+        )
+}
+
+#[test]
+fn test_control_variate_from_replicas()
+{
+    // an outcome that is fully explained by its control variate is estimated exactly
+    let outcome = |simulation: &Simulation| {
+        let control = simulation
+            .get_resource::<ControlVariate>()
+            .expect("no control variate");
+        control.value().mul_add(3.0, 1.0)
+    };
+
+    let replicas = Simulation::replicate_seeded(5, 20, 10, draws);
+    let ensemble = EnsembleResult::from_replicas(&replicas, outcome);
+    assert_close(ensemble.estimate().expect("no estimate"), 16.0);
+    assert!(ensemble.standard_error().expect("no error") < 1e-6);
+
+    let monitored = Simulation::replicate_monitored(20, 10, draws, outcome, |estimate| {
+        assert_eq!(estimate.len() > 1, estimate.control_coefficient().is_some());
+        std::ops::ControlFlow::Continue(())
+    });
+    assert_close(monitored.estimate().expect("no estimate"), 16.0);
+}
+
+#[test]
+#[should_panic(expected = "either all replicas or none of them must hold a control variate")]
+fn test_control_variate_missing()
+{
+    let replicas = Simulation::replicate(4, 1, |idx| {
+        let builder = SimulationBuilder::new();
+        if idx == 0
+        {
+            builder
+        }
+        else
+        {
+            builder.add_control_variate(0.0)
+        }
+    });
+    let _ = EnsembleResult::from_replicas(&replicas, |_| 1.0);
+}
+
+#[test]
+#[should_panic(expected = "invalid confidence level: 1")]
+fn test_invalid_level()
+{
+    let _ = EnsembleResult::new([1.0, 2.0]).confidence_interval(1.0);
+}
+
+#[test]
+fn test_replicate_monitored_seeds_replicas()
+{
+    // replicas whose builder sets no seed are seeded independently
+    #[allow(clippy::cast_precision_loss)]
+    let seed = |simulation: &Simulation| simulation.meta().seed.expect("no seed") as f64;
+    let ensemble = Simulation::replicate_monitored(
+        8,
+        1,
+        |_| SimulationBuilder::new(),
+        seed,
+        |_| std::ops::ControlFlow::Continue(()),
+    );
+
+    let mut seeds = ensemble.values().to_vec();
+    seeds.sort_unstable_by(f64::total_cmp);
+    seeds.dedup();
+    assert_eq!(seeds.len(), 8);
+}
@@ -0,0 +1,111 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+#![allow(clippy::cast_precision_loss)]
+use incerto::{
+    epidemic::{Compartment, EpidemicModule, EpidemicOutputs, Transmission},
+    prelude::*,
+};
+
+fn outputs(simulation: &Simulation) -> &EpidemicOutputs
+{
+    simulation
+        .get_resource::<EpidemicOutputs>()
+        .expect("missing epidemic outputs")
+}
+
+#[test]
+fn test_epidemic_well_mixed()
+{
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(1)
+        .add_module(EpidemicModule::sir(0.3, 0.1).with_population(2000, 10))
+        .build();
+    simulation.run(300);
+
+    let outputs = outputs(&simulation);
+    assert_eq!(outputs.history().len(), 300);
+    assert_eq!(outputs.history()[0].step, 1);
+
+    let latest = outputs.latest().expect("no steps recorded");
+    assert_eq!(latest.exposed, 0);
+    assert_eq!(
+        latest.susceptible + latest.infectious + latest.recovered,
+        2000
+    );
+    assert_eq!(
+        outputs.cumulative_incidence() + 10,
+        latest.infectious + latest.recovered
+    );
+    // with R0 = 3, over 90% of the population is expected to be infected
+    assert!(latest.recovered > 1700, "{}", latest.recovered);
+
+    let early = &outputs.history()[0];
+    assert!(early.r_eff.is_some_and(|r_eff| r_eff > 1.0));
+    assert_eq!(early.prevalence, early.infectious as f64 / 2000.0);
+}
+
+#[test]
+fn test_epidemic_network()
+{
+    // a chain of individuals, infected one after the other
+    let mut simulation = SimulationBuilder::new()
+        .add_module(EpidemicModule::seir(1.0, 1.0, 0.0).with_transmission(Transmission::Network))
+        .add_entity_spawner(|spawner| {
+            let people = (0..10)
+                .map(|i| {
+                    spawner.spawn(
+                        if i == 0
+                        {
+                            Compartment::Infectious
+                        }
+                        else
+                        {
+                            Compartment::Susceptible
+                        },
+                    )
+                })
+                .collect::<Vec<_>>();
+            spawner.add_edges::<Compartment>(&people, (1..10).map(|i| (i - 1, i)));
+        })
+        .build();
+    simulation.run(5);
+
+    let outputs = outputs(&simulation);
+    assert!(
+        outputs
+            .history()
+            .iter()
+            .all(|counts| counts.r_eff.is_none())
+    );
+
+    // every step, the next individual is exposed, and the previous becomes infectious
+    let incidence = outputs
+        .history()
+        .iter()
+        .map(|counts| (counts.incidence, counts.exposed, counts.infectious))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        incidence,
+        [(1, 1, 1), (0, 0, 2), (1, 1, 2), (0, 0, 3), (1, 1, 3)]
+    );
+}
+
+#[test]
+fn test_epidemic_spatial()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_module(EpidemicModule::sir(1.0, 0.0).with_transmission(Transmission::Spatial(None)))
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Compartment::Infectious, GridPosition2D::new(0, 0)));
+            spawner.spawn((Compartment::Susceptible, GridPosition2D::new(1, 1)));
+            spawner.spawn((Compartment::Susceptible, GridPosition2D::new(5, 5)));
+            spawner.spawn(Compartment::Susceptible);
+        })
+        .build();
+    simulation.run(3);
+
+    let latest = outputs(&simulation).latest().expect("no steps recorded");
+    assert_eq!(latest.infectious, 2);
+    assert_eq!(latest.susceptible, 2);
+    assert_eq!(outputs(&simulation).cumulative_incidence(), 1);
+}
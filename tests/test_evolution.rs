@@ -0,0 +1,225 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+#![allow(clippy::cast_precision_loss)]
+use incerto::{
+    evolution::{self, Evolution, EvolutionOutputs, EvolutionPlugin, Genome, Selection},
+    prelude::*,
+};
+use rand::SeedableRng;
+
+/// Bit string, with the number of ones as its fitness.
+#[derive(Component, Clone)]
+struct Bits(Vec<bool>);
+
+impl Genome for Bits
+{
+    fn crossover(&self, other: &Self, rng: &mut SimRng) -> Self
+    {
+        Self(evolution::single_point_crossover(&self.0, &other.0, rng))
+    }
+
+    fn mutate(&mut self, rate: f64, rng: &mut SimRng)
+    {
+        evolution::flip_bits(&mut self.0, rate, rng);
+    }
+}
+
+impl Sample<f64> for Bits
+{
+    fn sample(component: &Self) -> f64
+    {
+        component.0.iter().filter(|&&bit| bit).count() as f64
+    }
+}
+
+/// A real number, whose fitness is accumulated by a system over every generation.
+#[derive(Component, Clone)]
+struct Guess
+{
+    x: f64,
+    score: f64,
+}
+
+impl Genome for Guess
+{
+    fn crossover(&self, other: &Self, rng: &mut SimRng) -> Self
+    {
+        let x = evolution::uniform_crossover(&[self.x], &[other.x], rng)[0];
+        Self { x, score: 0.0 }
+    }
+
+    fn mutate(&mut self, rate: f64, rng: &mut SimRng)
+    {
+        let mut genes = [self.x];
+        evolution::perturb_gaussian(&mut genes, rate, 0.2, rng);
+        self.x = genes[0];
+    }
+
+    fn reset(&mut self)
+    {
+        self.score = 0.0;
+    }
+}
+
+impl Sample<f64> for Guess
+{
+    fn sample(component: &Self) -> f64
+    {
+        component.score
+    }
+}
+
+fn one_max(plugin: EvolutionPlugin<Bits>) -> Simulation
+{
+    SimulationBuilder::new()
+        .set_seed(17)
+        .add_evolution(plugin)
+        .add_entity_spawner(|spawner| {
+            for _ in 0..100
+            {
+                spawner.spawn(Bits(vec![false; 32]));
+            }
+        })
+        .build()
+}
+
+#[test]
+fn test_evolution_one_max()
+{
+    let plugin = EvolutionPlugin::new(1)
+        .with_selection(Selection::Tournament(3))
+        .with_mutation_rate(1.0 / 32.0)
+        .with_elitism(2);
+    let mut simulation = one_max(plugin);
+    simulation.run(150);
+
+    let evolution = simulation
+        .get_resource::<Evolution<Bits>>()
+        .expect("no evolution");
+    assert_eq!(evolution.generation(), 150);
+
+    let outputs = simulation
+        .get_resource::<EvolutionOutputs<Bits>>()
+        .expect("no outputs");
+    let history = outputs.history();
+    assert_eq!(history[0].generation, 1);
+    assert_eq!(history[0].best, 0.0);
+    assert!(history.last().expect("no generations").best >= 30.0);
+
+    // with elitism the best fitness never decreases
+    assert!(history.windows(2).all(|w| w[0].best <= w[1].best));
+    assert!(
+        history
+            .iter()
+            .all(|s| s.worst <= s.mean && s.mean <= s.best)
+    );
+
+    let fittest = evolution.fittest().expect("no fittest");
+    assert_eq!(
+        Bits::sample(fittest),
+        history.last().expect("no generations").best
+    );
+}
+
+#[test]
+fn test_evolution_selection_schemes()
+{
+    for selection in [
+        Selection::Tournament(4),
+        Selection::Roulette,
+        Selection::Truncation(0.2),
+    ]
+    {
+        let plugin = EvolutionPlugin::new(1)
+            .with_selection(selection)
+            .with_mutation_rate(0.05);
+        let mut simulation = one_max(plugin);
+        simulation.run(60);
+
+        let history = simulation
+            .get_resource::<EvolutionOutputs<Bits>>()
+            .expect("no outputs")
+            .history();
+        let last = history.last().expect("no generations");
+        assert!(last.mean > history[0].mean + 8.0, "{selection:?}: {last:?}");
+    }
+}
+
+#[test]
+fn test_evolution_generation_length_and_reset()
+{
+    let plugin = EvolutionPlugin::<Guess>::new(5).with_elitism(1);
+
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(4)
+        .add_evolution(plugin)
+        .add_seeded_entity_spawner(|spawner, rng| {
+            for _ in 0..50
+            {
+                let x = rand::Rng::random_range(rng, -10.0..10.0);
+                spawner.spawn(Guess { x, score: 0.0 });
+            }
+        })
+        // the closer to 3.0, the higher the score earned on every step
+        .add_systems(|mut query: Query<&mut Guess>| {
+            for mut guess in &mut query
+            {
+                guess.score -= (guess.x - 3.0).powi(2);
+            }
+        })
+        .build();
+    simulation.run(200);
+
+    let evolution = simulation
+        .get_resource::<Evolution<Guess>>()
+        .expect("no evolution");
+    assert_eq!(evolution.generation(), 40);
+
+    // every generation is scored over exactly five steps
+    let fittest = evolution.fittest().expect("no fittest");
+    let outputs = simulation
+        .get_resource::<EvolutionOutputs<Guess>>()
+        .expect("no outputs");
+    let best = outputs.latest().expect("no generations").best;
+
+    // the generations are recorded at their last step
+    let best_series = outputs.time_series(|stats| stats.best);
+    let best_series = best_series.as_time_series();
+    assert_eq!(best_series.sample_interval(), 5);
+    assert_eq!(best_series.time_slice()[..2], [5, 10]);
+    assert!(5.0f64.mul_add((fittest.x - 3.0).powi(2), best).abs() < 1e-9);
+    assert!((fittest.x - 3.0).abs() < 0.2, "fittest: {}", fittest.x);
+
+    // the scores were reset at the end of the last generation
+    assert!(simulation.iter::<Guess>().all(|guess| guess.score == 0.0));
+}
+
+#[test]
+fn test_evolution_operators()
+{
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let zeros = [0; 8];
+    let ones = [1; 10];
+
+    for _ in 0..20
+    {
+        let child = evolution::single_point_crossover(&zeros, &ones, &mut rng);
+        assert_eq!(child.len(), 8);
+        let point = child.iter().take_while(|&&gene| gene == 0).count();
+        assert!(child[point..].iter().all(|&gene| gene == 1));
+
+        let child = evolution::uniform_crossover(&zeros, &ones, &mut rng);
+        assert_eq!(child.len(), 8);
+    }
+
+    let mut bits = vec![false; 1000];
+    evolution::flip_bits(&mut bits, 0.0, &mut rng);
+    assert!(bits.iter().all(|&bit| !bit));
+    evolution::flip_bits(&mut bits, 1.0, &mut rng);
+    assert!(bits.iter().all(|&bit| bit));
+
+    let mut genes = vec![1.0; 1000];
+    evolution::perturb_gaussian(&mut genes, 0.5, 1.0, &mut rng);
+    let changed = genes.iter().filter(|&&gene| gene != 1.0).count();
+    assert!((400..600).contains(&changed));
+}
@@ -0,0 +1,123 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::prelude::*;
+use rand::{Rng, SeedableRng};
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64)
+{
+    assert!(
+        (actual - expected).abs() < tolerance,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn test_generalized_pareto()
+{
+    // the exponential distribution
+    let exponential = GeneralizedPareto::new(0.0, 2.0);
+    assert_close(exponential.survival(2.0), (-1.0_f64).exp(), 1e-12);
+    assert_close(exponential.quantile(0.5), 2.0 * 2.0_f64.ln(), 1e-12);
+    assert_eq!(exponential.survival(-1.0), 1.0);
+    assert_eq!(exponential.cdf(0.0), 0.0);
+
+    // a heavy tail, which decays as a power law
+    let heavy = GeneralizedPareto::new(0.5, 1.0);
+    assert_close(heavy.survival(2.0), 0.25, 1e-12);
+    assert_close(heavy.quantile(0.75), 2.0, 1e-12);
+
+    // a bounded tail, which ends at 2
+    let bounded = GeneralizedPareto::new(-0.5, 1.0);
+    assert_close(bounded.survival(1.0), 0.25, 1e-12);
+    assert_eq!(bounded.survival(2.0), 0.0);
+    assert_eq!(bounded.cdf(3.0), 1.0);
+
+    for p in [0.0, 0.1, 0.5, 0.9, 0.999]
+    {
+        for distribution in [exponential, heavy, bounded]
+        {
+            assert_close(distribution.cdf(distribution.quantile(p)), p, 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_fit_generalized_pareto()
+{
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+
+    for shape in [-0.3, 0.0, 0.3]
+    {
+        let truth = GeneralizedPareto::new(shape, 2.0);
+        let excesses = (0..20_000).map(|_| truth.quantile(rng.random_range(0.0..1.0)));
+        let fitted = GeneralizedPareto::fit(excesses).expect("no fit");
+
+        assert_close(fitted.shape(), shape, 0.05);
+        assert_close(fitted.scale(), 2.0, 0.1);
+    }
+
+    assert_eq!(GeneralizedPareto::fit([1.0]), None);
+    assert_eq!(GeneralizedPareto::fit([1.0, -1.0]), None);
+    assert_eq!(GeneralizedPareto::fit([0.0, 0.0, 0.0]), None);
+}
+
+#[test]
+#[should_panic(expected = "invalid scale")]
+fn test_invalid_scale()
+{
+    let _ = GeneralizedPareto::new(0.1, 0.0);
+}
+
+#[test]
+fn test_peaks_over_threshold()
+{
+    let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+
+    // exponential outcomes, whose tail beyond any threshold is exponential too
+    let ensemble = (0..20_000)
+        .map(|_| -(1.0 - rng.random_range(0.0_f64..1.0)).ln())
+        .collect::<EnsembleResult>();
+
+    let threshold = 2.0;
+    let tail = ensemble
+        .peaks_over_threshold(threshold)
+        .expect("no tail model");
+    assert_eq!(tail.threshold(), threshold);
+    assert_close(tail.exceedance_rate(), (-threshold).exp(), 0.01);
+    assert_eq!(
+        Some(tail.exceedance_rate()),
+        ensemble.exceedance_probability(threshold)
+    );
+    assert!(tail.exceedances() > 2000);
+
+    // levels beyond the largest value are extrapolated, rather than zero
+    let largest = ensemble
+        .values()
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    assert_eq!(ensemble.exceedance_probability(largest), Some(0.0));
+    assert!(tail.exceedance_probability(largest + 1.0) > 0.0);
+
+    assert_close(tail.exceedance_probability(5.0).ln(), -5.0, 0.3);
+    assert_close(tail.return_level(1000.0), 1000.0_f64.ln(), 0.3);
+    assert_close(
+        tail.exceedance_probability(tail.return_level(500.0)),
+        1.0 / 500.0,
+        1e-9,
+    );
+
+    // too few values above the threshold
+    assert_eq!(ensemble.peaks_over_threshold(largest), None);
+    assert_eq!(EnsembleResult::default().exceedance_probability(0.0), None);
+}
+
+#[test]
+#[should_panic(expected = "is below the threshold")]
+fn test_short_return_period()
+{
+    let tail = PeaksOverThreshold::fit([1.0, 2.0, 3.0, 4.0, 6.0, 9.0], 2.5).expect("no tail model");
+
+    // two in three values exceed the threshold, so the level exceeded more often is below it
+    let _ = tail.return_level(1.2);
+}
@@ -0,0 +1,217 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use bevy::math::DVec2;
+use incerto::{
+    flocking::{Boid, Boundary, FlockOutputs, FlockingPlugin, Predator},
+    prelude::*,
+};
+
+#[derive(Component)]
+struct Fish;
+
+#[derive(Component)]
+struct Bird;
+
+/// A flock without any steering behaviors, to which they are added one at a time, in which boids
+/// reach their desired velocity in a single step.
+fn still() -> FlockingPlugin<Fish>
+{
+    FlockingPlugin::new(2.0)
+        .with_separation(0.0, 0.0)
+        .with_alignment(0.0)
+        .with_cohesion(0.0)
+        .with_avoidance(0.0, 0.0)
+        .with_limits(1.0, 10.0)
+}
+
+/// Runs one step of a flock of fish, and returns their boids in the order they were given.
+fn step(plugin: FlockingPlugin<Fish>, boids: &[Boid]) -> Vec<Boid>
+{
+    let boids = boids.to_vec();
+    let mut simulation = SimulationBuilder::new()
+        .add_flocking(plugin)
+        .add_entity_spawner(move |spawner| {
+            for (index, boid) in boids.iter().enumerate()
+            {
+                spawner.spawn((Fish, *boid, Order(index)));
+            }
+        })
+        .build();
+    simulation.run(1);
+
+    let mut boids = simulation
+        .iter_with::<Boid, With<Fish>>()
+        .copied()
+        .zip(
+            simulation
+                .iter_with::<Order, With<Fish>>()
+                .map(|order| order.0),
+        )
+        .collect::<Vec<_>>();
+    boids.sort_by_key(|(_, order)| *order);
+    boids.into_iter().map(|(boid, _)| boid).collect()
+}
+
+#[derive(Component)]
+struct Order(usize);
+
+const fn at(x: f64, y: f64) -> Boid
+{
+    Boid::new(DVec2::new(x, y), DVec2::ZERO)
+}
+
+#[test]
+fn test_alignment()
+{
+    let boids = step(
+        still().with_alignment(0.5),
+        &[
+            Boid::new(DVec2::ZERO, DVec2::X),
+            Boid::new(DVec2::X, DVec2::Y),
+        ],
+    );
+    assert_eq!(boids[0].velocity(), DVec2::splat(0.5));
+    assert_eq!(boids[1].velocity(), DVec2::splat(0.5));
+    assert_eq!(boids[0].position(), DVec2::splat(0.5));
+    assert_eq!(boids[1].position(), DVec2::new(1.5, 0.5));
+}
+
+#[test]
+fn test_cohesion_and_separation()
+{
+    let boids = step(still().with_cohesion(0.5), &[at(0.0, 0.0), at(2.0, 0.0)]);
+    assert_eq!(boids[0].position(), DVec2::new(0.5, 0.0));
+    assert_eq!(boids[1].position(), DVec2::new(1.5, 0.0));
+
+    let boids = step(
+        still().with_separation(1.0, 1.0),
+        &[at(0.0, 0.0), at(0.5, 0.0)],
+    );
+    assert_eq!(boids[0].position(), DVec2::new(-1.0, 0.0));
+    assert_eq!(boids[1].position(), DVec2::new(1.5, 0.0));
+
+    // boids move away from the closest flockmates, even when more are on the other side
+    let boids = step(
+        still().with_separation(1.0, 1.0),
+        &[at(0.0, 0.0), at(0.25, 0.0), at(-0.75, 0.1), at(-0.75, -0.1)],
+    );
+    assert_eq!(boids[0].position(), DVec2::new(-1.0, 0.0));
+
+    // boids beyond the perception radius are ignored
+    let boids = step(still().with_cohesion(1.0), &[at(0.0, 0.0), at(2.5, 0.0)]);
+    assert_eq!(boids[0].position(), DVec2::ZERO);
+}
+
+#[test]
+fn test_limits()
+{
+    let boids = step(
+        still().with_cohesion(1.0).with_limits(0.5, 0.2),
+        &[Boid::new(DVec2::ZERO, DVec2::X), at(0.0, 2.0)],
+    );
+    // the steering force is limited first, and then the speed
+    assert_eq!(boids[1].velocity(), DVec2::new(0.0, -0.2));
+    assert!((boids[0].velocity().length() - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn test_predator_avoidance()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_flocking(still().with_avoidance(1.0, 3.0))
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Fish, at(0.0, 0.0)));
+            spawner.spawn((Fish, at(10.0, 0.0)));
+            spawner.spawn((Predator::<Fish>::default(), at(2.0, 0.0)));
+        })
+        .build();
+    simulation.run(1);
+
+    let mut fish = simulation
+        .iter_with::<Boid, With<Fish>>()
+        .map(Boid::position)
+        .collect::<Vec<_>>();
+    fish.sort_by(|a, b| a.x.total_cmp(&b.x));
+    assert_eq!(fish, vec![DVec2::new(-1.0, 0.0), DVec2::new(10.0, 0.0)]);
+
+    // the predator is not part of the flock
+    let predator = simulation
+        .iter_with::<Boid, With<Predator<Fish>>>()
+        .next()
+        .expect("no predator");
+    assert_eq!(predator.position(), DVec2::new(2.0, 0.0));
+}
+
+#[test]
+fn test_boundaries()
+{
+    let wrap = Boundary::Wrap {
+        min: DVec2::ZERO,
+        max: DVec2::splat(10.0),
+    };
+    let boids = step(
+        still().with_boundary(wrap),
+        &[Boid::new(DVec2::new(9.5, 5.0), DVec2::X)],
+    );
+    assert_eq!(boids[0].position(), DVec2::new(0.5, 5.0));
+
+    // flockmates are sensed across the edges
+    let boids = step(
+        still().with_cohesion(0.5).with_boundary(wrap),
+        &[at(9.5, 5.0), at(0.5, 5.0)],
+    );
+    assert_eq!(boids[0].position(), DVec2::new(0.0, 5.0));
+    assert_eq!(boids[1].position(), DVec2::new(0.0, 5.0));
+
+    let reflect = Boundary::Reflect {
+        min: DVec2::ZERO,
+        max: DVec2::splat(10.0),
+    };
+    let boids = step(
+        still().with_boundary(reflect),
+        &[Boid::new(DVec2::new(9.5, 5.0), DVec2::X)],
+    );
+    assert_eq!(boids[0].position(), DVec2::new(9.5, 5.0));
+    assert_eq!(boids[0].velocity(), -DVec2::X);
+}
+
+#[test]
+fn test_grid_positions_and_species()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_flocking(still().with_cohesion(1.0))
+        .add_flocking(FlockingPlugin::<Bird>::new(2.0))
+        .add_entity_spawner(|spawner| {
+            let cell = GridPosition2D::new(3, 3);
+            spawner.spawn((Fish, Boid::at_cell(cell, DVec2::new(0.6, 0.0)), cell));
+            spawner.spawn((Bird, at(3.0, 3.0)));
+        })
+        .build();
+    simulation.run(1);
+
+    // the fish ignores the bird, which belongs to another flock
+    let position = simulation
+        .iter_with::<GridPosition2D, With<Fish>>()
+        .next()
+        .expect("no fish");
+    assert_eq!(*position, GridPosition2D::new(4, 3));
+
+    let metrics = simulation
+        .get_resource::<FlockOutputs<Fish>>()
+        .expect("no flock outputs")
+        .latest()
+        .copied()
+        .expect("no steps recorded");
+    assert_eq!(metrics.step, 1);
+    assert_eq!(metrics.boids, 1);
+    assert_eq!(metrics.mean_neighbors, 0.0);
+    assert_eq!(metrics.polarization, 1.0);
+    assert_eq!(metrics.mean_speed, 0.6);
+}
+
+#[test]
+#[should_panic(expected = "invalid perception radius: 0")]
+fn test_invalid_perception()
+{
+    let _ = FlockingPlugin::<Fish>::new(0.0);
+}
@@ -0,0 +1,269 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::{
+    flow::{CommodityLedger, ConservationViolation, Flow, FlowOutputs, Stock},
+    prelude::*,
+};
+
+struct Money;
+
+#[derive(Component)]
+struct Bank;
+
+#[derive(Component)]
+struct Customer;
+
+fn ledger(simulation: &Simulation) -> &CommodityLedger<Money>
+{
+    simulation
+        .get_resource::<CommodityLedger<Money>>()
+        .expect("no ledger")
+}
+
+fn outputs(simulation: &Simulation) -> &FlowOutputs<Money>
+{
+    simulation
+        .get_resource::<FlowOutputs<Money>>()
+        .expect("no outputs")
+}
+
+fn amount<F: Component>(simulation: &Simulation) -> f64
+{
+    simulation
+        .iter_with::<Stock<Money>, With<F>>()
+        .map(Stock::amount)
+        .sum()
+}
+
+/// Builds a simulation of a bank and a customer, with the flows returned by `flows`.
+fn bank(
+    bank_amount: f64,
+    customer_amount: f64,
+    flows: impl Fn(Entity, Entity) -> Vec<Flow<Money>> + 'static,
+) -> SimulationBuilder
+{
+    SimulationBuilder::new()
+        .add_commodity::<Money>()
+        .record_events::<ConservationViolation>()
+        .add_entity_spawner(move |spawner| {
+            let bank = spawner
+                .spawn((Bank, Stock::<Money>::new(bank_amount)))
+                .entity();
+            let customer = spawner
+                .spawn((Customer, Stock::<Money>::new(customer_amount)))
+                .entity();
+            for flow in flows(bank, customer)
+            {
+                spawner.spawn(flow);
+            }
+        })
+}
+
+#[test]
+fn test_transfers_conserve_total()
+{
+    let mut simulation = bank(100.0, 0.0, |bank, customer| {
+        vec![Flow::between(bank, customer, 10.0)]
+    })
+    .build();
+
+    simulation.run(5);
+
+    assert_eq!(amount::<Bank>(&simulation), 50.0);
+    assert_eq!(amount::<Customer>(&simulation), 50.0);
+
+    let outputs = outputs(&simulation);
+    assert_eq!(outputs.history().len(), 5);
+    assert!(outputs.history().iter().all(|record| record.total == 100.0));
+    assert!(
+        outputs
+            .history()
+            .iter()
+            .all(|record| record.transferred == 10.0)
+    );
+    assert!(
+        outputs
+            .history()
+            .iter()
+            .all(|record| record.imbalance == 0.0)
+    );
+
+    // the initial stocks are created in the first step
+    assert_eq!(outputs.history()[0].created, 100.0);
+    assert_eq!(outputs.history()[1].created, 0.0);
+
+    let events = simulation
+        .event_log::<ConservationViolation>()
+        .expect("no event log");
+    assert_eq!(events.iter().count(), 0);
+}
+
+#[test]
+fn test_inflows_and_outflows()
+{
+    let mut simulation = bank(100.0, 0.0, |bank, customer| {
+        vec![
+            Flow::inflow(bank, 5.0),
+            Flow::between(bank, customer, 3.0),
+            Flow::outflow(customer, 1.0),
+        ]
+    })
+    .build();
+
+    simulation.run(10);
+
+    let outputs = outputs(&simulation);
+    let latest = outputs.latest().expect("no steps");
+    assert_eq!(latest.step, 10);
+    assert_eq!(latest.inflow, 5.0);
+    assert_eq!(latest.outflow, 1.0);
+    assert_eq!(latest.transferred, 3.0);
+    // 50 flowed in and 9 out, since the customer had nothing to spend on the first step
+    assert_eq!(latest.total, 141.0);
+    assert_eq!(outputs.history()[0].outflow, 0.0);
+
+    let ledger = ledger(&simulation);
+    let customer_outflow: f64 = ledger
+        .fluxes()
+        .filter(|(from, to, _)| from.is_some() && to.is_none())
+        .map(|(_, _, flux)| flux)
+        .sum();
+    assert_eq!(customer_outflow, 1.0);
+    assert_eq!(ledger.fluxes().count(), 3);
+}
+
+#[test]
+fn test_short_stock_scales_flows()
+{
+    let mut simulation = bank(3.0, 0.0, |bank, customer| {
+        vec![Flow::between(bank, customer, 2.0), Flow::outflow(bank, 4.0)]
+    })
+    .build();
+
+    simulation.run(1);
+
+    assert_eq!(amount::<Bank>(&simulation), 0.0);
+    assert_eq!(amount::<Customer>(&simulation), 1.0);
+
+    let mut fluxes: Vec<f64> = simulation.iter::<Flow<Money>>().map(Flow::flux).collect();
+    fluxes.sort_by(f64::total_cmp);
+    assert_eq!(fluxes, vec![1.0, 2.0]);
+
+    // once empty, the bank has nothing left to give
+    simulation.run(1);
+    assert!(
+        simulation
+            .iter::<Flow<Money>>()
+            .all(|flow| flow.flux() == 0.0)
+    );
+    assert_eq!(outputs(&simulation).latest().expect("no steps").total, 1.0);
+}
+
+#[test]
+fn test_direct_transfers_are_conserved()
+{
+    let mut simulation = bank(100.0, 0.0, |_, _| Vec::new())
+        .add_systems(
+            |mut bank: Single<&mut Stock<Money>, (With<Bank>, Without<Customer>)>,
+             mut customer: Single<&mut Stock<Money>, With<Customer>>| {
+                let withdrawn = bank.withdraw(30.0);
+                customer.deposit(withdrawn);
+            },
+        )
+        .build();
+
+    simulation.run(5);
+
+    assert_eq!(amount::<Bank>(&simulation), 0.0);
+    assert_eq!(amount::<Customer>(&simulation), 100.0);
+
+    let events = simulation
+        .event_log::<ConservationViolation>()
+        .expect("no event log");
+    assert_eq!(events.iter().count(), 0);
+}
+
+#[test]
+fn test_unmatched_deposit_is_a_violation()
+{
+    let mut simulation = bank(100.0, 0.0, |_, _| Vec::new())
+        .add_systems(
+            |mut customer: Single<&mut Stock<Money>, With<Customer>>, step: Res<StepNumber>| {
+                if step.get() == 2
+                {
+                    customer.deposit(7.0);
+                }
+            },
+        )
+        .build();
+
+    simulation.run(5);
+
+    let events = simulation
+        .event_log::<ConservationViolation>()
+        .expect("no event log");
+    let events: Vec<_> = events.iter().collect();
+    assert_eq!(events.len(), 1);
+
+    let (step, violation) = events[0];
+    assert_eq!(step, 2);
+    assert_eq!(violation.expected, 100.0);
+    assert_eq!(violation.actual, 107.0);
+
+    // the new total is accepted from then on
+    let outputs = outputs(&simulation);
+    assert_eq!(outputs.history()[1].imbalance, 7.0);
+    assert_eq!(outputs.history()[2].imbalance, 0.0);
+    assert_eq!(outputs.latest().expect("no steps").total, 107.0);
+}
+
+#[test]
+#[should_panic(expected = "not conserved")]
+fn test_violation_panics_when_strict()
+{
+    let mut simulation = bank(100.0, 0.0, |_, _| Vec::new())
+        .set_strictness(StrictnessPolicy::Strict)
+        .add_systems(|mut customer: Single<&mut Stock<Money>, With<Customer>>| {
+            customer.deposit(1.0);
+        })
+        .build();
+
+    simulation.run(1);
+}
+
+#[test]
+fn test_spawned_and_despawned_stocks()
+{
+    let mut simulation = bank(100.0, 20.0, |_, _| Vec::new()).build();
+
+    simulation.run(1);
+    simulation.spawn((Customer, Stock::<Money>::new(5.0)));
+    simulation.run(1);
+    assert_eq!(simulation.despawn_where::<With<Bank>>(), 1);
+    simulation.run(1);
+
+    let history = outputs(&simulation).history();
+    assert_eq!(history[1].created, 5.0);
+    assert_eq!(history[1].total, 125.0);
+    assert_eq!(history[2].destroyed, 100.0);
+    assert_eq!(history[2].total, 25.0);
+    assert!(history.iter().all(|record| record.imbalance == 0.0));
+}
+
+#[test]
+fn test_flows_without_stocks_move_nothing()
+{
+    let mut simulation = bank(100.0, 0.0, |bank, customer| {
+        vec![Flow::between(bank, customer, 10.0)]
+    })
+    .build();
+
+    simulation.run(1);
+    assert_eq!(simulation.despawn_where::<With<Customer>>(), 1);
+    simulation.run(1);
+
+    assert_eq!(amount::<Bank>(&simulation), 90.0);
+    let latest = outputs(&simulation).latest().expect("no steps");
+    assert_eq!(latest.transferred, 0.0);
+    assert_eq!(ledger(&simulation).fluxes().count(), 0);
+}
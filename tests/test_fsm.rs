@@ -0,0 +1,164 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::cast_precision_loss)]
+use incerto::{
+    fsm::{Fsm, StateCounts, Transitions},
+    prelude::*,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Health
+{
+    Healthy,
+    Exposed,
+    Infectious,
+    Recovered,
+}
+
+#[derive(Component)]
+struct Vaccinated;
+
+/// Builds a simulation of `count` healthy agents following the given transitions.
+fn agents(count: usize, transitions: Transitions<Health>) -> Simulation
+{
+    SimulationBuilder::new()
+        .set_seed(9)
+        .add_state_machine(transitions)
+        .add_entity_spawner(move |spawner| {
+            for _ in 0..count
+            {
+                spawner.spawn(Fsm::new(Health::Healthy));
+            }
+        })
+        .build()
+}
+
+fn counts(simulation: &Simulation) -> &StateCounts<Health>
+{
+    simulation
+        .get_resource::<StateCounts<Health>>()
+        .expect("no state counts")
+}
+
+#[test]
+fn test_fsm_after_steps()
+{
+    let transitions = Transitions::new()
+        .after_steps(Health::Healthy, Health::Exposed, 0)
+        .after_steps(Health::Exposed, Health::Infectious, 3)
+        .after_steps(Health::Infectious, Health::Recovered, 2);
+    let mut simulation = agents(10, transitions);
+
+    let mut history = Vec::new();
+    for _ in 0..7
+    {
+        simulation.run(1);
+        let fsm = simulation.iter::<Fsm<Health>>().next().expect("no agents");
+        history.push(*fsm.state());
+        assert_eq!(counts(&simulation).get(fsm.state()), 10);
+    }
+
+    // three steps exposed, two infectious
+    assert_eq!(
+        history,
+        [
+            Health::Exposed,
+            Health::Exposed,
+            Health::Exposed,
+            Health::Infectious,
+            Health::Infectious,
+            Health::Recovered,
+            Health::Recovered,
+        ]
+    );
+    let fsm = simulation.iter::<Fsm<Health>>().next().expect("no agents");
+    assert_eq!(fsm.steps_in_state(), 2);
+}
+
+#[test]
+fn test_fsm_probabilities()
+{
+    let transitions = Transitions::new()
+        .with_probability(Health::Healthy, Health::Exposed, 0.2)
+        .with_probability(Health::Healthy, Health::Recovered, 0.3);
+    let mut simulation = agents(10_000, transitions);
+    simulation.run(1);
+
+    let counts = counts(&simulation);
+    assert_eq!(counts.total(), 10_000);
+    assert_eq!(counts.get(&Health::Infectious), 0);
+
+    let exposed = counts.get(&Health::Exposed) as f64 / 10_000.0;
+    let recovered = counts.get(&Health::Recovered) as f64 / 10_000.0;
+    assert!((exposed - 0.2).abs() < 0.02, "exposed: {exposed}");
+    assert!((recovered - 0.3).abs() < 0.02, "recovered: {recovered}");
+    assert_eq!(counts.iter().count(), 3);
+}
+
+#[test]
+fn test_fsm_guarded_transitions()
+{
+    let transitions = Transitions::new()
+        .when(Health::Healthy, Health::Recovered, |agent| {
+            agent.contains::<Vaccinated>()
+        })
+        .with_probability(Health::Healthy, Health::Infectious, 1.0);
+
+    let mut simulation = SimulationBuilder::new()
+        .add_state_machine(transitions)
+        .add_entity_spawner(|spawner| {
+            for i in 0..100
+            {
+                if i % 4 == 0
+                {
+                    spawner.spawn((Fsm::new(Health::Healthy), Vaccinated));
+                }
+                else
+                {
+                    spawner.spawn(Fsm::new(Health::Healthy));
+                }
+            }
+        })
+        .build();
+    simulation.run(1);
+
+    let counts = counts(&simulation);
+    assert_eq!(counts.get(&Health::Recovered), 25);
+    assert_eq!(counts.get(&Health::Infectious), 75);
+    assert_eq!(counts.get(&Health::Healthy), 0);
+}
+
+#[test]
+fn test_fsm_set_state_from_system()
+{
+    let transitions = Transitions::new().after_steps(Health::Infectious, Health::Recovered, 1);
+
+    let mut simulation = SimulationBuilder::new()
+        .add_state_machine(transitions)
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Fsm::new(Health::Healthy));
+        })
+        .add_systems(|mut query: Query<&mut Fsm<Health>>| {
+            for mut fsm in &mut query
+            {
+                if *fsm.state() == Health::Healthy
+                {
+                    fsm.set_state(Health::Infectious);
+                }
+            }
+        })
+        .build();
+
+    simulation.run(1);
+    assert_eq!(counts(&simulation).get(&Health::Infectious), 1);
+    simulation.run(1);
+    assert_eq!(counts(&simulation).get(&Health::Recovered), 1);
+}
+
+#[test]
+#[should_panic(expected = "add up to")]
+fn test_fsm_probabilities_exceed_one()
+{
+    let _ = Transitions::new()
+        .with_probability(Health::Healthy, Health::Exposed, 0.6)
+        .with_probability(Health::Healthy, Health::Recovered, 0.6);
+}
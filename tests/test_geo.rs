@@ -0,0 +1,108 @@
+#![allow(clippy::expect_used)]
+use incerto::prelude::*;
+
+const ASCII_GRID: &str = "\
+ncols 3
+nrows 2
+xllcorner 100.0
+yllcorner 200.0
+cellsize 10.0
+NODATA_value -9999
+1 2 3
+4 -9999 6
+";
+
+#[test]
+fn test_read_ascii_grid()
+{
+    let (layer, transform) = geo::parse_ascii_grid(ASCII_GRID).expect("failed to parse raster");
+
+    assert_eq!(layer.bounds().max, IVec2::new(2, 1));
+    assert_eq!(transform.origin, bevy::math::DVec2::new(100.0, 200.0));
+
+    // the first row of the raster is the top of the grid
+    assert_eq!(layer.get(&GridPosition2D::new(0, 1)), Some(1.0));
+    assert_eq!(layer.get(&GridPosition2D::new(2, 1)), Some(3.0));
+    assert_eq!(layer.get(&GridPosition2D::new(0, 0)), Some(4.0));
+    assert!(
+        layer
+            .get(&GridPosition2D::new(1, 0))
+            .is_some_and(f64::is_nan)
+    );
+    assert_eq!(layer.get(&GridPosition2D::new(3, 0)), None);
+
+    assert_eq!(
+        transform.cell_at(transform.cell_center(&GridPosition2D::new(2, 1))),
+        GridPosition2D::new(2, 1)
+    );
+
+    let truncated = ASCII_GRID.replace("4 -9999 6", "4 5");
+    assert_eq!(
+        geo::parse_ascii_grid(&truncated),
+        Err(DatasetError::InvalidRecord { line: None })
+    );
+    let invalid = ASCII_GRID.replace("4 -9999 6", "4 x 6");
+    assert_eq!(
+        geo::parse_ascii_grid(&invalid),
+        Err(DatasetError::InvalidRecord { line: Some(8) })
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_read_geojson()
+{
+    // a 10x10 square with a 2x2 hole, and a smaller square overlapping its corner
+    let geojson = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "properties": { "density": 1.5 },
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [
+                        [[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]],
+                        [[4, 4], [6, 4], [6, 6], [4, 6], [4, 4]]
+                    ]
+                }
+            },
+            {
+                "type": "Feature",
+                "properties": { "density": 3 },
+                "geometry": {
+                    "type": "MultiPolygon",
+                    "coordinates": [[[[8, 8], [12, 8], [12, 12], [8, 12], [8, 8]]]]
+                }
+            }
+        ]
+    }"#;
+    let transform = geo::GeoTransform {
+        origin: bevy::math::DVec2::ZERO,
+        cell_size: 1.0,
+    };
+    let bounds = GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(11, 11),
+    };
+    let layer =
+        geo::parse_geojson(geojson, "density", &transform, bounds).expect("failed to parse");
+
+    assert_eq!(layer.get(&GridPosition2D::new(0, 0)), Some(1.5));
+    assert_eq!(layer.get(&GridPosition2D::new(3, 5)), Some(1.5));
+    assert!(
+        layer
+            .get(&GridPosition2D::new(4, 4))
+            .is_some_and(f64::is_nan)
+    );
+    assert_eq!(layer.get(&GridPosition2D::new(9, 9)), Some(3.0));
+    assert_eq!(layer.get(&GridPosition2D::new(11, 11)), Some(3.0));
+    assert!(
+        layer
+            .get(&GridPosition2D::new(11, 0))
+            .is_some_and(f64::is_nan)
+    );
+
+    let populated = layer.iter().filter(|(_, value)| value.is_finite()).count();
+    assert_eq!(populated, 100 - 4 + 12);
+}
@@ -0,0 +1,157 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+#![allow(clippy::cast_precision_loss)]
+use incerto::{gillespie::SimulationTime, prelude::*};
+
+#[derive(Component)]
+struct Molecules(u32);
+
+impl SampleAggregate<u32> for Molecules
+{
+    fn sample_aggregate(components: &[&Self]) -> u32
+    {
+        components.iter().map(|molecules| molecules.0).sum()
+    }
+}
+
+/// Builds a birth–death process, with births at a constant rate and deaths at a rate
+/// proportional to the population.
+fn birth_death(seed: u64, initial: u32, birth_rate: f64, death_rate: f64) -> Simulation
+{
+    SimulationBuilder::new()
+        .set_seed(seed)
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn(Molecules(initial));
+        })
+        .add_reaction(
+            move || birth_rate,
+            |mut molecules: Single<&mut Molecules>| molecules.0 += 1,
+        )
+        .add_reaction(
+            move |molecules: Single<&Molecules>| death_rate * f64::from(molecules.0),
+            |mut molecules: Single<&mut Molecules>| molecules.0 -= 1,
+        )
+        .record_trajectory::<Molecules, u32>()
+        .build()
+}
+
+#[test]
+fn test_gillespie_birth_death_stationary_mean()
+{
+    let mut simulation = birth_death(3, 0, 5.0, 0.5);
+    simulation.run_until(2000.0);
+
+    let time = simulation
+        .get_resource::<SimulationTime>()
+        .expect("no clock");
+    assert!(time.get() >= 2000.0);
+    assert!(!time.is_exhausted());
+
+    // the stationary distribution is Poisson with a mean of 5.0 / 0.5 = 10.0
+    let trajectory = simulation
+        .get_trajectory::<Molecules, u32>()
+        .expect("trajectory not recorded");
+    let samples = trajectory.resample(0.5, 2000.0);
+    assert_eq!(samples.len(), 4001);
+    let mean =
+        samples[200..].iter().map(|&n| f64::from(n)).sum::<f64>() / (samples.len() - 200) as f64;
+    assert!((mean - 10.0).abs() < 0.5, "mean: {mean}");
+}
+
+#[test]
+fn test_gillespie_trajectory_points()
+{
+    let mut simulation = birth_death(7, 20, 1.0, 0.2);
+    simulation.run(50);
+
+    let events = simulation
+        .get_resource::<SimulationTime>()
+        .expect("no clock")
+        .events();
+    assert_eq!(events, 50);
+
+    let trajectory = simulation
+        .get_trajectory::<Molecules, u32>()
+        .expect("trajectory not recorded");
+    assert_eq!(trajectory.len(), 51);
+    assert_eq!(trajectory.times()[0], 0.0);
+    assert_eq!(trajectory.values()[0], 20);
+    assert!(trajectory.times().windows(2).all(|w| w[0] < w[1]));
+
+    // every event changes the population by exactly one
+    assert!(
+        trajectory
+            .values()
+            .windows(2)
+            .all(|w| w[0].abs_diff(w[1]) == 1)
+    );
+
+    let (last_time, &last_value) = trajectory.iter().last().expect("empty trajectory");
+    assert_eq!(trajectory.value_at(last_time + 1.0), Some(&last_value));
+    assert_eq!(trajectory.value_at(-1.0), None);
+}
+
+#[test]
+fn test_gillespie_decay_exhausts()
+{
+    // a pure death process, which ends once all molecules have decayed
+    let mut simulation = birth_death(1, 200, 0.0, 1.0);
+    simulation.run_until(f64::INFINITY);
+
+    let clock = simulation
+        .get_resource::<SimulationTime>()
+        .expect("no clock");
+    assert!(clock.is_exhausted());
+    assert_eq!(clock.events(), 200);
+
+    let trajectory = simulation
+        .get_trajectory::<Molecules, u32>()
+        .expect("trajectory not recorded");
+    assert_eq!(trajectory.len(), 201);
+    assert_eq!(trajectory.values().last(), Some(&0));
+
+    // half of the molecules have decayed after ln(2) on average
+    let half_life = trajectory
+        .iter()
+        .find(|&(_, &n)| n <= 100)
+        .map(|(time, _)| time)
+        .expect("molecules never halved");
+    assert!(
+        (half_life - 2.0_f64.ln()).abs() < 0.2,
+        "half-life: {half_life}"
+    );
+}
+
+#[test]
+fn test_gillespie_reproducible()
+{
+    let run = || {
+        let mut simulation = birth_death(42, 10, 2.0, 0.3);
+        simulation.run_until(30.0);
+        simulation
+            .get_trajectory::<Molecules, u32>()
+            .expect("trajectory not recorded")
+            .clone()
+    };
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn test_gillespie_trajectory_not_recorded()
+{
+    let simulation = SimulationBuilder::new().add_reaction(|| 1.0, || ()).build();
+
+    let err = simulation
+        .get_trajectory::<Molecules, u32>()
+        .expect_err("trajectory was not recorded");
+    assert!(matches!(err, SamplingError::TimeSeriesNotRecorded { .. }));
+}
+
+#[test]
+#[should_panic(expected = "no reactions were added")]
+fn test_gillespie_run_until_without_reactions()
+{
+    let mut simulation = SimulationBuilder::new().build();
+    simulation.run_until(1.0);
+}
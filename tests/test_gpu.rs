@@ -0,0 +1,385 @@
+#![cfg(feature = "gpu")]
+#![allow(clippy::expect_used)]
+#![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::cast_sign_loss)]
+#![allow(clippy::float_cmp)]
+use incerto::{
+    gpu::{GpuAutomatonPlugin, GpuCell, GpuRule},
+    prelude::*,
+    spin::{Ising, Spin},
+};
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+struct Concentration(f32);
+
+impl Sample<f32> for Concentration
+{
+    fn sample(component: &Self) -> f32
+    {
+        component.to_gpu()
+    }
+}
+
+impl GpuCell for Concentration
+{
+    fn to_gpu(&self) -> f32
+    {
+        self.0
+    }
+
+    fn from_gpu(value: f32) -> Self
+    {
+        Self(value)
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum Forest
+{
+    Empty,
+    Tree,
+    Burning,
+}
+
+impl Sample<f32> for Forest
+{
+    fn sample(component: &Self) -> f32
+    {
+        component.to_gpu()
+    }
+}
+
+impl GpuCell for Forest
+{
+    fn to_gpu(&self) -> f32
+    {
+        match self
+        {
+            Self::Empty => 0.0,
+            Self::Tree => 1.0,
+            Self::Burning => 2.0,
+        }
+    }
+
+    fn from_gpu(value: f32) -> Self
+    {
+        match value
+        {
+            0.0 => Self::Empty,
+            1.0 => Self::Tree,
+            _ => Self::Burning,
+        }
+    }
+}
+
+const fn bounds(size: i32) -> GridBounds2D
+{
+    GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(size - 1, size - 1),
+    }
+}
+
+fn cells<C: GpuCell + Sample<f32>>(simulation: &Simulation) -> Vec<(IVec2, C)>
+{
+    let (positions, values) = simulation
+        .export_columns_with_ids::<C, GridPosition2D, f32>()
+        .expect("no cells");
+
+    let mut cells: Vec<_> = positions
+        .into_iter()
+        .zip(values)
+        .map(|(position, value)| (position.0, C::from_gpu(value)))
+        .collect();
+    cells.sort_by_key(|(position, _)| (position.x, position.y));
+    cells
+}
+
+/// Adds the automaton to a new simulation.
+///
+/// The tests that call this need a GPU adapter that supports compute shaders, so they are
+/// ignored by default, and are run with `cargo test --features gpu -- --ignored`.
+fn gpu_builder<C: GpuCell>(plugin: GpuAutomatonPlugin<C>) -> SimulationBuilder
+{
+    SimulationBuilder::new()
+        .add_gpu_automaton(plugin)
+        .expect("no GPU adapter available")
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn test_diffusion_matches_the_cpu()
+{
+    let bounds = bounds(8);
+    let rate = 0.2;
+    let builder = gpu_builder(GpuAutomatonPlugin::<Concentration>::new(
+        bounds,
+        GpuRule::Diffusion { rate },
+    ));
+    let mut simulation = builder
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |position| {
+                Some(Concentration(
+                    if position.0 == IVec2::new(3, 4)
+                    {
+                        100.0
+                    }
+                    else
+                    {
+                        0.0
+                    },
+                ))
+            });
+        })
+        .build();
+    simulation.run(10);
+
+    // the same steps on the cpu, with no flux across the edges
+    let mut expected = [[0.0_f32; 8]; 8];
+    expected[3][4] = 100.0;
+    for _ in 0..10
+    {
+        let current = expected;
+        for x in 0..8_i32
+        {
+            for y in 0..8_i32
+            {
+                let value = current[x as usize][y as usize];
+                let flux: f32 = [(0, -1), (-1, 0), (1, 0), (0, 1)]
+                    .into_iter()
+                    .map(|(dx, dy)| (x + dx, y + dy))
+                    .filter(|&(nx, ny)| (0..8).contains(&nx) && (0..8).contains(&ny))
+                    .map(|(nx, ny)| current[nx as usize][ny as usize] - value)
+                    .sum();
+                expected[x as usize][y as usize] = (rate as f32).mul_add(flux, value);
+            }
+        }
+    }
+
+    let cells = cells::<Concentration>(&simulation);
+    assert_eq!(cells.len(), 64);
+    for (position, concentration) in &cells
+    {
+        let expected = expected[position.x as usize][position.y as usize];
+        assert!(
+            (concentration.0 - expected).abs() < 1e-3,
+            "{position}: {concentration:?}"
+        );
+    }
+
+    let total: f32 = cells.iter().map(|(_, concentration)| concentration.0).sum();
+    assert!((total - 100.0).abs() < 1e-3, "{total}");
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn test_forest_fire_spreads_to_neighbors()
+{
+    let bounds = bounds(16);
+    let rule = GpuRule::ForestFire {
+        growth: 0.0,
+        lightning: 0.0,
+    };
+    let builder = gpu_builder(GpuAutomatonPlugin::<Forest>::new(bounds, rule));
+    let mut simulation = builder
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |position| {
+                Some(
+                    if position.0 == IVec2::new(8, 8)
+                    {
+                        Forest::Burning
+                    }
+                    else
+                    {
+                        Forest::Tree
+                    },
+                )
+            });
+        })
+        .build();
+    simulation.run(3);
+
+    // the fire is a ring of the cells three steps away, with the burned cells inside it
+    for (position, cell) in cells::<Forest>(&simulation)
+    {
+        let distance = (position - IVec2::new(8, 8)).abs().max_element();
+        let expected = match distance
+        {
+            0..3 => Forest::Empty,
+            3 => Forest::Burning,
+            _ => Forest::Tree,
+        };
+        assert_eq!(cell, expected, "{position}");
+    }
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn test_forest_fire_is_reproducible()
+{
+    let run = |seed| {
+        let bounds = bounds(32);
+        let rule = GpuRule::ForestFire {
+            growth: 0.05,
+            lightning: 0.001,
+        };
+        let mut simulation =
+            gpu_builder(GpuAutomatonPlugin::<Forest>::new(bounds, rule).with_wrapping())
+                .set_seed(seed)
+                .add_entity_spawner(move |spawner| {
+                    spawner.spawn_grid(bounds, |_| Some(Forest::Empty));
+                })
+                .build();
+        simulation.run(50);
+        cells::<Forest>(&simulation)
+    };
+
+    let first = run(1);
+    assert!(first.iter().any(|&(_, cell)| cell == Forest::Tree));
+    assert_eq!(first, run(1));
+    assert_ne!(first, run(2));
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn test_ising_aligns_with_field()
+{
+    let bounds = bounds(16);
+    let rule = GpuRule::Ising {
+        model: Ising::new(0.0).with_field(1.0),
+        temperature: 0.0,
+    };
+    let builder = gpu_builder(GpuAutomatonPlugin::<Spin>::new(bounds, rule));
+    let mut simulation = builder
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |_| Some(Spin::Down));
+        })
+        .build();
+    simulation.run(1);
+
+    assert!(simulation.iter::<Spin>().all(|spin| *spin == Spin::Up));
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn test_ising_orders_at_low_temperature()
+{
+    let bounds = bounds(32);
+    let rule = GpuRule::Ising {
+        model: Ising::new(1.0),
+        temperature: 1.0,
+    };
+    let builder = gpu_builder(GpuAutomatonPlugin::<Spin>::new(bounds, rule).with_wrapping());
+    let mut simulation = builder
+        .set_seed(3)
+        .add_seeded_entity_spawner(move |spawner, rng| {
+            spawner.spawn_grid(bounds, |_| {
+                Some(
+                    if rand::Rng::random_bool(rng, 0.7)
+                    {
+                        Spin::Up
+                    }
+                    else
+                    {
+                        Spin::Down
+                    },
+                )
+            });
+        })
+        .build();
+    simulation.run(200);
+
+    let magnetization = simulation
+        .iter::<Spin>()
+        .map(|spin| spin.value())
+        .sum::<f64>()
+        / 1024.0;
+    assert!(magnetization > 0.9, "{magnetization}");
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn test_wgsl_rule()
+{
+    // the game of life, with a blinker that oscillates with a period of two steps
+    let source = r"
+        fn rule(cell: f32) -> f32 {
+            var alive = 0.0;
+            for (var k = 0u; k < neighbor_count(); k++) {
+                alive += state(neighbor(k));
+            }
+            return select(0.0, 1.0, alive == 3.0 || (cell == 1.0 && alive == 2.0));
+        }
+    ";
+    let rule = GpuRule::Wgsl {
+        source: source.to_owned(),
+        parameters: [0.0; 4],
+    };
+
+    let bounds = bounds(5);
+    let builder = gpu_builder(GpuAutomatonPlugin::<Concentration>::new(bounds, rule));
+    let mut simulation = builder
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |position| {
+                Some(Concentration(
+                    if position.y() == 2 && (1..=3).contains(&position.x())
+                    {
+                        1.0
+                    }
+                    else
+                    {
+                        0.0
+                    },
+                ))
+            });
+        })
+        .build();
+
+    let alive = |simulation: &Simulation| {
+        cells::<Concentration>(simulation)
+            .into_iter()
+            .filter(|(_, cell)| cell.0 == 1.0)
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>()
+    };
+
+    simulation.run(1);
+    let vertical = (1..=3).map(|y| IVec2::new(2, y)).collect::<Vec<_>>();
+    assert_eq!(alive(&simulation), vertical);
+
+    simulation.run(1);
+    let horizontal = (1..=3).map(|x| IVec2::new(x, 2)).collect::<Vec<_>>();
+    assert_eq!(alive(&simulation), horizontal);
+}
+
+#[test]
+#[ignore = "requires a GPU adapter"]
+fn test_missing_cells_are_skipped()
+{
+    let bounds = bounds(4);
+    let builder = gpu_builder(GpuAutomatonPlugin::<Concentration>::new(
+        bounds,
+        GpuRule::Diffusion { rate: 0.25 },
+    ));
+    let mut simulation = builder
+        .add_entity_spawner(|spawner| {
+            // two cells with a gap between them, and one outside the bounds
+            spawner.spawn((Concentration(8.0), GridPosition2D::new(0, 0)));
+            spawner.spawn((Concentration(0.0), GridPosition2D::new(2, 0)));
+            spawner.spawn((Concentration(8.0), GridPosition2D::new(9, 9)));
+        })
+        .build();
+    simulation.run(5);
+
+    let cells = cells::<Concentration>(&simulation);
+    assert_eq!(cells[0], (IVec2::new(0, 0), Concentration(8.0)));
+    assert_eq!(cells[1], (IVec2::new(2, 0), Concentration(0.0)));
+    assert_eq!(cells[2], (IVec2::new(9, 9), Concentration(8.0)));
+}
+
+#[test]
+#[should_panic(expected = "unstable diffusion rate")]
+fn test_unstable_diffusion()
+{
+    let _ = GpuAutomatonPlugin::<Concentration>::new(bounds(4), GpuRule::Diffusion { rate: 0.3 });
+}
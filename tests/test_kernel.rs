@@ -0,0 +1,129 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::{
+    kernel::{InteractionKernel, Metric},
+    prelude::*,
+};
+
+#[test]
+fn test_metrics()
+{
+    let displacement = IVec2::new(3, -4);
+    assert_eq!(Metric::Euclidean.length(displacement), 5.0);
+    assert_eq!(Metric::Manhattan.length(displacement), 7.0);
+    assert_eq!(Metric::Chebyshev.length(displacement), 4.0);
+    assert_eq!(Metric::default(), Metric::Euclidean);
+}
+
+#[test]
+fn test_kernel_shapes()
+{
+    let uniform = InteractionKernel::uniform(0.5, 2.0);
+    assert_eq!(uniform.weight_at(0.0), 0.5);
+    assert_eq!(uniform.weight_at(2.0), 0.5);
+    assert_eq!(uniform.weight_at(2.1), 0.0);
+    assert_eq!(uniform.weight(IVec2::new(1, 1)), 0.5);
+    assert_eq!(uniform.weight(IVec2::new(2, 1)), 0.0);
+
+    let exponential = InteractionKernel::exponential(2.0, 3.0, 10.0);
+    assert_eq!(exponential.weight_at(0.0), 2.0);
+    assert_eq!(exponential.weight_at(3.0), 2.0 * (-1.0_f64).exp());
+    assert_eq!(exponential.weight_at(11.0), 0.0);
+
+    let gravity = InteractionKernel::gravity(8.0, 2.0, 5.0);
+    assert_eq!(gravity.weight_at(0.0), 8.0);
+    assert_eq!(gravity.weight_at(0.5), 8.0);
+    assert_eq!(gravity.weight_at(2.0), 2.0);
+    assert_eq!(gravity.weight(IVec2::new(0, 4)), 0.5);
+    assert_eq!(gravity.radius(), 5.0);
+
+    let table = InteractionKernel::table([0.3, 0.2, 0.1]);
+    assert_eq!(table.radius(), 2.0);
+    assert_eq!(table.weight_at(0.0), 0.3);
+    assert_eq!(table.weight_at(0.5), 0.2);
+    assert_eq!(table.weight(IVec2::new(1, 1)), 0.1);
+    assert_eq!(table.weight(IVec2::new(2, 1)), 0.0);
+    assert_eq!(
+        table
+            .with_metric(Metric::Chebyshev)
+            .weight(IVec2::new(2, 1)),
+        0.1
+    );
+}
+
+#[test]
+fn test_offsets()
+{
+    let count = |metric| {
+        InteractionKernel::uniform(1.0, 2.0)
+            .with_metric(metric)
+            .offsets()
+            .count()
+    };
+    assert_eq!(count(Metric::Manhattan), 13);
+    assert_eq!(count(Metric::Euclidean), 13);
+    assert_eq!(count(Metric::Chebyshev), 25);
+
+    // cells with a weight of zero are skipped
+    let ring = InteractionKernel::table([0.0, 1.0]).with_metric(Metric::Chebyshev);
+    let offsets = ring.offsets().collect::<Vec<_>>();
+    assert_eq!(offsets.len(), 8);
+    assert!(!offsets.iter().any(|(offset, _)| *offset == IVec2::ZERO));
+    assert!(offsets.iter().all(|(_, weight)| *weight == 1.0));
+}
+
+#[derive(Component)]
+struct Person;
+
+#[derive(Resource, Default)]
+struct Pressure(Vec<(Entity, f64)>);
+
+#[test]
+fn test_contacts_on_grid()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_spatial_grid_2d::<Person>(None)
+        .add_resource(Pressure::default())
+        .add_entity_spawner(|spawner| {
+            for (x, y) in [(5, 5), (5, 5), (6, 5), (7, 7), (9, 5)]
+            {
+                spawner.spawn((Person, GridPosition2D::new(x, y)));
+            }
+        })
+        .add_systems(
+            |grid: Res<SpatialGrid2D<Person>>, mut pressure: ResMut<Pressure>| {
+                let kernel = InteractionKernel::exponential(1.0, 1.0, 3.0);
+                kernel.contacts(&grid, GridPosition2D::new(5, 5), &mut pressure.0);
+            },
+        )
+        .build();
+
+    simulation.run(1);
+
+    let contacts = simulation
+        .resource_scope(|pressure: &mut Pressure| std::mem::take(&mut pressure.0))
+        .expect("missing pressure");
+    let mut weights = contacts
+        .iter()
+        .map(|(_, weight)| *weight)
+        .collect::<Vec<_>>();
+    weights.sort_by(f64::total_cmp);
+    assert_eq!(
+        weights,
+        vec![(-(8.0_f64).sqrt()).exp(), (-1.0_f64).exp(), 1.0, 1.0]
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid kernel radius: -1")]
+fn test_negative_radius()
+{
+    let _ = InteractionKernel::uniform(1.0, -1.0);
+}
+
+#[test]
+#[should_panic(expected = "invalid kernel weight: NaN")]
+fn test_invalid_table()
+{
+    let _ = InteractionKernel::table([0.1, f64::NAN]);
+}
@@ -0,0 +1,149 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::prelude::*;
+use rand::Rng;
+
+#[derive(Component)]
+struct Height(f64);
+
+impl Sample<f64> for Height
+{
+    fn sample(component: &Self) -> f64
+    {
+        component.0
+    }
+}
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64)
+{
+    assert!(
+        (actual - expected).abs() < tolerance,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn test_bandwidth_rules()
+{
+    let samples = [1.0, 2.0, 3.0, 4.0, 5.0];
+    let shrink = 5.0_f64.powf(-0.2);
+
+    // the interquartile range is 2, which is tighter than the standard deviation
+    let silverman = KernelDensity::new(samples);
+    assert_close(silverman.bandwidth(), 0.9 * (2.0 / 1.34) * shrink, 1e-12);
+
+    let scott = KernelDensity::new(samples).with_bandwidth(Bandwidth::Scott);
+    assert_close(scott.bandwidth(), 1.06 * 2.5_f64.sqrt() * shrink, 1e-12);
+
+    let fixed = KernelDensity::new(samples).with_bandwidth(Bandwidth::Fixed(0.5));
+    assert_eq!(fixed.bandwidth(), 0.5);
+
+    // identical samples fall back to a unit bandwidth
+    let constant = KernelDensity::new([2.0; 4]);
+    assert_eq!(constant.bandwidth(), 1.0);
+    assert_eq!(constant.samples(), &[2.0; 4]);
+}
+
+#[test]
+fn test_density_integrates_to_one()
+{
+    let mut rng = rand::rng();
+    let density = KernelDensity::new((0..200).map(|_| rng.random_range(0.0..10.0)));
+    assert!(density.samples().is_sorted());
+
+    let points = density.evaluate(2000);
+    assert_eq!(points.len(), 2000);
+    let step = points[1].0 - points[0].0;
+    let integral = points.iter().map(|(_, value)| value * step).sum::<f64>();
+
+    // three bandwidths on either side leave out a little of the tails
+    assert_close(integral, 1.0, 0.01);
+    assert!(points.iter().all(|&(_, value)| value >= 0.0));
+}
+
+#[test]
+fn test_cdf_and_quantile()
+{
+    let density = KernelDensity::new([-1.0, 0.0, 1.0]).with_bandwidth(Bandwidth::Fixed(0.5));
+
+    // symmetric around zero, up to the accuracy of the normal distribution function
+    assert_close(density.cdf(0.0), 0.5, 1e-6);
+    assert_close(density.quantile(0.5), 0.0, 1e-6);
+    assert_close(density.density(-0.7), density.density(0.7), 1e-12);
+    assert!(density.cdf(-10.0) < 1e-6);
+    assert!(density.cdf(10.0) > 1.0 - 1e-6);
+
+    for p in [0.01, 0.1, 0.25, 0.75, 0.9, 0.99]
+    {
+        assert_close(density.cdf(density.quantile(p)), p, 1e-9);
+    }
+}
+
+#[test]
+#[should_panic(expected = "invalid bandwidth")]
+fn test_invalid_bandwidth()
+{
+    let _ = KernelDensity::new([1.0, 2.0]).with_bandwidth(Bandwidth::Fixed(0.0));
+}
+
+#[test]
+#[should_panic(expected = "a kernel density needs samples")]
+fn test_no_samples()
+{
+    let _ = KernelDensity::new([]);
+}
+
+#[test]
+fn test_ensemble_density()
+{
+    let ensemble = EnsembleResult::new([1.0, 2.0, 3.0, 4.0, 5.0]);
+    let density = ensemble.kernel_density().expect("no density");
+    assert_eq!(density, KernelDensity::new([1.0, 2.0, 3.0, 4.0, 5.0]));
+
+    assert_eq!(EnsembleResult::default().kernel_density(), None);
+}
+
+#[test]
+fn test_sample_density() -> Result<(), SamplingError>
+{
+    let simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for height in [150.0, 160.0, 170.0, 180.0, 190.0]
+            {
+                spawner.spawn(Height(height));
+            }
+        })
+        .build();
+
+    let density = simulation.sample_density::<Height>()?;
+    assert_eq!(density.samples(), &[150.0, 160.0, 170.0, 180.0, 190.0]);
+    assert_close(density.quantile(0.5), 170.0, 1e-6);
+
+    let mut empty = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Height(0.0));
+        })
+        .build();
+    empty.despawn_where::<With<Height>>();
+    assert!(matches!(
+        empty.sample_density::<Height>(),
+        Err(SamplingError::AggregateNoEntities { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[should_panic = "but found none"]
+fn test_strict_empty_density()
+{
+    let mut simulation = SimulationBuilder::new()
+        .set_strictness(StrictnessPolicy::Strict)
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Height(0.0));
+        })
+        .build();
+    simulation.despawn_where::<With<Height>>();
+
+    let _ = simulation.sample_density::<Height>();
+}
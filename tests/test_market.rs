@@ -0,0 +1,215 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::{
+    market::{Order, OrderBook, Side, Trade},
+    prelude::*,
+};
+use rand::Rng;
+
+#[derive(Component)]
+struct Trader;
+
+fn market() -> Simulation
+{
+    SimulationBuilder::new()
+        .add_market()
+        .record_events::<Trade>()
+        .build()
+}
+
+fn book(simulation: &Simulation) -> &OrderBook
+{
+    simulation
+        .get_resource::<OrderBook>()
+        .expect("no order book")
+}
+
+fn trades(simulation: &Simulation) -> Vec<Trade>
+{
+    simulation
+        .event_log::<Trade>()
+        .expect("trades not recorded")
+        .iter()
+        .map(|(_, trade)| *trade)
+        .collect()
+}
+
+#[test]
+fn test_market_partial_fill()
+{
+    let mut simulation = market();
+    let seller = simulation.spawn(()).entity();
+    let buyer = simulation.spawn(()).entity();
+
+    simulation.spawn(Order::ask(101.0, 10).by(seller));
+    simulation.run(1);
+    assert_eq!(book(&simulation).best_ask(), Some(101.0));
+    assert_eq!(book(&simulation).best_bid(), None);
+
+    simulation.spawn(Order::bid(102.0, 4).by(buyer));
+    simulation.run(1);
+
+    let trades = trades(&simulation);
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].price, 101.0);
+    assert_eq!(trades[0].quantity, 4);
+    assert_eq!(trades[0].buyer, Some(buyer));
+    assert_eq!(trades[0].seller, Some(seller));
+    assert_eq!(trades[0].step, 2);
+
+    // the bid was filled, while the rest of the ask is still in the book
+    let orders: Vec<&Order> = simulation.iter::<Order>().collect();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].side(), Side::Ask);
+    assert_eq!(orders[0].quantity(), 10);
+    assert_eq!(orders[0].remaining(), 6);
+
+    let book = book(&simulation);
+    assert_eq!(book.depth(Side::Ask), 1);
+    assert_eq!(book.depth(Side::Bid), 0);
+    assert_eq!(book.last_price(), Some(101.0));
+}
+
+#[test]
+fn test_market_price_time_priority()
+{
+    let mut simulation = market();
+    let first = simulation.spawn(Trader).entity();
+    let second = simulation.spawn(Trader).entity();
+    simulation.spawn(Order::ask(100.0, 1).by(first));
+    simulation.spawn(Order::ask(100.0, 1).by(second));
+    simulation.spawn(Order::ask(99.0, 1));
+    simulation.spawn(Order::bid(98.0, 1));
+    simulation.run(1);
+
+    assert_eq!(book(&simulation).best_bid(), Some(98.0));
+    assert_eq!(book(&simulation).best_ask(), Some(99.0));
+    assert_eq!(book(&simulation).spread(), Some(1.0));
+    assert_eq!(book(&simulation).mid_price(), Some(98.5));
+
+    // the cheapest ask is filled first, and then the earliest of those at the same price
+    simulation.spawn(Order::bid(100.0, 3));
+    simulation.run(1);
+
+    let prices: Vec<f64> = trades(&simulation)
+        .iter()
+        .map(|trade| trade.price)
+        .collect();
+    assert_eq!(prices, [99.0, 100.0, 100.0]);
+    let sellers: Vec<Option<Entity>> = trades(&simulation)
+        .iter()
+        .map(|trade| trade.seller)
+        .collect();
+    assert_eq!(sellers, [None, Some(first), Some(second)]);
+    assert_eq!(book(&simulation).depth(Side::Ask), 0);
+    assert_eq!(book(&simulation).best_bid(), Some(98.0));
+
+    let bar = book(&simulation).bars()[0];
+    assert_eq!(bar.step, 2);
+    assert_eq!(
+        (bar.open, bar.high, bar.low, bar.close),
+        (99.0, 100.0, 99.0, 100.0)
+    );
+    assert_eq!(bar.volume, 3);
+}
+
+#[test]
+fn test_market_time_priority_across_archetypes()
+{
+    #[derive(Component)]
+    struct Urgent;
+
+    let mut simulation = market();
+    let first = simulation.spawn(Trader).entity();
+    let second = simulation.spawn(Trader).entity();
+    simulation.spawn(Order::bid(50.0, 1));
+    simulation.run(1);
+
+    // the earlier order is stored apart from the later one, since it has another component
+    simulation.spawn((Order::ask(100.0, 1).by(first), Urgent));
+    simulation.spawn(Order::ask(100.0, 1).by(second));
+    simulation.run(1);
+
+    simulation.spawn(Order::bid(100.0, 1));
+    simulation.run(1);
+
+    let sellers: Vec<Option<Entity>> = trades(&simulation)
+        .iter()
+        .map(|trade| trade.seller)
+        .collect();
+    assert_eq!(sellers, [Some(first)]);
+}
+
+#[test]
+fn test_market_cancelled_orders()
+{
+    let mut simulation = market();
+    simulation.spawn(Order::bid(50.0, 5));
+    simulation.run(1);
+    assert_eq!(book(&simulation).depth(Side::Bid), 1);
+
+    simulation.despawn_where::<With<Order>>();
+    simulation.spawn(Order::ask(45.0, 5));
+    simulation.run(1);
+
+    assert!(trades(&simulation).is_empty());
+    assert_eq!(book(&simulation).best_ask(), Some(45.0));
+    assert_eq!(book(&simulation).depth(Side::Bid), 0);
+}
+
+#[test]
+fn test_market_random_traders()
+{
+    #[derive(Component)]
+    struct Trader;
+
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(8)
+        .add_market()
+        .add_entity_spawner(|spawner| {
+            for _ in 0..50
+            {
+                spawner.spawn(Trader);
+            }
+        })
+        .add_systems(
+            |mut commands: Commands,
+             traders: Query<Entity, With<Trader>>,
+             book: Res<OrderBook>,
+             mut rng: ResMut<SimRng>| {
+                let reference = book.last_price().unwrap_or(100.0);
+                for trader in &traders
+                {
+                    let price = reference * rng.random_range(0.95..1.05);
+                    let quantity = rng.random_range(1..=5);
+                    let order = if rng.random_bool(0.5)
+                    {
+                        Order::bid(price, quantity)
+                    }
+                    else
+                    {
+                        Order::ask(price, quantity)
+                    };
+                    commands.spawn(order.by(trader));
+                }
+            },
+        )
+        .build();
+    simulation.run(200);
+
+    let book = book(&simulation);
+    assert!(book.bars().len() > 150);
+    assert!(book.bars().windows(2).all(|w| w[0].step < w[1].step));
+    for bar in book.bars()
+    {
+        assert!(bar.low <= bar.open.min(bar.close));
+        assert!(bar.high >= bar.open.max(bar.close));
+        assert!(bar.volume > 0);
+    }
+
+    // the book is never left crossed
+    if let (Some(bid), Some(ask)) = (book.best_bid(), book.best_ask())
+    {
+        assert!(bid < ask);
+    }
+}
@@ -0,0 +1,129 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::prelude::*;
+
+#[derive(Component)]
+struct Person;
+
+#[derive(Component)]
+struct Quarantined;
+
+#[test]
+fn test_network_edges()
+{
+    let mut simulation = SimulationBuilder::new().add_network::<Person>().build();
+    let [a, b, c] = [(); 3].map(|()| simulation.spawn(Person).entity());
+
+    simulation
+        .resource_scope(|network: &mut Network<Person>| {
+            network.add_edge(a, b);
+            network.add_weighted_edge(a, c, 0.5);
+            network.add_edge(c, c);
+        })
+        .expect("missing network");
+
+    let network = simulation
+        .get_resource::<Network<Person>>()
+        .expect("missing network");
+    assert_eq!(network.num_edges(), 2);
+    assert_eq!(network.num_nodes(), 3);
+    assert_eq!(network.neighbors_of(a).collect::<Vec<_>>(), [b, c]);
+    assert_eq!(network.neighbors_of(c).collect::<Vec<_>>(), [a]);
+    assert_eq!(network.weight(c, a), Some(0.5));
+    assert!(network.has_edge(b, a));
+    assert!(!network.has_edge(b, c));
+    assert_eq!(network.degree(a), 2);
+    assert_eq!(network.weighted_degree(a), 1.5);
+    assert_eq!(network.degree_distribution(), [0, 2, 1]);
+
+    simulation
+        .resource_scope(|network: &mut Network<Person>| {
+            // adding an existing edge updates its weight in both directions
+            network.add_weighted_edge(b, a, 2.0);
+            assert_eq!(network.weight(a, b), Some(2.0));
+            assert_eq!(network.num_edges(), 2);
+
+            assert_eq!(network.remove_edge(c, a), Some(0.5));
+            assert_eq!(network.remove_edge(c, a), None);
+            assert_eq!(network.num_edges(), 1);
+            assert_eq!(network.num_nodes(), 2);
+            assert_eq!(network.degree(c), 0);
+        })
+        .expect("missing network");
+}
+
+#[test]
+fn test_network_spawner()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_network::<Person>()
+        .add_entity_spawner(|spawner| {
+            let hub = spawner.spawn(Person);
+            for _ in 0..5
+            {
+                let leaf = spawner.spawn(Person);
+                spawner.add_edge::<Person>(hub, leaf);
+            }
+        })
+        .build();
+    simulation.run(1);
+
+    let network = simulation
+        .get_resource::<Network<Person>>()
+        .expect("missing network");
+    assert_eq!(network.num_edges(), 5);
+    assert_eq!(network.degree_distribution(), [0, 5, 0, 0, 0, 1]);
+
+    let report = simulation.memory_report();
+    assert_eq!(report.network_nodes, 6);
+    assert!(report.network_bytes > 0);
+}
+
+#[test]
+fn test_network_cleanup()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_network::<Person>()
+        .add_entity_spawner(|spawner| {
+            let people = (0..4)
+                .map(|i| {
+                    if i == 0
+                    {
+                        spawner.spawn((Person, Quarantined))
+                    }
+                    else
+                    {
+                        spawner.spawn(Person)
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            // a complete graph of four people
+            for (i, &a) in people.iter().enumerate()
+            {
+                for &b in &people[i + 1..]
+                {
+                    spawner.add_edge::<Person>(a, b);
+                }
+            }
+        })
+        .build();
+
+    assert_eq!(
+        simulation
+            .get_resource::<Network<Person>>()
+            .expect("missing network")
+            .num_edges(),
+        6
+    );
+
+    assert_eq!(simulation.despawn_where::<With<Quarantined>>(), 1);
+    simulation.run(1);
+
+    let network = simulation
+        .get_resource::<Network<Person>>()
+        .expect("missing network");
+    assert_eq!(network.num_edges(), 3);
+    assert_eq!(network.num_nodes(), 3);
+    assert_eq!(network.degree_distribution(), [0, 0, 3]);
+}
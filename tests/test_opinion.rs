@@ -0,0 +1,150 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+#![allow(clippy::cast_precision_loss)]
+use incerto::{
+    opinion::{Interaction, Opinion, OpinionModule, OpinionOutputs},
+    prelude::*,
+};
+
+/// Builds a simulation of agents with the given initial opinions, connected by the edges
+/// produced by `topology`.
+fn network(
+    module: OpinionModule,
+    opinions: Vec<f64>,
+    topology: impl Fn(usize, &mut SimRng) -> Vec<(usize, usize)> + 'static,
+) -> Simulation
+{
+    SimulationBuilder::new()
+        .set_seed(6)
+        .add_module(module.with_interaction(Interaction::Network))
+        .add_seeded_entity_spawner(move |spawner, rng| {
+            let agents: Vec<_> = opinions
+                .iter()
+                .map(|&opinion| spawner.spawn(Opinion(opinion)))
+                .collect();
+            spawner.add_edges::<Opinion>(&agents, topology(agents.len(), rng));
+        })
+        .build()
+}
+
+fn outputs(simulation: &Simulation) -> &OpinionOutputs
+{
+    simulation
+        .get_resource::<OpinionOutputs>()
+        .expect("no opinion outputs")
+}
+
+#[test]
+fn test_opinion_voter_consensus()
+{
+    let opinions = (0..30).map(|i| f64::from(i % 2)).collect();
+    let mut simulation = network(OpinionModule::voter(), opinions, |n, rng| {
+        topology::erdos_renyi(n, 1.0, rng)
+    });
+    simulation.run(500);
+
+    let outputs = outputs(&simulation);
+    let consensus = outputs.consensus_time().expect("no consensus");
+    assert!(consensus < 500);
+
+    let latest = outputs.latest().expect("no steps");
+    assert_eq!(latest.spread, 0.0);
+    assert_eq!(latest.polarization, 0.0);
+    assert!(latest.mean == 0.0 || latest.mean == 1.0);
+    assert!(
+        outputs.history()[..consensus - 1]
+            .iter()
+            .all(|m| m.spread > 0.0)
+    );
+}
+
+#[test]
+fn test_opinion_degroot_preserves_mean_on_ring()
+{
+    let opinions: Vec<f64> = (0..20).map(|i| f64::from(i) / 19.0).collect();
+    let initial_mean = opinions.iter().sum::<f64>() / 20.0;
+
+    let module = OpinionModule::degroot().with_consensus_tolerance(1e-3);
+    let mut simulation = network(module, opinions, |n, rng| {
+        topology::watts_strogatz(n, 2, 0.0, rng)
+    });
+    simulation.run(1000);
+
+    let outputs = outputs(&simulation);
+    assert!(outputs.consensus_time().is_some());
+    let latest = outputs.latest().expect("no steps");
+    assert!((latest.mean - initial_mean).abs() < 1e-9);
+    assert!(
+        outputs
+            .history()
+            .windows(2)
+            .all(|w| w[1].variance <= w[0].variance + 1e-12)
+    );
+}
+
+#[test]
+fn test_opinion_bounded_confidence_clusters()
+{
+    let opinions: Vec<f64> = (0..100).map(|i| f64::from(i) / 99.0).collect();
+
+    // with a narrow confidence bound the population splits into separate clusters
+    let module = OpinionModule::bounded_confidence(0.1, 0.5).with_consensus_tolerance(1e-3);
+    let mut simulation = network(module, opinions.clone(), |n, rng| {
+        topology::erdos_renyi(n, 1.0, rng)
+    });
+    simulation.run(2000);
+    let outputs_narrow = outputs(&simulation);
+    assert_eq!(outputs_narrow.consensus_time(), None);
+    assert!(outputs_narrow.latest().expect("no steps").spread > 0.5);
+
+    // with a wide one it reaches consensus
+    let module = OpinionModule::bounded_confidence(0.6, 0.5).with_consensus_tolerance(1e-3);
+    let mut simulation = network(module, opinions, |n, rng| {
+        topology::erdos_renyi(n, 1.0, rng)
+    });
+    simulation.run(2000);
+    assert!(outputs(&simulation).consensus_time().is_some());
+}
+
+#[test]
+fn test_opinion_metrics_without_neighbors()
+{
+    let opinions = (0..10).map(|i| f64::from(i % 2)).collect();
+    let mut simulation = network(OpinionModule::voter(), opinions, |_, _| Vec::new());
+    simulation.run(3);
+
+    let outputs = outputs(&simulation);
+    assert_eq!(outputs.history().len(), 3);
+    assert_eq!(outputs.consensus_time(), None);
+
+    let latest = outputs.latest().expect("no steps");
+    assert_eq!(latest.step, 3);
+    assert_eq!(latest.mean, 0.5);
+    assert_eq!(latest.variance, 0.25);
+    assert_eq!(latest.spread, 1.0);
+    assert_eq!(latest.polarization, 0.5);
+}
+
+#[test]
+fn test_opinion_voter_on_grid()
+{
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(2)
+        .add_module(OpinionModule::voter().with_interaction(Interaction::Spatial(None)))
+        .add_entity_spawner(|spawner| {
+            for x in 0..6
+            {
+                for y in 0..6
+                {
+                    let opinion = f64::from(i32::from(x < 3));
+                    spawner.spawn((Opinion(opinion), GridPosition(IVec2::new(x, y))));
+                }
+            }
+        })
+        .build();
+    simulation.run(5000);
+
+    let outputs = outputs(&simulation);
+    assert!(outputs.consensus_time().is_some());
+    assert_eq!(outputs.history()[0].step, 1);
+}
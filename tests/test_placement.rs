@@ -0,0 +1,91 @@
+#![allow(clippy::expect_used)]
+use incerto::prelude::*;
+
+const BOUNDS: GridBounds2D = GridBounds2D {
+    min: IVec2::new(0, 0),
+    max: IVec2::new(49, 49),
+};
+
+#[test]
+fn test_placement_within_bounds()
+{
+    let mut rng = SimRng::from_seed(1);
+
+    let uniform = placement::uniform(&BOUNDS, 500, &mut rng);
+    assert_eq!(uniform.len(), 500);
+
+    let clustered = placement::clustered(&BOUNDS, 4, 500, 3.0, &mut rng);
+    assert_eq!(clustered.len(), 500);
+
+    for position in uniform.iter().chain(&clustered)
+    {
+        assert!(BOUNDS.contains(&position.0));
+    }
+}
+
+#[test]
+fn test_placement_poisson_disk()
+{
+    const MIN_DISTANCE: f32 = 5.0;
+
+    let mut rng = SimRng::from_seed(2);
+    let positions = placement::poisson_disk(&BOUNDS, MIN_DISTANCE, &mut rng);
+
+    // the grid should be reasonably well covered
+    assert!(positions.len() > 50);
+
+    for (i, a) in positions.iter().enumerate()
+    {
+        assert!(BOUNDS.contains(&a.0));
+        for b in &positions[i + 1..]
+        {
+            assert!(a.0.as_vec2().distance(b.0.as_vec2()) >= MIN_DISTANCE);
+        }
+    }
+}
+
+#[test]
+fn test_placement_ring()
+{
+    let center = GridPosition2D::new(0, 25);
+    let positions = placement::ring(&BOUNDS, center, 10.0, 4);
+
+    // the point to the left of the center is out of bounds
+    assert_eq!(
+        positions,
+        vec![
+            GridPosition2D::new(10, 25),
+            GridPosition2D::new(0, 35),
+            GridPosition2D::new(0, 15),
+        ]
+    );
+}
+
+#[test]
+fn test_placement_weighted()
+{
+    let mut layer = GridLayer::new(BOUNDS, f64::NAN);
+    layer
+        .set(&GridPosition2D::new(2, 3), 1.0)
+        .expect("position in bounds");
+    layer
+        .set(&GridPosition2D::new(7, 7), 3.0)
+        .expect("position in bounds");
+    assert_eq!(
+        layer.set(&GridPosition2D::new(50, 0), 1.0),
+        Err(SpatialGridError::OutOfBounds)
+    );
+
+    let mut rng = SimRng::from_seed(3);
+    let positions = placement::weighted(&layer, 4000, &mut rng);
+    assert_eq!(positions.len(), 4000);
+
+    let heavy = positions
+        .iter()
+        .filter(|&&position| position == GridPosition2D::new(7, 7))
+        .count();
+    assert!(positions.iter().all(|&position| {
+        position == GridPosition2D::new(2, 3) || position == GridPosition2D::new(7, 7)
+    }));
+    assert!((2800..3200).contains(&heavy));
+}
@@ -0,0 +1,133 @@
+#![allow(clippy::expect_used)]
+use std::thread;
+
+use incerto::{policy::Policy, prelude::*};
+
+#[derive(Component)]
+struct Position(i64);
+
+/// Observation of an agent, answered with the step it should take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Observation
+{
+    agent: usize,
+    position: i64,
+}
+
+#[derive(Component)]
+struct Agent(usize);
+
+fn agents(count: usize, policy: Policy<Observation, i64>) -> Simulation
+{
+    SimulationBuilder::new()
+        .add_policy(policy)
+        .add_entity_spawner(move |spawner| {
+            for agent in 0..count
+            {
+                spawner.spawn((Agent(agent), Position(10)));
+            }
+        })
+        .add_systems(
+            |mut agents: Query<(&Agent, &mut Position)>,
+             mut policy: ResMut<Policy<Observation, i64>>| {
+                for (agent, mut position) in &mut agents
+                {
+                    let observation = Observation {
+                        agent: agent.0,
+                        position: position.0,
+                    };
+                    if let Some(step) = policy.decide(observation)
+                    {
+                        position.0 += step;
+                    }
+                }
+            },
+        )
+        .build()
+}
+
+fn positions(simulation: &Simulation) -> Vec<i64>
+{
+    simulation
+        .iter::<Position>()
+        .map(|position| position.0)
+        .collect()
+}
+
+#[test]
+fn test_function_policy()
+{
+    let mut simulation = agents(
+        3,
+        Policy::from_fn(|observation: Observation| {
+            -observation.position.signum() * i64::try_from(observation.agent + 1).unwrap_or(1)
+        }),
+    );
+
+    simulation.run(4);
+
+    let mut positions = positions(&simulation);
+    positions.sort_unstable();
+    assert_eq!(positions, vec![-2, 2, 6]);
+
+    let policy = simulation
+        .get_resource::<Policy<Observation, i64>>()
+        .expect("no policy");
+    assert_eq!(policy.decisions(), 12);
+}
+
+#[test]
+fn test_channel_policy()
+{
+    let (policy, endpoint) = Policy::channel();
+    let trainer = thread::spawn(move || {
+        let mut observations = Vec::new();
+        endpoint.serve(|observation: Observation| {
+            observations.push(observation);
+            -1
+        });
+        observations
+    });
+
+    let mut simulation = agents(1, policy);
+    simulation.run(5);
+    assert_eq!(positions(&simulation), vec![5]);
+    drop(simulation);
+
+    // the endpoint saw every observation in order, and stopped once the simulation was dropped
+    let observations = trainer.join().expect("trainer panicked");
+    let seen: Vec<i64> = observations
+        .iter()
+        .map(|observation| observation.position)
+        .collect();
+    assert_eq!(seen, vec![10, 9, 8, 7, 6]);
+}
+
+#[test]
+fn test_manual_endpoint()
+{
+    let (policy, endpoint) = Policy::channel();
+    let operator = thread::spawn(move || {
+        // a human in the loop answering the first two observations by hand
+        for step in [3, -1]
+        {
+            let observation: Observation = endpoint.next_observation().expect("no observation");
+            assert_eq!(observation.agent, 0);
+            assert!(endpoint.respond(step));
+        }
+    });
+
+    let mut simulation = agents(1, policy);
+    simulation.run(2);
+    operator.join().expect("operator panicked");
+    assert_eq!(positions(&simulation), vec![12]);
+
+    // once the operator is gone, no more decisions are made
+    simulation.run(3);
+    assert_eq!(positions(&simulation), vec![12]);
+
+    let policy = simulation
+        .get_resource::<Policy<Observation, i64>>()
+        .expect("no policy");
+    assert_eq!(policy.decisions(), 2);
+}
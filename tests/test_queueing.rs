@@ -0,0 +1,98 @@
+#![allow(clippy::expect_used)]
+use incerto::{
+    prelude::*,
+    queueing::{Arrivals, Queue, QueueLength, Server, ServiceTime, Utilization, WaitingTime},
+};
+
+/// Builds a simulation of a single queue with the given arrivals and servers.
+fn single_queue(queue: Queue, rate: f64, servers: usize, service: ServiceTime) -> Simulation
+{
+    SimulationBuilder::new()
+        .set_seed(7)
+        .add_queueing()
+        .add_entity_spawner(move |spawner| {
+            let queue = spawner.spawn((queue.clone(), Arrivals::poisson(rate)));
+            for _ in 0..servers
+            {
+                spawner.spawn(Server::new(queue.entity(), service));
+            }
+        })
+        .build()
+}
+
+#[test]
+fn test_queueing_single_server()
+{
+    // customers arrive every 4 steps on average, and take 2 steps to serve
+    let mut simulation = single_queue(Queue::new(), 0.25, 1, ServiceTime::Constant(2.0));
+    simulation.run(20_000);
+
+    let queue = simulation.iter::<Queue>().next().expect("missing queue");
+    let server = simulation.iter::<Server>().next().expect("missing server");
+
+    assert!((4_700..5_300).contains(&queue.arrived()));
+    assert_eq!(queue.rejected(), 0);
+    assert_eq!(queue.arrived(), queue.served() + queue.len());
+    assert_eq!(server.served(), queue.served());
+    assert!(
+        (0.45..0.55).contains(&server.utilization()),
+        "{}",
+        server.utilization()
+    );
+
+    let WaitingTime(wait) = simulation
+        .sample_aggregate::<Queue, WaitingTime>()
+        .expect("missing queue");
+    assert_eq!(Some(wait), queue.mean_wait());
+    assert!(wait > 0.0);
+}
+
+#[test]
+fn test_queueing_bounded()
+{
+    // far more customers arrive than the servers can handle
+    let mut simulation = single_queue(
+        Queue::bounded(5),
+        3.0,
+        2,
+        ServiceTime::Exponential { mean: 2.0 },
+    );
+    simulation.run(1_000);
+
+    let queue = simulation.iter::<Queue>().next().expect("missing queue");
+    assert!(queue.rejected() > 0);
+    // idle servers take customers from the queue after the arrivals of every step
+    assert!((3..=5).contains(&queue.len()));
+    assert_eq!(
+        queue.arrived(),
+        queue.served() + queue.rejected() + queue.len()
+    );
+
+    assert_eq!(
+        simulation.sample_aggregate::<Queue, QueueLength>(),
+        Ok(QueueLength(queue.len()))
+    );
+    assert_eq!(
+        simulation.sample_aggregate::<Server, Utilization>(),
+        Ok(Utilization(1.0))
+    );
+}
+
+#[test]
+fn test_queueing_reproducible()
+{
+    let run = || {
+        let mut simulation = single_queue(
+            Queue::new(),
+            1.5,
+            3,
+            ServiceTime::Uniform { min: 0.5, max: 2.5 },
+        );
+        simulation.run(500);
+        simulation
+            .sample_aggregate::<Queue, WaitingTime>()
+            .expect("missing queue")
+    };
+
+    assert_eq!(run(), run());
+}
@@ -0,0 +1,145 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::cast_precision_loss)]
+use bevy::{
+    math::DVec2,
+    prelude::{IVec2, IVec3},
+};
+use incerto::{
+    prelude::*,
+    random_walk::{
+        Boundary, FirstPassageTimes, MeanSquaredDisplacement, RandomWalker, Step, WalkSpace,
+    },
+};
+
+/// Builds a simulation of `count` copies of the given walker.
+fn walkers<P: WalkSpace>(count: usize, walker: RandomWalker<P>) -> Simulation
+{
+    SimulationBuilder::new()
+        .set_seed(11)
+        .add_random_walks::<P>()
+        .add_entity_spawner(move |spawner| {
+            for _ in 0..count
+            {
+                spawner.spawn(walker.clone());
+            }
+        })
+        .build()
+}
+
+#[test]
+fn test_random_walk_gamblers_ruin()
+{
+    let walker =
+        RandomWalker::new(3, Step::Lattice).with_boundary(Boundary::Absorb { min: 0, max: 10 });
+    let mut simulation = walkers(4000, walker);
+    simulation.run(1000);
+
+    let FirstPassageTimes(durations) = simulation
+        .sample_aggregate::<RandomWalker<i32>, _>()
+        .expect("no walkers");
+    assert_eq!(durations.len(), 4000);
+    assert!(durations.is_sorted());
+
+    // the expected duration is 3 * 7 = 21 steps, and the probability of winning 3 / 10
+    let mean = durations.iter().sum::<usize>() as f64 / 4000.0;
+    assert!((19.0..23.0).contains(&mean), "{mean}");
+
+    let won = simulation
+        .iter::<RandomWalker<i32>>()
+        .filter(|walker| walker.position() == 10)
+        .count();
+    assert!((1050..1350).contains(&won), "{won}");
+    assert!(
+        simulation
+            .iter::<RandomWalker<i32>>()
+            .all(|walker| walker.is_absorbed() && walker.first_passage() == Some(walker.steps()))
+    );
+}
+
+#[test]
+fn test_random_walk_diffusion()
+{
+    // on a lattice, every step adds 1 to the expected squared displacement
+    let mut simulation = walkers(2000, RandomWalker::new(IVec3::ZERO, Step::Lattice));
+    simulation.run(100);
+    let MeanSquaredDisplacement(msd) = simulation
+        .sample_aggregate::<RandomWalker<IVec3>, _>()
+        .expect("no walkers");
+    assert!((90.0..110.0).contains(&msd), "{msd}");
+
+    // in continuous space, every step adds the variance of each coordinate
+    let mut simulation = walkers(
+        2000,
+        RandomWalker::new(DVec2::ZERO, Step::Gaussian { std_dev: 0.5 }),
+    );
+    simulation.run(100);
+    let MeanSquaredDisplacement(msd) = simulation
+        .sample_aggregate::<RandomWalker<DVec2>, _>()
+        .expect("no walkers");
+    assert!((45.0..55.0).contains(&msd), "{msd}");
+}
+
+#[test]
+fn test_random_walk_boundaries()
+{
+    let (min, max) = (IVec2::new(0, 0), IVec2::new(4, 4));
+
+    for boundary in [Boundary::Reflect { min, max }, Boundary::Wrap { min, max }]
+    {
+        let mut simulation = SimulationBuilder::new()
+            .add_random_walks::<IVec2>()
+            .add_spatial_grid_2d::<RandomWalker<IVec2>>(None)
+            .add_entity_spawner(move |spawner| {
+                for _ in 0..100
+                {
+                    spawner.spawn((
+                        RandomWalker::new(IVec2::new(2, 2), Step::Gaussian { std_dev: 3.0 })
+                            .with_boundary(boundary),
+                        GridPosition2D::new(2, 2),
+                    ));
+                }
+            })
+            .build();
+        simulation.run(50);
+
+        for walker in simulation.iter::<RandomWalker<IVec2>>()
+        {
+            let position = walker.position();
+            assert!(position.cmpge(min).all() && position.cmple(max).all());
+            assert!(!walker.is_absorbed());
+        }
+
+        // the grid positions follow the walkers
+        let mut walker_positions = simulation
+            .iter::<RandomWalker<IVec2>>()
+            .map(|walker| walker.position().to_array())
+            .collect::<Vec<_>>();
+        let mut grid_positions = simulation
+            .iter::<GridPosition2D>()
+            .map(|position| position.0.to_array())
+            .collect::<Vec<_>>();
+        walker_positions.sort_unstable();
+        grid_positions.sort_unstable();
+        assert_eq!(walker_positions, grid_positions);
+    }
+}
+
+#[test]
+fn test_random_walk_target()
+{
+    let walker = RandomWalker::new(IVec2::ZERO, Step::Lattice)
+        .with_target(IVec2::new(1, 1), 0.0)
+        .with_boundary(Boundary::Wrap {
+            min: IVec2::new(-2, -2),
+            max: IVec2::new(2, 2),
+        });
+    let mut simulation = walkers(500, walker);
+    simulation.run(2000);
+
+    // on a finite torus, every walker eventually reaches the target, in at least 2 steps
+    let FirstPassageTimes(times) = simulation
+        .sample_aggregate::<RandomWalker<IVec2>, _>()
+        .expect("no walkers");
+    assert_eq!(times.len(), 500);
+    assert_eq!(times[0], 2);
+}
@@ -0,0 +1,241 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::{
+    prelude::*,
+    reaction_diffusion::{Boundary, ReactionDiffusionPlugin, ScalarFields},
+};
+
+const fn bounds(width: i32, height: i32) -> GridBounds2D
+{
+    GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(width - 1, height - 1),
+    }
+}
+
+fn fields(simulation: &Simulation) -> &ScalarFields
+{
+    simulation
+        .get_resource::<ScalarFields>()
+        .expect("no scalar fields")
+}
+
+fn value(simulation: &Simulation, name: &str, x: i32, y: i32) -> f64
+{
+    fields(simulation)
+        .get(name, IVec2::new(x, y))
+        .expect("no such cell")
+}
+
+/// Builds a simulation of the given plugin, with the field `heat` set to `1` in the given cell.
+fn heated(plugin: ReactionDiffusionPlugin, x: i32, y: i32) -> Simulation
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_reaction_diffusion(plugin)
+        .build();
+    simulation
+        .resource_scope(|fields: &mut ScalarFields| fields.set("heat", IVec2::new(x, y), 1.0))
+        .expect("no scalar fields");
+    simulation
+}
+
+#[test]
+fn test_diffusion_stencil()
+{
+    let plugin = ReactionDiffusionPlugin::new(bounds(3, 3)).with_field("heat", 0.25, 0.0);
+    let mut simulation = heated(plugin, 1, 1);
+
+    simulation.run(1);
+
+    assert_eq!(value(&simulation, "heat", 1, 1), 0.0);
+    for (x, y) in [(0, 1), (2, 1), (1, 0), (1, 2)]
+    {
+        assert_eq!(value(&simulation, "heat", x, y), 0.25);
+    }
+    for (x, y) in [(0, 0), (2, 0), (0, 2), (2, 2)]
+    {
+        assert_eq!(value(&simulation, "heat", x, y), 0.0);
+    }
+}
+
+#[test]
+fn test_no_flux_conserves_total()
+{
+    let plugin = ReactionDiffusionPlugin::new(bounds(5, 5)).with_field("heat", 0.2, 0.0);
+    let mut simulation = heated(plugin, 2, 2);
+
+    simulation.run(50);
+
+    let total = fields(&simulation).total("heat").expect("no heat field");
+    assert!((total - 1.0).abs() < 1e-9, "{total}");
+
+    // the heat spreads out evenly in every direction
+    assert!(value(&simulation, "heat", 2, 2) < 0.1);
+    assert!((value(&simulation, "heat", 0, 0) - value(&simulation, "heat", 4, 4)).abs() < 1e-12);
+    assert!((value(&simulation, "heat", 0, 2) - value(&simulation, "heat", 2, 0)).abs() < 1e-12);
+}
+
+#[test]
+fn test_boundaries()
+{
+    let plugin = |boundary| {
+        ReactionDiffusionPlugin::new(bounds(3, 1))
+            .with_field("heat", 0.25, 0.0)
+            .with_boundary(boundary)
+    };
+
+    let mut periodic = heated(plugin(Boundary::Periodic), 0, 0);
+    periodic.run(1);
+    assert_eq!(value(&periodic, "heat", 0, 0), 0.5);
+    assert_eq!(value(&periodic, "heat", 1, 0), 0.25);
+    assert_eq!(value(&periodic, "heat", 2, 0), 0.25);
+
+    let mut absorbing = heated(plugin(Boundary::Absorbing), 0, 0);
+    absorbing.run(1);
+    assert_eq!(value(&absorbing, "heat", 0, 0), 0.0);
+    assert_eq!(value(&absorbing, "heat", 1, 0), 0.25);
+    assert_eq!(value(&absorbing, "heat", 2, 0), 0.0);
+    assert_eq!(fields(&absorbing).total("heat"), Some(0.25));
+}
+
+#[test]
+fn test_coupled_reactions()
+{
+    // a substrate converted into a product, at a rate proportional to the substrate
+    let plugin = ReactionDiffusionPlugin::new(bounds(4, 4))
+        .with_field("substrate", 0.1, 1.0)
+        .with_field("product", 0.0, 0.0)
+        .with_reaction(|values, rates| {
+            rates[0] = -0.1 * values[0];
+            rates[1] = 0.1 * values[0];
+        })
+        .with_substeps(10);
+    let mut simulation = SimulationBuilder::new()
+        .add_reaction_diffusion(plugin)
+        .build();
+
+    simulation.run(10);
+
+    let fields = fields(&simulation);
+    assert_eq!(
+        fields.names().collect::<Vec<_>>(),
+        vec!["substrate", "product"]
+    );
+    let substrate = value(&simulation, "substrate", 0, 0);
+    assert!((substrate - (-1.0_f64).exp()).abs() < 0.01, "{substrate}");
+    let total =
+        fields.total("substrate").unwrap_or_default() + fields.total("product").unwrap_or_default();
+    assert!((total - 16.0).abs() < 1e-9, "{total}");
+}
+
+#[derive(Component)]
+struct Source;
+
+#[derive(Component)]
+struct Sensor
+{
+    gradient: (f64, f64),
+}
+
+#[test]
+fn test_agents_read_and_write()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_reaction_diffusion(ReactionDiffusionPlugin::new(bounds(7, 7)).with_field(
+            "pheromone",
+            0.2,
+            0.0,
+        ))
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Source, GridPosition2D::new(3, 3)));
+            spawner.spawn((
+                Sensor {
+                    gradient: (0.0, 0.0),
+                },
+                GridPosition2D::new(5, 3),
+            ));
+        })
+        .add_systems(
+            |mut fields: ResMut<ScalarFields>,
+             sources: Query<&GridPosition2D, With<Source>>,
+             mut sensors: Query<(&mut Sensor, &GridPosition2D)>| {
+                for position in &sources
+                {
+                    assert!(fields.add("pheromone", position.0, 1.0));
+                }
+                for (mut sensor, position) in &mut sensors
+                {
+                    let gradient = fields
+                        .gradient("pheromone", position.0)
+                        .expect("sensor outside the grid");
+                    sensor.gradient = (gradient.x, gradient.y);
+                }
+            },
+        )
+        .build();
+
+    simulation.run(10);
+
+    let total = fields(&simulation)
+        .total("pheromone")
+        .expect("no pheromone field");
+    assert!((total - 10.0).abs() < 1e-9, "{total}");
+
+    // the pheromone is strongest at the source, which the sensor senses to its left
+    let (x, y) = simulation
+        .iter::<Sensor>()
+        .next()
+        .expect("no sensor")
+        .gradient;
+    assert!(x < 0.0);
+    assert!(y.abs() < 1e-12, "{y}");
+}
+
+#[test]
+fn test_missing_cells_and_fields()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_reaction_diffusion(
+            ReactionDiffusionPlugin::new(bounds(2, 2)).with_field("heat", 0.1, 3.0),
+        )
+        .build();
+
+    let fields = fields(&simulation);
+    assert_eq!(fields.get("heat", IVec2::new(1, 1)), Some(3.0));
+    assert_eq!(fields.get("heat", IVec2::new(2, 1)), None);
+    assert_eq!(fields.get("cold", IVec2::new(1, 1)), None);
+    assert_eq!(fields.values("heat").map(<[f64]>::len), Some(4));
+
+    let written = simulation
+        .resource_scope(|fields: &mut ScalarFields| {
+            (
+                fields.set("heat", IVec2::new(-1, 0), 1.0),
+                fields.add("cold", IVec2::new(0, 0), 1.0),
+            )
+        })
+        .expect("no scalar fields");
+    assert_eq!(written, (false, false));
+}
+
+#[test]
+#[should_panic(expected = "unstable diffusion of field heat: use at least 4 substeps")]
+fn test_unstable_diffusion()
+{
+    let _ = SimulationBuilder::new().add_reaction_diffusion(
+        ReactionDiffusionPlugin::new(bounds(2, 2)).with_field("heat", 1.0, 0.0),
+    );
+}
+
+#[test]
+fn test_substeps_stabilize_diffusion()
+{
+    let plugin = ReactionDiffusionPlugin::new(bounds(9, 9))
+        .with_field("heat", 1.0, 0.0)
+        .with_substeps(4);
+    let mut simulation = heated(plugin, 4, 4);
+
+    simulation.run(20);
+
+    let values = fields(&simulation).values("heat").expect("no heat field");
+    assert!(values.iter().all(|value| (0.0..=1.0).contains(value)));
+}
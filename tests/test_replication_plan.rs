@@ -0,0 +1,123 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::prelude::*;
+use rand::Rng;
+
+#[derive(Component)]
+struct Value(f64);
+
+/// Every replica spawns a single value, drawn from a uniform distribution of the given width
+/// around its index.
+fn build_uniform(width: f64) -> impl Fn(usize) -> SimulationBuilder + Sync
+{
+    move |idx| {
+        SimulationBuilder::new()
+            .set_seed(SimRng::derive_seed(7, idx as u64))
+            .add_seeded_entity_spawner(move |spawner, rng| {
+                spawner.spawn(Value(rng.random_range(0.0..width)));
+            })
+    }
+}
+
+fn value(simulation: &Simulation) -> f64
+{
+    simulation.iter::<Value>().next().expect("no value").0
+}
+
+#[test]
+fn test_required_replicas()
+{
+    let plan = ReplicationPlan::new(0.5, 0.95);
+    assert_eq!(plan.half_width(), 0.5);
+    assert_eq!(plan.level(), 0.95);
+    assert_eq!(plan.pilot(), 10);
+    assert_eq!(plan.max_replicas(), None);
+
+    let ensemble = EnsembleResult::new([1.0, 2.0, 3.0, 4.0, 5.0]);
+    let additional = ensemble.additional_runs(0.5, 0.95).expect("no estimate");
+    assert_eq!(plan.required_replicas(&ensemble), Some(5 + additional));
+    assert!(!plan.is_met(&ensemble));
+
+    let wide = ReplicationPlan::new(10.0, 0.95);
+    assert_eq!(wide.required_replicas(&ensemble), Some(5));
+    assert!(wide.is_met(&ensemble));
+
+    assert_eq!(plan.required_replicas(&EnsembleResult::new([1.0])), None);
+    assert!(!plan.is_met(&EnsembleResult::default()));
+}
+
+#[test]
+fn test_replicate_until_met()
+{
+    let plan = ReplicationPlan::new(0.1, 0.95).with_pilot(5);
+    let ensemble = Simulation::replicate_planned(&plan, 1, build_uniform(2.0), value);
+
+    // the standard deviation is 1 / sqrt(3), which needs about 128 replications
+    assert!(plan.is_met(&ensemble));
+    assert!(ensemble.len() > 100 && ensemble.len() < 200);
+
+    // the replicas are indexed across the batches
+    let rerun = Simulation::replicate(ensemble.len(), 1, build_uniform(2.0));
+    assert_eq!(EnsembleResult::from_replicas(&rerun, value), ensemble);
+}
+
+#[test]
+fn test_replicate_within_pilot()
+{
+    // an outcome that does not vary meets any target with the pilot alone
+    let plan = ReplicationPlan::new(0.1, 0.95).with_pilot(4);
+    let ensemble = Simulation::replicate_planned(
+        &plan,
+        1,
+        |_| {
+            SimulationBuilder::new().add_entity_spawner(|spawner| {
+                spawner.spawn(Value(3.0));
+            })
+        },
+        value,
+    );
+    assert_eq!(ensemble.len(), 4);
+    assert_eq!(ensemble.mean(), Some(3.0));
+}
+
+#[test]
+fn test_replicate_up_to_limit()
+{
+    let plan = ReplicationPlan::new(0.01, 0.95).with_max_replicas(30);
+    let ensemble = Simulation::replicate_planned(&plan, 1, build_uniform(100.0), value);
+
+    assert_eq!(ensemble.len(), 30);
+    assert!(!plan.is_met(&ensemble));
+}
+
+#[test]
+#[should_panic(expected = "the pilot needs at least 3 replications")]
+fn test_small_pilot()
+{
+    let _ = ReplicationPlan::new(0.1, 0.95).with_pilot(2);
+}
+
+#[test]
+#[should_panic(expected = "invalid target half-width")]
+fn test_invalid_half_width()
+{
+    let _ = ReplicationPlan::new(0.0, 0.95);
+}
+
+#[test]
+fn test_replicas_are_seeded_independently()
+{
+    // replicas whose builder sets no seed are seeded independently, across the batches too
+    #[allow(clippy::cast_precision_loss)]
+    let seed = |simulation: &Simulation| simulation.meta().seed.expect("no seed") as f64;
+    let plan = ReplicationPlan::new(0.01, 0.95)
+        .with_pilot(4)
+        .with_max_replicas(12);
+    let ensemble = Simulation::replicate_planned(&plan, 1, |_| SimulationBuilder::new(), seed);
+    assert_eq!(ensemble.len(), 12);
+
+    let mut seeds = ensemble.values().to_vec();
+    seeds.sort_unstable_by(f64::total_cmp);
+    seeds.dedup();
+    assert_eq!(seeds.len(), 12);
+}
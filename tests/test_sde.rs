@@ -0,0 +1,152 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+#![allow(clippy::cast_precision_loss)]
+use incerto::{
+    prelude::*,
+    sde::{Process, StochasticProcess},
+};
+
+/// Builds a simulation of `count` copies of the given process.
+fn processes(count: usize, process: StochasticProcess) -> Simulation
+{
+    SimulationBuilder::new()
+        .set_seed(5)
+        .add_stochastic_processes()
+        .add_entity_spawner(move |spawner| {
+            for _ in 0..count
+            {
+                spawner.spawn(process.clone());
+            }
+        })
+        .build()
+}
+
+fn mean_value(simulation: &Simulation) -> f64
+{
+    let values: Vec<f64> = simulation
+        .iter::<StochasticProcess>()
+        .map(StochasticProcess::value)
+        .collect();
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[test]
+fn test_sde_ornstein_uhlenbeck_reverts_to_mean()
+{
+    let process = StochasticProcess::new(
+        10.0,
+        Process::OrnsteinUhlenbeck {
+            theta: 0.5,
+            mean: 2.0,
+            sigma: 0.3,
+        },
+    )
+    .with_dt(0.1);
+    let mut simulation = processes(2000, process);
+    simulation.run(200);
+
+    let mean = mean_value(&simulation);
+    assert!((mean - 2.0).abs() < 0.05, "mean: {mean}");
+
+    for process in simulation.iter::<StochasticProcess>()
+    {
+        assert!((process.time() - 20.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_sde_geometric_brownian_mean_growth()
+{
+    let (mu, t) = (0.1, 2.0);
+    let process =
+        StochasticProcess::new(1.0, Process::GeometricBrownian { mu, sigma: 0.2 }).with_dt(0.01);
+    let mut simulation = processes(4000, process);
+    simulation.run(200);
+
+    let expected = (mu * t).exp();
+    let mean = mean_value(&simulation);
+    assert!(
+        (mean - expected).abs() < 0.03,
+        "mean: {mean}, expected: {expected}"
+    );
+
+    // a geometric Brownian motion never turns negative
+    assert!(
+        simulation
+            .iter::<StochasticProcess>()
+            .all(|p| p.value() > 0.0)
+    );
+}
+
+#[test]
+fn test_sde_lower_bound_absorbs()
+{
+    let process = StochasticProcess::new(
+        1.0,
+        Process::Brownian {
+            mu: -0.1,
+            sigma: 0.5,
+        },
+    )
+    .with_lower_bound(0.0);
+    let mut simulation = processes(500, process);
+    simulation.run(500);
+
+    let absorbed: Vec<&StochasticProcess> = simulation
+        .iter::<StochasticProcess>()
+        .filter(|p| p.is_absorbed())
+        .collect();
+    assert!(absorbed.len() > 450);
+    for process in absorbed
+    {
+        assert_eq!(process.value(), 0.0);
+        assert!(process.time() < 500.0);
+    }
+}
+
+#[test]
+fn test_sde_custom_deterministic()
+{
+    let process = StochasticProcess::new(
+        1.0,
+        Process::Custom {
+            drift: |t, _| 2.0 * t,
+            diffusion: |_, _| 0.0,
+        },
+    )
+    .with_dt(0.5);
+    let mut simulation = processes(3, process);
+    simulation.run(4);
+
+    // x(t) = 1 + sum of 2 t_i dt, for t_i = 0.0, 0.5, 1.0, 1.5
+    for process in simulation.iter::<StochasticProcess>()
+    {
+        assert_eq!(process.value(), 4.0);
+        assert_eq!(process.time(), 2.0);
+    }
+}
+
+#[test]
+fn test_sde_reproducible()
+{
+    let process = StochasticProcess::new(
+        0.0,
+        Process::Brownian {
+            mu: 0.0,
+            sigma: 1.0,
+        },
+    );
+
+    let run = || {
+        let mut simulation = processes(10, process.clone());
+        simulation.run(50);
+        simulation
+            .iter::<StochasticProcess>()
+            .map(f64::from)
+            .collect::<Vec<_>>()
+    };
+
+    let first = run();
+    assert_eq!(first.len(), 10);
+    assert_eq!(first, run());
+}
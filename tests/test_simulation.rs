@@ -0,0 +1,1956 @@
+#![allow(clippy::expect_used)]
+use std::ops::ControlFlow;
+
+use incerto::prelude::*;
+use rand::Rng;
+
+#[derive(Component)]
+struct Person;
+
+#[derive(Component)]
+struct Infected;
+
+#[derive(Component)]
+struct Cash(usize);
+
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash)]
+struct TraderId(usize);
+
+impl Sample<usize> for Cash
+{
+    fn sample(component: &Self) -> usize
+    {
+        component.0
+    }
+}
+
+#[test]
+fn test_spawn_despawn_between_runs() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for _ in 0..10
+            {
+                spawner.spawn(Person);
+            }
+        })
+        .build();
+
+    simulation.run(5);
+    assert_eq!(simulation.count::<With<Person>>()?, 10);
+
+    // seed a second wave of infections
+    for _ in 0..3
+    {
+        simulation.spawn((Person, Infected));
+    }
+    simulation.run(5);
+    assert_eq!(simulation.count::<With<Person>>()?, 13);
+    assert_eq!(simulation.count::<With<Infected>>()?, 3);
+
+    // cull the infected
+    let despawned = simulation.despawn_where::<With<Infected>>();
+    assert_eq!(despawned, 3);
+    assert_eq!(simulation.count::<With<Person>>()?, 10);
+    assert_eq!(simulation.count::<With<Infected>>()?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_modify_components_between_runs() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Cash(10), TraderId(1)));
+            spawner.spawn((Cash(20), TraderId(2)));
+        })
+        .build();
+
+    simulation.modify::<Cash, _>(&TraderId(2), |cash| cash.0 += 5)?;
+    assert_eq!(simulation.sample::<Cash, _, usize>(&TraderId(1))?, 10);
+    assert_eq!(simulation.sample::<Cash, _, usize>(&TraderId(2))?, 25);
+
+    // grant every trader a stimulus payment
+    let modified = simulation.modify_all::<Cash>(|cash| cash.0 += 100)?;
+    assert_eq!(modified, 2);
+    assert_eq!(simulation.sample::<Cash, _, usize>(&TraderId(1))?, 110);
+    assert_eq!(simulation.sample::<Cash, _, usize>(&TraderId(2))?, 125);
+
+    let err = simulation.modify::<Cash, _>(&TraderId(3), |cash| cash.0 = 0);
+    assert_eq!(
+        err,
+        Err(SamplingError::EntityIdentifierNotFound {
+            identifier: std::any::type_name::<TraderId>()
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_background_run() -> Result<(), SimulationError>
+{
+    let simulation = SimulationBuilder::new()
+        .add_systems(|mut query: Query<&mut Cash>| {
+            for mut cash in &mut query
+            {
+                cash.0 += 1;
+            }
+        })
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Cash(0), TraderId(1)));
+        })
+        .build();
+
+    let background = simulation.spawn_background(100, 10);
+    let statuses = background.status().iter().collect::<Vec<_>>();
+    let simulation = background.join();
+
+    assert_eq!(statuses.len(), 10);
+    assert_eq!(
+        statuses.last(),
+        Some(&SimulationStatus {
+            step: 100,
+            remaining_steps: 0
+        })
+    );
+    assert_eq!(simulation.current_step(), 100);
+    assert_eq!(simulation.sample::<Cash, _, usize>(&TraderId(1))?, 100);
+
+    Ok(())
+}
+
+#[test]
+fn test_background_pause_stop()
+{
+    let simulation = SimulationBuilder::new().build();
+
+    let background = simulation.spawn_background(usize::MAX, 1);
+    background
+        .status()
+        .recv()
+        .expect("expected at least one status");
+    background.pause();
+    background.resume();
+
+    let simulation = background.stop();
+    assert!(simulation.current_step() > 0);
+}
+
+#[test]
+fn test_step_end_hooks() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_systems(|mut query: Query<&mut Cash>| {
+            for mut cash in &mut query
+            {
+                cash.0 += 1;
+            }
+        })
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Cash(0), TraderId(1)));
+        })
+        .build();
+
+    let mut last_step = 0;
+    simulation.on_step_end(move |world, step| {
+        // the hook should be invoked exactly once per step
+        assert_eq!(step, last_step + 1);
+        last_step = step;
+
+        // stop as soon as the trader has enough cash
+        let mut query = world.try_query::<&Cash>().expect("no cash component");
+        let cash = query.single(world).expect("expected a single trader");
+        if cash.0 >= 25
+        {
+            ControlFlow::Break(())
+        }
+        else
+        {
+            ControlFlow::Continue(())
+        }
+    });
+
+    simulation.run(100);
+    assert_eq!(simulation.current_step(), 25);
+    assert_eq!(simulation.sample::<Cash, _, usize>(&TraderId(1))?, 25);
+
+    Ok(())
+}
+
+#[test]
+fn test_send_event() -> Result<(), SimulationError>
+{
+    #[derive(Event)]
+    struct Stimulus(usize);
+
+    let mut simulation = SimulationBuilder::new()
+        .register_event::<Stimulus>()
+        .add_systems(
+            |mut query: Query<&mut Cash>, mut events: EventReader<Stimulus>| {
+                for stimulus in events.read()
+                {
+                    for mut cash in &mut query
+                    {
+                        cash.0 += stimulus.0;
+                    }
+                }
+            },
+        )
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Cash(0), TraderId(1)));
+        })
+        .build();
+
+    simulation.run(5);
+    simulation.send_event(Stimulus(10));
+    simulation.send_event(Stimulus(5));
+    simulation.run(5);
+
+    // each event should have been delivered exactly once
+    assert_eq!(simulation.sample::<Cash, _, usize>(&TraderId(1))?, 15);
+
+    Ok(())
+}
+
+#[test]
+fn test_iter_components()
+{
+    let simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Cash(10));
+            spawner.spawn((Cash(20), Infected));
+            spawner.spawn((Cash(30), Infected));
+        })
+        .build();
+
+    let mut all = simulation.iter::<Cash>().map(|c| c.0).collect::<Vec<_>>();
+    all.sort_unstable();
+    assert_eq!(all, vec![10, 20, 30]);
+
+    let infected_cash = simulation
+        .iter_with::<Cash, With<Infected>>()
+        .map(|c| c.0)
+        .sum::<usize>();
+    assert_eq!(infected_cash, 50);
+
+    // components that were never spawned
+    assert_eq!(simulation.iter::<Person>().count(), 0);
+}
+
+#[test]
+fn test_runtime_resources() -> Result<(), SimulationError>
+{
+    #[derive(Resource)]
+    struct Interest(usize);
+
+    #[derive(Resource, Default)]
+    struct TotalPaid(usize);
+
+    #[derive(Resource)]
+    struct Unused;
+
+    let mut simulation = SimulationBuilder::new()
+        .add_resource(Interest(1))
+        .add_resource(TotalPaid::default())
+        .add_systems(
+            |mut query: Query<&mut Cash>, interest: Res<Interest>, mut total: ResMut<TotalPaid>| {
+                for mut cash in &mut query
+                {
+                    cash.0 += interest.0;
+                    total.0 += interest.0;
+                }
+            },
+        )
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Cash(0), TraderId(1)));
+        })
+        .build();
+
+    simulation.run(10);
+    simulation.insert_resource(Interest(2));
+    simulation.run(10);
+    assert_eq!(simulation.get_resource::<TotalPaid>()?.0, 30);
+
+    simulation.resource_scope(|interest: &mut Interest| interest.0 = 0)?;
+    simulation.run(10);
+    assert_eq!(simulation.sample::<Cash, _, usize>(&TraderId(1))?, 30);
+
+    assert_eq!(
+        simulation.get_resource::<StepNumber>().map(StepNumber::get),
+        Ok(31)
+    );
+    assert!(matches!(
+        simulation.resource_scope(|_: &mut Unused| ()),
+        Err(SamplingError::ResourceDoesNotExist { resource }) if resource.ends_with("Unused")
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_replicate() -> Result<(), SimulationError>
+{
+    const NUM_REPLICAS: usize = 6;
+
+    let replicas = Simulation::replicate(NUM_REPLICAS, 10, |idx| {
+        SimulationBuilder::new()
+            .add_systems(|mut query: Query<&mut Cash>| {
+                for mut cash in &mut query
+                {
+                    cash.0 += 1;
+                }
+            })
+            .add_entity_spawner(move |spawner| {
+                spawner.spawn((Cash(100 * idx), TraderId(1)));
+            })
+    });
+
+    assert_eq!(replicas.len(), NUM_REPLICAS);
+    for (idx, replica) in replicas.iter().enumerate()
+    {
+        assert_eq!(replica.current_step(), 10);
+        assert_eq!(
+            replica.sample::<Cash, _, usize>(&TraderId(1))?,
+            100 * idx + 10
+        );
+    }
+
+    // the replicas are seeded independently, unless their builder sets a seed
+    let mut seeds = replicas
+        .iter()
+        .map(|replica| replica.meta().seed)
+        .collect::<Vec<_>>();
+    assert!(seeds.iter().all(Option::is_some));
+    seeds.sort_unstable();
+    seeds.dedup();
+    assert_eq!(seeds.len(), NUM_REPLICAS);
+
+    let replicas = Simulation::replicate(2, 1, |_| SimulationBuilder::new().set_seed(7));
+    assert!(
+        replicas
+            .iter()
+            .all(|replica| replica.meta().seed == Some(7))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_replicate_seeded()
+{
+    const NUM_REPLICAS: usize = 8;
+
+    let run = |seed| {
+        Simulation::replicate_seeded(seed, NUM_REPLICAS, 5, |_| {
+            SimulationBuilder::new().add_seeded_entity_spawner(|spawner, rng| {
+                spawner.spawn((Cash(rng.random_range(0..1_000_000)), TraderId(1)));
+            })
+        })
+        .iter()
+        .map(|replica| {
+            (
+                replica.meta().seed,
+                replica.iter::<Cash>().next().map(|c| c.0),
+            )
+        })
+        .collect::<Vec<_>>()
+    };
+
+    // the same seed should reproduce the whole batch, in order
+    let batch = run(42);
+    assert_eq!(batch, run(42));
+    assert_ne!(batch, run(43));
+
+    // each replica should be seeded differently
+    for (idx, (seed, _)) in batch.iter().enumerate()
+    {
+        assert_eq!(*seed, Some(SimRng::derive_seed(42, idx as u64)));
+    }
+    let mut seeds = batch.iter().map(|(seed, _)| *seed).collect::<Vec<_>>();
+    seeds.sort_unstable();
+    seeds.dedup();
+    assert_eq!(seeds.len(), NUM_REPLICAS);
+}
+
+#[test]
+fn test_memory_report() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_spatial_grid::<IVec2, Person>(None)
+        .record_time_series::<Cash, TraderId, usize>(1)?
+        .add_entity_spawner(|spawner| {
+            for x in 0..10
+            {
+                spawner.spawn((Person, GridPosition2D::new(x, 0)));
+            }
+            for id in 0..10
+            {
+                spawner.spawn((Cash(100), TraderId(id)));
+            }
+        })
+        .build();
+
+    let empty = SimulationBuilder::new().build().memory_report();
+    assert_eq!(empty.spatial_grid_cells, 0);
+    assert_eq!(empty.time_series_samples, 0);
+    assert_eq!(empty.total_bytes(), 0);
+
+    simulation.run(5);
+
+    let report = simulation.memory_report();
+    assert!(report.entities >= 20);
+    assert!(report.archetypes > 1);
+    assert_eq!(report.spatial_grid_cells, 10);
+    assert!(report.spatial_grid_bytes > 0);
+    assert_eq!(report.time_series_samples, 10 * 5);
+    assert!(report.time_series_bytes >= 10 * 5 * 2 * size_of::<usize>());
+    assert_eq!(
+        report.total_bytes(),
+        report.spatial_grid_bytes + report.time_series_bytes
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_benchmark()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_systems(|| std::thread::sleep(std::time::Duration::from_millis(2)))
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Cash(1));
+        })
+        .build();
+
+    let report = simulation.benchmark(5);
+    assert_eq!(report.steps, 5);
+    assert_eq!(simulation.current_step(), 5);
+    assert!(report.user_systems >= std::time::Duration::from_millis(10));
+    assert!(report.user_systems > report.index_maintenance + report.sampling);
+    assert_eq!(
+        report.total,
+        report.user_systems + report.index_maintenance + report.sampling + report.other
+    );
+    assert!(report.mean_step_latency() >= std::time::Duration::from_millis(2));
+    assert!(report.steps_per_second() > 0.0);
+
+    // the benchmark should end early like a regular run
+    simulation.on_step_end(|_, step| {
+        if step >= 8
+        {
+            ControlFlow::Break(())
+        }
+        else
+        {
+            ControlFlow::Continue(())
+        }
+    });
+    assert_eq!(simulation.benchmark(10).steps, 3);
+    assert_eq!(simulation.current_step(), 8);
+}
+
+#[test]
+fn test_owned_time_series() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Cash(0), TraderId(1)));
+        })
+        .add_systems(|mut query: Query<&mut Cash>| {
+            for mut cash in &mut query
+            {
+                cash.0 += 1;
+            }
+        })
+        .record_time_series::<Cash, TraderId, usize>(2)?
+        .build();
+    simulation.run(6);
+
+    let owned = simulation
+        .get_time_series::<Cash, TraderId, usize>(&TraderId(1))?
+        .into_owned();
+    drop(simulation);
+
+    let time_series = owned.as_time_series();
+    assert_eq!(time_series.values_slice(), &[2, 4, 6]);
+    assert_eq!(time_series.time_slice(), &[2, 4, 6]);
+    assert_eq!(time_series.sample_interval(), 2);
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip()
+{
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let json = serde_json::to_string(value).expect("failed to serialize");
+        serde_json::from_str(&json).expect("failed to deserialize")
+    }
+
+    let position = GridPosition2D::new(3, -4);
+    assert_eq!(round_trip(&position), position);
+
+    let bounds = GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(9, 9),
+    };
+    assert_eq!(round_trip(&bounds), bounds);
+
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Cash(7), TraderId(1)));
+        })
+        .record_time_series::<Cash, TraderId, usize>(1)
+        .expect("failed to record time series")
+        .build();
+    simulation.run(3);
+    let owned = simulation
+        .get_time_series::<Cash, TraderId, usize>(&TraderId(1))
+        .expect("time series not recorded")
+        .into_owned();
+    assert_eq!(round_trip(&owned), owned);
+
+    let report = simulation.memory_report();
+    assert_eq!(round_trip(&report), report);
+
+    let error = DatasetError::InvalidRecord { line: Some(3) };
+    assert_eq!(round_trip(&error), error);
+
+    // errors holding type names can only be serialized
+    let error =
+        SimulationError::Sampling(SamplingError::ComponentDoesNotExist { component: "Cash" });
+    assert_eq!(
+        serde_json::to_value(error).expect("failed to serialize"),
+        serde_json::json!({ "Sampling": { "ComponentDoesNotExist": { "component": "Cash" } } })
+    );
+}
+
+#[cfg(feature = "python")]
+#[test]
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::float_cmp
+)]
+fn test_experiment_registry()
+{
+    use incerto::python::{ExperimentRegistry, Parameters};
+
+    let registry = ExperimentRegistry::new().register(
+        "deposits",
+        |parameters| {
+            let deposit = parameters.get("deposit").copied().unwrap_or(1.0) as usize;
+            SimulationBuilder::new()
+                .add_entity_spawner(|spawner| {
+                    spawner.spawn(Cash(0));
+                })
+                .add_systems(move |mut query: Query<&mut Cash>| {
+                    for mut cash in &mut query
+                    {
+                        cash.0 += deposit;
+                    }
+                })
+        },
+        |simulation| {
+            simulation
+                .iter::<Cash>()
+                .map(|cash| cash.0 as f64)
+                .chain([simulation.meta().seed.unwrap_or_default() as f64])
+                .collect()
+        },
+    );
+
+    let parameters = Parameters::from([("deposit".to_string(), 3.0)]);
+    let rows = registry
+        .run_experiment("deposits", 4, 10, &parameters, Some(7))
+        .expect("experiment not registered");
+    assert_eq!(rows.len(), 4);
+    for (idx, row) in rows.iter().enumerate()
+    {
+        assert_eq!(row[0], 30.0);
+        assert_eq!(row[1], SimRng::derive_seed(7, idx as u64) as f64);
+    }
+
+    let rows = registry
+        .run_experiment("deposits", 2, 5, &Parameters::new(), None)
+        .expect("experiment not registered");
+    assert_eq!(
+        rows.iter().map(|row| row[0]).collect::<Vec<_>>(),
+        [5.0, 5.0]
+    );
+
+    assert!(
+        registry
+            .run_experiment("withdrawals", 1, 1, &parameters, None)
+            .is_none()
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_export_state() -> Result<(), SimulationError>
+{
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    struct Health(f32);
+
+    let mut simulation = SimulationBuilder::new()
+        .register_type::<Health>()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Health(1.0), Person));
+            spawner.spawn(Health(0.5));
+            // not registered, so expected to be omitted
+            spawner.spawn(Person);
+        })
+        .build();
+    simulation.run(3);
+
+    let state = simulation.export_state()?;
+    assert!(state.contains("\"step\": 3"));
+    assert_eq!(state.matches("\"entity\"").count(), 2);
+    assert_eq!(state.matches("Health\"").count(), 2);
+    assert!(!state.contains("Person"));
+
+    // the export should be deterministic
+    assert_eq!(state, simulation.export_state()?);
+
+    Ok(())
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_export_state_snapshot() -> Result<(), SimulationError>
+{
+    #[derive(Component, Reflect)]
+    #[reflect(Component)]
+    struct Health(f32);
+
+    let mut simulation = SimulationBuilder::new()
+        .register_type::<Health>()
+        .add_entity_spawner(|spawner| {
+            for _ in 0..100
+            {
+                spawner.spawn((Health(1.0), Person));
+            }
+        })
+        .build();
+    simulation.run(3);
+
+    let json = simulation.export_state()?;
+    let bytes = simulation.export_state_snapshot()?;
+    assert!(bytes.len() < json.len());
+    assert_eq!(snapshot::version(&bytes), Ok(snapshot::VERSION));
+
+    // the snapshot holds the same state as the json export
+    let state: serde_json::Value = snapshot::decode(&bytes).expect("failed to decode state");
+    let expected: serde_json::Value = serde_json::from_str(&json).expect("invalid json");
+    assert_eq!(state, expected);
+
+    let mut outdated = bytes.clone();
+    outdated[4..6].copy_from_slice(&(snapshot::VERSION + 1).to_le_bytes());
+    assert_eq!(
+        snapshot::decode::<serde_json::Value>(&outdated),
+        Err(SnapshotError::UnsupportedVersion {
+            version: snapshot::VERSION + 1
+        })
+    );
+    assert_eq!(
+        snapshot::decode::<serde_json::Value>(json.as_bytes()),
+        Err(SnapshotError::InvalidHeader)
+    );
+    assert_eq!(
+        snapshot::decode::<serde_json::Value>(&bytes[..bytes.len() / 2]),
+        Err(SnapshotError::Malformed)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_population_ledger() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .track_population::<Person>()
+        .add_systems(
+            |mut commands: Commands, query: Query<Entity, With<Infected>>| {
+                for entity in &query
+                {
+                    commands.entity(entity).despawn();
+                }
+            },
+        )
+        .add_entity_spawner(|spawner| {
+            for _ in 0..10
+            {
+                spawner.spawn(Person);
+            }
+            for _ in 0..4
+            {
+                spawner.spawn((Person, Infected));
+            }
+        })
+        .build();
+
+    simulation.run(1);
+    simulation.spawn((Person, Infected));
+    simulation.spawn(Person);
+    simulation.run(1);
+
+    let ledger = simulation.ledger::<Person>()?;
+    assert_eq!(ledger.spawned(), 16);
+    assert_eq!(ledger.despawned(), 5);
+    assert_eq!(ledger.alive(), 11);
+
+    assert!(matches!(
+        simulation.ledger::<Infected>(),
+        Err(SamplingError::PopulationNotTracked { component }) if component.ends_with("Infected")
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_event_log_replay() -> Result<(), SimulationError>
+{
+    #[derive(Event, Clone)]
+    struct Deposit(usize);
+
+    fn apply_deposits(mut query: Query<&mut Cash>, mut events: EventReader<Deposit>)
+    {
+        for deposit in events.read()
+        {
+            for mut cash in &mut query
+            {
+                cash.0 += deposit.0;
+            }
+        }
+    }
+
+    fn spawn_trader(spawner: &mut Spawner)
+    {
+        spawner.spawn((Cash(0), TraderId(1)));
+    }
+
+    fn random_deposits(mut events: EventWriter<Deposit>)
+    {
+        events.write(Deposit(rand::random_range(0..100)));
+    }
+
+    let mut simulation = SimulationBuilder::new()
+        .record_events::<Deposit>()
+        .add_systems((random_deposits, apply_deposits).chain())
+        .add_entity_spawner(spawn_trader)
+        .build();
+    simulation.run(50);
+    simulation.send_event(Deposit(1000));
+    simulation.run(1);
+
+    let log = simulation.event_log::<Deposit>()?.clone();
+    assert_eq!(log.len(), 52);
+
+    // replay the recorded deposits without the system that randomly generated them
+    let mut replay = SimulationBuilder::new()
+        .replay_events(log)
+        .add_systems(apply_deposits)
+        .add_entity_spawner(spawn_trader)
+        .build();
+    replay.run(51);
+
+    assert_eq!(
+        replay.sample::<Cash, _, usize>(&TraderId(1))?,
+        simulation.sample::<Cash, _, usize>(&TraderId(1))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_future_events() -> Result<(), SimulationError>
+{
+    #[derive(Event, Clone, Debug, PartialEq, Eq)]
+    struct Ping(usize);
+
+    let mut simulation = SimulationBuilder::new()
+        .add_future_events::<Ping>()
+        .record_events::<Ping>()
+        .add_systems(
+            |step: Res<StepNumber>, mut future: ResMut<FutureEvents<Ping>>| {
+                if step.get() == 2
+                {
+                    future.schedule_in(3, Ping(1));
+                    future.schedule_in(1, Ping(2));
+                    future.schedule_in(3, Ping(3));
+                    future.schedule_at(1, Ping(4));
+                }
+            },
+        )
+        .build();
+
+    simulation.run(3);
+    {
+        let future = simulation
+            .get_resource::<FutureEvents<Ping>>()
+            .expect("missing future events");
+        assert_eq!(future.len(), 2);
+        assert_eq!(future.next_step(), Some(5));
+    }
+
+    simulation.run(5);
+    let log = simulation.event_log::<Ping>()?;
+    assert_eq!(
+        log.iter().collect::<Vec<_>>(),
+        [(3, &Ping(2)), (3, &Ping(4)), (5, &Ping(1)), (5, &Ping(3))]
+    );
+    assert!(
+        simulation
+            .get_resource::<FutureEvents<Ping>>()
+            .expect("missing future events")
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_simulation_meta()
+{
+    let simulation = SimulationBuilder::new()
+        .set_name("stimulus")
+        .set_description("one-off stimulus payment to all traders")
+        .set_seed(42)
+        .set_parameters(&(100_usize, "payment"))
+        .build();
+
+    let meta = simulation.meta();
+    assert_eq!(meta.name, "stimulus");
+    assert_eq!(meta.seed, Some(42));
+    assert_eq!(
+        meta.parameter_hash,
+        Some(SimulationMeta::hash_parameters(&(100_usize, "payment")))
+    );
+    assert!(meta.created_at > std::time::SystemTime::UNIX_EPOCH);
+
+    #[cfg(feature = "serde")]
+    {
+        let state = simulation.export_state().expect("failed to export state");
+        assert!(state.contains("\"name\": \"stimulus\""));
+    }
+}
+
+#[test]
+fn test_sample_entity_handle() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_systems(|mut query: Query<&mut Cash>| {
+            for mut cash in &mut query
+            {
+                cash.0 += 1;
+            }
+        })
+        .build();
+
+    let rich = simulation.spawn((Cash(1000), Infected));
+    let poor = simulation.spawn(Cash(0));
+    simulation.run(10);
+
+    assert_eq!(simulation.sample_entity::<Cash, usize>(rich)?, 1010);
+    assert_eq!(simulation.sample_entity::<Cash, usize>(poor)?, 10);
+
+    simulation.despawn_where::<With<Infected>>();
+    assert_eq!(
+        simulation.sample_entity::<Cash, usize>(rich),
+        Err(SamplingError::EntityHandleNotFound {
+            component: std::any::type_name::<Cash>()
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_seeded_spawner()
+{
+    fn build(seed: Option<u64>) -> Simulation
+    {
+        let mut builder = SimulationBuilder::new().add_seeded_entity_spawner(|spawner, rng| {
+            for i in 0..100
+            {
+                spawner.spawn((Cash(rng.random_range(0..1000)), TraderId(i)));
+            }
+        });
+        if let Some(seed) = seed
+        {
+            builder = builder.set_seed(seed);
+        }
+        builder.build()
+    }
+
+    let cash = |simulation: &Simulation| simulation.iter::<Cash>().map(|c| c.0).sum::<usize>();
+
+    // the same seed should produce the same initial state
+    let a = build(Some(7));
+    let b = build(Some(7));
+    assert_eq!(cash(&a), cash(&b));
+
+    // an unseeded simulation should record the seed it picked, so that it can be reproduced
+    let unseeded = build(None);
+    let seed = unseeded.meta().seed.expect("seed should be recorded");
+    assert_eq!(cash(&unseeded), cash(&build(Some(seed))));
+}
+
+#[test]
+fn test_scheduled_spawners() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Person);
+        })
+        .add_scheduled_spawner(5, |spawner, _| {
+            spawner.spawn((Person, Infected));
+        })
+        .add_recurring_spawner(10, |spawner, _| {
+            spawner.spawn(Person);
+        })
+        .add_systems(|query: Query<(), With<Infected>>, step: Res<StepNumber>| {
+            // the infected person should take part in the step in which they were spawned
+            assert_eq!(query.iter().count(), usize::from(step.get() >= 5));
+        })
+        .build();
+
+    simulation.run(4);
+    assert_eq!(simulation.count::<With<Person>>()?, 1);
+
+    simulation.run(1);
+    assert_eq!(simulation.count::<With<Infected>>()?, 1);
+
+    simulation.run(30);
+    assert_eq!(simulation.count::<With<Person>>()?, 5);
+    assert_eq!(simulation.count::<With<Infected>>()?, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_dependent_spawner()
+{
+    #[derive(Component)]
+    struct Household;
+
+    let simulation = SimulationBuilder::new()
+        .add_dependent_entity_spawner(|spawner, world| {
+            // one household for every two people
+            let num_people = world
+                .try_query_filtered::<(), With<Person>>()
+                .map_or(0, |mut query| query.iter(world).count());
+            for _ in 0..num_people / 2
+            {
+                spawner.spawn(Household);
+            }
+        })
+        .add_entity_spawner(|spawner| {
+            for _ in 0..10
+            {
+                spawner.spawn(Person);
+            }
+        })
+        .build();
+
+    assert_eq!(simulation.iter::<Household>().count(), 5);
+}
+
+#[derive(Resource)]
+struct Population(usize);
+
+#[test]
+fn test_spawner_with_resource() -> Result<(), SimulationError>
+{
+    let simulation = SimulationBuilder::new()
+        .add_entity_spawner_with_resource(|spawner, population: &Population| {
+            for _ in 0..population.0
+            {
+                spawner.spawn(Person);
+            }
+        })
+        .add_resource(Population(25))
+        .build();
+
+    assert_eq!(simulation.count::<With<Person>>()?, 25);
+    // the resource is still available to the simulation
+    assert_eq!(simulation.get_resource::<Population>()?.0, 25);
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "read by a spawner does not exist")]
+fn test_spawner_with_missing_resource()
+{
+    let _ = SimulationBuilder::new()
+        .add_entity_spawner_with_resource(|spawner, population: &Population| {
+            for _ in 0..population.0
+            {
+                spawner.spawn(Person);
+            }
+        })
+        .build();
+}
+
+#[test]
+fn test_weighted_spawner() -> Result<(), SimulationError>
+{
+    let build = || {
+        SimulationBuilder::new()
+            .set_seed(7)
+            .add_seeded_entity_spawner(|spawner, rng| {
+                WeightedSpawner::new()
+                    .add(0.7, || Person)
+                    .add(0.3, || (Person, Infected))
+                    .add(0.0, || Cash(0))
+                    .spawn(spawner, 1000, rng);
+            })
+            .build()
+    };
+
+    let simulation = build();
+    assert_eq!(simulation.count::<With<Person>>()?, 1000);
+    assert_eq!(simulation.iter::<Cash>().count(), 0);
+
+    let infected = simulation.count::<With<Infected>>()?;
+    assert!((250..350).contains(&infected));
+    assert_eq!(build().count::<With<Infected>>()?, infected);
+
+    Ok(())
+}
+
+#[test]
+fn test_spawn_with_children()
+{
+    #[derive(Component)]
+    struct Household;
+
+    let households = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let simulation = {
+        let households = households.clone();
+        SimulationBuilder::new()
+            .add_entity_spawner(move |spawner| {
+                let household = spawner.spawn_with_children(Household, |household| {
+                    household.spawn(Person);
+                    household.spawn_with_children(Person, |person| {
+                        person.spawn(Cash(10));
+                    });
+                });
+                households.lock().expect("poisoned").push(household);
+            })
+            .add_dependent_entity_spawner(|spawner, _| {
+                spawner.spawn_with_children(Household, |household| {
+                    household.spawn(Person);
+                });
+            })
+            .build()
+    };
+
+    let household = households.lock().expect("poisoned")[0].entity();
+    let children = simulation
+        .iter::<Children>()
+        .map(|children| children.len())
+        .collect::<Vec<_>>();
+    assert_eq!(children.len(), 3);
+    assert_eq!(children.iter().sum::<usize>(), 4);
+    assert_eq!(
+        simulation
+            .iter_with::<ChildOf, With<Person>>()
+            .filter(|child_of| child_of.parent() == household)
+            .count(),
+        2
+    );
+    assert_eq!(simulation.iter_with::<ChildOf, With<Cash>>().count(), 1);
+}
+
+#[test]
+fn test_refill_spawner() -> Result<(), SimulationError>
+{
+    fn leave(mut commands: Commands, query: Query<Entity, With<Person>>)
+    {
+        // three people leave every step
+        for entity in query.iter().take(3)
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for _ in 0..10
+            {
+                spawner.spawn(Person);
+            }
+        })
+        .add_systems(leave)
+        .add_refill_spawner::<Person>(5, 10, |spawner, missing, _| {
+            for _ in 0..missing
+            {
+                spawner.spawn(Person);
+            }
+        })
+        .build();
+
+    simulation.run(1);
+    assert_eq!(simulation.count::<With<Person>>()?, 7);
+    simulation.run(1);
+    assert_eq!(simulation.count::<With<Person>>()?, 4);
+
+    // the population dropped below the threshold, so it is refilled before the next step
+    simulation.run(1);
+    assert_eq!(simulation.count::<With<Person>>()?, 7);
+
+    Ok(())
+}
+
+#[test]
+fn test_numeric_guard() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Price(f64);
+
+    impl Sample<f64> for Price
+    {
+        fn sample(component: &Self) -> f64
+        {
+            component.0
+        }
+    }
+
+    fn crash(mut query: Query<&mut Price>, step: Res<StepNumber>)
+    {
+        for mut price in &mut query
+        {
+            price.0 -= 1.0;
+            if step.get() == 5
+            {
+                price.0 = price.0.ln();
+            }
+        }
+    }
+
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Price(10.0));
+            spawner.spawn(Price(5.0));
+        })
+        .add_systems(crash)
+        .add_numeric_guard::<Price, f64>()
+        .build();
+
+    simulation.run(3);
+    simulation.check_numeric_guards()?;
+
+    // the second price reaches zero in the fifth step, and its logarithm is negative infinity
+    simulation.run(10);
+    assert_eq!(simulation.current_step(), 5);
+
+    let err = simulation
+        .check_numeric_guards()
+        .expect_err("the guard should have caught the infinity");
+    assert!(matches!(
+        err,
+        NumericGuardError::Infinite {
+            step: 5,
+            negative: true,
+            ..
+        }
+    ));
+    assert!(err.to_string().contains("-infinity at step 5"));
+
+    Ok(())
+}
+
+#[test]
+fn test_sampling_error_kinds()
+{
+    #[derive(Component)]
+    struct Unspawned;
+
+    #[derive(Resource)]
+    struct Unused;
+
+    #[derive(Event, Clone)]
+    struct Unrecorded;
+
+    impl SampleAggregate<usize> for Unspawned
+    {
+        fn sample_aggregate(components: &[&Self]) -> usize
+        {
+            components.len()
+        }
+    }
+
+    fn kind<T>(result: Result<T, SamplingError>) -> Option<SamplingErrorKind>
+    {
+        result.err().map(|err| err.kind())
+    }
+
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Person, Cash(10), TraderId(1)));
+            spawner.spawn((Person, Cash(20), TraderId(1)));
+            spawner.spawn(Infected);
+        })
+        .build();
+    let handle = simulation.spawn(Person);
+    simulation.run(1);
+
+    // types that were never added to the simulation
+    assert_eq!(
+        kind(simulation.sample_aggregate::<Unspawned, usize>()),
+        Some(SamplingErrorKind::TypeDoesNotExist)
+    );
+    assert_eq!(
+        kind(simulation.count::<With<Unspawned>>()),
+        Some(SamplingErrorKind::TypeDoesNotExist)
+    );
+    assert_eq!(
+        kind(simulation.get_resource::<Unused>()),
+        Some(SamplingErrorKind::TypeDoesNotExist)
+    );
+
+    // data that was never recorded
+    assert_eq!(
+        kind(simulation.get_time_series::<Cash, TraderId, usize>(&TraderId(1))),
+        Some(SamplingErrorKind::NotRecorded)
+    );
+    assert_eq!(
+        kind(simulation.get_aggregate_time_series::<Cash, Mean<usize>>()),
+        Some(SamplingErrorKind::NotRecorded)
+    );
+    assert_eq!(
+        kind(simulation.event_log::<Unrecorded>()),
+        Some(SamplingErrorKind::NotRecorded)
+    );
+    assert_eq!(
+        kind(simulation.ledger::<Person>()),
+        Some(SamplingErrorKind::NotRecorded)
+    );
+
+    // no or multiple entities, where a specific number was expected
+    assert_eq!(
+        kind(simulation.sample_aggregate_filtered::<Cash, With<Infected>, Mean<usize>>()),
+        Some(SamplingErrorKind::NoEntities)
+    );
+    assert_eq!(
+        kind(simulation.sample_single::<Cash, usize>()),
+        Some(SamplingErrorKind::MultipleEntities)
+    );
+    assert_eq!(
+        kind(simulation.sample::<Cash, _, usize>(&TraderId(1))),
+        Some(SamplingErrorKind::MultipleEntities)
+    );
+
+    // specific entities that do not exist
+    assert_eq!(
+        kind(simulation.sample::<Cash, _, usize>(&TraderId(2))),
+        Some(SamplingErrorKind::EntityNotFound)
+    );
+    assert_eq!(
+        kind(simulation.modify::<Cash, _>(&TraderId(2), |_| ())),
+        Some(SamplingErrorKind::EntityNotFound)
+    );
+    assert_eq!(
+        kind(simulation.sample_entity::<Cash, usize>(handle)),
+        Some(SamplingErrorKind::EntityNotFound)
+    );
+}
+
+fn build_duplicate_traders_simulation(policy: StrictnessPolicy) -> Simulation
+{
+    SimulationBuilder::new()
+        .set_strictness(policy)
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Person, Cash(10), TraderId(1)));
+            spawner.spawn((Person, Cash(20), TraderId(1)));
+            spawner.spawn((Person, Infected));
+        })
+        .build()
+}
+
+#[test]
+fn test_lenient_anomalies()
+{
+    let mut simulation = build_duplicate_traders_simulation(StrictnessPolicy::default());
+    simulation.run(1);
+
+    assert_eq!(
+        simulation.sample::<Cash, _, usize>(&TraderId(1)),
+        Err(SamplingError::EntityIdentifierNotUnique {
+            identifier: std::any::type_name::<TraderId>()
+        })
+    );
+    assert_eq!(
+        simulation.sample_aggregate_filtered::<Cash, With<Infected>, Mean<usize>>(),
+        Err(SamplingError::AggregateNoEntities {
+            component: std::any::type_name::<Cash>()
+        })
+    );
+}
+
+#[test]
+#[should_panic = "more than one entity"]
+fn test_strict_duplicate_identifiers()
+{
+    let mut simulation = build_duplicate_traders_simulation(StrictnessPolicy::Strict);
+    simulation.run(1);
+
+    let _ = simulation.sample::<Cash, _, usize>(&TraderId(1));
+}
+
+#[test]
+#[should_panic = "but found none"]
+fn test_strict_empty_aggregate()
+{
+    let mut simulation = build_duplicate_traders_simulation(StrictnessPolicy::Strict);
+    simulation.run(1);
+
+    let _ = simulation.sample_aggregate_filtered::<Cash, With<Infected>, Mean<usize>>();
+}
+
+#[test]
+fn test_deferred_spawn_despawn() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Resident;
+
+    #[derive(Component)]
+    struct Newborn;
+
+    impl SampleAggregate<usize> for Resident
+    {
+        fn sample_aggregate(components: &[&Self]) -> usize
+        {
+            components.len()
+        }
+    }
+
+    // every step, all infected die, and a newborn replaces each of them
+    fn turnover(
+        query: Query<Entity, With<Infected>>,
+        mut despawn: ResMut<DeferredDespawn>,
+        mut spawn: ResMut<DeferredSpawn<(Resident, Newborn)>>,
+    )
+    {
+        despawn.extend(&query);
+        spawn.extend(query.iter().map(|_| (Resident, Newborn)));
+
+        // nothing is applied before the flush
+        assert_eq!(despawn.len(), spawn.len());
+    }
+
+    let mut simulation = SimulationBuilder::new()
+        .add_deferred_despawn()
+        .add_deferred_spawn::<(Resident, Newborn)>()
+        .add_entity_spawner(|spawner| {
+            for i in 0..100
+            {
+                if i % 4 == 0
+                {
+                    spawner.spawn((Resident, Infected));
+                }
+                else
+                {
+                    spawner.spawn(Resident);
+                }
+            }
+        })
+        .add_systems(turnover)
+        .record_aggregate_time_series::<Resident, usize>(1)?
+        .build();
+    simulation.run(3);
+
+    assert_eq!(simulation.count::<With<Infected>>()?, 0);
+    assert_eq!(simulation.count::<With<Newborn>>()?, 25);
+    assert_eq!(simulation.count::<With<Resident>>()?, 100);
+
+    // the time series is recorded after the flush, so the dead are never counted
+    let population = simulation.get_aggregate_time_series::<Resident, usize>()?;
+    assert_eq!(
+        population.values().copied().collect::<Vec<_>>(),
+        vec![100; 3]
+    );
+
+    let deferred = simulation.get_resource::<DeferredDespawn>()?;
+    assert!(deferred.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_export_columns() -> Result<(), SimulationError>
+{
+    let simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for i in 0..50
+            {
+                spawner.spawn((Cash(i * 10), TraderId(i)));
+            }
+            // an anonymous trader, with no identifier
+            spawner.spawn(Cash(1000));
+        })
+        .build();
+
+    let mut values = simulation.export_columns::<Cash, usize>()?;
+    values.sort_unstable();
+    assert_eq!(values.len(), 51);
+    assert_eq!(values[..3], [0, 10, 20]);
+    assert_eq!(values[50], 1000);
+
+    let (ids, values) = simulation.export_columns_with_ids::<Cash, TraderId, usize>()?;
+    assert_eq!(ids.len(), 50);
+    assert_eq!(values.len(), 50);
+    for (id, value) in ids.iter().zip(&values)
+    {
+        assert_eq!(id.0 * 10, *value);
+    }
+
+    assert_eq!(
+        SimulationBuilder::new()
+            .build()
+            .export_columns::<Cash, usize>()
+            .err()
+            .map(|err| err.kind()),
+        Some(SamplingErrorKind::TypeDoesNotExist)
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+impl ArrowValue for TraderId
+{
+    fn data_type() -> incerto::arrow_schema::DataType
+    {
+        usize::data_type()
+    }
+
+    fn into_array(values: Vec<Self>) -> incerto::arrow_array::ArrayRef
+    {
+        usize::into_array(values.into_iter().map(|id| id.0).collect())
+    }
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_export_arrow() -> Result<(), SimulationError>
+{
+    use incerto::arrow_array::{Array, UInt64Array};
+
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for i in 0..20
+            {
+                spawner.spawn((Cash(i * 10), TraderId(i)));
+            }
+        })
+        .add_systems(|mut query: Query<&mut Cash>| {
+            for mut cash in &mut query
+            {
+                cash.0 += 1;
+            }
+        })
+        .record_time_series::<Cash, TraderId, usize>(2)?
+        .build();
+    simulation.run(10);
+
+    let batch = simulation.export_record_batch::<Cash, TraderId, usize>()?;
+    assert_eq!(batch.num_rows(), 20);
+    let column = |name| {
+        batch
+            .column_by_name(name)
+            .and_then(|column| column.as_any().downcast_ref::<UInt64Array>())
+            .expect("missing column")
+    };
+    for row in 0..batch.num_rows()
+    {
+        assert_eq!(
+            column("id").value(row) * 10 + 10,
+            column("value").value(row)
+        );
+    }
+
+    let time_series = simulation
+        .get_time_series::<Cash, TraderId, usize>(&TraderId(3))?
+        .to_record_batch()?;
+    assert_eq!(time_series.num_rows(), 5);
+    assert_eq!(time_series.schema().field(0).name(), "step");
+    assert_eq!(time_series.schema().field(1).name(), "value");
+    assert_eq!(time_series.column(1).null_count(), 0);
+
+    let path = std::env::temp_dir().join("incerto_test_export_arrow.parquet");
+    incerto::write_parquet(&batch, &path)?;
+    let contents = std::fs::read(&path).expect("parquet file not written");
+    std::fs::remove_file(&path).expect("failed to clean up parquet file");
+    assert!(contents.starts_with(b"PAR1") && contents.ends_with(b"PAR1"));
+
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_export_feather() -> Result<(), SimulationError>
+{
+    use incerto::arrow_ipc::reader::FileReader;
+
+    let mut simulation = SimulationBuilder::new()
+        .set_name("traders")
+        .set_seed(42)
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Cash(0), TraderId(0)));
+        })
+        .record_time_series::<Cash, TraderId, usize>(1)?
+        .build();
+    simulation.run(4);
+
+    let batch = simulation
+        .get_time_series::<Cash, TraderId, usize>(&TraderId(0))?
+        .to_record_batch()?;
+    let batch = simulation.with_run_metadata(&batch, &[("rate", 0.5)])?;
+
+    let path = std::env::temp_dir().join("incerto_test_export_feather.feather");
+    incerto::write_feather(&batch, &path)?;
+    let file = std::fs::File::open(&path).expect("feather file not written");
+    let reader = FileReader::try_new(file, None).expect("invalid feather file");
+
+    let metadata = reader.schema().metadata().clone();
+    assert_eq!(metadata["name"], "traders");
+    assert_eq!(metadata["seed"], "42");
+    assert_eq!(metadata["steps"], "4");
+    assert_eq!(metadata["parameter.rate"], "0.5");
+
+    let batches = reader
+        .collect::<Result<Vec<_>, _>>()
+        .expect("invalid record batch");
+    std::fs::remove_file(&path).expect("failed to clean up feather file");
+    assert_eq!(batches, [batch]);
+
+    Ok(())
+}
+
+#[cfg(feature = "polars")]
+impl PolarsValue for TraderId
+{
+    fn into_column(name: &str, values: Vec<Self>) -> incerto::polars::prelude::Column
+    {
+        usize::into_column(name, values.into_iter().map(|id| id.0).collect())
+    }
+}
+
+#[cfg(feature = "polars")]
+#[test]
+fn test_export_polars() -> Result<(), SimulationError>
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for i in 0..20
+            {
+                spawner.spawn((Cash(i * 10), TraderId(i)));
+            }
+        })
+        .add_systems(|mut query: Query<&mut Cash>| {
+            for mut cash in &mut query
+            {
+                cash.0 += 1;
+            }
+        })
+        .record_time_series::<Cash, TraderId, usize>(2)?
+        .build();
+    simulation.run(10);
+
+    let table = simulation.sample_table::<Cash, TraderId, usize>()?;
+    assert_eq!(table.shape(), (20, 2));
+    let column = |name| {
+        table
+            .column(name)
+            .and_then(|column| column.u64())
+            .expect("missing column")
+    };
+    for (id, value) in column("id")
+        .into_no_null_iter()
+        .zip(column("value").into_no_null_iter())
+    {
+        assert_eq!(id * 10 + 10, value);
+    }
+
+    let time_series = simulation
+        .get_time_series::<Cash, TraderId, usize>(&TraderId(3))?
+        .to_dataframe()?;
+    assert_eq!(time_series.shape(), (5, 2));
+    assert_eq!(time_series.get_column_names(), ["step", "value"]);
+
+    Ok(())
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn test_serve_metrics() -> Result<(), SimulationError>
+{
+    use std::io::{Read, Write};
+
+    #[derive(Component)]
+    struct Balance(f64);
+
+    impl SampleAggregate<f64> for Balance
+    {
+        fn sample_aggregate(components: &[&Self]) -> f64
+        {
+            components.iter().map(|balance| balance.0).sum()
+        }
+    }
+
+    let address = "127.0.0.1:39091";
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for _ in 0..4
+            {
+                spawner.spawn(Balance(2.5));
+            }
+        })
+        .serve_metrics(address)?
+        .add_metric::<Balance, f64>("total_balance")
+        .build();
+    simulation.run(3);
+
+    let mut stream = std::net::TcpStream::connect(address).expect("endpoint not served");
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .expect("failed to send request");
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .expect("failed to read response");
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("\nincerto_step 1\n"));
+    assert!(response.contains("\nincerto_entities 4\n"));
+    assert!(response.contains("# TYPE total_balance gauge\ntotal_balance 10\n"));
+
+    assert!(matches!(
+        SimulationBuilder::new().serve_metrics(address),
+        Err(BuilderError::MetricsEndpointUnavailable)
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "websocket")]
+#[test]
+fn test_serve_websocket() -> Result<(), SimulationError>
+{
+    use std::{net::TcpStream, time::Duration};
+
+    #[derive(Component)]
+    struct Balance(f64);
+
+    impl SampleAggregate<f64> for Balance
+    {
+        fn sample_aggregate(components: &[&Self]) -> f64
+        {
+            components.iter().map(|balance| balance.0).sum()
+        }
+    }
+
+    let address = "127.0.0.1:39092";
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for _ in 0..4
+            {
+                spawner.spawn(Balance(2.5));
+            }
+        })
+        .record_aggregate_time_series::<Balance, f64>(1)?
+        .serve_websocket(address)?
+        .add_websocket_stream::<Balance, f64>()
+        .build();
+
+    let stream = TcpStream::connect(address).expect("endpoint not served");
+    stream
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .expect("failed to set timeout");
+    let (mut client, _) = tungstenite::client(format!("ws://{address}"), stream)
+        .map_err(drop)
+        .expect("handshake failed");
+
+    // the client is registered asynchronously, so steps are run until it receives a message
+    let message = (0..100)
+        .find_map(|_| {
+            simulation.run(1);
+            client.read().ok()
+        })
+        .expect("no message received");
+    let message: serde_json::Value =
+        serde_json::from_str(message.to_text().expect("not a text message"))
+            .expect("not a json message");
+
+    assert!(
+        message["series"]
+            .as_str()
+            .is_some_and(|name| name.ends_with("Balance"))
+    );
+    let points = message["points"].as_array().expect("missing points");
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0][1], 10.0);
+
+    assert!(matches!(
+        SimulationBuilder::new().serve_websocket(address),
+        Err(BuilderError::StreamEndpointUnavailable)
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_result_store() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Balance(f64);
+
+    impl SampleAggregate<f64> for Balance
+    {
+        fn sample_aggregate(components: &[&Self]) -> f64
+        {
+            components.iter().map(|balance| balance.0).sum()
+        }
+    }
+
+    let path = std::env::temp_dir().join("incerto_test_result_store.sqlite");
+    let _ = std::fs::remove_file(&path);
+    let store = ResultStore::open(&path)?;
+
+    for (seed, deposit) in [(1, 1.0), (2, 2.0), (3, 1.0)]
+    {
+        let mut simulation = SimulationBuilder::new()
+            .set_name("deposits")
+            .set_seed(seed)
+            .add_entity_spawner(|spawner| {
+                spawner.spawn(Balance(0.0));
+            })
+            .add_systems(move |mut query: Query<&mut Balance>| {
+                for mut balance in &mut query
+                {
+                    balance.0 += deposit;
+                }
+            })
+            .record_aggregate_time_series::<Balance, f64>(2)?
+            .build();
+        simulation.run(10);
+
+        let run = store.save_run(&simulation, &[("deposit", deposit), ("accounts", 1.0)])?;
+        let series = simulation.get_aggregate_time_series::<Balance, f64>()?;
+        store.save_time_series(run, "balance", &series)?;
+    }
+    drop(store);
+
+    // the results persist after the store is reopened
+    let store = ResultStore::open(&path)?;
+    assert_eq!(store.runs()?.len(), 3);
+    assert!(store.runs_named("withdrawals")?.is_empty());
+
+    let runs = store.runs_with_parameter("deposit", 1.0)?;
+    assert_eq!(
+        runs.iter().map(|run| run.meta.seed).collect::<Vec<_>>(),
+        vec![Some(1), Some(3)]
+    );
+    assert_eq!(runs[0].meta.name, "deposits");
+    assert_eq!(runs[0].steps, 10);
+    assert_eq!(
+        runs[0].parameters,
+        vec![
+            (String::from("accounts"), 1.0),
+            (String::from("deposit"), 1.0)
+        ]
+    );
+
+    let series = store.time_series(runs[1].id, "balance")?;
+    let series = series.as_time_series();
+    assert_eq!(series.sample_interval(), 2);
+    assert_eq!(series.time_slice(), [2, 4, 6, 8, 10]);
+    assert_eq!(series.values_slice(), [2.0, 4.0, 6.0, 8.0, 10.0]);
+
+    store.delete_run(runs[1].id)?;
+    assert_eq!(store.runs()?.len(), 2);
+    assert_eq!(
+        store.time_series(runs[1].id, "balance"),
+        Err(StoreError::SeriesNotFound { run: runs[1].id })
+    );
+    assert_eq!(
+        store.delete_run(runs[1].id),
+        Err(StoreError::RunNotFound { run: runs[1].id })
+    );
+
+    drop(store);
+    std::fs::remove_file(&path).expect("failed to clean up database");
+
+    Ok(())
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_run_experiment() -> Result<(), incerto::RunnerError>
+{
+    use incerto::cli::{ExperimentConfig, ModelRegistry, RunRequest};
+
+    #[derive(Component)]
+    struct Balance(f64);
+
+    let registry = ModelRegistry::new().register(
+        "deposits",
+        &["balance"],
+        |parameters| {
+            let deposit = parameters["deposit"] * parameters["count"];
+            SimulationBuilder::new()
+                .add_entity_spawner(|spawner| {
+                    spawner.spawn(Balance(0.0));
+                })
+                .add_systems(move |mut query: Query<&mut Balance>| {
+                    for mut balance in &mut query
+                    {
+                        balance.0 += deposit;
+                    }
+                })
+        },
+        |simulation| {
+            simulation
+                .iter::<Balance>()
+                .map(|balance| balance.0)
+                .collect()
+        },
+    );
+
+    let path = std::env::temp_dir().join("incerto_test_run_experiment.csv");
+    let config = ExperimentConfig::parse(&format!(
+        r#"
+        plugin = "libdeposits.so"
+        model = "deposits"
+        replicas = 2
+        steps = 10
+        seed = 3
+        output = "{}"
+
+        [parameters]
+        count = 2.0
+
+        [sweep]
+        deposit = [1.0, 5.0]
+        "#,
+        path.display()
+    ))?;
+    assert_eq!(config.parameter_sets().len(), 2);
+
+    config.execute(|request| registry.run(request))?;
+
+    let output = std::fs::read_to_string(&path).expect("failed to read output");
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        [
+            "count,deposit,replica,balance",
+            "2,1,0,20",
+            "2,1,1,20",
+            "2,5,0,100",
+            "2,5,1,100",
+        ]
+    );
+
+    let missing = registry.run(&RunRequest {
+        model: String::from("withdrawals"),
+        replicas: 1,
+        steps: 1,
+        seed: None,
+        parameters: incerto::cli::Parameters::new(),
+    });
+    assert!(matches!(
+        missing,
+        Err(incerto::RunnerError::ModelNotFound { .. })
+    ));
+
+    assert!(matches!(
+        ExperimentConfig::parse("model = 1"),
+        Err(incerto::RunnerError::InvalidConfig { .. })
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "tensorboard")]
+#[test]
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn test_log_tensorboard() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Balance(u32);
+
+    impl SampleAggregate<u32> for Balance
+    {
+        fn sample_aggregate(components: &[&Self]) -> u32
+        {
+            components.iter().map(|balance| balance.0).sum()
+        }
+    }
+
+    let dir = std::env::temp_dir().join("incerto_test_log_tensorboard");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn(Balance(0));
+        })
+        .add_systems(|mut query: Query<&mut Balance>| {
+            for mut balance in &mut query
+            {
+                balance.0 += 1;
+            }
+        })
+        .record_aggregate_time_series::<Balance, u32>(2)?
+        .log_tensorboard(&dir)?
+        .add_tensorboard_scalar::<Balance, u32>("balance")
+        .build();
+    simulation.run(10);
+
+    let entries = std::fs::read_dir(&dir)
+        .expect("missing log directory")
+        .map(|entry| entry.expect("failed to read log directory").path())
+        .collect::<Vec<_>>();
+    assert_eq!(entries.len(), 1);
+    let file_name = entries[0].file_name().expect("missing file name");
+    assert!(
+        file_name
+            .to_string_lossy()
+            .starts_with("events.out.tfevents.")
+    );
+
+    // every record is framed by its length and its checksums
+    let bytes = std::fs::read(&entries[0]).expect("failed to read event file");
+    let mut records = Vec::new();
+    let mut rest = bytes.as_slice();
+    while !rest.is_empty()
+    {
+        let len = u64::from_le_bytes(rest[..8].try_into().expect("truncated record")) as usize;
+        records.push(&rest[12..12 + len]);
+        rest = &rest[12 + len + 4..];
+    }
+
+    let series = simulation.get_aggregate_time_series::<Balance, u32>()?;
+    assert_eq!(records.len(), 1 + series.len());
+    assert!(records[0].ends_with(b"brain.Event:2"));
+    for (record, value) in records[1..].iter().zip(series.values())
+    {
+        assert!(record.windows(7).any(|window| window == b"balance"));
+        assert!(record.ends_with(&(*value as f32).to_le_bytes()));
+    }
+
+    Ok(())
+}
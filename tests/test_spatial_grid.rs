@@ -297,3 +297,441 @@ fn test_spatial_grid_reset_functionality()
         .expect("Failed to sample TestResetEntity count");
     assert_eq!(entity_count, 3);
 }
+
+#[test]
+fn test_spawn_grid()
+{
+    #[derive(Component)]
+    struct Cell;
+
+    let bounds = GridBounds3D {
+        min: IVec3::new(0, 0, 0),
+        max: IVec3::new(2, 3, 4),
+    };
+
+    let positions = bounds.positions().collect::<Vec<_>>();
+    assert_eq!(positions.len(), 3 * 4 * 5);
+    assert_eq!(positions[0], GridPosition3D::new(0, 0, 0));
+    assert_eq!(positions[1], GridPosition3D::new(0, 0, 1));
+    assert_eq!(positions[59], GridPosition3D::new(2, 3, 4));
+
+    let simulation = SimulationBuilder::new()
+        .add_entity_spawner(move |spawner| {
+            // only fill the bottom layer
+            spawner.spawn_grid(bounds, |pos| (pos.z() == 0).then_some(Cell));
+        })
+        .build();
+
+    let count = simulation
+        .count::<(With<Cell>, With<GridPosition3D>)>()
+        .expect("failed to count cells");
+    assert_eq!(count, 3 * 4);
+}
+
+#[test]
+fn test_expected_entities()
+{
+    #[derive(Component)]
+    struct Cell;
+
+    let bounds = GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(99, 99),
+    };
+
+    let mut simulation = SimulationBuilder::new()
+        .expected_entities(10_000)
+        .add_spatial_grid_2d::<Cell>(Some(bounds))
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |_| Some(Cell));
+        })
+        .add_dependent_entity_spawner(|spawner, _| {
+            spawner.reserve(100);
+            spawner.spawn_from_iter((0..100).map(|x| (GridPosition2D::new(x, 0), Cell)));
+        })
+        .build();
+    simulation.run(1);
+
+    let grid = simulation
+        .get_resource::<SpatialGrid2D<Cell>>()
+        .expect("spatial grid should exist");
+    assert_eq!(grid.num_entities(), 10_100);
+    assert_eq!(simulation.iter::<Cell>().count(), 10_100);
+}
+
+#[derive(Component)]
+struct Walker;
+
+fn walk_off_the_map(mut query: Query<&mut GridPosition2D, With<Walker>>)
+{
+    for mut position in &mut query
+    {
+        position.0.x += 5;
+    }
+}
+
+fn build_walker_simulation(strict: bool) -> Simulation
+{
+    SimulationBuilder::new()
+        .add_spatial_grid_2d::<Walker>(Some(GridBounds2D {
+            min: IVec2::new(0, 0),
+            max: IVec2::new(9, 9),
+        }))
+        .set_strictness(
+            if strict
+            {
+                StrictnessPolicy::Strict
+            }
+            else
+            {
+                StrictnessPolicy::Lenient
+            },
+        )
+        .record_events::<BoundsViolation<IVec2>>()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Walker, GridPosition2D::new(2, 2)));
+        })
+        .add_systems(walk_off_the_map)
+        .build()
+}
+
+#[test]
+fn test_bounds_violation()
+{
+    let mut simulation = build_walker_simulation(false);
+
+    simulation.run(2);
+    let grid = simulation
+        .get_resource::<SpatialGrid2D<Walker>>()
+        .expect("spatial grid should exist");
+    assert_eq!(grid.num_entities(), 1);
+    assert_eq!(
+        grid.entities_at(&GridPosition2D::new(7, 2)).count(),
+        1,
+        "walker should be at its position after the first step"
+    );
+
+    // the walker is now off the map, which should not abort the simulation
+    simulation.run(2);
+    let grid = simulation
+        .get_resource::<SpatialGrid2D<Walker>>()
+        .expect("spatial grid should exist");
+    assert_eq!(grid.num_entities(), 0);
+
+    let violations = simulation
+        .event_log::<BoundsViolation<IVec2>>()
+        .expect("violations should be recorded");
+    assert_eq!(violations.len(), 2);
+
+    let mut grid = SpatialGrid2D::<Walker>::new(grid.bounds());
+    let entity = Entity::from_raw(0);
+    assert_eq!(
+        grid.try_insert(entity, GridPosition2D::new(10, 0)),
+        Err(SpatialGridError::OutOfBounds)
+    );
+    assert_eq!(grid.try_insert(entity, GridPosition2D::new(9, 0)), Ok(()));
+}
+
+#[test]
+#[should_panic(expected = "outside spatial grid bounds")]
+fn test_strict_bounds_violation()
+{
+    build_walker_simulation(true).run(4);
+}
+
+#[test]
+fn test_dense_spatial_grid()
+{
+    #[derive(Component)]
+    struct Sparse;
+
+    #[derive(Component)]
+    struct Dense;
+
+    // drift every entity diagonally, wrapping around the grid
+    fn drift(mut query: Query<&mut GridPosition3D>)
+    {
+        for mut position in &mut query
+        {
+            position.0 = (position.0 + IVec3::new(1, 1, 0)) % IVec3::new(4, 5, 3);
+        }
+    }
+
+    fn sorted(entities: impl Iterator<Item = Entity>) -> Vec<Entity>
+    {
+        let mut entities = entities.collect::<Vec<_>>();
+        entities.sort();
+        entities
+    }
+
+    let bounds = GridBounds3D {
+        min: IVec3::new(0, 0, 0),
+        max: IVec3::new(3, 4, 2),
+    };
+
+    let mut simulation = SimulationBuilder::new()
+        .add_spatial_grid::<IVec3, Sparse>(Some(bounds))
+        .add_dense_spatial_grid::<IVec3, Dense>(bounds)
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |pos| {
+                (pos.x() != pos.y()).then_some((Sparse, Dense))
+            });
+        })
+        .add_systems(drift)
+        .build();
+    simulation.run(3);
+
+    let sparse = simulation
+        .get_resource::<SpatialGrid3D<Sparse>>()
+        .expect("sparse grid missing");
+    let dense = simulation
+        .get_resource::<SpatialGrid3D<Dense>>()
+        .expect("dense grid missing");
+
+    assert_eq!(dense.num_entities(), sparse.num_entities());
+    for position in bounds.positions().chain([GridPosition3D::new(-1, 0, 0)])
+    {
+        assert_eq!(dense.is_empty(&position), sparse.is_empty(&position));
+        assert_eq!(
+            sorted(dense.entities_at(&position)),
+            sorted(sparse.entities_at(&position))
+        );
+        assert_eq!(
+            sorted(dense.neighbors_of(&position)),
+            sorted(sparse.neighbors_of(&position))
+        );
+        assert_eq!(
+            sorted(dense.orthogonal_neighbors_of(&position)),
+            sorted(sparse.orthogonal_neighbors_of(&position))
+        );
+    }
+}
+
+#[test]
+fn test_neighbor_query_buffers()
+{
+    #[derive(Component)]
+    struct Cell;
+
+    let bounds = GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(4, 4),
+    };
+
+    let mut simulation = SimulationBuilder::new()
+        .add_spatial_grid_2d::<Cell>(Some(bounds))
+        .add_entity_spawner(move |spawner| {
+            spawner.spawn_grid(bounds, |_| Some(Cell));
+        })
+        .build();
+    simulation.run(1);
+
+    let grid = simulation
+        .get_resource::<SpatialGrid2D<Cell>>()
+        .expect("grid missing");
+    let mut buffer = Vec::new();
+    for position in bounds.positions()
+    {
+        buffer.clear();
+        grid.neighbors_of_into(&position, &mut buffer);
+        assert_eq!(buffer, grid.neighbors_of(&position).collect::<Vec<_>>());
+
+        buffer.clear();
+        grid.orthogonal_neighbors_of_into(&position, &mut buffer);
+        assert_eq!(
+            buffer,
+            grid.orthogonal_neighbors_of(&position).collect::<Vec<_>>()
+        );
+    }
+
+    // the buffer is appended to, rather than overwritten
+    buffer.clear();
+    grid.entities_at_into(&GridPosition2D::new(0, 0), &mut buffer);
+    grid.entities_at_into(&GridPosition2D::new(1, 0), &mut buffer);
+    grid.entities_at_into(&GridPosition2D::new(5, 5), &mut buffer);
+    assert_eq!(buffer.len(), 2);
+}
+
+#[test]
+fn test_unchanged_positions_skip_index()
+{
+    #[derive(Component)]
+    struct Cell;
+
+    // jitter every entity back and forth within the step, ending up where it started
+    fn jitter(mut query: Query<&mut GridPosition2D>)
+    {
+        for mut position in &mut query
+        {
+            let start = *position;
+            position.0 += IVec2::new(1, 0);
+            *position = start;
+        }
+    }
+
+    let bounds = GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(2, 2),
+    };
+
+    let mut simulation = SimulationBuilder::new()
+        .add_dense_spatial_grid::<IVec2, Cell>(bounds)
+        .add_entity_spawner(|spawner| {
+            for _ in 0..3
+            {
+                spawner.spawn((Cell, GridPosition2D::new(1, 1)));
+            }
+        })
+        .add_systems(jitter)
+        .build();
+    simulation.run(1);
+
+    let entities_at_center = |simulation: &Simulation| {
+        simulation
+            .get_resource::<SpatialGrid2D<Cell>>()
+            .expect("grid missing")
+            .entities_at(&GridPosition2D::new(1, 1))
+            .collect::<Vec<_>>()
+    };
+
+    // re-inserting the entities would shuffle them within their cell
+    let initial = entities_at_center(&simulation);
+    assert_eq!(initial.len(), 3);
+    simulation.run(5);
+    assert_eq!(entities_at_center(&simulation), initial);
+}
+
+#[cfg(feature = "image")]
+#[test]
+#[allow(clippy::cast_precision_loss)]
+fn test_record_grid_frames() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Walker;
+
+    let bounds = GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(9, 4),
+    };
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            for x in 0..5
+            {
+                spawner.spawn((Walker, GridPosition2D::new(x, 0)));
+            }
+        })
+        .add_systems(|mut query: Query<&mut GridPosition<IVec2>>| {
+            for mut position in &mut query
+            {
+                position.0.y = (position.0.y + 1) % 5;
+            }
+        })
+        .record_grid_frames::<Walker>(bounds, 2, |walkers| walkers.len() as f64)
+        .build();
+    simulation.run(10);
+
+    let dir = std::env::temp_dir().join("incerto_test_record_grid_frames");
+    simulation.write_frames_png::<Walker>(&dir, 3)?;
+    for index in 0..5
+    {
+        let image = image::open(dir.join(format!("frame_{index:05}.png"))).expect("missing frame");
+        assert_eq!((image.width(), image.height()), (30, 15));
+    }
+    assert!(!dir.join("frame_00005.png").exists());
+
+    let path = std::env::temp_dir().join("incerto_test_record_grid_frames.gif");
+    simulation.write_frames_gif::<Walker>(&path, 3, std::time::Duration::from_millis(100))?;
+    assert!(std::fs::metadata(&path).is_ok_and(|metadata| metadata.len() > 0));
+
+    assert!(matches!(
+        simulation.write_frames_png::<GridPosition2D>(&dir, 1),
+        Err(SimulationError::Sampling(
+            SamplingError::FramesNotRecorded { .. }
+        ))
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "netcdf")]
+#[test]
+#[allow(clippy::cast_precision_loss, clippy::float_cmp)]
+fn test_write_frames_netcdf() -> Result<(), SimulationError>
+{
+    #[derive(Component)]
+    struct Walker;
+
+    let bounds = GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(2, 1),
+    };
+    let mut simulation = SimulationBuilder::new()
+        .add_entity_spawner(|spawner| {
+            spawner.spawn((Walker, GridPosition2D::new(0, 0)));
+        })
+        .add_systems(|mut query: Query<&mut GridPosition<IVec2>>| {
+            for mut position in &mut query
+            {
+                position.0.x = (position.0.x + 1) % 3;
+            }
+        })
+        .record_grid_frames::<Walker>(bounds, 1, |walkers| walkers.len() as f64)
+        .build();
+    simulation.run(5);
+
+    let path = std::env::temp_dir().join("incerto_test_write_frames_netcdf.nc");
+    simulation.write_frames_netcdf::<Walker>(&path, "walkers")?;
+    let bytes = std::fs::read(&path).expect("failed to read dataset");
+
+    // the magic bytes, the number of records, and the first dimension named `time`
+    assert_eq!(&bytes[..4], b"CDF\x02");
+    assert_eq!(&bytes[16..24], b"\0\0\0\x04time");
+    let frames = u32::from_be_bytes(bytes[24..28].try_into().expect("truncated header")) as usize;
+    assert!(frames >= 5);
+
+    // the values of the last variable are stored at the end, over the dimensions (time, x, y)
+    let values = bytes[bytes.len() - frames * 6 * 8..]
+        .chunks(8)
+        .map(|chunk| f64::from_be_bytes(chunk.try_into().expect("truncated value")))
+        .collect::<Vec<_>>();
+    let walker_x = values
+        .chunks(6)
+        .map(|frame| {
+            assert_eq!(frame.iter().sum::<f64>(), 1.0);
+            frame
+                .iter()
+                .position(|&value| value == 1.0)
+                .expect("no walker")
+                / 2
+        })
+        .collect::<Vec<_>>();
+    for pair in walker_x.windows(2)
+    {
+        assert_eq!(pair[1], (pair[0] + 1) % 3);
+    }
+
+    assert!(matches!(
+        simulation.write_frames_netcdf::<GridPosition2D>(&path, "positions"),
+        Err(SimulationError::Sampling(
+            SamplingError::FramesNotRecorded { .. }
+        ))
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "invalid grid bounds")]
+fn test_dense_spatial_grid_inverted_bounds()
+{
+    #[derive(Component)]
+    struct Cell;
+
+    let bounds = GridBounds2D {
+        min: IVec2::new(0, 5),
+        max: IVec2::new(9, 0),
+    };
+    let _ = SimulationBuilder::new()
+        .add_dense_spatial_grid::<IVec2, Cell>(bounds)
+        .build();
+}
@@ -0,0 +1,218 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use incerto::{
+    prelude::*,
+    spin::{Hamiltonian, Ising, MetropolisModule, Spin, SpinOutputs, Temperature},
+};
+
+const fn bounds(size: i32) -> GridBounds2D
+{
+    GridBounds2D {
+        min: IVec2::new(0, 0),
+        max: IVec2::new(size - 1, size - 1),
+    }
+}
+
+fn outputs(simulation: &Simulation) -> &SpinOutputs
+{
+    simulation
+        .get_resource::<SpinOutputs>()
+        .expect("missing spin outputs")
+}
+
+#[test]
+fn test_ground_state_at_zero_temperature()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_module(
+            MetropolisModule::new(bounds(8), Ising::new(1.0), 0.0)
+                .with_wrapping()
+                .with_lattice(Some(Spin::Up)),
+        )
+        .build();
+    simulation.run(5);
+
+    let history = outputs(&simulation).history();
+    assert_eq!(history.len(), 5);
+    assert_eq!(history[0].step, 1);
+    for metrics in history
+    {
+        assert_eq!(metrics.magnetization, 1.0);
+        // every spin has four aligned neighbors, and shares each bond with one of them
+        assert_eq!(metrics.energy, -2.0);
+        assert_eq!(metrics.acceptance, 0.0);
+    }
+    assert!(simulation.iter::<Spin>().all(|spin| *spin == Spin::Up));
+}
+
+#[test]
+fn test_open_boundaries()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_module(
+            MetropolisModule::new(bounds(2), Ising::new(1.0), 0.0).with_lattice(Some(Spin::Down)),
+        )
+        .build();
+    simulation.run(1);
+
+    // a 2x2 square without wrapping has four bonds
+    let latest = outputs(&simulation).latest().expect("no steps recorded");
+    assert_eq!(latest.magnetization, -1.0);
+    assert_eq!(latest.energy, -1.0);
+}
+
+#[test]
+fn test_disorder_at_high_temperature()
+{
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(3)
+        .add_module(
+            MetropolisModule::new(bounds(32), Ising::new(1.0), 100.0)
+                .with_wrapping()
+                .with_lattice(Some(Spin::Up)),
+        )
+        .build();
+    simulation.run(50);
+
+    let latest = outputs(&simulation).latest().expect("no steps recorded");
+    assert!(latest.magnetization.abs() < 0.1, "{}", latest.magnetization);
+    assert!(latest.energy.abs() < 0.2, "{}", latest.energy);
+    assert!(latest.acceptance > 0.9, "{}", latest.acceptance);
+}
+
+#[test]
+fn test_magnetization_and_energy_time_series()
+{
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(3)
+        .add_module(
+            MetropolisModule::new(bounds(16), Ising::new(1.0), 100.0)
+                .with_wrapping()
+                .with_lattice(Some(Spin::Up)),
+        )
+        .build();
+    simulation.run(500);
+
+    let outputs = outputs(&simulation);
+    let magnetization = outputs.time_series(|metrics| metrics.magnetization);
+    let magnetization = magnetization.as_time_series();
+    assert_eq!(magnetization.len(), 500);
+    assert_eq!(magnetization.time_slice()[0], 1);
+    assert_eq!(
+        magnetization.value_at(1),
+        Some(&outputs.history()[0].magnetization)
+    );
+
+    // the ordered start is left out of the steady state
+    let steady = magnetization.batch_means().expect("too short");
+    assert!(steady.truncation() > 0);
+    assert!(steady.mean().abs() < 0.05, "{}", steady.mean());
+
+    let energy = outputs.time_series(|metrics| metrics.energy);
+    let steady = energy.as_time_series().batch_means().expect("too short");
+    assert!(steady.mean().abs() < 0.1, "{}", steady.mean());
+}
+
+#[test]
+fn test_external_field_aligns_spins()
+{
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(5)
+        .add_module(
+            MetropolisModule::new(bounds(16), Ising::new(0.0).with_field(1.0), 0.0)
+                .with_lattice(Some(Spin::Down)),
+        )
+        .build();
+    simulation.run(20);
+
+    let latest = outputs(&simulation).latest().expect("no steps recorded");
+    assert!(latest.magnetization > 0.99, "{}", latest.magnetization);
+    assert!(latest.energy < -0.99, "{}", latest.energy);
+}
+
+#[test]
+fn test_annealing()
+{
+    let mut simulation = SimulationBuilder::new()
+        .set_seed(11)
+        .add_module(
+            MetropolisModule::new(bounds(16), Ising::new(1.0), 10.0)
+                .with_wrapping()
+                .with_lattice(None),
+        )
+        .build();
+    simulation.run(10);
+    for temperature in [2.0, 1.0, 0.0]
+    {
+        simulation
+            .resource_scope(|current: &mut Temperature| current.0 = temperature)
+            .expect("no temperature");
+        simulation.run(100);
+    }
+
+    let history = outputs(&simulation).history();
+    assert_eq!(history[9].temperature, 10.0);
+    assert_eq!(history[10].temperature, 2.0);
+    assert_eq!(history[309].temperature, 0.0);
+
+    // the energy never increases at zero temperature
+    assert!(
+        history[210..]
+            .windows(2)
+            .all(|pair| pair[1].energy <= pair[0].energy)
+    );
+    assert!(history[309].energy < history[9].energy);
+}
+
+/// An antiferromagnet, in which adjacent spins prefer to be opposite.
+struct Antiferromagnet;
+
+impl Hamiltonian for Antiferromagnet
+{
+    fn bond_energy(&self, a: Spin, b: Spin) -> f64
+    {
+        a.value() * b.value()
+    }
+}
+
+#[test]
+fn test_custom_hamiltonian()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_module(MetropolisModule::new(bounds(4), Antiferromagnet, 0.0).with_wrapping())
+        .add_entity_spawner(|spawner| {
+            spawner.spawn_grid(bounds(4), |position| {
+                let spin = if (position.x() + position.y()) % 2 == 0
+                {
+                    Spin::Up
+                }
+                else
+                {
+                    Spin::Down
+                };
+                Some(spin)
+            });
+            // a spin outside the lattice is left alone
+            spawner.spawn((Spin::Up, GridPosition2D::new(10, 10)));
+        })
+        .build();
+    simulation.run(3);
+
+    let latest = outputs(&simulation).latest().expect("no steps recorded");
+    assert_eq!(latest.magnetization, 0.0);
+    assert_eq!(latest.energy, -2.0);
+    assert_eq!(
+        simulation
+            .iter::<Spin>()
+            .filter(|spin| **spin == Spin::Up)
+            .count(),
+        9
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid temperature")]
+fn test_negative_temperature()
+{
+    let _ = MetropolisModule::new(bounds(4), Ising::new(1.0), -1.0);
+}
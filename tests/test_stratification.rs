@@ -0,0 +1,164 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use std::sync::Mutex;
+
+use incerto::prelude::*;
+use rand::Rng;
+
+#[derive(Component)]
+struct Level(f64);
+
+/// A replica whose outcome is `10 * stratum`, plus uniform noise of the given spread in every
+/// stratum.
+fn noisy(spread: &'static [f64]) -> impl Fn(usize, usize) -> SimulationBuilder + Sync
+{
+    move |stratum, idx| {
+        SimulationBuilder::new()
+            .set_seed(SimRng::derive_seed(1, idx as u64))
+            .add_seeded_entity_spawner(move |spawner, rng| {
+                let noise = rng.random_range(-1.0..=1.0) * spread[stratum];
+                #[allow(clippy::cast_precision_loss)]
+                spawner.spawn(Level(10.0f64.mul_add(stratum as f64, noise)));
+            })
+    }
+}
+
+fn level(simulation: &Simulation) -> f64
+{
+    simulation.iter::<Level>().next().expect("no level").0
+}
+
+fn counts(result: &StratifiedResult) -> Vec<usize>
+{
+    result.strata().iter().map(EnsembleResult::len).collect()
+}
+
+#[test]
+fn test_proportional_allocation()
+{
+    let stratification = Stratification::new([5.0, 3.0, 2.0]);
+    assert_eq!(stratification.weights(), &[0.5, 0.3, 0.2]);
+
+    let result = Simulation::replicate_stratified(&stratification, 20, 1, noisy(&[0.0; 3]), level);
+    assert_eq!(counts(&result), vec![10, 6, 4]);
+    assert_eq!(result.len(), 20);
+    assert!((result.estimate().expect("no estimate") - 7.0).abs() < 1e-12);
+    assert_eq!(result.standard_error(), Some(0.0));
+
+    // every stratum gets at least two replicas, taken from the largest
+    let stratification = Stratification::new([0.9, 0.05, 0.05]);
+    let result = Simulation::replicate_stratified(&stratification, 10, 1, noisy(&[0.0; 3]), level);
+    assert_eq!(counts(&result), vec![6, 2, 2]);
+}
+
+#[test]
+fn test_neyman_allocation()
+{
+    // the first stratum does not vary, so all but its pilot go to the second
+    let stratification =
+        Stratification::uniform(2).with_allocation(Allocation::Neyman { pilot: 3 });
+    let result =
+        Simulation::replicate_stratified(&stratification, 20, 1, noisy(&[0.0, 1.0]), level);
+    assert_eq!(counts(&result), vec![3, 17]);
+
+    // the spread of the second stratum is three times that of the first
+    let stratification =
+        Stratification::uniform(2).with_allocation(Allocation::Neyman { pilot: 20 });
+    let result =
+        Simulation::replicate_stratified(&stratification, 200, 1, noisy(&[1.0, 3.0]), level);
+    let counts = counts(&result);
+    assert!(
+        counts[1] > 2 * counts[0] && counts[1] < 4 * counts[0],
+        "{counts:?}"
+    );
+}
+
+#[test]
+fn test_stratified_estimate()
+{
+    let stratification = Stratification::new([0.25, 0.75]);
+    let result =
+        Simulation::replicate_stratified(&stratification, 40, 1, noisy(&[1.0, 1.0]), level);
+
+    let interval = result.confidence_interval(0.99).expect("no interval");
+    assert!(interval.contains(7.5), "{interval}");
+
+    // far more precise than drawing the strata at random, since they differ a lot
+    let plain = (0..40).map(|idx| if idx % 4 == 0 { 0.0 } else { 10.0 });
+    let plain = EnsembleResult::new(plain);
+    assert!(
+        result.standard_error().expect("no error") * 5.0
+            < plain.standard_error().expect("no error")
+    );
+}
+
+#[test]
+fn test_replica_indices()
+{
+    let seen = Mutex::new(Vec::new());
+    let stratification =
+        Stratification::uniform(3).with_allocation(Allocation::Neyman { pilot: 2 });
+    let _ = Simulation::replicate_stratified(
+        &stratification,
+        12,
+        1,
+        |stratum, idx| {
+            seen.lock().expect("poisoned").push((stratum, idx));
+            SimulationBuilder::new()
+        },
+        |_| 1.0,
+    );
+
+    let mut seen = seen.into_inner().expect("poisoned");
+    seen.sort_unstable_by_key(|(_, idx)| *idx);
+    assert_eq!(
+        seen.iter().map(|(_, idx)| *idx).collect::<Vec<_>>(),
+        (0..12).collect::<Vec<_>>()
+    );
+    // the pilot runs first
+    assert_eq!(
+        seen[..6]
+            .iter()
+            .map(|(stratum, _)| *stratum)
+            .collect::<Vec<_>>(),
+        vec![0, 0, 1, 1, 2, 2]
+    );
+}
+
+#[test]
+#[should_panic(expected = "a stratified experiment of 3 strata needs at least 6 replicas")]
+fn test_too_few_replicas()
+{
+    let _ = Simulation::replicate_stratified(
+        &Stratification::uniform(3),
+        5,
+        1,
+        |_, _| SimulationBuilder::new(),
+        |_| 1.0,
+    );
+}
+
+#[test]
+fn test_replicas_are_seeded_independently()
+{
+    // replicas whose builder sets no seed are seeded independently, across the strata too
+    #[allow(clippy::cast_precision_loss)]
+    let seed = |simulation: &Simulation| simulation.meta().seed.expect("no seed") as f64;
+    let stratification = Stratification::uniform(2);
+    let result = Simulation::replicate_stratified(
+        &stratification,
+        8,
+        1,
+        |_, _| SimulationBuilder::new(),
+        seed,
+    );
+
+    let mut seeds = result
+        .strata()
+        .iter()
+        .flat_map(|stratum| stratum.values().to_vec())
+        .collect::<Vec<_>>();
+    seeds.sort_unstable_by(f64::total_cmp);
+    seeds.dedup();
+    assert_eq!(seeds.len(), 8);
+}
@@ -0,0 +1,123 @@
+#![allow(clippy::expect_used)]
+use std::collections::HashSet;
+
+use incerto::prelude::*;
+
+#[derive(Component)]
+struct Person;
+
+/// Asserts that the edges connect distinct nodes within `0..n`, each pair at most once.
+fn assert_simple(n: usize, edges: &[(usize, usize)])
+{
+    let mut seen = HashSet::new();
+    for &(a, b) in edges
+    {
+        assert!(a < n && b < n);
+        assert_ne!(a, b);
+        assert!(
+            seen.insert((a.min(b), a.max(b))),
+            "duplicate edge ({a}, {b})"
+        );
+    }
+}
+
+fn degrees(n: usize, edges: &[(usize, usize)]) -> Vec<usize>
+{
+    let mut degrees = vec![0; n];
+    for &(a, b) in edges
+    {
+        degrees[a] += 1;
+        degrees[b] += 1;
+    }
+    degrees
+}
+
+#[test]
+fn test_topology_erdos_renyi()
+{
+    let mut rng = SimRng::from_seed(1);
+
+    assert!(topology::erdos_renyi(100, 0.0, &mut rng).is_empty());
+    assert_eq!(topology::erdos_renyi(100, 1.0, &mut rng).len(), 4950);
+
+    let edges = topology::erdos_renyi(1000, 0.01, &mut rng);
+    assert_simple(1000, &edges);
+    // the expected number of edges is 4995, with a standard deviation of about 70
+    assert!((4700..5300).contains(&edges.len()), "{}", edges.len());
+
+    let mut rng = SimRng::from_seed(1);
+    topology::erdos_renyi(100, 0.0, &mut rng);
+    topology::erdos_renyi(100, 1.0, &mut rng);
+    assert_eq!(topology::erdos_renyi(1000, 0.01, &mut rng), edges);
+}
+
+#[test]
+fn test_topology_watts_strogatz()
+{
+    let mut rng = SimRng::from_seed(2);
+
+    let lattice = topology::watts_strogatz(20, 4, 0.0, &mut rng);
+    assert_simple(20, &lattice);
+    assert_eq!(lattice.len(), 40);
+    assert!(degrees(20, &lattice).iter().all(|&degree| degree == 4));
+    assert!(lattice.contains(&(19, 1)));
+
+    let rewired = topology::watts_strogatz(500, 6, 0.2, &mut rng);
+    assert_simple(500, &rewired);
+    assert_eq!(rewired.len(), 1500);
+    assert!(
+        rewired
+            .iter()
+            .any(|&(a, b)| a.abs_diff(b) > 3 && a.abs_diff(b) < 497)
+    );
+
+    let complete = topology::watts_strogatz(5, 4, 1.0, &mut rng);
+    assert_simple(5, &complete);
+    assert_eq!(complete.len(), 10);
+}
+
+#[test]
+fn test_topology_barabasi_albert()
+{
+    let mut rng = SimRng::from_seed(3);
+
+    let edges = topology::barabasi_albert(2000, 3, &mut rng);
+    assert_simple(2000, &edges);
+    assert_eq!(edges.len(), 6 + (2000 - 4) * 3);
+
+    let degrees = degrees(2000, &edges);
+    assert!(degrees.iter().all(|&degree| degree >= 3));
+    // preferential attachment produces hubs far above the average degree of 6
+    assert!(degrees.iter().max().expect("no nodes") > &50);
+}
+
+#[test]
+#[should_panic(expected = "the number of neighbors must be even")]
+fn test_topology_watts_strogatz_odd()
+{
+    topology::watts_strogatz(10, 3, 0.1, &mut SimRng::from_seed(4));
+}
+
+#[test]
+fn test_topology_spawner()
+{
+    let mut simulation = SimulationBuilder::new()
+        .add_network::<Person>()
+        .add_seeded_entity_spawner(|spawner, rng| {
+            let people = (0..100).map(|_| spawner.spawn(Person)).collect::<Vec<_>>();
+            spawner.add_edges::<Person>(&people, topology::barabasi_albert(100, 2, rng));
+        })
+        .build();
+
+    let network = simulation
+        .get_resource::<Network<Person>>()
+        .expect("missing network");
+    assert_eq!(network.num_nodes(), 100);
+    assert_eq!(network.num_edges(), 1 + 98 * 2);
+
+    simulation.run(1);
+    let network = simulation
+        .get_resource::<Network<Person>>()
+        .expect("missing network");
+    assert_eq!(network.num_edges(), 1 + 98 * 2);
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "trace")]
+#![allow(clippy::expect_used)]
+use std::sync::{
+    Arc, Mutex, PoisonError,
+    atomic::{AtomicU64, Ordering},
+};
+
+use incerto::prelude::*;
+use tracing::{
+    Event, Level, Metadata,
+    span::{Attributes, Id, Record},
+    subscriber::Subscriber,
+};
+
+#[derive(Component)]
+struct Value(usize);
+
+impl Sample<usize> for Value
+{
+    fn sample(component: &Self) -> usize
+    {
+        component.0
+    }
+}
+
+/// Subscriber that records the names of all spans created on the current thread.
+#[derive(Default, Clone)]
+struct SpanNames
+{
+    names: Arc<Mutex<Vec<&'static str>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl SpanNames
+{
+    fn count(&self, name: &str) -> usize
+    {
+        self.names
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|&&n| n == name)
+            .count()
+    }
+}
+
+impl Subscriber for SpanNames
+{
+    fn enabled(&self, _: &Metadata<'_>) -> bool
+    {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id
+    {
+        self.names
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(span.metadata().name());
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn run_traced(level: Option<Level>) -> SpanNames
+{
+    let spans = SpanNames::default();
+
+    tracing::subscriber::with_default(spans.clone(), || {
+        let mut builder = SimulationBuilder::new().add_entity_spawner(|spawner| {
+            spawner.spawn(Value(1));
+        });
+        if let Some(level) = level
+        {
+            builder = builder.set_trace_level(level);
+        }
+
+        let mut simulation = builder.build();
+        simulation.run(3);
+        simulation
+            .sample_single::<Value, usize>()
+            .expect("failed to sample value");
+    });
+
+    spans
+}
+
+#[test]
+fn test_trace_spans()
+{
+    let spans = run_traced(None);
+    assert_eq!(spans.count("build"), 1);
+    assert_eq!(spans.count("step"), 3);
+    assert_eq!(spans.count("sample"), 1);
+}
+
+#[test]
+fn test_trace_level()
+{
+    let spans = run_traced(Some(Level::INFO));
+    assert_eq!(spans.count("build"), 1);
+    assert_eq!(spans.count("step"), 3);
+    assert_eq!(spans.count("sample"), 0);
+}
@@ -0,0 +1,193 @@
+#![allow(clippy::expect_used)]
+#![allow(clippy::float_cmp)]
+use bevy::prelude::Events;
+use incerto::{
+    prelude::*,
+    traffic::{Road, TrafficMetrics, TrafficOutputs, Vehicle, VehicleArrived},
+};
+
+#[derive(Component)]
+struct Intersection;
+
+/// Builds a traffic simulation with the given number of intersections.
+fn network<const N: usize>() -> (Simulation, [Entity; N])
+{
+    let mut simulation = SimulationBuilder::new().add_traffic().build();
+    let intersections = [(); N].map(|()| simulation.spawn(Intersection).entity());
+    (simulation, intersections)
+}
+
+fn history(simulation: &Simulation) -> &[TrafficMetrics]
+{
+    simulation
+        .get_resource::<TrafficOutputs>()
+        .expect("missing traffic outputs")
+        .history()
+}
+
+fn road(simulation: &Simulation, free_flow_time: f64) -> &Road
+{
+    simulation
+        .iter::<Road>()
+        .find(|road| road.free_flow_time() == free_flow_time)
+        .expect("no such road")
+}
+
+#[test]
+fn test_free_flow()
+{
+    let (mut simulation, [home, work]) = network();
+    simulation.spawn(Road::new(home, work, 3.0, 10.0));
+    simulation.spawn(Vehicle::new(home, work));
+
+    simulation.run(1);
+    let vehicle = simulation.iter::<Vehicle>().next().expect("no vehicle");
+    assert_eq!(vehicle.location(), home);
+    assert_eq!(vehicle.route().len(), 1);
+    assert_eq!(vehicle.elapsed(), 1.0);
+    assert_eq!(road(&simulation, 3.0).occupancy(), 1);
+    assert_eq!(road(&simulation, 3.0).entered(), 1);
+
+    simulation.run(2);
+    assert_eq!(simulation.iter::<Vehicle>().count(), 0);
+    assert_eq!(road(&simulation, 3.0).occupancy(), 0);
+
+    let history = history(&simulation);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].step, 1);
+    assert_eq!(history[0].en_route, 1);
+    assert_eq!(history[0].max_volume_capacity_ratio, 0.1);
+    assert_eq!(history[1].mean_travel_time, None);
+    assert_eq!(history[2].arrivals, 1);
+    assert_eq!(history[2].mean_travel_time, Some(3.0));
+}
+
+#[test]
+fn test_congestion_causes_detours()
+{
+    let (mut simulation, [home, work, detour]) = network();
+    simulation.spawn(Road::new(home, work, 5.0, 10.0));
+    simulation.spawn(Road::new(home, detour, 4.0, 50.0));
+    simulation.spawn(Road::new(detour, work, 4.5, 50.0));
+    for _ in 0..40
+    {
+        simulation.spawn(Vehicle::new(home, work));
+    }
+
+    // the direct road is faster than the detour until 15 vehicles are on it
+    simulation.run(1);
+    assert_eq!(road(&simulation, 5.0).entered(), 15);
+    assert_eq!(road(&simulation, 4.0).entered(), 25);
+    assert_eq!(road(&simulation, 4.5).entered(), 0);
+
+    let latest = history(&simulation)[0];
+    assert_eq!(latest.en_route, 40);
+    assert_eq!(latest.congested_roads, 1);
+    assert_eq!(latest.max_volume_capacity_ratio, 1.5);
+
+    simulation.run(19);
+    assert_eq!(simulation.iter::<Vehicle>().count(), 0);
+    let arrivals: usize = history(&simulation)
+        .iter()
+        .map(|metrics| metrics.arrivals)
+        .sum();
+    assert_eq!(arrivals, 40);
+    assert_eq!(road(&simulation, 4.5).occupancy(), 0);
+}
+
+#[test]
+fn test_closed_roads_are_avoided()
+{
+    let (mut simulation, [home, work, detour]) = network();
+    simulation.spawn(Road::new(home, work, 1.0, 0.0));
+    simulation.spawn(Road::new(home, detour, 2.0, 10.0));
+    simulation.spawn(Road::new(detour, work, 3.0, 10.0));
+    simulation.spawn(Vehicle::new(home, work));
+
+    simulation.run(5);
+
+    assert_eq!(road(&simulation, 1.0).entered(), 0);
+    assert!(road(&simulation, 1.0).is_closed());
+    assert_eq!(road(&simulation, 1.0).travel_time(), f64::INFINITY);
+    let history = history(&simulation);
+    assert_eq!(history[4].arrivals, 1);
+    assert_eq!(history[4].mean_travel_time, Some(5.0));
+    // closed roads are not counted towards congestion
+    assert!(history.iter().all(|metrics| metrics.congested_roads == 0));
+    assert!(
+        history
+            .iter()
+            .all(|metrics| metrics.max_volume_capacity_ratio <= 0.1)
+    );
+}
+
+#[test]
+fn test_stranded_vehicles_wait()
+{
+    let (mut simulation, [home, work]) = network();
+    simulation.spawn(Vehicle::new(home, work));
+
+    simulation.run(3);
+    assert!(
+        history(&simulation)
+            .iter()
+            .all(|metrics| metrics.stranded == 1 && metrics.en_route == 0)
+    );
+    let vehicle = simulation.iter::<Vehicle>().next().expect("no vehicle");
+    assert_eq!(vehicle.road(), None);
+    assert_eq!(vehicle.elapsed(), 3.0);
+
+    // the vehicle sets off once a road is built
+    simulation.spawn(Road::new(home, work, 2.0, 1.0));
+    simulation.run(2);
+    let latest = history(&simulation).last().expect("no steps recorded");
+    assert_eq!(latest.stranded, 0);
+    assert_eq!(latest.arrivals, 1);
+    assert_eq!(latest.mean_travel_time, Some(5.0));
+}
+
+#[test]
+fn test_arrival_events()
+{
+    let (mut simulation, [home, work]) = network();
+    let [there, back] = Road::two_way(home, work, 1.0, 5.0);
+    assert_eq!((there.from(), there.to()), (home, work));
+    assert_eq!((back.from(), back.to()), (work, home));
+    simulation.spawn(there);
+    simulation.spawn(back);
+    let commuter = simulation.spawn(Vehicle::new(home, work)).entity();
+    let returning = simulation.spawn(Vehicle::new(work, home)).entity();
+
+    simulation.run(1);
+
+    let events = simulation
+        .get_resource::<Events<VehicleArrived>>()
+        .expect("missing arrival events");
+    let mut arrivals: Vec<_> = events.iter_current_update_events().copied().collect();
+    arrivals.sort_by_key(|arrival| arrival.vehicle);
+    let mut expected = vec![
+        VehicleArrived {
+            vehicle: commuter,
+            origin: home,
+            destination: work,
+            travel_time: 1.0,
+        },
+        VehicleArrived {
+            vehicle: returning,
+            origin: work,
+            destination: home,
+            travel_time: 1.0,
+        },
+    ];
+    expected.sort_by_key(|arrival| arrival.vehicle);
+    assert_eq!(arrivals, expected);
+    assert_eq!(simulation.iter::<Vehicle>().count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "invalid free-flow travel time: 0")]
+fn test_invalid_free_flow_time()
+{
+    let (mut simulation, [home, work]) = network();
+    simulation.spawn(Road::new(home, work, 0.0, 1.0));
+}